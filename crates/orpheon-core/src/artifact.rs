@@ -3,15 +3,52 @@
 //! An ExecutionArtifact is the "Proof of Outcome" generated when an intent is finalized.
 
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-use crate::intent::Intent;
+use crate::crypto;
+use crate::error::Result;
+use crate::intent::{Constraint, Intent, Signature};
+use crate::money::MoneyAmount;
 use crate::plan::Plan;
+use crate::pointer;
+
+/// Default limit on the serialized size of an event's `data`, in bytes.
+pub const DEFAULT_MAX_EVENT_DATA_BYTES: usize = 64 * 1024;
+
+/// How much of an oversized payload is kept as a preview, in bytes.
+const EVENT_DATA_PREVIEW_BYTES: usize = 256;
+
+/// Merkle root algorithm used for new artifacts.
+///
+/// Version 1 hashes each event's JSON and concatenated child hashes as-is.
+/// Version 2 prefixes leaf hashes with `0x00` and internal node hashes with
+/// `0x01`, so a leaf can never be mistaken for an internal node.
+pub const MERKLE_VERSION: u8 = 2;
+
+/// Merkle root versions this build can compute.
+pub const SUPPORTED_MERKLE_VERSIONS: [u8; 2] = [1, 2];
+
+/// Domain-separation prefix of version 2 leaf hashes.
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+
+/// Domain-separation prefix of version 2 internal node hashes.
+const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+/// Artifacts from before the version was recorded use version 1.
+fn legacy_merkle_version() -> u8 {
+    1
+}
+
+fn is_legacy_merkle_version(version: &u8) -> bool {
+    *version == 1
+}
 
 /// The execution artifact provides proof of outcome.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExecutionArtifact {
     /// Unique identifier for this artifact.
     pub id: Uuid,
@@ -19,9 +56,21 @@ pub struct ExecutionArtifact {
     /// The original intent that was fulfilled.
     pub intent: Intent,
 
+    /// Content hash of `intent` when the artifact was created.
+    #[serde(default)]
+    pub intent_hash: String,
+
     /// The final plan that was executed.
     pub final_plan: Plan,
 
+    /// Revision number of `final_plan` among the intent's plans (1-based, 0 if unknown).
+    #[serde(default)]
+    pub plan_revision: u32,
+
+    /// Artifacts of the child intents a decomposed intent was split into.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub child_artifacts: Vec<Uuid>,
+
     /// Trace of all execution events.
     pub trace: Vec<ExecutionEvent>,
 
@@ -34,18 +83,37 @@ pub struct ExecutionArtifact {
     /// Merkle root of the execution trace for verifiable logging.
     pub merkle_root: String,
 
+    /// Algorithm `merkle_root` was computed with; see [`MERKLE_VERSION`].
+    /// Omitted for version 1, so older artifacts keep their content hash.
+    #[serde(default = "legacy_merkle_version", skip_serializing_if = "is_legacy_merkle_version")]
+    pub merkle_version: u8,
+
     /// Total actual cost incurred.
     pub actual_cost: f64,
 
+    /// `actual_cost` exactly, in minor units; tracked when the intent's
+    /// budget has an exact limit, and preferred over the float when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_cost_minor: Option<MoneyAmount>,
+
     /// Total actual duration in milliseconds.
     pub actual_duration_ms: u64,
 
     /// Metadata about the execution environment.
     pub execution_metadata: ExecutionMetadata,
+
+    /// Per-constraint evidence computed when the artifact was finalized.
+    #[serde(default)]
+    pub constraint_report: Option<ConstraintReport>,
+
+    /// Node signature over [`ExecutionArtifact::content_hash`].
+    #[serde(default)]
+    pub signature: Option<Signature>,
 }
 
 /// An event that occurred during execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExecutionEvent {
     /// Unique identifier for this event.
     pub id: Uuid,
@@ -68,6 +136,7 @@ pub struct ExecutionEvent {
 
 /// Types of execution events.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ExecutionEventType {
     /// Step execution started.
@@ -78,6 +147,12 @@ pub enum ExecutionEventType {
     StepFailed,
     /// Step is being retried.
     StepRetrying,
+    /// Step was skipped because its `skip_if` condition held.
+    StepSkipped,
+    /// Execution was paused before dispatching further steps.
+    Paused,
+    /// Paused execution was resumed.
+    Resumed,
     /// Compensation action started.
     CompensationStarted,
     /// Compensation action completed.
@@ -98,6 +173,7 @@ pub enum ExecutionEventType {
 
 /// Outcome of an intent execution.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum Outcome {
     /// Execution completed successfully.
@@ -135,10 +211,315 @@ impl Outcome {
     pub fn is_failure(&self) -> bool {
         matches!(self, Outcome::Failure { .. })
     }
+
+    /// A partial success with `success_rate` of the steps succeeding, as a
+    /// fraction clamped to 0.0..=1.0 and stored as a whole percentage.
+    pub fn partial(success_rate: f32, details: impl Into<String>) -> Self {
+        let fraction = if success_rate.is_nan() { 0.0 } else { success_rate.clamp(0.0, 1.0) };
+        Outcome::PartialSuccess {
+            success_rate: (fraction * 100.0).round() as u8,
+            details: details.into(),
+        }
+    }
+
+    /// How much attention the outcome needs: none for a success, a look for
+    /// a partial success or cancellation, and action for a failure.
+    pub fn severity(&self) -> OutcomeSeverity {
+        match self {
+            Outcome::Success => OutcomeSeverity::Ok,
+            Outcome::PartialSuccess { .. } | Outcome::Cancelled { .. } => OutcomeSeverity::Warning,
+            Outcome::Failure { .. } => OutcomeSeverity::Error,
+        }
+    }
+
+    /// Whether running the intent again may succeed: after a failure that
+    /// was not compensated, or a cancellation by `timeout`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Outcome::Failure { compensated, .. } => !compensated,
+            Outcome::Cancelled { by, .. } => by == "timeout",
+            Outcome::Success | Outcome::PartialSuccess { .. } => false,
+        }
+    }
+
+    /// One line describing the outcome, e.g. `failure (quota exceeded,
+    /// compensated)` or `partial success at 75% (optional steps failed)`.
+    pub fn summary(&self) -> String {
+        match self {
+            Outcome::Success => "success".to_string(),
+            Outcome::Failure { reason, compensated: true } => format!("failure ({}, compensated)", reason),
+            Outcome::Failure { reason, compensated: false } => format!("failure ({})", reason),
+            Outcome::PartialSuccess { success_rate, details } => {
+                format!("partial success at {}% ({})", success_rate, details)
+            }
+            Outcome::Cancelled { by, reason } => format!("cancelled by {} ({})", by, reason),
+        }
+    }
+}
+
+/// How much attention an [`Outcome`] needs; see [`Outcome::severity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeSeverity {
+    /// Nothing to do.
+    Ok,
+    /// Worth a look, e.g. optional steps failed.
+    Warning,
+    /// Needs action.
+    Error,
+}
+
+/// Process exit code for tooling: 0 for a success, 1 for a partial
+/// success, 2 for a failure and 3 for a cancellation.
+impl From<&Outcome> for i32 {
+    fn from(outcome: &Outcome) -> Self {
+        match outcome {
+            Outcome::Success => 0,
+            Outcome::PartialSuccess { .. } => 1,
+            Outcome::Failure { .. } => 2,
+            Outcome::Cancelled { .. } => 3,
+        }
+    }
+}
+
+/// Result of checking one constraint against an execution.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintStatus {
+    /// The constraint was checked and honored.
+    Satisfied,
+    /// The constraint was checked and broken.
+    Violated,
+    /// A soft (best-effort) constraint was checked and broken.
+    Warning,
+    /// The constraint type cannot be checked from the artifact.
+    NotEvaluated,
+}
+
+/// Evidence for a single constraint of the intent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConstraintCheck {
+    /// The constraint as declared on the intent.
+    pub constraint: Constraint,
+
+    /// Whether the constraint was declared as soft (best-effort).
+    #[serde(default)]
+    pub soft: bool,
+
+    /// Outcome of the check.
+    pub status: ConstraintStatus,
+
+    /// The measured value compared against the constraint (if any).
+    pub measured: Option<serde_json::Value>,
+
+    /// Trace events supporting the measurement.
+    pub evidence: Vec<Uuid>,
+
+    /// Human-readable explanation.
+    pub detail: String,
+}
+
+impl ConstraintCheck {
+    /// Whether the constraint was actually checked.
+    pub fn checked(&self) -> bool {
+        self.status != ConstraintStatus::NotEvaluated
+    }
+
+    /// Whether the constraint was checked and honored.
+    ///
+    /// Soft constraints reported as warnings are not satisfied.
+    pub fn satisfied(&self) -> bool {
+        self.status == ConstraintStatus::Satisfied
+    }
+}
+
+/// Report of how each hard constraint was honored by an execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ConstraintReport {
+    /// One entry per constraint on the intent, in declaration order
+    /// (hard constraints first, then soft ones).
+    pub checks: Vec<ConstraintCheck>,
+
+    /// True when no hard constraint was violated.
+    pub all_satisfied: bool,
+
+    /// When the report was computed.
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl ConstraintReport {
+    /// Evaluate every constraint of the artifact's intent against its execution.
+    pub fn evaluate(artifact: &ExecutionArtifact) -> Self {
+        let checks: Vec<ConstraintCheck> = artifact
+            .intent
+            .constraints
+            .iter()
+            .map(|c| Self::check(c, artifact, false))
+            .chain(
+                artifact
+                    .intent
+                    .soft_constraints
+                    .iter()
+                    .map(|c| Self::check(c, artifact, true)),
+            )
+            .collect();
+        let all_satisfied = checks.iter().all(|c| c.status != ConstraintStatus::Violated);
+
+        Self {
+            checks,
+            all_satisfied,
+            evaluated_at: Utc::now(),
+        }
+    }
+
+    /// Get the checks that were violated.
+    pub fn violations(&self) -> Vec<&ConstraintCheck> {
+        self.checks
+            .iter()
+            .filter(|c| c.status == ConstraintStatus::Violated)
+            .collect()
+    }
+
+    /// Get the soft constraints that were not honored.
+    pub fn warnings(&self) -> Vec<&ConstraintCheck> {
+        self.checks
+            .iter()
+            .filter(|c| c.status == ConstraintStatus::Warning)
+            .collect()
+    }
+
+    fn check(constraint: &Constraint, artifact: &ExecutionArtifact, soft: bool) -> ConstraintCheck {
+        let completed: Vec<Uuid> = artifact
+            .trace
+            .iter()
+            .filter(|e| e.event_type == ExecutionEventType::StepCompleted)
+            .map(|e| e.id)
+            .collect();
+        let last_event: Vec<Uuid> = artifact.trace.last().map(|e| e.id).into_iter().collect();
+
+        let (status, measured, evidence, detail) = match constraint {
+            Constraint::ResourceLimit { resource, limit } => {
+                match resource.to_ascii_lowercase().as_str() {
+                    "cost" | "total_cost" => (
+                        ConstraintStatus::from_bool(artifact.total_cost() <= *limit),
+                        Some(serde_json::json!(artifact.total_cost())),
+                        completed,
+                        format!("actual cost {:.2} vs limit {:.2}", artifact.total_cost(), limit),
+                    ),
+                    "duration_ms" | "total_duration_ms" => (
+                        ConstraintStatus::from_bool(artifact.actual_duration_ms as f64 <= *limit),
+                        Some(serde_json::json!(artifact.actual_duration_ms)),
+                        completed,
+                        format!(
+                            "actual duration {}ms vs limit {}ms",
+                            artifact.actual_duration_ms, limit
+                        ),
+                    ),
+                    other => Self::not_evaluated(format!("resource '{}' is not measured", other)),
+                }
+            }
+            Constraint::Deadline { by } => (
+                ConstraintStatus::from_bool(artifact.timestamp <= *by),
+                Some(serde_json::json!(artifact.timestamp)),
+                last_event,
+                format!("completed at {} vs deadline {}", artifact.timestamp, by),
+            ),
+            Constraint::Sla { metric, threshold, unit }
+                if metric == "latency" && unit == "ms" =>
+            {
+                (
+                    ConstraintStatus::from_bool(artifact.actual_duration_ms <= *threshold),
+                    Some(serde_json::json!(artifact.actual_duration_ms)),
+                    completed,
+                    format!(
+                        "actual latency {}ms vs threshold {}ms",
+                        artifact.actual_duration_ms, threshold
+                    ),
+                )
+            }
+            Constraint::Provider { node_id } => {
+                let executed = &artifact.execution_metadata.node_id;
+                if executed.is_empty() {
+                    Self::not_evaluated("executing node did not report an id".to_string())
+                } else {
+                    (
+                        ConstraintStatus::from_bool(executed == node_id),
+                        Some(serde_json::json!(executed)),
+                        Vec::new(),
+                        format!("executed on '{}', required '{}'", executed, node_id),
+                    )
+                }
+            }
+            Constraint::GeoFence { regions, allowed } => {
+                match &artifact.execution_metadata.region {
+                    Some(region) => {
+                        let listed = regions.iter().any(|r| r.eq_ignore_ascii_case(region));
+                        (
+                            ConstraintStatus::from_bool(listed == *allowed),
+                            Some(serde_json::json!(region)),
+                            Vec::new(),
+                            format!(
+                                "executed in '{}', {} regions {:?}",
+                                region,
+                                if *allowed { "allowed" } else { "denied" },
+                                regions
+                            ),
+                        )
+                    }
+                    None => Self::not_evaluated("execution region was not reported".to_string()),
+                }
+            }
+            Constraint::Sla { metric, .. } => {
+                Self::not_evaluated(format!("SLA metric '{}' is not measured", metric))
+            }
+            Constraint::DependsOn { intent_id, .. } => {
+                Self::not_evaluated(format!("intent {} was waited for before execution", intent_id))
+            }
+            Constraint::StateMatch { .. } | Constraint::Custom { .. } => {
+                Self::not_evaluated("constraint type is not evaluated".to_string())
+            }
+        };
+
+        let status = if soft && status == ConstraintStatus::Violated {
+            ConstraintStatus::Warning
+        } else {
+            status
+        };
+
+        ConstraintCheck {
+            constraint: constraint.clone(),
+            soft,
+            status,
+            measured,
+            evidence,
+            detail,
+        }
+    }
+
+    fn not_evaluated(
+        detail: String,
+    ) -> (ConstraintStatus, Option<serde_json::Value>, Vec<Uuid>, String) {
+        (ConstraintStatus::NotEvaluated, None, Vec::new(), detail)
+    }
+}
+
+impl ConstraintStatus {
+    fn from_bool(satisfied: bool) -> Self {
+        if satisfied {
+            ConstraintStatus::Satisfied
+        } else {
+            ConstraintStatus::Violated
+        }
+    }
 }
 
 /// Metadata about the execution environment.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ExecutionMetadata {
     /// Node ID that executed the intent.
     pub node_id: String,
@@ -158,17 +539,26 @@ impl ExecutionArtifact {
     /// Create a new execution artifact.
     pub fn new(intent: Intent, plan: Plan, outcome: Outcome) -> Self {
         let now = Utc::now();
+        let intent_hash = intent.content_hash();
+        let actual_cost_minor = intent.budget.max_cost_minor.as_ref().map(|limit| MoneyAmount::zero(&limit.currency));
         let mut artifact = Self {
             id: Uuid::new_v4(),
             intent,
+            intent_hash,
             final_plan: plan,
+            plan_revision: 0,
+            child_artifacts: Vec::new(),
             trace: Vec::new(),
             outcome,
             timestamp: now,
             merkle_root: String::new(),
+            merkle_version: MERKLE_VERSION,
             actual_cost: 0.0,
+            actual_cost_minor,
             actual_duration_ms: 0,
             execution_metadata: ExecutionMetadata::default(),
+            constraint_report: None,
+            signature: None,
         };
         artifact.merkle_root = artifact.compute_merkle_root();
         artifact
@@ -184,10 +574,69 @@ impl ExecutionArtifact {
         self.merkle_root = self.compute_merkle_root();
     }
 
-    /// Compute the Merkle root of the execution trace.
+    /// Add an event, truncating its data first if it exceeds `max_bytes`.
+    ///
+    /// Returns the original payload when it was truncated, so the caller
+    /// can keep it elsewhere. See [`ExecutionEvent::truncate_data`].
+    pub fn add_event_with_limit(&mut self, mut event: ExecutionEvent, max_bytes: usize) -> Option<serde_json::Value> {
+        let original = event.truncate_data(max_bytes);
+        self.add_event(event);
+        original
+    }
+
+    /// Add `cost` to the total, and to the exact total if one is tracked,
+    /// rounded to a minor unit. An amount the exact total cannot take,
+    /// e.g. on overflow, stops it being tracked.
+    pub fn add_cost(&mut self, cost: f64) {
+        self.actual_cost += cost;
+        self.actual_cost_minor = self
+            .actual_cost_minor
+            .take()
+            .and_then(|total| total.checked_add(&MoneyAmount::from_major(cost, &total.currency)?));
+    }
+
+    /// Add the total cost of a child intent's artifact, exactly if it
+    /// tracked one.
+    pub fn add_child_cost(&mut self, child: &ExecutionArtifact) {
+        self.actual_cost += child.actual_cost;
+        self.actual_cost_minor = self.actual_cost_minor.take().and_then(|total| match &child.actual_cost_minor {
+            Some(exact) => total.checked_add(exact),
+            None => total.checked_add(&MoneyAmount::from_major(child.actual_cost, &total.currency)?),
+        });
+    }
+
+    /// Total cost in major units, from `actual_cost_minor` when set.
+    pub fn total_cost(&self) -> f64 {
+        self.actual_cost_minor.as_ref().map_or(self.actual_cost, MoneyAmount::to_major)
+    }
+
+    /// Finalize the artifact once execution has ended.
+    ///
+    /// Stamps the completion time, recomputes the Merkle root and attaches
+    /// the constraint report.
+    pub fn finalize(&mut self) {
+        self.timestamp = Utc::now();
+        self.merkle_root = self.compute_merkle_root();
+        self.constraint_report = Some(ConstraintReport::evaluate(self));
+    }
+
+    /// Compute the Merkle root of the execution trace with the artifact's
+    /// `merkle_version`; empty if this build does not know that version.
     pub fn compute_merkle_root(&self) -> String {
+        self.merkle_root_with(self.merkle_version).unwrap_or_default()
+    }
+
+    /// Compute the Merkle root of the execution trace with a given
+    /// algorithm version, or `None` if the version is not supported.
+    pub fn merkle_root_with(&self, version: u8) -> Option<String> {
+        // Version 1 hashes without domain separation
+        let (leaf_prefix, node_prefix): (&[u8], &[u8]) = match version {
+            1 => (&[], &[]),
+            2 => (&[MERKLE_LEAF_PREFIX], &[MERKLE_NODE_PREFIX]),
+            _ => return None,
+        };
         if self.trace.is_empty() {
-            return "0".repeat(64);
+            return Some("0".repeat(64));
         }
 
         // Hash each event
@@ -197,6 +646,7 @@ impl ExecutionArtifact {
             .map(|event| {
                 let json = serde_json::to_string(event).unwrap_or_default();
                 let mut hasher = Sha256::new();
+                hasher.update(leaf_prefix);
                 hasher.update(json.as_bytes());
                 hasher.finalize().to_vec()
             })
@@ -208,6 +658,7 @@ impl ExecutionArtifact {
 
             for chunk in hashes.chunks(2) {
                 let mut hasher = Sha256::new();
+                hasher.update(node_prefix);
                 hasher.update(&chunk[0]);
                 if chunk.len() > 1 {
                     hasher.update(&chunk[1]);
@@ -221,15 +672,27 @@ impl ExecutionArtifact {
             hashes = next_level;
         }
 
-        hashes
-            .first()
-            .map(|h| h.iter().map(|b| format!("{:02x}", b)).collect())
-            .unwrap_or_else(|| "0".repeat(64))
+        hashes.first().map(crypto::hex_encode)
+    }
+
+    /// Hash of the artifact content, excluding the signature (hex-encoded SHA-256).
+    ///
+    /// This is the payload a node signs.
+    pub fn content_hash(&self) -> String {
+        let mut unsigned = self.clone();
+        unsigned.signature = None;
+
+        let json = serde_json::to_string(&unsigned).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(json.as_bytes());
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
     }
 
-    /// Verify the Merkle root matches the trace.
+    /// Verify the Merkle root matches the trace, using the algorithm
+    /// version recorded in `merkle_version`.
     pub fn verify_merkle_root(&self) -> bool {
-        self.merkle_root == self.compute_merkle_root()
+        self.merkle_root_with(self.merkle_version)
+            .is_some_and(|root| root == self.merkle_root)
     }
 
     /// Get all failed steps from the trace.
@@ -268,6 +731,53 @@ impl ExecutionArtifact {
 
         completed as f32 / total as f32
     }
+
+    /// The success rate (0.0 to 1.0) with each finished step counting for
+    /// its weight in the embedded plan: a failed 20-minute step then
+    /// outweighs several 50ms ones that succeeded. Falls back to counting
+    /// steps when every finished step weighs nothing, e.g. an unpriced plan.
+    pub fn success_rate_weighted(&self, weights: WeightBy) -> f32 {
+        let weight = |event: &ExecutionEvent| -> f64 {
+            let step = self.final_plan.steps.iter().find(|s| s.id == event.step_id);
+            match (weights, step) {
+                (WeightBy::Count, _) => 1.0,
+                (WeightBy::Cost, Some(step)) => step.estimated_cost.max(0.0),
+                (WeightBy::Duration, Some(step)) => step.estimated_duration_ms as f64,
+                (_, None) => 0.0,
+            }
+        };
+        let (mut completed, mut total) = (0.0, 0.0);
+        for event in &self.trace {
+            let w = match event.event_type {
+                ExecutionEventType::StepCompleted | ExecutionEventType::StepFailed => weight(event),
+                _ => continue,
+            };
+            total += w;
+            if event.event_type == ExecutionEventType::StepCompleted {
+                completed += w;
+            }
+        }
+
+        if total > 0.0 && total.is_finite() {
+            (completed / total) as f32
+        } else {
+            self.success_rate()
+        }
+    }
+}
+
+/// What each step counts for in
+/// [`ExecutionArtifact::success_rate_weighted`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightBy {
+    /// Every step counts the same, as in [`ExecutionArtifact::success_rate`].
+    Count,
+    /// The step's estimated cost.
+    #[default]
+    Cost,
+    /// The step's estimated duration.
+    Duration,
 }
 
 impl ExecutionEvent {
@@ -307,18 +817,158 @@ impl ExecutionEvent {
         }
     }
 
+    /// Create an event for a failed attempt that will be retried.
+    pub fn step_retrying(step_id: Uuid, attempt: u32, error: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id,
+            event_type: ExecutionEventType::StepRetrying,
+            timestamp: Utc::now(),
+            duration_ms: None,
+            data: serde_json::json!({ "attempt": attempt, "error": error.into() }),
+        }
+    }
+
+    /// Create an event for a step skipped by its `skip_if` condition.
+    pub fn step_skipped(step_id: Uuid, condition: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id,
+            event_type: ExecutionEventType::StepSkipped,
+            timestamp: Utc::now(),
+            duration_ms: None,
+            data: serde_json::json!({ "skip_if": condition.into() }),
+        }
+    }
+
+    /// Create an event for execution pausing. Pauses concern the whole
+    /// intent, so the event has a nil `step_id`.
+    pub fn paused() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id: Uuid::nil(),
+            event_type: ExecutionEventType::Paused,
+            timestamp: Utc::now(),
+            duration_ms: None,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    /// Create an event for execution resuming after a pause of
+    /// `paused_ms`. The pause adds to the artifact's `actual_duration_ms`
+    /// only if `counted`.
+    pub fn resumed(paused_ms: u64, counted: bool) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id: Uuid::nil(),
+            event_type: ExecutionEventType::Resumed,
+            timestamp: Utc::now(),
+            duration_ms: counted.then_some(paused_ms),
+            data: serde_json::json!({ "paused_ms": paused_ms }),
+        }
+    }
+
+    /// Create an event for an attempt that exceeded its timeout.
+    pub fn step_timed_out(step_id: Uuid, timeout_ms: u64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id,
+            event_type: ExecutionEventType::Timeout,
+            timestamp: Utc::now(),
+            duration_ms: Some(timeout_ms),
+            data: serde_json::Value::Null,
+        }
+    }
+
+    /// Create an event for a compensation action starting on a step.
+    pub fn compensation_started(step_id: Uuid, action: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id,
+            event_type: ExecutionEventType::CompensationStarted,
+            timestamp: Utc::now(),
+            duration_ms: None,
+            data: serde_json::json!({ "action": action.into() }),
+        }
+    }
+
+    /// Create an event for a compensation action that completed.
+    pub fn compensation_completed(step_id: Uuid, duration_ms: u64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            step_id,
+            event_type: ExecutionEventType::CompensationCompleted,
+            timestamp: Utc::now(),
+            duration_ms: Some(duration_ms),
+            data: serde_json::Value::Null,
+        }
+    }
+
     /// Add data to the event.
     pub fn with_data(mut self, data: serde_json::Value) -> Self {
         self.data = data;
         self
     }
+
+    /// Set one top-level data key, keeping the others.
+    pub fn with_data_field(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        if !self.data.is_object() {
+            self.data = serde_json::json!({});
+        }
+        if let Some(data) = self.data.as_object_mut() {
+            data.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// The data at a JSON pointer such as `/output/status`, or `None` if
+    /// it is missing or null. Fails if it is not a `T`.
+    pub fn data_get<T: DeserializeOwned>(&self, pointer: &str) -> Result<Option<T>> {
+        pointer::get(&self.data, pointer)
+    }
+
+    /// Set the data at a JSON pointer, creating objects on the way.
+    pub fn data_set(&mut self, pointer: &str, value: impl Serialize) -> Result<()> {
+        pointer::set(&mut self.data, pointer, value)
+    }
+
+    /// Replace `data` with a reference if its serialized size exceeds `max_bytes`.
+    ///
+    /// The reference keeps a preview of the serialized payload, its size and
+    /// its SHA-256, and is marked `"truncated": true`. Returns the original
+    /// payload when it was replaced.
+    pub fn truncate_data(&mut self, max_bytes: usize) -> Option<serde_json::Value> {
+        let json = serde_json::to_string(&self.data).unwrap_or_default();
+        if json.len() <= max_bytes {
+            return None;
+        }
+
+        let mut preview_len = EVENT_DATA_PREVIEW_BYTES.min(max_bytes).min(json.len());
+        while !json.is_char_boundary(preview_len) {
+            preview_len -= 1;
+        }
+
+        let reference = serde_json::json!({
+            "truncated": true,
+            "preview": &json[..preview_len],
+            "size_bytes": json.len(),
+            "sha256": crypto::hex_encode(Sha256::digest(json.as_bytes())),
+        });
+        Some(std::mem::replace(&mut self.data, reference))
+    }
+
+    /// Whether this event's data was replaced by [`truncate_data`](Self::truncate_data).
+    pub fn is_truncated(&self) -> bool {
+        self.data.get("truncated").and_then(|t| t.as_bool()).unwrap_or(false)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::intent::Intent;
-    use crate::plan::{Plan, PlanningStrategy};
+    use crate::error::OrpheonError;
+    use crate::intent::{Budget, Intent};
+    use crate::plan::{Plan, PlanningStrategy, Step};
 
     fn create_test_intent() -> Intent {
         Intent::builder()
@@ -350,6 +1000,135 @@ mod tests {
         assert!(artifact.verify_merkle_root());
     }
 
+    /// Signed artifacts emitted with each Merkle version.
+    const MERKLE_V1_FIXTURE: &str = include_str!("../fixtures/artifact_merkle_v1.json");
+    const MERKLE_V2_FIXTURE: &str = include_str!("../fixtures/artifact_merkle_v2.json");
+
+    #[test]
+    fn test_merkle_fixtures_verify_with_their_version() {
+        for (fixture, version) in [(MERKLE_V1_FIXTURE, 1), (MERKLE_V2_FIXTURE, 2)] {
+            let artifact: ExecutionArtifact = serde_json::from_str(fixture).unwrap();
+            assert_eq!(artifact.merkle_version, version);
+            assert!(artifact.verify_merkle_root());
+
+            // Re-serializing keeps the signed content unchanged
+            #[cfg(feature = "ed25519")]
+            {
+                let signature = artifact.signature.as_ref().unwrap();
+                crypto::verify_ed25519(&signature.public_key, artifact.content_hash().as_bytes(), &signature.signature)
+                    .unwrap();
+            }
+        }
+
+        // Version 1 artifacts predate the field and still omit it
+        let v1: ExecutionArtifact = serde_json::from_str(MERKLE_V1_FIXTURE).unwrap();
+        assert!(serde_json::to_value(&v1).unwrap().get("merkle_version").is_none());
+    }
+
+    #[test]
+    fn test_merkle_versions_hash_differently() {
+        let v1: ExecutionArtifact = serde_json::from_str(MERKLE_V1_FIXTURE).unwrap();
+        let mut as_v2 = v1.clone();
+        as_v2.merkle_version = 2;
+        assert!(!as_v2.verify_merkle_root());
+        assert_ne!(v1.merkle_root_with(1), v1.merkle_root_with(2));
+
+        // A single leaf is still prefixed, so it differs from the plain hash
+        let mut single = v1.clone();
+        single.trace.truncate(1);
+        assert_ne!(single.merkle_root_with(1), single.merkle_root_with(2));
+
+        let mut unknown = v1;
+        unknown.merkle_version = 9;
+        assert_eq!(unknown.merkle_root_with(9), None);
+        assert!(!unknown.verify_merkle_root());
+    }
+
+    #[test]
+    fn test_new_artifacts_use_current_merkle_version() {
+        let intent = create_test_intent();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.add_event(ExecutionEvent::step_started(Uuid::new_v4()));
+
+        assert_eq!(artifact.merkle_version, MERKLE_VERSION);
+        assert_eq!(Some(artifact.merkle_root.clone()), artifact.merkle_root_with(2));
+        assert_eq!(serde_json::to_value(&artifact).unwrap()["merkle_version"], 2);
+    }
+
+    #[test]
+    fn test_content_hash_survives_round_trip() {
+        let intent = create_test_intent();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        let step_id = Uuid::new_v4();
+        artifact.add_event(ExecutionEvent::step_started(step_id));
+        artifact.add_event(ExecutionEvent::step_completed(step_id, 100));
+        artifact.actual_cost = 3.7;
+        artifact.finalize();
+
+        let json = serde_json::to_string(&artifact).unwrap();
+        let decoded: ExecutionArtifact = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.content_hash(), artifact.content_hash());
+        assert_eq!(decoded.intent_hash, decoded.intent.content_hash());
+
+        // The signature is not part of the signed content
+        let mut signed = decoded.clone();
+        signed.signature = Some(Signature {
+            algorithm: "ed25519".to_string(),
+            public_key: "00".to_string(),
+            signature: "00".to_string(),
+            signed_at: Utc::now(),
+        });
+        assert_eq!(signed.content_hash(), artifact.content_hash());
+
+        signed.actual_cost = 1.0;
+        assert_ne!(signed.content_hash(), artifact.content_hash());
+    }
+
+    #[test]
+    fn test_exact_cost_does_not_drift() {
+        let intent = Intent::builder().kind("test").budget(Budget::usd_cents(10_000_000_000_000)).build().unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        for _ in 0..10_000 {
+            artifact.add_cost(9_876_543.21);
+        }
+
+        assert_eq!(artifact.actual_cost_minor, Some(MoneyAmount::usd_cents(9_876_543_210_000)));
+        assert_eq!(artifact.total_cost(), 98_765_432_100.0);
+        let drift = (artifact.actual_cost - 98_765_432_100.0).abs();
+        assert!(drift >= 0.01, "the float total drifted by only {}", drift);
+    }
+
+    #[test]
+    fn test_float_only_artifacts_are_unchanged() {
+        let intent = create_test_intent();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.add_cost(2.5);
+        artifact.finalize();
+
+        // No exact total is tracked, or serialized, without an exact budget
+        let mut json = serde_json::to_value(&artifact).unwrap();
+        assert!(json.get("actual_cost_minor").is_none());
+        assert_eq!(artifact.total_cost(), 2.5);
+
+        json.as_object_mut().unwrap().remove("actual_cost_minor");
+        let decoded: ExecutionArtifact = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.actual_cost_minor, None);
+        assert_eq!(decoded.content_hash(), artifact.content_hash());
+
+        let mut parent = ExecutionArtifact::new(
+            Intent::builder().kind("test").budget(Budget::usd_cents(1_000)).build().unwrap(),
+            Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic),
+            Outcome::Success,
+        );
+        parent.add_child_cost(&decoded);
+        parent.add_child_cost(&decoded);
+        assert_eq!(parent.actual_cost_minor, Some(MoneyAmount::usd_cents(500)));
+    }
+
     #[test]
     fn test_success_rate() {
         let intent = create_test_intent();
@@ -368,6 +1147,139 @@ mod tests {
         assert!((rate - 0.666).abs() < 0.01);
     }
 
+    #[test]
+    fn test_success_rate_weighted_by_cost() {
+        let intent = create_test_intent();
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let checks: Vec<Step> = (0..5)
+            .map(|i| Step::new(format!("verify_{}", i), "verify").with_cost(0.01).with_duration(50))
+            .collect();
+        let provision = Step::new("provision", "provision_cluster").with_cost(10.0).with_duration(20 * 60 * 1000);
+        for step in checks.iter().chain([&provision]) {
+            plan.add_step(step.clone());
+        }
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        for step in &checks {
+            artifact.add_event(ExecutionEvent::step_completed(step.id, 50));
+        }
+        artifact.add_event(ExecutionEvent::step_failed(provision.id, "quota exceeded"));
+
+        let by_count = artifact.success_rate_weighted(WeightBy::Count);
+        assert_eq!(by_count, artifact.success_rate());
+        assert!((by_count - 0.833).abs() < 0.01, "{}", by_count);
+        let by_cost = artifact.success_rate_weighted(WeightBy::Cost);
+        assert!(by_cost < 0.01, "{}", by_cost);
+        let by_duration = artifact.success_rate_weighted(WeightBy::Duration);
+        assert!(by_duration < 0.001, "{}", by_duration);
+
+        // An unpriced plan is judged by count
+        let mut free = artifact.clone();
+        free.final_plan.steps.iter_mut().for_each(|s| s.estimated_cost = 0.0);
+        assert_eq!(free.success_rate_weighted(WeightBy::Cost), by_count);
+    }
+
+    #[test]
+    fn test_constraint_report_resource_limit_satisfied() {
+        let intent = Intent::builder()
+            .kind("test_intent")
+            .resource_limit("total_cost", 10.0)
+            .build()
+            .unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+
+        let step = Uuid::new_v4();
+        let completed = ExecutionEvent::step_completed(step, 100);
+        let completed_id = completed.id;
+        artifact.add_event(completed);
+        artifact.actual_cost = 4.5;
+        artifact.finalize();
+
+        let report = artifact.constraint_report.as_ref().unwrap();
+        assert!(report.all_satisfied);
+        assert_eq!(report.checks.len(), 1);
+        assert!(report.checks[0].satisfied());
+        assert_eq!(report.checks[0].measured, Some(serde_json::json!(4.5)));
+        assert_eq!(report.checks[0].evidence, vec![completed_id]);
+    }
+
+    #[test]
+    fn test_constraint_report_deadline_violated_on_success() {
+        let intent = Intent::builder()
+            .kind("test_intent")
+            .constraint(Constraint::Deadline {
+                by: Utc::now() - chrono::Duration::seconds(1),
+            })
+            .build()
+            .unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.add_event(ExecutionEvent::step_completed(Uuid::new_v4(), 100));
+        artifact.finalize();
+
+        // Outcome stays Success, but the report flags the missed deadline
+        assert!(artifact.outcome.is_success());
+        let report = artifact.constraint_report.as_ref().unwrap();
+        assert!(!report.all_satisfied);
+        assert_eq!(report.violations().len(), 1);
+        assert_eq!(report.checks[0].status, ConstraintStatus::Violated);
+        assert_eq!(report.checks[0].evidence, vec![artifact.trace[0].id]);
+    }
+
+    #[test]
+    fn test_constraint_report_custom_not_evaluated() {
+        let intent = Intent::builder()
+            .kind("test_intent")
+            .constraint(Constraint::Custom {
+                name: "gpu_vendor".to_string(),
+                data: serde_json::json!({"vendor": "nvidia"}),
+            })
+            .build()
+            .unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.finalize();
+
+        let report = artifact.constraint_report.as_ref().unwrap();
+        assert!(report.all_satisfied);
+        assert_eq!(report.checks[0].status, ConstraintStatus::NotEvaluated);
+        assert!(!report.checks[0].checked());
+    }
+
+    #[test]
+    fn test_constraint_report_soft_violation_is_warning() {
+        let intent = Intent::builder()
+            .kind("test_intent")
+            .soft_constraint(Constraint::GeoFence {
+                regions: vec!["eu-west".to_string()],
+                allowed: false,
+            })
+            .build()
+            .unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.execution_metadata.region = Some("eu-west".to_string());
+        artifact.finalize();
+
+        let report = artifact.constraint_report.as_ref().unwrap();
+        assert!(report.all_satisfied);
+        assert!(report.violations().is_empty());
+        assert_eq!(report.warnings().len(), 1);
+        assert!(report.checks[0].soft);
+    }
+
+    #[test]
+    fn test_artifact_without_report_deserializes() {
+        let intent = create_test_intent();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+
+        let mut json = serde_json::to_value(&artifact).unwrap();
+        json.as_object_mut().unwrap().remove("constraint_report");
+        let restored: ExecutionArtifact = serde_json::from_value(json).unwrap();
+        assert!(restored.constraint_report.is_none());
+    }
+
     #[test]
     fn test_outcome_checks() {
         assert!(Outcome::Success.is_success());
@@ -380,4 +1292,91 @@ mod tests {
         assert!(failure.is_failure());
         assert!(!failure.is_success());
     }
+
+    #[test]
+    fn test_outcome_summaries() {
+        let cancelled = |by: &str| Outcome::Cancelled { by: by.to_string(), reason: "too slow".to_string() };
+        let failure = |compensated| Outcome::Failure { reason: "quota exceeded".to_string(), compensated };
+        let cases = [
+            (Outcome::Success, OutcomeSeverity::Ok, false, 0, "success"),
+            (failure(false), OutcomeSeverity::Error, true, 2, "failure (quota exceeded)"),
+            (failure(true), OutcomeSeverity::Error, false, 2, "failure (quota exceeded, compensated)"),
+            (
+                Outcome::partial(0.75, "optional steps failed: notify"),
+                OutcomeSeverity::Warning,
+                false,
+                1,
+                "partial success at 75% (optional steps failed: notify)",
+            ),
+            (cancelled("timeout"), OutcomeSeverity::Warning, true, 3, "cancelled by timeout (too slow)"),
+            (cancelled("client"), OutcomeSeverity::Warning, false, 3, "cancelled by client (too slow)"),
+        ];
+        for (outcome, severity, retryable, exit_code, summary) in cases {
+            assert_eq!(outcome.severity(), severity, "{:?}", outcome);
+            assert_eq!(outcome.is_retryable(), retryable, "{:?}", outcome);
+            assert_eq!(i32::from(&outcome), exit_code, "{:?}", outcome);
+            assert_eq!(outcome.summary(), summary);
+        }
+        assert!(OutcomeSeverity::Ok < OutcomeSeverity::Warning && OutcomeSeverity::Warning < OutcomeSeverity::Error);
+    }
+
+    #[test]
+    fn test_partial_outcome_clamps_its_rate() {
+        let rate = |fraction: f32| match Outcome::partial(fraction, "") {
+            Outcome::PartialSuccess { success_rate, .. } => success_rate,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(rate(0.333), 33);
+        assert_eq!(rate(0.666), 67);
+        assert_eq!(rate(1.0), 100);
+        assert_eq!(rate(1.5), 100);
+        assert_eq!(rate(-0.2), 0);
+        assert_eq!(rate(f32::NAN), 0);
+        assert_eq!(rate(f32::INFINITY), 100);
+    }
+
+    #[test]
+    fn test_oversized_event_data_is_truncated() {
+        let intent = create_test_intent();
+        let plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        let payload = serde_json::json!({ "body": "x".repeat(10_000) });
+        let full_json = serde_json::to_string(&payload).unwrap();
+
+        let small = ExecutionEvent::step_completed(Uuid::new_v4(), 10).with_data(serde_json::json!({"ok": true}));
+        assert!(artifact.add_event_with_limit(small, 1024).is_none());
+
+        let big = ExecutionEvent::step_completed(Uuid::new_v4(), 10).with_data(payload.clone());
+        assert_eq!(artifact.add_event_with_limit(big, 1024), Some(payload));
+
+        let stored = &artifact.trace[1];
+        assert!(stored.is_truncated());
+        assert!(!artifact.trace[0].is_truncated());
+        assert_eq!(stored.data["size_bytes"], full_json.len());
+        assert_eq!(
+            stored.data["sha256"],
+            crypto::hex_encode(Sha256::digest(full_json.as_bytes()))
+        );
+        assert!(full_json.starts_with(stored.data["preview"].as_str().unwrap()));
+
+        // The merkle root covers the stored, truncated event
+        artifact.finalize();
+        let decoded: ExecutionArtifact = serde_json::from_str(&serde_json::to_string(&artifact).unwrap()).unwrap();
+        assert!(decoded.verify_merkle_root());
+    }
+
+    #[test]
+    fn test_typed_event_data_accessors() {
+        let mut event = ExecutionEvent::step_completed(Uuid::new_v4(), 10)
+            .with_data_field("status", 200)
+            .with_data_field("body", serde_json::json!({ "ok": true }));
+
+        assert_eq!(event.data_get::<u16>("/status").unwrap(), Some(200));
+        assert_eq!(event.data_get::<bool>("/body/ok").unwrap(), Some(true));
+        assert_eq!(event.data_get::<bool>("/body/retried").unwrap(), None);
+        assert!(matches!(event.data_get::<String>("/status"), Err(OrpheonError::SerializationError(_))));
+
+        event.data_set("/body/headers/etag", "abc").unwrap();
+        assert_eq!(event.data["body"], serde_json::json!({ "ok": true, "headers": { "etag": "abc" } }));
+    }
 }
@@ -0,0 +1,344 @@
+//! Self-contained artifact bundles for offline auditing.
+//!
+//! A bundle carries everything needed to check an execution without talking
+//! to the node: the artifact, the executed plan, the intent, the node public
+//! key, and a manifest of SHA-256 hashes signed by the node key.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::artifact::ExecutionArtifact;
+use crate::crypto::{self, NodeKey};
+use crate::intent::Intent;
+use crate::plan::Plan;
+
+/// Current bundle format version.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// A component of an artifact bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleComponent {
+    /// The execution artifact.
+    Artifact,
+    /// The executed plan.
+    Plan,
+    /// The intent that was fulfilled.
+    Intent,
+    /// The hex-encoded public key of the signing node.
+    NodePublicKey,
+}
+
+impl BundleComponent {
+    /// All components, in manifest order.
+    pub const ALL: [BundleComponent; 4] = [
+        BundleComponent::Artifact,
+        BundleComponent::Plan,
+        BundleComponent::Intent,
+        BundleComponent::NodePublicKey,
+    ];
+
+    /// File name used when the bundle is packed as an archive.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            BundleComponent::Artifact => "artifact.json",
+            BundleComponent::Plan => "plan.json",
+            BundleComponent::Intent => "intent.json",
+            BundleComponent::NodePublicKey => "node_public_key",
+        }
+    }
+}
+
+impl fmt::Display for BundleComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BundleComponent::Artifact => "artifact",
+            BundleComponent::Plan => "plan",
+            BundleComponent::Intent => "intent",
+            BundleComponent::NodePublicKey => "node_public_key",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Hash of one bundle component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The component.
+    pub component: BundleComponent,
+    /// Hex-encoded SHA-256 of the component bytes.
+    pub sha256: String,
+}
+
+/// Manifest describing a bundle's contents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle format version.
+    pub format_version: u32,
+    /// Schema version of each component type.
+    pub schema_versions: BTreeMap<String, String>,
+    /// The intent the bundle is for.
+    pub intent_id: Uuid,
+    /// The bundled artifact.
+    pub artifact_id: Uuid,
+    /// When the bundle was produced.
+    pub created_at: DateTime<Utc>,
+    /// Hash of each component.
+    pub components: Vec<ManifestEntry>,
+}
+
+impl BundleManifest {
+    /// Hex-encoded SHA-256 of the manifest; this is what the node signs.
+    pub fn hash(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        crypto::hex_encode(Sha256::digest(&json))
+    }
+
+    /// Look up the recorded hash of a component.
+    pub fn entry(&self, component: BundleComponent) -> Option<&ManifestEntry> {
+        self.components.iter().find(|e| e.component == component)
+    }
+}
+
+/// Errors found when verifying a bundle.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum BundleError {
+    /// The bundle uses a format this version does not understand.
+    #[error("unsupported bundle format version {0}")]
+    UnsupportedVersion(u32),
+
+    /// The manifest does not list a component.
+    #[error("manifest does not list component {0}")]
+    MissingComponent(BundleComponent),
+
+    /// A component does not match its manifest hash.
+    #[error("component {component} does not match the manifest (expected {expected}, got {actual})")]
+    HashMismatch {
+        component: BundleComponent,
+        expected: String,
+        actual: String,
+    },
+
+    /// A component contradicts another component.
+    #[error("component {component} is inconsistent: {message}")]
+    Inconsistent {
+        component: BundleComponent,
+        message: String,
+    },
+
+    /// The manifest signature is invalid.
+    #[error("invalid bundle signature: {0}")]
+    InvalidSignature(String),
+}
+
+/// A signed, self-contained execution bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactBundle {
+    /// Manifest of component hashes.
+    pub manifest: BundleManifest,
+    /// The execution artifact.
+    pub artifact: ExecutionArtifact,
+    /// The executed plan.
+    pub plan: Plan,
+    /// The intent that was fulfilled.
+    pub intent: Intent,
+    /// Hex-encoded ed25519 public key of the node.
+    pub node_public_key: String,
+    /// Hex-encoded signature over the manifest hash.
+    pub signature: String,
+}
+
+impl ArtifactBundle {
+    /// Build and sign a bundle for an artifact.
+    pub fn new(artifact: ExecutionArtifact, node_key: &NodeKey) -> Self {
+        let schema_version = env!("CARGO_PKG_VERSION").to_string();
+        let schema_versions = ["artifact", "plan", "intent"]
+            .into_iter()
+            .map(|name| (name.to_string(), schema_version.clone()))
+            .collect();
+
+        let mut bundle = Self {
+            manifest: BundleManifest {
+                format_version: BUNDLE_FORMAT_VERSION,
+                schema_versions,
+                intent_id: artifact.intent.id,
+                artifact_id: artifact.id,
+                created_at: Utc::now(),
+                components: Vec::new(),
+            },
+            plan: artifact.final_plan.clone(),
+            intent: artifact.intent.clone(),
+            artifact,
+            node_public_key: node_key.public_key_hex(),
+            signature: String::new(),
+        };
+
+        bundle.manifest.components = BundleComponent::ALL
+            .into_iter()
+            .map(|component| ManifestEntry {
+                component,
+                sha256: bundle.component_hash(component),
+            })
+            .collect();
+        bundle.signature = node_key.sign(bundle.manifest.hash().as_bytes());
+        bundle
+    }
+
+    /// Serialized bytes of a component, as hashed in the manifest.
+    pub fn component_bytes(&self, component: BundleComponent) -> Vec<u8> {
+        match component {
+            BundleComponent::Artifact => serde_json::to_vec(&self.artifact),
+            BundleComponent::Plan => serde_json::to_vec(&self.plan),
+            BundleComponent::Intent => serde_json::to_vec(&self.intent),
+            BundleComponent::NodePublicKey => Ok(self.node_public_key.as_bytes().to_vec()),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Hex-encoded SHA-256 of a component.
+    pub fn component_hash(&self, component: BundleComponent) -> String {
+        crypto::hex_encode(Sha256::digest(self.component_bytes(component)))
+    }
+
+    /// Verify the bundle offline.
+    ///
+    /// Checks every component against the manifest, checks the components
+    /// agree with each other, and checks the manifest signature against the
+    /// bundled node key. Callers should also compare `node_public_key` with
+    /// a key they trust.
+    pub fn verify(&self) -> Result<(), BundleError> {
+        if self.manifest.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedVersion(self.manifest.format_version));
+        }
+
+        for component in BundleComponent::ALL {
+            let entry = self
+                .manifest
+                .entry(component)
+                .ok_or(BundleError::MissingComponent(component))?;
+            let actual = self.component_hash(component);
+            if !entry.sha256.eq_ignore_ascii_case(&actual) {
+                return Err(BundleError::HashMismatch {
+                    component,
+                    expected: entry.sha256.clone(),
+                    actual,
+                });
+            }
+        }
+
+        self.check_consistency()?;
+
+        crypto::verify_ed25519(&self.node_public_key, self.manifest.hash().as_bytes(), &self.signature)
+            .map_err(|e| BundleError::InvalidSignature(e.to_string()))
+    }
+
+    fn check_consistency(&self) -> Result<(), BundleError> {
+        let inconsistent = |component, message: String| Err(BundleError::Inconsistent { component, message });
+
+        if self.artifact.id != self.manifest.artifact_id {
+            return inconsistent(BundleComponent::Artifact, "artifact id does not match the manifest".to_string());
+        }
+        if self.intent.id != self.manifest.intent_id || self.intent.id != self.artifact.intent.id {
+            return inconsistent(BundleComponent::Intent, format!("intent {} is not the artifact's intent", self.intent.id));
+        }
+        if self.plan.id != self.artifact.final_plan.id || self.plan.intent_id != self.intent.id {
+            return inconsistent(BundleComponent::Plan, format!("plan {} is not the artifact's final plan", self.plan.id));
+        }
+        if let Some(signature) = &self.artifact.signature {
+            if !signature.public_key.eq_ignore_ascii_case(&self.node_public_key) {
+                return inconsistent(BundleComponent::NodePublicKey, "artifact was signed by a different key".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::{ExecutionEvent, Outcome};
+    use crate::plan::{PlanningStrategy, Step};
+
+    fn bundle() -> (ArtifactBundle, NodeKey) {
+        let key = NodeKey::from_bytes(&[3u8; 32]);
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(2.0));
+        let step_id = plan.steps[0].id;
+
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.add_event(ExecutionEvent::step_started(step_id));
+        artifact.add_event(ExecutionEvent::step_completed(step_id, 100));
+        artifact.finalize();
+        key.sign_artifact(&mut artifact);
+
+        (ArtifactBundle::new(artifact, &key), key)
+    }
+
+    /// Serialize and parse, as an auditor receiving the file would.
+    fn round_trip(bundle: &ArtifactBundle) -> ArtifactBundle {
+        serde_json::from_str(&serde_json::to_string(bundle).unwrap()).unwrap()
+    }
+
+    fn mismatched_component(bundle: &ArtifactBundle) -> Option<BundleComponent> {
+        match bundle.verify() {
+            Err(BundleError::HashMismatch { component, .. }) => Some(component),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_verifies_after_round_trip() {
+        let (bundle, key) = bundle();
+        assert!(bundle.verify().is_ok());
+        assert!(round_trip(&bundle).verify().is_ok());
+        assert_eq!(bundle.node_public_key, key.public_key_hex());
+        assert_eq!(bundle.manifest.components.len(), 4);
+    }
+
+    #[test]
+    fn test_corrupted_component_is_pinpointed() {
+        let (bundle, _) = bundle();
+
+        let mut corrupted = round_trip(&bundle);
+        corrupted.plan.steps[0].estimated_cost = 0.0;
+        assert_eq!(mismatched_component(&corrupted), Some(BundleComponent::Plan));
+
+        let mut corrupted = round_trip(&bundle);
+        corrupted.intent.kind = "destroy".to_string();
+        assert_eq!(mismatched_component(&corrupted), Some(BundleComponent::Intent));
+
+        let mut corrupted = round_trip(&bundle);
+        corrupted.artifact.actual_cost = 99.0;
+        assert_eq!(mismatched_component(&corrupted), Some(BundleComponent::Artifact));
+
+        let mut corrupted = round_trip(&bundle);
+        corrupted.node_public_key = NodeKey::from_bytes(&[4u8; 32]).public_key_hex();
+        assert_eq!(mismatched_component(&corrupted), Some(BundleComponent::NodePublicKey));
+    }
+
+    #[test]
+    fn test_rehashed_manifest_fails_signature() {
+        let (mut bundle, _) = bundle();
+        bundle.plan.steps[0].estimated_cost = 0.0;
+        // An attacker updating the manifest hash still cannot re-sign it
+        bundle.manifest.components[1].sha256 = bundle.component_hash(BundleComponent::Plan);
+
+        assert!(matches!(bundle.verify(), Err(BundleError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_missing_manifest_entry() {
+        let (mut bundle, _) = bundle();
+        bundle.manifest.components.retain(|e| e.component != BundleComponent::Intent);
+
+        assert_eq!(bundle.verify(), Err(BundleError::MissingComponent(BundleComponent::Intent)));
+    }
+}
@@ -0,0 +1,123 @@
+//! Canonical JSON encoding for content hashes.
+//!
+//! Hashes that get signed must not depend on how a JSON library happens to
+//! order keys or print numbers, so they are taken over this encoding:
+//!
+//! - object keys are sorted by their UTF-8 bytes;
+//! - there is no whitespace outside strings;
+//! - strings are escaped as by `serde_json` (only `"`, `\` and control
+//!   characters are escaped);
+//! - integers are written as is, and other numbers as the shortest decimal
+//!   that round-trips the `f64`, without an exponent (`5.0` is `5`, `-0.0`
+//!   is `0`, `0.1` is `0.1`).
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::crypto::hex_encode;
+use crate::error::{OrpheonError, Result};
+
+/// Encode a JSON value canonically.
+pub fn encode(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Hex-encoded SHA-256 of the canonical encoding of a JSON value.
+pub fn hash(value: &Value) -> String {
+    hex_encode(Sha256::digest(encode(value).as_bytes()))
+}
+
+/// Hex-encoded SHA-256 of the canonical encoding of anything serializable.
+pub fn hash_of<T: Serialize>(value: &T) -> Result<String> {
+    let value = serde_json::to_value(value).map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+    Ok(hash(&value))
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                out.push_str(&i.to_string());
+            } else if let Some(u) = n.as_u64() {
+                out.push_str(&u.to_string());
+            } else {
+                out.push_str(&format_f64(n.as_f64().unwrap_or_default()));
+            }
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // Serializing a string cannot fail
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+/// Shortest round-tripping decimal, never in exponent form.
+fn format_f64(f: f64) -> String {
+    if f == 0.0 {
+        return "0".to_string();
+    }
+    format!("{}", f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_encoding_is_canonical() {
+        let value = json!({
+            "b": [1, -2, 3.5, 5.0, -0.0, 0.1, 1e21, 18446744073709551615u64],
+            "a": { "z": null, "é": "line\nbreak \"quoted\"", "B": true },
+        });
+        assert_eq!(
+            encode(&value),
+            r#"{"a":{"B":true,"z":null,"é":"line\nbreak \"quoted\""},"b":[1,-2,3.5,5,0,0.1,1000000000000000000000,18446744073709551615]}"#
+        );
+    }
+
+    #[test]
+    fn test_key_order_does_not_matter() {
+        let mut forward = serde_json::Map::new();
+        let mut backward = serde_json::Map::new();
+        for key in ["alpha", "beta", "gamma"] {
+            forward.insert(key.to_string(), json!(key.len()));
+        }
+        for key in ["gamma", "beta", "alpha"] {
+            backward.insert(key.to_string(), json!(key.len()));
+        }
+        assert_eq!(hash(&Value::Object(forward)), hash(&Value::Object(backward)));
+        assert_eq!(hash_of(&json!({ "a": 1 })).unwrap(), hash(&json!({ "a": 1 })));
+    }
+}
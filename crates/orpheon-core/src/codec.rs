@@ -0,0 +1,99 @@
+//! Compact binary encoding of the wire types.
+//!
+//! [`Intent`](crate::Intent), [`Plan`](crate::Plan) and
+//! [`ExecutionArtifact`](crate::ExecutionArtifact) encode to CBOR (RFC 8949)
+//! through the same serde impls as JSON, so anything that survives a JSON
+//! round trip survives a CBOR one. Free-form `serde_json::Value` fields keep
+//! their numbers as they were: integers stay integers and `1.0` stays a
+//! float. Content hashes and signatures are still taken over canonical
+//! JSON, so decoding a CBOR artifact does not break its signature.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{OrpheonError, Result};
+
+/// Media type of CBOR bodies.
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Encode a value as CBOR.
+pub fn to_cbor<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Decode a value from CBOR.
+pub fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    ciborium::from_reader(bytes).map_err(|e| OrpheonError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::artifact::ExecutionEventType;
+    use crate::{Constraint, ExecutionArtifact, ExecutionEvent, Intent, Outcome, Plan, PlanningStrategy, Step};
+
+    #[test]
+    fn test_intent_round_trips() {
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: true })
+            .constraint(Constraint::Custom { name: "approved".to_string(), data: json!({ "by": ["ops"], "quorum": 2 }) })
+            .soft_constraint(Constraint::Sla { metric: "latency".to_string(), threshold: 250, unit: "ms".to_string() })
+            .metadata(json!({ "ratio": 1.0, "count": 3, "big": u64::MAX, "none": null, "nested": { "x": [1.5, "a"] } }))
+            .build()
+            .unwrap();
+
+        let decoded: Intent = from_cbor(&to_cbor(&intent).unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&intent).unwrap());
+        assert_eq!(decoded.content_hash(), intent.content_hash());
+        assert!(decoded.metadata["ratio"].is_f64());
+        assert!(decoded.metadata["count"].is_u64());
+        assert_eq!(decoded.metadata["big"], u64::MAX);
+    }
+
+    #[test]
+    fn test_plan_round_trips() {
+        let intent_id = Uuid::new_v4();
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Deterministic);
+        plan.add_step(Step::new("provision", "provision").with_parameters(json!({ "size": 2, "weights": [0.5, 1.0] })));
+
+        let decoded: Plan = from_cbor(&to_cbor(&plan).unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&plan).unwrap());
+    }
+
+    #[test]
+    fn test_artifact_round_trips_smaller_than_json() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let outcome = Outcome::Failure { reason: "quota".to_string(), compensated: true };
+        let mut artifact = ExecutionArtifact::new(intent, plan, outcome);
+        let step_id = Uuid::new_v4();
+        for i in 0..50 {
+            artifact.add_event(ExecutionEvent::step_started(step_id).with_data(json!({ "attempt": i, "load": 0.25 })));
+        }
+        let mut custom = ExecutionEvent::step_started(step_id);
+        custom.event_type = ExecutionEventType::Custom("checkpoint".to_string());
+        artifact.add_event(custom);
+        artifact.finalize();
+
+        let bytes = to_cbor(&artifact).unwrap();
+        assert!(bytes.len() < serde_json::to_vec(&artifact).unwrap().len());
+        let decoded: ExecutionArtifact = from_cbor(&bytes).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&artifact).unwrap());
+        assert_eq!(decoded.content_hash(), artifact.content_hash());
+        assert!(decoded.verify_merkle_root());
+        assert_eq!(decoded.outcome, artifact.outcome);
+        assert_eq!(decoded.trace.last().unwrap().event_type, ExecutionEventType::Custom("checkpoint".to_string()));
+    }
+
+    #[test]
+    fn test_malformed_cbor_is_a_serialization_error() {
+        let err = from_cbor::<Plan>(&[0xff, 0x00]).unwrap_err();
+        assert!(matches!(err, OrpheonError::SerializationError(_)), "{:?}", err);
+    }
+}
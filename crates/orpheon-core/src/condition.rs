@@ -0,0 +1,401 @@
+//! Boolean conditions over execution context and state.
+//!
+//! A [`StateExpr`] is evaluated against the same values steps can bind to
+//! (see [`crate::context`]) plus `state`, a snapshot of state-store keys:
+//!
+//! ```text
+//! intent.metadata.fast_mode                         truthy test
+//! intent.metadata.replicas >= 3                     comparison with a literal
+//! state['compute/gpu-1'] && !intent.metadata.force  && || ! and parentheses
+//! state['quota/compute'].available < 4              fields of a state value
+//! ```
+//!
+//! Literals are numbers, quoted strings, `true`, `false` and `null`. `null`,
+//! `false`, `0`, `""` and empty arrays and objects are falsy. A path that
+//! leads nowhere is an error rather than `false`, so typos do not pass
+//! silently; state keys that are not set are present as `null`.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// Errors from parsing or evaluating a [`StateExpr`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConditionError {
+    /// The expression is malformed.
+    #[error("invalid condition {text:?}: {message}")]
+    Invalid { text: String, message: String },
+
+    /// Nothing exists at a path the expression reads.
+    #[error("condition reads missing variable {path}")]
+    Missing { path: String },
+}
+
+/// A parsed boolean condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateExpr {
+    text: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Or(Vec<Node>),
+    And(Vec<Node>),
+    Not(Box<Node>),
+    Truthy(Vec<String>),
+    Compare(Vec<String>, Op, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl StateExpr {
+    /// Parse a condition.
+    pub fn parse(text: &str) -> Result<Self, ConditionError> {
+        let invalid = |message: String| ConditionError::Invalid { text: text.to_string(), message };
+        let tokens = tokenize(text).map_err(invalid)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser.or().map_err(invalid)?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {}", token)));
+        }
+        Ok(Self { text: text.trim().to_string(), root })
+    }
+
+    /// The condition as written.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// State-store keys the condition reads, i.e. those under `state`.
+    pub fn state_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        self.root.collect_state_keys(&mut keys);
+        keys
+    }
+
+    /// Evaluate against a JSON root such as an execution context's.
+    pub fn eval(&self, root: &Value) -> Result<bool, ConditionError> {
+        self.root.evaluate(root)
+    }
+}
+
+impl FromStr for StateExpr {
+    type Err = ConditionError;
+
+    fn from_str(text: &str) -> Result<Self, ConditionError> {
+        Self::parse(text)
+    }
+}
+
+impl fmt::Display for StateExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Node {
+    fn evaluate(&self, root: &Value) -> Result<bool, ConditionError> {
+        Ok(match self {
+            Node::Or(nodes) => {
+                for node in nodes {
+                    if node.evaluate(root)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            Node::And(nodes) => {
+                for node in nodes {
+                    if !node.evaluate(root)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Node::Not(node) => !node.evaluate(root)?,
+            Node::Truthy(path) => truthy(lookup(root, path)?),
+            Node::Compare(path, op, literal) => compare(lookup(root, path)?, *op, literal),
+        })
+    }
+
+    fn collect_state_keys(&self, keys: &mut Vec<String>) {
+        match self {
+            Node::Or(nodes) | Node::And(nodes) => nodes.iter().for_each(|n| n.collect_state_keys(keys)),
+            Node::Not(node) => node.collect_state_keys(keys),
+            Node::Truthy(path) | Node::Compare(path, ..) => {
+                if let [root, key, ..] = path.as_slice() {
+                    if root == "state" && !keys.contains(key) {
+                        keys.push(key.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn lookup<'a>(root: &'a Value, path: &[String]) -> Result<&'a Value, ConditionError> {
+    let mut current = root;
+    for segment in path {
+        current = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        }
+        .ok_or_else(|| ConditionError::Missing { path: path.join(".") })?;
+    }
+    Ok(current)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+fn compare(value: &Value, op: Op, literal: &Value) -> bool {
+    let ordering = match (value, literal) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (a, b) => (a == b).then_some(Ordering::Equal),
+    };
+    match op {
+        Op::Eq => ordering == Some(Ordering::Equal),
+        Op::Ne => ordering != Some(Ordering::Equal),
+        Op::Lt => ordering == Some(Ordering::Less),
+        Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Op::Gt => ordering == Some(Ordering::Greater),
+        Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A run of name characters and dots, e.g. `intent.kind` or `3.5`.
+    Word(String),
+    Quoted(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "`{}`", w),
+            Token::Quoted(s) => write!(f, "{:?}", s),
+            Token::Symbol(s) => write!(f, "`{}`", s),
+        }
+    }
+}
+
+/// Symbols, longest first so `<=` is not read as `<`.
+const SYMBOLS: [&str; 13] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", "[", "]"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' || c == '"' {
+            let end = rest[1..].find(c).ok_or("unterminated string")?;
+            tokens.push(Token::Quoted(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if is_word_char(c) {
+            let end = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            return Err(format!("unexpected character {:?}", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.and()?];
+        while self.eat("||") {
+            nodes.push(self.and()?);
+        }
+        Ok(if nodes.len() == 1 { nodes.remove(0) } else { Node::Or(nodes) })
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![self.unary()?];
+        while self.eat("&&") {
+            nodes.push(self.unary()?);
+        }
+        Ok(if nodes.len() == 1 { nodes.remove(0) } else { Node::And(nodes) })
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let node = self.or()?;
+            if !self.eat(")") {
+                return Err("missing `)`".to_string());
+            }
+            return Ok(node);
+        }
+        let path = self.path()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("==")) => Op::Eq,
+            Some(Token::Symbol("!=")) => Op::Ne,
+            Some(Token::Symbol("<")) => Op::Lt,
+            Some(Token::Symbol("<=")) => Op::Le,
+            Some(Token::Symbol(">")) => Op::Gt,
+            Some(Token::Symbol(">=")) => Op::Ge,
+            _ => return Ok(Node::Truthy(path)),
+        };
+        self.pos += 1;
+        Ok(Node::Compare(path, op, self.literal()?))
+    }
+
+    fn path(&mut self) -> Result<Vec<String>, String> {
+        let mut segments = match self.next() {
+            Some(Token::Word(word)) if !word.starts_with('.') => split_path(&word)?,
+            Some(token) => return Err(format!("expected a variable, found {}", token)),
+            None => return Err("expected a variable".to_string()),
+        };
+        loop {
+            match self.peek() {
+                Some(Token::Symbol("[")) => {
+                    self.pos += 1;
+                    match (self.next(), self.next()) {
+                        (Some(Token::Quoted(key)), Some(Token::Symbol("]"))) => segments.push(key),
+                        _ => return Err("expected ['key']".to_string()),
+                    }
+                }
+                Some(Token::Word(word)) if word.starts_with('.') => {
+                    let word = word[1..].to_string();
+                    self.pos += 1;
+                    segments.extend(split_path(&word)?);
+                }
+                _ => return Ok(segments),
+            }
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::Quoted(s)) => Ok(Value::String(s)),
+            Some(Token::Word(word)) => match word.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                _ => serde_json::from_str::<serde_json::Number>(&word)
+                    .map(Value::Number)
+                    .map_err(|_| format!("expected a literal, found `{}`; quote strings", word)),
+            },
+            Some(token) => Err(format!("expected a literal, found {}", token)),
+            None => Err("expected a literal".to_string()),
+        }
+    }
+}
+
+fn split_path(word: &str) -> Result<Vec<String>, String> {
+    let segments: Vec<String> = word.split('.').map(str::to_string).collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(format!("malformed variable `{}`", word));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn root() -> Value {
+        json!({
+            "intent": { "kind": "deploy", "metadata": { "fast_mode": true, "replicas": 3, "hosts": ["a", "b"] } },
+            "state": { "compute/gpu-1": { "available": 2 }, "compute/gpu-2": null },
+        })
+    }
+
+    fn eval(text: &str) -> Result<bool, ConditionError> {
+        StateExpr::parse(text).unwrap().eval(&root())
+    }
+
+    #[test]
+    fn test_evaluates_conditions() {
+        assert_eq!(eval("intent.metadata.fast_mode"), Ok(true));
+        assert_eq!(eval("intent.metadata.replicas >= 3 && intent.kind == 'deploy'"), Ok(true));
+        assert_eq!(eval("intent.metadata.replicas > 3 || !intent.metadata.fast_mode"), Ok(false));
+        assert_eq!(eval("!(intent.kind != \"deploy\")"), Ok(true));
+        assert_eq!(eval("intent.metadata.hosts.1 == 'b'"), Ok(true));
+        assert_eq!(eval("state['compute/gpu-1'].available < 4"), Ok(true));
+        assert_eq!(eval("state['compute/gpu-2']"), Ok(false));
+        assert_eq!(eval("intent.metadata.replicas == '3'"), Ok(false));
+    }
+
+    #[test]
+    fn test_missing_variable_is_an_error() {
+        assert_eq!(
+            eval("intent.metadata.fast_mod"),
+            Err(ConditionError::Missing { path: "intent.metadata.fast_mod".to_string() })
+        );
+        // Short-circuiting skips the missing side
+        assert_eq!(eval("intent.metadata.fast_mode || intent.metadata.nope"), Ok(true));
+    }
+
+    #[test]
+    fn test_state_keys() {
+        let expr = StateExpr::parse("state['compute/gpu-1'] && state.flag && !state['compute/gpu-1'].busy").unwrap();
+        assert_eq!(expr.state_keys(), vec!["compute/gpu-1", "flag"]);
+    }
+
+    #[test]
+    fn test_rejects_malformed_conditions() {
+        for text in ["", "intent.kind ==", "(intent.kind", "intent.kind == deploy", "a..b", "state['x", "a b"] {
+            assert!(matches!(StateExpr::parse(text), Err(ConditionError::Invalid { .. })), "{}", text);
+        }
+    }
+}
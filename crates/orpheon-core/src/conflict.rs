@@ -0,0 +1,335 @@
+//! Static detection of mutually unsatisfiable intent constraints.
+//!
+//! Every check here is conservative: a conflict is only reported when no
+//! execution could satisfy both sides. Upper bounds never conflict with
+//! each other (the tighter one wins), so e.g. a deadline sooner than
+//! `budget.max_duration_ms` is tight, not contradictory. Two different
+//! limits on the same resource are the exception: the intent does not say
+//! which one it means, so they are reported rather than silently merged.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::intent::{Constraint, Intent};
+
+/// Two parts of an intent that cannot both be satisfied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConstraintConflict {
+    /// Where the first side of the conflict comes from (e.g. `constraints[0]`).
+    pub first: String,
+    /// Where the second side comes from.
+    pub second: String,
+    /// Why the two cannot both hold.
+    pub explanation: String,
+}
+
+impl ConstraintConflict {
+    fn new(first: impl Into<String>, second: impl Into<String>, explanation: impl Into<String>) -> Self {
+        Self {
+            first: first.into(),
+            second: second.into(),
+            explanation: explanation.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConstraintConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} vs {}: {}", self.first, self.second, self.explanation)
+    }
+}
+
+/// Find conflicts between an intent's hard constraints, budget and validity window.
+pub fn find_conflicts(intent: &Intent) -> Vec<ConstraintConflict> {
+    let mut conflicts = Vec::new();
+    check_window(intent, &mut conflicts);
+    check_deadlines(intent, &mut conflicts);
+    check_geo_fences(intent, &mut conflicts);
+    check_resource_limits(intent, &mut conflicts);
+    check_budget(intent, &mut conflicts);
+    conflicts
+}
+
+fn check_window(intent: &Intent, conflicts: &mut Vec<ConstraintConflict>) {
+    let window = &intent.validity_window;
+    if let (Some(start), Some(end)) = (window.not_before, window.not_after) {
+        if end < start {
+            conflicts.push(ConstraintConflict::new(
+                "validity_window.not_before",
+                "validity_window.not_after",
+                format!("the window closes at {} before it opens at {}", end, start),
+            ));
+            return;
+        }
+    }
+    if let Some(end) = window.not_after.filter(|_| !window.recurrence.is_empty()) {
+        if window.next_opening_after(intent.created_at).is_none() {
+            conflicts.push(ConstraintConflict::new(
+                "validity_window.recurrence",
+                "validity_window.not_after",
+                format!("none of the daily windows opens before the window closes at {}", end),
+            ));
+        }
+    }
+}
+
+fn check_deadlines(intent: &Intent, conflicts: &mut Vec<ConstraintConflict>) {
+    let (start, start_source): (DateTime<Utc>, &str) = match intent.validity_window.not_before {
+        Some(not_before) if not_before > intent.created_at => (not_before, "validity_window.not_before"),
+        _ => (intent.created_at, "created_at"),
+    };
+
+    for (idx, constraint) in intent.constraints.iter().enumerate() {
+        let Constraint::Deadline { by } = constraint else {
+            continue;
+        };
+        if *by <= start {
+            conflicts.push(ConstraintConflict::new(
+                format!("constraints[{}]", idx),
+                start_source,
+                format!("deadline {} is not after the earliest start {}", by, start),
+            ));
+        }
+        if let Some(end) = intent.validity_window.not_after.filter(|end| by > end) {
+            conflicts.push(ConstraintConflict::new(
+                format!("constraints[{}]", idx),
+                "validity_window.not_after",
+                format!("deadline {} is after the window closes at {}", by, end),
+            ));
+        }
+    }
+}
+
+fn check_geo_fences(intent: &Intent, conflicts: &mut Vec<ConstraintConflict>) {
+    let fences: Vec<(usize, HashSet<String>, bool)> = intent
+        .constraints
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, c)| match c {
+            Constraint::GeoFence { regions, allowed } => {
+                Some((idx, regions.iter().map(|r| r.to_ascii_lowercase()).collect(), *allowed))
+            }
+            _ => None,
+        })
+        .collect();
+
+    for (i, (a_idx, a_regions, a_allowed)) in fences.iter().enumerate() {
+        for (b_idx, b_regions, b_allowed) in &fences[i + 1..] {
+            let explanation = match (a_allowed, b_allowed) {
+                (true, true) if a_regions.is_disjoint(b_regions) => {
+                    "the allowed region sets have no region in common".to_string()
+                }
+                (true, false) | (false, true) if !a_regions.is_disjoint(b_regions) => {
+                    let mut both: Vec<&String> = a_regions.intersection(b_regions).collect();
+                    both.sort();
+                    let both: Vec<&str> = both.into_iter().map(String::as_str).collect();
+                    format!("{} is both allowed and denied", both.join(", "))
+                }
+                _ => continue,
+            };
+            conflicts.push(ConstraintConflict::new(
+                format!("constraints[{}]", a_idx),
+                format!("constraints[{}]", b_idx),
+                explanation,
+            ));
+        }
+    }
+}
+
+fn check_resource_limits(intent: &Intent, conflicts: &mut Vec<ConstraintConflict>) {
+    let limits: Vec<(usize, &String, f64)> = intent
+        .constraints
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, c)| match c {
+            Constraint::ResourceLimit { resource, limit } => Some((idx, resource, *limit)),
+            _ => None,
+        })
+        .collect();
+
+    for (idx, resource, limit) in &limits {
+        if *limit < 0.0 {
+            conflicts.push(ConstraintConflict::new(
+                format!("constraints[{}]", idx),
+                format!("{} usage", resource),
+                format!("{} cannot be kept below {} since usage is never negative", resource, limit),
+            ));
+        }
+    }
+
+    for (i, (a_idx, a_resource, a_limit)) in limits.iter().enumerate() {
+        for (b_idx, b_resource, b_limit) in &limits[i + 1..] {
+            if a_resource == b_resource && a_limit != b_limit {
+                conflicts.push(ConstraintConflict::new(
+                    format!("constraints[{}]", a_idx),
+                    format!("constraints[{}]", b_idx),
+                    format!("{} is limited to both {} and {}", a_resource, a_limit, b_limit),
+                ));
+            }
+        }
+    }
+}
+
+fn check_budget(intent: &Intent, conflicts: &mut Vec<ConstraintConflict>) {
+    if intent.budget.max_duration_ms == Some(0) {
+        conflicts.push(ConstraintConflict::new(
+            "budget.max_duration_ms",
+            "execution",
+            "no plan can finish within 0 ms",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intent::{DailyWindow, TimeWindow};
+    use chrono::Duration;
+
+    fn intent_with(constraints: Vec<Constraint>) -> Intent {
+        let mut builder = Intent::builder().kind("deploy");
+        for c in constraints {
+            builder = builder.constraint(c);
+        }
+        builder.build().unwrap()
+    }
+
+    fn geo(regions: &[&str], allowed: bool) -> Constraint {
+        Constraint::GeoFence {
+            regions: regions.iter().map(|r| r.to_string()).collect(),
+            allowed,
+        }
+    }
+
+    #[test]
+    fn test_deadline_before_window_opens() {
+        let mut intent = intent_with(vec![Constraint::Deadline { by: Utc::now() + Duration::minutes(5) }]);
+        intent.validity_window = TimeWindow {
+            not_before: Some(Utc::now() + Duration::hours(1)),
+            not_after: Some(Utc::now() + Duration::hours(2)),
+            recurrence: Vec::new(),
+        };
+
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].first, "constraints[0]");
+        assert_eq!(conflicts[0].second, "validity_window.not_before");
+    }
+
+    #[test]
+    fn test_deadline_already_passed() {
+        let intent = intent_with(vec![Constraint::Deadline { by: Utc::now() - Duration::seconds(1) }]);
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].second, "created_at");
+    }
+
+    #[test]
+    fn test_recurring_window_that_never_opens() {
+        let mut intent = intent_with(vec![]);
+        let now = Utc::now();
+        let (start, end) = ((now + Duration::hours(2)).time(), (now + Duration::hours(3)).time());
+        intent.validity_window = TimeWindow {
+            not_before: None,
+            not_after: Some(now + Duration::hours(1)),
+            recurrence: vec![DailyWindow { start, end, weekdays: vec![] }],
+        };
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].first, "validity_window.recurrence");
+
+        intent.validity_window.not_after = Some(now + Duration::hours(4));
+        assert!(find_conflicts(&intent).is_empty());
+    }
+
+    #[test]
+    fn test_inverted_window() {
+        let mut intent = intent_with(vec![]);
+        intent.validity_window = TimeWindow {
+            not_before: Some(Utc::now() + Duration::hours(2)),
+            not_after: Some(Utc::now() + Duration::hours(1)),
+            recurrence: Vec::new(),
+        };
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts[0].first, "validity_window.not_before");
+        assert_eq!(conflicts[0].second, "validity_window.not_after");
+    }
+
+    #[test]
+    fn test_disjoint_allowed_regions() {
+        let intent = intent_with(vec![geo(&["us-east"], true), geo(&["EU-WEST"], true)]);
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].explanation.contains("no region in common"));
+    }
+
+    #[test]
+    fn test_allowed_regions_all_denied() {
+        let intent = intent_with(vec![geo(&["eu-west", "eu-central"], false), geo(&["eu-west"], true)]);
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].to_string(), "constraints[0] vs constraints[1]: eu-west is both allowed and denied");
+    }
+
+    #[test]
+    fn test_negative_resource_limit() {
+        let intent = intent_with(vec![
+            Constraint::ResourceLimit { resource: "gpu".to_string(), limit: 4.0 },
+            Constraint::ResourceLimit { resource: "gpu".to_string(), limit: -1.0 },
+        ]);
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].first, "constraints[1]");
+        assert!(conflicts[0].explanation.contains("never negative"));
+        assert_eq!(conflicts[1].to_string(), "constraints[0] vs constraints[1]: gpu is limited to both 4 and -1");
+    }
+
+    #[test]
+    fn test_deadline_after_window_closes() {
+        let mut intent = intent_with(vec![Constraint::Deadline { by: Utc::now() + Duration::hours(3) }]);
+        intent.validity_window.not_after = Some(Utc::now() + Duration::hours(1));
+
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].second, "validity_window.not_after");
+    }
+
+    #[test]
+    fn test_region_both_allowed_and_denied() {
+        let intent = intent_with(vec![geo(&["us-east", "eu-west"], true), geo(&["EU-West", "ap-south"], false)]);
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].explanation, "eu-west is both allowed and denied");
+    }
+
+    #[test]
+    fn test_zero_duration_budget() {
+        let mut intent = intent_with(vec![]);
+        intent.budget.max_duration_ms = Some(0);
+
+        let conflicts = find_conflicts(&intent);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].first, "budget.max_duration_ms");
+    }
+
+    #[test]
+    fn test_tight_but_satisfiable_is_not_a_conflict() {
+        let mut intent = intent_with(vec![
+            Constraint::Deadline { by: Utc::now() + Duration::seconds(10) },
+            Constraint::Sla { metric: "latency".to_string(), threshold: 60_000, unit: "ms".to_string() },
+            Constraint::ResourceLimit { resource: "cost".to_string(), limit: 0.5 },
+            Constraint::ResourceLimit { resource: "cost".to_string(), limit: 0.5 },
+            Constraint::ResourceLimit { resource: "gpu".to_string(), limit: 2.0 },
+            geo(&["us-east", "eu-west"], true),
+            geo(&["eu-west", "us-west"], true),
+            geo(&["ap-south"], false),
+        ]);
+        intent.budget.max_cost = Some(1.0);
+        intent.budget.max_duration_ms = Some(60_000);
+
+        assert!(find_conflicts(&intent).is_empty());
+    }
+}
@@ -0,0 +1,542 @@
+//! Constraint evaluation.
+//!
+//! An [`Evaluator`] judges a [`Constraint`] against an [`EvaluationContext`]:
+//! the state variables, the cost and time accumulated so far, and where the
+//! work runs. The planner evaluates the state each candidate action leads
+//! to, and the engine the state after each wave of steps.
+//!
+//! ```text
+//! StateMatch      the expression holds over the variables (see StateExpr)
+//! ResourceLimit   cost, duration_ms, a consumed resource or a numeric variable is at most the limit
+//! Sla             latency (elapsed time) or a numeric variable is at most the threshold
+//! Deadline        started_at + elapsed is no later than the deadline
+//! Provider        the work runs on the named node
+//! GeoFence        the region is listed (allowed) or not listed (denied)
+//! Custom          the check registered under its name passes
+//! ```
+//!
+//! A constraint the context holds nothing to judge by, such as a geo-fence
+//! while the region is unknown, is [`ConstraintOutcome::Unknown`] rather
+//! than violated.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::condition::{ConditionError, StateExpr};
+use crate::error::OrpheonError;
+use crate::intent::{Budget, Constraint};
+use crate::types::ResourceType;
+
+/// Check for a `Custom` constraint: given its data, `Err` with the reason it
+/// is violated.
+pub type CustomCheck = Arc<dyn Fn(&Value, &EvaluationContext) -> Result<(), String> + Send + Sync>;
+
+/// Rules for the `Custom` constraints of one name, e.g. a deployment-specific
+/// quota.
+pub trait CustomConstraintValidator: Send + Sync {
+    /// Check a constraint's data when an intent is validated; `Err` with
+    /// what is wrong with it.
+    fn validate(&self, data: &Value) -> Result<(), String>;
+
+    /// Judge a constraint in `ctx`; `Err` with the reason it is violated.
+    fn check(&self, data: &Value, ctx: &EvaluationContext) -> Result<(), String>;
+}
+
+/// A [`CustomCheck`] as a validator that accepts any data.
+struct CheckOnly(CustomCheck);
+
+impl CustomConstraintValidator for CheckOnly {
+    fn validate(&self, _data: &Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn check(&self, data: &Value, ctx: &EvaluationContext) -> Result<(), String> {
+        (self.0)(data, ctx)
+    }
+}
+
+/// Validators for `Custom` constraints, by name.
+///
+/// Intents validated against a registry may only use the custom
+/// constraints it knows (see [`Intent::validate_against`](crate::Intent::validate_against)),
+/// and an [`Evaluator`] judges them with its registry's checks.
+#[derive(Clone, Default)]
+pub struct ConstraintRegistry {
+    validators: HashMap<String, Arc<dyn CustomConstraintValidator>>,
+}
+
+impl fmt::Debug for ConstraintRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ConstraintRegistry").field(&self.names()).finish()
+    }
+}
+
+impl ConstraintRegistry {
+    /// Registry without validators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the validator for `Custom` constraints named `name`,
+    /// replacing any earlier one.
+    pub fn register(&mut self, name: impl Into<String>, validator: impl CustomConstraintValidator + 'static) {
+        self.validators.insert(name.into(), Arc::new(validator));
+    }
+
+    /// Like [`register`](Self::register), for building a registry.
+    pub fn with(mut self, name: impl Into<String>, validator: impl CustomConstraintValidator + 'static) -> Self {
+        self.register(name, validator);
+        self
+    }
+
+    /// The validator registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn CustomConstraintValidator> {
+        self.validators.get(name).map(|v| v.as_ref())
+    }
+
+    /// Registered names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.validators.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// What constraints are evaluated against.
+#[derive(Debug, Clone)]
+pub struct EvaluationContext {
+    /// State variables, read by `StateMatch` and by limits on anything
+    /// other than cost and time.
+    pub variables: HashMap<String, Value>,
+
+    /// Cost accumulated so far.
+    pub cost: f64,
+
+    /// Time spent so far, in milliseconds.
+    pub elapsed_ms: u64,
+
+    /// Resources consumed so far, e.g. memory in bytes.
+    pub resources: HashMap<ResourceType, f64>,
+
+    /// When the work started; `elapsed_ms` after it is when it finishes.
+    pub started_at: DateTime<Utc>,
+
+    /// Region the work runs in, if known.
+    pub region: Option<String>,
+
+    /// Node the work runs on, if known.
+    pub node_id: Option<String>,
+}
+
+impl Default for EvaluationContext {
+    fn default() -> Self {
+        Self {
+            variables: HashMap::new(),
+            cost: 0.0,
+            elapsed_ms: 0,
+            resources: HashMap::new(),
+            started_at: Utc::now(),
+            region: None,
+            node_id: None,
+        }
+    }
+}
+
+impl EvaluationContext {
+    /// Empty context for work starting now.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the state variables.
+    pub fn with_variables(mut self, variables: HashMap<String, Value>) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Set the accumulated cost.
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Set the time spent so far.
+    pub fn with_elapsed_ms(mut self, elapsed_ms: u64) -> Self {
+        self.elapsed_ms = elapsed_ms;
+        self
+    }
+
+    /// Set the resources consumed so far.
+    pub fn with_resources(mut self, resources: HashMap<ResourceType, f64>) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Set when the work started.
+    pub fn with_started_at(mut self, started_at: DateTime<Utc>) -> Self {
+        self.started_at = started_at;
+        self
+    }
+
+    /// Set the region the work runs in.
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Set the node the work runs on.
+    pub fn with_node_id(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    /// When the work finishes, as far as is known.
+    pub fn finished_at(&self) -> DateTime<Utc> {
+        self.started_at + Duration::milliseconds(self.elapsed_ms.min(i64::MAX as u64) as i64)
+    }
+
+    /// Measured amount of a resource or metric: cost, duration, a consumed
+    /// resource or a numeric variable.
+    fn measure(&self, name: &str) -> Option<f64> {
+        match name.to_ascii_lowercase().as_str() {
+            "cost" | "total_cost" => Some(self.cost),
+            "duration_ms" | "total_duration_ms" => Some(self.elapsed_ms as f64),
+            _ => self
+                .resources
+                .get(&ResourceType::from_name(name))
+                .copied()
+                .or_else(|| self.variables.get(name).and_then(Value::as_f64)),
+        }
+    }
+}
+
+/// Verdict on one constraint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintOutcome {
+    Satisfied,
+    Violated,
+    /// The context holds nothing to judge the constraint by.
+    Unknown,
+}
+
+/// A constraint's verdict and the reason for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintResult {
+    pub constraint: Constraint,
+    pub outcome: ConstraintOutcome,
+    /// Human-readable explanation, e.g. `cost 7.50 exceeds 5.00`.
+    pub detail: String,
+}
+
+impl ConstraintResult {
+    fn new(constraint: &Constraint, outcome: ConstraintOutcome, detail: impl Into<String>) -> Self {
+        Self { constraint: constraint.clone(), outcome, detail: detail.into() }
+    }
+
+    fn judge(constraint: &Constraint, satisfied: bool, detail: impl Into<String>) -> Self {
+        let outcome = if satisfied { ConstraintOutcome::Satisfied } else { ConstraintOutcome::Violated };
+        Self::new(constraint, outcome, detail)
+    }
+
+    fn unknown(constraint: &Constraint, detail: impl Into<String>) -> Self {
+        Self::new(constraint, ConstraintOutcome::Unknown, detail)
+    }
+
+    /// Whether the constraint is known to be broken.
+    pub fn is_violated(&self) -> bool {
+        self.outcome == ConstraintOutcome::Violated
+    }
+
+    /// The violation as an error of `intent_id`.
+    pub fn into_error(self, intent_id: Uuid) -> OrpheonError {
+        OrpheonError::ConstraintViolation {
+            intent_id,
+            constraint: self.constraint.to_string(),
+            reason: self.detail,
+        }
+    }
+}
+
+/// Evaluates constraints, with checks for the `Custom` ones registered by
+/// name.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluator {
+    custom: ConstraintRegistry,
+}
+
+impl Evaluator {
+    /// Evaluator without custom checks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the check for `Custom` constraints named `name`, replacing
+    /// any earlier one. Their data is not validated.
+    pub fn with_custom(
+        mut self,
+        name: impl Into<String>,
+        check: impl Fn(&Value, &EvaluationContext) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.custom.register(name, CheckOnly(Arc::new(check)));
+        self
+    }
+
+    /// Judge `Custom` constraints with the validators in `registry`,
+    /// replacing any registered before.
+    pub fn with_registry(mut self, registry: ConstraintRegistry) -> Self {
+        self.custom = registry;
+        self
+    }
+
+    /// Validators the `Custom` constraints are judged with.
+    pub fn registry(&self) -> &ConstraintRegistry {
+        &self.custom
+    }
+
+    /// Judge one constraint.
+    pub fn evaluate(&self, constraint: &Constraint, ctx: &EvaluationContext) -> ConstraintResult {
+        match constraint {
+            Constraint::StateMatch { expression } => self.state_match(constraint, expression, ctx),
+            Constraint::ResourceLimit { resource, limit } => match ctx.measure(resource) {
+                Some(used) => ConstraintResult::judge(
+                    constraint,
+                    used <= *limit,
+                    format!("{} is {:.2}, limit {:.2}", resource, used, limit),
+                ),
+                None => ConstraintResult::unknown(constraint, format!("{} is not measured", resource)),
+            },
+            Constraint::Sla { metric, threshold, unit } => {
+                let measured = if metric.eq_ignore_ascii_case("latency") {
+                    match unit.as_str() {
+                        "ms" => Some(ctx.elapsed_ms as f64),
+                        "s" => Some(ctx.elapsed_ms as f64 / 1000.0),
+                        _ => None,
+                    }
+                } else {
+                    ctx.measure(metric)
+                };
+                match measured {
+                    Some(value) => ConstraintResult::judge(
+                        constraint,
+                        value <= *threshold as f64,
+                        format!("{} is {}{}, threshold {}{}", metric, value, unit, threshold, unit),
+                    ),
+                    None => ConstraintResult::unknown(constraint, format!("{} in {} is not measured", metric, unit)),
+                }
+            }
+            Constraint::Deadline { by } => {
+                let finished = ctx.finished_at();
+                ConstraintResult::judge(
+                    constraint,
+                    finished <= *by,
+                    format!("finishes at {} vs deadline {}", finished.to_rfc3339(), by.to_rfc3339()),
+                )
+            }
+            Constraint::Provider { node_id } => match &ctx.node_id {
+                Some(node) => ConstraintResult::judge(
+                    constraint,
+                    node == node_id,
+                    format!("runs on '{}', required '{}'", node, node_id),
+                ),
+                None => ConstraintResult::unknown(constraint, "node is not known"),
+            },
+            Constraint::GeoFence { regions, allowed } => match &ctx.region {
+                Some(region) => {
+                    let listed = regions.iter().any(|r| r.eq_ignore_ascii_case(region));
+                    ConstraintResult::judge(
+                        constraint,
+                        listed == *allowed,
+                        format!(
+                            "runs in '{}', {} regions {:?}",
+                            region,
+                            if *allowed { "allowed" } else { "denied" },
+                            regions
+                        ),
+                    )
+                }
+                None => ConstraintResult::unknown(constraint, "region is not known"),
+            },
+            Constraint::DependsOn { intent_id, .. } => {
+                ConstraintResult::unknown(constraint, format!("intent {} is waited for before this one starts", intent_id))
+            }
+            Constraint::Custom { name, data } => match self.custom.get(name) {
+                Some(validator) => match validator.check(data, ctx) {
+                    Ok(()) => ConstraintResult::judge(constraint, true, format!("{} passed", name)),
+                    Err(reason) => ConstraintResult::judge(constraint, false, reason),
+                },
+                None => ConstraintResult::unknown(constraint, format!("no check is registered for {}", name)),
+            },
+        }
+    }
+
+    /// The constraints among `constraints` that are violated, in order.
+    pub fn violations<'a>(
+        &self,
+        constraints: impl IntoIterator<Item = &'a Constraint>,
+        ctx: &EvaluationContext,
+    ) -> Vec<ConstraintResult> {
+        constraints
+            .into_iter()
+            .map(|c| self.evaluate(c, ctx))
+            .filter(ConstraintResult::is_violated)
+            .collect()
+    }
+
+    fn state_match(&self, constraint: &Constraint, expression: &str, ctx: &EvaluationContext) -> ConstraintResult {
+        let root = Value::Object(ctx.variables.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
+        match StateExpr::parse(expression).and_then(|expr| expr.eval(&root)) {
+            Ok(holds) => ConstraintResult::judge(
+                constraint,
+                holds,
+                format!("{} {}", expression, if holds { "holds" } else { "does not hold" }),
+            ),
+            Err(ConditionError::Missing { path }) => ConstraintResult::unknown(constraint, format!("{} is not set", path)),
+            Err(e) => ConstraintResult::unknown(constraint, e.to_string()),
+        }
+    }
+}
+
+/// A budget's cost, duration and resource caps, as resource limits.
+pub fn budget_constraints(budget: &Budget) -> Vec<Constraint> {
+    let cost = budget.cost_limit().map(|limit| Constraint::ResourceLimit { resource: "total_cost".to_string(), limit });
+    let duration = budget
+        .max_duration_ms
+        .map(|ms| Constraint::ResourceLimit { resource: "total_duration_ms".to_string(), limit: ms as f64 });
+    let resources = budget
+        .resource_limits
+        .iter()
+        .map(|(resource, limit)| Constraint::ResourceLimit { resource: resource.name(), limit: *limit });
+    cost.into_iter().chain(duration).chain(resources).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outcome(constraint: Constraint, ctx: &EvaluationContext) -> ConstraintOutcome {
+        Evaluator::new().evaluate(&constraint, ctx).outcome
+    }
+
+    #[test]
+    fn test_state_match_expressions() {
+        let variables = HashMap::from([
+            ("region".to_string(), json!("us-east")),
+            ("replicas".to_string(), json!(3)),
+        ]);
+        let ctx = EvaluationContext::new().with_variables(variables);
+        let state = |expression: &str| outcome(Constraint::StateMatch { expression: expression.to_string() }, &ctx);
+
+        assert_eq!(state("region == 'us-east'"), ConstraintOutcome::Satisfied);
+        assert_eq!(state("region != 'us-east'"), ConstraintOutcome::Violated);
+        assert_eq!(state("replicas > 2"), ConstraintOutcome::Satisfied);
+        assert_eq!(state("replicas < 3"), ConstraintOutcome::Violated);
+        assert_eq!(state("gpu_type == 'H100'"), ConstraintOutcome::Unknown);
+        assert_eq!(state("region ==="), ConstraintOutcome::Unknown);
+    }
+
+    #[test]
+    fn test_limits_and_deadlines() {
+        let started_at = Utc::now();
+        let ctx = EvaluationContext::new()
+            .with_started_at(started_at)
+            .with_cost(7.5)
+            .with_elapsed_ms(1_500)
+            .with_variables(HashMap::from([("gpu_hours".to_string(), json!(4.0))]));
+        let limit = |resource: &str, limit: f64| outcome(Constraint::ResourceLimit { resource: resource.to_string(), limit }, &ctx);
+        let sla = |metric: &str, threshold: u64, unit: &str| {
+            outcome(Constraint::Sla { metric: metric.to_string(), threshold, unit: unit.to_string() }, &ctx)
+        };
+
+        assert_eq!(limit("total_cost", 7.5), ConstraintOutcome::Satisfied);
+        assert_eq!(limit("cost", 5.0), ConstraintOutcome::Violated);
+        assert_eq!(limit("duration_ms", 1_000.0), ConstraintOutcome::Violated);
+        assert_eq!(limit("gpu_hours", 8.0), ConstraintOutcome::Satisfied);
+        assert_eq!(limit("bandwidth", 8.0), ConstraintOutcome::Unknown);
+        assert_eq!(sla("latency", 2_000, "ms"), ConstraintOutcome::Satisfied);
+        assert_eq!(sla("latency", 1, "s"), ConstraintOutcome::Violated);
+        assert_eq!(sla("latency", 1, "h"), ConstraintOutcome::Unknown);
+
+        let due = |ms: i64| outcome(Constraint::Deadline { by: started_at + Duration::milliseconds(ms) }, &ctx);
+        assert_eq!(due(2_000), ConstraintOutcome::Satisfied);
+        assert_eq!(due(1_000), ConstraintOutcome::Violated);
+    }
+
+    #[test]
+    fn test_resource_limits_measure_consumption() {
+        let ctx = EvaluationContext::new()
+            .with_resources(HashMap::from([(ResourceType::Memory, 6e9), (ResourceType::Custom("gpu".into()), 2.0)]))
+            .with_variables(HashMap::from([("memory".to_string(), json!(1.0))]));
+        let limit = |resource: &str, limit: f64| outcome(Constraint::ResourceLimit { resource: resource.to_string(), limit }, &ctx);
+
+        // Consumption wins over a variable of the same name
+        assert_eq!(limit("memory", 4e9), ConstraintOutcome::Violated);
+        assert_eq!(limit("Memory", 8e9), ConstraintOutcome::Satisfied);
+        assert_eq!(limit("gpu", 1.0), ConstraintOutcome::Violated);
+        assert_eq!(limit("storage", 1.0), ConstraintOutcome::Unknown);
+
+        let budget = Budget::usd(5.0).with_resource(ResourceType::Memory, 4e9).with_resource(ResourceType::Memory, 8e9);
+        assert_eq!(budget.resource_limit(&ResourceType::Memory), Some(8e9));
+        let constraints = budget_constraints(&budget);
+        assert_eq!(constraints[1], Constraint::ResourceLimit { resource: "memory".to_string(), limit: 8e9 });
+        assert!(Evaluator::new().violations(&constraints, &ctx).is_empty());
+    }
+
+    #[test]
+    fn test_placement_needs_to_be_known() {
+        let fence = Constraint::GeoFence { regions: vec!["EU-West".to_string()], allowed: true };
+        let provider = Constraint::Provider { node_id: "node-a".to_string() };
+        let unknown = EvaluationContext::new();
+        assert_eq!(outcome(fence.clone(), &unknown), ConstraintOutcome::Unknown);
+        assert_eq!(outcome(provider.clone(), &unknown), ConstraintOutcome::Unknown);
+
+        let ctx = EvaluationContext::new().with_region("eu-west").with_node_id("node-b");
+        assert_eq!(outcome(fence, &ctx), ConstraintOutcome::Satisfied);
+        let denied = Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: false };
+        assert_eq!(outcome(denied, &ctx), ConstraintOutcome::Violated);
+        assert_eq!(outcome(provider, &ctx), ConstraintOutcome::Violated);
+    }
+
+    #[test]
+    fn test_custom_checks_are_delegated() {
+        let evaluator = Evaluator::new().with_custom("max_replicas", |data, ctx| {
+            let max = data["max"].as_f64().unwrap_or_default();
+            match ctx.variables.get("replicas").and_then(Value::as_f64) {
+                Some(replicas) if replicas > max => Err(format!("{} replicas, at most {}", replicas, max)),
+                _ => Ok(()),
+            }
+        });
+        let ctx = EvaluationContext::new().with_variables(HashMap::from([("replicas".to_string(), json!(5))]));
+        let custom = |name: &str, max: u32| Constraint::Custom { name: name.to_string(), data: json!({ "max": max }) };
+
+        assert_eq!(evaluator.evaluate(&custom("max_replicas", 8), &ctx).outcome, ConstraintOutcome::Satisfied);
+        let violated = evaluator.evaluate(&custom("max_replicas", 4), &ctx);
+        assert_eq!(violated.detail, "5 replicas, at most 4");
+        assert_eq!(evaluator.evaluate(&custom("unregistered", 4), &ctx).outcome, ConstraintOutcome::Unknown);
+
+        let intent_id = Uuid::new_v4();
+        let err = violated.into_error(intent_id);
+        assert_eq!(err.intent_id(), Some(intent_id));
+        assert_eq!(err.to_string(), "Constraint violated: custom constraint max_replicas (5 replicas, at most 4)");
+    }
+
+    #[test]
+    fn test_budget_caps_are_limits() {
+        let budget = Budget::usd(5.0).with_duration(1_000);
+        let constraints = budget_constraints(&budget);
+        assert_eq!(constraints.len(), 2);
+
+        let ctx = EvaluationContext::new().with_cost(6.0).with_elapsed_ms(500);
+        let violations = Evaluator::new().violations(&constraints, &ctx);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].constraint.to_string(), "total_cost <= 5");
+        assert!(budget_constraints(&Budget::default()).is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+//! Values plan steps can reference from their parameters.
+//!
+//! String parameters may contain `${path}` placeholders, resolved against an
+//! [`ExecutionContext`] just before the step runs:
+//!
+//! - `${intent.id}`, `${intent.kind}` and `${intent.metadata.*}` from the intent
+//! - `${budget.*}`, e.g. `${budget.max_cost}`
+//! - `${steps.<name>.*}` from the output data of an earlier step
+//!
+//! A parameter that is exactly one placeholder takes the bound value with its
+//! JSON type; placeholders inside longer strings are replaced by their text.
+//! Paths are dot-separated, with numeric segments indexing into arrays. A
+//! binding to a missing or `null` value is an error.
+//!
+//! The same values, plus state-store keys, are what `skip_if` conditions
+//! read; see [`crate::condition`].
+
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::condition::{ConditionError, StateExpr};
+use crate::intent::Intent;
+use crate::plan::Plan;
+
+/// Errors from resolving `${...}` bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BindingError {
+    /// Nothing is bound at the path.
+    #[error("unresolved binding ${{{path}}}")]
+    Missing { path: String },
+
+    /// A `${` without a closing `}`.
+    #[error("unterminated binding in {text:?}")]
+    Unterminated { text: String },
+
+    /// A binding error in a particular step's parameters.
+    #[error("step {step}: {source}")]
+    InStep {
+        step: String,
+        #[source]
+        source: Box<BindingError>,
+    },
+}
+
+impl BindingError {
+    fn in_step(self, step: &str) -> Self {
+        BindingError::InStep { step: step.to_string(), source: Box::new(self) }
+    }
+}
+
+/// Parameters with their bindings substituted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolved {
+    /// The substituted parameters.
+    pub value: Value,
+
+    /// Each binding used and the value it resolved to.
+    pub bindings: BTreeMap<String, Value>,
+}
+
+/// Bindings available to the steps of one execution.
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    root: Value,
+}
+
+impl ExecutionContext {
+    /// Context seeded from an intent.
+    pub fn for_intent(intent: &Intent) -> Self {
+        let root = serde_json::json!({
+            "intent": {
+                "id": intent.id,
+                "kind": intent.kind,
+                "metadata": intent.metadata,
+            },
+            "budget": intent.budget,
+            "steps": {},
+        });
+        Self { root }
+    }
+
+    /// Make a completed step's output available as `steps.<name>`.
+    pub fn record_step_output(&mut self, step_name: &str, data: Value) {
+        if let Some(steps) = self.root.get_mut("steps").and_then(Value::as_object_mut) {
+            steps.insert(step_name.to_string(), data);
+        }
+    }
+
+    /// Value bound at a dot-separated path; `None` if it is missing or null.
+    pub fn resolve(&self, path: &str) -> Option<&Value> {
+        let mut current = &self.root;
+        for segment in path.split('.') {
+            current = match current {
+                Value::Object(map) => map.get(segment)?,
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        (!current.is_null()).then_some(current)
+    }
+
+    /// Substitute every binding in `value`.
+    pub fn substitute(&self, value: &Value) -> Result<Resolved, BindingError> {
+        let mut bindings = BTreeMap::new();
+        let value = self.substitute_value(value, &mut bindings)?;
+        Ok(Resolved { value, bindings })
+    }
+
+    /// Check, before anything runs, that every binding in a plan can resolve.
+    ///
+    /// `steps.<name>` bindings must name an earlier step; their outputs are
+    /// only known once the step has run.
+    pub fn check_plan(&self, plan: &Plan) -> Result<(), BindingError> {
+        for (idx, step) in plan.steps.iter().enumerate() {
+            for path in references(&step.parameters).map_err(|e| e.in_step(&step.name))? {
+                let resolvable = match path.strip_prefix("steps.") {
+                    Some(rest) => {
+                        let name = rest.split('.').next().unwrap_or_default();
+                        plan.steps[..idx].iter().any(|s| s.name == name)
+                    }
+                    None => self.resolve(&path).is_some(),
+                };
+                if !resolvable {
+                    return Err(BindingError::Missing { path }.in_step(&step.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Substitute the bindings in a text template, always producing text.
+    pub fn render(&self, template: &str) -> Result<String, BindingError> {
+        Ok(match self.substitute_str(template, &mut BTreeMap::new())? {
+            Value::String(text) => text,
+            other => other.to_string(),
+        })
+    }
+
+    /// Evaluate a condition, with `state` bound to a snapshot of state-store
+    /// values keyed by key.
+    pub fn evaluate(&self, condition: &StateExpr, state: Map<String, Value>) -> Result<bool, ConditionError> {
+        let mut root = self.root.clone();
+        if let Some(map) = root.as_object_mut() {
+            map.insert("state".to_string(), Value::Object(state));
+        }
+        condition.eval(&root)
+    }
+
+    fn substitute_value(&self, value: &Value, bindings: &mut BTreeMap<String, Value>) -> Result<Value, BindingError> {
+        Ok(match value {
+            Value::String(text) => self.substitute_str(text, bindings)?,
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.substitute_value(item, bindings))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| Ok((k.clone(), self.substitute_value(v, bindings)?)))
+                    .collect::<Result<Map<_, _>, BindingError>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    fn substitute_str(&self, text: &str, bindings: &mut BTreeMap<String, Value>) -> Result<Value, BindingError> {
+        let pieces = parse(text)?;
+        let mut lookup = |path: &str| -> Result<Value, BindingError> {
+            let value = self
+                .resolve(path)
+                .cloned()
+                .ok_or_else(|| BindingError::Missing { path: path.to_string() })?;
+            bindings.insert(path.to_string(), value.clone());
+            Ok(value)
+        };
+
+        // A lone placeholder keeps the bound value's type
+        if let [Piece::Binding(path)] = pieces.as_slice() {
+            return lookup(path);
+        }
+        let mut out = String::new();
+        for piece in pieces {
+            match piece {
+                Piece::Text(t) => out.push_str(t),
+                Piece::Binding(path) => match lookup(path)? {
+                    Value::String(s) => out.push_str(&s),
+                    other => out.push_str(&other.to_string()),
+                },
+            }
+        }
+        Ok(Value::String(out))
+    }
+}
+
+/// Paths of every binding in `value`, in order of appearance.
+pub fn references(value: &Value) -> Result<Vec<String>, BindingError> {
+    let mut paths = Vec::new();
+    collect_references(value, &mut paths)?;
+    Ok(paths)
+}
+
+fn collect_references(value: &Value, paths: &mut Vec<String>) -> Result<(), BindingError> {
+    match value {
+        Value::String(text) => {
+            for piece in parse(text)? {
+                if let Piece::Binding(path) = piece {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_references(item, paths)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values() {
+                collect_references(item, paths)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Binding(&'a str),
+}
+
+/// Split a string into literal text and `${...}` bindings.
+fn parse(text: &str) -> Result<Vec<Piece<'_>>, BindingError> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if start > 0 {
+            pieces.push(Piece::Text(&rest[..start]));
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| BindingError::Unterminated { text: text.to_string() })?;
+        pieces.push(Piece::Binding(after[..end].trim()));
+        rest = &after[end + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest));
+    }
+    Ok(pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::{PlanningStrategy, Step};
+    use serde_json::json;
+
+    fn intent() -> Intent {
+        Intent::builder()
+            .kind("deploy")
+            .metadata(json!({ "region": "eu-west", "replicas": 3 }))
+            .budget(crate::Budget { max_cost: Some(25.0), ..Default::default() })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_substitutes_intent_bindings() {
+        let intent = intent();
+        let context = ExecutionContext::for_intent(&intent);
+        let params = json!({
+            "region": "${intent.metadata.region}",
+            "replicas": "${intent.metadata.replicas}",
+            "label": "${intent.kind}-${intent.metadata.region}",
+            "cap": "${budget.max_cost}",
+        });
+
+        let resolved = context.substitute(&params).unwrap();
+        assert_eq!(resolved.value, json!({
+            "region": "eu-west",
+            "replicas": 3,
+            "label": "deploy-eu-west",
+            "cap": 25.0,
+        }));
+        assert_eq!(resolved.bindings["intent.metadata.region"], json!("eu-west"));
+        assert_eq!(resolved.bindings.len(), 4);
+        assert_eq!(context.resolve("intent.id"), Some(&json!(intent.id)));
+    }
+
+    #[test]
+    fn test_step_outputs_are_bindable() {
+        let mut context = ExecutionContext::for_intent(&intent());
+        context.record_step_output("provision", json!({ "hosts": ["a", "b"] }));
+
+        let resolved = context.substitute(&json!("${steps.provision.hosts.1}")).unwrap();
+        assert_eq!(resolved.value, json!("b"));
+        assert_eq!(
+            context.substitute(&json!("${steps.deploy.url}")),
+            Err(BindingError::Missing { path: "steps.deploy.url".to_string() })
+        );
+        assert!(matches!(context.substitute(&json!("${intent.kind")), Err(BindingError::Unterminated { .. })));
+    }
+
+    #[test]
+    fn test_check_plan_reports_missing_path() {
+        let context = ExecutionContext::for_intent(&intent());
+        let mut plan = Plan::new(uuid::Uuid::new_v4(), PlanningStrategy::Heuristic);
+        plan.steps.push(Step::new("provision", "provision_compute").with_parameters(json!({ "zone": "${intent.metadata.region}" })));
+        plan.steps.push(Step::new("deploy", "deploy_workload").with_parameters(json!({ "host": "${steps.provision.host}" })));
+        assert_eq!(context.check_plan(&plan), Ok(()));
+
+        plan.steps.push(Step::new("verify", "verify_health").with_parameters(json!({ "cluster": "${intent.metadata.cluster}" })));
+        let err = context.check_plan(&plan).unwrap_err();
+        assert_eq!(err.to_string(), "step verify: unresolved binding ${intent.metadata.cluster}");
+
+        // Later steps' outputs are not available yet
+        plan.steps.swap(0, 1);
+        assert!(context.check_plan(&plan).is_err());
+    }
+}
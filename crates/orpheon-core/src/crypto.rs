@@ -0,0 +1,288 @@
+//! Signing helpers shared by nodes and clients.
+//!
+//! Keys and signatures are exchanged as hex-encoded bytes. Nodes sign with
+//! ed25519, behind the default `ed25519` feature; intents may also be
+//! signed with secp256k1 when the `secp256k1` feature is enabled, see
+//! [`SignatureScheme`].
+
+#[cfg(feature = "ed25519")]
+use chrono::Utc;
+#[cfg(feature = "ed25519")]
+use ed25519_dalek::{Signer, Verifier, VerifyingKey};
+
+#[cfg(feature = "ed25519")]
+pub use ed25519_dalek::SigningKey;
+
+#[cfg(feature = "ed25519")]
+use crate::artifact::ExecutionArtifact;
+use crate::error::{OrpheonError, Result};
+#[cfg(feature = "ed25519")]
+use crate::intent::{Intent, Signature};
+
+/// Name of the ed25519 signature algorithm.
+pub const ED25519: &str = "ed25519";
+
+/// Name of the secp256k1 signature algorithm: ECDSA over the SHA-256 of
+/// the message.
+pub const SECP256K1: &str = "secp256k1";
+
+/// A signature algorithm that [`Signature::algorithm`] may name.
+pub trait SignatureScheme: Send + Sync {
+    /// The algorithm's name, as in [`Signature::algorithm`].
+    fn name(&self) -> &'static str;
+
+    /// Verify a hex-encoded signature over `message` by a hex-encoded
+    /// public key.
+    ///
+    /// Returns `Ok(false)` for a well-formed signature that does not match;
+    /// fails with [`OrpheonError::CryptoError`] if the key or signature is
+    /// malformed.
+    fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> Result<bool>;
+}
+
+/// ed25519 with 32-byte public keys and 64-byte signatures.
+#[cfg(feature = "ed25519")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ed25519;
+
+#[cfg(feature = "ed25519")]
+impl SignatureScheme for Ed25519 {
+    fn name(&self) -> &'static str {
+        ED25519
+    }
+
+    fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> Result<bool> {
+        let key_bytes: [u8; 32] = hex_decode(public_key)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| OrpheonError::CryptoError("public key is not 32 hex-encoded bytes".to_string()))?;
+        let key = VerifyingKey::from_bytes(&key_bytes)
+            .map_err(|e| OrpheonError::CryptoError(format!("invalid public key: {}", e)))?;
+
+        let sig_bytes: [u8; 64] = hex_decode(signature)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| OrpheonError::CryptoError("signature is not 64 hex-encoded bytes".to_string()))?;
+
+        Ok(key.verify(message, &ed25519_dalek::Signature::from_bytes(&sig_bytes)).is_ok())
+    }
+}
+
+/// ECDSA over secp256k1 with SEC1 public keys, compressed or not, and
+/// 64-byte `r || s` signatures in low-S form.
+#[cfg(feature = "secp256k1")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Secp256k1;
+
+#[cfg(feature = "secp256k1")]
+impl SignatureScheme for Secp256k1 {
+    fn name(&self) -> &'static str {
+        SECP256K1
+    }
+
+    fn verify(&self, public_key: &str, message: &[u8], signature: &str) -> Result<bool> {
+        use k256::ecdsa::signature::Verifier as _;
+
+        let key_bytes = hex_decode(public_key)
+            .ok_or_else(|| OrpheonError::CryptoError("public key is not hex-encoded".to_string()))?;
+        let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key_bytes)
+            .map_err(|e| OrpheonError::CryptoError(format!("invalid public key: {}", e)))?;
+
+        let sig_bytes: [u8; 64] = hex_decode(signature)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| OrpheonError::CryptoError("signature is not 64 hex-encoded bytes".to_string()))?;
+        let signature = k256::ecdsa::Signature::from_slice(&sig_bytes)
+            .map_err(|e| OrpheonError::CryptoError(format!("invalid signature: {}", e)))?;
+
+        Ok(key.verify(message, &signature).is_ok())
+    }
+}
+
+/// The scheme for a [`Signature::algorithm`], matched case-insensitively.
+///
+/// Fails with [`OrpheonError::CryptoError`] for algorithms this build does
+/// not support.
+pub fn scheme(algorithm: &str) -> Result<&'static dyn SignatureScheme> {
+    #[cfg(feature = "ed25519")]
+    if algorithm.eq_ignore_ascii_case(ED25519) {
+        return Ok(&Ed25519);
+    }
+    #[cfg(feature = "secp256k1")]
+    if algorithm.eq_ignore_ascii_case(SECP256K1) {
+        return Ok(&Secp256k1);
+    }
+    Err(OrpheonError::CryptoError(format!("unsupported algorithm '{}'", algorithm)))
+}
+
+/// Hex-encode bytes (lowercase).
+pub fn hex_encode(bytes: impl AsRef<[u8]>) -> String {
+    bytes.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode a hex string, returning `None` if it is malformed.
+pub fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Verify a hex-encoded ed25519 signature over `message`.
+#[cfg(feature = "ed25519")]
+pub fn verify_ed25519(public_key: &str, message: &[u8], signature: &str) -> Result<()> {
+    if Ed25519.verify(public_key, message, signature)? {
+        Ok(())
+    } else {
+        Err(OrpheonError::CryptoError("signature does not match".to_string()))
+    }
+}
+
+/// Sign a content hash with `key`, returning the signature to attach to
+/// whatever was hashed.
+#[cfg(feature = "ed25519")]
+pub(crate) fn sign_hash_with(key: &SigningKey, hash: &str) -> Signature {
+    Signature {
+        algorithm: ED25519.to_string(),
+        public_key: hex_encode(key.verifying_key().as_bytes()),
+        signature: hex_encode(key.sign(hash.as_bytes()).to_bytes()),
+        signed_at: Utc::now(),
+    }
+}
+
+/// An ed25519 key a node signs with.
+#[cfg(feature = "ed25519")]
+#[derive(Clone)]
+pub struct NodeKey {
+    signing_key: SigningKey,
+}
+
+#[cfg(feature = "ed25519")]
+impl NodeKey {
+    /// Generate a fresh random key.
+    pub fn generate() -> Self {
+        Self::from_bytes(&rand::random())
+    }
+
+    /// Create a key from its 32-byte secret.
+    pub fn from_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    /// Hex-encoded public key.
+    pub fn public_key_hex(&self) -> String {
+        hex_encode(self.signing_key.verifying_key().as_bytes())
+    }
+
+    /// Sign a message, returning the hex-encoded signature.
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex_encode(self.signing_key.sign(message).to_bytes())
+    }
+
+    /// Sign a content hash, returning the signature to attach to whatever
+    /// was hashed.
+    pub fn sign_hash(&self, hash: &str) -> Signature {
+        sign_hash_with(&self.signing_key, hash)
+    }
+
+    /// Sign an intent's content hash and attach the signature.
+    pub fn sign_intent(&self, intent: &mut Intent) {
+        intent.sign(&self.signing_key);
+    }
+
+    /// Sign an artifact's content hash and attach the signature.
+    pub fn sign_artifact(&self, artifact: &mut ExecutionArtifact) {
+        artifact.signature = None;
+        artifact.signature = Some(self.sign_hash(&artifact.content_hash()));
+    }
+}
+
+#[cfg(feature = "ed25519")]
+impl std::fmt::Debug for NodeKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeKey")
+            .field("public_key", &self.public_key_hex())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = vec![0x00, 0x0a, 0xff, 0x42];
+        assert_eq!(hex_encode(&bytes), "000aff42");
+        assert_eq!(hex_decode("000AFF42"), Some(bytes));
+        assert_eq!(hex_decode("abc"), None);
+        assert_eq!(hex_decode("zz"), None);
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_sign_and_verify() {
+        let key = NodeKey::from_bytes(&[7u8; 32]);
+        let signature = key.sign(b"hello");
+
+        assert!(verify_ed25519(&key.public_key_hex(), b"hello", &signature).is_ok());
+        assert!(verify_ed25519(&key.public_key_hex(), b"hullo", &signature).is_err());
+
+        let other = NodeKey::generate();
+        assert!(verify_ed25519(&other.public_key_hex(), b"hello", &signature).is_err());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_ed25519_vector() {
+        // RFC 8032, section 7.1, test 1
+        let public_key = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+        let signature = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                         5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+        let key = NodeKey::from_bytes(&hex_decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap().try_into().unwrap());
+        assert_eq!(key.public_key_hex(), public_key);
+        assert_eq!(key.sign(b""), signature);
+
+        let scheme = scheme("Ed25519").unwrap();
+        assert_eq!(scheme.name(), ED25519);
+        assert!(scheme.verify(public_key, b"", signature).unwrap());
+        assert!(!scheme.verify(public_key, b"x", signature).unwrap());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_secp256k1_vector() {
+        // Private key 1, so the public key is the generator; deterministic
+        // (RFC 6979) signature over SHA-256("Satoshi Nakamoto")
+        let public_key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let signature = "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8\
+                         2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5";
+
+        let scheme = scheme("secp256k1").unwrap();
+        assert_eq!(scheme.name(), SECP256K1);
+        assert!(scheme.verify(public_key, b"Satoshi Nakamoto", signature).unwrap());
+        assert!(!scheme.verify(public_key, b"Satoshi Nakamoto!", signature).unwrap());
+
+        // The same key uncompressed
+        let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+                            483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+        assert!(scheme.verify(uncompressed, b"Satoshi Nakamoto", signature).unwrap());
+        assert!(matches!(scheme.verify("02ab", b"Satoshi Nakamoto", signature), Err(OrpheonError::CryptoError(_))));
+    }
+
+    #[test]
+    fn test_unknown_scheme_is_unsupported() {
+        let err = scheme("rsa").map(|s| s.name()).unwrap_err();
+        assert!(matches!(&err, OrpheonError::CryptoError(m) if m == "unsupported algorithm 'rsa'"), "{}", err);
+    }
+
+    #[cfg(not(feature = "ed25519"))]
+    #[test]
+    fn test_ed25519_is_unsupported_without_its_feature() {
+        let err = scheme("ed25519").map(|s| s.name()).unwrap_err();
+        assert!(matches!(&err, OrpheonError::CryptoError(m) if m == "unsupported algorithm 'ed25519'"), "{}", err);
+    }
+}
@@ -0,0 +1,321 @@
+//! What changed between two versions of an intent.
+//!
+//! Only what the intent asks for is compared: its id, creation time,
+//! signature and parent are identity, not content. Constraints are compared
+//! as multisets, so reordering them is not a change; preferences are paired
+//! by objective; metadata is compared key by key down to scalars and
+//! arrays, each change named by its JSON pointer.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::intent::{Constraint, Intent, Preference};
+
+/// Changes from one intent to another; see [`Intent::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntentDiff {
+    /// Hard constraints only the new intent has.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints_added: Vec<Constraint>,
+
+    /// Hard constraints only the old intent has.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints_removed: Vec<Constraint>,
+
+    /// Soft constraints only the new intent has.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub soft_constraints_added: Vec<Constraint>,
+
+    /// Soft constraints only the old intent has.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub soft_constraints_removed: Vec<Constraint>,
+
+    /// Preferences added, removed or reweighted, by objective.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preferences: Vec<PreferenceChange>,
+
+    /// Changes to the kind, budget, validity window, priority and labels,
+    /// e.g. `budget.max_cost` or `labels.team`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+
+    /// Metadata values added, removed or changed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<MetadataChange>,
+}
+
+/// A preference of one objective, before and after; `None` where the
+/// intent has none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreferenceChange {
+    pub objective: String,
+    pub before: Option<Preference>,
+    pub after: Option<Preference>,
+}
+
+/// A field of the intent, before and after; null where it was unset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Path of the field, e.g. `validity_window.not_after`.
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// A metadata value, before and after; `None` where it is missing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataChange {
+    /// JSON pointer to the value, e.g. `/labels/env`.
+    pub pointer: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+impl IntentDiff {
+    /// Compare `before` with `after`.
+    pub fn between(before: &Intent, after: &Intent) -> Self {
+        let (constraints_removed, constraints_added) = multiset_difference(&before.constraints, &after.constraints);
+        let (soft_constraints_removed, soft_constraints_added) =
+            multiset_difference(&before.soft_constraints, &after.soft_constraints);
+        let mut metadata = Vec::new();
+        diff_values(String::new(), Some(&before.metadata), Some(&after.metadata), &mut metadata);
+        Self {
+            constraints_added,
+            constraints_removed,
+            soft_constraints_added,
+            soft_constraints_removed,
+            preferences: diff_preferences(&before.preferences, &after.preferences),
+            fields: diff_fields(before, after),
+            metadata,
+        }
+    }
+
+    /// Whether the two intents ask for the same thing.
+    pub fn is_empty(&self) -> bool {
+        self.constraints_added.is_empty()
+            && self.constraints_removed.is_empty()
+            && self.soft_constraints_added.is_empty()
+            && self.soft_constraints_removed.is_empty()
+            && self.preferences.is_empty()
+            && self.fields.is_empty()
+            && self.metadata.is_empty()
+    }
+}
+
+/// Items of `before` not matched in `after`, and of `after` not matched in
+/// `before`, each item matching at most once.
+fn multiset_difference<T: PartialEq + Clone>(before: &[T], after: &[T]) -> (Vec<T>, Vec<T>) {
+    let mut unmatched: Vec<Option<&T>> = after.iter().map(Some).collect();
+    let mut removed = Vec::new();
+    for item in before {
+        match unmatched.iter_mut().find(|slot| slot.is_some_and(|other| other == item)) {
+            Some(slot) => *slot = None,
+            None => removed.push(item.clone()),
+        }
+    }
+    let added = unmatched.into_iter().flatten().cloned().collect();
+    (removed, added)
+}
+
+fn diff_preferences(before: &[Preference], after: &[Preference]) -> Vec<PreferenceChange> {
+    let mut objectives: Vec<&str> = Vec::new();
+    for preference in before.iter().chain(after) {
+        if !objectives.contains(&preference.objective.as_str()) {
+            objectives.push(&preference.objective);
+        }
+    }
+    objectives
+        .into_iter()
+        .filter_map(|objective| {
+            let find = |list: &[Preference]| list.iter().find(|p| p.objective == objective).cloned();
+            let (before, after) = (find(before), find(after));
+            (before != after).then(|| PreferenceChange { objective: objective.to_string(), before, after })
+        })
+        .collect()
+}
+
+fn diff_fields(before: &Intent, after: &Intent) -> Vec<FieldChange> {
+    let pairs = [
+        ("kind", Value::from(before.kind.clone()), Value::from(after.kind.clone())),
+        ("budget.max_cost", json(&before.budget.cost_limit()), json(&after.budget.cost_limit())),
+        ("budget.currency", json(&before.budget.currency), json(&after.budget.currency)),
+        ("budget.max_duration_ms", json(&before.budget.max_duration_ms), json(&after.budget.max_duration_ms)),
+        ("budget.max_retries", json(&before.budget.max_retries), json(&after.budget.max_retries)),
+        (
+            "validity_window.not_before",
+            json(&before.validity_window.not_before),
+            json(&after.validity_window.not_before),
+        ),
+        (
+            "validity_window.not_after",
+            json(&before.validity_window.not_after),
+            json(&after.validity_window.not_after),
+        ),
+        (
+            "validity_window.recurrence",
+            json(&before.validity_window.recurrence),
+            json(&after.validity_window.recurrence),
+        ),
+        ("priority", json(&before.priority), json(&after.priority)),
+    ];
+    let mut changes: Vec<FieldChange> = pairs
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange { field: field.to_string(), before, after })
+        .collect();
+    let keys = before.labels.keys().chain(after.labels.keys().filter(|key| !before.labels.contains_key(*key)));
+    for key in keys {
+        let (old, new) = (json(&before.labels.get(key)), json(&after.labels.get(key)));
+        if old != new {
+            changes.push(FieldChange { field: format!("labels.{}", key), before: old, after: new });
+        }
+    }
+    changes
+}
+
+fn json(value: &impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn diff_values(pointer: String, before: Option<&Value>, after: Option<&Value>, changes: &mut Vec<MetadataChange>) {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key))) {
+                let child = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                diff_values(child, before.get(key), after.get(key), changes);
+            }
+        }
+        (before, after) if before != after => changes.push(MetadataChange {
+            pointer,
+            before: before.cloned(),
+            after: after.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// One change per item, separated by `; `, e.g.
+/// `+constraint total_cost <= 5; budget.max_cost: 10.0 -> 12.0`.
+impl fmt::Display for IntentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no changes");
+        }
+        let mut items = Vec::new();
+        items.extend(self.constraints_added.iter().map(|c| format!("+constraint {}", c)));
+        items.extend(self.constraints_removed.iter().map(|c| format!("-constraint {}", c)));
+        items.extend(self.soft_constraints_added.iter().map(|c| format!("+soft constraint {}", c)));
+        items.extend(self.soft_constraints_removed.iter().map(|c| format!("-soft constraint {}", c)));
+        items.extend(self.preferences.iter().map(|change| match (&change.before, &change.after) {
+            (Some(before), Some(after)) if before.direction == after.direction => {
+                format!("preference {}: weight {} -> {}", change.objective, before.weight, after.weight)
+            }
+            (_, Some(after)) => format!("+preference {} {:?} {}", change.objective, after.direction, after.weight),
+            (_, None) => format!("-preference {}", change.objective),
+        }));
+        items.extend(self.fields.iter().map(|change| format!("{}: {} -> {}", change.field, change.before, change.after)));
+        items.extend(self.metadata.iter().map(|change| {
+            let show = |value: &Option<Value>| value.as_ref().map_or_else(|| "(none)".to_string(), Value::to_string);
+            format!("metadata {}: {} -> {}", change.pointer, show(&change.before), show(&change.after))
+        }));
+        f.write_str(&items.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::json;
+
+    use super::*;
+    use crate::intent::{Budget, TimeWindow};
+    use crate::types::Priority;
+
+    #[test]
+    fn test_constraint_order_is_not_a_change() {
+        let window = TimeWindow::valid_for(Duration::hours(1));
+        let before = Intent::builder()
+            .kind("deploy")
+            .resource_limit("total_cost", 5.0)
+            .provider("node-a")
+            .resource_limit("total_cost", 5.0)
+            .validity_window(window.clone())
+            .build()
+            .unwrap();
+        let after = Intent::builder()
+            .kind("deploy")
+            .provider("node-a")
+            .resource_limit("total_cost", 5.0)
+            .resource_limit("total_cost", 5.0)
+            .validity_window(window)
+            .build()
+            .unwrap();
+
+        let diff = before.diff(&after);
+        assert!(diff.is_empty(), "{}", diff);
+        assert_eq!(diff.to_string(), "no changes");
+        assert_eq!(serde_json::to_value(&diff).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_structured_changes() {
+        let window = TimeWindow::valid_for(Duration::hours(1));
+        let before = Intent::builder()
+            .kind("deploy")
+            .resource_limit("total_cost", 5.0)
+            .resource_limit("total_cost", 5.0)
+            .minimize("cost", 0.5)
+            .minimize("latency", 0.5)
+            .budget(Budget::usd(10.0))
+            .validity_window(window.clone())
+            .metadata(json!({ "team": "ml", "labels": { "env": "prod", "tier": "web" } }))
+            .label("team", "ml")
+            .build()
+            .unwrap();
+        let after = Intent::builder()
+            .kind("deploy")
+            .resource_limit("total_cost", 5.0)
+            .geo_fence(vec!["eu-west".to_string()], true)
+            .minimize("cost", 0.8)
+            .maximize("reliability", 0.2)
+            .budget(Budget::usd(12.0).with_duration(60_000))
+            .validity_window(TimeWindow { not_after: window.not_after.map(|t| t + Duration::hours(1)), ..window })
+            .priority(Priority::High)
+            .metadata(json!({ "team": "infra", "labels": { "env": "prod" }, "ticket": 42 }))
+            .label("team", "infra")
+            .label("env", "prod")
+            .build()
+            .unwrap();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.constraints_removed, vec![Constraint::ResourceLimit { resource: "total_cost".to_string(), limit: 5.0 }]);
+        assert_eq!(diff.constraints_added.len(), 1);
+        let objectives: Vec<_> = diff.preferences.iter().map(|c| c.objective.as_str()).collect();
+        assert_eq!(objectives, ["cost", "latency", "reliability"]);
+        assert!(diff.preferences[1].after.is_none());
+        let fields: Vec<_> = diff.fields.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["budget.max_cost", "budget.max_duration_ms", "validity_window.not_after", "priority", "labels.team", "labels.env"]
+        );
+        assert_eq!(diff.fields[0].before, json!(10.0));
+        assert_eq!(diff.fields[3].after, json!("high"));
+        assert_eq!((&diff.fields[5].before, &diff.fields[5].after), (&Value::Null, &json!("prod")));
+        let pointers: Vec<_> = diff.metadata.iter().map(|c| c.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/labels/tier", "/team", "/ticket"]);
+        assert_eq!(diff.metadata[0].after, None);
+
+        let summary = diff.to_string();
+        assert!(summary.starts_with("+constraint region in ['eu-west']; -constraint total_cost <= 5; "), "{}", summary);
+        assert!(summary.contains("preference cost: weight 0.5 -> 0.8"), "{}", summary);
+        assert!(summary.contains("metadata /team: \"ml\" -> \"infra\""), "{}", summary);
+
+        let round_trip: IntentDiff = serde_json::from_value(serde_json::to_value(&diff).unwrap()).unwrap();
+        assert_eq!(round_trip, diff);
+        assert!(after.diff(&after.clone()).is_empty());
+    }
+}
@@ -3,6 +3,10 @@
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::conflict::ConstraintConflict;
+use crate::types::IntentStatus;
+use crate::validation::FieldError;
+
 /// Main error type for Orpheon operations.
 #[derive(Error, Debug, Clone)]
 pub enum OrpheonError {
@@ -10,6 +14,38 @@ pub enum OrpheonError {
     #[error("Intent validation failed: {message}")]
     IntentInvalid { intent_id: Option<Uuid>, message: String },
 
+    /// Preference weights do not sum to 1.0.
+    #[error(
+        "Preference weights must sum to 1.0, got {sum} ({}){}",
+        describe_weights(.weights),
+        normalize_hint(.normalizable)
+    )]
+    PreferenceWeightsInvalid {
+        intent_id: Option<Uuid>,
+        /// The computed sum of all weights.
+        sum: f32,
+        /// Each preference's objective and weight, in order.
+        weights: Vec<(String, f32)>,
+        /// Whether scaling the weights would make them valid.
+        normalizable: bool,
+    },
+
+    /// Budget, limit or weight fields with out-of-range values.
+    #[error("Intent has invalid fields: {}", describe_fields(.errors))]
+    FieldsInvalid {
+        intent_id: Option<Uuid>,
+        errors: Vec<FieldError>,
+        /// Conflicts found alongside the invalid fields.
+        conflicts: Vec<ConstraintConflict>,
+    },
+
+    /// Constraints that can never be satisfied together.
+    #[error("Intent has conflicting constraints: {}", describe_conflicts(.conflicts))]
+    ConstraintConflict {
+        intent_id: Option<Uuid>,
+        conflicts: Vec<ConstraintConflict>,
+    },
+
     /// Planning failed to find a viable path.
     #[error("Planning failed for intent {intent_id}: {message}")]
     PlanningFailed { intent_id: Uuid, message: String },
@@ -32,8 +68,12 @@ pub enum OrpheonError {
     Timeout { duration_ms: u64, message: String },
 
     /// Constraint violation detected.
-    #[error("Constraint violated: {constraint}")]
-    ConstraintViolation { intent_id: Uuid, constraint: String },
+    #[error("Constraint violated: {constraint} ({reason})")]
+    ConstraintViolation { intent_id: Uuid, constraint: String, reason: String },
+
+    /// The node lacks capabilities the intent requires.
+    #[error("Intent {intent_id} requires capabilities this node lacks: {}", .missing.join(", "))]
+    CapabilityMissing { intent_id: Uuid, missing: Vec<String> },
 
     /// Budget exceeded.
     #[error("Budget exceeded: spent {spent}, limit {limit}")]
@@ -43,6 +83,14 @@ pub enum OrpheonError {
         limit: f64,
     },
 
+    /// A cost, weight or estimate that is NaN or infinite.
+    #[error("{context} must be a finite number, got {value}")]
+    NonFinite {
+        /// What the value is, e.g. `cost of action provision_vm`.
+        context: String,
+        value: f64,
+    },
+
     /// State store error.
     #[error("State store error: {message}")]
     StateError { message: String },
@@ -59,6 +107,25 @@ pub enum OrpheonError {
     #[error("Resource not found: {resource_type} with id {id}")]
     NotFound { resource_type: String, id: String },
 
+    /// The intent exists but has not produced the resource yet.
+    #[error("{resource_type} for intent {intent_id} is not available yet; the intent is {}", .status.as_str())]
+    NotReady {
+        intent_id: Uuid,
+        resource_type: String,
+        /// The intent's status when asked.
+        status: IntentStatus,
+    },
+
+    /// The intent's status does not allow the requested action.
+    #[error("Cannot {action} intent {intent_id} while it is {}", .status.as_str())]
+    InvalidTransition {
+        intent_id: Uuid,
+        /// What was asked, e.g. `pause`.
+        action: String,
+        /// The intent's status when asked.
+        status: IntentStatus,
+    },
+
     /// Internal error (should not happen).
     #[error("Internal error: {0}")]
     Internal(String),
@@ -66,6 +133,10 @@ pub enum OrpheonError {
     /// Connection error.
     #[error("Connection error: {0}")]
     ConnectionError(String),
+
+    /// The peer speaks a negotiation protocol version this side cannot.
+    #[error("Unsupported negotiation protocol version {version}; supported versions are {min} to {max}")]
+    UnsupportedProtocol { version: u32, min: u32, max: u32 },
 }
 
 impl OrpheonError {
@@ -83,16 +154,55 @@ impl OrpheonError {
     pub fn intent_id(&self) -> Option<Uuid> {
         match self {
             OrpheonError::IntentInvalid { intent_id, .. } => *intent_id,
+            OrpheonError::PreferenceWeightsInvalid { intent_id, .. } => *intent_id,
+            OrpheonError::FieldsInvalid { intent_id, .. } => *intent_id,
+            OrpheonError::ConstraintConflict { intent_id, .. } => *intent_id,
             OrpheonError::PlanningFailed { intent_id, .. } => Some(*intent_id),
             OrpheonError::ExecutionFailed { intent_id, .. } => Some(*intent_id),
             OrpheonError::NegotiationRejected { intent_id, .. } => Some(*intent_id),
             OrpheonError::ConstraintViolation { intent_id, .. } => Some(*intent_id),
+            OrpheonError::CapabilityMissing { intent_id, .. } => Some(*intent_id),
             OrpheonError::BudgetExceeded { intent_id, .. } => Some(*intent_id),
+            OrpheonError::NotReady { intent_id, .. } => Some(*intent_id),
+            OrpheonError::InvalidTransition { intent_id, .. } => Some(*intent_id),
             _ => None,
         }
     }
 }
 
+fn describe_weights(weights: &[(String, f32)]) -> String {
+    weights
+        .iter()
+        .map(|(objective, weight)| format!("{}={}", objective, weight))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe_fields(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Join conflicts into one line, e.g. `a vs b: why; c vs d: why`.
+pub(crate) fn describe_conflicts(conflicts: &[ConstraintConflict]) -> String {
+    conflicts
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn normalize_hint(normalizable: &bool) -> &'static str {
+    if *normalizable {
+        "; enable weight normalization to scale them automatically"
+    } else {
+        ""
+    }
+}
+
 /// Convenience Result type for Orpheon operations.
 pub type Result<T> = std::result::Result<T, OrpheonError>;
 
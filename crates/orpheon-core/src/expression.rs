@@ -0,0 +1,208 @@
+//! Constraint expressions: constraints written as one-line text.
+//!
+//! ```text
+//! provider == 'node-a'                  Provider
+//! region in ['us-east', 'eu-west']      GeoFence (allowed)
+//! region not in ['cn-north']            GeoFence (denied)
+//! deadline < 2026-01-01T00:00:00Z       Deadline
+//! latency < 200ms                       Sla (a whole number with a unit)
+//! total_cost < 5.00                     ResourceLimit (a bare number)
+//! gpu_type == 'H100'                    StateMatch (any other comparison)
+//! ```
+//!
+//! Constraints display in the same syntax, except `DependsOn` and `Custom`
+//! ones, which have none.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::error::{OrpheonError, Result};
+use crate::intent::Constraint;
+
+/// Comparison operators, longest first so `<=` is not read as `<`.
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+/// Parse a constraint expression.
+pub fn parse_constraint(text: &str) -> Result<Constraint> {
+    let text = text.trim();
+    let invalid = |reason: &str| OrpheonError::IntentInvalid {
+        intent_id: None,
+        message: format!("invalid constraint expression {:?}: {}", text, reason),
+    };
+
+    if let Some((subject, list)) = split_keyword(text, " not in ") {
+        return Ok(Constraint::GeoFence { regions: parse_list(subject, list).map_err(|e| invalid(&e))?, allowed: false });
+    }
+    if let Some((subject, list)) = split_keyword(text, " in ") {
+        return Ok(Constraint::GeoFence { regions: parse_list(subject, list).map_err(|e| invalid(&e))?, allowed: true });
+    }
+
+    let (subject, op, value) = OPERATORS
+        .iter()
+        .find_map(|op| text.split_once(op).map(|(l, r)| (l.trim(), *op, r.trim())))
+        .ok_or_else(|| invalid("expected a comparison such as `latency < 200ms`"))?;
+    if !is_identifier(subject) {
+        return Err(invalid("left-hand side must be a name"));
+    }
+    if value.is_empty() {
+        return Err(invalid("missing right-hand side"));
+    }
+
+    match (subject, op) {
+        ("provider", "==") => {
+            let node_id = unquote(value).ok_or_else(|| invalid("provider must be a quoted node ID"))?;
+            return Ok(Constraint::Provider { node_id: node_id.to_string() });
+        }
+        ("deadline", "<" | "<=") => {
+            let by = DateTime::parse_from_rfc3339(value)
+                .map_err(|e| invalid(&format!("deadline must be an RFC 3339 time: {}", e)))?;
+            return Ok(Constraint::Deadline { by: by.with_timezone(&Utc) });
+        }
+        _ => {}
+    }
+
+    if unquote(value).is_some() || !matches!(op, "<" | "<=") {
+        return Ok(Constraint::StateMatch { expression: text.to_string() });
+    }
+
+    // An upper bound: a bare number is a resource limit, one with a unit an SLA
+    let unit_start = value.find(|c: char| c.is_ascii_alphabetic() || c == '%').unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    if unit.is_empty() {
+        let limit: f64 = number.parse().map_err(|_| invalid("limit must be a number"))?;
+        return Ok(Constraint::ResourceLimit { resource: subject.to_string(), limit });
+    }
+    let threshold: u64 = number
+        .trim()
+        .parse()
+        .map_err(|_| invalid("SLA threshold must be a whole number followed by a unit"))?;
+    Ok(Constraint::Sla { metric: subject.to_string(), threshold, unit: unit.to_string() })
+}
+
+impl FromStr for Constraint {
+    type Err = OrpheonError;
+
+    fn from_str(text: &str) -> Result<Self> {
+        parse_constraint(text)
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::StateMatch { expression } => f.write_str(expression),
+            Constraint::ResourceLimit { resource, limit } => write!(f, "{} <= {}", resource, limit),
+            Constraint::Sla { metric, threshold, unit } => write!(f, "{} <= {}{}", metric, threshold, unit),
+            Constraint::Deadline { by } => write!(f, "deadline <= {}", by.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            Constraint::Provider { node_id } => write!(f, "provider == '{}'", node_id),
+            Constraint::GeoFence { regions, allowed } => {
+                let regions: Vec<String> = regions.iter().map(|r| format!("'{}'", r)).collect();
+                let keyword = if *allowed { "in" } else { "not in" };
+                write!(f, "region {} [{}]", keyword, regions.join(", "))
+            }
+            Constraint::DependsOn { intent_id, require_success: true } => {
+                write!(f, "after intent {} succeeds", intent_id)
+            }
+            Constraint::DependsOn { intent_id, require_success: false } => write!(f, "after intent {} finishes", intent_id),
+            Constraint::Custom { name, .. } => write!(f, "custom constraint {}", name),
+        }
+    }
+}
+
+fn split_keyword<'a>(text: &'a str, keyword: &str) -> Option<(&'a str, &'a str)> {
+    text.split_once(keyword).map(|(l, r)| (l.trim(), r.trim()))
+}
+
+fn parse_list(subject: &str, list: &str) -> std::result::Result<Vec<String>, String> {
+    if subject != "region" {
+        return Err(format!("`in` only applies to region, not {}", subject));
+    }
+    let inner = list
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or("expected a list such as ['us-east', 'eu-west']")?;
+    let regions = inner
+        .split(',')
+        .map(|item| unquote(item.trim()).map(str::to_string).ok_or(format!("{} is not a quoted region", item.trim())))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    if regions.iter().any(String::is_empty) {
+        return Err("regions must not be empty".to_string());
+    }
+    Ok(regions)
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    ['\'', '"']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+}
+
+fn is_identifier(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_each_form() {
+        assert!(matches!(parse_constraint("provider == 'node-a'").unwrap(), Constraint::Provider { node_id } if node_id == "node-a"));
+        assert!(matches!(
+            parse_constraint("region not in ['cn-north']").unwrap(),
+            Constraint::GeoFence { regions, allowed: false } if regions == vec!["cn-north"]
+        ));
+        assert!(matches!(
+            parse_constraint("region in ['us-east', \"eu-west\"]").unwrap(),
+            Constraint::GeoFence { regions, allowed: true } if regions == vec!["us-east", "eu-west"]
+        ));
+        assert!(matches!(parse_constraint("deadline < 2026-01-01T00:00:00Z").unwrap(), Constraint::Deadline { .. }));
+        assert!(matches!(
+            parse_constraint("latency < 200ms").unwrap(),
+            Constraint::Sla { metric, threshold: 200, unit } if metric == "latency" && unit == "ms"
+        ));
+        assert!(matches!(
+            parse_constraint(" total_cost <= 5.50 ").unwrap(),
+            Constraint::ResourceLimit { resource, limit } if resource == "total_cost" && limit == 5.5
+        ));
+        assert!(matches!(
+            "gpu_type == 'H100'".parse::<Constraint>().unwrap(),
+            Constraint::StateMatch { expression } if expression == "gpu_type == 'H100'"
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for text in [
+            "provider == 'node-a'",
+            "region not in ['cn-north']",
+            "region in ['us-east', 'eu-west']",
+            "deadline <= 2026-01-01T00:00:00Z",
+            "latency <= 200ms",
+            "total_cost <= 5.5",
+            "gpu_type == 'H100'",
+        ] {
+            let constraint = parse_constraint(text).unwrap();
+            assert_eq!(constraint.to_string(), text);
+            assert_eq!(parse_constraint(&constraint.to_string()).unwrap(), constraint);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_expressions() {
+        for (text, reason) in [
+            ("cheap please", "expected a comparison"),
+            ("latency < 1.5ms", "whole number"),
+            ("total_cost < 5.5.5", "must be a number"),
+            ("zone in ['a']", "only applies to region"),
+            ("region in 'us-east'", "expected a list"),
+            ("deadline < tomorrow", "RFC 3339"),
+            ("provider == node-a", "quoted node ID"),
+        ] {
+            let err = parse_constraint(text).unwrap_err().to_string();
+            assert!(err.contains(reason), "{}: {}", text, err);
+        }
+    }
+}
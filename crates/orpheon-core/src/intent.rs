@@ -2,17 +2,28 @@
 //!
 //! An Intent is the core primitive - a declaration of a desired future state.
 
-use chrono::{DateTime, Duration, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::canonical;
+use crate::conflict::{self, ConstraintConflict};
+use crate::constraint::ConstraintRegistry;
+use crate::crypto;
+use crate::diff::IntentDiff;
 use crate::error::{OrpheonError, Result};
-use crate::types::Priority;
+use crate::money::{CurrencyConverter, MoneyAmount};
+use crate::pointer;
+use crate::types::{Priority, ResourceType};
+use crate::validation::{self, IntentLimits};
 
 /// An Intent is a declaration of a desired future state.
 /// It is immutable once signed.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Intent {
     /// Unique identifier for this intent.
     pub id: Uuid,
@@ -23,6 +34,11 @@ pub struct Intent {
     /// Hard constraints that MUST be met.
     pub constraints: Vec<Constraint>,
 
+    /// Best-effort constraints: honored if possible, penalized rather than
+    /// pruned by the planner when violated.
+    #[serde(default)]
+    pub soft_constraints: Vec<Constraint>,
+
     /// Optimization preferences (e.g., minimize cost vs minimize latency).
     pub preferences: Vec<Preference>,
 
@@ -39,6 +55,11 @@ pub struct Intent {
     #[serde(default)]
     pub metadata: serde_json::Value,
 
+    /// Key/value labels for grouping and filtering intents, e.g.
+    /// `team=ml`; see [`IntentBuilder::label`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
     /// Cryptographic signature of the issuer.
     pub signature: Option<Signature>,
 
@@ -51,6 +72,7 @@ pub struct Intent {
 
 /// Hard constraint that MUST be satisfied.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Constraint {
     /// State must match expression (e.g., "region == 'US-EAST'").
@@ -71,12 +93,17 @@ pub enum Constraint {
     /// Geographic restriction.
     GeoFence { regions: Vec<String>, allowed: bool },
 
+    /// Must not start until another intent has finished, and with
+    /// `require_success` fails if that intent does not complete.
+    DependsOn { intent_id: Uuid, require_success: bool },
+
     /// Custom constraint with arbitrary data.
     Custom { name: String, data: serde_json::Value },
 }
 
 /// Optimization preference (soft constraint).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Preference {
     /// What to optimize (e.g., "cost", "latency", "reliability").
     pub objective: String,
@@ -90,6 +117,7 @@ pub struct Preference {
 
 /// Optimization direction.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum OptimizationDirection {
     Minimize,
@@ -98,10 +126,15 @@ pub enum OptimizationDirection {
 
 /// Budget for intent execution.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Budget {
     /// Maximum monetary cost allowed.
     pub max_cost: Option<f64>,
 
+    /// `max_cost` exactly, in minor units; preferred over it when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_minor: Option<MoneyAmount>,
+
     /// Currency for the cost (e.g., "USD").
     pub currency: String,
 
@@ -110,6 +143,11 @@ pub struct Budget {
 
     /// Maximum number of retries allowed.
     pub max_retries: u32,
+
+    /// Most of each resource, e.g. memory in bytes, the plan's actions may
+    /// consume in total.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resource_limits: Vec<(ResourceType, f64)>,
 }
 
 impl Budget {
@@ -117,10 +155,62 @@ impl Budget {
     pub fn usd(amount: f64) -> Self {
         Self {
             max_cost: Some(amount),
+            max_cost_minor: None,
             currency: "USD".to_string(),
             max_duration_ms: None,
             max_retries: 3,
+            resource_limits: Vec::new(),
+        }
+    }
+
+    /// Create a new budget with an exact USD cost limit in cents.
+    pub fn usd_cents(cents: u64) -> Self {
+        let limit = MoneyAmount::usd_cents(cents);
+        Self {
+            max_cost: Some(limit.to_major()),
+            max_cost_minor: Some(limit),
+            ..Self::usd(0.0)
+        }
+    }
+
+    /// The cost limit in major units, taken from `max_cost_minor` when set.
+    pub fn cost_limit(&self) -> Option<f64> {
+        self.max_cost_minor.as_ref().map(MoneyAmount::to_major).or(self.max_cost)
+    }
+
+    /// The same budget in `currency`, converting its cost limit with
+    /// `converter`.
+    ///
+    /// Fails with [`OrpheonError::IntentInvalid`] if the budget's currency
+    /// cannot be converted or its exact limit is in another currency. A
+    /// budget with no currency has no monetary limit and is only relabelled.
+    pub fn converted(&self, currency: &str, converter: &dyn CurrencyConverter) -> Result<Budget> {
+        if let Some(exact) = self.max_cost_minor.as_ref().filter(|exact| exact.currency != self.currency) {
+            return Err(OrpheonError::IntentInvalid {
+                intent_id: None,
+                message: format!(
+                    "Budget limit is in {} but the budget is in {}",
+                    exact.currency, self.currency
+                ),
+            });
         }
+        if self.currency.is_empty() || self.currency == currency {
+            return Ok(Budget { currency: currency.to_string(), ..self.clone() });
+        }
+
+        // Converting even without a limit rejects currencies the converter
+        // does not know before anything is quoted in them
+        let limit = converter.convert(self.cost_limit().unwrap_or(0.0), &self.currency, currency)?;
+        let max_cost = self.cost_limit().map(|_| limit);
+        Ok(Budget {
+            max_cost,
+            max_cost_minor: self
+                .max_cost_minor
+                .as_ref()
+                .and_then(|_| MoneyAmount::from_major(limit, currency)),
+            currency: currency.to_string(),
+            ..self.clone()
+        })
     }
 
     /// Set maximum duration.
@@ -134,16 +224,36 @@ impl Budget {
         self.max_retries = retries;
         self
     }
+
+    /// Limit the total consumption of `resource`, replacing any earlier
+    /// limit on it.
+    pub fn with_resource(mut self, resource: ResourceType, limit: f64) -> Self {
+        self.resource_limits.retain(|(r, _)| *r != resource);
+        self.resource_limits.push((resource, limit));
+        self
+    }
+
+    /// The limit on `resource`, if there is one.
+    pub fn resource_limit(&self, resource: &ResourceType) -> Option<f64> {
+        self.resource_limits.iter().find(|(r, _)| r == resource).map(|(_, limit)| *limit)
+    }
 }
 
 /// Time window during which an intent is valid.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TimeWindow {
     /// Earliest time the intent can begin execution.
     pub not_before: Option<DateTime<Utc>>,
 
     /// Latest time by which the intent must complete.
     pub not_after: Option<DateTime<Utc>>,
+
+    /// Times of day execution may begin in, e.g. 02:00 to 04:00 UTC on
+    /// weekdays; any time of day if empty. Execution that has begun is not
+    /// stopped when one of these windows ends.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recurrence: Vec<DailyWindow>,
 }
 
 impl Default for TimeWindow {
@@ -151,6 +261,7 @@ impl Default for TimeWindow {
         Self {
             not_before: None,
             not_after: Some(Utc::now() + Duration::hours(24)),
+            recurrence: Vec::new(),
         }
     }
 }
@@ -161,20 +272,100 @@ impl TimeWindow {
         Self {
             not_before: None,
             not_after: Some(Utc::now() + duration),
+            recurrence: Vec::new(),
         }
     }
 
     /// Check if the current time is within the window.
     pub fn is_valid_now(&self) -> bool {
-        let now = Utc::now();
-        let after_start = self.not_before.map_or(true, |t| now >= t);
-        let before_end = self.not_after.map_or(true, |t| now <= t);
-        after_start && before_end
+        self.is_open_at(Utc::now())
+    }
+
+    /// Whether `time` is within the window, and within one of its daily
+    /// windows if it has any.
+    pub fn is_open_at(&self, time: DateTime<Utc>) -> bool {
+        let after_start = self.not_before.is_none_or(|t| time >= t);
+        let before_end = self.not_after.is_none_or(|t| time <= t);
+        let recurring = self.recurrence.is_empty() || self.recurrence.iter().any(|w| w.contains(time));
+        after_start && before_end && recurring
+    }
+
+    /// Whether the window has yet to open, or is between two of its daily
+    /// windows, so execution must wait.
+    pub fn is_pending(&self) -> bool {
+        !self.has_closed() && !self.is_valid_now()
+    }
+
+    /// Whether the window has already closed.
+    pub fn has_closed(&self) -> bool {
+        self.not_after.is_some_and(|t| Utc::now() > t)
+    }
+
+    /// When the window is next open, now if it is open already; `None` if
+    /// it closes first.
+    pub fn next_opening(&self) -> Option<DateTime<Utc>> {
+        self.next_opening_after(Utc::now())
+    }
+
+    /// The first time from `time` on that the window is open; `None` if it
+    /// closes first.
+    pub fn next_opening_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let from = self.not_before.map_or(time, |start| start.max(time));
+        let opening = match self.recurrence.iter().any(|w| w.contains(from)) || self.recurrence.is_empty() {
+            true => Some(from),
+            false => self.recurrence.iter().filter_map(|w| w.next_start(from)).min(),
+        };
+        opening.filter(|t| self.not_after.is_none_or(|end| *t <= end))
+    }
+}
+
+/// A window of the day, in UTC, that recurs on some days of the week; see
+/// [`TimeWindow::recurrence`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DailyWindow {
+    /// Time of day the window opens.
+    pub start: NaiveTime,
+
+    /// Time of day the window closes, exclusive. Earlier than `start` for
+    /// a window crossing midnight, which then closes the next day.
+    pub end: NaiveTime,
+
+    /// Days the window opens on; every day if empty. A window crossing
+    /// midnight belongs to the day it opens on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weekdays: Vec<Weekday>,
+}
+
+impl DailyWindow {
+    /// Whether the window opens on `day`.
+    pub fn opens_on(&self, day: Weekday) -> bool {
+        self.weekdays.is_empty() || self.weekdays.contains(&day)
+    }
+
+    /// Whether `time` falls within the window.
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        let (day, of_day) = (time.weekday(), time.time());
+        if self.start <= self.end {
+            self.opens_on(day) && self.start <= of_day && of_day < self.end
+        } else {
+            (of_day >= self.start && self.opens_on(day)) || (of_day < self.end && self.opens_on(day.pred()))
+        }
+    }
+
+    /// The first time from `time` on that the window opens, within a week.
+    fn next_start(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (0..=7)
+            .map(|days| time.date_naive() + Duration::days(days))
+            .filter(|date| self.opens_on(date.weekday()))
+            .map(|date| date.and_time(self.start).and_utc())
+            .find(|start| *start >= time)
     }
 }
 
 /// Cryptographic signature for intent authentication.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Signature {
     /// The algorithm used (e.g., "ed25519", "secp256k1").
     pub algorithm: String,
@@ -194,12 +385,15 @@ pub struct Signature {
 pub struct IntentBuilder {
     kind: Option<String>,
     constraints: Vec<Constraint>,
+    soft_constraints: Vec<Constraint>,
     preferences: Vec<Preference>,
     budget: Budget,
     validity_window: TimeWindow,
     priority: Priority,
     metadata: serde_json::Value,
+    labels: BTreeMap<String, String>,
     parent_id: Option<Uuid>,
+    normalize_preferences: bool,
 }
 
 impl IntentBuilder {
@@ -208,6 +402,11 @@ impl IntentBuilder {
         Self::default()
     }
 
+    /// Start from an existing intent's spec; see [`Intent::to_builder`].
+    pub fn from_template(template: &Intent) -> Self {
+        template.to_builder()
+    }
+
     /// Set the kind of intent.
     pub fn kind(mut self, kind: impl Into<String>) -> Self {
         self.kind = Some(kind.into());
@@ -220,6 +419,12 @@ impl IntentBuilder {
         self
     }
 
+    /// Add a best-effort (soft) constraint.
+    pub fn soft_constraint(mut self, constraint: Constraint) -> Self {
+        self.soft_constraints.push(constraint);
+        self
+    }
+
     /// Add a state match constraint.
     pub fn state_match(self, expression: impl Into<String>) -> Self {
         self.constraint(Constraint::StateMatch {
@@ -244,6 +449,29 @@ impl IntentBuilder {
         })
     }
 
+    /// Add a deadline constraint.
+    pub fn deadline(self, by: DateTime<Utc>) -> Self {
+        self.constraint(Constraint::Deadline { by })
+    }
+
+    /// Add a deadline constraint `duration` from now.
+    pub fn deadline_in(self, duration: Duration) -> Self {
+        self.deadline(Utc::now() + duration)
+    }
+
+    /// Add a provider constraint.
+    pub fn provider(self, node_id: impl Into<String>) -> Self {
+        self.constraint(Constraint::Provider {
+            node_id: node_id.into(),
+        })
+    }
+
+    /// Add a geo-fence constraint: only `regions` if `allowed`, otherwise
+    /// anywhere but `regions`.
+    pub fn geo_fence(self, regions: Vec<String>, allowed: bool) -> Self {
+        self.constraint(Constraint::GeoFence { regions, allowed })
+    }
+
     /// Add a preference.
     pub fn preference(mut self, preference: Preference) -> Self {
         self.preferences.push(preference);
@@ -268,6 +496,15 @@ impl IntentBuilder {
         })
     }
 
+    /// Scale preference weights to sum to 1.0 at build time.
+    ///
+    /// The weights as given are recorded in metadata under
+    /// `original_preference_weights`. Weights summing to zero still fail.
+    pub fn normalize_preferences(mut self) -> Self {
+        self.normalize_preferences = true;
+        self
+    }
+
     /// Set the budget.
     pub fn budget(mut self, budget: Budget) -> Self {
         self.budget = budget;
@@ -280,6 +517,25 @@ impl IntentBuilder {
         self
     }
 
+    /// Defer execution until `time`; the validity window must still close
+    /// after it.
+    pub fn starts_at(mut self, time: DateTime<Utc>) -> Self {
+        self.validity_window.not_before = Some(time);
+        self
+    }
+
+    /// Defer execution until `duration` from now.
+    pub fn starts_in(self, duration: Duration) -> Self {
+        self.starts_at(Utc::now() + duration)
+    }
+
+    /// Only begin execution between `start` and `end` UTC on `weekdays`,
+    /// or on every day if `weekdays` is empty; see [`DailyWindow`].
+    pub fn recurring_window(mut self, start: NaiveTime, end: NaiveTime, weekdays: Vec<Weekday>) -> Self {
+        self.validity_window.recurrence.push(DailyWindow { start, end, weekdays });
+        self
+    }
+
     /// Set the priority.
     pub fn priority(mut self, priority: Priority) -> Self {
         self.priority = priority;
@@ -292,6 +548,37 @@ impl IntentBuilder {
         self
     }
 
+    /// Merge into the metadata set so far: objects are merged key by key,
+    /// recursively, and any other value in `metadata` replaces the existing
+    /// one.
+    pub fn merge_metadata(mut self, metadata: serde_json::Value) -> Self {
+        merge_json(&mut self.metadata, metadata);
+        self
+    }
+
+    /// Set one top-level metadata key, keeping the others.
+    pub fn metadata_field(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        if !self.metadata.is_object() {
+            self.metadata = serde_json::json!({});
+        }
+        if let Some(metadata) = self.metadata.as_object_mut() {
+            metadata.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    /// Set a label, replacing any earlier value for `key`.
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Wait for another intent to finish before starting, and with
+    /// `require_success` fail unless it completes.
+    pub fn depends_on(self, intent_id: Uuid, require_success: bool) -> Self {
+        self.constraint(Constraint::DependsOn { intent_id, require_success })
+    }
+
     /// Set parent intent ID (for recursive intents).
     pub fn parent(mut self, parent_id: Uuid) -> Self {
         self.parent_id = Some(parent_id);
@@ -299,21 +586,27 @@ impl IntentBuilder {
     }
 
     /// Build the Intent.
-    pub fn build(self) -> Result<Intent> {
-        let kind = self.kind.ok_or_else(|| OrpheonError::IntentInvalid {
+    pub fn build(mut self) -> Result<Intent> {
+        let kind = self.kind.take().ok_or_else(|| OrpheonError::IntentInvalid {
             intent_id: None,
             message: "Intent kind is required".to_string(),
         })?;
 
+        if self.normalize_preferences && !self.preferences.is_empty() {
+            self.apply_preference_normalization()?;
+        }
+
         Ok(Intent {
             id: Uuid::new_v4(),
             kind,
             constraints: self.constraints,
+            soft_constraints: self.soft_constraints,
             preferences: self.preferences,
             budget: self.budget,
             validity_window: self.validity_window,
             priority: self.priority,
             metadata: self.metadata,
+            labels: self.labels,
             signature: None,
             created_at: Utc::now(),
             parent_id: self.parent_id,
@@ -321,18 +614,131 @@ impl IntentBuilder {
     }
 }
 
+impl IntentBuilder {
+    fn apply_preference_normalization(&mut self) -> Result<()> {
+        normalize_preference_weights(None, &mut self.preferences, &mut self.metadata)
+    }
+}
+
+/// Rescale weights to sum to 1.0, recording the originals under
+/// `original_preference_weights` in `metadata`.
+fn normalize_preference_weights(
+    intent_id: Option<Uuid>,
+    preferences: &mut Vec<Preference>,
+    metadata: &mut serde_json::Value,
+) -> Result<()> {
+    let Some(normalized) = normalized_weights(preferences) else {
+        let sum: f32 = preferences.iter().map(|p| p.weight).sum();
+        return Err(preference_weights_error(intent_id, preferences, sum));
+    };
+
+    let original: Vec<serde_json::Value> = preferences
+        .iter()
+        .map(|p| serde_json::json!({ "objective": p.objective, "weight": p.weight }))
+        .collect();
+    *preferences = normalized;
+
+    if metadata.is_null() {
+        *metadata = serde_json::json!({});
+    }
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert("original_preference_weights".to_string(), original.into());
+    }
+
+    Ok(())
+}
+
+/// The preferences with weights scaled to sum to 1.0, all equal if every
+/// weight is zero; `None` if a weight is negative or not finite.
+fn normalized_weights(preferences: &[Preference]) -> Option<Vec<Preference>> {
+    if !preference_weights_normalizable(preferences) {
+        return None;
+    }
+    let sum: f32 = preferences.iter().map(|p| p.weight).sum();
+    let count = preferences.len() as f32;
+    Some(
+        preferences
+            .iter()
+            .map(|p| Preference {
+                weight: if sum > 0.0 { p.weight / sum } else { 1.0 / count },
+                ..p.clone()
+            })
+            .collect(),
+    )
+}
+
+fn merge_json(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Whether scaling would bring the weights to a valid sum of 1.0.
+fn preference_weights_normalizable(preferences: &[Preference]) -> bool {
+    let sum: f32 = preferences.iter().map(|p| p.weight).sum();
+    sum.is_finite() && preferences.iter().all(|p| p.weight >= 0.0)
+}
+
+/// How [`Intent::validate_with_mode`] treats problems it can fix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationMode {
+    /// Every problem is an error.
+    #[default]
+    Strict,
+    /// Preference weights not summing to 1.0 are rescaled first, as
+    /// [`IntentBuilder::normalize_preferences`] would.
+    Lenient,
+}
+
+fn preference_weights_error(intent_id: Option<Uuid>, preferences: &[Preference], sum: f32) -> OrpheonError {
+    OrpheonError::PreferenceWeightsInvalid {
+        intent_id,
+        sum,
+        weights: preferences.iter().map(|p| (p.objective.clone(), p.weight)).collect(),
+        normalizable: preference_weights_normalizable(preferences),
+    }
+}
+
 impl Intent {
     /// Create a new IntentBuilder.
     pub fn builder() -> IntentBuilder {
         IntentBuilder::new()
     }
 
-    /// Calculate a hash of the intent content (for signing).
+    /// A builder for a new intent with the same spec as this one.
+    ///
+    /// The ID, signature and creation time are not carried over.
+    pub fn to_builder(&self) -> IntentBuilder {
+        IntentBuilder {
+            kind: Some(self.kind.clone()),
+            constraints: self.constraints.clone(),
+            soft_constraints: self.soft_constraints.clone(),
+            preferences: self.preferences.clone(),
+            budget: self.budget.clone(),
+            validity_window: self.validity_window.clone(),
+            priority: self.priority,
+            metadata: self.metadata.clone(),
+            labels: self.labels.clone(),
+            parent_id: self.parent_id,
+            normalize_preferences: false,
+        }
+    }
+
+    /// Calculate a hash of the intent content (for signing), over its
+    /// [canonical](crate::canonical) encoding.
     pub fn content_hash(&self) -> String {
-        let content = serde_json::json!({
+        let mut content = serde_json::json!({
             "id": self.id,
             "kind": self.kind,
             "constraints": self.constraints,
+            "soft_constraints": self.soft_constraints,
             "preferences": self.preferences,
             "budget": self.budget,
             "validity_window": self.validity_window,
@@ -341,14 +747,163 @@ impl Intent {
             "created_at": self.created_at,
             "parent_id": self.parent_id,
         });
+        // Left out when empty, so unlabelled intents keep their hash
+        if !self.labels.is_empty() {
+            content["labels"] = serde_json::json!(self.labels);
+        }
+
+        canonical::hash(&content)
+    }
 
-        let mut hasher = Sha256::new();
-        hasher.update(content.to_string().as_bytes());
-        hex::encode(hasher.finalize())
+    /// Sign [`Intent::content_hash`] with `key` as the issuer, replacing any
+    /// earlier signature.
+    #[cfg(feature = "ed25519")]
+    pub fn sign(&mut self, key: &crypto::SigningKey) {
+        self.signature = Some(crypto::sign_hash_with(key, &self.content_hash()));
     }
 
-    /// Validate the intent.
+    /// Verify the issuer signature over [`Intent::content_hash`], with the
+    /// [scheme](crypto::scheme) its algorithm names.
+    ///
+    /// Returns `Ok(false)` if the signature is well-formed but does not
+    /// match, e.g. because the intent changed since it was signed. Fails
+    /// with [`OrpheonError::CryptoError`] if the intent is unsigned, uses an
+    /// unsupported algorithm, or has a malformed key or signature.
+    pub fn verify_signature(&self) -> Result<bool> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| OrpheonError::CryptoError("intent is not signed".to_string()))?;
+
+        crypto::scheme(&signature.algorithm)?.verify(
+            &signature.public_key,
+            self.content_hash().as_bytes(),
+            &signature.signature,
+        )
+    }
+
+    /// IDs of the intent's ancestors through `parent_id`, parent first.
+    ///
+    /// `resolver` looks up intents by ID; a parent it cannot find ends the
+    /// chain, listed last. Fails with [`OrpheonError::IntentInvalid`] if
+    /// the intent is its own parent, if the chain loops, or if it is longer
+    /// than `max_depth`.
+    pub fn ancestor_chain(&self, resolver: &dyn Fn(Uuid) -> Option<Intent>, max_depth: usize) -> Result<Vec<Uuid>> {
+        let invalid = |message: String| OrpheonError::IntentInvalid { intent_id: Some(self.id), message };
+        if self.parent_id == Some(self.id) {
+            return Err(invalid(format!("Intent {} is its own parent", self.id)));
+        }
+
+        let mut chain = Vec::new();
+        let mut next = self.parent_id;
+        while let Some(id) = next {
+            if id == self.id || chain.contains(&id) {
+                return Err(invalid(format!("Parent chain of intent {} loops back to intent {}", self.id, id)));
+            }
+            if chain.len() == max_depth {
+                return Err(invalid(format!(
+                    "Parent chain of intent {} is deeper than the limit of {}",
+                    self.id, max_depth
+                )));
+            }
+            chain.push(id);
+            next = resolver(id).and_then(|parent| parent.parent_id);
+        }
+        Ok(chain)
+    }
+
+    /// Intents this one waits for through hard `DependsOn` constraints,
+    /// each with whether it must succeed.
+    pub fn dependencies(&self) -> impl Iterator<Item = (Uuid, bool)> + '_ {
+        self.constraints.iter().filter_map(|constraint| match constraint {
+            Constraint::DependsOn { intent_id, require_success } => Some((*intent_id, *require_success)),
+            _ => None,
+        })
+    }
+
+    /// Check that no chain of `DependsOn` constraints leads from this
+    /// intent back to itself.
+    ///
+    /// `resolver` looks up intents by ID; dependencies it cannot find are
+    /// not followed. Fails with [`OrpheonError::IntentInvalid`] naming the
+    /// dependency that closes the loop.
+    pub fn check_dependency_cycles(&self, resolver: &dyn Fn(Uuid) -> Option<Intent>) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        let mut pending: Vec<(Uuid, Uuid)> = self.dependencies().map(|(id, _)| (id, id)).collect();
+        while let Some((id, via)) = pending.pop() {
+            if id == self.id {
+                return Err(OrpheonError::IntentInvalid {
+                    intent_id: Some(self.id),
+                    message: format!("Dependencies of intent {} loop back to it through intent {}", self.id, via),
+                });
+            }
+            if seen.insert(id) {
+                if let Some(dependency) = resolver(id) {
+                    pending.extend(dependency.dependencies().map(|(next, _)| (next, via)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find hard constraints, budget and window settings that can never be
+    /// satisfied together.
+    pub fn check_consistency(&self) -> Vec<ConstraintConflict> {
+        conflict::find_conflicts(self)
+    }
+
+    /// What changed from this intent to `other`, e.g. between two rounds of
+    /// a negotiation.
+    pub fn diff(&self, other: &Intent) -> IntentDiff {
+        IntentDiff::between(self, other)
+    }
+
+    /// Validate the intent against the default [`IntentLimits`].
     pub fn validate(&self) -> Result<()> {
+        self.validate_with(&IntentLimits::default())
+    }
+
+    /// Validate the intent under `mode`, fixing what lenient validation
+    /// may first. Signed intents cannot be changed, so are always
+    /// validated strictly; weights that cannot be scaled are reported as
+    /// they are.
+    pub fn validate_with_mode(
+        &mut self,
+        limits: &IntentLimits,
+        registry: Option<&ConstraintRegistry>,
+        mode: ValidationMode,
+    ) -> Result<()> {
+        if mode == ValidationMode::Lenient
+            && self.signature.is_none()
+            && !self.preference_weights_valid()
+            && preference_weights_normalizable(&self.preferences)
+        {
+            normalize_preference_weights(Some(self.id), &mut self.preferences, &mut self.metadata)?;
+        }
+        self.validate_against(limits, registry)
+    }
+
+    /// Preferences with weights scaled to sum to 1.0, all equal if every
+    /// weight is zero; the weights as they are if they cannot be scaled.
+    pub fn normalized_preferences(&self) -> Vec<Preference> {
+        normalized_weights(&self.preferences).unwrap_or_else(|| self.preferences.clone())
+    }
+
+    fn preference_weights_valid(&self) -> bool {
+        let total_weight: f32 = self.preferences.iter().map(|p| p.weight).sum();
+        self.preferences.is_empty() || (total_weight - 1.0).abs() <= 0.01
+    }
+
+    /// Validate the intent, bounding its size and budget by `limits`.
+    /// Custom constraints are not checked.
+    pub fn validate_with(&self, limits: &IntentLimits) -> Result<()> {
+        self.validate_against(limits, None)
+    }
+
+    /// Validate the intent, bounding its size and budget by `limits` and,
+    /// given a registry, rejecting custom constraints it has no validator for or
+    /// whose data their validator rejects.
+    pub fn validate_against(&self, limits: &IntentLimits, registry: Option<&ConstraintRegistry>) -> Result<()> {
         // Check kind is not empty
         if self.kind.trim().is_empty() {
             return Err(OrpheonError::IntentInvalid {
@@ -357,23 +912,49 @@ impl Intent {
             });
         }
 
-        // Check validity window
-        if !self.validity_window.is_valid_now() {
-            return Err(OrpheonError::IntentInvalid {
+        // Check sizes before anything iterates over the intent's fields
+        if let Some(message) = validation::size_violation(self, limits) {
+            return Err(OrpheonError::IntentInvalid { intent_id: Some(self.id), message });
+        }
+
+        // Check field ranges before anything sums or compares them
+        let mut errors = validation::field_errors(self, limits);
+        if let Some(registry) = registry {
+            errors.extend(validation::custom_errors(self, registry));
+        }
+        if !errors.is_empty() {
+            // Conflicts only compare fields, so they are still worth reporting
+            return Err(OrpheonError::FieldsInvalid {
                 intent_id: Some(self.id),
-                message: "Intent is outside its validity window".to_string(),
+                errors,
+                conflicts: self.check_consistency(),
             });
         }
 
-        // Validate preference weights
-        let total_weight: f32 = self.preferences.iter().map(|p| p.weight).sum();
-        if !self.preferences.is_empty() && (total_weight - 1.0).abs() > 0.01 {
+        // Check for contradictory constraints before the window, so an
+        // inverted window is reported as such
+        let conflicts = self.check_consistency();
+        if !conflicts.is_empty() {
+            return Err(OrpheonError::ConstraintConflict {
+                intent_id: Some(self.id),
+                conflicts,
+            });
+        }
+
+        // A window that has not opened yet only defers execution
+        if self.validity_window.has_closed() {
             return Err(OrpheonError::IntentInvalid {
                 intent_id: Some(self.id),
-                message: format!("Preference weights must sum to 1.0, got {}", total_weight),
+                message: "Intent's validity window has already closed".to_string(),
             });
         }
 
+        // Validate preference weights
+        if !self.preference_weights_valid() {
+            let total_weight: f32 = self.preferences.iter().map(|p| p.weight).sum();
+            return Err(preference_weights_error(Some(self.id), &self.preferences, total_weight));
+        }
+
         Ok(())
     }
 
@@ -381,17 +962,47 @@ impl Intent {
     pub fn is_child(&self) -> bool {
         self.parent_id.is_some()
     }
-}
 
-// Add hex dependency for content_hash
-fn hex_encode(bytes: impl AsRef<[u8]>) -> String {
-    bytes.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
-}
+    /// The metadata at a JSON pointer such as `/labels/env`, or `None` if
+    /// it is missing or null. Fails if it is not a `T`.
+    pub fn metadata_get<T: DeserializeOwned>(&self, pointer: &str) -> Result<Option<T>> {
+        pointer::get(&self.metadata, pointer)
+    }
+
+    /// Set the metadata at a JSON pointer, creating objects on the way.
+    /// Changing a signed intent's metadata invalidates its signature.
+    pub fn metadata_set(&mut self, pointer: &str, value: impl Serialize) -> Result<()> {
+        pointer::set(&mut self.metadata, pointer, value)
+    }
+
+    /// Capabilities a node needs to run this intent, sorted, e.g.
+    /// `region:us-east-1`.
+    ///
+    /// A hard `GeoFence` allowing a single region requires that region;
+    /// anything else, such as `executor:http` or `capability:gpu`, is listed
+    /// under the `requirements` metadata key.
+    pub fn requirements(&self) -> Vec<String> {
+        let regions = self.constraints.iter().filter_map(|c| match c {
+            Constraint::GeoFence { regions, allowed: true } if regions.len() == 1 => {
+                Some(format!("region:{}", regions[0]))
+            }
+            _ => None,
+        });
+        let listed = self.metadata["requirements"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str().map(str::to_string));
+
+        let mut requirements: Vec<String> = regions.chain(listed).collect();
+        requirements.sort();
+        requirements.dedup();
+        requirements
+    }
 
-// Use the local function instead of the hex crate
-mod hex {
-    pub fn encode(bytes: impl AsRef<[u8]>) -> String {
-        super::hex_encode(bytes)
+    /// The [requirements](Self::requirements) not among `capabilities`.
+    pub fn missing_capabilities(&self, capabilities: &[String]) -> Vec<String> {
+        self.requirements().into_iter().filter(|r| !capabilities.contains(r)).collect()
     }
 }
 
@@ -416,18 +1027,227 @@ mod tests {
         assert_eq!(intent.preferences.len(), 2);
     }
 
+    #[test]
+    fn test_soft_constraints_default_when_absent() {
+        let intent = Intent::builder()
+            .kind("test")
+            .soft_constraint(Constraint::GeoFence {
+                regions: vec!["eu-west".to_string()],
+                allowed: false,
+            })
+            .build()
+            .unwrap();
+        assert_eq!(intent.soft_constraints.len(), 1);
+
+        let mut json = serde_json::to_value(&intent).unwrap();
+        json.as_object_mut().unwrap().remove("soft_constraints");
+        let restored: Intent = serde_json::from_value(json).unwrap();
+        assert!(restored.soft_constraints.is_empty());
+    }
+
     #[test]
     fn test_intent_validation() {
         let intent = Intent::builder().kind("test").build().unwrap();
         assert!(intent.validate().is_ok());
     }
 
+    #[test]
+    fn test_normalize_preferences() {
+        let intent = Intent::builder()
+            .kind("test")
+            .minimize("cost", 0.5)
+            .maximize("speed", 0.6)
+            .normalize_preferences()
+            .build()
+            .unwrap();
+
+        assert!((intent.preferences[0].weight - 0.5 / 1.1).abs() < 1e-6);
+        assert!((intent.preferences[1].weight - 0.6 / 1.1).abs() < 1e-6);
+        assert!(intent.validate().is_ok());
+
+        let original = &intent.metadata["original_preference_weights"];
+        assert_eq!(original[0]["objective"], "cost");
+        assert_eq!(original[0]["weight"].as_f64().unwrap() as f32, 0.5);
+        assert_eq!(original[1]["weight"].as_f64().unwrap() as f32, 0.6);
+    }
+
+    #[test]
+    fn test_normalize_zero_weights_evenly() {
+        let intent = Intent::builder()
+            .kind("test")
+            .minimize("cost", 0.0)
+            .maximize("speed", 0.0)
+            .normalize_preferences()
+            .build()
+            .unwrap();
+
+        assert_eq!(intent.preferences[0].weight, 0.5);
+        assert_eq!(intent.preferences[1].weight, 0.5);
+        assert!(intent.validate().is_ok());
+    }
+
+    #[test]
+    fn test_normalized_preferences() {
+        let single = Intent::builder().kind("test").minimize("cost", 0.2).build().unwrap();
+        assert!(single.validate().is_err());
+        assert_eq!(single.normalized_preferences()[0].weight, 1.0);
+        assert_eq!(single.preferences[0].weight, 0.2);
+
+        let triple = Intent::builder()
+            .kind("test")
+            .minimize("cost", 1.0)
+            .maximize("speed", 1.0)
+            .minimize("latency", 1.0)
+            .build()
+            .unwrap();
+        let weights: Vec<f32> = triple.normalized_preferences().iter().map(|p| p.weight).collect();
+        assert!(weights.iter().all(|w| (w - 1.0 / 3.0).abs() < 1e-6), "{:?}", weights);
+
+        let mut negative = Intent::builder().kind("test").minimize("cost", -1.0).build().unwrap();
+        assert_eq!(negative.normalized_preferences()[0].weight, -1.0);
+        let err = negative.validate_with_mode(&IntentLimits::default(), None, ValidationMode::Lenient).unwrap_err();
+        assert!(matches!(err, OrpheonError::FieldsInvalid { .. }));
+    }
+
+    #[test]
+    fn test_lenient_validation_normalizes_weights() {
+        let limits = IntentLimits::default();
+        let mut intent = Intent::builder()
+            .kind("test")
+            .minimize("cost", 1.0)
+            .maximize("speed", 1.0)
+            .minimize("latency", 1.0)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            intent.clone().validate_with_mode(&limits, None, ValidationMode::Strict),
+            Err(OrpheonError::PreferenceWeightsInvalid { sum, normalizable: true, .. }) if sum == 3.0
+        ));
+
+        intent.validate_with_mode(&limits, None, ValidationMode::Lenient).unwrap();
+        assert!(intent.preferences.iter().all(|p| (p.weight - 1.0 / 3.0).abs() < 1e-6));
+        assert_eq!(intent.metadata["original_preference_weights"][2]["weight"], 1.0);
+
+        let mut zero = Intent::builder().kind("test").minimize("cost", 0.0).maximize("speed", 0.0).build().unwrap();
+        zero.validate_with_mode(&limits, None, ValidationMode::Lenient).unwrap();
+        assert_eq!(zero.preferences[1].weight, 0.5);
+
+        // Valid weights are left alone
+        let mut valid = Intent::builder().kind("test").minimize("cost", 0.995).build().unwrap();
+        valid.validate_with_mode(&limits, None, ValidationMode::Lenient).unwrap();
+        assert_eq!(valid.preferences[0].weight, 0.995);
+        assert!(valid.metadata.get("original_preference_weights").is_none());
+    }
+
+    #[test]
+    fn test_preference_weight_error_details() {
+        let intent = Intent::builder()
+            .kind("test")
+            .minimize("cost", 0.5)
+            .maximize("speed", 0.6)
+            .build()
+            .unwrap();
+
+        let err = intent.validate().unwrap_err();
+        match &err {
+            OrpheonError::PreferenceWeightsInvalid { intent_id, sum, weights, normalizable } => {
+                assert_eq!(*intent_id, Some(intent.id));
+                assert!((sum - 1.1).abs() < 1e-6);
+                assert_eq!(weights, &vec![("cost".to_string(), 0.5), ("speed".to_string(), 0.6)]);
+                assert!(normalizable);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let message = err.to_string();
+        assert!(message.contains("cost=0.5"));
+        assert!(message.contains("speed=0.6"));
+        assert!(message.contains("normaliz"));
+    }
+
     #[test]
     fn test_intent_builder_missing_kind() {
         let result = Intent::builder().build();
         assert!(result.is_err());
     }
 
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_template_copies_spec_but_not_identity() {
+        let mut template = Intent::builder()
+            .kind("deploy")
+            .resource_limit("cpu", 4.0)
+            .minimize("cost", 1.0)
+            .budget(Budget::usd(10.0))
+            .priority(Priority::High)
+            .metadata(serde_json::json!({ "team": "infra", "labels": { "env": "prod", "tier": "web" } }))
+            .build()
+            .unwrap();
+        template.sign(&crypto::SigningKey::from_bytes(&[3u8; 32]));
+
+        let intent = IntentBuilder::from_template(&template)
+            .budget(Budget::usd(20.0))
+            .merge_metadata(serde_json::json!({ "run": 7, "labels": { "tier": "api" } }))
+            .build()
+            .unwrap();
+        assert_ne!(intent.id, template.id);
+        assert!(intent.signature.is_none());
+        assert_eq!(intent.kind, template.kind);
+        assert_eq!(intent.constraints, template.constraints);
+        assert_eq!(intent.preferences, template.preferences);
+        assert_eq!(intent.priority, Priority::High);
+        assert_eq!(intent.budget.max_cost, Some(20.0));
+        assert_eq!(
+            intent.metadata,
+            serde_json::json!({ "team": "infra", "run": 7, "labels": { "env": "prod", "tier": "api" } })
+        );
+
+        // Merging into no metadata sets it
+        let bare = Intent::builder().kind("deploy").merge_metadata(serde_json::json!({ "run": 1 })).build().unwrap();
+        assert_eq!(bare.metadata, serde_json::json!({ "run": 1 }));
+    }
+
+    #[test]
+    fn test_typed_metadata_accessors() {
+        let mut intent = Intent::builder()
+            .kind("deploy")
+            .metadata_field("team", "infra")
+            .metadata_field("replicas", 3)
+            .metadata_field("labels", serde_json::json!({ "env": "prod" }))
+            .build()
+            .unwrap();
+
+        assert_eq!(intent.metadata_get::<String>("/team").unwrap(), Some("infra".to_string()));
+        assert_eq!(intent.metadata_get::<u32>("/replicas").unwrap(), Some(3));
+        assert_eq!(intent.metadata_get::<String>("/labels/env").unwrap(), Some("prod".to_string()));
+        assert_eq!(intent.metadata_get::<String>("/owner").unwrap(), None);
+
+        let err = intent.metadata_get::<u32>("/labels/env").unwrap_err();
+        assert!(matches!(err, OrpheonError::SerializationError(ref message) if message.contains("/labels/env")));
+
+        intent.metadata_set("/labels/tier", "web").unwrap();
+        intent.metadata_set("/rollout/batch/size", 10).unwrap();
+        assert_eq!(intent.metadata["labels"], serde_json::json!({ "env": "prod", "tier": "web" }));
+        assert_eq!(intent.metadata_get::<u64>("/rollout/batch/size").unwrap(), Some(10));
+        assert!(intent.metadata_set("/team/lead", "ana").is_err());
+    }
+
+    #[test]
+    fn test_requirements_from_constraints_and_metadata() {
+        let intent = Intent::builder()
+            .kind("train")
+            .geo_fence(vec!["us-east-1".to_string()], true)
+            .geo_fence(vec!["eu-west-1".to_string(), "eu-central-1".to_string()], true)
+            .metadata(serde_json::json!({ "requirements": ["executor:http", "capability:gpu", 7] }))
+            .build()
+            .unwrap();
+        assert_eq!(intent.requirements(), vec!["capability:gpu", "executor:http", "region:us-east-1"]);
+
+        let capabilities = vec!["capability:gpu".to_string(), "region:us-east-1".to_string()];
+        assert_eq!(intent.missing_capabilities(&capabilities), vec!["executor:http"]);
+        assert!(Intent::builder().kind("train").build().unwrap().requirements().is_empty());
+    }
+
     #[test]
     fn test_budget_builder() {
         let budget = Budget::usd(50.0).with_duration(5000).with_retries(5);
@@ -436,9 +1256,403 @@ mod tests {
         assert_eq!(budget.max_retries, 5);
     }
 
+    #[test]
+    fn test_constraint_helpers() {
+        let by = Utc::now() + Duration::hours(1);
+        let intent = Intent::builder()
+            .kind("deploy")
+            .deadline(by)
+            .provider("node-7")
+            .geo_fence(vec!["eu-west".to_string()], true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            intent.constraints,
+            vec![
+                Constraint::Deadline { by },
+                Constraint::Provider { node_id: "node-7".to_string() },
+                Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: true },
+            ]
+        );
+        assert!(intent.validate().is_ok());
+    }
+
+    #[test]
+    fn test_deadline_validation() {
+        let past = Intent::builder().kind("deploy").deadline_in(Duration::minutes(-1)).build().unwrap();
+        match past.validate().unwrap_err() {
+            OrpheonError::FieldsInvalid { errors, .. } => {
+                assert_eq!(errors[0].field, "constraints[0].by");
+                assert!(errors[0].message.contains("past"), "{}", errors[0].message);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let window = TimeWindow::valid_for(Duration::hours(1));
+        let beyond = Intent::builder()
+            .kind("deploy")
+            .validity_window(window.clone())
+            .deadline_in(Duration::hours(2))
+            .build()
+            .unwrap();
+        match beyond.validate().unwrap_err() {
+            OrpheonError::ConstraintConflict { conflicts, .. } => {
+                assert_eq!(conflicts[0].first, "constraints[0]");
+                assert_eq!(conflicts[0].second, "validity_window.not_after");
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        // Exactly at the end of the window is fine, as is any deadline
+        // under an open-ended window
+        let at_end = Intent::builder().kind("deploy").validity_window(window.clone()).deadline(window.not_after.unwrap()).build().unwrap();
+        assert!(at_end.validate().is_ok());
+        let open = TimeWindow { not_before: None, not_after: None, recurrence: Vec::new() };
+        let open = Intent::builder().kind("deploy").validity_window(open).deadline_in(Duration::days(365)).build().unwrap();
+        assert!(open.validate().is_ok());
+    }
+
     #[test]
     fn test_time_window_validity() {
         let window = TimeWindow::valid_for(Duration::hours(1));
         assert!(window.is_valid_now());
+        assert!(!window.is_pending() && !window.has_closed());
+    }
+
+    #[test]
+    fn test_recurring_windows() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let time = |h: u32| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+
+        // 22:00 Friday to 02:00 Saturday
+        let intent = Intent::builder()
+            .kind("maintenance")
+            .validity_window(TimeWindow { not_before: None, not_after: None, recurrence: Vec::new() })
+            .recurring_window(time(22), time(2), vec![Weekday::Fri])
+            .build()
+            .unwrap();
+        let window = &intent.validity_window;
+        assert!(window.is_open_at(at("2026-01-09T23:00:00Z")));
+        assert!(window.is_open_at(at("2026-01-10T01:59:59Z")));
+        assert!(!window.is_open_at(at("2026-01-10T02:00:00Z")));
+        assert!(!window.is_open_at(at("2026-01-10T23:00:00Z")));
+        assert!(!window.is_open_at(at("2026-01-09T01:00:00Z")));
+        assert_eq!(window.next_opening_after(at("2026-01-10T03:00:00Z")), Some(at("2026-01-16T22:00:00Z")));
+        assert_eq!(window.next_opening_after(at("2026-01-10T01:00:00Z")), Some(at("2026-01-10T01:00:00Z")));
+
+        // 02:00 to 04:00 every day, but only until the window closes
+        let daily = TimeWindow {
+            not_before: Some(at("2026-01-05T03:30:00Z")),
+            not_after: Some(at("2026-01-06T01:00:00Z")),
+            recurrence: vec![DailyWindow { start: time(2), end: time(4), weekdays: vec![] }],
+        };
+        assert!(!daily.is_open_at(at("2026-01-05T02:30:00Z")));
+        assert_eq!(daily.next_opening_after(at("2026-01-05T00:00:00Z")), Some(at("2026-01-05T03:30:00Z")));
+        assert_eq!(daily.next_opening_after(at("2026-01-05T05:00:00Z")), None);
+
+        // Older windows have no recurrence and serialize as they did
+        let json = serde_json::to_value(TimeWindow::valid_for(Duration::hours(1))).unwrap();
+        assert!(json.get("recurrence").is_none());
+        assert!(serde_json::from_value::<TimeWindow>(json).unwrap().recurrence.is_empty());
+        let round_trip: TimeWindow = serde_json::from_value(serde_json::to_value(window).unwrap()).unwrap();
+        assert_eq!(round_trip.recurrence, window.recurrence);
+    }
+
+    #[test]
+    fn test_deferred_intent_validates() {
+        let deferred = Intent::builder().kind("deploy").starts_in(Duration::minutes(5)).build().unwrap();
+        assert!(deferred.validity_window.is_pending());
+        assert!(!deferred.validity_window.is_valid_now());
+        assert!(deferred.validate().is_ok());
+
+        let closed = TimeWindow { not_before: None, not_after: Some(Utc::now() - Duration::minutes(1)), recurrence: Vec::new() };
+        let closed = Intent::builder().kind("deploy").validity_window(closed).build().unwrap();
+        assert!(closed.validity_window.has_closed());
+        assert!(matches!(closed.validate(), Err(OrpheonError::IntentInvalid { .. })));
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_signature_survives_round_trip() {
+        let key = crypto::NodeKey::from_bytes(&[9u8; 32]);
+        let mut intent = Intent::builder()
+            .kind("deploy")
+            .priority(Priority::High)
+            .metadata(serde_json::json!({"team": "infra", "tier": 2}))
+            .minimize("cost", 1.0)
+            .build()
+            .unwrap();
+        assert!(intent.verify_signature().is_err());
+
+        key.sign_intent(&mut intent);
+        let decoded: Intent = serde_json::from_str(&serde_json::to_string(&intent).unwrap()).unwrap();
+        assert!(decoded.verify_signature().unwrap());
+
+        let mut tampered = decoded.clone();
+        tampered.priority = Priority::Critical;
+        assert!(!tampered.verify_signature().unwrap());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_labels_are_signed() {
+        let key = crypto::NodeKey::from_bytes(&[9u8; 32]);
+        let mut intent = Intent::builder().kind("deploy").label("team", "ml").label("env", "prod").build().unwrap();
+        let unlabelled = Intent { labels: BTreeMap::new(), ..intent.clone() };
+        assert_ne!(intent.content_hash(), unlabelled.content_hash());
+
+        key.sign_intent(&mut intent);
+        let mut tampered = intent.clone();
+        tampered.labels.insert("env".to_string(), "staging".to_string());
+        assert!(intent.verify_signature().unwrap());
+        assert!(!tampered.verify_signature().unwrap());
+
+        // Payloads from before labels still parse, and serialize as they were
+        let legacy = serde_json::to_value(&unlabelled).unwrap();
+        assert!(legacy.get("labels").is_none());
+        assert!(serde_json::from_value::<Intent>(legacy).unwrap().labels.is_empty());
+    }
+
+    /// Canonical encoding of [`golden_intent`]'s hashed fields.
+    const GOLDEN_ENCODING: &str = concat!(
+        r#"{"budget":{"currency":"USD","max_cost":100,"max_duration_ms":60000,"max_retries":3},"#,
+        r#""constraints":[{"allowed":true,"regions":["eu-west"],"type":"geo_fence"},"#,
+        r#"{"limit":12.5,"resource":"total_cost","type":"resource_limit"}],"#,
+        r#""created_at":"2025-06-01T12:00:00Z","id":"6f1c2a9e-3b4d-4e5f-8a7b-0c1d2e3f4a5b","#,
+        r#""kind":"provision_gpu_cluster","metadata":{"team":"ml","ticket":42},"parent_id":null,"#,
+        r#""preferences":[{"direction":"minimize","objective":"cost","weight":0.5}],"priority":"normal","#,
+        r#""soft_constraints":[{"metric":"latency","threshold":200,"type":"sla","unit":"ms"}],"#,
+        r#""validity_window":{"not_after":"2030-01-01T00:00:00Z","not_before":null}}"#,
+    );
+
+    /// SHA-256 of [`GOLDEN_ENCODING`].
+    const GOLDEN_HASH: &str = "dd5a4f59c9e2e4cb0bdaa50bcb9bb7cf7e8beb05c173fe453f7c2501cbaab1c2";
+
+    /// An intent with every hashed field fixed.
+    fn golden_intent() -> Intent {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let mut intent = Intent::builder()
+            .kind("provision_gpu_cluster")
+            .constraint(Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: true })
+            .resource_limit("total_cost", 12.5)
+            .soft_constraint(Constraint::Sla { metric: "latency".to_string(), threshold: 200, unit: "ms".to_string() })
+            .minimize("cost", 0.5)
+            .budget(Budget::usd(100.0).with_duration(60_000))
+            .validity_window(TimeWindow { not_before: None, not_after: Some(at("2030-01-01T00:00:00Z")), recurrence: Vec::new() })
+            .metadata(serde_json::json!({ "team": "ml", "ticket": 42 }))
+            .build()
+            .unwrap();
+        intent.id = Uuid::parse_str("6f1c2a9e-3b4d-4e5f-8a7b-0c1d2e3f4a5b").unwrap();
+        intent.created_at = at("2025-06-01T12:00:00Z");
+        intent
+    }
+
+    #[test]
+    fn test_content_hash_golden_vector() {
+        let intent = golden_intent();
+        let content = serde_json::json!({
+            "id": intent.id,
+            "kind": intent.kind,
+            "constraints": intent.constraints,
+            "soft_constraints": intent.soft_constraints,
+            "preferences": intent.preferences,
+            "budget": intent.budget,
+            "validity_window": intent.validity_window,
+            "priority": intent.priority,
+            "metadata": intent.metadata,
+            "created_at": intent.created_at,
+            "parent_id": intent.parent_id,
+        });
+        assert_eq!(canonical::encode(&content), GOLDEN_ENCODING);
+        assert_eq!(intent.content_hash(), GOLDEN_HASH);
+    }
+
+    #[test]
+    fn test_budget_minor_units_serde() {
+        // Documents from before exact limits still parse, and serialize as they were
+        let legacy = serde_json::json!({ "max_cost": 12.5, "currency": "USD", "max_duration_ms": null, "max_retries": 3 });
+        let budget: Budget = serde_json::from_value(legacy.clone()).unwrap();
+        assert_eq!(budget.max_cost_minor, None);
+        assert_eq!(budget.cost_limit(), Some(12.5));
+        assert_eq!(serde_json::to_value(&budget).unwrap(), legacy);
+
+        let exact = Budget::usd_cents(1999);
+        let json = serde_json::to_value(&exact).unwrap();
+        assert_eq!(json["max_cost"], 19.99);
+        assert_eq!(json["max_cost_minor"], serde_json::json!({ "amount_minor": 1999, "currency": "USD" }));
+        let decoded: Budget = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.max_cost_minor, Some(MoneyAmount::usd_cents(1999)));
+
+        // The exact limit wins over a float that disagrees with it
+        let preferred = Budget { max_cost: Some(20.0), ..exact };
+        assert_eq!(preferred.cost_limit(), Some(19.99));
+        assert!(Intent::builder().kind("test").budget(Budget::usd_cents(1999)).build().unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_budget_converts_to_another_currency() {
+        let rates = crate::StaticRates::new("USD").with_rate("EUR", 1.1);
+        let euros = Budget {
+            max_cost_minor: Some(MoneyAmount::new(1000, "EUR")),
+            max_cost: Some(10.0),
+            currency: "EUR".to_string(),
+            ..Budget::usd(0.0)
+        };
+
+        let dollars = euros.converted("USD", &rates).unwrap();
+        assert_eq!((dollars.currency.as_str(), dollars.cost_limit()), ("USD", Some(11.0)));
+        assert_eq!(dollars.max_cost_minor, Some(MoneyAmount::usd_cents(1100)));
+        assert_eq!(Budget::usd(5.0).converted("USD", &rates).unwrap().max_cost, Some(5.0));
+
+        let francs = Budget { currency: "CHF".to_string(), max_cost: None, ..Budget::usd(0.0) };
+        assert!(matches!(francs.converted("USD", &rates), Err(OrpheonError::IntentInvalid { .. })));
+        let mismatched = Budget { max_cost_minor: Some(MoneyAmount::usd_cents(5)), ..euros };
+        assert!(matches!(mismatched.converted("USD", &rates), Err(OrpheonError::IntentInvalid { .. })));
+    }
+
+    #[test]
+    fn test_content_hash_ignores_metadata_key_order() {
+        let intent = golden_intent();
+        let mut reordered = intent.clone();
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("ticket".to_string(), serde_json::json!(42));
+        metadata.insert("team".to_string(), serde_json::json!("ml"));
+        reordered.metadata = serde_json::Value::Object(metadata);
+        assert_eq!(reordered.content_hash(), intent.content_hash());
+
+        let mut changed = intent.clone();
+        changed.metadata["ticket"] = serde_json::json!(43);
+        assert_ne!(changed.content_hash(), intent.content_hash());
+
+        // Reordering the constraints themselves is a change
+        let mut swapped = intent.clone();
+        swapped.constraints.reverse();
+        assert_ne!(swapped.content_hash(), intent.content_hash());
+    }
+
+    #[test]
+    fn test_ancestor_chain() {
+        let root = Intent::builder().kind("root").build().unwrap();
+        let mid = Intent::builder().kind("mid").parent(root.id).build().unwrap();
+        let leaf = Intent::builder().kind("leaf").parent(mid.id).build().unwrap();
+        let known = [root.clone(), mid.clone(), leaf.clone()];
+        let resolver = |id: Uuid| known.iter().find(|i| i.id == id).cloned();
+
+        assert_eq!(leaf.ancestor_chain(&resolver, 2).unwrap(), vec![mid.id, root.id]);
+        assert!(root.ancestor_chain(&resolver, 0).unwrap().is_empty());
+        let err = leaf.ancestor_chain(&resolver, 1).unwrap_err();
+        assert!(err.to_string().contains("deeper than the limit of 1"), "{}", err);
+
+        // An unknown parent ends the chain
+        let orphan = Intent::builder().kind("orphan").parent(Uuid::new_v4()).build().unwrap();
+        assert_eq!(orphan.ancestor_chain(&resolver, 2).unwrap(), vec![orphan.parent_id.unwrap()]);
+
+        let mut own = leaf.clone();
+        own.parent_id = Some(own.id);
+        assert!(own.ancestor_chain(&resolver, 8).unwrap_err().to_string().contains("is its own parent"));
+
+        // The root claiming the leaf as its parent closes a loop
+        let mut looped = known.clone();
+        looped[0].parent_id = Some(leaf.id);
+        let resolver = |id: Uuid| looped.iter().find(|i| i.id == id).cloned();
+        let err = leaf.ancestor_chain(&resolver, 8).unwrap_err();
+        assert!(err.to_string().contains(&format!("loops back to intent {}", leaf.id)), "{}", err);
+    }
+
+    #[test]
+    fn test_dependency_cycles() {
+        let a = Intent::builder().kind("a").build().unwrap();
+        let b = Intent::builder().kind("b").depends_on(a.id, true).build().unwrap();
+        let c = Intent::builder().kind("c").depends_on(b.id, false).depends_on(Uuid::new_v4(), true).build().unwrap();
+        assert_eq!(c.dependencies().map(|(_, required)| required).collect::<Vec<_>>(), vec![false, true]);
+        let known = [a.clone(), b.clone(), c.clone()];
+        let resolver = |id: Uuid| known.iter().find(|i| i.id == id).cloned();
+        assert!(c.check_dependency_cycles(&resolver).is_ok());
+
+        // A amended to wait for C closes the loop A -> C -> B -> A
+        let amended = Intent { constraints: vec![Constraint::DependsOn { intent_id: c.id, require_success: true }], ..a };
+        let err = amended.check_dependency_cycles(&resolver).unwrap_err();
+        assert!(err.to_string().contains(&format!("loop back to it through intent {}", c.id)), "{}", err);
+
+        let mut own = b.clone();
+        own.constraints.push(Constraint::DependsOn { intent_id: own.id, require_success: false });
+        assert!(own.check_dependency_cycles(&resolver).is_err());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_sign_and_verify() {
+        let key = crypto::SigningKey::from_bytes(&[3u8; 32]);
+        let mut intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: true })
+            .build()
+            .unwrap();
+        intent.sign(&key);
+        let signature = intent.signature.clone().unwrap();
+        assert_eq!(signature.algorithm, crypto::ED25519);
+        assert_eq!(signature.public_key, crypto::hex_encode(key.verifying_key().as_bytes()));
+        assert!(intent.verify_signature().unwrap());
+
+        // Changing a constraint invalidates the signature
+        let mut tampered = intent.clone();
+        tampered.constraints[0] = Constraint::GeoFence { regions: vec!["us-east".to_string()], allowed: true };
+        assert!(!tampered.verify_signature().unwrap());
+
+        // So does claiming another key signed it
+        let other = crypto::SigningKey::from_bytes(&[4u8; 32]);
+        let mut wrong_key = intent.clone();
+        wrong_key.signature.as_mut().unwrap().public_key = crypto::hex_encode(other.verifying_key().as_bytes());
+        assert!(!wrong_key.verify_signature().unwrap());
+
+        let mut malformed = intent.clone();
+        malformed.signature.as_mut().unwrap().signature = "not hex".to_string();
+        assert!(matches!(malformed.verify_signature(), Err(OrpheonError::CryptoError(_))));
+
+        let mut unknown = intent;
+        unknown.signature.as_mut().unwrap().algorithm = "rsa".to_string();
+        assert!(unknown.verify_signature().unwrap_err().to_string().contains("unsupported algorithm 'rsa'"));
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_sign_and_verify_secp256k1() {
+        use k256::ecdsa::signature::Signer as _;
+
+        let key = k256::ecdsa::SigningKey::from_slice(&[5u8; 32]).unwrap();
+        let mut intent = Intent::builder().kind("deploy").build().unwrap();
+        let signature: k256::ecdsa::Signature = key.sign(intent.content_hash().as_bytes());
+        intent.signature = Some(Signature {
+            algorithm: crypto::SECP256K1.to_string(),
+            public_key: crypto::hex_encode(key.verifying_key().to_sec1_bytes()),
+            signature: crypto::hex_encode(signature.to_bytes()),
+            signed_at: Utc::now(),
+        });
+        assert!(intent.verify_signature().unwrap());
+
+        let mut tampered = intent.clone();
+        tampered.kind = "destroy".to_string();
+        assert!(!tampered.verify_signature().unwrap());
+
+        // A secp256k1 signature claiming to be ed25519 is rejected
+        let mut mislabelled = intent;
+        mislabelled.signature.as_mut().unwrap().algorithm = crypto::ED25519.to_string();
+        assert!(matches!(mislabelled.verify_signature(), Err(OrpheonError::CryptoError(_))));
+    }
+
+    #[test]
+    fn test_validate_reports_constraint_conflicts() {
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::GeoFence { regions: vec!["us-east".to_string()], allowed: false })
+            .constraint(Constraint::GeoFence { regions: vec!["us-east".to_string()], allowed: true })
+            .build()
+            .unwrap();
+
+        match intent.validate() {
+            Err(OrpheonError::ConstraintConflict { conflicts, .. }) => assert_eq!(conflicts.len(), 1),
+            other => panic!("expected a constraint conflict, got {:?}", other),
+        }
     }
 }
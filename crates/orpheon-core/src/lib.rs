@@ -9,24 +9,62 @@
 //! - [`OrpheonError`] - Protocol error types
 
 pub mod artifact;
+#[cfg(feature = "ed25519")]
+pub mod bundle;
+pub mod canonical;
+#[cfg(feature = "cbor")]
+pub mod codec;
+pub mod condition;
+pub mod conflict;
+pub mod constraint;
+pub mod context;
+pub mod crypto;
+pub mod diff;
 pub mod error;
+pub mod expression;
 pub mod intent;
+pub mod money;
 pub mod plan;
+pub mod pointer;
+pub mod sel;
+pub mod summary;
 pub mod types;
+pub mod validation;
 
 // Re-exports for convenience
-pub use artifact::{ExecutionArtifact, ExecutionEvent, Outcome};
+pub use artifact::{
+    ConstraintReport, ExecutionArtifact, ExecutionEvent, Outcome, OutcomeSeverity, WeightBy, DEFAULT_MAX_EVENT_DATA_BYTES,
+    MERKLE_VERSION,
+};
+#[cfg(feature = "ed25519")]
+pub use bundle::ArtifactBundle;
+pub use condition::{ConditionError, StateExpr};
+pub use conflict::ConstraintConflict;
+pub use constraint::{
+    ConstraintOutcome, ConstraintRegistry, ConstraintResult, CustomConstraintValidator, EvaluationContext, Evaluator,
+};
+pub use context::{BindingError, ExecutionContext};
+#[cfg(feature = "ed25519")]
+pub use crypto::NodeKey;
+pub use diff::IntentDiff;
 pub use error::{OrpheonError, Result};
-pub use intent::{Budget, Constraint, Intent, IntentBuilder, Preference, Signature, TimeWindow};
+pub use expression::parse_constraint;
+pub use intent::{
+    Budget, Constraint, DailyWindow, Intent, IntentBuilder, Preference, Signature, TimeWindow, ValidationMode,
+};
+pub use money::{CurrencyConverter, MoneyAmount, StaticRates};
 pub use plan::{Plan, PlanningStrategy, Step};
+pub use summary::{ArtifactSummary, IntentSummary, PlanSummary};
 pub use types::*;
+pub use validation::{finite_or_err, FieldError, IntentLimits};
 
 /// Prelude module for common imports
 pub mod prelude {
-    pub use crate::artifact::{ExecutionArtifact, ExecutionEvent, Outcome};
+    pub use crate::artifact::{ExecutionArtifact, ExecutionEvent, Outcome, OutcomeSeverity};
     pub use crate::error::{OrpheonError, Result};
     pub use crate::intent::{
-        Budget, Constraint, Intent, IntentBuilder, Preference, Signature, TimeWindow,
+        Budget, Constraint, DailyWindow, Intent, IntentBuilder, Preference, Signature, TimeWindow, ValidationMode,
     };
+    pub use crate::money::MoneyAmount;
     pub use crate::plan::{Plan, PlanningStrategy, Step};
 }
@@ -0,0 +1,264 @@
+//! Exact monetary amounts in integer minor units.
+//!
+//! Costs are carried as `f64` throughout the protocol, which rounds on
+//! every addition. A [`MoneyAmount`] sits alongside those floats where a
+//! caller wants totals and comparisons to be exact; code that finds one
+//! present prefers it over the float.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{OrpheonError, Result};
+
+/// Currencies with no minor unit.
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "VND", "VUV", "XAF", "XOF",
+    "XPF",
+];
+
+/// Currencies with a minor unit of a thousandth.
+const THREE_DECIMAL_CURRENCIES: &[&str] = &["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+/// Number of decimal places in a minor unit of `currency`, 2 unless
+/// ISO 4217 says otherwise.
+pub fn minor_unit_exponent(currency: &str) -> u32 {
+    if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        0
+    } else if THREE_DECIMAL_CURRENCIES.contains(&currency) {
+        3
+    } else {
+        2
+    }
+}
+
+/// A non-negative amount of money, as a whole number of minor units
+/// (e.g. cents) of its currency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MoneyAmount {
+    /// The amount in minor units of `currency`.
+    pub amount_minor: u64,
+
+    /// ISO 4217 currency code (e.g., "USD").
+    pub currency: String,
+}
+
+impl MoneyAmount {
+    /// An amount of `amount_minor` minor units of `currency`.
+    pub fn new(amount_minor: u64, currency: impl Into<String>) -> Self {
+        Self { amount_minor, currency: currency.into() }
+    }
+
+    /// An amount in US cents.
+    pub fn usd_cents(cents: u64) -> Self {
+        Self::new(cents, "USD")
+    }
+
+    /// Nothing, in `currency`.
+    pub fn zero(currency: impl Into<String>) -> Self {
+        Self::new(0, currency)
+    }
+
+    /// `amount` major units of `currency`, rounded to the nearest minor
+    /// unit; `None` if it is negative, not finite or too large.
+    pub fn from_major(amount: f64, currency: impl Into<String>) -> Option<Self> {
+        let currency = currency.into();
+        let minor = (amount * Self::scale(&currency)).round();
+        if !(minor.is_finite() && minor >= 0.0 && minor < u64::MAX as f64) {
+            return None;
+        }
+        Some(Self::new(minor as u64, currency))
+    }
+
+    /// The amount in major units, e.g. dollars, for the float fields it
+    /// sits beside.
+    pub fn to_major(&self) -> f64 {
+        self.amount_minor as f64 / Self::scale(&self.currency)
+    }
+
+    /// `self + other`; `None` on overflow or if the currencies differ.
+    pub fn checked_add(&self, other: &MoneyAmount) -> Option<Self> {
+        self.same_currency(other)?;
+        Some(Self::new(self.amount_minor.checked_add(other.amount_minor)?, self.currency.clone()))
+    }
+
+    /// `self - other`; `None` if it would be negative or the currencies
+    /// differ.
+    pub fn checked_sub(&self, other: &MoneyAmount) -> Option<Self> {
+        self.same_currency(other)?;
+        Some(Self::new(self.amount_minor.checked_sub(other.amount_minor)?, self.currency.clone()))
+    }
+
+    /// `self * factor`; `None` on overflow.
+    pub fn checked_mul(&self, factor: u64) -> Option<Self> {
+        Some(Self::new(self.amount_minor.checked_mul(factor)?, self.currency.clone()))
+    }
+
+    /// Total of `amounts` in `currency`; `None` on overflow or if any is
+    /// in another currency.
+    pub fn checked_sum<'a>(
+        currency: impl Into<String>,
+        amounts: impl IntoIterator<Item = &'a MoneyAmount>,
+    ) -> Option<Self> {
+        amounts.into_iter().try_fold(Self::zero(currency), |total, amount| total.checked_add(amount))
+    }
+
+    fn same_currency(&self, other: &MoneyAmount) -> Option<()> {
+        (self.currency == other.currency).then_some(())
+    }
+
+    fn scale(currency: &str) -> f64 {
+        10f64.powi(minor_unit_exponent(currency) as i32)
+    }
+}
+
+impl fmt::Display for MoneyAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exponent = minor_unit_exponent(&self.currency);
+        if exponent == 0 {
+            return write!(f, "{} {}", self.amount_minor, self.currency);
+        }
+        let scale = 10u64.pow(exponent);
+        write!(
+            f,
+            "{}.{:0width$} {}",
+            self.amount_minor / scale,
+            self.amount_minor % scale,
+            self.currency,
+            width = exponent as usize
+        )
+    }
+}
+
+/// Converts amounts of money between currencies.
+///
+/// Budgets carry their own currency while action costs are in the node's
+/// base currency; a converter brings the two together before they are
+/// compared.
+pub trait CurrencyConverter: Send + Sync {
+    /// `amount` of `from` in `to`. Fails with
+    /// [`OrpheonError::IntentInvalid`] for a currency it cannot convert.
+    fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64>;
+}
+
+/// A [`CurrencyConverter`] with fixed exchange rates against a base
+/// currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StaticRates {
+    /// Currency the rates are quoted against.
+    pub base: String,
+
+    /// Value of one unit of each currency in `base`.
+    #[serde(default)]
+    pub rates: HashMap<String, f64>,
+}
+
+impl StaticRates {
+    /// Rates against `base`, converting nothing but `base` itself until
+    /// rates are added.
+    pub fn new(base: impl Into<String>) -> Self {
+        Self { base: base.into(), rates: HashMap::new() }
+    }
+
+    /// One unit of `currency` is worth `rate` units of the base currency.
+    pub fn with_rate(mut self, currency: impl Into<String>, rate: f64) -> Self {
+        self.rates.insert(currency.into(), rate);
+        self
+    }
+
+    /// Value of one unit of `currency` in the base currency.
+    fn rate(&self, currency: &str) -> Result<f64> {
+        if currency == self.base {
+            return Ok(1.0);
+        }
+        self.rates
+            .get(currency)
+            .copied()
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+            .ok_or_else(|| OrpheonError::IntentInvalid {
+                intent_id: None,
+                message: format!("No exchange rate from {} to {}", currency, self.base),
+            })
+    }
+}
+
+impl Default for StaticRates {
+    fn default() -> Self {
+        Self::new("USD")
+    }
+}
+
+impl CurrencyConverter for StaticRates {
+    fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64> {
+        if from == to {
+            return Ok(amount);
+        }
+        Ok(amount * self.rate(from)? / self.rate(to)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_major_unit_conversion() {
+        assert_eq!(MoneyAmount::from_major(12.3456, "USD"), Some(MoneyAmount::usd_cents(1235)));
+        assert_eq!(MoneyAmount::from_major(0.1 + 0.2, "USD"), Some(MoneyAmount::usd_cents(30)));
+        assert_eq!(MoneyAmount::from_major(1500.4, "JPY"), Some(MoneyAmount::new(1500, "JPY")));
+        assert_eq!(MoneyAmount::from_major(1.2346, "KWD"), Some(MoneyAmount::new(1235, "KWD")));
+        assert_eq!(MoneyAmount::from_major(-0.5, "USD"), None);
+        assert_eq!(MoneyAmount::from_major(f64::NAN, "USD"), None);
+        assert_eq!(MoneyAmount::from_major(1e30, "USD"), None);
+
+        assert_eq!(MoneyAmount::usd_cents(1999).to_major(), 19.99);
+        assert_eq!(MoneyAmount::usd_cents(1999).to_string(), "19.99 USD");
+        assert_eq!(MoneyAmount::usd_cents(5).to_string(), "0.05 USD");
+        assert_eq!(MoneyAmount::new(7, "JPY").to_string(), "7 JPY");
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = MoneyAmount::usd_cents(150);
+        let b = MoneyAmount::usd_cents(75);
+
+        assert_eq!(a.checked_add(&b), Some(MoneyAmount::usd_cents(225)));
+        assert_eq!(a.checked_sub(&b), Some(MoneyAmount::usd_cents(75)));
+        assert_eq!(b.checked_sub(&a), None);
+        assert_eq!(a.checked_mul(3), Some(MoneyAmount::usd_cents(450)));
+        assert_eq!(MoneyAmount::usd_cents(u64::MAX).checked_add(&b), None);
+        assert_eq!(a.checked_mul(u64::MAX), None);
+
+        let euros = MoneyAmount::new(75, "EUR");
+        assert_eq!(a.checked_add(&euros), None);
+        assert_eq!(MoneyAmount::checked_sum("USD", [&a, &b, &b]), Some(MoneyAmount::usd_cents(300)));
+        assert_eq!(MoneyAmount::checked_sum("USD", [&a, &euros]), None);
+        assert_eq!(MoneyAmount::checked_sum("USD", []), Some(MoneyAmount::zero("USD")));
+    }
+
+    #[test]
+    fn test_serializes_minor_units_and_currency() {
+        let json = serde_json::to_value(MoneyAmount::usd_cents(1234)).unwrap();
+        assert_eq!(json, serde_json::json!({ "amount_minor": 1234, "currency": "USD" }));
+
+        let parsed: MoneyAmount = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, MoneyAmount::usd_cents(1234));
+        assert!(serde_json::from_value::<MoneyAmount>(serde_json::json!({ "amount_minor": -1, "currency": "USD" })).is_err());
+    }
+
+    #[test]
+    fn test_static_rates_convert_through_the_base() {
+        let rates = StaticRates::default().with_rate("EUR", 1.1).with_rate("GBP", 1.25);
+
+        assert_eq!(rates.convert(10.0, "EUR", "USD").unwrap(), 11.0);
+        assert_eq!(rates.convert(11.0, "USD", "EUR").unwrap(), 10.0);
+        assert!((rates.convert(10.0, "GBP", "EUR").unwrap() - 12.5 / 1.1).abs() < 1e-9);
+        assert_eq!(rates.convert(3.0, "CHF", "CHF").unwrap(), 3.0);
+
+        let err = rates.convert(1.0, "CHF", "USD").unwrap_err();
+        assert!(matches!(err, OrpheonError::IntentInvalid { .. }));
+        assert_eq!(err.to_string(), "Intent validation failed: No exchange rate from CHF to USD");
+    }
+}
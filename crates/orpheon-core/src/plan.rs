@@ -4,12 +4,15 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::crypto::hex_encode;
 use crate::error::{OrpheonError, Result};
 
 /// A Plan is a DAG of steps to satisfy an Intent.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Plan {
     /// Unique identifier for this plan.
     pub id: Uuid,
@@ -26,6 +29,11 @@ pub struct Plan {
     /// Estimated total latency in milliseconds.
     pub estimated_latency_ms: u64,
 
+    /// Estimated total work in milliseconds: the sum of all step durations,
+    /// however many of them run at once.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub estimated_work_ms: u64,
+
     /// Confidence score (0.0 to 1.0) based on historical success.
     pub confidence_score: f32,
 
@@ -40,10 +48,20 @@ pub struct Plan {
 
     /// Version for optimistic concurrency.
     pub version: u32,
+
+    /// Planner-provided metadata (e.g. violated soft constraints).
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+
+    /// The segment this plan continues, when an over-long plan was split
+    /// (see [`Plan::split`]). Its steps run once that segment is done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuation_of: Option<Uuid>,
 }
 
 /// A single step in an execution plan.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Step {
     /// Unique identifier for this step.
     pub id: Uuid,
@@ -77,10 +95,42 @@ pub struct Step {
 
     /// Timeout in milliseconds (None = no timeout).
     pub timeout_ms: Option<u64>,
+
+    /// Operator-facing description, e.g. "Provision 8×H100 in us-east-1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Searchable tags, e.g. `gpu` or `network`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Whether the plan carries on if this step fails; the intent then ends
+    /// partially complete instead of failed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+
+    /// Condition under which the step is skipped, checked just before it
+    /// would run; see [`crate::condition`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_if: Option<String>,
+
+    /// Facts that must hold before the step runs, so a plan can be checked
+    /// without the catalog that produced it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preconditions: Vec<String>,
+
+    /// Facts that hold once the step has run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub produces: Vec<String>,
+
+    /// Notes about how the step was planned, e.g. `duration_source`.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub metadata: serde_json::Value,
 }
 
 /// Compensation action for rollback.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CompensationAction {
     /// The action to perform for compensation.
     pub action: String,
@@ -91,6 +141,7 @@ pub struct CompensationAction {
 
 /// Strategy used to generate a plan.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum PlanningStrategy {
     /// Rule-based deterministic planning.
@@ -110,6 +161,19 @@ pub enum PlanningStrategy {
     Hybrid,
 }
 
+impl PlanningStrategy {
+    /// The strategy's name as it appears on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanningStrategy::Deterministic => "deterministic",
+            PlanningStrategy::Heuristic => "heuristic",
+            PlanningStrategy::Generative => "generative",
+            PlanningStrategy::MonteCarlo => "monte_carlo",
+            PlanningStrategy::Hybrid => "hybrid",
+        }
+    }
+}
+
 impl Plan {
     /// Create a new plan for an intent.
     pub fn new(intent_id: Uuid, strategy: PlanningStrategy) -> Self {
@@ -119,17 +183,61 @@ impl Plan {
             steps: Vec::new(),
             estimated_cost: 0.0,
             estimated_latency_ms: 0,
+            estimated_work_ms: 0,
             confidence_score: 0.0,
             strategy,
             created_at: Utc::now(),
             expires_at: None,
             version: 1,
+            metadata: serde_json::Value::Null,
+            continuation_of: None,
+        }
+    }
+
+    /// Split the plan into segments of at most `max_steps` steps each, in
+    /// step order.
+    ///
+    /// The first segment keeps the plan's ID; each later one gets a new ID
+    /// and names the one before it in `continuation_of`. Steps may depend
+    /// on steps of earlier segments. Estimates are per segment.
+    pub fn split(self, max_steps: usize) -> Vec<Plan> {
+        if self.steps.len() <= max_steps || max_steps == 0 {
+            return vec![self];
+        }
+
+        let mut segments: Vec<Plan> = Vec::new();
+        for chunk in self.steps.chunks(max_steps) {
+            let previous = segments.last().map(|s| s.id);
+            segments.push(Plan {
+                id: if previous.is_some() { Uuid::new_v4() } else { self.id },
+                steps: chunk.to_vec(),
+                estimated_cost: chunk.iter().map(|s| s.estimated_cost).sum(),
+                estimated_latency_ms: chunk.iter().map(|s| s.estimated_duration_ms).sum(),
+                estimated_work_ms: chunk.iter().map(|s| s.estimated_duration_ms).sum(),
+                continuation_of: previous.or(self.continuation_of),
+                ..self.clone()
+            });
         }
+        segments
+    }
+
+    /// Append the segments continuing this one, undoing
+    /// [`split`](Self::split): the result keeps this plan's ID and sums the
+    /// estimates.
+    pub fn joined(mut self, continuations: impl IntoIterator<Item = Plan>) -> Plan {
+        for segment in continuations {
+            self.estimated_cost += segment.estimated_cost;
+            self.estimated_latency_ms += segment.estimated_latency_ms;
+            self.estimated_work_ms += segment.estimated_work_ms;
+            self.steps.extend(segment.steps);
+        }
+        self
     }
 
     /// Add a step to the plan.
     pub fn add_step(&mut self, step: Step) {
         self.estimated_cost += step.estimated_cost;
+        self.estimated_work_ms += step.estimated_duration_ms;
         // Only add duration if no dependencies (critical path estimation is simplified)
         if step.dependencies.is_empty() {
             self.estimated_latency_ms += step.estimated_duration_ms;
@@ -216,10 +324,8 @@ impl Plan {
         }
 
         for step in &self.steps {
-            if !visited.contains(&step.id) {
-                if dfs(step.id, &adj, &mut visited, &mut rec_stack) {
-                    return true;
-                }
+            if !visited.contains(&step.id) && dfs(step.id, &adj, &mut visited, &mut rec_stack) {
+                return true;
             }
         }
 
@@ -247,9 +353,95 @@ impl Plan {
             .collect()
     }
 
+    /// Estimated time to finish the steps not in `done`, in milliseconds:
+    /// the longest chain of their estimated durations through the
+    /// dependency graph. Dependencies outside the plan count as done.
+    pub fn critical_path_ms(&self, done: &std::collections::HashSet<Uuid>) -> u64 {
+        self.finish_times(done).into_values().map(|(finish, _)| finish).max().unwrap_or(0)
+    }
+
+    /// The longest chain of estimated durations through the dependency
+    /// graph, from an entry point to an exit point, and its length in
+    /// milliseconds: how long the plan takes with every independent step
+    /// running at once. Ties go to the step listed first.
+    pub fn critical_path(&self) -> (Vec<Uuid>, u64) {
+        let finish = self.finish_times(&std::collections::HashSet::new());
+        let mut last: Option<(Uuid, u64)> = None;
+        for step in &self.steps {
+            if let Some(&(time, _)) = finish.get(&step.id) {
+                if last.is_none_or(|(_, longest)| time > longest) {
+                    last = Some((step.id, time));
+                }
+            }
+        }
+
+        let Some((end, length)) = last else {
+            return (Vec::new(), 0);
+        };
+        let mut path = vec![end];
+        while let Some(&(_, Some(previous))) = finish.get(path.last().unwrap()) {
+            path.push(previous);
+        }
+        path.reverse();
+        (path, length)
+    }
+
+    /// The steps grouped into waves that can run in parallel: the first
+    /// holds the entry points, and each later one the steps whose
+    /// dependencies are all in earlier waves. Steps keep their plan order
+    /// within a wave, and dependencies outside the plan count as done.
+    pub fn parallel_groups(&self) -> Vec<Vec<Uuid>> {
+        use std::collections::HashMap;
+
+        let mut waves: HashMap<Uuid, usize> = HashMap::new();
+        for step in self.topological_sort() {
+            let wave = step.dependencies.iter().filter_map(|d| waves.get(d)).max().map_or(0, |w| w + 1);
+            waves.insert(step.id, wave);
+        }
+
+        let mut groups: Vec<Vec<Uuid>> = Vec::new();
+        for step in &self.steps {
+            if let Some(&wave) = waves.get(&step.id) {
+                if groups.len() <= wave {
+                    groups.resize_with(wave + 1, Vec::new);
+                }
+                groups[wave].push(step.id);
+            }
+        }
+        groups
+    }
+
+    /// When each step would finish, counting steps in `done` as taking no
+    /// time, with the dependency it last waited for.
+    fn finish_times(&self, done: &std::collections::HashSet<Uuid>) -> std::collections::HashMap<Uuid, (u64, Option<Uuid>)> {
+        use std::collections::HashMap;
+
+        let mut finish: HashMap<Uuid, (u64, Option<Uuid>)> = HashMap::new();
+        for step in self.topological_sort() {
+            let own = if done.contains(&step.id) { 0 } else { step.estimated_duration_ms };
+            let mut start = (0, None);
+            for dep in &step.dependencies {
+                if let Some(&(time, _)) = finish.get(dep) {
+                    if start.1.is_none() || time > start.0 {
+                        start = (time, Some(*dep));
+                    }
+                }
+            }
+            finish.insert(step.id, (start.0 + own, start.1));
+        }
+        finish
+    }
+
+    /// Hash of the whole plan (hex-encoded SHA-256), linking an agreement
+    /// about a plan to the plan later executed.
+    pub fn content_hash(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        hex_encode(Sha256::digest(json.as_bytes()))
+    }
+
     /// Topologically sort the steps.
     pub fn topological_sort(&self) -> Vec<&Step> {
-        use std::collections::{HashMap, HashSet, VecDeque};
+        use std::collections::{HashMap, VecDeque};
 
         let mut in_degree: HashMap<Uuid, usize> = HashMap::new();
         let mut dependents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
@@ -294,6 +486,10 @@ impl Plan {
     }
 }
 
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
 impl Step {
     /// Create a new step.
     pub fn new(name: impl Into<String>, action: impl Into<String>) -> Self {
@@ -309,6 +505,13 @@ impl Step {
             retryable: true,
             max_retries: 3,
             timeout_ms: None,
+            description: None,
+            tags: Vec::new(),
+            optional: false,
+            skip_if: None,
+            preconditions: Vec::new(),
+            produces: Vec::new(),
+            metadata: serde_json::Value::Null,
         }
     }
 
@@ -356,6 +559,47 @@ impl Step {
         self.retryable = false;
         self
     }
+
+    /// Set the description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Add a tag.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// Let the plan carry on if this step fails.
+    pub fn as_optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Skip the step when `condition` holds.
+    pub fn with_skip_if(mut self, condition: impl Into<String>) -> Self {
+        self.skip_if = Some(condition.into());
+        self
+    }
+
+    /// Add a fact that must hold before the step runs.
+    pub fn requires(mut self, fact: impl Into<String>) -> Self {
+        self.preconditions.push(fact.into());
+        self
+    }
+
+    /// Add a fact that holds once the step has run.
+    pub fn produces(mut self, fact: impl Into<String>) -> Self {
+        self.produces.push(fact.into());
+        self
+    }
+
+    /// Whether the step carries a tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
 }
 
 #[cfg(test)]
@@ -381,6 +625,25 @@ mod tests {
         assert_eq!(step.action, "allocate");
         assert_eq!(step.estimated_duration_ms, 1000);
         assert_eq!(step.timeout_ms, Some(30000));
+
+        let step = step.with_description("Allocate 8 GPUs").with_tag("gpu");
+        assert_eq!(step.description.as_deref(), Some("Allocate 8 GPUs"));
+        assert!(step.has_tag("gpu"));
+        assert!(!step.has_tag("network"));
+    }
+
+    #[test]
+    fn test_step_facts() {
+        let step = Step::new("Deploy", "deploy").requires("provisioned").requires("configured").produces("deployed");
+        assert_eq!(step.preconditions, vec!["provisioned", "configured"]);
+        assert_eq!(step.produces, vec!["deployed"]);
+
+        // Steps from before facts were embedded still load
+        let mut json = serde_json::to_value(Step::new("Deploy", "deploy")).unwrap();
+        assert!(json.get("preconditions").is_none() && json.get("produces").is_none());
+        json["id"] = serde_json::json!(Uuid::new_v4());
+        let old: Step = serde_json::from_value(json).unwrap();
+        assert!(old.preconditions.is_empty() && old.produces.is_empty());
     }
 
     #[test]
@@ -440,4 +703,100 @@ mod tests {
         assert_eq!(sorted[0].id, step1_id);
         assert_eq!(sorted[1].id, step2_id);
     }
+
+    #[test]
+    fn test_critical_path_skips_done_steps() {
+        use std::collections::HashSet;
+
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic);
+        let fetch = Step::new("fetch", "fetch").with_duration(100);
+        let build = Step::new("build", "build").with_duration(300).depends_on(fetch.id);
+        let lint = Step::new("lint", "lint").with_duration(50).depends_on(fetch.id);
+        let ship = Step::new("ship", "ship").with_duration(200).depends_on(build.id).depends_on(lint.id);
+        let (fetch_id, build_id) = (fetch.id, build.id);
+        for step in [fetch, build, lint, ship] {
+            plan.add_step(step);
+        }
+
+        assert_eq!(plan.critical_path_ms(&HashSet::new()), 600);
+        assert_eq!(plan.critical_path_ms(&HashSet::from([fetch_id])), 500);
+        assert_eq!(plan.critical_path_ms(&HashSet::from([fetch_id, build_id])), 250);
+        assert_eq!(Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic).critical_path_ms(&HashSet::new()), 0);
+    }
+
+    #[test]
+    fn test_critical_path_and_waves_of_fan_out_fan_in() {
+        // fetch fans out to three builds, which fan back in to ship
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic);
+        let fetch = Step::new("fetch", "fetch").with_duration(100);
+        let amd64 = Step::new("build amd64", "build").with_duration(400).depends_on(fetch.id);
+        let arm64 = Step::new("build arm64", "build").with_duration(700).depends_on(fetch.id);
+        let docs = Step::new("build docs", "build").with_duration(200).depends_on(fetch.id);
+        let ship =
+            Step::new("ship", "ship").with_duration(50).depends_on(amd64.id).depends_on(arm64.id).depends_on(docs.id);
+        let ids = [fetch.id, amd64.id, arm64.id, docs.id, ship.id];
+        for step in [fetch, amd64, arm64, docs, ship] {
+            plan.add_step(step);
+        }
+
+        let (path, length) = plan.critical_path();
+        assert_eq!(path, vec![ids[0], ids[2], ids[4]]);
+        assert_eq!(length, 850);
+        assert_eq!(plan.estimated_work_ms, 1450);
+        assert!(length < plan.estimated_work_ms);
+
+        assert_eq!(plan.parallel_groups(), vec![vec![ids[0]], vec![ids[1], ids[2], ids[3]], vec![ids[4]]]);
+
+        let empty = Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic);
+        assert_eq!(empty.critical_path(), (Vec::new(), 0));
+        assert!(empty.parallel_groups().is_empty());
+    }
+
+    #[test]
+    fn test_split_links_segments_and_joins_back() {
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        let mut previous: Option<Uuid> = None;
+        for i in 0..12 {
+            let mut step = Step::new(format!("step {}", i), "act").with_cost(1.0).with_duration(10);
+            if let Some(id) = previous {
+                step = step.depends_on(id);
+            }
+            previous = Some(step.id);
+            plan.steps.push(step);
+        }
+        plan.estimated_cost = 12.0;
+        plan.estimated_latency_ms = 120;
+        let original_ids: Vec<Uuid> = plan.steps.iter().map(|s| s.id).collect();
+
+        let segments = plan.clone().split(5);
+        assert_eq!(segments.iter().map(|s| s.steps.len()).collect::<Vec<_>>(), vec![5, 5, 2]);
+        assert_eq!(segments[0].id, plan.id);
+        assert_eq!(segments[0].continuation_of, None);
+        assert_eq!(segments[1].continuation_of, Some(segments[0].id));
+        assert_eq!(segments[2].continuation_of, Some(segments[1].id));
+        assert_eq!(segments[2].estimated_cost, 2.0);
+
+        let mut segments = segments.into_iter();
+        let joined = segments.next().unwrap().joined(segments);
+        assert_eq!(joined.id, plan.id);
+        assert_eq!(joined.steps.iter().map(|s| s.id).collect::<Vec<_>>(), original_ids);
+        assert_eq!(joined.estimated_cost, 12.0);
+        assert_eq!(joined.estimated_latency_ms, 120);
+
+        // Short plans are left whole
+        assert_eq!(plan.split(12).len(), 1);
+    }
+
+    #[test]
+    fn test_content_hash_survives_serialization_and_tracks_changes() {
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(0.1).with_duration(250));
+        let hash = plan.content_hash();
+
+        let round_trip: Plan = serde_json::from_str(&serde_json::to_string(&plan).unwrap()).unwrap();
+        assert_eq!(round_trip.content_hash(), hash);
+
+        plan.steps[0].estimated_cost = 0.2;
+        assert_ne!(plan.content_hash(), hash);
+    }
 }
@@ -0,0 +1,126 @@
+//! Typed access to JSON values by [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901),
+//! behind [`Intent::metadata_get`](crate::Intent::metadata_get) and
+//! [`ExecutionEvent::data_get`](crate::ExecutionEvent::data_get).
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{OrpheonError, Result};
+
+/// The value at `pointer` in `root` as a `T`, or `None` if there is none
+/// or it is null.
+pub fn get<T: DeserializeOwned>(root: &Value, pointer: &str) -> Result<Option<T>> {
+    check(pointer)?;
+    match root.pointer(pointer) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => T::deserialize(value)
+            .map(Some)
+            .map_err(|e| error(pointer, format!("holds the wrong type: {}", e))),
+    }
+}
+
+/// Set the value at `pointer` in `root`, creating objects on the way as
+/// needed; null counts as an empty object. Array elements are addressed
+/// by index, or `-` to append.
+pub fn set(root: &mut Value, pointer: &str, value: impl Serialize) -> Result<()> {
+    check(pointer)?;
+    let value = serde_json::to_value(value).map_err(|e| error(pointer, e.to_string()))?;
+    let mut target = root;
+    for token in pointer.split('/').skip(1).map(unescape) {
+        if target.is_null() {
+            *target = Value::Object(Default::default());
+        }
+        let found = kind(target);
+        target = match target {
+            Value::Object(map) => map.entry(token).or_insert(Value::Null),
+            Value::Array(items) => {
+                let index = if token == "-" {
+                    items.push(Value::Null);
+                    Some(items.len() - 1)
+                } else {
+                    token.parse::<usize>().ok().filter(|index| *index < items.len())
+                };
+                match index {
+                    Some(index) => &mut items[index],
+                    None => return Err(error(pointer, format!("has no array element {:?}", token))),
+                }
+            }
+            _ => return Err(error(pointer, format!("goes through a {} at {:?}", found, token))),
+        };
+    }
+    *target = value;
+    Ok(())
+}
+
+fn check(pointer: &str) -> Result<()> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        Ok(())
+    } else {
+        Err(error(pointer, "is not a JSON pointer; it must be empty or start with '/'"))
+    }
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn error(pointer: &str, message: impl std::fmt::Display) -> OrpheonError {
+    OrpheonError::SerializationError(format!("{:?} {}", pointer, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_typed_values() {
+        let root = json!({ "team": "ml", "limits": { "replicas": 3 }, "tags": ["a", "b"], "a/b": { "~x": true }, "gone": null });
+
+        assert_eq!(get::<String>(&root, "/team").unwrap(), Some("ml".to_string()));
+        assert_eq!(get::<u32>(&root, "/limits/replicas").unwrap(), Some(3));
+        assert_eq!(get::<Vec<String>>(&root, "/tags").unwrap(), Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(get::<String>(&root, "/tags/1").unwrap(), Some("b".to_string()));
+        assert_eq!(get::<bool>(&root, "/a~1b/~0x").unwrap(), Some(true));
+        assert_eq!(get::<String>(&root, "/missing/deeper").unwrap(), None);
+        assert_eq!(get::<String>(&root, "/gone").unwrap(), None);
+
+        let err = get::<u32>(&root, "/team").unwrap_err();
+        assert!(matches!(err, OrpheonError::SerializationError(_)));
+        assert!(err.to_string().contains("\"/team\""), "{}", err);
+        assert!(get::<String>(&root, "team").is_err());
+    }
+
+    #[test]
+    fn test_set_creates_objects_on_the_way() {
+        let mut root = Value::Null;
+        set(&mut root, "/labels/env", "prod").unwrap();
+        set(&mut root, "/labels/tier", "web").unwrap();
+        set(&mut root, "/retries", 2).unwrap();
+        assert_eq!(root, json!({ "labels": { "env": "prod", "tier": "web" }, "retries": 2 }));
+
+        set(&mut root, "/tags", ["a"]).unwrap();
+        set(&mut root, "/tags/-", "b").unwrap();
+        set(&mut root, "/tags/0", "z").unwrap();
+        assert_eq!(root["tags"], json!(["z", "b"]));
+        assert!(set(&mut root, "/tags/5", "c").is_err());
+
+        let err = set(&mut root, "/retries/max", 3).unwrap_err();
+        assert!(err.to_string().contains("goes through a number"), "{}", err);
+
+        set(&mut root, "", json!({ "fresh": true })).unwrap();
+        assert_eq!(root, json!({ "fresh": true }));
+    }
+}
@@ -0,0 +1,23 @@
+//! The State Expression Language (SEL).
+//!
+//! SEL is what [`Constraint::StateMatch`](crate::Constraint::StateMatch)
+//! expressions, step conditions and state subscription filters are written
+//! in. It is implemented by [`StateExpr`](crate::StateExpr); see
+//! [`crate::condition`] for the syntax. Paths resolve into whatever JSON
+//! value an expression is evaluated against:
+//!
+//! ```
+//! use orpheon_core::sel;
+//! use serde_json::json;
+//!
+//! let expr = sel::parse("region == 'us-east' && (gpus.free >= 2 || !exclusive)").unwrap();
+//! assert!(expr.eval(&json!({ "region": "us-east", "gpus": { "free": 4 }, "exclusive": true })).unwrap());
+//! assert!(sel::parse("region = 'us-east'").is_err());
+//! ```
+
+pub use crate::condition::{ConditionError, StateExpr as Expr};
+
+/// Parse an SEL expression.
+pub fn parse(text: &str) -> Result<Expr, ConditionError> {
+    Expr::parse(text)
+}
@@ -0,0 +1,289 @@
+//! One-line summaries of intents, plans and artifacts.
+//!
+//! Each summary is a small serializable struct for listings, and its
+//! `Display` is the line logs and CLI tools print, e.g.
+//! `deploy [high]: 2 constraints (+1 soft), 1 preference, budget 10.00 USD / 5000 ms`.
+//! [`Intent`], [`Plan`] and [`ExecutionArtifact`] display as their summary.
+
+use std::fmt;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::{ExecutionArtifact, Outcome};
+use crate::intent::Intent;
+use crate::plan::{Plan, PlanningStrategy};
+use crate::types::Priority;
+
+/// The gist of an [`Intent`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IntentSummary {
+    /// The intent's kind.
+    pub kind: String,
+
+    /// The intent's priority.
+    pub priority: Priority,
+
+    /// Number of hard constraints.
+    pub constraints: usize,
+
+    /// Number of soft constraints.
+    pub soft_constraints: usize,
+
+    /// Number of preferences.
+    pub preferences: usize,
+
+    /// Cost limit, in `currency`.
+    pub max_cost: Option<f64>,
+
+    /// Currency of the cost limit.
+    pub currency: String,
+
+    /// Duration limit in milliseconds.
+    pub max_duration_ms: Option<u64>,
+
+    /// Start of the validity window.
+    pub not_before: Option<DateTime<Utc>>,
+
+    /// End of the validity window.
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+/// The gist of a [`Plan`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PlanSummary {
+    /// How the plan was generated.
+    pub strategy: PlanningStrategy,
+
+    /// Number of steps.
+    pub steps: usize,
+
+    /// Estimated total cost.
+    pub estimated_cost: f64,
+
+    /// Estimated latency in milliseconds.
+    pub estimated_latency_ms: u64,
+
+    /// Confidence in the plan, from 0.0 to 1.0.
+    pub confidence_score: f32,
+}
+
+/// The gist of an [`ExecutionArtifact`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ArtifactSummary {
+    /// How execution ended.
+    pub outcome: Outcome,
+
+    /// Actual total cost.
+    pub actual_cost: f64,
+
+    /// Actual duration in milliseconds.
+    pub actual_duration_ms: u64,
+
+    /// Percentage of finished steps that succeeded.
+    pub success_rate: u8,
+}
+
+impl Intent {
+    /// Summarize the intent; see [`IntentSummary`].
+    pub fn summary(&self) -> IntentSummary {
+        IntentSummary {
+            kind: self.kind.clone(),
+            priority: self.priority,
+            constraints: self.constraints.len(),
+            soft_constraints: self.soft_constraints.len(),
+            preferences: self.preferences.len(),
+            max_cost: self.budget.cost_limit(),
+            currency: self.budget.currency.clone(),
+            max_duration_ms: self.budget.max_duration_ms,
+            not_before: self.validity_window.not_before,
+            not_after: self.validity_window.not_after,
+        }
+    }
+}
+
+impl Plan {
+    /// Summarize the plan; see [`PlanSummary`].
+    pub fn summary(&self) -> PlanSummary {
+        PlanSummary {
+            strategy: self.strategy,
+            steps: self.steps.len(),
+            estimated_cost: self.estimated_cost,
+            estimated_latency_ms: self.estimated_latency_ms,
+            confidence_score: self.confidence_score,
+        }
+    }
+}
+
+impl ExecutionArtifact {
+    /// Summarize the artifact; see [`ArtifactSummary`].
+    pub fn summary(&self) -> ArtifactSummary {
+        ArtifactSummary {
+            outcome: self.outcome.clone(),
+            actual_cost: self.total_cost(),
+            actual_duration_ms: self.actual_duration_ms,
+            success_rate: (self.success_rate() * 100.0).round() as u8,
+        }
+    }
+}
+
+/// `n thing` or `n things`.
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+fn timestamp(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// `kind [priority]: constraints, preferences, budget`, then the validity
+/// window if it has bounds.
+impl fmt::Display for IntentSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.kind, self.priority.as_str(), count(self.constraints, "constraint"))?;
+        if self.soft_constraints > 0 {
+            write!(f, " (+{} soft)", self.soft_constraints)?;
+        }
+        write!(f, ", {}", count(self.preferences, "preference"))?;
+
+        let mut limits = Vec::new();
+        if let Some(max_cost) = self.max_cost {
+            limits.push(format!("{:.2} {}", max_cost, self.currency).trim_end().to_string());
+        }
+        if let Some(max_duration_ms) = self.max_duration_ms {
+            limits.push(format!("{} ms", max_duration_ms));
+        }
+        match limits.is_empty() {
+            true => f.write_str(", no budget")?,
+            false => write!(f, ", budget {}", limits.join(" / "))?,
+        }
+
+        match (&self.not_before, &self.not_after) {
+            (Some(from), Some(until)) => write!(f, ", valid {} to {}", timestamp(from), timestamp(until)),
+            (Some(from), None) => write!(f, ", valid from {}", timestamp(from)),
+            (None, Some(until)) => write!(f, ", valid until {}", timestamp(until)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// `steps (strategy), est. cost, latency, confidence`.
+impl fmt::Display for PlanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}), est. cost {:.2}, {} ms, {:.0}% confidence",
+            count(self.steps, "step"),
+            self.strategy.as_str(),
+            self.estimated_cost,
+            self.estimated_latency_ms,
+            self.confidence_score * 100.0
+        )
+    }
+}
+
+/// `outcome, cost, duration, success rate`.
+impl fmt::Display for ArtifactSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, cost {:.2}, {} ms, {}% of steps succeeded",
+            self.outcome.summary(),
+            self.actual_cost,
+            self.actual_duration_ms,
+            self.success_rate
+        )
+    }
+}
+
+impl fmt::Display for Intent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
+
+impl fmt::Display for ExecutionArtifact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.summary().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::artifact::ExecutionEvent;
+    use crate::intent::{Budget, Constraint, TimeWindow};
+    use crate::plan::Step;
+
+    #[test]
+    fn test_intent_display() {
+        let window = TimeWindow {
+            not_before: None,
+            not_after: Some(Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap()),
+            recurrence: Vec::new(),
+        };
+        let intent = Intent::builder()
+            .kind("deploy")
+            .priority(Priority::High)
+            .resource_limit("total_cost", 5.0)
+            .constraint(Constraint::Provider { node_id: "n1".to_string() })
+            .soft_constraint(Constraint::GeoFence { regions: vec!["eu".to_string()], allowed: true })
+            .minimize("cost", 1.0)
+            .budget(Budget { max_duration_ms: Some(5000), ..Budget::usd(10.0) })
+            .validity_window(window)
+            .build()
+            .unwrap();
+        assert_eq!(
+            intent.to_string(),
+            "deploy [high]: 2 constraints (+1 soft), 1 preference, budget 10.00 USD / 5000 ms, valid until 2026-01-02T03:04:05Z"
+        );
+
+        let open = TimeWindow { not_before: None, not_after: None, recurrence: Vec::new() };
+        let bare = Intent::builder().kind("ping").validity_window(open).build().unwrap();
+        assert_eq!(bare.to_string(), "ping [normal]: 0 constraints, 0 preferences, no budget");
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("a", "provision"));
+        plan.add_step(Step::new("b", "configure"));
+        plan.estimated_cost = 12.5;
+        plan.estimated_latency_ms = 3000;
+        plan.confidence_score = 0.85;
+        assert_eq!(plan.to_string(), "2 steps (heuristic), est. cost 12.50, 3000 ms, 85% confidence");
+    }
+
+    #[test]
+    fn test_artifact_display() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        let outcome = Outcome::Failure { reason: "quota exceeded".to_string(), compensated: true };
+        let mut artifact = ExecutionArtifact::new(intent, plan, outcome);
+        let (ok, failed) = (Uuid::new_v4(), Uuid::new_v4());
+        artifact.add_event(ExecutionEvent::step_completed(ok, 100));
+        artifact.add_event(ExecutionEvent::step_completed(ok, 100));
+        artifact.add_event(ExecutionEvent::step_completed(ok, 100));
+        artifact.add_event(ExecutionEvent::step_failed(failed, "boom"));
+        artifact.actual_cost = 3.7;
+        artifact.actual_duration_ms = 1200;
+        assert_eq!(artifact.to_string(), "failure (quota exceeded, compensated), cost 3.70, 1200 ms, 75% of steps succeeded");
+
+        let summary = artifact.summary();
+        assert_eq!(summary.success_rate, 75);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(serde_json::from_value::<ArtifactSummary>(json).unwrap(), summary);
+    }
+}
@@ -2,6 +2,42 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Request header counting how many times an intent has been forwarded
+/// between nodes; used to stop forwarding loops.
+pub const FORWARD_HOPS_HEADER: &str = "x-orpheon-forward-hops";
+
+/// Request header carrying the caller's API key.
+pub const API_KEY_HEADER: &str = "x-orpheon-api-key";
+
+/// Request header naming the client library, e.g.
+/// `orpheon-sdk/0.1.0 (linux; x86_64)`.
+pub const CLIENT_HEADER: &str = "x-orpheon-client";
+
+/// Request header naming what submitted an intent, e.g. `ci` or
+/// `dashboard`, as set by the application.
+pub const SOURCE_HEADER: &str = "x-orpheon-source";
+
+/// Where a submitted intent came from, as recorded by the node that
+/// received it. It is not part of the intent, so not of its content hash.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The [`SOURCE_HEADER`] the submitter sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// The [`CLIENT_HEADER`] the submitter sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+
+    /// The submitter's `User-Agent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+
+    /// Address the submission came from, as the node saw it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_addr: Option<String>,
+}
+
 /// Status of an Intent in the system.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -14,14 +50,20 @@ pub enum IntentStatus {
     Negotiating,
     /// Plan has been accepted and is executing.
     Executing,
+    /// Execution is on hold between steps until the client resumes it.
+    Paused,
     /// Execution is being compensated due to failure.
     Compensating,
     /// Intent has been successfully fulfilled.
     Complete,
+    /// Execution finished but some optional steps failed.
+    PartiallyComplete,
     /// Intent has failed and cannot be recovered.
     Failed,
     /// Intent was cancelled by the client.
     Cancelled,
+    /// Intent's validity window closed before it started executing.
+    Expired,
 }
 
 impl IntentStatus {
@@ -29,10 +71,22 @@ impl IntentStatus {
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            IntentStatus::Complete | IntentStatus::Failed | IntentStatus::Cancelled
+            IntentStatus::Complete
+                | IntentStatus::PartiallyComplete
+                | IntentStatus::Failed
+                | IntentStatus::Cancelled
+                | IntentStatus::Expired
         )
     }
 
+    /// Whether an intent in this status may move to `next`.
+    ///
+    /// Terminal statuses are final; the only move out of one is to itself,
+    /// which changes nothing.
+    pub fn can_transition_to(&self, next: IntentStatus) -> bool {
+        !self.is_terminal() || *self == next
+    }
+
     /// Returns true if the intent is currently being processed.
     pub fn is_active(&self) -> bool {
         matches!(
@@ -41,13 +95,106 @@ impl IntentStatus {
                 | IntentStatus::Planning
                 | IntentStatus::Negotiating
                 | IntentStatus::Executing
+                | IntentStatus::Paused
                 | IntentStatus::Compensating
         )
     }
+
+    /// Whether the intent has yet to start executing, so it expires if
+    /// its validity window closes.
+    pub fn awaits_execution(&self) -> bool {
+        matches!(self, IntentStatus::Received | IntentStatus::Planning | IntentStatus::Negotiating)
+    }
+
+    /// The status name used on the wire, e.g. `partially_complete`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntentStatus::Received => "received",
+            IntentStatus::Planning => "planning",
+            IntentStatus::Negotiating => "negotiating",
+            IntentStatus::Executing => "executing",
+            IntentStatus::Paused => "paused",
+            IntentStatus::Compensating => "compensating",
+            IntentStatus::Complete => "complete",
+            IntentStatus::PartiallyComplete => "partially_complete",
+            IntentStatus::Failed => "failed",
+            IntentStatus::Cancelled => "cancelled",
+            IntentStatus::Expired => "expired",
+        }
+    }
 }
 
-/// Priority level for an intent.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+/// Why a node closed a WebSocket stream.
+///
+/// Sent as the reason of the close frame, with [`close_code`](Self::close_code)
+/// as its code, so clients can tell a finished stream from one they should
+/// not reopen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsCloseReason {
+    /// The intent or negotiation does not exist.
+    NotFound,
+    /// The caller may not watch this stream.
+    Unauthorized,
+    /// There is nothing more to send, e.g. the intent finished.
+    Terminal,
+    /// The node is shutting down.
+    ServerShutdown,
+    /// The client broke the stream's protocol.
+    ProtocolError,
+    /// The caller opened too many streams.
+    RateLimited,
+}
+
+impl WsCloseReason {
+    /// Every reason, for looking one up by name.
+    pub const ALL: [WsCloseReason; 6] = [
+        WsCloseReason::NotFound,
+        WsCloseReason::Unauthorized,
+        WsCloseReason::Terminal,
+        WsCloseReason::ServerShutdown,
+        WsCloseReason::ProtocolError,
+        WsCloseReason::RateLimited,
+    ];
+
+    /// The reason name used on the wire, e.g. `server_shutdown`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WsCloseReason::NotFound => "not_found",
+            WsCloseReason::Unauthorized => "unauthorized",
+            WsCloseReason::Terminal => "terminal",
+            WsCloseReason::ServerShutdown => "server_shutdown",
+            WsCloseReason::ProtocolError => "protocol_error",
+            WsCloseReason::RateLimited => "rate_limited",
+        }
+    }
+
+    /// The reason named `name`, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.as_str() == name)
+    }
+
+    /// WebSocket close code sent with the reason.
+    pub fn close_code(&self) -> u16 {
+        match self {
+            WsCloseReason::Terminal => 1000,
+            WsCloseReason::ServerShutdown => 1001,
+            WsCloseReason::ProtocolError => 1002,
+            WsCloseReason::Unauthorized => 4401,
+            WsCloseReason::NotFound => 4404,
+            WsCloseReason::RateLimited => 4429,
+        }
+    }
+
+    /// Whether reopening the stream later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, WsCloseReason::ServerShutdown | WsCloseReason::RateLimited)
+    }
+}
+
+/// Priority level for an intent, ordered from `Low` to `Critical`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum Priority {
     /// Lowest priority, processed when resources are available.
@@ -61,8 +208,29 @@ pub enum Priority {
     Critical,
 }
 
+impl Priority {
+    /// Every priority, lowest first.
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Normal, Priority::High, Priority::Critical];
+
+    /// The priority `levels` above this one, capped at `Critical`.
+    pub fn raised(self, levels: usize) -> Priority {
+        Self::ALL[(self as usize).saturating_add(levels).min(Self::ALL.len() - 1)]
+    }
+
+    /// The priority's name as it appears on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Critical => "critical",
+        }
+    }
+}
+
 /// Resource type for budget and constraint tracking.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ResourceType {
     /// Monetary cost in a specific currency.
@@ -81,6 +249,34 @@ pub enum ResourceType {
     Custom(String),
 }
 
+impl ResourceType {
+    /// The resource a `ResourceLimit` constraint names, e.g. `memory`;
+    /// names of no built-in resource are custom ones.
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "compute" | "cpu" => ResourceType::Compute,
+            "memory" => ResourceType::Memory,
+            "storage" => ResourceType::Storage,
+            "bandwidth" => ResourceType::Bandwidth,
+            _ => ResourceType::Custom(name.to_string()),
+        }
+    }
+
+    /// Name of the resource in `ResourceLimit` constraints. Money and time
+    /// are the cost and duration every plan already accounts for.
+    pub fn name(&self) -> String {
+        match self {
+            ResourceType::Money { .. } => "cost".to_string(),
+            ResourceType::Time => "duration_ms".to_string(),
+            ResourceType::Compute => "compute".to_string(),
+            ResourceType::Memory => "memory".to_string(),
+            ResourceType::Storage => "storage".to_string(),
+            ResourceType::Bandwidth => "bandwidth".to_string(),
+            ResourceType::Custom(name) => name.clone(),
+        }
+    }
+}
+
 /// Event types that can occur during intent processing.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -119,13 +315,53 @@ mod tests {
         assert!(IntentStatus::Complete.is_terminal());
         assert!(IntentStatus::Failed.is_terminal());
         assert!(IntentStatus::Cancelled.is_terminal());
+        assert!(IntentStatus::PartiallyComplete.is_terminal());
+        assert!(IntentStatus::Expired.is_terminal());
         assert!(!IntentStatus::Executing.is_terminal());
     }
 
+    #[test]
+    fn test_terminal_statuses_are_final() {
+        assert!(IntentStatus::Executing.can_transition_to(IntentStatus::Cancelled));
+        assert!(IntentStatus::Paused.can_transition_to(IntentStatus::Executing));
+        assert!(IntentStatus::Cancelled.can_transition_to(IntentStatus::Cancelled));
+        assert!(!IntentStatus::Cancelled.can_transition_to(IntentStatus::Complete));
+        assert!(!IntentStatus::Complete.can_transition_to(IntentStatus::Failed));
+    }
+
+    #[test]
+    fn test_intent_status_names_match_serde() {
+        for status in [IntentStatus::Received, IntentStatus::Paused, IntentStatus::PartiallyComplete, IntentStatus::Cancelled, IntentStatus::Expired] {
+            assert_eq!(serde_json::to_value(status).unwrap(), status.as_str());
+        }
+    }
+
+    #[test]
+    fn test_close_reason_names_match_serde() {
+        for reason in WsCloseReason::ALL {
+            assert_eq!(serde_json::to_value(reason).unwrap(), reason.as_str());
+            assert_eq!(WsCloseReason::from_name(reason.as_str()), Some(reason));
+        }
+        assert_eq!(WsCloseReason::from_name("bye"), None);
+        assert!(!WsCloseReason::NotFound.is_retryable());
+        assert!(!WsCloseReason::Unauthorized.is_retryable());
+        assert!(WsCloseReason::ServerShutdown.is_retryable());
+    }
+
+    #[test]
+    fn test_priority_order() {
+        assert!(Priority::Low < Priority::Normal && Priority::High < Priority::Critical);
+        assert_eq!(Priority::ALL.iter().max(), Some(&Priority::Critical));
+        assert_eq!(Priority::Low.raised(2), Priority::High);
+        assert_eq!(Priority::High.raised(5), Priority::Critical);
+    }
+
     #[test]
     fn test_intent_status_active() {
         assert!(IntentStatus::Executing.is_active());
         assert!(IntentStatus::Planning.is_active());
+        assert!(IntentStatus::Paused.is_active());
+        assert!(!IntentStatus::Paused.is_terminal());
         assert!(!IntentStatus::Complete.is_active());
     }
 }
@@ -0,0 +1,564 @@
+//! Field-level checks on intent budgets, limits, weights and state-match
+//! expressions.
+//!
+//! [`Intent::validate`] runs these with the default [`IntentLimits`]; nodes
+//! pass their own limits through [`Intent::validate_with`], and the
+//! validators for their custom constraints through
+//! [`Intent::validate_against`].
+
+use std::fmt;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::condition::StateExpr;
+use crate::constraint::ConstraintRegistry;
+use crate::error::{OrpheonError, Result};
+use crate::intent::{Constraint, Intent};
+use crate::money::MoneyAmount;
+
+/// Active ISO 4217 currency codes.
+pub const ISO_4217_CODES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT", "BGN",
+    "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD", "CDF",
+    "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE", "CZK", "DJF", "DKK",
+    "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GNF",
+    "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD", "IRR", "ISK", "JMD", "JOD",
+    "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD", "KZT", "LAK", "LBP", "LKR", "LRD",
+    "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR", "MWK", "MXN",
+    "MXV", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP",
+    "PKR", "PLN", "PYG", "QAR", "RON", "RSD", "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD",
+    "SHP", "SLE", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP",
+    "TRY", "TTD", "TWD", "TZS", "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES",
+    "VND", "VUV", "WST", "XAF", "XAG", "XAU", "XBA", "XBB", "XBC", "XBD", "XCD", "XDR", "XOF", "XPD",
+    "XPF", "XPT", "XSU", "XUA", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// Whether `code` is an ISO 4217 currency code, ignoring case.
+pub fn is_currency_code(code: &str) -> bool {
+    let code = code.to_ascii_uppercase();
+    ISO_4217_CODES.binary_search(&code.as_str()).is_ok()
+}
+
+/// `value` if it is finite, otherwise a [`OrpheonError::NonFinite`]
+/// naming it by `context`.
+pub fn finite_or_err(value: f64, context: impl fmt::Display) -> Result<f64> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(OrpheonError::NonFinite { context: context.to_string(), value })
+    }
+}
+
+/// Upper bounds on budget fields and on the size of intents that a node is
+/// willing to accept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntentLimits {
+    /// Longest `budget.max_duration_ms` accepted.
+    pub max_duration_ms: u64,
+
+    /// Most `budget.max_retries` accepted.
+    pub max_retries: u32,
+
+    /// Most hard and soft constraints accepted, together.
+    pub max_constraints: usize,
+
+    /// Most preferences accepted.
+    pub max_preferences: usize,
+
+    /// Largest `metadata` accepted, in bytes of compact JSON.
+    pub max_metadata_bytes: usize,
+
+    /// Longest `kind` accepted, in bytes.
+    pub max_kind_len: usize,
+
+    /// Most labels accepted.
+    pub max_labels: usize,
+
+    /// Longest label key accepted, in bytes.
+    pub max_label_key_len: usize,
+
+    /// Longest label value accepted, in bytes.
+    pub max_label_value_len: usize,
+
+    /// Most ancestors an intent may have through `parent_id`; see
+    /// [`Intent::ancestor_chain`](crate::Intent::ancestor_chain).
+    pub max_parent_depth: usize,
+}
+
+impl Default for IntentLimits {
+    fn default() -> Self {
+        Self {
+            max_duration_ms: 24 * 60 * 60 * 1000,
+            max_retries: 10,
+            max_constraints: 256,
+            max_preferences: 32,
+            max_metadata_bytes: 64 * 1024,
+            max_kind_len: 128,
+            max_labels: 64,
+            max_label_key_len: 63,
+            max_label_value_len: 63,
+            max_parent_depth: 32,
+        }
+    }
+}
+
+/// The first size limit `intent` exceeds, naming the limit and the size
+/// observed. Checked before anything iterates over the intent's fields.
+pub(crate) fn size_violation(intent: &Intent, limits: &IntentLimits) -> Option<String> {
+    let sizes = [
+        ("kind length", "max_kind_len", intent.kind.len(), limits.max_kind_len),
+        (
+            "constraint count",
+            "max_constraints",
+            intent.constraints.len() + intent.soft_constraints.len(),
+            limits.max_constraints,
+        ),
+        ("preference count", "max_preferences", intent.preferences.len(), limits.max_preferences),
+        ("metadata size", "max_metadata_bytes", json_len(&intent.metadata), limits.max_metadata_bytes),
+        ("label count", "max_labels", intent.labels.len(), limits.max_labels),
+    ];
+    sizes
+        .into_iter()
+        .find(|(_, _, observed, max)| observed > max)
+        .map(|(what, limit, observed, max)| format!("Intent {} is {}, exceeding {} of {}", what, observed, limit, max))
+}
+
+/// Length of `value` as compact JSON, without building the string.
+fn json_len(value: &serde_json::Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    // Writing to a counter cannot fail
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+/// One invalid field, named by its path in the intent, e.g.
+/// `budget.max_cost` or `preferences[1].weight`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Whether `text` is a label key or value: ASCII letters, digits, `-`,
+/// `_` and `.`, starting and ending with a letter or digit. Keys may also
+/// contain `/`, e.g. `example.com/team`.
+fn is_label_text(text: &str, allow_slash: bool) -> bool {
+    let edges_ok = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+    edges_ok(text.chars().next())
+        && edges_ok(text.chars().last())
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') || (allow_slash && c == '/'))
+}
+
+/// Every invalid budget, limit, weight, expression and label field of
+/// `intent`.
+pub(crate) fn field_errors(intent: &Intent, limits: &IntentLimits) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let budget = &intent.budget;
+
+    if let Some(max_cost) = budget.max_cost {
+        if !(max_cost.is_finite() && max_cost >= 0.0) {
+            errors.push(FieldError::new(
+                "budget.max_cost",
+                format!("must be a finite, non-negative number, got {}", max_cost),
+            ));
+        }
+    }
+    if let Some(exact) = &budget.max_cost_minor {
+        if exact.currency != budget.currency {
+            errors.push(FieldError::new(
+                "budget.max_cost_minor.currency",
+                format!("must match budget.currency {:?}, got {:?}", budget.currency, exact.currency),
+            ));
+        } else if let Some(max_cost) = budget
+            .max_cost
+            .filter(|max_cost| MoneyAmount::from_major(*max_cost, &budget.currency).as_ref() != Some(exact))
+        {
+            errors.push(FieldError::new(
+                "budget.max_cost_minor",
+                format!("{} disagrees with budget.max_cost {}", exact, max_cost),
+            ));
+        }
+    }
+    // An empty currency is fine as long as there is no monetary limit
+    if budget.currency.is_empty() {
+        if budget.max_cost.is_some() || budget.max_cost_minor.is_some() {
+            errors.push(FieldError::new("budget.currency", "is required when budget.max_cost is set"));
+        }
+    } else if budget.currency != budget.currency.to_ascii_uppercase() {
+        errors.push(FieldError::new(
+            "budget.currency",
+            format!("must be upper case, e.g. {:?}", budget.currency.to_ascii_uppercase()),
+        ));
+    } else if !is_currency_code(&budget.currency) {
+        errors.push(FieldError::new(
+            "budget.currency",
+            format!("{:?} is not an ISO 4217 currency code", budget.currency),
+        ));
+    }
+    if let Some(duration) = budget.max_duration_ms {
+        if duration == 0 {
+            errors.push(FieldError::new("budget.max_duration_ms", "must be greater than 0"));
+        } else if duration > limits.max_duration_ms {
+            errors.push(FieldError::new(
+                "budget.max_duration_ms",
+                format!("must be at most {}, got {}", limits.max_duration_ms, duration),
+            ));
+        }
+    }
+    if budget.max_retries > limits.max_retries {
+        errors.push(FieldError::new(
+            "budget.max_retries",
+            format!("must be at most {}, got {}", limits.max_retries, budget.max_retries),
+        ));
+    }
+    for (i, (resource, limit)) in budget.resource_limits.iter().enumerate() {
+        if !(limit.is_finite() && *limit >= 0.0) {
+            errors.push(FieldError::new(
+                format!("budget.resource_limits[{}]", i),
+                format!("{} must be a finite, non-negative number, got {}", resource.name(), limit),
+            ));
+        }
+    }
+
+    // Negative limits and hard deadlines past the window are reported as
+    // conflicts, since they involve more than one field
+    let now = Utc::now();
+    let not_after = intent.validity_window.not_after;
+    for (list, constraints) in [("constraints", &intent.constraints), ("soft_constraints", &intent.soft_constraints)] {
+        for (i, constraint) in constraints.iter().enumerate() {
+            match constraint {
+                Constraint::ResourceLimit { limit, .. } if !limit.is_finite() => errors.push(FieldError::new(
+                    format!("{}[{}].limit", list, i),
+                    format!("must be a finite number, got {}", limit),
+                )),
+                Constraint::Deadline { by } if *by < now => errors.push(FieldError::new(
+                    format!("{}[{}].by", list, i),
+                    format!("must not be in the past, got {}", by.to_rfc3339()),
+                )),
+                Constraint::Deadline { by } if list == "soft_constraints" && not_after.is_some_and(|end| *by > end) => {
+                    errors.push(FieldError::new(
+                        format!("{}[{}].by", list, i),
+                        format!("must not be after validity_window.not_after, got {}", by.to_rfc3339()),
+                    ))
+                }
+                Constraint::DependsOn { .. } if list == "soft_constraints" => errors.push(FieldError::new(
+                    format!("{}[{}]", list, i),
+                    "depends_on must be a hard constraint",
+                )),
+                Constraint::StateMatch { expression } => {
+                    if let Err(e) = StateExpr::parse(expression) {
+                        errors.push(FieldError::new(format!("{}[{}].expression", list, i), e.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (i, window) in intent.validity_window.recurrence.iter().enumerate() {
+        if window.start == window.end {
+            errors.push(FieldError::new(format!("validity_window.recurrence[{}]", i), "start and end must differ"));
+        }
+    }
+
+    for (i, preference) in intent.preferences.iter().enumerate() {
+        let weight = preference.weight;
+        if !(weight.is_finite() && (0.0..=1.0).contains(&weight)) {
+            errors.push(FieldError::new(
+                format!("preferences[{}].weight", i),
+                format!("must be a finite number in [0, 1], got {}", weight),
+            ));
+        }
+    }
+
+    for (key, value) in &intent.labels {
+        let field = format!("labels.{}", key);
+        if key.len() > limits.max_label_key_len {
+            errors.push(FieldError::new(field, format!("key must be at most {} bytes, got {}", limits.max_label_key_len, key.len())));
+        } else if !is_label_text(key, true) {
+            errors.push(FieldError::new(
+                field,
+                "key must be letters, digits, '-', '_', '.' and '/', starting and ending with a letter or digit",
+            ));
+        } else if value.len() > limits.max_label_value_len {
+            errors.push(FieldError::new(
+                field,
+                format!("value must be at most {} bytes, got {}", limits.max_label_value_len, value.len()),
+            ));
+        } else if !value.is_empty() && !is_label_text(value, false) {
+            errors.push(FieldError::new(
+                field,
+                "value must be empty or letters, digits, '-', '_' and '.', starting and ending with a letter or digit",
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Every `Custom` constraint of `intent` that `registry` has no validator
+/// for, or whose data its validator rejects.
+pub(crate) fn custom_errors(intent: &Intent, registry: &ConstraintRegistry) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    for (list, constraints) in [("constraints", &intent.constraints), ("soft_constraints", &intent.soft_constraints)] {
+        for (i, constraint) in constraints.iter().enumerate() {
+            let Constraint::Custom { name, data } = constraint else {
+                continue;
+            };
+            match registry.get(name) {
+                Some(validator) => {
+                    if let Err(message) = validator.validate(data) {
+                        errors.push(FieldError::new(format!("{}[{}].data", list, i), message));
+                    }
+                }
+                None => errors.push(FieldError::new(
+                    format!("{}[{}].name", list, i),
+                    format!("no validator is registered for {:?}", name),
+                )),
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraint::{CustomConstraintValidator, EvaluationContext, Evaluator};
+    use crate::intent::{Budget, OptimizationDirection, Preference};
+
+    /// Makes one field of a valid intent invalid.
+    type Breakage = fn(&mut Intent);
+
+    #[test]
+    fn test_currency_codes_are_sorted_and_case_insensitive() {
+        assert!(ISO_4217_CODES.windows(2).all(|w| w[0] < w[1]));
+        assert!(is_currency_code("USD"));
+        assert!(is_currency_code("eur"));
+        assert!(!is_currency_code("dollars"));
+        assert!(!is_currency_code("XYZ"));
+    }
+
+    #[test]
+    fn test_each_invalid_field_is_reported() {
+        let cases: Vec<(&str, Breakage)> = vec![
+            ("budget.max_cost", |i| i.budget.max_cost = Some(-50.0)),
+            ("budget.max_cost", |i| i.budget.max_cost = Some(f64::INFINITY)),
+            ("budget.max_cost", |i| i.budget.max_cost = Some(f64::NAN)),
+            ("budget.currency", |i| i.budget.currency = "dollars".to_string()),
+            ("budget.currency", |i| i.budget.currency = String::new()),
+            ("budget.currency", |i| i.budget.currency = "usd".to_string()),
+            ("budget.max_cost_minor.currency", |i| i.budget.max_cost_minor = Some(MoneyAmount::new(500, "EUR"))),
+            ("budget.max_cost_minor", |i| i.budget.max_cost_minor = Some(MoneyAmount::usd_cents(1))),
+            ("budget.max_duration_ms", |i| i.budget.max_duration_ms = Some(u64::MAX)),
+            ("budget.max_duration_ms", |i| i.budget.max_duration_ms = Some(0)),
+            ("budget.max_retries", |i| i.budget.max_retries = 1_000),
+            ("budget.resource_limits[0]", |i| i.budget.resource_limits.push((crate::ResourceType::Memory, -1.0))),
+            (
+                "constraints[0].limit",
+                |i| i.constraints.push(Constraint::ResourceLimit { resource: "cpu".into(), limit: f64::INFINITY }),
+            ),
+            (
+                "soft_constraints[0].limit",
+                |i| i.soft_constraints.push(Constraint::ResourceLimit { resource: "cpu".into(), limit: f64::NAN }),
+            ),
+            ("constraints[0].by", |i| i.constraints.push(Constraint::Deadline { by: Utc::now() - chrono::Duration::hours(1) })),
+            (
+                "soft_constraints[0].by",
+                |i| i.soft_constraints.push(Constraint::Deadline { by: Utc::now() + chrono::Duration::days(2) }),
+            ),
+            ("constraints[0].expression", |i| i.constraints.push(Constraint::StateMatch { expression: "region ===".into() })),
+            (
+                "soft_constraints[0].expression",
+                |i| i.soft_constraints.push(Constraint::StateMatch { expression: "(replicas > 2".into() }),
+            ),
+            (
+                "soft_constraints[0]",
+                |i| i.soft_constraints.push(Constraint::DependsOn { intent_id: uuid::Uuid::new_v4(), require_success: true }),
+            ),
+            ("validity_window.recurrence[0]", |i| {
+                let midnight = chrono::NaiveTime::MIN;
+                i.validity_window.recurrence.push(crate::intent::DailyWindow { start: midnight, end: midnight, weekdays: vec![] });
+            }),
+            ("preferences[0].weight", |i| i.preferences[0].weight = f32::NAN),
+            ("preferences[0].weight", |i| i.preferences[0].weight = 1.5),
+            ("labels.", |i| {
+                i.labels.insert(String::new(), "ml".into());
+            }),
+            ("labels.-team", |i| {
+                i.labels.insert("-team".into(), "ml".into());
+            }),
+            ("labels.team", |i| {
+                i.labels.insert("team".into(), "machine learning".into());
+            }),
+            ("labels.team", |i| {
+                i.labels.insert("team".into(), "x".repeat(64));
+            }),
+            ("labels.example.com/team", |i| {
+                i.labels.insert("example.com/team".into(), "ml/".into());
+            }),
+        ];
+
+        for (field, break_intent) in cases {
+            let mut intent = Intent::builder()
+                .kind("deploy")
+                .budget(Budget::usd(100.0))
+                .preference(Preference { objective: "cost".into(), direction: OptimizationDirection::Minimize, weight: 1.0 })
+                .label("env", "prod")
+                .label("example.com/owner", "")
+                .build()
+                .unwrap();
+            assert!(field_errors(&intent, &IntentLimits::default()).is_empty());
+
+            break_intent(&mut intent);
+            let errors = field_errors(&intent, &IntentLimits::default());
+            assert_eq!(errors.len(), 1, "{}: {:?}", field, errors);
+            assert_eq!(errors[0].field, field);
+        }
+    }
+
+    #[test]
+    fn test_limits_are_configurable() {
+        let intent = Intent::builder().kind("deploy").budget(Budget::usd(1.0).with_duration(60_000)).build().unwrap();
+        assert!(field_errors(&intent, &IntentLimits::default()).is_empty());
+        let strict = IntentLimits { max_duration_ms: 30_000, max_retries: 1, ..Default::default() };
+        let fields: Vec<String> = field_errors(&intent, &strict).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["budget.max_duration_ms", "budget.max_retries"]);
+    }
+
+    #[test]
+    fn test_positive_usd_budgets_validate() {
+        // Spread over many orders of magnitude, down to the smallest positive value
+        let mut amounts = vec![f64::MIN_POSITIVE, 1e-9, 0.01, 1.0, 99.99, 1e6, 1e15, f64::MAX];
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..1_000 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let amount = f64::from_bits(seed >> 2);
+            if amount.is_finite() && amount > 0.0 {
+                amounts.push(amount);
+            }
+        }
+
+        for amount in amounts {
+            let intent = Intent::builder().kind("deploy").budget(Budget::usd(amount)).build().unwrap();
+            assert!(intent.validate().is_ok(), "{}", amount);
+        }
+    }
+
+    /// Accepts `{ "max": n }` and holds while `replicas` is at most `n`.
+    struct MaxReplicas;
+
+    impl CustomConstraintValidator for MaxReplicas {
+        fn validate(&self, data: &serde_json::Value) -> std::result::Result<(), String> {
+            data["max"].as_u64().map(|_| ()).ok_or_else(|| "max must be a non-negative integer".to_string())
+        }
+
+        fn check(&self, data: &serde_json::Value, ctx: &EvaluationContext) -> std::result::Result<(), String> {
+            match ctx.variables.get("replicas").and_then(serde_json::Value::as_u64) {
+                Some(replicas) if Some(replicas) > data["max"].as_u64() => Err(format!("{} replicas", replicas)),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_constraints_need_a_validator() {
+        let registry = ConstraintRegistry::new().with("max_replicas", MaxReplicas);
+        let custom = |name: &str, data: serde_json::Value| Constraint::Custom { name: name.to_string(), data };
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(custom("max_replicas", serde_json::json!({ "max": 3 })))
+            .soft_constraint(custom("max_replicas", serde_json::json!({ "max": "three" })))
+            .constraint(custom("max_replica", serde_json::json!({ "max": 3 })))
+            .build()
+            .unwrap();
+
+        let fields: Vec<String> = custom_errors(&intent, &registry).into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["constraints[1].name", "soft_constraints[0].data"]);
+        assert!(matches!(
+            intent.validate_against(&IntentLimits::default(), Some(&registry)),
+            Err(OrpheonError::FieldsInvalid { .. })
+        ));
+        // Without a registry, custom constraints are not checked
+        intent.validate().unwrap();
+
+        let evaluator = Evaluator::new().with_registry(registry);
+        let ctx = EvaluationContext::new().with_variables([("replicas".to_string(), serde_json::json!(5))].into());
+        let result = evaluator.evaluate(&intent.constraints[0], &ctx);
+        assert!(result.is_violated());
+        assert_eq!(result.detail, "5 replicas");
+    }
+
+    #[test]
+    fn test_size_limits_name_the_limit_exceeded() {
+        let limits = IntentLimits {
+            max_constraints: 2,
+            max_preferences: 1,
+            max_metadata_bytes: 32,
+            max_kind_len: 8,
+            ..Default::default()
+        };
+        let message = |intent: Intent| match intent.validate_with(&limits) {
+            Err(OrpheonError::IntentInvalid { message, .. }) => message,
+            other => panic!("expected IntentInvalid, got {:?}", other),
+        };
+        let limit = |resource: &str| Constraint::ResourceLimit { resource: resource.into(), limit: 1.0 };
+
+        assert_eq!(
+            message(Intent::builder().kind("provision_gpu").build().unwrap()),
+            "Intent kind length is 13, exceeding max_kind_len of 8"
+        );
+        let crowded = Intent::builder()
+            .kind("deploy")
+            .constraint(limit("cpu"))
+            .constraint(limit("gpu"))
+            .soft_constraint(limit("ram"))
+            .build()
+            .unwrap();
+        assert_eq!(message(crowded), "Intent constraint count is 3, exceeding max_constraints of 2");
+        let picky = Intent::builder().kind("deploy").minimize("cost", 0.5).maximize("reliability", 0.5).build().unwrap();
+        assert_eq!(message(picky), "Intent preference count is 2, exceeding max_preferences of 1");
+        let chatty = Intent::builder().kind("deploy").metadata(serde_json::json!({ "notes": "x".repeat(64) })).build().unwrap();
+        assert_eq!(message(chatty), "Intent metadata size is 76, exceeding max_metadata_bytes of 32");
+
+        let small = Intent::builder().kind("deploy").constraint(limit("cpu")).build().unwrap();
+        small.validate_with(&limits).unwrap();
+    }
+
+    #[test]
+    fn test_finite_or_err() {
+        assert_eq!(finite_or_err(2.5, "cost").unwrap(), 2.5);
+        let err = finite_or_err(f64::NAN, "cost of action deploy").unwrap_err();
+        assert_eq!(err.to_string(), "cost of action deploy must be a finite number, got NaN");
+        assert!(finite_or_err(f64::NEG_INFINITY, "cost").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Protocol versioning.
+//!
+//! The server opens a negotiation with a [`NegotiationMessage::Hello`]
+//! naming its protocol version and features, and the client answers with
+//! its own. Each side then speaks the lower of the two versions and only
+//! uses the features both announced; see [`Agreement`].
+//!
+//! Version 1 is the protocol from before the handshake. A client that does
+//! not answer the server's hello, or a server that does not send one, is
+//! taken to speak it.
+
+use std::collections::BTreeSet;
+
+use orpheon_core::{OrpheonError, Result};
+
+use crate::protocol::NegotiationMessage;
+
+/// Negotiation protocol version this crate speaks.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol version this crate still speaks.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// The client may send [`NegotiationMessage::Counter`].
+pub const COUNTER_OFFERS: &str = "counter_offers";
+
+/// Proposals carry per-step [`line_items`](crate::Proposal::line_items).
+pub const LINE_ITEMS: &str = "line_items";
+
+/// Confirmations carry a signed [`AcceptanceReceipt`](crate::AcceptanceReceipt).
+pub const SIGNED_RECEIPTS: &str = "signed_receipts";
+
+/// Features a protocol version provides.
+pub fn version_features(version: u32) -> &'static [&'static str] {
+    match version {
+        0 => &[],
+        1 => &[COUNTER_OFFERS, LINE_ITEMS],
+        _ => &[COUNTER_OFFERS, LINE_ITEMS, SIGNED_RECEIPTS],
+    }
+}
+
+/// This side's hello.
+pub fn hello() -> NegotiationMessage {
+    NegotiationMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        supported_features: version_features(PROTOCOL_VERSION).iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+/// What both sides of a negotiation speak.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Agreement {
+    /// Lower of the two sides' versions.
+    pub protocol_version: u32,
+
+    /// Features both sides support.
+    pub features: BTreeSet<String>,
+}
+
+impl Agreement {
+    /// Agree with a peer that said hello with `version` and `features`.
+    ///
+    /// Fails if the peer's version is older than [`MIN_PROTOCOL_VERSION`].
+    pub fn with_peer(version: u32, features: &[String]) -> Result<Self> {
+        if version < MIN_PROTOCOL_VERSION {
+            return Err(OrpheonError::UnsupportedProtocol {
+                version,
+                min: MIN_PROTOCOL_VERSION,
+                max: PROTOCOL_VERSION,
+            });
+        }
+        let protocol_version = version.min(PROTOCOL_VERSION);
+        let features = version_features(protocol_version)
+            .iter()
+            .filter(|ours| features.iter().any(|theirs| theirs == *ours))
+            .map(|f| f.to_string())
+            .collect();
+        Ok(Self { protocol_version, features })
+    }
+
+    /// Agreement with a peer that never said hello.
+    pub fn legacy() -> Self {
+        Self {
+            protocol_version: 1,
+            features: version_features(1).iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Whether both sides support `feature`.
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Leave out of an outgoing message whatever the peer did not agree to.
+    pub fn restrict(&self, message: NegotiationMessage) -> NegotiationMessage {
+        match message {
+            NegotiationMessage::Offer(mut proposal) if !self.supports(LINE_ITEMS) => {
+                proposal.line_items.clear();
+                NegotiationMessage::Offer(proposal)
+            }
+            NegotiationMessage::Confirmed { proposal_id, execution_id, .. } if !self.supports(SIGNED_RECEIPTS) => {
+                NegotiationMessage::Confirmed { proposal_id, execution_id, receipt: None }
+            }
+            message => message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Proposal;
+    use orpheon_core::{Plan, PlanningStrategy, Step};
+    use uuid::Uuid;
+
+    fn features(names: &[&str]) -> Vec<String> {
+        names.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn test_agreement_is_the_intersection() {
+        let agreement = Agreement::with_peer(PROTOCOL_VERSION, &features(&[COUNTER_OFFERS, "telepathy"])).unwrap();
+        assert_eq!(agreement.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(agreement.features, BTreeSet::from([COUNTER_OFFERS.to_string()]));
+
+        // A newer peer is met at our version, with only features we know
+        let agreement = Agreement::with_peer(PROTOCOL_VERSION + 1, &features(&[SIGNED_RECEIPTS, "telepathy"])).unwrap();
+        assert_eq!(agreement.protocol_version, PROTOCOL_VERSION);
+        assert!(agreement.supports(SIGNED_RECEIPTS));
+        assert!(!agreement.supports("telepathy"));
+
+        // An older peer cannot use features its version lacks
+        let agreement = Agreement::with_peer(1, &features(&[COUNTER_OFFERS, SIGNED_RECEIPTS])).unwrap();
+        assert_eq!(agreement.protocol_version, 1);
+        assert!(!agreement.supports(SIGNED_RECEIPTS));
+    }
+
+    #[test]
+    fn test_too_old_peer_is_rejected() {
+        let err = Agreement::with_peer(0, &[]).unwrap_err();
+        assert!(matches!(err, OrpheonError::UnsupportedProtocol { version: 0, .. }), "{:?}", err);
+        assert!(err.to_string().contains("supported versions are 1 to 2"));
+    }
+
+    #[test]
+    fn test_restrict_drops_unagreed_parts() {
+        let intent_id = Uuid::new_v4();
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(2.0));
+        let proposal = Proposal::new(intent_id, plan).unwrap();
+        assert!(!proposal.line_items.is_empty());
+
+        let agreement = Agreement::with_peer(PROTOCOL_VERSION, &features(&[COUNTER_OFFERS])).unwrap();
+        match agreement.restrict(NegotiationMessage::Offer(Box::new(proposal.clone()))) {
+            NegotiationMessage::Offer(offer) => assert!(offer.line_items.is_empty()),
+            other => panic!("expected an offer, got {:?}", other),
+        }
+
+        // Legacy peers still get line items
+        match Agreement::legacy().restrict(NegotiationMessage::Offer(Box::new(proposal))) {
+            NegotiationMessage::Offer(offer) => assert!(!offer.line_items.is_empty()),
+            other => panic!("expected an offer, got {:?}", other),
+        }
+    }
+}
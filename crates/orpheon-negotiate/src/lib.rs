@@ -2,8 +2,16 @@
 //!
 //! WebSocket-based negotiation protocol for the Orpheon Protocol.
 
+pub mod handshake;
+pub mod options;
 pub mod protocol;
+pub mod receipt;
 pub mod session;
 
-pub use protocol::{NegotiationMessage, Proposal, CounterOffer};
-pub use session::{NegotiationSession, NegotiationState};
+pub use handshake::{Agreement, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+pub use options::NegotiationOptions;
+pub use protocol::{NegotiationMessage, Pricing, Proposal, ProposalLineItem, CounterOffer, PreferenceAdjustment};
+pub use receipt::AcceptanceReceipt;
+pub use session::{
+    NegotiationSession, NegotiationState, ProposalSummary, SessionEvent, SessionObserver, DEFAULT_HISTORY_CAP, TIMEOUT_REASON,
+};
@@ -0,0 +1,138 @@
+//! Options a client submits an intent with to negotiate its plan.
+
+use orpheon_core::Budget;
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::Proposal;
+
+/// Per-intent negotiation options supplied at submission.
+///
+/// Clients that would accept any reasonable first proposal can set
+/// auto-accept bounds: a proposal within all of them is accepted by the
+/// node on their behalf, and any other is negotiated as usual.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct NegotiationOptions {
+    /// How long the client has to accept a proposal, in milliseconds.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Maximum number of proposal rounds.
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: u32,
+
+    /// Accept proposals quoting at most this, in the budget's currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_accept_below_cost: Option<f64>,
+
+    /// Accept proposals estimating at most this many milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_accept_within_latency_ms: Option<u64>,
+}
+
+fn default_timeout_ms() -> u64 {
+    60_000
+}
+
+fn default_max_rounds() -> u32 {
+    5
+}
+
+impl Default for NegotiationOptions {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout_ms(),
+            max_rounds: default_max_rounds(),
+            auto_accept_below_cost: None,
+            auto_accept_within_latency_ms: None,
+        }
+    }
+}
+
+impl NegotiationOptions {
+    /// Accept proposals quoting at most `cost` without asking.
+    pub fn auto_accept_below_cost(mut self, cost: f64) -> Self {
+        self.auto_accept_below_cost = Some(cost);
+        self
+    }
+
+    /// Accept proposals estimating at most `latency_ms` without asking.
+    pub fn auto_accept_within_latency_ms(mut self, latency_ms: u64) -> Self {
+        self.auto_accept_within_latency_ms = Some(latency_ms);
+        self
+    }
+
+    /// Whether any auto-accept bound is set.
+    pub fn auto_accepts_any(&self) -> bool {
+        self.auto_accept_below_cost.is_some() || self.auto_accept_within_latency_ms.is_some()
+    }
+
+    /// Whether `proposal` may be accepted on the client's behalf: some
+    /// bound is set and the proposal is within every one that is.
+    pub fn auto_accepts(&self, proposal: &Proposal) -> bool {
+        self.auto_accepts_any()
+            && self.auto_accept_below_cost.is_none_or(|cost| proposal.quoted_cost <= cost)
+            && self.auto_accept_within_latency_ms.is_none_or(|ms| proposal.estimated_latency_ms <= ms)
+    }
+
+    /// Check the auto-accept bounds against the intent's budget; the node
+    /// must never accept more than the client could have.
+    pub fn check_budget(&self, budget: &Budget) -> Result<(), String> {
+        if let Some(cost) = self.auto_accept_below_cost {
+            if !cost.is_finite() || cost < 0.0 {
+                return Err(format!("auto_accept_below_cost must be a non-negative number, got {}", cost));
+            }
+            if let Some(max_cost) = budget.cost_limit().filter(|max_cost| cost > *max_cost) {
+                return Err(format!("auto_accept_below_cost {} exceeds the budget's max_cost {}", cost, max_cost));
+            }
+        }
+        if let Some(ms) = self.auto_accept_within_latency_ms {
+            if let Some(max_ms) = budget.max_duration_ms.filter(|max_ms| ms > *max_ms) {
+                return Err(format!(
+                    "auto_accept_within_latency_ms {} exceeds the budget's max_duration_ms {}",
+                    ms, max_ms
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::{Plan, PlanningStrategy, Step};
+    use uuid::Uuid;
+
+    fn proposal(cost: f64, duration_ms: u64) -> Proposal {
+        let intent_id = Uuid::new_v4();
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(cost).with_duration(duration_ms));
+        Proposal::new(intent_id, plan).unwrap()
+    }
+
+    #[test]
+    fn test_auto_accepts_within_every_bound() {
+        assert!(!NegotiationOptions::default().auto_accepts(&proposal(1.0, 1)));
+
+        let options = NegotiationOptions::default().auto_accept_below_cost(50.0).auto_accept_within_latency_ms(60_000);
+        assert!(options.auto_accepts(&proposal(50.0, 60_000)));
+        assert!(!options.auto_accepts(&proposal(50.5, 1_000)));
+        assert!(!options.auto_accepts(&proposal(10.0, 61_000)));
+
+        let cost_only = NegotiationOptions::default().auto_accept_below_cost(50.0);
+        assert!(cost_only.auto_accepts(&proposal(10.0, 3_600_000)));
+    }
+
+    #[test]
+    fn test_bounds_must_fit_the_budget() {
+        let budget = Budget::usd(40.0).with_duration(30_000);
+        assert!(NegotiationOptions::default().auto_accept_below_cost(40.0).check_budget(&budget).is_ok());
+
+        let err = NegotiationOptions::default().auto_accept_below_cost(50.0).check_budget(&budget).unwrap_err();
+        assert!(err.contains("max_cost 40"), "{}", err);
+        let err = NegotiationOptions::default().auto_accept_within_latency_ms(60_000).check_budget(&budget).unwrap_err();
+        assert!(err.contains("max_duration_ms"), "{}", err);
+        assert!(NegotiationOptions::default().auto_accept_below_cost(f64::NAN).check_budget(&Budget::usd(100.0)).is_err());
+    }
+}
@@ -1,16 +1,30 @@
 //! Negotiation protocol messages.
 
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
-use orpheon_core::Plan;
+use orpheon_core::{
+    finite_or_err, parse_constraint, Constraint, CurrencyConverter, Intent, IntentBuilder, MoneyAmount, OrpheonError,
+    Plan, Result, StaticRates, Step,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::receipt::AcceptanceReceipt;
+
 /// Message types for the negotiation protocol.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum NegotiationMessage {
+    /// Protocol version and features of the sender; the server's first
+    /// frame, answered by the client's own. See [`crate::handshake`].
+    Hello {
+        protocol_version: u32,
+        supported_features: Vec<String>,
+    },
+    
     /// Server offers a plan to the client.
-    Offer(Proposal),
+    Offer(Box<Proposal>),
     
     /// Client accepts the current proposal.
     Accept { proposal_id: Uuid },
@@ -22,7 +36,13 @@ pub enum NegotiationMessage {
     Counter(CounterOffer),
     
     /// Server acknowledges acceptance and begins execution.
-    Confirmed { proposal_id: Uuid, execution_id: Uuid },
+    Confirmed {
+        proposal_id: Uuid,
+        execution_id: Uuid,
+        /// The node's record of the accepted terms.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        receipt: Option<Box<AcceptanceReceipt>>,
+    },
     
     /// Negotiation failed.
     Failed { reason: String },
@@ -34,6 +54,9 @@ pub enum NegotiationMessage {
     Pong { timestamp: DateTime<Utc> },
 }
 
+/// Tolerance when checking that line items add up to the quoted cost.
+pub const LINE_ITEM_EPSILON: f64 = 1e-6;
+
 /// A proposal from the server to the client.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Proposal {
@@ -49,6 +72,14 @@ pub struct Proposal {
     /// Quoted cost for execution.
     pub quoted_cost: f64,
     
+    /// `quoted_cost` exactly, in minor units; see [`Proposal::in_minor_units`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quoted_cost_minor: Option<MoneyAmount>,
+    
+    /// Per-step breakdown of the quoted cost.
+    #[serde(default)]
+    pub line_items: Vec<ProposalLineItem>,
+    
     /// Currency for the cost.
     pub currency: String,
     
@@ -68,6 +99,47 @@ pub struct Proposal {
     pub metadata: serde_json::Value,
 }
 
+/// The priced part of a proposal contributed by one plan step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalLineItem {
+    /// The step this item prices.
+    pub step_id: Uuid,
+    
+    /// Name of the step.
+    pub step_name: String,
+    
+    /// Action the step performs.
+    pub action: String,
+    
+    /// Cost of running the step once.
+    pub unit_cost: f64,
+    
+    /// `unit_cost` exactly, in minor units.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_cost_minor: Option<MoneyAmount>,
+    
+    /// Estimated duration in milliseconds.
+    pub estimated_duration_ms: u64,
+    
+    /// Provider the step runs on, if the plan pins one.
+    pub provider: Option<String>,
+}
+
+impl ProposalLineItem {
+    /// Price a plan step.
+    pub fn from_step(step: &Step) -> Self {
+        Self {
+            step_id: step.id,
+            step_name: step.name.clone(),
+            action: step.action.clone(),
+            unit_cost: step.estimated_cost,
+            unit_cost_minor: None,
+            estimated_duration_ms: step.estimated_duration_ms,
+            provider: step.parameters.get("provider").and_then(|p| p.as_str()).map(String::from),
+        }
+    }
+}
+
 /// An SLA guarantee offered in a proposal.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlaGuarantee {
@@ -116,21 +188,171 @@ pub struct PreferenceAdjustment {
     pub weight: f32,
 }
 
+/// The currency plans are costed in and how to quote them in others.
+#[derive(Clone)]
+pub struct Pricing {
+    /// Currency of action and plan costs.
+    pub base: String,
+
+    /// Converts costs out of `base`.
+    pub converter: Arc<dyn CurrencyConverter>,
+}
+
+impl Pricing {
+    /// Plans costed in `base`, converted with `converter`.
+    pub fn new(base: impl Into<String>, converter: Arc<dyn CurrencyConverter>) -> Self {
+        Self { base: base.into(), converter }
+    }
+}
+
+impl Default for Pricing {
+    /// Plans costed in USD, quoted in nothing else.
+    fn default() -> Self {
+        Self::new("USD", Arc::new(StaticRates::default()))
+    }
+}
+
+impl std::fmt::Debug for Pricing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pricing").field("base", &self.base).finish_non_exhaustive()
+    }
+}
+
 impl Proposal {
-    /// Create a new proposal.
-    pub fn new(intent_id: Uuid, plan: Plan) -> Self {
-        Self {
+    /// Create a new proposal for a plan costed in USD, itemized by plan
+    /// step. See [`Proposal::priced`].
+    pub fn new(intent_id: Uuid, plan: Plan) -> Result<Self> {
+        Self::priced(intent_id, plan, "USD", &Pricing::default())
+    }
+
+    /// Create a new proposal for a plan costed in `pricing.base`, itemized
+    /// by plan step and quoted in `currency`.
+    ///
+    /// Soft constraints the plan violates are disclosed in the metadata.
+    /// Fails if the plan's step costs do not add up to its estimated cost,
+    /// and with [`OrpheonError::IntentInvalid`] if `currency` cannot be
+    /// converted to.
+    pub fn priced(intent_id: Uuid, plan: Plan, currency: &str, pricing: &Pricing) -> Result<Self> {
+        let metadata = match plan.metadata.get("soft_violations") {
+            Some(violations) => serde_json::json!({ "soft_violations": violations }),
+            None => serde_json::Value::Null,
+        };
+        
+        let line_items = plan.steps.iter().map(ProposalLineItem::from_step).collect();
+        
+        let proposal = Self {
             id: Uuid::new_v4(),
             intent_id,
             plan: plan.clone(),
             quoted_cost: plan.estimated_cost,
-            currency: "USD".to_string(),
+            quoted_cost_minor: None,
+            line_items,
+            currency: pricing.base.clone(),
             estimated_latency_ms: plan.estimated_latency_ms,
             sla_guarantees: Vec::new(),
             expires_at: Utc::now() + chrono::Duration::minutes(5),
             version: 1,
-            metadata: serde_json::Value::Null,
+            metadata,
+        };
+        proposal.check_line_items()?;
+        proposal.convert(currency, pricing)
+    }
+    
+    /// Quote the proposal in `currency` instead; its items and cost must be
+    /// in `pricing.base`.
+    fn convert(mut self, currency: &str, pricing: &Pricing) -> Result<Self> {
+        if currency == self.currency {
+            return Ok(self);
+        }
+        let intent_id = self.intent_id;
+        let with_intent = |e: OrpheonError| match e {
+            OrpheonError::IntentInvalid { intent_id: None, message } => {
+                OrpheonError::IntentInvalid { intent_id: Some(intent_id), message }
+            }
+            other => other,
+        };
+        
+        for item in &mut self.line_items {
+            item.unit_cost = pricing.converter.convert(item.unit_cost, &pricing.base, currency).map_err(with_intent)?;
+            item.unit_cost_minor = None;
         }
+        // Summing the converted items keeps them adding up to the quote
+        self.quoted_cost = self.line_items_total();
+        self.quoted_cost_minor = None;
+        self.currency = currency.to_string();
+        self.check_line_items()?;
+        Ok(self)
+    }
+    
+    /// Price the proposal exactly: each line item is rounded to a minor
+    /// unit of the proposal's currency and the quote becomes their sum,
+    /// with the float costs following suit.
+    ///
+    /// Fails if a cost is negative, not finite or too large.
+    pub fn in_minor_units(mut self) -> Result<Self> {
+        let (intent_id, currency) = (self.intent_id, self.currency.clone());
+        for item in &mut self.line_items {
+            let exact = MoneyAmount::from_major(item.unit_cost, &currency).ok_or_else(|| OrpheonError::NegotiationRejected {
+                intent_id,
+                reason: format!("Line item {} costs {}, which is not a valid amount", item.step_name, item.unit_cost),
+            })?;
+            item.unit_cost = exact.to_major();
+            item.unit_cost_minor = Some(exact);
+        }
+        let quoted = self
+            .line_items_total_minor()
+            .ok_or_else(|| self.rejection("Proposal line items overflow the quoted cost".to_string()))?;
+        self.quoted_cost = quoted.to_major();
+        self.quoted_cost_minor = Some(quoted);
+        Ok(self)
+    }
+    
+    /// Sum of the line item costs.
+    pub fn line_items_total(&self) -> f64 {
+        self.line_items.iter().map(|item| item.unit_cost).sum()
+    }
+    
+    /// Exact sum of the line item costs in the proposal's currency;
+    /// `None` if an item has no exact cost, or on overflow.
+    pub fn line_items_total_minor(&self) -> Option<MoneyAmount> {
+        let items: Option<Vec<&MoneyAmount>> = self.line_items.iter().map(|item| item.unit_cost_minor.as_ref()).collect();
+        MoneyAmount::checked_sum(&self.currency, items?)
+    }
+    
+    /// Check that the quoted cost and line items are finite and that the
+    /// line items add up to the quoted cost, exactly if the quote is in
+    /// minor units.
+    pub fn check_line_items(&self) -> Result<()> {
+        if let Some(quoted) = &self.quoted_cost_minor {
+            return match self.line_items_total_minor() {
+                Some(total) if total == *quoted => Ok(()),
+                Some(total) => Err(self.rejection(format!(
+                    "Proposal line items total {} but quoted cost is {}",
+                    total, quoted
+                ))),
+                None => Err(self.rejection(format!(
+                    "Proposal quotes {} exactly but its line items cannot be summed exactly in {}",
+                    quoted, self.currency
+                ))),
+            };
+        }
+        
+        finite_or_err(self.quoted_cost, "quoted cost of the proposal")?;
+        for item in &self.line_items {
+            finite_or_err(item.unit_cost, format_args!("cost of line item {}", item.step_name))?;
+        }
+        let total = self.line_items_total();
+        if (total - self.quoted_cost).abs() > LINE_ITEM_EPSILON {
+            return Err(self.rejection(format!(
+                "Proposal line items total {:.6} but quoted cost is {:.6}",
+                total, self.quoted_cost
+            )));
+        }
+        Ok(())
+    }
+    
+    fn rejection(&self, reason: String) -> OrpheonError {
+        OrpheonError::NegotiationRejected { intent_id: self.intent_id, reason }
     }
     
     /// Check if the proposal has expired.
@@ -169,6 +391,14 @@ impl CounterOffer {
         self
     }
     
+    /// Check that the requested cost cap is a finite number.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(cost) = self.max_cost {
+            finite_or_err(cost, "max_cost of the counter-offer")?;
+        }
+        Ok(())
+    }
+    
     /// Request lower latency.
     pub fn with_max_latency(mut self, latency_ms: u64) -> Self {
         self.max_latency_ms = Some(latency_ms);
@@ -180,12 +410,94 @@ impl CounterOffer {
         self.message = Some(message.into());
         self
     }
+    
+    /// The intent to re-plan against: the original with the requested
+    /// cost and latency caps tightened into its budget.
+    pub fn tighten(&self, intent: &Intent) -> Intent {
+        let mut intent = intent.clone();
+        if let Some(cost) = self.max_cost {
+            intent.budget.max_cost = Some(intent.budget.max_cost.map_or(cost, |max| max.min(cost)));
+        }
+        if let Some(latency) = self.max_latency_ms {
+            intent.budget.max_duration_ms =
+                Some(intent.budget.max_duration_ms.map_or(latency, |max| max.min(latency)));
+        }
+        intent
+    }
+    
+    /// A builder for a fresh intent embodying this counter-offer, e.g. to
+    /// resubmit after the negotiation failed.
+    ///
+    /// Starting from the original's spec, the budget is tightened to
+    /// `max_cost`, `max_latency_ms` becomes a `latency` SLA in ms,
+    /// `additional_constraints` are parsed as constraint expressions, and
+    /// adjusted preference weights are renormalized to sum to 1.0. The new
+    /// intent's parent is the original.
+    pub fn apply_to(&self, intent: &Intent) -> Result<IntentBuilder> {
+        let invalid = |message: String| OrpheonError::IntentInvalid { intent_id: Some(intent.id), message };
+        let mut spec = self.tighten(intent);
+        spec.budget.max_duration_ms = intent.budget.max_duration_ms;
+        
+        if let Some(latency) = self.max_latency_ms {
+            let existing = spec.constraints.iter_mut().find_map(|c| match c {
+                Constraint::Sla { metric, threshold, unit } if metric == "latency" && unit == "ms" => Some(threshold),
+                _ => None,
+            });
+            match existing {
+                Some(threshold) => *threshold = (*threshold).min(latency),
+                None => spec.constraints.push(Constraint::Sla {
+                    metric: "latency".to_string(),
+                    threshold: latency,
+                    unit: "ms".to_string(),
+                }),
+            }
+        }
+        
+        for (idx, expression) in self.additional_constraints.iter().enumerate() {
+            let constraint = parse_constraint(expression)
+                .map_err(|e| invalid(format!("additional_constraints[{}]: {}", idx, e)))?;
+            spec.constraints.push(constraint);
+        }
+        
+        for adjustment in &self.preference_adjustments {
+            if !(0.0..=1.0).contains(&adjustment.weight) {
+                return Err(invalid(format!(
+                    "weight {} for preference {} is outside 0.0..=1.0",
+                    adjustment.weight, adjustment.objective
+                )));
+            }
+            let preference = spec
+                .preferences
+                .iter_mut()
+                .find(|p| p.objective == adjustment.objective)
+                .ok_or_else(|| invalid(format!("intent has no preference for {}", adjustment.objective)))?;
+            preference.weight = adjustment.weight;
+        }
+        
+        let mut builder = spec.to_builder().parent(intent.id);
+        if !self.preference_adjustments.is_empty() {
+            builder = builder.normalize_preferences();
+        }
+        Ok(builder)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orpheon_core::{Plan, PlanningStrategy};
+    use orpheon_core::{Budget, PlanningStrategy};
+
+    fn itemized_plan(intent_id: Uuid) -> Plan {
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("allocate", "allocate_gpu").with_cost(7.25).with_duration(400));
+        plan.add_step(
+            Step::new("deploy", "deploy_workload")
+                .with_cost(0.1)
+                .with_parameters(serde_json::json!({ "provider": "node-a" })),
+        );
+        plan.add_step(Step::new("verify", "health_check").with_cost(0.2));
+        plan
+    }
 
     #[test]
     fn test_proposal_creation() {
@@ -193,6 +505,7 @@ mod tests {
         let plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
         
         let proposal = Proposal::new(intent_id, plan)
+            .unwrap()
             .with_sla("latency_p99", 200.0, "ms");
         
         assert_eq!(proposal.intent_id, intent_id);
@@ -200,6 +513,166 @@ mod tests {
         assert!(!proposal.is_expired());
     }
 
+    #[test]
+    fn test_line_items_sum_to_quoted_cost() {
+        let intent_id = Uuid::new_v4();
+        let proposal = Proposal::new(intent_id, itemized_plan(intent_id)).unwrap();
+        
+        assert_eq!(proposal.line_items.len(), 3);
+        assert!((proposal.line_items_total() - proposal.quoted_cost).abs() <= LINE_ITEM_EPSILON);
+        assert_eq!(proposal.line_items[0].unit_cost, 7.25);
+        assert_eq!(proposal.line_items[0].estimated_duration_ms, 400);
+        assert_eq!(proposal.line_items[1].provider.as_deref(), Some("node-a"));
+        assert_eq!(proposal.line_items[2].provider, None);
+    }
+
+    #[test]
+    fn test_priced_quotes_in_the_intent_currency() {
+        let intent_id = Uuid::new_v4();
+        let rates = StaticRates::new("USD").with_rate("EUR", 1.25);
+        let pricing = Pricing::new("USD", Arc::new(rates));
+        
+        let proposal = Proposal::priced(intent_id, itemized_plan(intent_id), "EUR", &pricing).unwrap();
+        assert_eq!(proposal.currency, "EUR");
+        assert!((proposal.line_items[0].unit_cost - 5.8).abs() < 1e-9);
+        assert!((proposal.quoted_cost - 7.55 / 1.25).abs() < 1e-9);
+        assert_eq!(proposal.plan.estimated_cost, itemized_plan(intent_id).estimated_cost);
+        
+        let exact = proposal.in_minor_units().unwrap();
+        assert_eq!(exact.quoted_cost_minor, Some(MoneyAmount::new(604, "EUR")));
+        
+        let err = Proposal::priced(intent_id, itemized_plan(intent_id), "CHF", &pricing).unwrap_err();
+        assert!(matches!(err, OrpheonError::IntentInvalid { intent_id: Some(id), .. } if id == intent_id));
+        assert_eq!(Proposal::new(intent_id, itemized_plan(intent_id)).unwrap().currency, "USD");
+    }
+    
+    #[test]
+    fn test_mismatched_plan_cost_rejected() {
+        let intent_id = Uuid::new_v4();
+        let mut plan = itemized_plan(intent_id);
+        plan.estimated_cost += 1.0;
+        
+        let err = Proposal::new(intent_id, plan).unwrap_err();
+        assert!(err.to_string().contains("line items total"));
+    }
+
+    #[test]
+    fn test_line_items_sum_exactly_in_minor_units() {
+        let intent_id = Uuid::new_v4();
+        let proposal = Proposal::new(intent_id, itemized_plan(intent_id)).unwrap().in_minor_units().unwrap();
+
+        assert_eq!(proposal.quoted_cost_minor, Some(MoneyAmount::usd_cents(755)));
+        assert_eq!(proposal.quoted_cost, 7.55);
+        assert_eq!(proposal.line_items[1].unit_cost_minor, Some(MoneyAmount::usd_cents(10)));
+        assert!(proposal.check_line_items().is_ok());
+
+        // A float total off by less than the tolerance no longer passes
+        let mut tampered = proposal.clone();
+        tampered.line_items[2].unit_cost_minor = Some(MoneyAmount::usd_cents(21));
+        let err = tampered.check_line_items().unwrap_err();
+        assert!(err.to_string().contains("total 7.56 USD but quoted cost is 7.55 USD"), "{}", err);
+
+        let mut partial = proposal;
+        partial.line_items[0].unit_cost_minor = None;
+        assert!(partial.check_line_items().is_err());
+    }
+
+    #[test]
+    fn test_float_only_proposals_still_parse() {
+        let intent_id = Uuid::new_v4();
+        let proposal = Proposal::new(intent_id, itemized_plan(intent_id)).unwrap();
+        let json = serde_json::to_value(&proposal).unwrap();
+        assert!(json.get("quoted_cost_minor").is_none());
+        assert!(json["line_items"][0].get("unit_cost_minor").is_none());
+
+        let decoded: Proposal = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.quoted_cost_minor, None);
+        assert_eq!(decoded.line_items, proposal.line_items);
+        assert!(decoded.check_line_items().is_ok());
+    }
+
+    #[test]
+    fn test_non_finite_plan_cost_rejected() {
+        let intent_id = Uuid::new_v4();
+        let mut plan = itemized_plan(intent_id);
+        plan.steps[1].estimated_cost = f64::NAN;
+        plan.estimated_cost = f64::NAN;
+        
+        // NaN compares unequal to everything, so the sum check alone lets it through
+        let err = Proposal::new(intent_id, plan).unwrap_err();
+        assert!(matches!(err, OrpheonError::NonFinite { .. }));
+        assert_eq!(err.to_string(), "quoted cost of the proposal must be a finite number, got NaN");
+        
+        let mut plan = itemized_plan(intent_id);
+        plan.steps[0].estimated_cost = f64::INFINITY;
+        let err = Proposal::new(intent_id, plan).unwrap_err();
+        assert_eq!(err.to_string(), "cost of line item allocate must be a finite number, got inf");
+    }
+
+    #[test]
+    fn test_counter_offer_tightens_budget() {
+        let mut intent = Intent::builder().kind("deploy").budget(Budget::usd(20.0)).build().unwrap();
+        intent.budget.max_duration_ms = Some(500);
+        
+        let counter = CounterOffer::new(Uuid::new_v4()).with_max_cost(5.0).with_max_latency(1_000);
+        let replan = counter.tighten(&intent);
+        assert_eq!(replan.budget.max_cost, Some(5.0));
+        assert_eq!(replan.budget.max_duration_ms, Some(500));
+        assert_eq!(replan.id, intent.id);
+    }
+
+    #[test]
+    fn test_counter_offer_builds_resubmission() {
+        let intent = Intent::builder()
+            .kind("train")
+            .budget(Budget::usd(20.0))
+            .sla("latency", 800, "ms")
+            .minimize("cost", 0.5)
+            .maximize("reliability", 0.5)
+            .metadata(serde_json::json!({ "team": "search" }))
+            .build()
+            .unwrap();
+        
+        let mut counter = CounterOffer::new(Uuid::new_v4()).with_max_cost(12.0).with_max_latency(500);
+        counter.additional_constraints = vec!["region in ['eu-west']".to_string(), "gpu_count < 8".to_string()];
+        counter.preference_adjustments = vec![PreferenceAdjustment { objective: "cost".to_string(), weight: 1.0 }];
+        
+        let resubmit = counter.apply_to(&intent).unwrap().build().unwrap();
+        assert_ne!(resubmit.id, intent.id);
+        assert_eq!(resubmit.parent_id, Some(intent.id));
+        assert_eq!(resubmit.kind, "train");
+        assert_eq!(resubmit.budget.max_cost, Some(12.0));
+        assert_eq!(resubmit.budget.max_duration_ms, intent.budget.max_duration_ms);
+        assert_eq!(resubmit.metadata["team"], "search");
+        
+        // The existing latency SLA is tightened rather than duplicated
+        assert_eq!(resubmit.constraints.len(), 3);
+        assert!(matches!(&resubmit.constraints[0], Constraint::Sla { threshold: 500, .. }));
+        assert!(matches!(&resubmit.constraints[1], Constraint::GeoFence { allowed: true, .. }));
+        assert!(matches!(&resubmit.constraints[2], Constraint::ResourceLimit { limit, .. } if *limit == 8.0));
+        
+        // 1.0 and 0.5 renormalized
+        let weights: Vec<f32> = resubmit.preferences.iter().map(|p| p.weight).collect();
+        assert!((weights[0] - 2.0 / 3.0).abs() < 1e-6 && (weights[1] - 1.0 / 3.0).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_counter_offer_rejects_invalid_adjustments() {
+        let intent = Intent::builder().kind("train").minimize("cost", 1.0).build().unwrap();
+        
+        let mut counter = CounterOffer::new(Uuid::new_v4());
+        counter.additional_constraints = vec!["latency < soon".to_string()];
+        let err = counter.apply_to(&intent).unwrap_err().to_string();
+        assert!(err.contains("additional_constraints[0]") && err.contains("latency < soon"), "{}", err);
+        
+        let mut counter = CounterOffer::new(Uuid::new_v4());
+        counter.preference_adjustments = vec![PreferenceAdjustment { objective: "latency".to_string(), weight: 0.5 }];
+        assert!(counter.apply_to(&intent).unwrap_err().to_string().contains("no preference for latency"));
+        
+        counter.preference_adjustments = vec![PreferenceAdjustment { objective: "cost".to_string(), weight: 1.5 }];
+        assert!(counter.apply_to(&intent).unwrap_err().to_string().contains("outside 0.0..=1.0"));
+    }
+    
     #[test]
     fn test_counter_offer() {
         let proposal_id = Uuid::new_v4();
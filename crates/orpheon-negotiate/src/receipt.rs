@@ -0,0 +1,151 @@
+//! Signed records of accepted proposals.
+//!
+//! When a client accepts a proposal, the node issues an
+//! [`AcceptanceReceipt`] holding the agreed terms. The receipt carries the
+//! [`Plan::content_hash`](orpheon_core::Plan::content_hash) of the accepted
+//! plan and the final artifact records the receipt's ID, so the agreement
+//! and the outcome can be checked against each other.
+
+use chrono::{DateTime, Utc};
+use orpheon_core::crypto::{self, hex_encode};
+use orpheon_core::{NodeKey, OrpheonError, Result, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::protocol::{Proposal, SlaGuarantee};
+
+/// The terms a client agreed to, signed by the node that offered them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptanceReceipt {
+    /// Unique ID for this receipt.
+    pub id: Uuid,
+
+    /// The negotiated intent.
+    pub intent_id: Uuid,
+
+    /// The accepted proposal.
+    pub proposal_id: Uuid,
+
+    /// Version of the accepted proposal.
+    pub proposal_version: u32,
+
+    /// The accepted plan.
+    pub plan_id: Uuid,
+
+    /// Content hash of the accepted plan.
+    pub plan_hash: String,
+
+    /// Agreed cost.
+    pub quoted_cost: f64,
+
+    /// Currency of `quoted_cost`.
+    pub currency: String,
+
+    /// SLA guarantees agreed to.
+    pub sla_guarantees: Vec<SlaGuarantee>,
+
+    /// When the client accepted.
+    pub accepted_at: DateTime<Utc>,
+
+    /// Whether the node accepted on the client's behalf, under the
+    /// auto-accept bounds it submitted.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_accepted_by_policy: bool,
+
+    /// Node signature over [`AcceptanceReceipt::content_hash`].
+    #[serde(default)]
+    pub signature: Option<Signature>,
+}
+
+impl AcceptanceReceipt {
+    /// An unsigned receipt for accepting `proposal` now.
+    pub fn for_proposal(proposal: &Proposal) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            intent_id: proposal.intent_id,
+            proposal_id: proposal.id,
+            proposal_version: proposal.version,
+            plan_id: proposal.plan.id,
+            plan_hash: proposal.plan.content_hash(),
+            quoted_cost: proposal.quoted_cost,
+            currency: proposal.currency.clone(),
+            sla_guarantees: proposal.sla_guarantees.clone(),
+            accepted_at: Utc::now(),
+            auto_accepted_by_policy: false,
+            signature: None,
+        }
+    }
+
+    /// Hash of the receipt, excluding the signature (hex-encoded SHA-256).
+    ///
+    /// This is the payload a node signs.
+    pub fn content_hash(&self) -> String {
+        let mut unsigned = self.clone();
+        unsigned.signature = None;
+
+        let json = serde_json::to_string(&unsigned).unwrap_or_default();
+        hex_encode(Sha256::digest(json.as_bytes()))
+    }
+
+    /// Sign the receipt with `key`, replacing any earlier signature.
+    pub fn sign(&mut self, key: &NodeKey) {
+        self.signature = None;
+        self.signature = Some(key.sign_hash(&self.content_hash()));
+    }
+
+    /// Check that the receipt was signed by `public_key` and has not
+    /// changed since.
+    pub fn verify_signature(&self, public_key: &str) -> Result<()> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| OrpheonError::CryptoError("receipt is not signed".to_string()))?;
+        if !signature.public_key.eq_ignore_ascii_case(public_key) {
+            return Err(OrpheonError::CryptoError("receipt was signed by a different key".to_string()));
+        }
+        crypto::verify_ed25519(public_key, self.content_hash().as_bytes(), &signature.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::{Plan, PlanningStrategy, Step};
+
+    fn proposal() -> Proposal {
+        let intent_id = Uuid::new_v4();
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(2.5));
+        Proposal::new(intent_id, plan).unwrap().with_sla("latency", 200.0, "ms")
+    }
+
+    #[test]
+    fn test_receipt_records_the_terms() {
+        let proposal = proposal();
+        let receipt = AcceptanceReceipt::for_proposal(&proposal);
+
+        assert_eq!(receipt.proposal_id, proposal.id);
+        assert_eq!(receipt.plan_id, proposal.plan.id);
+        assert_eq!(receipt.plan_hash, proposal.plan.content_hash());
+        assert_eq!(receipt.quoted_cost, 2.5);
+        assert_eq!(receipt.currency, "USD");
+        assert_eq!(receipt.sla_guarantees[0].metric, "latency");
+        assert!(receipt.signature.is_none());
+    }
+
+    #[test]
+    fn test_signature_detects_tampering() {
+        let key = NodeKey::generate();
+        let mut receipt = AcceptanceReceipt::for_proposal(&proposal());
+        receipt.sign(&key);
+        receipt.verify_signature(&key.public_key_hex()).unwrap();
+
+        let other = NodeKey::generate();
+        assert!(receipt.verify_signature(&other.public_key_hex()).is_err());
+
+        receipt.quoted_cost = 1.0;
+        let err = receipt.verify_signature(&key.public_key_hex()).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+}
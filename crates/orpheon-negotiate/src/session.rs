@@ -1,14 +1,26 @@
 //! Negotiation session management.
 
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use orpheon_core::{Intent, OrpheonError, Plan, Result};
+use orpheon_core::{Intent, NodeKey, OrpheonError, Plan, Result};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
-use crate::protocol::{CounterOffer, NegotiationMessage, Proposal};
+use crate::protocol::{CounterOffer, NegotiationMessage, Pricing, Proposal};
+use crate::receipt::AcceptanceReceipt;
+
+/// Reason sent to the client when a session times out.
+pub const TIMEOUT_REASON: &str = "negotiation timed out";
+
+/// Proposals and counter-offers a session keeps by default; older ones are
+/// dropped first.
+pub const DEFAULT_HISTORY_CAP: usize = 16;
 
 /// State of a negotiation session.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,6 +42,72 @@ pub enum NegotiationState {
     Executing,
 }
 
+impl NegotiationState {
+    /// Returns true once the negotiation has been resolved one way or another.
+    pub fn is_resolved(&self) -> bool {
+        matches!(
+            self,
+            NegotiationState::Accepted
+                | NegotiationState::Rejected
+                | NegotiationState::TimedOut
+                | NegotiationState::Executing
+        )
+    }
+}
+
+/// A round transition or outcome of a negotiation session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// A proposal was sent, opening `round`.
+    ProposalSent { round: u32 },
+    /// The client countered the proposal of `round`.
+    Countered { round: u32 },
+    /// The session was accepted, rejected or timed out after `rounds`
+    /// rounds, `elapsed_ms` after it started.
+    Resolved { state: NegotiationState, rounds: u32, elapsed_ms: u64 },
+}
+
+/// Receives the [`SessionEvent`]s of the sessions it is attached to.
+///
+/// Events are delivered while the session's state is locked, so they
+/// arrive in order; observers must not call back into the session.
+#[async_trait]
+pub trait SessionObserver: Send + Sync {
+    /// Called once per event of a session negotiating `intent`.
+    async fn observe(&self, intent: &Intent, event: SessionEvent);
+}
+
+/// What a session remembers about a past proposal, without its plan.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposalSummary {
+    /// The proposal's ID.
+    pub id: Uuid,
+    
+    /// The proposal's version.
+    pub version: u32,
+    
+    /// Quoted cost for execution.
+    pub quoted_cost: f64,
+    
+    /// When the proposal was sent.
+    pub proposed_at: DateTime<Utc>,
+}
+
+/// A proposal in a session's history.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    proposal: Arc<Proposal>,
+    proposed_at: DateTime<Utc>,
+}
+
+/// Push onto a history, dropping the oldest entries beyond `cap`.
+fn push_capped<T>(history: &mut VecDeque<T>, item: T, cap: usize) {
+    history.push_back(item);
+    while history.len() > cap {
+        history.pop_front();
+    }
+}
+
 /// A negotiation session between client and server.
 pub struct NegotiationSession {
     /// Unique ID for this session.
@@ -41,14 +119,17 @@ pub struct NegotiationSession {
     /// Current state of the negotiation.
     state: Arc<RwLock<NegotiationState>>,
     
-    /// Current proposal (if any).
-    current_proposal: Arc<RwLock<Option<Proposal>>>,
+    /// Current proposal (if any), shared with the history.
+    current_proposal: Arc<RwLock<Option<Arc<Proposal>>>>,
+    
+    /// Most recent proposals, oldest first; the current one is always kept.
+    proposal_history: Arc<RwLock<VecDeque<HistoryEntry>>>,
     
-    /// History of proposals.
-    proposal_history: Arc<RwLock<Vec<Proposal>>>,
+    /// Most recent counter-offers, oldest first.
+    counter_history: Arc<RwLock<VecDeque<CounterOffer>>>,
     
-    /// History of counter-offers.
-    counter_history: Arc<RwLock<Vec<CounterOffer>>>,
+    /// How many proposals and counter-offers the histories keep.
+    history_cap: usize,
     
     /// When the session started.
     pub started_at: DateTime<Utc>,
@@ -65,13 +146,31 @@ pub struct NegotiationSession {
     /// Channel for outgoing messages.
     outgoing_tx: mpsc::Sender<NegotiationMessage>,
     
-    /// Channel for incoming messages.
-    incoming_rx: Arc<RwLock<mpsc::Receiver<NegotiationMessage>>>,
+    /// Keeps the incoming channel open; nothing reads it yet.
+    _incoming_rx: Arc<RwLock<mpsc::Receiver<NegotiationMessage>>>,
+    
+    /// Told about round transitions and the outcome.
+    observer: Option<Arc<dyn SessionObserver>>,
+    
+    /// Signs acceptance receipts; they are issued unsigned without one.
+    signer: Option<Arc<NodeKey>>,
+    
+    /// Receipt issued when the client accepted.
+    receipt: Arc<RwLock<Option<AcceptanceReceipt>>>,
+    
+    /// Currency plans are costed in and how to quote in the intent's.
+    pricing: Pricing,
 }
 
 impl NegotiationSession {
     /// Create a new negotiation session.
     pub fn new(intent: Intent, timeout_seconds: u64, max_rounds: u32) -> (Self, mpsc::Sender<NegotiationMessage>, mpsc::Receiver<NegotiationMessage>) {
+        Self::with_timeout(intent, Duration::from_secs(timeout_seconds), max_rounds)
+    }
+    
+    /// Create a new negotiation session with a precise timeout.
+    pub fn with_timeout(intent: Intent, timeout: Duration, max_rounds: u32) -> (Self, mpsc::Sender<NegotiationMessage>, mpsc::Receiver<NegotiationMessage>) {
+        let timeout = chrono::Duration::from_std(timeout).unwrap_or(chrono::Duration::MAX);
         let (outgoing_tx, outgoing_rx) = mpsc::channel(100);
         let (incoming_tx, incoming_rx) = mpsc::channel(100);
         
@@ -80,19 +179,63 @@ impl NegotiationSession {
             intent,
             state: Arc::new(RwLock::new(NegotiationState::Pending)),
             current_proposal: Arc::new(RwLock::new(None)),
-            proposal_history: Arc::new(RwLock::new(Vec::new())),
-            counter_history: Arc::new(RwLock::new(Vec::new())),
+            proposal_history: Arc::new(RwLock::new(VecDeque::new())),
+            counter_history: Arc::new(RwLock::new(VecDeque::new())),
+            history_cap: DEFAULT_HISTORY_CAP,
             started_at: Utc::now(),
-            timeout_at: Utc::now() + chrono::Duration::seconds(timeout_seconds as i64),
+            timeout_at: Utc::now() + timeout,
             max_rounds,
             round: Arc::new(RwLock::new(0)),
             outgoing_tx,
-            incoming_rx: Arc::new(RwLock::new(incoming_rx)),
+            _incoming_rx: Arc::new(RwLock::new(incoming_rx)),
+            observer: None,
+            signer: None,
+            receipt: Arc::new(RwLock::new(None)),
+            pricing: Pricing::default(),
         };
         
         (session, incoming_tx, outgoing_rx)
     }
     
+    /// Report the session's round transitions and outcome to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn SessionObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+    
+    /// Keep at most `cap` proposals and counter-offers, dropping the oldest
+    /// first; at least the current proposal is always kept.
+    pub fn with_history_cap(mut self, cap: usize) -> Self {
+        self.history_cap = cap.max(1);
+        self
+    }
+    
+    /// Sign acceptance receipts with `key`.
+    pub fn with_signer(mut self, key: Arc<NodeKey>) -> Self {
+        self.signer = Some(key);
+        self
+    }
+    
+    /// Plans are costed as `pricing` says; proposals are quoted in the
+    /// intent's budget currency. Without this, plans are taken to be in USD.
+    pub fn with_pricing(mut self, pricing: Pricing) -> Self {
+        self.pricing = pricing;
+        self
+    }
+    
+    async fn notify(&self, event: SessionEvent) {
+        if let Some(observer) = &self.observer {
+            observer.observe(&self.intent, event).await;
+        }
+    }
+    
+    /// Report that the session was resolved as `state`.
+    async fn notify_resolved(&self, state: NegotiationState) {
+        let rounds = *self.round.read().await;
+        let elapsed_ms = (Utc::now() - self.started_at).num_milliseconds().max(0) as u64;
+        self.notify(SessionEvent::Resolved { state, rounds, elapsed_ms }).await;
+    }
+    
     /// Get the current state.
     pub async fn state(&self) -> NegotiationState {
         *self.state.read().await
@@ -100,7 +243,12 @@ impl NegotiationSession {
     
     /// Get the current proposal.
     pub async fn current_proposal(&self) -> Option<Proposal> {
-        self.current_proposal.read().await.clone()
+        self.current_proposal.read().await.as_deref().cloned()
+    }
+    
+    /// The receipt issued when the client accepted, if it has.
+    pub async fn receipt(&self) -> Option<AcceptanceReceipt> {
+        self.receipt.read().await.clone()
     }
     
     /// Get the current round number.
@@ -116,6 +264,39 @@ impl NegotiationSession {
     /// Send a proposal to the client.
     pub async fn send_proposal(&self, plan: Plan) -> Result<Proposal> {
         let mut state = self.state.write().await;
+        self.offer(&mut state, plan).await
+    }
+    
+    /// Replace the accepted proposal with a fresh one for `plan`, e.g.
+    /// because its price moved before execution began. The acceptance and
+    /// its receipt are void; the client has to accept again.
+    ///
+    /// Fails unless the session is accepted. If no rounds are left the
+    /// session is rejected instead.
+    pub async fn reopen(&self, plan: Plan) -> Result<Proposal> {
+        let mut state = self.state.write().await;
+        
+        if *state != NegotiationState::Accepted {
+            return Err(OrpheonError::NegotiationRejected {
+                intent_id: self.intent.id,
+                reason: format!("Only an accepted negotiation can be reopened ({:?})", *state),
+            });
+        }
+        *self.receipt.write().await = None;
+        
+        match self.offer(&mut state, plan).await {
+            Ok(proposal) => Ok(proposal),
+            Err(e) => {
+                *state = NegotiationState::Rejected;
+                self.notify_resolved(NegotiationState::Rejected).await;
+                let _ = self.outgoing_tx.send(NegotiationMessage::Failed { reason: e.to_string() }).await;
+                Err(e)
+            }
+        }
+    }
+    
+    /// Open a new round with a proposal for `plan`, under the state lock.
+    async fn offer(&self, state: &mut NegotiationState, plan: Plan) -> Result<Proposal> {
         let mut round = self.round.write().await;
         
         if *round >= self.max_rounds {
@@ -125,36 +306,66 @@ impl NegotiationSession {
             });
         }
         
+        let budget = &self.intent.budget;
+        let currency = match &budget.max_cost_minor {
+            Some(limit) => &limit.currency,
+            None if budget.currency.is_empty() => &self.pricing.base,
+            None => &budget.currency,
+        };
+        let mut proposal = Proposal::priced(self.intent.id, plan, currency, &self.pricing)?;
+        // Quote exactly for clients that set an exact limit
+        if budget.max_cost_minor.is_some() {
+            proposal = proposal.in_minor_units()?;
+        }
         *round += 1;
         
-        let proposal = Proposal::new(self.intent.id, plan);
-        
         // Store proposal
+        let shared = Arc::new(proposal.clone());
         {
             let mut current = self.current_proposal.write().await;
-            *current = Some(proposal.clone());
+            *current = Some(Arc::clone(&shared));
         }
         
         {
             let mut history = self.proposal_history.write().await;
-            history.push(proposal.clone());
+            let entry = HistoryEntry { proposal: shared, proposed_at: Utc::now() };
+            push_capped(&mut history, entry, self.history_cap);
         }
         
         // Send message
         self.outgoing_tx
-            .send(NegotiationMessage::Offer(proposal.clone()))
+            .send(NegotiationMessage::Offer(Box::new(proposal.clone())))
             .await
             .map_err(|_| OrpheonError::Internal("Failed to send proposal".to_string()))?;
         
         *state = NegotiationState::ProposalSent;
+        self.notify(SessionEvent::ProposalSent { round: *round }).await;
         
         Ok(proposal)
     }
     
     /// Process an acceptance from the client.
     pub async fn accept(&self, proposal_id: Uuid) -> Result<Uuid> {
+        self.accept_with(proposal_id, false).await
+    }
+    
+    /// Accept a proposal on the client's behalf, because it is within the
+    /// bounds the client agreed to in advance; the receipt says so.
+    pub async fn accept_by_policy(&self, proposal_id: Uuid) -> Result<Uuid> {
+        self.accept_with(proposal_id, true).await
+    }
+    
+    async fn accept_with(&self, proposal_id: Uuid, by_policy: bool) -> Result<Uuid> {
         let mut state = self.state.write().await;
         
+        // Whichever transition takes the state lock first wins
+        if state.is_resolved() {
+            return Err(OrpheonError::NegotiationRejected {
+                intent_id: self.intent.id,
+                reason: format!("Negotiation already resolved ({:?})", *state),
+            });
+        }
+        
         let current = self.current_proposal.read().await;
         let proposal = current.as_ref().ok_or_else(|| {
             OrpheonError::NegotiationRejected {
@@ -177,7 +388,17 @@ impl NegotiationSession {
             });
         }
         
+        proposal.check_line_items()?;
+        
+        let mut receipt = AcceptanceReceipt::for_proposal(proposal);
+        receipt.auto_accepted_by_policy = by_policy;
+        if let Some(key) = &self.signer {
+            receipt.sign(key);
+        }
+        *self.receipt.write().await = Some(receipt.clone());
+        
         *state = NegotiationState::Accepted;
+        self.notify_resolved(NegotiationState::Accepted).await;
         
         let execution_id = Uuid::new_v4();
         
@@ -185,6 +406,7 @@ impl NegotiationSession {
             .send(NegotiationMessage::Confirmed {
                 proposal_id,
                 execution_id,
+                receipt: Some(Box::new(receipt)),
             })
             .await
             .map_err(|_| OrpheonError::Internal("Failed to send confirmation".to_string()))?;
@@ -194,8 +416,16 @@ impl NegotiationSession {
     
     /// Process a counter-offer from the client.
     pub async fn counter(&self, counter: CounterOffer) -> Result<()> {
+        counter.validate()?;
         let mut state = self.state.write().await;
         
+        if state.is_resolved() {
+            return Err(OrpheonError::NegotiationRejected {
+                intent_id: self.intent.id,
+                reason: format!("Negotiation already resolved ({:?})", *state),
+            });
+        }
+        
         let current = self.current_proposal.read().await;
         let proposal = current.as_ref().ok_or_else(|| {
             OrpheonError::NegotiationRejected {
@@ -214,10 +444,11 @@ impl NegotiationSession {
         // Store counter-offer
         {
             let mut history = self.counter_history.write().await;
-            history.push(counter);
+            push_capped(&mut history, counter, self.history_cap);
         }
         
         *state = NegotiationState::Countered;
+        self.notify(SessionEvent::Countered { round: *self.round.read().await }).await;
         
         Ok(())
     }
@@ -225,7 +456,16 @@ impl NegotiationSession {
     /// Reject the negotiation.
     pub async fn reject(&self, reason: String) -> Result<()> {
         let mut state = self.state.write().await;
+        
+        if state.is_resolved() {
+            return Err(OrpheonError::NegotiationRejected {
+                intent_id: self.intent.id,
+                reason: format!("Negotiation already resolved ({:?})", *state),
+            });
+        }
+        
         *state = NegotiationState::Rejected;
+        self.notify_resolved(NegotiationState::Rejected).await;
         
         self.outgoing_tx
             .send(NegotiationMessage::Failed { reason })
@@ -235,15 +475,74 @@ impl NegotiationSession {
         Ok(())
     }
     
+    /// Time the session out if it is still unresolved.
+    ///
+    /// Sends `Failed` with [`TIMEOUT_REASON`] to the client. Returns false if
+    /// another transition (e.g. an acceptance) already resolved the session.
+    pub async fn expire(&self) -> bool {
+        let mut state = self.state.write().await;
+        
+        if state.is_resolved() {
+            return false;
+        }
+        
+        *state = NegotiationState::TimedOut;
+        self.notify_resolved(NegotiationState::TimedOut).await;
+        
+        // Sent while holding the lock so no later message can overtake it
+        let _ = self.outgoing_tx
+            .send(NegotiationMessage::Failed { reason: TIMEOUT_REASON.to_string() })
+            .await;
+        
+        true
+    }
+    
+    /// Spawn a task that times the session out at `timeout_at`.
+    ///
+    /// The task resolves to true if it was the timeout that ended the session.
+    pub fn spawn_timeout_watch(self: &Arc<Self>) -> JoinHandle<bool> {
+        let session = Arc::clone(self);
+        tokio::spawn(async move {
+            let remaining = (session.timeout_at - Utc::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(remaining).await;
+            session.expire().await
+        })
+    }
+    
+    /// Mark an accepted negotiation as executing.
+    pub async fn mark_executing(&self) -> bool {
+        let mut state = self.state.write().await;
+        if *state != NegotiationState::Accepted {
+            return false;
+        }
+        *state = NegotiationState::Executing;
+        true
+    }
+    
     /// Get the last counter-offer.
     pub async fn last_counter(&self) -> Option<CounterOffer> {
         let history = self.counter_history.read().await;
-        history.last().cloned()
+        history.back().cloned()
     }
     
-    /// Get proposal history.
-    pub async fn proposal_history(&self) -> Vec<Proposal> {
-        self.proposal_history.read().await.clone()
+    /// Get the kept proposals, oldest first.
+    pub async fn proposal_history(&self) -> Vec<Arc<Proposal>> {
+        self.proposal_history.read().await.iter().map(|entry| Arc::clone(&entry.proposal)).collect()
+    }
+    
+    /// Summaries of the kept proposals, oldest first.
+    pub async fn history_summary(&self) -> Vec<ProposalSummary> {
+        self.proposal_history
+            .read()
+            .await
+            .iter()
+            .map(|entry| ProposalSummary {
+                id: entry.proposal.id,
+                version: entry.proposal.version,
+                quoted_cost: entry.proposal.quoted_cost,
+                proposed_at: entry.proposed_at,
+            })
+            .collect()
     }
 }
 
@@ -275,7 +574,7 @@ mod tests {
         let (session, _incoming_tx, mut outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
         
         let plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
-        let proposal = session.send_proposal(plan).await.unwrap();
+        session.send_proposal(plan).await.unwrap();
         
         assert_eq!(session.state().await, NegotiationState::ProposalSent);
         assert_eq!(session.current_round().await, 1);
@@ -300,4 +599,251 @@ mod tests {
         let result = session.send_proposal(plan).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_accept_rejects_unbalanced_line_items() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(3.0));
+        let proposal = session.send_proposal(plan).await.unwrap();
+        
+        Arc::make_mut(session.current_proposal.write().await.as_mut().unwrap()).line_items[0].unit_cost = 1.0;
+        
+        assert!(session.accept(proposal.id).await.is_err());
+        assert_eq!(session.state().await, NegotiationState::ProposalSent);
+    }
+
+    #[tokio::test]
+    async fn test_exact_budget_gets_exact_quote() {
+        let intent = Intent::builder().kind("test").budget(orpheon_core::Budget::usd_cents(1_000)).build().unwrap();
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(0.1));
+        plan.add_step(orpheon_core::Step::new("verify", "health_check").with_cost(0.2));
+        let proposal = session.send_proposal(plan).await.unwrap();
+        assert_eq!(proposal.quoted_cost_minor, Some(orpheon_core::MoneyAmount::usd_cents(30)));
+        
+        // The float costs still balance, but the exact ones do not
+        Arc::make_mut(session.current_proposal.write().await.as_mut().unwrap()).line_items[0].unit_cost_minor =
+            Some(orpheon_core::MoneyAmount::usd_cents(11));
+        assert!(session.accept(proposal.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proposals_quote_in_the_budget_currency() {
+        let budget = orpheon_core::Budget { currency: "EUR".to_string(), ..orpheon_core::Budget::usd(10.0) };
+        let intent = Intent::builder().kind("test").budget(budget).build().unwrap();
+        let rates = orpheon_core::StaticRates::new("USD").with_rate("EUR", 2.0);
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        let session = session.with_pricing(Pricing::new("USD", Arc::new(rates)));
+        
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(3.0));
+        let proposal = session.send_proposal(plan.clone()).await.unwrap();
+        assert_eq!((proposal.currency.as_str(), proposal.quoted_cost), ("EUR", 1.5));
+        assert_eq!(proposal.plan.estimated_cost, 3.0);
+        
+        // Without rates for the budget's currency nothing is quoted
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent, 60, 5);
+        assert!(matches!(session.send_proposal(plan).await, Err(OrpheonError::IntentInvalid { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_counter_with_non_finite_cost_rejected() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        let proposal = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        
+        let err = session.counter(CounterOffer::new(proposal.id).with_max_cost(f64::NAN)).await.unwrap_err();
+        assert!(matches!(err, OrpheonError::NonFinite { .. }));
+        assert_eq!(session.state().await, NegotiationState::ProposalSent);
+        assert!(session.counter_history.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_accept_issues_signed_receipt() {
+        let intent = create_test_intent();
+        let key = Arc::new(NodeKey::generate());
+        let (session, _incoming_tx, mut outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        let session = session.with_signer(key.clone());
+        let proposal = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        outgoing_rx.recv().await.unwrap();
+        assert!(session.receipt().await.is_none());
+        
+        session.accept(proposal.id).await.unwrap();
+        let receipt = match outgoing_rx.recv().await {
+            Some(NegotiationMessage::Confirmed { receipt: Some(receipt), .. }) => *receipt,
+            other => panic!("expected confirmation with a receipt, got {:?}", other),
+        };
+        assert_eq!(receipt.proposal_id, proposal.id);
+        assert_eq!(receipt.plan_hash, proposal.plan.content_hash());
+        assert!(!receipt.auto_accepted_by_policy);
+        receipt.verify_signature(&key.public_key_hex()).unwrap();
+        assert_eq!(session.receipt().await.unwrap().id, receipt.id);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_watch_sends_failed() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, mut outgoing_rx) =
+            NegotiationSession::with_timeout(intent.clone(), Duration::from_millis(50), 5);
+        let session = Arc::new(session);
+        session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        
+        assert!(session.spawn_timeout_watch().await.unwrap());
+        assert_eq!(session.state().await, NegotiationState::TimedOut);
+        
+        assert!(matches!(outgoing_rx.recv().await, Some(NegotiationMessage::Offer(_))));
+        match outgoing_rx.recv().await {
+            Some(NegotiationMessage::Failed { reason }) => assert_eq!(reason, TIMEOUT_REASON),
+            other => panic!("expected timeout failure, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_accept_and_timeout_race_has_one_winner() {
+        for _ in 0..20 {
+            let intent = create_test_intent();
+            let (session, _incoming_tx, mut outgoing_rx) =
+                NegotiationSession::with_timeout(intent.clone(), Duration::from_secs(60), 5);
+            let session = Arc::new(session);
+            let proposal = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+            outgoing_rx.recv().await.unwrap();
+            
+            let accepting = Arc::clone(&session);
+            let accept = tokio::spawn(async move { accepting.accept(proposal.id).await });
+            let expired = session.expire().await;
+            let accepted = accept.await.unwrap().is_ok();
+            
+            // Exactly one transition wins, and the client hears only about that one
+            assert!(accepted != expired);
+            let terminal = outgoing_rx.recv().await.unwrap();
+            if accepted {
+                assert_eq!(session.state().await, NegotiationState::Accepted);
+                assert!(matches!(terminal, NegotiationMessage::Confirmed { .. }));
+            } else {
+                assert_eq!(session.state().await, NegotiationState::TimedOut);
+                assert!(matches!(terminal, NegotiationMessage::Failed { .. }));
+            }
+            assert!(outgoing_rx.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reopen_voids_acceptance() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, mut outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 2);
+        let first = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        assert!(session.reopen(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.is_err());
+        
+        session.accept(first.id).await.unwrap();
+        let second = session.reopen(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(session.state().await, NegotiationState::ProposalSent);
+        assert!(session.receipt().await.is_none());
+        assert!(matches!(outgoing_rx.recv().await, Some(NegotiationMessage::Offer(_))));
+        assert!(matches!(outgoing_rx.recv().await, Some(NegotiationMessage::Confirmed { .. })));
+        assert!(matches!(outgoing_rx.recv().await, Some(NegotiationMessage::Offer(offer)) if offer.id == second.id));
+        
+        // Without rounds left, reopening rejects the negotiation
+        session.accept(second.id).await.unwrap();
+        assert!(session.reopen(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.is_err());
+        assert_eq!(session.state().await, NegotiationState::Rejected);
+        outgoing_rx.recv().await.unwrap();
+        assert!(matches!(outgoing_rx.recv().await, Some(NegotiationMessage::Failed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_history_is_capped_and_keeps_latest() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, mut outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 100);
+        let session = session.with_history_cap(4);
+        
+        let mut last = None;
+        for round in 1..=50 {
+            let mut plan = Plan::new(intent.id, PlanningStrategy::Deterministic);
+            plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(round as f64));
+            let proposal = session.send_proposal(plan).await.unwrap();
+            outgoing_rx.recv().await.unwrap();
+            session.counter(CounterOffer::new(proposal.id).with_max_cost(1.0)).await.unwrap();
+            
+            let history = session.proposal_history().await;
+            assert!(history.len() <= 4);
+            assert_eq!(history.last().unwrap().id, proposal.id);
+            assert!(session.counter_history.read().await.len() <= 4);
+            last = Some(proposal);
+        }
+        let last = last.unwrap();
+        
+        // The current proposal and the history share one copy
+        let history = session.proposal_history().await;
+        assert_eq!(history.len(), 4);
+        assert!(Arc::ptr_eq(history.last().unwrap(), session.current_proposal.read().await.as_ref().unwrap()));
+        assert_eq!(session.last_counter().await.unwrap().proposal_id, last.id);
+        
+        let summary = session.history_summary().await;
+        let costs: Vec<f64> = summary.iter().map(|entry| entry.quoted_cost).collect();
+        assert_eq!(costs, vec![47.0, 48.0, 49.0, 50.0]);
+        assert_eq!(summary.iter().map(|entry| entry.id).collect::<Vec<_>>(), history.iter().map(|p| p.id).collect::<Vec<_>>());
+        assert!(summary.windows(2).all(|pair| pair[0].proposed_at <= pair[1].proposed_at));
+        assert!(summary.iter().all(|entry| entry.version == 1));
+        
+        // The last proposal can still be accepted
+        session.accept(last.id).await.unwrap();
+        assert_eq!(session.proposal_history().await.last().unwrap().id, last.id);
+    }
+
+    #[tokio::test]
+    async fn test_zero_history_cap_keeps_current_proposal() {
+        let intent = create_test_intent();
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        let session = session.with_history_cap(0);
+        session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        let second = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        
+        let history = session.proposal_history().await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, second.id);
+    }
+
+    /// Observer that keeps the events it is told about.
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<SessionEvent>>);
+
+    #[async_trait]
+    impl SessionObserver for Recorder {
+        async fn observe(&self, _intent: &Intent, event: SessionEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_sees_rounds_and_outcome() {
+        let intent = create_test_intent();
+        let recorder = Arc::new(Recorder::default());
+        let (session, _incoming_tx, _outgoing_rx) = NegotiationSession::new(intent.clone(), 60, 5);
+        let session = session.with_observer(recorder.clone());
+        
+        let first = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        session.counter(CounterOffer::new(first.id).with_max_cost(1.0)).await.unwrap();
+        let second = session.send_proposal(Plan::new(intent.id, PlanningStrategy::Deterministic)).await.unwrap();
+        session.accept(second.id).await.unwrap();
+        
+        // A failed transition is not reported
+        assert!(session.reject("too late".to_string()).await.is_err());
+        
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(events[..3], [
+            SessionEvent::ProposalSent { round: 1 },
+            SessionEvent::Countered { round: 1 },
+            SessionEvent::ProposalSent { round: 2 },
+        ]);
+        assert!(matches!(
+            events[3..],
+            [SessionEvent::Resolved { state: NegotiationState::Accepted, rounds: 2, .. }]
+        ));
+    }
 }
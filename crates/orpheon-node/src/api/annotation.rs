@@ -0,0 +1,82 @@
+//! Operator annotations of plans and artifacts.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use orpheon_core::Plan;
+use orpheon_sdk::{Annotation, NewAnnotation};
+use uuid::Uuid;
+
+use crate::api::error::ApiError;
+use crate::api::intent::missing_resource;
+use crate::state::{AnnotationTarget, AppState};
+
+/// Attach a note to a plan, or to one of its steps.
+///
+/// Answers `404` for unknown plans and `400` for an empty author or text,
+/// or a step the plan does not have.
+pub async fn annotate_plan(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<NewAnnotation>,
+) -> Result<(StatusCode, Json<Annotation>), ApiError> {
+    let plan = state.get_plan(id).await.ok_or_else(|| {
+        ApiError::from((StatusCode::NOT_FOUND, format!("Plan {} not found", id)))
+    })?;
+    let annotation = annotation(&plan, request)?;
+
+    state
+        .annotate(AnnotationTarget::Plan(id), annotation.clone())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(annotation)))
+}
+
+/// Attach a note to an intent's execution artifact, or to one of its steps.
+///
+/// Answers `404` for unknown intents, `409` with the intent's status while
+/// it has no artifact yet, and `400` for an empty author or text, or a step
+/// the artifact's plan does not have.
+pub async fn annotate_artifact(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<NewAnnotation>,
+) -> Result<(StatusCode, Json<Annotation>), ApiError> {
+    let Some(artifact) = state.get_artifact_for_intent(id).await else {
+        return Err(missing_resource(&state, id, "Artifact").await);
+    };
+    let annotation = annotation(&artifact.final_plan, request)?;
+
+    state
+        .annotate(AnnotationTarget::Artifact(artifact.id), annotation.clone())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(annotation)))
+}
+
+/// Check `request` against the plan it is about.
+fn annotation(plan: &Plan, request: NewAnnotation) -> Result<Annotation, (StatusCode, String)> {
+    if request.author.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Annotation author must not be empty".to_string()));
+    }
+    if request.text.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Annotation text must not be empty".to_string()));
+    }
+    if let Some(step_id) = request.target_step_id {
+        if !plan.steps.iter().any(|step| step.id == step_id) {
+            return Err((StatusCode::BAD_REQUEST, format!("Plan {} has no step {}", plan.id, step_id)));
+        }
+    }
+
+    Ok(Annotation {
+        id: Uuid::new_v4(),
+        target_step_id: request.target_step_id,
+        author: request.author,
+        text: request.text,
+        labels: request.labels,
+        created_at: Utc::now(),
+    })
+}
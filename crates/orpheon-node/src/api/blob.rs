@@ -0,0 +1,87 @@
+//! Full payloads of truncated execution events.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Get the full data of an execution event whose payload was truncated.
+///
+/// Only available when the node keeps blobs (see
+/// [`EventDataConfig::store_blobs`](crate::config::EventDataConfig::store_blobs)).
+pub async fn get_event_blob(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let blob = state
+        .get_event_blob(id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    
+    blob.map(Json).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("No stored data for event {}", id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    
+    use async_trait::async_trait;
+    use orpheon_core::{Intent, Step};
+    use sha2::{Digest, Sha256};
+    
+    use crate::config::{EventDataConfig, NodeConfig};
+    use crate::engine::{StepContext, StepExecutor, StepOutput};
+    use crate::testing::TestNode;
+    
+    /// Executor whose steps return a large response body.
+    struct ChattyExecutor;
+    
+    #[async_trait]
+    impl StepExecutor for ChattyExecutor {
+        async fn execute(&self, step: &Step, _ctx: &StepContext) -> Result<StepOutput, String> {
+            let body = format!("{}:{}", step.action, "x".repeat(4096));
+            Ok(StepOutput::new(1).with_data(serde_json::json!({ "response": body })))
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_oversized_event_data_is_stored_as_blob() {
+        let config = NodeConfig {
+            event_data: EventDataConfig { max_bytes: 1024, store_blobs: true },
+            ..Default::default()
+        };
+        let node = TestNode::spawn(config).await;
+        node.state.set_step_executor(Arc::new(ChattyExecutor)).await;
+        
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        node.state.store_intent(intent).await;
+        
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap();
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while node.state.get_artifact_for_intent(intent_id).await.is_none() {
+            assert!(tokio::time::Instant::now() < deadline, "intent did not complete");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        
+        let (artifact, report) = client.get_verified_artifact(intent_id).await.unwrap();
+        assert!(report.is_valid());
+        let event = artifact.trace.iter().find(|e| e.is_truncated()).expect("no truncated event");
+        assert!(serde_json::to_string(&event.data).unwrap().len() <= 1024);
+        
+        // The full payload is served and matches the recorded hash
+        let blob = client.get_event_blob(event.id).await.unwrap();
+        assert!(blob["response"].as_str().unwrap().len() > 4096);
+        let hash = orpheon_core::crypto::hex_encode(Sha256::digest(serde_json::to_string(&blob).unwrap()));
+        assert_eq!(event.data["sha256"], hash.as_str());
+        
+        let missing = client.get_event_blob(uuid::Uuid::new_v4()).await;
+        assert!(matches!(missing, Err(orpheon_core::OrpheonError::NotFound { .. })));
+    }
+}
@@ -0,0 +1,192 @@
+//! Artifact bundle download.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use orpheon_core::bundle::BundleComponent;
+use orpheon_core::ArtifactBundle;
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Content type for the archive form of a bundle.
+const GZIP: &str = "application/gzip";
+
+/// Download a signed, self-contained bundle for an intent's artifact.
+///
+/// Returns JSON by default, or a tar.gz archive when the client sends
+/// `Accept: application/gzip`.
+pub async fn get_artifact_bundle(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Intent {} not found", id))
+    })?;
+    
+    if !record.status.is_terminal() {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Intent {} is still {:?}; bundles are only available for finished intents", id, record.status),
+        ));
+    }
+    
+    let artifact = state.get_artifact_for_intent(id).await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Artifact for intent {} not found", id))
+    })?;
+    
+    let bundle = ArtifactBundle::new(artifact, &state.node_key);
+    
+    let wants_gzip = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(GZIP));
+    
+    if !wants_gzip {
+        return Ok(Json(bundle).into_response());
+    }
+    
+    let archive = bundle_archive(&bundle).map_err(|e| {
+        (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build bundle archive: {}", e))
+    })?;
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, GZIP.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"bundle-{}.tar.gz\"", id),
+            ),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+/// Pack a bundle as a tar.gz with one file per component plus the
+/// manifest and detached signature.
+pub fn bundle_archive(bundle: &ArtifactBundle) -> std::io::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    
+    let manifest = serde_json::to_vec_pretty(&bundle.manifest)?;
+    append_file(&mut builder, "manifest.json", &manifest)?;
+    for component in BundleComponent::ALL {
+        append_file(&mut builder, component.file_name(), &bundle.component_bytes(component))?;
+    }
+    append_file(&mut builder, "manifest.sig", bundle.signature.as_bytes())?;
+    
+    builder.into_inner()?.finish()
+}
+
+fn append_file<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use axum::http::HeaderValue;
+    use axum_test::TestServer;
+    use orpheon_core::bundle::BundleError;
+    use orpheon_core::{Intent, IntentStatus};
+    use sha2::{Digest, Sha256};
+    
+    async fn finished_intent(state: &AppState) -> Uuid {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        let engine = std::sync::Arc::new(crate::engine::Engine::new(state.clone()));
+        tokio::spawn(engine.run());
+        
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while state.get_artifact_for_intent(intent_id).await.is_none() {
+            assert!(tokio::time::Instant::now() < deadline, "intent did not complete");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        intent_id
+    }
+    
+    #[tokio::test]
+    async fn test_bundle_verifies_and_pinpoints_corruption() {
+        let state = AppState::new();
+        let intent_id = finished_intent(&state).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        
+        let response = server.get(&format!("/api/v1/intent/{}/artifact/bundle", intent_id)).await;
+        response.assert_status_ok();
+        let bundle: ArtifactBundle = response.json();
+        
+        assert!(bundle.verify().is_ok());
+        assert_eq!(bundle.node_public_key, state.node_key.public_key_hex());
+        assert!(bundle.artifact.signature.is_some());
+        
+        let mut corrupted = bundle.clone();
+        corrupted.plan.estimated_cost += 1.0;
+        match corrupted.verify() {
+            Err(BundleError::HashMismatch { component, .. }) => assert_eq!(component, BundleComponent::Plan),
+            other => panic!("expected plan hash mismatch, got {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_bundle_as_tar_gz() {
+        let state = AppState::new();
+        let intent_id = finished_intent(&state).await;
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        
+        let response = server
+            .get(&format!("/api/v1/intent/{}/artifact/bundle", intent_id))
+            .add_header(header::ACCEPT, HeaderValue::from_static(GZIP))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_TYPE), GZIP);
+        
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(response.as_bytes().as_ref()));
+        let mut files = std::collections::HashMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            files.insert(path, data);
+        }
+        
+        let manifest: orpheon_core::bundle::BundleManifest =
+            serde_json::from_slice(&files["manifest.json"]).unwrap();
+        for entry in &manifest.components {
+            let data = &files[entry.component.file_name()];
+            let hash = orpheon_core::crypto::hex_encode(Sha256::digest(data));
+            assert_eq!(hash, entry.sha256, "{} hash mismatch", entry.component);
+        }
+        assert!(files.contains_key("manifest.sig"));
+    }
+    
+    #[tokio::test]
+    async fn test_bundle_for_unfinished_intent_conflicts() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        state.update_intent_status(intent_id, IntentStatus::Executing).await;
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/artifact/bundle", intent_id)).await;
+        response.assert_status(StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,98 @@
+//! Runtime management of fault injection rules.
+//!
+//! Every handler answers 403 unless the node was started with
+//! [`ChaosConfig::unsafe_chaos`](crate::chaos::ChaosConfig::unsafe_chaos).
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use uuid::Uuid;
+
+use crate::chaos::{FaultInjector, FaultRule, InjectedFault};
+use crate::journal::JournalEvent;
+use crate::state::AppState;
+
+fn injector(state: &AppState) -> Result<&Arc<FaultInjector>, (StatusCode, String)> {
+    state.chaos.as_ref().ok_or_else(|| {
+        (
+            StatusCode::FORBIDDEN,
+            "Chaos mode is disabled; start the node with unsafe_chaos to inject faults".to_string(),
+        )
+    })
+}
+
+/// List installed fault rules.
+pub async fn list_rules(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<InjectedFault>>, (StatusCode, String)> {
+    Ok(Json(injector(&state)?.list()))
+}
+
+/// Install a fault rule.
+pub async fn add_rule(
+    State(state): State<AppState>,
+    Json(rule): Json<FaultRule>,
+) -> Result<(StatusCode, Json<InjectedFault>), (StatusCode, String)> {
+    let injected = injector(&state)?.add(rule);
+    state.journal.append(JournalEvent::ConfigChanged { description: format!("fault rule {} added", injected.id) });
+    Ok((StatusCode::CREATED, Json(injected)))
+}
+
+/// Remove a fault rule.
+pub async fn remove_rule(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if injector(&state)?.remove(id) {
+        state.journal.append(JournalEvent::ConfigChanged { description: format!("fault rule {} removed", id) });
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("Fault rule {} not found", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+
+    use crate::chaos::FaultInjector;
+
+    #[tokio::test]
+    async fn test_rules_require_unsafe_chaos() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+
+        server.get("/api/v1/admin/chaos/rules").await.assert_status(StatusCode::FORBIDDEN);
+        let response = server
+            .post("/api/v1/admin/chaos/rules")
+            .json(&FaultRule::fail_step("deploy", "boom"))
+            .await;
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_add_list_remove_rules() {
+        let mut state = AppState::new();
+        state.enable_chaos(FaultInjector::default()).await;
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+
+        let rule = FaultRule::delay_action("deploy_workload", 2_000);
+        let response = server.post("/api/v1/admin/chaos/rules").json(&rule).await;
+        response.assert_status(StatusCode::CREATED);
+        let added: InjectedFault = response.json();
+        assert_eq!(added.rule, rule);
+
+        let rules: Vec<InjectedFault> = server.get("/api/v1/admin/chaos/rules").await.json();
+        assert_eq!(rules, vec![added.clone()]);
+
+        let path = format!("/api/v1/admin/chaos/rules/{}", added.id);
+        server.delete(&path).await.assert_status(StatusCode::NO_CONTENT);
+        server.delete(&path).await.assert_status_not_found();
+        let rules: Vec<InjectedFault> = server.get("/api/v1/admin/chaos/rules").await.json();
+        assert!(rules.is_empty());
+    }
+}
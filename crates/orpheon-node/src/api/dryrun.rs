@@ -0,0 +1,284 @@
+//! Dry runs: executing an intent's plan against no-op steps.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_core::{ExecutionArtifact, Outcome, Plan};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::engine::Engine;
+use crate::state::AppState;
+
+/// Request body for a dry run; every field is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct DryRunRequest {
+    /// Multiplier for step durations and timeouts, in `(0, 1]`; defaults
+    /// to the node's configured scale.
+    pub time_scale: Option<f64>,
+}
+
+/// How one step went in a dry run. Times are wall-clock milliseconds from
+/// the start of the run; `simulated_ms` is the step's unscaled duration.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunStep {
+    pub step_id: Uuid,
+    pub name: String,
+    /// `completed`, `failed`, `skipped` or `not_run`.
+    pub status: String,
+    pub started_at_ms: Option<u64>,
+    pub wall_ms: Option<u64>,
+    pub simulated_ms: Option<u64>,
+}
+
+/// Result of a dry run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunResponse {
+    pub intent_id: Uuid,
+    pub plan_id: Uuid,
+    pub time_scale: f64,
+    pub outcome: Outcome,
+    /// Wall-clock time from the first step starting to the last one ending.
+    pub wall_time_ms: u64,
+    /// Longest chain of dependent steps, by estimated duration, unscaled.
+    pub critical_path_ms: u64,
+    /// Most steps that were running at the same time.
+    pub realized_parallelism: usize,
+    pub steps: Vec<DryRunStep>,
+    /// The dry-run artifact, marked `execution_metadata.extra.dry_run`.
+    pub artifact: ExecutionArtifact,
+}
+
+impl DryRunResponse {
+    /// Summarize a dry-run artifact from its trace.
+    pub fn from_artifact(artifact: ExecutionArtifact) -> Self {
+        let time_scale = artifact.execution_metadata.extra["time_scale"].as_f64().unwrap_or(1.0);
+        let origin = artifact.trace.first().map(|e| e.timestamp);
+        let offset = |at: DateTime<Utc>| origin.map_or(0, |o| (at - o).num_milliseconds().max(0) as u64);
+
+        let mut spans: HashMap<Uuid, Span> = HashMap::new();
+        for event in &artifact.trace {
+            let span = spans.entry(event.step_id).or_default();
+            match event.event_type {
+                ExecutionEventType::StepStarted => span.start = Some(event.timestamp),
+                ExecutionEventType::StepCompleted => span.end(event.timestamp, "completed"),
+                ExecutionEventType::StepFailed => span.end(event.timestamp, "failed"),
+                ExecutionEventType::StepSkipped => span.status = "skipped",
+                _ => {}
+            }
+        }
+
+        let steps = artifact
+            .final_plan
+            .steps
+            .iter()
+            .map(|step| {
+                let Span { start, end, status } = spans.get(&step.id).copied().unwrap_or_default();
+                DryRunStep {
+                    step_id: step.id,
+                    name: step.name.clone(),
+                    status: status.to_string(),
+                    started_at_ms: start.map(offset),
+                    wall_ms: start.zip(end).map(|(s, e)| (e - s).num_milliseconds().max(0) as u64),
+                    simulated_ms: (status == "completed").then_some(step.estimated_duration_ms),
+                }
+            })
+            .collect();
+
+        // Sweep over step start and end times for the peak overlap
+        let mut edges: Vec<(DateTime<Utc>, i32)> = spans
+            .values()
+            .filter_map(|span| span.start.zip(span.end))
+            .flat_map(|(s, e)| [(s, 1), (e, -1)])
+            .collect();
+        edges.sort_by_key(|&(at, delta)| (at, delta));
+        let realized_parallelism = edges
+            .iter()
+            .scan(0, |running, (_, delta)| {
+                *running += delta;
+                Some(*running)
+            })
+            .max()
+            .unwrap_or(0) as usize;
+
+        Self {
+            intent_id: artifact.intent.id,
+            plan_id: artifact.final_plan.id,
+            time_scale,
+            outcome: artifact.outcome.clone(),
+            wall_time_ms: artifact.trace.last().map_or(0, |e| offset(e.timestamp)),
+            critical_path_ms: critical_path_ms(&artifact.final_plan),
+            realized_parallelism,
+            steps,
+            artifact,
+        }
+    }
+}
+
+/// When a step ran and how it ended, from its trace events.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    status: &'static str,
+}
+
+impl Default for Span {
+    fn default() -> Self {
+        Self { start: None, end: None, status: "not_run" }
+    }
+}
+
+impl Span {
+    fn end(&mut self, at: DateTime<Utc>, status: &'static str) {
+        self.end = Some(at);
+        self.status = status;
+    }
+}
+
+/// Longest chain of dependent steps, summing estimated durations.
+fn critical_path_ms(plan: &Plan) -> u64 {
+    let mut finish: HashMap<Uuid, u64> = HashMap::new();
+    // Dependencies may come after their dependents in the list; repeat
+    // until every step has a finish time (bounded for malformed plans).
+    for _ in 0..plan.steps.len() {
+        for step in &plan.steps {
+            let deps: Option<Vec<u64>> = step.dependencies.iter().map(|d| finish.get(d).copied()).collect();
+            if let Some(deps) = deps {
+                finish.insert(step.id, deps.into_iter().max().unwrap_or(0) + step.estimated_duration_ms);
+            }
+        }
+        if finish.len() == plan.steps.len() {
+            break;
+        }
+    }
+    finish.values().copied().max().unwrap_or(0)
+}
+
+/// Dry-run an intent's current plan.
+///
+/// The plan runs through the engine with every step a no-op that waits
+/// its estimated duration times `time_scale`. The intent is not changed;
+/// the artifact is kept with the intent's other dry runs.
+pub async fn dry_run_intent(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    request: Option<Json<DryRunRequest>>,
+) -> Result<Json<DryRunResponse>, (StatusCode, String)> {
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    let time_scale = request.time_scale.unwrap_or(state.dry_run.time_scale);
+    if !(time_scale > 0.0 && time_scale <= 1.0) {
+        return Err((StatusCode::BAD_REQUEST, format!("time_scale must be in (0, 1], got {}", time_scale)));
+    }
+
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Intent {} not found", id))
+    })?;
+    let plan = state.get_plan_for_intent(id).await.ok_or_else(|| {
+        (StatusCode::CONFLICT, format!("Intent {} has no plan to dry-run yet", id))
+    })?;
+
+    let artifact = Engine::new(state).dry_run(&record.intent, plan, time_scale).await;
+    Ok(Json(DryRunResponse::from_artifact(artifact)))
+}
+
+/// List an intent's dry runs, oldest first.
+pub async fn list_dry_runs(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DryRunResponse>>, (StatusCode, String)> {
+    if state.get_intent(id).await.is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("Intent {} not found", id)));
+    }
+    let runs = state.get_dry_runs(id).await;
+    Ok(Json(runs.into_iter().map(DryRunResponse::from_artifact).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::{Intent, IntentStatus, PlanningStrategy, Step};
+    use axum_test::TestServer;
+    use serde_json::json;
+
+    /// A → (B, C) → D, with C the longer branch.
+    fn diamond(intent_id: Uuid) -> Plan {
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        let a = Step::new("a", "allocate_resource").with_duration(1_000);
+        let b = Step::new("b", "provision_compute").with_duration(2_000).depends_on(a.id);
+        let c = Step::new("c", "configure_network").with_duration(3_000).depends_on(a.id);
+        let d = Step::new("d", "deploy_workload").with_duration(1_000).depends_on(b.id).depends_on(c.id);
+        plan.steps = vec![a, b, c, d];
+        plan
+    }
+
+    async fn stored_intent(state: &AppState) -> Uuid {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        state.store_plan(diamond(intent_id)).await;
+        intent_id
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_of_diamond_follows_critical_path() {
+        let state = AppState::new();
+        state.pause_engine();
+        let intent_id = stored_intent(&state).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let response = server
+            .post(&format!("/api/v1/intent/{}/dryrun", intent_id))
+            .json(&json!({ "time_scale": 0.2 }))
+            .await;
+        response.assert_status_ok();
+        let report: DryRunResponse = response.json();
+
+        // Critical path a → c → d is 5 s, 1 s at 0.2×; running steps one
+        // after another would take 1.4 s
+        assert_eq!(report.critical_path_ms, 5_000);
+        assert!(report.wall_time_ms >= 1_000, "{}", report.wall_time_ms);
+        assert!(report.wall_time_ms < 1_300, "{}", report.wall_time_ms);
+        assert_eq!(report.realized_parallelism, 2);
+        assert!(report.steps.iter().all(|s| s.status == "completed"));
+        assert_eq!(report.steps[2].simulated_ms, Some(3_000));
+        assert!(report.outcome.is_success());
+        assert_eq!(report.artifact.execution_metadata.extra["dry_run"], true);
+        assert!(report.artifact.signature.is_none());
+
+        // The intent itself is untouched
+        let record = state.get_intent(intent_id).await.unwrap();
+        assert_eq!(record.status, IntentStatus::Received);
+        assert!(record.artifact_id.is_none());
+        assert!(state.artifacts.read().await.is_empty());
+
+        let runs: Vec<DryRunResponse> = server.get(&format!("/api/v1/intent/{}/dryruns", intent_id)).await.json();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].artifact.id, report.artifact.id);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_needs_a_plan() {
+        let state = AppState::new();
+        state.pause_engine();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+
+        let response = server.post(&format!("/api/v1/intent/{}/dryrun", intent_id)).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let response = server
+            .post(&format!("/api/v1/intent/{}/dryrun", intent_id))
+            .json(&json!({ "time_scale": 0.0 }))
+            .await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        server.post(&format!("/api/v1/intent/{}/dryrun", Uuid::new_v4())).await.assert_status(StatusCode::NOT_FOUND);
+    }
+}
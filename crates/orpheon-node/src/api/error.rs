@@ -0,0 +1,95 @@
+//! Structured error responses.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use orpheon_core::{ConstraintConflict, FieldError, IntentStatus, OrpheonError};
+use serde::{Deserialize, Serialize};
+
+/// An error response body: `{ "error": { "code", "message", "fields" } }`.
+///
+/// `fields` lists each invalid request field, and is omitted when the error
+/// is not about particular fields; `conflicts` likewise lists contradictory
+/// constraints (code `constraint_conflict`). `intent_status` is set when a resource
+/// of an existing intent is not available yet (`409`, code `not_ready`) or
+/// its status does not allow an action (`409`, code `invalid_transition`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+
+    /// Machine-readable kind of error, e.g. `invalid_fields`.
+    pub code: String,
+
+    /// Human-readable description.
+    pub message: String,
+
+    /// Per-field problems, in request order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+
+    /// Constraints that can never be satisfied together.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ConstraintConflict>,
+
+    /// Status of the intent the request was about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent_status: Option<IntentStatus>,
+}
+
+impl ApiError {
+    /// An error without field details.
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        Self { status, code: code.to_string(), message: message.into(), fields: Vec::new(), conflicts: Vec::new(), intent_status: None }
+    }
+
+    /// A `400` naming the request fields that failed validation.
+    pub fn invalid_fields(message: impl Into<String>, fields: Vec<FieldError>) -> Self {
+        Self { fields, ..Self::new(StatusCode::BAD_REQUEST, "invalid_fields", message) }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status;
+        (status, Json(serde_json::json!({ "error": self }))).into_response()
+    }
+}
+
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        let code = status.canonical_reason().unwrap_or("error").to_lowercase().replace(' ', "_");
+        Self::new(status, &code, message)
+    }
+}
+
+/// Intent validation failures are the client's fault; a resource that is
+/// not ready yet, or an action the status does not allow, is a conflict
+/// with the intent's current state.
+impl From<OrpheonError> for ApiError {
+    fn from(error: OrpheonError) -> Self {
+        let message = error.to_string();
+        match error {
+            OrpheonError::FieldsInvalid { errors, conflicts, .. } => Self {
+                conflicts,
+                ..Self::invalid_fields(message, errors)
+            },
+            OrpheonError::NotReady { status, .. } => Self {
+                intent_status: Some(status),
+                ..Self::new(StatusCode::CONFLICT, "not_ready", message)
+            },
+            OrpheonError::InvalidTransition { status, .. } => Self {
+                intent_status: Some(status),
+                ..Self::new(StatusCode::CONFLICT, "invalid_transition", message)
+            },
+            OrpheonError::ConstraintConflict { conflicts, .. } => Self {
+                conflicts,
+                ..Self::new(StatusCode::BAD_REQUEST, "constraint_conflict", message)
+            },
+            OrpheonError::NonFinite { .. } => Self::new(StatusCode::BAD_REQUEST, "non_finite", message),
+            _ => Self::new(StatusCode::BAD_REQUEST, "invalid_intent", message),
+        }
+    }
+}
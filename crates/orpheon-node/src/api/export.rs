@@ -0,0 +1,195 @@
+//! Bulk exports of intents and artifacts as newline-delimited JSON.
+//!
+//! Warehouses pull every record at once, so rather than paging the listing
+//! API they get one record per line in a single streamed response. Only the
+//! IDs of matching records are collected up front; the records themselves
+//! are serialized a chunk at a time, under a short read lock, as the body
+//! is sent, so memory stays flat however many there are. Responses are
+//! gzip-compressed for clients that accept it.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use orpheon_sdk::ExportFilter;
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Content type of an export, one JSON record per line.
+const NDJSON: &str = "application/x-ndjson";
+
+/// Records serialized per chunk of the response body.
+pub const EXPORT_CHUNK_RECORDS: usize = 128;
+
+/// Counts of what exports have serialized, to keep an eye on their memory use.
+#[derive(Debug, Default)]
+pub struct ExportStats {
+    /// Records exported so far.
+    records: AtomicU64,
+
+    /// Most records serialized into a single chunk.
+    largest_chunk: AtomicUsize,
+}
+
+impl ExportStats {
+    /// Records exported so far.
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    /// Most records held serialized at once by any export.
+    pub fn largest_chunk(&self) -> usize {
+        self.largest_chunk.load(Ordering::Relaxed)
+    }
+
+    fn chunk_sent(&self, records: usize) {
+        self.records.fetch_add(records as u64, Ordering::Relaxed);
+        self.largest_chunk.fetch_max(records, Ordering::Relaxed);
+    }
+}
+
+/// Stream every intent matching the filter, terminal ones only unless
+/// `include_active` is set, oldest first.
+pub async fn export_intents(State(state): State<AppState>, Query(filter): Query<ExportFilter>) -> Response {
+    let ids = state.intent_ids_for_export(&filter).await;
+    ndjson(state, ids, |state, ids| async move { state.intents_ndjson(&ids).await })
+}
+
+/// Stream every stored artifact matching the filter, oldest first.
+pub async fn export_artifacts(State(state): State<AppState>, Query(filter): Query<ExportFilter>) -> Response {
+    let ids = state.artifact_ids_for_export(&filter).await;
+    ndjson(state, ids, |state, ids| async move { state.artifacts_ndjson(&ids).await })
+}
+
+/// A streamed NDJSON response of the records with `ids`, each chunk of
+/// them rendered by `render` into its line count and lines.
+fn ndjson<F, Fut>(state: AppState, ids: Vec<Uuid>, render: F) -> Response
+where
+    F: Fn(AppState, Vec<Uuid>) -> Fut + Send + 'static,
+    Fut: Future<Output = (usize, String)> + Send + 'static,
+{
+    let stats = state.exports.clone();
+    let chunks = futures::stream::iter(ids)
+        .chunks(EXPORT_CHUNK_RECORDS)
+        .then(move |chunk| render(state.clone(), chunk))
+        .map(move |(records, lines)| {
+            stats.chunk_sent(records);
+            Ok::<_, Infallible>(lines)
+        });
+
+    ([(header::CONTENT_TYPE, NDJSON)], Body::from_stream(chunks)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    use axum::http::HeaderValue;
+    use axum_test::TestServer;
+    use chrono::{Duration, Utc};
+    use futures::TryStreamExt;
+    use orpheon_core::{ExecutionArtifact, Intent, IntentStatus, Outcome, Plan, PlanningStrategy};
+    use orpheon_sdk::{IntentRecordExport, OrpheonClient};
+
+    use crate::testing::TestNode;
+
+    /// 300 intents created a second apart, every third one still active;
+    /// the finished ones have artifacts.
+    async fn synthetic(state: &AppState) -> chrono::DateTime<Utc> {
+        let start = Utc::now() - Duration::hours(1);
+        for i in 0..300 {
+            let mut intent = Intent::builder().kind("deploy").build().unwrap();
+            intent.created_at = start + Duration::seconds(i);
+            state.store_intent(intent.clone()).await;
+            if i % 3 != 0 {
+                let plan = state.store_plan(Plan::new(intent.id, PlanningStrategy::Heuristic)).await;
+                state.update_intent_status(intent.id, IntentStatus::Executing).await;
+                state.store_artifact(ExecutionArtifact::new(intent, plan, Outcome::Success)).await;
+            }
+        }
+        start
+    }
+
+    fn lines(body: &str) -> Vec<serde_json::Value> {
+        body.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_export_streams_matching_intents() {
+        let state = AppState::new();
+        let start = synthetic(&state).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let response = server.get("/api/v1/export/intents").await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_TYPE), NDJSON);
+        let finished = lines(&response.text());
+        assert_eq!(finished.len(), 200);
+        assert!(finished.iter().all(|r| r["status"] == "complete" && r["artifact_id"].is_string()));
+
+        let all = lines(&server.get("/api/v1/export/intents").add_query_param("include_active", true).await.text());
+        assert_eq!(all.len(), 300);
+        let created: Vec<&str> = all.iter().map(|r| r["intent"]["created_at"].as_str().unwrap()).collect();
+        assert!(created.windows(2).all(|w| w[0] < w[1]));
+
+        let recent = server
+            .get("/api/v1/export/intents")
+            .add_query_param("created_after", (start + Duration::milliseconds(149_500)).to_rfc3339())
+            .await
+            .text();
+        assert_eq!(lines(&recent).len(), 100);
+
+        // Records were serialized a chunk at a time, never all at once
+        assert_eq!(state.exports.records(), 600);
+        assert_eq!(state.exports.largest_chunk(), EXPORT_CHUNK_RECORDS);
+    }
+
+    #[tokio::test]
+    async fn test_export_artifacts_gzipped() {
+        let state = AppState::new();
+        synthetic(&state).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let response = server
+            .get("/api/v1/export/artifacts")
+            .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"))
+            .await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_ENCODING), "gzip");
+
+        let mut body = String::new();
+        flate2::read::GzDecoder::new(&response.as_bytes()[..]).read_to_string(&mut body).unwrap();
+        let artifacts: Vec<ExecutionArtifact> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(artifacts.len(), 200);
+        assert!(artifacts.iter().all(|a| a.outcome.is_success()));
+    }
+
+    #[tokio::test]
+    async fn test_sdk_streams_export() {
+        let state = AppState::new();
+        let start = synthetic(&state).await;
+        // Keep the active intents as they are
+        state.pause_engine();
+        let node = TestNode::with_state(state).await;
+        let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+        let filter = ExportFilter::default().with_active().created_after(start + Duration::milliseconds(99_500));
+        let records: Vec<IntentRecordExport> = client.export_intents(&filter).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(records.len(), 200);
+        assert!(records.iter().all(|r| r.intent.created_at > start + Duration::seconds(99)));
+        assert_eq!(records.iter().filter(|r| r.status == IntentStatus::Received).count(), 66);
+
+        let artifacts: Vec<ExecutionArtifact> =
+            client.export_artifacts(&ExportFilter::default()).await.unwrap().try_collect().await.unwrap();
+        assert_eq!(artifacts.len(), 200);
+    }
+}
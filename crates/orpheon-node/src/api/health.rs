@@ -1,7 +1,11 @@
 //! Health check endpoint.
 
-use axum::Json;
-use serde::Serialize;
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::backlog::BacklogWatermarks;
+use crate::state::AppState;
 
 /// Health check response.
 #[derive(Serialize)]
@@ -11,6 +15,26 @@ pub struct HealthResponse {
     pub protocol: String,
 }
 
+/// Readiness check response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadyResponse {
+    /// Whether the node accepts new work.
+    pub ready: bool,
+    /// Capabilities intents may require of this node.
+    pub capabilities: Vec<String>,
+    /// The oldest intent waiting in each status.
+    #[serde(default)]
+    pub backlog: BacklogWatermarks,
+}
+
+/// Capabilities advertised by the node.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    /// The node's federation ID, if it has one.
+    pub node_id: Option<String>,
+    pub capabilities: Vec<String>,
+}
+
 /// Health check endpoint.
 pub async fn health_check() -> Json<HealthResponse> {
     Json(HealthResponse {
@@ -19,3 +43,44 @@ pub async fn health_check() -> Json<HealthResponse> {
         protocol: "orpheon/1.0".to_string(),
     })
 }
+
+/// Readiness check: `503` once the node is shutting down.
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadyResponse>) {
+    let ready = !*state.shutdown_signal().borrow();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let backlog = state.backlog_index.watermarks(Utc::now());
+    (status, Json(ReadyResponse { ready, capabilities: state.capabilities.clone(), backlog }))
+}
+
+/// List the capabilities intents may require of this node.
+pub async fn capabilities(State(state): State<AppState>) -> Json<CapabilitiesResponse> {
+    Json(CapabilitiesResponse {
+        node_id: state.federation.config().node_id.clone(),
+        capabilities: state.capabilities.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+
+    #[tokio::test]
+    async fn test_capabilities_are_advertised() {
+        let mut state = AppState::new();
+        state.capabilities = vec!["capability:gpu".to_string(), "region:us-east-1".to_string()];
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let advertised: CapabilitiesResponse = server.get("/api/v1/capabilities").await.json();
+        assert_eq!(advertised.capabilities, state.capabilities);
+
+        let response = server.get("/health/ready").await;
+        response.assert_status_ok();
+        let ready: ReadyResponse = response.json();
+        assert!(ready.ready);
+        assert_eq!(ready.capabilities, state.capabilities);
+
+        state.begin_shutdown();
+        server.get("/health/ready").await.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -1,18 +1,33 @@
 //! Intent API endpoints.
 
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use orpheon_core::{Budget, Constraint, Intent, IntentBuilder, IntentStatus, Preference, TimeWindow};
-use serde::{Deserialize, Serialize};
+use orpheon_core::{
+    codec, Budget, Constraint, ConstraintConflict, ConstraintRegistry, FieldError, Intent, IntentLimits, IntentStatus,
+    IntentSummary, OrpheonError, Preference, Provenance, ResourceType, ValidationMode, CLIENT_HEADER, FORWARD_HOPS_HEADER, SOURCE_HEADER,
+};
+use orpheon_sdk::{Annotated, Annotation};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 use uuid::Uuid;
 
-use crate::state::AppState;
+use crate::api::error::ApiError;
+use crate::federation::{self, FederationError, ForwardedIntent};
+use crate::kinds::ORIGINAL_KIND_KEY;
+use crate::lineage::{Lineage, LineageEntry, LineageError};
+use crate::negotiation::NegotiationOptions;
+use crate::state::{AnnotationTarget, AppState, IntentRecord};
 
 /// Request to submit a new intent.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SubmitIntentRequest {
     /// The kind of intent.
     pub kind: String,
@@ -21,6 +36,10 @@ pub struct SubmitIntentRequest {
     #[serde(default)]
     pub constraints: Vec<ConstraintInput>,
     
+    /// Best-effort constraints for the intent.
+    #[serde(default)]
+    pub soft_constraints: Vec<ConstraintInput>,
+    
     /// Preferences for the intent.
     #[serde(default)]
     pub preferences: Vec<PreferenceInput>,
@@ -31,29 +50,247 @@ pub struct SubmitIntentRequest {
     /// Metadata.
     #[serde(default)]
     pub metadata: serde_json::Value,
+    
+    /// Labels to group and filter intents by, e.g. `{"team": "ml"}`.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    
+    /// Scale preference weights to sum to 1.0 instead of rejecting them.
+    #[serde(default)]
+    pub normalize_weights: bool,
+    
+    /// Negotiate the plan over `/ws/negotiate/:id` before executing it.
+    #[serde(default)]
+    pub negotiation: Option<NegotiationOptions>,
+    
+    /// How fixable problems are treated; lenient unless set.
+    #[serde(default = "lenient")]
+    pub validation: ValidationMode,
+    
+    /// Intent this one is a sub-goal of, which must be stored on this node
+    /// and still running.
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
+fn lenient() -> ValidationMode {
+    ValidationMode::Lenient
+}
+
+impl SubmitIntentRequest {
+    /// Build the intent and validate it in the requested mode.
+    pub fn into_validated(self, limits: &IntentLimits, registry: &ConstraintRegistry) -> orpheon_core::Result<Intent> {
+        let mode = self.validation;
+        let mut intent = self.into_intent()?;
+        intent.validate_with_mode(limits, Some(registry), mode)?;
+        Ok(intent)
+    }
+
+    /// Build the core intent described by this request.
+    pub fn into_intent(self) -> orpheon_core::Result<Intent> {
+        let mut builder = Intent::builder().kind(&self.kind);
+        if let Some(parent_id) = self.parent_id {
+            builder = builder.parent(parent_id);
+        }
+        for (key, value) in self.labels {
+            builder = builder.label(key, value);
+        }
+        
+        // Add constraints
+        for c in self.constraints {
+            builder = builder.constraint(c.into());
+        }
+        for c in self.soft_constraints {
+            builder = builder.soft_constraint(c.into());
+        }
+        
+        // Add preferences
+        for p in self.preferences {
+            let direction = if p.direction == "minimize" {
+                orpheon_core::intent::OptimizationDirection::Minimize
+            } else {
+                orpheon_core::intent::OptimizationDirection::Maximize
+            };
+        
+            builder = builder.preference(Preference {
+                objective: p.objective,
+                direction,
+                weight: p.weight,
+            });
+        }
+        
+        // Add budget
+        if let Some(b) = self.budget {
+            let budget = Budget {
+                max_cost: b.max_cost,
+                max_cost_minor: None,
+                currency: b.currency.map_or_else(|| "USD".to_string(), |c| c.to_ascii_uppercase()),
+                max_duration_ms: b.max_duration_ms,
+                max_retries: b.max_retries.unwrap_or(3),
+                resource_limits: b.resource_limits,
+            };
+            builder = builder.budget(budget);
+        }
+        
+        // Add metadata
+        if !self.metadata.is_null() {
+            builder = builder.metadata(self.metadata);
+        }
+        
+        if self.normalize_weights {
+            builder = builder.normalize_preferences();
+        }
+        
+        builder.build()
+    }
+}
+
+/// Request to submit a complete intent document, as built by `IntentBuilder`.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FullIntentRequest {
+    /// The intent, in its core serialization.
+    pub intent: Intent,
+    
+    /// Negotiate the plan over `/ws/negotiate/:id` before executing it.
+    #[serde(default)]
+    pub negotiation: Option<NegotiationOptions>,
+    
+    /// How fixable problems are treated; lenient unless set.
+    #[serde(default = "lenient")]
+    pub validation: ValidationMode,
+}
+
+impl FullIntentRequest {
+    /// Prepare the submitted intent for storage.
+    ///
+    /// Unsigned intents get a fresh server-side id and creation time, and
+    /// their currency code is upper-cased. Signed
+    /// intents are kept exactly as submitted and must carry a valid signature.
+    pub fn into_intent(self) -> orpheon_core::Result<Intent> {
+        let mut intent = self.intent;
+        if intent.signature.is_some() {
+            if !intent.verify_signature()? {
+                return Err(OrpheonError::CryptoError("signature does not match".to_string()));
+            }
+        } else {
+            intent.id = Uuid::new_v4();
+            intent.created_at = chrono::Utc::now();
+            intent.budget.currency = intent.budget.currency.to_ascii_uppercase();
+        }
+        Ok(intent)
+    }
+}
+
+/// Body of the submit endpoint: a full intent document under `intent`, or
+/// the simplified legacy shape.
+#[derive(Debug, JsonSchema)]
+#[schemars(untagged)]
+pub enum SubmitIntentBody {
+    /// `{ "intent": { ... } }`
+    Full(FullIntentRequest),
+    /// The simplified [`SubmitIntentRequest`] shape.
+    Simple(SubmitIntentRequest),
+}
+
+impl SubmitIntentBody {
+    /// Negotiation options requested with the submission.
+    pub fn negotiation(&self) -> Option<NegotiationOptions> {
+        match self {
+            SubmitIntentBody::Full(req) => req.negotiation.clone(),
+            SubmitIntentBody::Simple(req) => req.negotiation.clone(),
+        }
+    }
+    
+    /// Build the intent to store.
+    pub fn into_intent(self) -> orpheon_core::Result<Intent> {
+        match self {
+            SubmitIntentBody::Full(req) => req.into_intent(),
+            SubmitIntentBody::Simple(req) => req.into_intent(),
+        }
+    }
+    
+    /// Build the intent to store and validate it in the requested mode,
+    /// normalizing its preference weights unless validation is strict.
+    pub fn into_validated(self, limits: &IntentLimits, registry: &ConstraintRegistry) -> orpheon_core::Result<Intent> {
+        let mode = match &self {
+            SubmitIntentBody::Full(req) => req.validation,
+            SubmitIntentBody::Simple(req) => req.validation,
+        };
+        let mut intent = self.into_intent()?;
+        intent.validate_with_mode(limits, Some(registry), mode)?;
+        Ok(intent)
+    }
+}
+
+impl<'de> Deserialize<'de> for SubmitIntentBody {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        
+        // Dispatch on the `intent` key so each shape reports its own errors
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if value.get("intent").is_some() {
+            FullIntentRequest::deserialize(value).map(SubmitIntentBody::Full).map_err(D::Error::custom)
+        } else {
+            SubmitIntentRequest::deserialize(value).map(SubmitIntentBody::Simple).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ConstraintInput {
     StateMatch { expression: String },
     ResourceLimit { resource: String, limit: f64 },
     Sla { metric: String, threshold: u64, unit: String },
+    Deadline { by: chrono::DateTime<chrono::Utc> },
+    Provider { node_id: String },
+    GeoFence { regions: Vec<String>, allowed: bool },
+    DependsOn { intent_id: Uuid, require_success: bool },
 }
 
-#[derive(Debug, Deserialize)]
+impl From<ConstraintInput> for Constraint {
+    fn from(input: ConstraintInput) -> Self {
+        match input {
+            ConstraintInput::StateMatch { expression } => {
+                Constraint::StateMatch { expression }
+            }
+            ConstraintInput::ResourceLimit { resource, limit } => {
+                Constraint::ResourceLimit { resource, limit }
+            }
+            ConstraintInput::Sla { metric, threshold, unit } => {
+                Constraint::Sla { metric, threshold, unit }
+            }
+            ConstraintInput::Deadline { by } => {
+                Constraint::Deadline { by }
+            }
+            ConstraintInput::Provider { node_id } => {
+                Constraint::Provider { node_id }
+            }
+            ConstraintInput::GeoFence { regions, allowed } => {
+                Constraint::GeoFence { regions, allowed }
+            }
+            ConstraintInput::DependsOn { intent_id, require_success } => {
+                Constraint::DependsOn { intent_id, require_success }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct PreferenceInput {
     pub objective: String,
     pub direction: String,
     pub weight: f32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct BudgetInput {
     pub max_cost: Option<f64>,
     pub currency: Option<String>,
     pub max_duration_ms: Option<u64>,
     pub max_retries: Option<u32>,
+    #[serde(default)]
+    pub resource_limits: Vec<(ResourceType, f64)>,
 }
 
 /// Response after submitting an intent.
@@ -62,6 +299,24 @@ pub struct SubmitIntentResponse {
     pub id: Uuid,
     pub status: String,
     pub message: String,
+    /// Absolute WebSocket URL of the intent's event stream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_url: Option<String>,
+    /// Things the submitter should fix, e.g. a deprecated kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Result of validating an intent without submitting it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateIntentResponse {
+    pub valid: bool,
+    /// Pairs of constraints that can never both be satisfied.
+    pub conflicts: Vec<ConstraintConflict>,
+    /// Fields with out-of-range values.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    pub error: Option<String>,
 }
 
 /// Response with intent details.
@@ -74,72 +329,184 @@ pub struct IntentResponse {
     pub artifact_id: Option<Uuid>,
     pub error: Option<String>,
     pub created_at: String,
+    /// Seq of the latest status change, as carried by stream updates.
+    pub seq: u64,
+    /// Percentage of steps that succeeded, for partially complete intents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_rate: Option<u8>,
+    /// When a deferred intent still waiting to start will begin executing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_for: Option<String>,
+    /// Whether the node accepted the plan under the client's auto-accept bounds.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub auto_accepted_by_policy: bool,
+    /// Who submitted the intent and from where.
+    pub provenance: Provenance,
+    /// Kind, constraints, budget and window at a glance.
+    pub summary: IntentSummary,
+    /// The intent's labels.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl From<&IntentRecord> for IntentResponse {
+    fn from(record: &IntentRecord) -> Self {
+        Self {
+            id: record.intent.id,
+            seq: record.seq(),
+            plan_id: record.plan_id(),
+            kind: record.intent.kind.clone(),
+            status: record.status.as_str().to_string(),
+            artifact_id: record.artifact_id,
+            error: record.error.clone(),
+            created_at: record.intent.created_at.to_rfc3339(),
+            success_rate: record.success_rate,
+            scheduled_for: record.scheduled_for().map(|t| t.to_rfc3339()),
+            auto_accepted_by_policy: record.auto_accepted_by_policy,
+            provenance: record.provenance.clone(),
+            summary: record.intent.summary(),
+            labels: record.intent.labels.clone(),
+        }
+    }
+}
+
+impl From<IntentRecord> for IntentResponse {
+    fn from(record: IntentRecord) -> Self {
+        Self::from(&record)
+    }
+}
+
+/// A single plan revision for an intent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanRevisionResponse {
+    pub revision: u32,
+    pub plan_id: Uuid,
+    pub estimated_cost: f64,
+    pub estimated_latency_ms: u64,
+    pub steps: usize,
+    pub created_at: String,
+    /// Whether this is the intent's current plan.
+    pub active: bool,
+    /// Whether a newer revision replaced this one.
+    pub superseded: bool,
+    /// Whether this revision was handed to execution.
+    pub executed: bool,
+}
+
+/// Response listing all plan revisions for an intent.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanRevisionsResponse {
+    pub intent_id: Uuid,
+    pub active_plan_id: Option<Uuid>,
+    pub executed_plan_id: Option<Uuid>,
+    pub revisions: Vec<PlanRevisionResponse>,
+}
+
+fn federation_error(e: FederationError) -> (StatusCode, String) {
+    let status = match e {
+        FederationError::LoopDetected { .. } => StatusCode::LOOP_DETECTED,
+        FederationError::UnknownPeer(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        FederationError::Peer { source: OrpheonError::NotFound { .. }, .. } => StatusCode::NOT_FOUND,
+        FederationError::Peer { .. } => StatusCode::BAD_GATEWAY,
+    };
+    (status, e.to_string())
+}
+
+/// Number of times a request has been forwarded between nodes.
+fn forward_hops(headers: &HeaderMap) -> Result<u32, (StatusCode, String)> {
+    match headers.get(FORWARD_HOPS_HEADER) {
+        None => Ok(0),
+        Some(value) => value.to_str().ok().and_then(|v| v.parse().ok()).ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, format!("Invalid {} header", FORWARD_HOPS_HEADER))
+        }),
+    }
+}
+
+/// WebSocket URL of an intent's event stream: under the configured public
+/// URL if there is one, otherwise on the host the request was sent to.
+fn stream_url(state: &AppState, headers: &HeaderMap, intent_id: Uuid) -> Option<String> {
+    let base = match &state.public_ws_url {
+        Some(base) => base.clone(),
+        None => format!("ws://{}", headers.get(header::HOST)?.to_str().ok()?),
+    };
+    Some(format!("{}/ws/intent/{}", base, intent_id))
 }
 
 /// Submit a new intent.
+///
+/// Intents whose `Provider` constraint names a known peer are forwarded to
+/// that peer and mirrored here (see [`crate::federation`]).
 pub async fn submit_intent(
     State(state): State<AppState>,
-    Json(req): Json<SubmitIntentRequest>,
-) -> Result<(StatusCode, Json<SubmitIntentResponse>), (StatusCode, String)> {
-    // Build the intent
-    let mut builder = Intent::builder().kind(&req.kind);
-    
-    // Add constraints
-    for c in req.constraints {
-        let constraint = match c {
-            ConstraintInput::StateMatch { expression } => {
-                Constraint::StateMatch { expression }
-            }
-            ConstraintInput::ResourceLimit { resource, limit } => {
-                Constraint::ResourceLimit { resource, limit }
-            }
-            ConstraintInput::Sla { metric, threshold, unit } => {
-                Constraint::Sla { metric, threshold, unit }
-            }
-        };
-        builder = builder.constraint(constraint);
+    remote: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Json(req): Json<SubmitIntentBody>,
+) -> Result<(StatusCode, Json<SubmitIntentResponse>), ApiError> {
+    let negotiation = req.negotiation();
+    let mut intent = req.into_validated(&state.intent_limits, state.constraint_registry())?;
+    state.base_budget(&intent)?;
+    let warnings = canonicalize_kind(&state, &mut intent, chrono::Utc::now()).await?;
+    if let Some(Err(message)) = negotiation.as_ref().map(|options| options.check_budget(&intent.budget)) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "invalid_negotiation_options", message));
     }
-    
-    // Add preferences
-    for p in req.preferences {
-        let direction = if p.direction == "minimize" {
-            orpheon_core::intent::OptimizationDirection::Minimize
-        } else {
-            orpheon_core::intent::OptimizationDirection::Maximize
-        };
-        
-        builder = builder.preference(Preference {
-            objective: p.objective,
-            direction,
-            weight: p.weight,
-        });
-    }
-    
-    // Add budget
-    if let Some(b) = req.budget {
-        let budget = Budget {
-            max_cost: b.max_cost,
-            currency: b.currency.unwrap_or_else(|| "USD".to_string()),
-            max_duration_ms: b.max_duration_ms,
-            max_retries: b.max_retries.unwrap_or(3),
-        };
-        builder = builder.budget(budget);
+    if state.require_signatures && intent.signature.is_none() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "signature_required",
+            "This node only accepts intents signed by their issuer",
+        ));
     }
-    
-    // Add metadata
-    if !req.metadata.is_null() {
-        builder = builder.metadata(req.metadata);
+    // The parent and dependencies of an intent a peer forwarded live on
+    // that peer
+    if !headers.contains_key(FORWARD_HOPS_HEADER) {
+        check_parent(&state, &intent).await?;
+        check_dependencies(&state, &intent).await?;
     }
     
-    // Build the intent
-    let intent = builder.build().map_err(|e| {
-        (StatusCode::BAD_REQUEST, e.to_string())
-    })?;
-    
     let intent_id = intent.id;
+    let stream_url = stream_url(&state, &headers, intent_id);
+    let provenance = provenance(&headers, remote.map(|ConnectInfo(addr)| addr));
+    
+    // Intents pinned to a peer, or needing capabilities only a peer has,
+    // run there
+    let peer = match state.federation.target_peer(&intent) {
+        Some(peer) => Some(peer),
+        None if !intent.missing_capabilities(&state.capabilities).is_empty() => {
+            state.federation.capable_peer(&intent.requirements()).await
+        }
+        None => None,
+    };
+    if let Some(peer) = peer {
+        if negotiation.is_some() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "negotiation_unsupported",
+                format!("Negotiation is not supported for intents forwarded to node {}", peer),
+            ));
+        }
+        let hops = forward_hops(&headers)?;
+        let forwarded = state
+            .federation
+            .forward(&peer, intent.clone(), hops)
+            .await
+            .map_err(federation_error)?;
+        state.store_forwarded_intent(intent, forwarded.clone(), provenance).await;
+        federation::spawn_mirror(state, intent_id, forwarded);
+        
+        return Ok((
+            StatusCode::CREATED,
+            Json(SubmitIntentResponse {
+                id: intent_id,
+                status: "received".to_string(),
+                message: format!("Intent forwarded to node {}", peer),
+                stream_url,
+                warnings,
+            }),
+        ));
+    }
     
     // Store the intent
-    state.store_intent(intent).await;
+    state.store_submitted_intent(intent, negotiation, provenance).await;
     
     Ok((
         StatusCode::CREATED,
@@ -147,10 +514,114 @@ pub async fn submit_intent(
             id: intent_id,
             status: "received".to_string(),
             message: "Intent submitted successfully".to_string(),
+            stream_url,
+            warnings,
         }),
     ))
 }
 
+/// Check that a submitted intent's parent is stored here and still
+/// running, and that the intent's parent chain is within
+/// [`IntentLimits::max_parent_depth`] and does not loop.
+async fn check_parent(state: &AppState, intent: &Intent) -> Result<(), ApiError> {
+    let Some(parent_id) = intent.parent_id else {
+        return Ok(());
+    };
+    state.ancestor_chain(intent).await?;
+    
+    let invalid = |message: String| ApiError::new(StatusCode::BAD_REQUEST, "invalid_parent", message);
+    match state.get_intent(parent_id).await {
+        None => Err(invalid(format!("Parent intent {} not found", parent_id))),
+        Some(parent) if parent.status.is_terminal() => Err(invalid(format!(
+            "Parent intent {} is already {}",
+            parent_id,
+            parent.status.as_str()
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Check that the intents a submitted intent depends on are stored here,
+/// and that waiting for them would not wait for the intent itself.
+async fn check_dependencies(state: &AppState, intent: &Intent) -> Result<(), ApiError> {
+    for (dependency, _) in intent.dependencies() {
+        if state.get_intent(dependency).await.is_none() {
+            return Err(ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_dependency",
+                format!("Dependency intent {} not found", dependency),
+            ));
+        }
+    }
+    state
+        .check_dependency_cycles(intent)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_dependency", e.to_string()))
+}
+
+/// Where a submission came from, by its headers and peer address.
+fn provenance(headers: &HeaderMap, remote: Option<SocketAddr>) -> Provenance {
+    let value = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    Provenance {
+        source: value(SOURCE_HEADER),
+        client: value(CLIENT_HEADER),
+        user_agent: value(header::USER_AGENT.as_str()),
+        remote_addr: remote.map(|addr| addr.to_string()),
+    }
+}
+
+/// Store an intent submitted under a kind alias under the kind's current
+/// name, returning the deprecation warning for the submitter. Signed
+/// intents cannot be renamed, so must be signed under the current name.
+async fn canonicalize_kind(
+    state: &AppState,
+    intent: &mut Intent,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<Vec<String>, ApiError> {
+    let Some(aliased) = state.resolve_kind_alias(&intent.kind).await else {
+        return Ok(Vec::new());
+    };
+    let warning = aliased
+        .check(now)
+        .map_err(|message| ApiError::new(StatusCode::BAD_REQUEST, "kind_retired", message))?;
+    if intent.signature.is_some() {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "kind_renamed",
+            format!("Kind '{}' was renamed to '{}'; sign the intent with the new kind", aliased.alias.name, aliased.kind),
+        ));
+    }
+    intent.metadata_set(&format!("/{}", ORIGINAL_KIND_KEY), &aliased.alias.name)?;
+    intent.kind = aliased.kind;
+    Ok(vec![warning])
+}
+
+/// Validate an intent without submitting it.
+///
+/// Accepts the same bodies as [`submit_intent`].
+pub async fn validate_intent(
+    State(state): State<AppState>,
+    Json(req): Json<SubmitIntentBody>,
+) -> Json<ValidateIntentResponse> {
+    let result = req
+        .into_validated(&state.intent_limits, state.constraint_registry())
+        .and_then(|intent| state.base_budget(&intent).map(|_| ()));
+    
+    let response = match result {
+        Ok(()) => ValidateIntentResponse { valid: true, conflicts: Vec::new(), fields: Vec::new(), error: None },
+        Err(e) => {
+            let error = Some(e.to_string());
+            let (conflicts, fields) = match e {
+                OrpheonError::ConstraintConflict { conflicts, .. } => (conflicts, Vec::new()),
+                OrpheonError::FieldsInvalid { errors, conflicts, .. } => (conflicts, errors),
+                _ => (Vec::new(), Vec::new()),
+            };
+            ValidateIntentResponse { valid: false, conflicts, fields, error }
+        }
+    };
+    Json(response)
+}
+
 /// Get an intent by ID.
 pub async fn get_intent(
     State(state): State<AppState>,
@@ -160,15 +631,59 @@ pub async fn get_intent(
         (StatusCode::NOT_FOUND, format!("Intent {} not found", id))
     })?;
     
-    Ok(Json(IntentResponse {
-        id: record.intent.id,
-        kind: record.intent.kind,
-        status: format!("{:?}", record.status).to_lowercase(),
-        plan_id: record.plan_id,
-        artifact_id: record.artifact_id,
-        error: record.error,
-        created_at: record.intent.created_at.to_rfc3339(),
-    }))
+    Ok(Json(IntentResponse::from(record)))
+}
+
+/// Amend an intent that has not started planning yet, replacing its
+/// constraints, preferences, budget and metadata; it keeps its ID and place
+/// in the queue.
+///
+/// Accepts the [`SubmitIntentRequest`] shape. Answers `409` with the
+/// intent's status once it has left `Received`, or if it was forwarded.
+pub async fn amend_intent(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SubmitIntentRequest>,
+) -> Result<Json<IntentResponse>, ApiError> {
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        ApiError::from((StatusCode::NOT_FOUND, format!("Intent {} not found", id)))
+    })?;
+    if let Some(forwarded) = &record.forwarded {
+        return Err(ApiError {
+            intent_status: Some(record.status),
+            ..ApiError::new(
+                StatusCode::CONFLICT,
+                "forwarded",
+                format!("Intent {} was forwarded to node {}; it cannot be amended here", id, forwarded.node_id),
+            )
+        });
+    }
+    
+    let mut intent = req.into_validated(&state.intent_limits, state.constraint_registry())?;
+    state.base_budget(&intent)?;
+    canonicalize_kind(&state, &mut intent, chrono::Utc::now()).await?;
+    intent.id = id;
+    check_dependencies(&state, &intent).await?;
+    if state.require_signatures {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "signature_required",
+            "This node only accepts intents signed by their issuer",
+        ));
+    }
+    
+    // The engine may have picked the intent up since it was read
+    match state.replace_intent(id, intent).await {
+        Ok(_) => {}
+        Err(OrpheonError::NotFound { .. }) => {
+            return Err(ApiError::from((StatusCode::NOT_FOUND, format!("Intent {} not found", id))));
+        }
+        Err(e) => return Err(e.into()),
+    }
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        ApiError::from((StatusCode::NOT_FOUND, format!("Intent {} not found", id)))
+    })?;
+    Ok(Json(IntentResponse::from(record)))
 }
 
 /// Cancel an intent.
@@ -188,53 +703,1399 @@ pub async fn cancel_intent(
         ));
     }
     
-    state.update_intent_status(id, IntentStatus::Cancelled).await;
+    if let Some(forwarded) = &record.forwarded {
+        peer_client(&state, forwarded)
+            .await?
+            .cancel(forwarded.remote_id)
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))?;
+    }
+    
+    // The intent may have finished since it was looked up
+    if !state.cancel_intent(id).await {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Intent {} is already in terminal state", id),
+        ));
+    }
     
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Get the plan for an intent.
-pub async fn get_plan(
+/// Pause an executing intent.
+///
+/// The steps already running finish, but no more start until the intent
+/// is resumed. Answers `409` with the intent's status unless it is
+/// executing.
+pub async fn pause_intent(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<orpheon_core::Plan>, (StatusCode, String)> {
-    let plan = state.get_plan_for_intent(id).await.ok_or_else(|| {
-        (StatusCode::NOT_FOUND, format!("Plan for intent {} not found", id))
+) -> Result<StatusCode, ApiError> {
+    change_execution(&state, id, "pause", IntentStatus::Executing, IntentStatus::Paused).await
+}
+
+/// Resume a paused intent from the steps it had not started yet.
+///
+/// Answers `409` with the intent's status unless it is paused.
+pub async fn resume_intent(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    change_execution(&state, id, "resume", IntentStatus::Paused, IntentStatus::Executing).await
+}
+
+/// Move an intent's execution from `from` to `to`. Forwarded intents are
+/// changed on the peer running them; the mirror picks up the new status.
+async fn change_execution(
+    state: &AppState,
+    id: Uuid,
+    action: &str,
+    from: IntentStatus,
+    to: IntentStatus,
+) -> Result<StatusCode, ApiError> {
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        ApiError::from((StatusCode::NOT_FOUND, format!("Intent {} not found", id)))
     })?;
     
-    Ok(Json(plan))
+    if let Some(forwarded) = &record.forwarded {
+        let client = peer_client(state, forwarded).await?;
+        let result = match to {
+            IntentStatus::Paused => client.pause(forwarded.remote_id).await,
+            _ => client.resume(forwarded.remote_id).await,
+        };
+        return match result {
+            Ok(()) => Ok(StatusCode::NO_CONTENT),
+            Err(OrpheonError::InvalidTransition { action, status, .. }) => {
+                Err(OrpheonError::InvalidTransition { intent_id: id, action, status }.into())
+            }
+            Err(e) => Err(peer_fetch_error(forwarded, e).into()),
+        };
+    }
+    
+    if !state.update_intent_status_if(id, from, to).await {
+        let status = state.get_intent(id).await.map_or(record.status, |r| r.status);
+        return Err(OrpheonError::InvalidTransition { intent_id: id, action: action.to_string(), status }.into());
+    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// Get the artifact for an intent.
-pub async fn get_artifact(
+async fn peer_client(
+    state: &AppState,
+    forwarded: &ForwardedIntent,
+) -> Result<orpheon_sdk::OrpheonClient, (StatusCode, String)> {
+    state.federation.client(&forwarded.node_id, 0).await.map_err(federation_error)
+}
+
+/// Where a forwarded intent lives, if `id` is a forwarded intent.
+async fn forwarded_to(state: &AppState, id: Uuid) -> Option<ForwardedIntent> {
+    state.get_intent(id).await.and_then(|record| record.forwarded)
+}
+
+fn peer_fetch_error(forwarded: &ForwardedIntent, e: OrpheonError) -> (StatusCode, String) {
+    federation_error(FederationError::Peer { node_id: forwarded.node_id.clone(), source: e })
+}
+
+/// Like [`peer_fetch_error`], but passes on a peer's `not_ready` answer
+/// for the local intent.
+fn peer_resource_error(id: Uuid, forwarded: &ForwardedIntent, e: OrpheonError) -> ApiError {
+    match e {
+        OrpheonError::NotReady { resource_type, status, .. } => {
+            OrpheonError::NotReady { intent_id: id, resource_type, status }.into()
+        }
+        e => peer_fetch_error(forwarded, e).into(),
+    }
+}
+
+/// `404` for an unknown intent, `409` with its status for one whose
+/// `resource_type` has not been produced yet.
+pub(crate) async fn missing_resource(state: &AppState, id: Uuid, resource_type: &str) -> ApiError {
+    match state.get_intent(id).await {
+        Some(record) => OrpheonError::NotReady {
+            intent_id: id,
+            resource_type: resource_type.to_string(),
+            status: record.status,
+        }
+        .into(),
+        None => (StatusCode::NOT_FOUND, format!("Intent {} not found", id)).into(),
+    }
+}
+
+/// Filter for the annotations served with a plan or artifact.
+#[derive(Debug, Default, Deserialize)]
+pub struct AnnotationQuery {
+    /// Only annotations carrying this label.
+    pub label: Option<String>,
+}
+
+impl AnnotationQuery {
+    async fn annotations(&self, state: &AppState, target: AnnotationTarget) -> Vec<Annotation> {
+        let mut annotations = state.annotations(target).await;
+        if let Some(label) = &self.label {
+            annotations.retain(|a| a.has_label(label));
+        }
+        annotations
+    }
+}
+
+/// Get the plan for an intent, with the annotations operators attached to
+/// it.
+///
+/// Answers `404` for unknown intents and `409` with the intent's status
+/// while it has no plan yet.
+pub async fn get_plan(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<orpheon_core::ExecutionArtifact>, (StatusCode, String)> {
-    let artifact = state.get_artifact_for_intent(id).await.ok_or_else(|| {
-        (StatusCode::NOT_FOUND, format!("Artifact for intent {} not found", id))
+    Query(query): Query<AnnotationQuery>,
+) -> Result<Json<Annotated<orpheon_core::Plan>>, ApiError> {
+    if let Some(forwarded) = forwarded_to(&state, id).await {
+        let client = peer_client(&state, &forwarded).await?;
+        let plan = client
+            .get_plan(forwarded.remote_id)
+            .await
+            .map_err(|e| peer_resource_error(id, &forwarded, e))?;
+        return Ok(Json(Annotated { record: plan, annotations: Vec::new() }));
+    }
+    
+    match state.get_plan_for_intent(id).await {
+        Some(plan) => {
+            let annotations = query.annotations(&state, AnnotationTarget::Plan(plan.id)).await;
+            Ok(Json(Annotated { record: plan, annotations }))
+        }
+        None => Err(missing_resource(&state, id, "Plan").await),
+    }
+}
+
+/// A proposal on offer, with what validating its plan found.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProposalResponse {
+    #[serde(flatten)]
+    pub proposal: orpheon_negotiate::Proposal,
+    pub validation: orpheon_planner::ValidationReport,
+}
+
+/// Get the proposal currently on offer for a negotiated intent.
+pub async fn get_proposal(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ProposalResponse>, (StatusCode, String)> {
+    let handle = state.get_negotiation(id).await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("No negotiation for intent {}", id))
     })?;
+    let proposal = handle.session.current_proposal().await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("No proposal for intent {}", id))
+    })?;
+    let validation = crate::negotiation::validate(&state, &proposal.plan)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     
-    Ok(Json(artifact))
+    Ok(Json(ProposalResponse { proposal, validation }))
 }
 
-/// List all intents.
-pub async fn list_intents(
+/// Get the receipt for the proposal a client accepted.
+///
+/// Answers `404` for unknown intents and intents that will never have a
+/// receipt, and `409` with the intent's status while its negotiation is
+/// still open.
+pub async fn get_receipt(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<orpheon_negotiate::AcceptanceReceipt>, ApiError> {
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        ApiError::from((StatusCode::NOT_FOUND, format!("Intent {} not found", id)))
+    })?;
+    if let Some(receipt) = record.receipt {
+        return Ok(Json(receipt));
+    }
+    if record.negotiation.is_none() || record.status.is_terminal() {
+        return Err((StatusCode::NOT_FOUND, format!("Intent {} has no accepted proposal", id)).into());
+    }
+    Err(missing_resource(&state, id, "Receipt").await)
+}
+
+/// List all plan revisions for an intent.
+pub async fn list_plans(
     State(state): State<AppState>,
-) -> Json<Vec<IntentResponse>> {
-    let records = state.list_intents().await;
-    
-    let responses: Vec<IntentResponse> = records
-        .into_iter()
-        .map(|r| IntentResponse {
-            id: r.intent.id,
-            kind: r.intent.kind,
-            status: format!("{:?}", r.status).to_lowercase(),
-            plan_id: r.plan_id,
-            artifact_id: r.artifact_id,
-            error: r.error,
-            created_at: r.intent.created_at.to_rfc3339(),
+    Path(id): Path<Uuid>,
+) -> Result<Json<PlanRevisionsResponse>, (StatusCode, String)> {
+    let record = state.get_intent(id).await.ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Intent {} not found", id))
+    })?;
+    let plans = state.get_plans_for_intent(id).await.unwrap_or_default();
+    
+    let active_plan_id = record.plan_id();
+    let revisions = plans
+        .iter()
+        .enumerate()
+        .map(|(idx, plan)| PlanRevisionResponse {
+            revision: idx as u32 + 1,
+            plan_id: plan.id,
+            estimated_cost: plan.estimated_cost,
+            estimated_latency_ms: plan.estimated_latency_ms,
+            steps: plan.steps.len(),
+            created_at: plan.created_at.to_rfc3339(),
+            active: Some(plan.id) == active_plan_id,
+            superseded: Some(plan.id) != active_plan_id,
+            executed: Some(plan.id) == record.executed_plan_id,
         })
         .collect();
     
-    Json(responses)
+    Ok(Json(PlanRevisionsResponse {
+        intent_id: id,
+        active_plan_id,
+        executed_plan_id: record.executed_plan_id,
+        revisions,
+    }))
+}
+
+/// Paging for an intent's children.
+#[derive(Debug, Deserialize)]
+pub struct ChildrenQuery {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_children_limit")]
+    pub limit: usize,
+}
+
+fn default_children_limit() -> usize {
+    100
+}
+
+/// A page of an intent's direct children.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChildrenResponse {
+    pub intent_id: Uuid,
+    /// Number of children in total, across all pages.
+    pub total: usize,
+    pub offset: usize,
+    pub children: Vec<LineageEntry>,
+}
+
+fn lineage_error(e: LineageError) -> (StatusCode, String) {
+    let status = match e {
+        LineageError::NotFound(_) => StatusCode::NOT_FOUND,
+        LineageError::Cycle(_) => StatusCode::CONFLICT,
+    };
+    (status, e.to_string())
+}
+
+/// Get an intent's ancestors and descendants.
+pub async fn get_lineage(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Lineage>, (StatusCode, String)> {
+    state.lineage(id).await.map(Json).map_err(lineage_error)
+}
+
+/// List an intent's direct children, a page at a time.
+pub async fn list_children(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ChildrenQuery>,
+) -> Result<Json<ChildrenResponse>, (StatusCode, String)> {
+    let children = state.child_entries(id).await.map_err(lineage_error)?;
+    let total = children.len();
+    let children = children.into_iter().skip(query.offset).take(query.limit).collect();
+    
+    Ok(Json(ChildrenResponse {
+        intent_id: id,
+        total,
+        offset: query.offset,
+        children,
+    }))
+}
+
+/// Get the artifact for an intent, with the annotations operators attached
+/// to it.
+///
+/// Answers `404` for unknown intents and `409` with the intent's status
+/// while it has no artifact yet. Answers in CBOR rather than JSON when the
+/// client sends `Accept: application/cbor`.
+///
+/// Local artifacts without annotations are served from their cached JSON.
+pub async fn get_artifact(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AnnotationQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let cbor = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(codec::CBOR_CONTENT_TYPE));
+    if forwarded_to(&state, id).await.is_some() {
+        return artifact_response(&fetch_artifact(&state, id).await?, cbor);
+    }
+    
+    let annotations = match state.get_intent(id).await.and_then(|record| record.artifact_id) {
+        Some(artifact_id) => query.annotations(&state, AnnotationTarget::Artifact(artifact_id)).await,
+        None => Vec::new(),
+    };
+    if annotations.is_empty() && !cbor {
+        if let Some(json) = state.artifact_json_for_intent(id).await {
+            return Ok(json_response(json));
+        }
+    }
+    let artifact = fetch_artifact(&state, id).await?;
+    artifact_response(&Annotated { record: artifact, annotations }, cbor)
+}
+
+/// An artifact response body, in CBOR or JSON.
+fn artifact_response<T: Serialize>(body: &T, cbor: bool) -> Result<Response, ApiError> {
+    if !cbor {
+        return Ok(Json(body).into_response());
+    }
+    let bytes = codec::to_cbor(body)?;
+    Ok(([(header::CONTENT_TYPE, codec::CBOR_CONTENT_TYPE)], bytes).into_response())
+}
+
+/// An intent's artifact, fetched from the peer if it was forwarded.
+async fn fetch_artifact(state: &AppState, id: Uuid) -> Result<orpheon_core::ExecutionArtifact, ApiError> {
+    if let Some(forwarded) = forwarded_to(state, id).await {
+        let client = peer_client(state, &forwarded).await?;
+        return client
+            .get_artifact(forwarded.remote_id)
+            .await
+            .map_err(|e| peer_resource_error(id, &forwarded, e));
+    }
+    
+    match state.get_artifact_for_intent(id).await {
+        Some(artifact) => Ok(artifact),
+        None => Err(missing_resource(state, id, "Artifact").await),
+    }
+}
+
+/// Verify an intent's artifact against this node's key, reporting which
+/// Merkle version its trace root was checked with.
+pub async fn verify_artifact(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<orpheon_sdk::VerificationReport>, ApiError> {
+    match state.get_artifact_for_intent(id).await {
+        Some(artifact) => {
+            let public_key = state.node_key.public_key_hex();
+            Ok(Json(orpheon_sdk::verify_artifact(&artifact, Some(&public_key))))
+        }
+        None => Err(missing_resource(&state, id, "Artifact").await),
+    }
+}
+
+/// Filter for an artifact's execution trace.
+#[derive(Debug, Deserialize)]
+pub struct TraceQuery {
+    /// Only events of steps carrying this tag.
+    pub tag: Option<String>,
+}
+
+/// Get the execution trace of an intent's artifact, optionally only the
+/// events of steps with a given tag.
+pub async fn get_trace(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TraceQuery>,
+) -> Result<Json<Vec<orpheon_core::ExecutionEvent>>, ApiError> {
+    let artifact = fetch_artifact(&state, id).await?;
+    let Some(tag) = query.tag else {
+        return Ok(Json(artifact.trace));
+    };
+    
+    let tagged: HashSet<Uuid> = artifact
+        .final_plan
+        .steps
+        .iter()
+        .filter(|step| step.has_tag(&tag))
+        .map(|step| step.id)
+        .collect();
+    let events = artifact.trace.into_iter().filter(|e| tagged.contains(&e.step_id)).collect();
+    Ok(Json(events))
+}
+
+/// Filters for listing intents.
+#[derive(Debug, Default)]
+pub struct ListIntentsQuery {
+    /// Only intents submitted with this source.
+    pub source: Option<String>,
+    /// Only intents carrying every one of these labels, given as repeated
+    /// `label=key=value` parameters.
+    pub labels: Vec<(String, String)>,
+}
+
+impl ListIntentsQuery {
+    /// Collect the filters from query parameters, which may repeat.
+    fn from_pairs(pairs: Vec<(String, String)>) -> Result<Self, ApiError> {
+        let mut query = Self::default();
+        for (name, value) in pairs {
+            match name.as_str() {
+                "source" => query.source = Some(value),
+                "label" => {
+                    let (key, value) = value.split_once('=').ok_or_else(|| {
+                        ApiError::new(
+                            StatusCode::BAD_REQUEST,
+                            "invalid_query",
+                            format!("label filter {:?} is not of the form key=value", value),
+                        )
+                    })?;
+                    query.labels.push((key.to_string(), value.to_string()));
+                }
+                _ => {}
+            }
+        }
+        Ok(query)
+    }
+    
+    fn matches(&self, record: &IntentRecord) -> bool {
+        let labels = &record.intent.labels;
+        (self.source.is_none() || record.provenance.source == self.source)
+            && self.labels.iter().all(|(key, value)| labels.get(key) == Some(value))
+    }
+}
+
+/// List intents, as an array of [`IntentResponse`]s assembled from cached
+/// JSON.
+pub async fn list_intents(
+    State(state): State<AppState>,
+    Query(pairs): Query<Vec<(String, String)>>,
+) -> Result<Response, ApiError> {
+    let query = ListIntentsQuery::from_pairs(pairs)?;
+    Ok(json_response(state.list_intents_json(|record| query.matches(record), |record| IntentResponse::from(record)).await))
+}
+
+/// A `200` with an already serialized JSON body.
+fn json_response(json: Bytes) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], json).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use orpheon_core::{Plan, PlanningStrategy};
+
+    #[tokio::test]
+    async fn test_list_plans_after_replan() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("test").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        
+        let first = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        state.mark_plan_executed(intent_id, first.id).await;
+        // Replan (e.g. after a counter-offer)
+        let second = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/plans", intent_id)).await;
+        response.assert_status_ok();
+        
+        let body: PlanRevisionsResponse = response.json();
+        assert_eq!(body.active_plan_id, Some(second.id));
+        assert_eq!(body.revisions.len(), 2);
+        
+        assert_eq!(body.revisions[0].plan_id, first.id);
+        assert_eq!(body.revisions[0].revision, 1);
+        assert!(!body.revisions[0].active);
+        assert!(body.revisions[0].superseded);
+        assert!(body.revisions[0].executed);
+        
+        assert_eq!(body.revisions[1].plan_id, second.id);
+        assert!(body.revisions[1].active);
+        assert!(!body.revisions[1].superseded);
+        assert!(!body.revisions[1].executed);
+    }
+
+    #[tokio::test]
+    async fn test_raw_submission_records_provenance() {
+        let state = AppState::new();
+        state.pause_engine();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let response = server
+            .post("/api/v1/intent")
+            .add_header(header::USER_AGENT, "curl/8.5.0".parse::<header::HeaderValue>().unwrap())
+            .add_header(SOURCE_HEADER.parse::<header::HeaderName>().unwrap(), "rogue-script".parse::<header::HeaderValue>().unwrap())
+            .json(&serde_json::json!({ "kind": "deploy" }))
+            .await;
+        let intent_id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        let expected = Provenance {
+            source: Some("rogue-script".to_string()),
+            user_agent: Some("curl/8.5.0".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(state.get_intent(intent_id).await.unwrap().provenance, expected);
+        
+        // Amending, even from elsewhere, keeps the original provenance
+        let url = format!("/api/v1/intent/{}", intent_id);
+        server.put(&url).json(&serde_json::json!({ "kind": "deploy", "metadata": { "v": 2 } })).await.assert_status_ok();
+        let body: serde_json::Value = server.get(&url).await.json();
+        assert_eq!(body["provenance"], serde_json::json!({ "source": "rogue-script", "user_agent": "curl/8.5.0" }));
+        
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").add_query_param("source", "rogue-script").await.json();
+        assert_eq!(listed.len(), 1);
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").add_query_param("source", "ci").await.json();
+        assert!(listed.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_list_filters_by_labels() {
+        let node = crate::testing::TestNode::with_state(AppState::new()).await;
+        node.state.pause_engine();
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap();
+        let mut ids = Vec::new();
+        for (team, env) in [("ml", "prod"), ("ml", "staging"), ("web", "prod")] {
+            let intent = Intent::builder().kind("deploy").label("team", team).label("env", env).build().unwrap();
+            ids.push(client.submit_detached(intent).await.unwrap());
+        }
+        
+        let label = |key: &str, value: &str| (key.to_string(), value.to_string());
+        let listed = client.list_intents(None, &[label("team", "ml"), label("env", "prod")]).await.unwrap();
+        assert_eq!(listed.iter().map(|i| i.id).collect::<Vec<_>>(), vec![ids[0]]);
+        assert_eq!(listed[0].labels.get("env").map(String::as_str), Some("prod"));
+        assert_eq!(client.list_intents(None, &[label("team", "ml")]).await.unwrap().len(), 2);
+        assert!(client.list_intents(None, &[label("team", "")]).await.unwrap().is_empty());
+        assert_eq!(client.list_intents(None, &[]).await.unwrap().len(), 3);
+        
+        let server = TestServer::new(crate::create_router(node.state.clone())).unwrap();
+        let response = server.get("/api/v1/intents").add_query_param("label", "team").await;
+        response.assert_status_bad_request();
+        assert_eq!(response.json::<serde_json::Value>()["error"]["code"], "invalid_query");
+        
+        // Labels are validated like any other field
+        let response = server
+            .post("/api/v1/intent")
+            .json(&serde_json::json!({ "kind": "deploy", "labels": { "team": "machine learning" } }))
+            .await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("labels.team"), "{}", response.text());
+    }
+
+    #[tokio::test]
+    async fn test_amend_only_while_received() {
+        let state = AppState::new();
+        state.pause_engine();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "kind": "deploy" })).await;
+        let intent_id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        let created_at = state.get_intent(intent_id).await.unwrap().intent.created_at;
+        let url = format!("/api/v1/intent/{}", intent_id);
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").await.json();
+        assert_eq!(listed[0]["summary"]["constraints"], 0);
+        let amendment = serde_json::json!({
+            "kind": "deploy",
+            "constraints": [{ "type": "resource_limit", "resource": "cpu", "limit": 2.0 }],
+            "budget": { "max_cost": 5.0 },
+            "metadata": { "ticket": "OPS-1" },
+        });
+        
+        let response = server.put(&url).json(&amendment).await;
+        response.assert_status_ok();
+        assert_eq!(response.json::<serde_json::Value>()["status"], "received");
+        let intent = state.get_intent(intent_id).await.unwrap().intent;
+        assert_eq!(intent.created_at, created_at);
+        assert_eq!(intent.constraints, vec![Constraint::ResourceLimit { resource: "cpu".to_string(), limit: 2.0 }]);
+        assert_eq!(intent.budget.max_cost, Some(5.0));
+        assert_eq!(intent.metadata["ticket"], "OPS-1");
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").await.json();
+        assert_eq!(listed[0]["summary"]["constraints"], 1);
+        assert_eq!(listed[0]["summary"]["max_cost"], 5.0);
+        
+        // Invalid amendments are refused like submissions
+        let invalid = serde_json::json!({ "kind": "deploy", "budget": { "max_cost": -1.0 } });
+        server.put(&url).json(&invalid).await.assert_status_bad_request();
+        
+        // The engine picks the intent up between the client's GET and PUT
+        let seen: serde_json::Value = server.get(&url).await.json();
+        assert_eq!(seen["status"], "received");
+        assert!(state.update_intent_status_if(intent_id, IntentStatus::Received, IntentStatus::Planning).await);
+        let response = server.put(&url).json(&serde_json::json!({ "kind": "deploy" })).await;
+        response.assert_status(StatusCode::CONFLICT);
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["code"], "invalid_transition");
+        assert_eq!(error["error"]["intent_status"], "planning");
+        assert_eq!(state.get_intent(intent_id).await.unwrap().intent.constraints.len(), 1);
+        
+        server
+            .put(&format!("/api/v1/intent/{}", Uuid::new_v4()))
+            .json(&amendment)
+            .await
+            .assert_status_not_found();
+    }
+    
+    #[tokio::test]
+    async fn test_dependencies_must_exist_and_not_loop() {
+        let state = AppState::new();
+        state.pause_engine();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let submit = |body: serde_json::Value| server.post("/api/v1/intent").json(&body);
+        let depends_on = |id: Uuid| serde_json::json!([{ "type": "depends_on", "intent_id": id, "require_success": true }]);
+        
+        let response = submit(serde_json::json!({ "kind": "deploy", "constraints": depends_on(Uuid::new_v4()) })).await;
+        response.assert_status_bad_request();
+        assert_eq!(response.json::<serde_json::Value>()["error"]["code"], "invalid_dependency");
+        
+        let first: serde_json::Value = submit(serde_json::json!({ "kind": "deploy" })).await.json();
+        let first: Uuid = first["id"].as_str().unwrap().parse().unwrap();
+        let second: serde_json::Value = submit(serde_json::json!({ "kind": "deploy", "constraints": depends_on(first) })).await.json();
+        let second: Uuid = second["id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(state.get_intent(second).await.unwrap().intent.dependencies().collect::<Vec<_>>(), vec![(first, true)]);
+        
+        // Amending the first intent to wait for the second closes a loop
+        let url = format!("/api/v1/intent/{}", first);
+        let response = server.put(&url).json(&serde_json::json!({ "kind": "deploy", "constraints": depends_on(second) })).await;
+        response.assert_status_bad_request();
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["code"], "invalid_dependency");
+        assert!(error["error"]["message"].as_str().unwrap().contains("loop back"), "{}", error);
+        assert!(state.get_intent(first).await.unwrap().intent.constraints.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_listing_reuses_unchanged_records() {
+        let state = AppState::new();
+        let mut ids = Vec::new();
+        for _ in 0..5_000 {
+            let intent = Intent::builder().kind("deploy").build().unwrap();
+            ids.push(intent.id);
+            state.store_intent(intent).await;
+        }
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").await.json();
+        assert_eq!(listed.len(), 5_000);
+        assert_eq!(state.responses.serializations(), 5_000);
+        
+        let response = server.get("/api/v1/intents").await;
+        assert_eq!(response.header(header::CONTENT_TYPE), "application/json");
+        assert_eq!(state.responses.serializations(), 5_000);
+        
+        // Only the changed record is serialized again
+        state.update_intent_status(ids[42], IntentStatus::Planning).await;
+        let listed: Vec<serde_json::Value> = server.get("/api/v1/intents").await.json();
+        assert_eq!(state.responses.serializations(), 5_001);
+        let changed = listed.iter().find(|r| r["id"] == ids[42].to_string()).unwrap();
+        assert_eq!(changed["status"], "planning");
+        assert_eq!(changed["seq"], 2);
+    }
+    
+    #[tokio::test]
+    async fn test_artifact_is_serialized_once() {
+        use orpheon_core::{ExecutionArtifact, Outcome};
+        
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        let artifact = ExecutionArtifact::new(intent, Plan::new(intent_id, PlanningStrategy::Heuristic), Outcome::Success);
+        state.store_artifact(artifact.clone()).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        
+        let url = format!("/api/v1/intent/{}/artifact", intent_id);
+        for _ in 0..3 {
+            let served: orpheon_core::ExecutionArtifact = server.get(&url).await.json();
+            assert_eq!(served.id, artifact.id);
+        }
+        assert_eq!(state.responses.serializations(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_trace_filters_by_step_tag() {
+        use orpheon_core::{ExecutionArtifact, ExecutionEvent, Outcome, Step};
+        
+        let state = AppState::new();
+        let intent = Intent::builder().kind("train").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        let gpu = Step::new("allocate", "allocate_gpu").with_tag("gpu").with_description("Allocate 8×H100");
+        let network = Step::new("peer", "configure_vpc").with_tag("network");
+        plan.add_step(gpu.clone());
+        plan.add_step(network.clone());
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        for step in [&gpu, &network] {
+            artifact.add_event(ExecutionEvent::step_started(step.id));
+            artifact.add_event(ExecutionEvent::step_completed(step.id, 10));
+        }
+        state.store_artifact(artifact).await;
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let url = format!("/api/v1/intent/{}/artifact/trace", intent_id);
+        let all: Vec<ExecutionEvent> = server.get(&url).await.json();
+        assert_eq!(all.len(), 4);
+        
+        let tagged: Vec<ExecutionEvent> = server.get(&url).add_query_param("tag", "gpu").await.json();
+        assert_eq!(tagged.len(), 2);
+        assert!(tagged.iter().all(|e| e.step_id == gpu.id));
+        
+        let untagged: Vec<ExecutionEvent> = server.get(&url).add_query_param("tag", "storage").await.json();
+        assert!(untagged.is_empty());
+        
+        // Descriptions and tags come back with the plan
+        let plan: Plan = server.get(&format!("/api/v1/intent/{}/artifact", intent_id)).await.json::<ExecutionArtifact>().final_plan;
+        assert_eq!(plan.steps[0].description.as_deref(), Some("Allocate 8×H100"));
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejects_unnormalized_weights_when_strict() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        let body = serde_json::json!({
+            "kind": "deploy",
+            "validation": "strict",
+            "preferences": [
+                { "objective": "cost", "direction": "minimize", "weight": 0.5 },
+                { "objective": "latency", "direction": "minimize", "weight": 0.6 }
+            ]
+        });
+        
+        let response = server.post("/api/v1/intent").json(&body).await;
+        response.assert_status_bad_request();
+        let message = response.text();
+        assert!(message.contains("got 1.1"), "{}", message);
+        assert!(message.contains("cost=0.5") && message.contains("latency=0.6"), "{}", message);
+    }
+    
+    #[tokio::test]
+    async fn test_submit_normalizes_weights_on_request() {
+        let state = AppState::new();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let body = serde_json::json!({
+            "kind": "deploy",
+            "normalize_weights": true,
+            "preferences": [
+                { "objective": "cost", "direction": "minimize", "weight": 1.0 },
+                { "objective": "latency", "direction": "minimize", "weight": 3.0 }
+            ]
+        });
+        
+        let response = server.post("/api/v1/intent").json(&body).await;
+        response.assert_status(StatusCode::CREATED);
+        let id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        
+        let intent = state.get_intent(id).await.unwrap().intent;
+        assert_eq!(intent.preferences[0].weight, 0.25);
+        assert_eq!(intent.preferences[1].weight, 0.75);
+        assert_eq!(intent.metadata["original_preference_weights"][1]["weight"], 3.0);
+    }
+    
+    #[tokio::test]
+    async fn test_submit_normalizes_weights_by_default() {
+        let state = AppState::new();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let intent = Intent::builder().kind("deploy").minimize("cost", 0.2).build().unwrap();
+        
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": intent })).await;
+        response.assert_status(StatusCode::CREATED);
+        let id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        let stored = state.get_intent(id).await.unwrap().intent;
+        assert_eq!(stored.preferences[0].weight, 1.0);
+        assert_eq!(stored.metadata["original_preference_weights"][0]["weight"].as_f64().unwrap() as f32, 0.2);
+        
+        // Signed intents are kept as signed, so their weights must be right
+        let mut signed = Intent::builder().kind("deploy").minimize("cost", 0.2).build().unwrap();
+        signed.sign(&orpheon_core::crypto::SigningKey::from_bytes(&[7u8; 32]));
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": signed })).await;
+        response.assert_status_bad_request();
+    }
+    
+    #[tokio::test]
+    async fn test_list_plans_unknown_intent() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/plans", Uuid::new_v4())).await;
+        response.assert_status_not_found();
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_artifact_distinguish_unknown_from_not_ready() {
+        use orpheon_core::{ExecutionArtifact, Outcome};
+        
+        let state = AppState::new();
+        state.pause_engine();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let plan_url = format!("/api/v1/intent/{}/plan", intent_id);
+        let artifact_url = format!("/api/v1/intent/{}/artifact", intent_id);
+        
+        // Unknown intents are 404
+        for resource in ["plan", "artifact"] {
+            let response = server.get(&format!("/api/v1/intent/{}/{}", Uuid::new_v4(), resource)).await;
+            response.assert_status_not_found();
+            assert_eq!(response.json::<serde_json::Value>()["error"]["code"], "not_found");
+        }
+        
+        // Known intents without the resource are 409, with the intent's status
+        for url in [&plan_url, &artifact_url] {
+            let response = server.get(url).await;
+            response.assert_status(StatusCode::CONFLICT);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["error"]["code"], "not_ready");
+            assert_eq!(body["error"]["intent_status"], "received");
+        }
+        
+        let plan = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        assert_eq!(server.get(&plan_url).await.json::<Plan>().id, plan.id);
+        server.get(&artifact_url).await.assert_status(StatusCode::CONFLICT);
+        
+        state.store_artifact(ExecutionArtifact::new(intent, plan, Outcome::Success)).await;
+        assert_eq!(server.get(&artifact_url).await.json::<ExecutionArtifact>().intent.id, intent_id);
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_only_apply_to_matching_status() {
+        let state = AppState::new();
+        state.pause_engine();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let pause = format!("/api/v1/intent/{}/pause", intent_id);
+        let resume = format!("/api/v1/intent/{}/resume", intent_id);
+        
+        server.post(&format!("/api/v1/intent/{}/pause", Uuid::new_v4())).await.assert_status_not_found();
+        
+        // Only executing intents pause, and only paused ones resume
+        for url in [&pause, &resume] {
+            let response = server.post(url).await;
+            response.assert_status(StatusCode::CONFLICT);
+            let body: serde_json::Value = response.json();
+            assert_eq!(body["error"]["code"], "invalid_transition");
+            assert_eq!(body["error"]["intent_status"], "received");
+        }
+        
+        state.update_intent_status(intent_id, IntentStatus::Executing).await;
+        server.post(&pause).await.assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(state.get_intent(intent_id).await.unwrap().status, IntentStatus::Paused);
+        server.post(&pause).await.assert_status(StatusCode::CONFLICT);
+        server.post(&resume).await.assert_status(StatusCode::NO_CONTENT);
+        assert_eq!(state.get_intent(intent_id).await.unwrap().status, IntentStatus::Executing);
+    }
+
+    #[tokio::test]
+    async fn test_sdk_fetches_verified_artifact() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        let addr = node.addr;
+        
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while state.get_artifact_for_intent(intent_id).await.is_none() {
+            assert!(tokio::time::Instant::now() < deadline, "intent did not complete");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        
+        let client = orpheon_sdk::OrpheonClient::connect(&format!("http://{}", addr)).await.unwrap();
+        let (artifact, report) = client.get_verified_artifact(intent_id).await.unwrap();
+        assert_eq!(artifact.intent.id, intent_id);
+        assert!(report.is_valid());
+        assert!(report.warnings().is_empty());
+        assert_eq!(report.merkle_version, orpheon_core::MERKLE_VERSION);
+        
+        // The node's own check also passes, signature included
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/artifact/verify", intent_id)).await;
+        response.assert_status_ok();
+        let report: orpheon_sdk::VerificationReport = response.json();
+        assert_eq!(report.merkle_version, orpheon_core::MERKLE_VERSION);
+        assert!(report.checks.iter().all(|c| c.status == orpheon_sdk::verify::CheckStatus::Passed));
+        server
+            .get(&format!("/api/v1/intent/{}/artifact/verify", Uuid::new_v4()))
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_artifact_is_served_as_cbor_on_request() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").metadata(serde_json::json!({ "ratio": 1.0 })).build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        while state.get_artifact_for_intent(intent_id).await.is_none() {
+            assert!(tokio::time::Instant::now() < deadline, "intent did not complete");
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let url = format!("/api/v1/intent/{}/artifact", intent_id);
+        let response = server.get(&url).add_header(header::ACCEPT, codec::CBOR_CONTENT_TYPE).await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_TYPE), codec::CBOR_CONTENT_TYPE);
+        let served: orpheon_core::ExecutionArtifact = codec::from_cbor(response.as_bytes()).unwrap();
+        let stored = state.get_artifact_for_intent(intent_id).await.unwrap();
+        assert_eq!(serde_json::to_value(&served).unwrap(), serde_json::to_value(&stored).unwrap());
+        assert_eq!(server.get(&url).await.header(header::CONTENT_TYPE), "application/json");
+        
+        // The SDK asks for it, and the signature still checks out
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap().with_prefer_cbor(true);
+        let (artifact, report) = client.get_verified_artifact(intent_id).await.unwrap();
+        assert!(report.is_valid());
+        assert!(artifact.intent.metadata["ratio"].is_f64());
+    }
+
+    /// An intent using most fields, under a parent stored in `state`.
+    async fn full_intent(state: &AppState) -> Intent {
+        let parent = Intent::builder().kind("train").build().unwrap();
+        let parent_id = parent.id;
+        state.store_intent(parent).await;
+        Intent::builder()
+            .kind("deploy")
+            .priority(orpheon_core::Priority::Critical)
+            .parent(parent_id)
+            .constraint(Constraint::Custom {
+                name: "gpu_model".to_string(),
+                data: serde_json::json!({"model": "H100", "count": 8}),
+            })
+            .minimize("cost", 1.0)
+            .metadata(serde_json::json!({"team": "ml"}))
+            .label("team", "ml")
+            .build()
+            .unwrap()
+    }
+
+    /// Checks `gpu_model` constraints name a model.
+    struct GpuModel;
+    
+    impl orpheon_core::CustomConstraintValidator for GpuModel {
+        fn validate(&self, data: &serde_json::Value) -> Result<(), String> {
+            data["model"].as_str().map(|_| ()).ok_or_else(|| "model is required".to_string())
+        }
+        
+        fn check(&self, _data: &serde_json::Value, _ctx: &orpheon_core::EvaluationContext) -> Result<(), String> {
+            Ok(())
+        }
+    }
+    
+    /// State of a node that knows `gpu_model` constraints.
+    fn gpu_state() -> AppState {
+        let registry = ConstraintRegistry::new().with("gpu_model", GpuModel);
+        let evaluator = orpheon_core::Evaluator::new().with_registry(registry);
+        AppState::with_planner(orpheon_planner::AStarPlanner::new().with_evaluator(evaluator))
+    }
+    
+    #[tokio::test]
+    async fn test_custom_constraints_need_a_registered_validator() {
+        let server = TestServer::new(crate::create_router(gpu_state())).unwrap();
+        let submit = |data: serde_json::Value, name: &str| {
+            let constraint = Constraint::Custom { name: name.to_string(), data };
+            let intent = Intent::builder().kind("deploy").constraint(constraint).build().unwrap();
+            server.post("/api/v1/intent").json(&serde_json::json!({ "intent": intent }))
+        };
+        
+        submit(serde_json::json!({"model": "H100"}), "gpu_model").await.assert_status(StatusCode::CREATED);
+        for (data, name, field) in [
+            (serde_json::json!({"model": "H100"}), "gpu_modle", "constraints[0].name"),
+            (serde_json::json!({"count": 8}), "gpu_model", "constraints[0].data"),
+        ] {
+            let response = submit(data, name).await;
+            response.assert_status(StatusCode::BAD_REQUEST);
+            assert_eq!(response.json::<serde_json::Value>()["error"]["fields"][0]["field"], field);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_sdk_submits_full_intent() {
+        let node = crate::testing::TestNode::with_state(gpu_state()).await;
+        node.state.pause_engine();
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap();
+        
+        let intent = full_intent(&node.state).await;
+        let stream = client.submit(intent.clone()).await.unwrap();
+        let stored = node.state.get_intent(stream.intent_id()).await.unwrap().intent;
+        
+        // Unsigned intents get a server-side id and timestamp; nothing else changes
+        assert_ne!(stored.id, intent.id);
+        let mut expected = intent;
+        expected.id = stored.id;
+        expected.created_at = stored.created_at;
+        assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&expected).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_submit_signed_intent_keeps_signed_fields() {
+        let state = gpu_state();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let key = orpheon_core::NodeKey::generate();
+        let mut intent = full_intent(&state).await;
+        key.sign_intent(&mut intent);
+        
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": intent })).await;
+        response.assert_status(StatusCode::CREATED);
+        let stored = state.get_intent(intent.id).await.unwrap().intent;
+        assert_eq!(stored.created_at, intent.created_at);
+        assert!(stored.verify_signature().unwrap());
+        
+        // Tampering with a signed field is rejected
+        let mut tampered = intent.clone();
+        tampered.priority = orpheon_core::Priority::Low;
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": tampered })).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_require_signatures_rejects_unsigned_intents() {
+        let mut state = gpu_state();
+        state.require_signatures = true;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        
+        let mut intent = full_intent(&state).await;
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": intent })).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert_eq!(response.json::<serde_json::Value>()["error"]["code"], "signature_required");
+        assert!(state.get_intent(intent.id).await.is_none());
+        
+        intent.sign(&orpheon_core::crypto::SigningKey::from_bytes(&[7u8; 32]));
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": intent })).await;
+        response.assert_status(StatusCode::CREATED);
+        assert!(state.get_intent(intent.id).await.is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_submit_legacy_body_still_accepted() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        let response = server
+            .post("/api/v1/intent")
+            .json(&serde_json::json!({
+                "kind": "deploy",
+                "constraints": [{"type": "resource_limit", "resource": "cost", "limit": 10.0}],
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+    }
+    
+    #[tokio::test]
+    async fn test_submit_accepts_every_constraint_type() {
+        let state = AppState::new();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let by = chrono::Utc::now() + chrono::Duration::hours(1);
+        let response = server
+            .post("/api/v1/intent")
+            .json(&serde_json::json!({
+                "kind": "deploy",
+                "constraints": [
+                    {"type": "deadline", "by": by},
+                    {"type": "provider", "node_id": "node-7"},
+                ],
+                "soft_constraints": [{"type": "geo_fence", "regions": ["eu-west"], "allowed": false}],
+            }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        
+        let id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        let intent = state.get_intent(id).await.unwrap().intent;
+        assert_eq!(
+            intent.constraints,
+            vec![Constraint::Deadline { by }, Constraint::Provider { node_id: "node-7".to_string() }]
+        );
+        assert_eq!(intent.soft_constraints, vec![Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: false }]);
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_conflicts() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        
+        let body = serde_json::json!({
+            "kind": "deploy",
+            "constraints": [
+                {"type": "resource_limit", "resource": "gpu", "limit": -1.0},
+            ],
+        });
+        let response: ValidateIntentResponse = server.post("/api/v1/intent/validate").json(&body).await.json();
+        assert!(!response.valid);
+        assert_eq!(response.conflicts.len(), 1);
+        assert_eq!(response.conflicts[0].first, "constraints[0]");
+        
+        // Submission rejects the same intent with the explanation
+        let response = server.post("/api/v1/intent").json(&body).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        assert!(response.text().contains("never negative"));
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["code"], "constraint_conflict");
+        assert_eq!(error["error"]["conflicts"][0]["first"], "constraints[0]");
+        
+        let ok: ValidateIntentResponse = server
+            .post("/api/v1/intent/validate")
+            .json(&serde_json::json!({"kind": "deploy"}))
+            .await
+            .json();
+        assert!(ok.valid);
+    }
+    
+    #[tokio::test]
+    async fn test_submit_lists_every_conflict() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        
+        let body = serde_json::json!({
+            "kind": "deploy",
+            "budget": { "max_duration_ms": 0 },
+            "constraints": [
+                { "type": "resource_limit", "resource": "cpu", "limit": 2.0 },
+                { "type": "resource_limit", "resource": "cpu", "limit": 4.0 },
+                { "type": "geo_fence", "regions": ["eu-west"], "allowed": true },
+                { "type": "geo_fence", "regions": ["eu-west"], "allowed": false },
+            ],
+        });
+        let response = server.post("/api/v1/intent").json(&body).await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let error: serde_json::Value = response.json();
+        let conflicts: Vec<ConstraintConflict> = serde_json::from_value(error["error"]["conflicts"].clone()).unwrap();
+        let pairs: Vec<(&str, &str)> = conflicts.iter().map(|c| (c.first.as_str(), c.second.as_str())).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("constraints[2]", "constraints[3]"),
+                ("constraints[0]", "constraints[1]"),
+                ("budget.max_duration_ms", "execution"),
+            ]
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_submit_reports_each_invalid_field() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        let weights = |weight: f64| serde_json::json!([{ "objective": "cost", "direction": "minimize", "weight": weight }]);
+        let cases = [
+            ("budget.max_cost", serde_json::json!({ "budget": { "max_cost": -50.0 } })),
+            ("budget.currency", serde_json::json!({ "budget": { "currency": "dollars" } })),
+            ("budget.max_duration_ms", serde_json::json!({ "budget": { "max_duration_ms": 1u64 << 40 } })),
+            ("budget.max_duration_ms", serde_json::json!({ "budget": { "max_duration_ms": 0 } })),
+            ("budget.currency", serde_json::json!({ "budget": { "max_cost": 5.0, "currency": "" } })),
+            ("budget.max_retries", serde_json::json!({ "budget": { "max_retries": 500 } })),
+            ("constraints[0].by", serde_json::json!({ "constraints": [{ "type": "deadline", "by": "2000-01-01T00:00:00Z" }] })),
+            // Lenient validation would scale a lone weight down to 1.0
+            ("preferences[0].weight", serde_json::json!({ "preferences": weights(1.5), "validation": "strict" })),
+            ("preferences[0].weight", serde_json::json!({ "preferences": weights(-0.5) })),
+        ];
+        
+        for (field, mut body) in cases {
+            body["kind"] = "deploy".into();
+            let response = server.post("/api/v1/intent").json(&body).await;
+            response.assert_status_bad_request();
+            let error: serde_json::Value = response.json();
+            assert_eq!(error["error"]["code"], "invalid_fields", "{}", body);
+            assert_eq!(error["error"]["fields"].as_array().unwrap().len(), 1, "{}", body);
+            assert_eq!(error["error"]["fields"][0]["field"], field, "{}", body);
+            
+            let validated: ValidateIntentResponse = server.post("/api/v1/intent/validate").json(&body).await.json();
+            assert!(!validated.valid);
+            assert_eq!(validated.fields[0].field, field);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_submit_normalizes_currency() {
+        let mut state = AppState::new();
+        state.pause_engine();
+        let rates = orpheon_core::StaticRates::new("USD").with_rate("EUR", 1.1);
+        state.pricing = orpheon_negotiate::Pricing::new("USD", std::sync::Arc::new(rates));
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let response = server
+            .post("/api/v1/intent")
+            .json(&serde_json::json!({ "kind": "deploy", "budget": { "max_cost": 0.0, "currency": "eur" } }))
+            .await;
+        response.assert_status(StatusCode::CREATED);
+        let id: Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(state.get_intent(id).await.unwrap().intent.budget.currency, "EUR");
+    }
+    
+    #[tokio::test]
+    async fn test_budgets_must_convert_to_the_base_currency() {
+        let state = AppState::new();
+        state.pause_engine();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let body = serde_json::json!({ "kind": "deploy", "budget": { "max_cost": 10.0, "currency": "EUR" } });
+        
+        let response = server.post("/api/v1/intent").json(&body).await;
+        response.assert_status_bad_request();
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["code"], "invalid_intent");
+        assert_eq!(error["error"]["message"], "Intent validation failed: No exchange rate from EUR to USD");
+        assert!(state.list_intents().await.is_empty());
+        
+        let validated: ValidateIntentResponse = server.post("/api/v1/intent/validate").json(&body).await.json();
+        assert!(!validated.valid);
+        
+        // Budgets are judged at the node's rates
+        let mut state = AppState::new();
+        let rates = orpheon_core::StaticRates::new("USD").with_rate("EUR", 0.15);
+        state.pricing = orpheon_negotiate::Pricing::new("USD", std::sync::Arc::new(rates));
+        let intent = Intent::builder()
+            .kind("deploy")
+            .budget(Budget { currency: "EUR".to_string(), ..Budget::usd(10.0) })
+            .build()
+            .unwrap();
+        assert_eq!(state.base_budget(&intent).unwrap().cost_limit(), Some(1.5));
+        assert!(matches!(
+            state.base_budget(&Intent { budget: Budget { currency: "GBP".to_string(), ..Budget::usd(1.0) }, ..intent }),
+            Err(OrpheonError::IntentInvalid { intent_id: Some(_), .. })
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_submit_duration_limit_is_configurable() {
+        let mut state = AppState::new();
+        state.pause_engine();
+        state.intent_limits.max_duration_ms = 60_000;
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let body = |ms: u64| serde_json::json!({ "kind": "deploy", "budget": { "max_duration_ms": ms } });
+        
+        server.post("/api/v1/intent").json(&body(60_000)).await.assert_status(StatusCode::CREATED);
+        let response = server.post("/api/v1/intent").json(&body(60_001)).await;
+        response.assert_status_bad_request();
+        assert!(response.text().contains("must be at most 60000"));
+    }
+    
+    #[tokio::test]
+    async fn test_oversized_intents_are_not_stored() {
+        let mut state = AppState::new();
+        state.pause_engine();
+        state.intent_limits.max_constraints = 2;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let limit = serde_json::json!({ "type": "resource_limit", "resource": "cost", "limit": 10.0 });
+        let body = |count: usize| serde_json::json!({ "kind": "deploy", "constraints": vec![limit.clone(); count] });
+        
+        server.post("/api/v1/intent").json(&body(2)).await.assert_status(StatusCode::CREATED);
+        let response = server.post("/api/v1/intent").json(&body(3)).await;
+        response.assert_status_bad_request();
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["message"], "Intent validation failed: Intent constraint count is 3, exceeding max_constraints of 2");
+        assert_eq!(state.list_intents().await.len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_get_proposal_itemizes_plan() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(orpheon_core::Step::new("allocate", "allocate_gpu").with_cost(2.5));
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(0.75));
+        crate::negotiation::open(&state, intent, plan, &Default::default()).await.unwrap();
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/proposal", intent_id)).await;
+        response.assert_status_ok();
+        
+        let proposal: orpheon_negotiate::Proposal = response.json();
+        assert_eq!(proposal.quoted_cost, 3.25);
+        let actions: Vec<_> = proposal.line_items.iter().map(|item| item.action.as_str()).collect();
+        assert_eq!(actions, vec!["allocate_gpu", "deploy_workload"]);
+        assert!(proposal.check_line_items().is_ok());
+        
+        // The plan is not one the catalog can run, and the client is told so
+        let validation = response.json::<ProposalResponse>().validation;
+        assert!(!validation.valid);
+        assert_eq!(validation.unknown_actions, vec!["allocate_gpu"]);
+        assert_eq!(validation.failed_preconditions[0].action, "deploy_workload");
+        
+        let response = server.get(&format!("/api/v1/intent/{}/proposal", Uuid::new_v4())).await;
+        response.assert_status_not_found();
+    }
+    
+    #[tokio::test]
+    async fn test_lineage_and_children_endpoints() {
+        let state = AppState::new();
+        let root = Intent::builder().kind("batch").build().unwrap();
+        let mid = Intent::builder().kind("deploy").parent(root.id).build().unwrap();
+        let leaves: Vec<Intent> = (0..3)
+            .map(|_| Intent::builder().kind("verify").parent(mid.id).build().unwrap())
+            .collect();
+        let (root_id, mid_id) = (root.id, mid.id);
+        let leaf_ids: Vec<Uuid> = leaves.iter().map(|l| l.id).collect();
+        state.store_intent(root).await;
+        state.store_intent(mid).await;
+        for leaf in leaves {
+            state.store_intent(leaf).await;
+        }
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        
+        let lineage: Lineage = server.get(&format!("/api/v1/intent/{}/lineage", leaf_ids[2])).await.json();
+        let ancestors: Vec<Uuid> = lineage.ancestors.iter().map(|a| a.id).collect();
+        assert_eq!(ancestors, vec![root_id, mid_id]);
+        
+        let lineage: Lineage = server.get(&format!("/api/v1/intent/{}/lineage", root_id)).await.json();
+        assert_eq!(lineage.tree.children.len(), 1);
+        assert_eq!(lineage.tree.children[0].children.len(), 3);
+        
+        let page: ChildrenResponse = server
+            .get(&format!("/api/v1/intent/{}/children", mid_id))
+            .add_query_param("offset", 1)
+            .add_query_param("limit", 1)
+            .await
+            .json();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.children.len(), 1);
+        assert_eq!(page.children[0].id, leaf_ids[1]);
+        assert_eq!(page.children[0].parent_id, Some(mid_id));
+        
+        let response = server.get(&format!("/api/v1/intent/{}/lineage", Uuid::new_v4())).await;
+        response.assert_status_not_found();
+    }
+    
+    #[tokio::test]
+    async fn test_lineage_cycle_is_conflict() {
+        let state = AppState::new();
+        let mut a = Intent::builder().kind("a").build().unwrap();
+        let b = Intent::builder().kind("b").parent(a.id).build().unwrap();
+        a.parent_id = Some(b.id);
+        let a_id = a.id;
+        state.store_intent(a).await;
+        state.store_intent(b).await;
+        
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get(&format!("/api/v1/intent/{}/lineage", a_id)).await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert!(response.text().contains("cycle"));
+    }    
+    #[tokio::test]
+    async fn test_submit_checks_the_parent_chain() {
+        let mut state = AppState::new();
+        state.intent_limits.max_parent_depth = 2;
+        let root = Intent::builder().kind("root").build().unwrap();
+        let mid = Intent::builder().kind("mid").parent(root.id).build().unwrap();
+        let (root_id, mid_id) = (root.id, mid.id);
+        state.store_intent(root).await;
+        state.store_intent(mid).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let submit = |parent_id: Uuid| {
+            server.post("/api/v1/intent").json(&serde_json::json!({ "kind": "leaf", "parent_id": parent_id }))
+        };
+        let message = |response: axum_test::TestResponse| {
+            response.assert_status_bad_request();
+            response.json::<serde_json::Value>()["error"]["message"].as_str().unwrap().to_string()
+        };
+        
+        let response = submit(mid_id).await;
+        response.assert_status(StatusCode::CREATED);
+        let leaf_id = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        assert_eq!(state.get_intent(leaf_id).await.unwrap().intent.parent_id, Some(mid_id));
+        
+        // A third level is past the limit
+        assert!(message(submit(leaf_id).await).contains("deeper than the limit of 2"));
+        
+        let unknown = Uuid::new_v4();
+        assert_eq!(message(submit(unknown).await), format!("Parent intent {} not found", unknown));
+        state.cancel_intent(root_id).await;
+        assert_eq!(message(submit(root_id).await), format!("Parent intent {} is already cancelled", root_id));
+        
+        // Signed intents keep their id, so they can name themselves
+        let key = orpheon_core::crypto::SigningKey::from_bytes(&[9u8; 32]);
+        let mut own = Intent::builder().kind("own").build().unwrap();
+        own.parent_id = Some(own.id);
+        own.sign(&key);
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": own })).await;
+        assert_eq!(message(response), format!("Intent validation failed: Intent {} is its own parent", own.id));
+        
+        // Or close a loop through stored intents
+        let mut a = Intent::builder().kind("a").build().unwrap();
+        let mut b = Intent::builder().kind("b").parent(a.id).build().unwrap();
+        a.parent_id = Some(b.id);
+        b.sign(&key);
+        state.store_intent(a).await;
+        let response = server.post("/api/v1/intent").json(&serde_json::json!({ "intent": b })).await;
+        assert!(message(response).contains(&format!("loops back to intent {}", b.id)));
+    }
 }
@@ -0,0 +1,105 @@
+//! Read access to the operations journal.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::journal::JournalEntry;
+use crate::state::AppState;
+
+/// Largest page the journal endpoint returns.
+const MAX_JOURNAL_PAGE: usize = 1_000;
+
+/// Paging for journal queries.
+#[derive(Debug, Deserialize)]
+pub struct JournalQuery {
+    /// Only return entries after this seq.
+    #[serde(default)]
+    pub since_seq: u64,
+    #[serde(default = "default_journal_limit")]
+    pub limit: usize,
+}
+
+fn default_journal_limit() -> usize {
+    100
+}
+
+/// A page of journal entries.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalResponse {
+    pub entries: Vec<JournalEntry>,
+    /// Seq of the newest entry in the journal.
+    pub last_seq: u64,
+}
+
+/// List journal entries, oldest first.
+pub async fn list_entries(
+    State(state): State<AppState>,
+    Query(query): Query<JournalQuery>,
+) -> Result<Json<JournalResponse>, (StatusCode, String)> {
+    if query.limit > MAX_JOURNAL_PAGE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("limit must be at most {}", MAX_JOURNAL_PAGE),
+        ));
+    }
+
+    Ok(Json(JournalResponse {
+        entries: state.journal.since(query.since_seq, query.limit),
+        last_seq: state.journal.last_seq(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use orpheon_core::{Intent, IntentStatus};
+
+    use crate::journal::JournalEvent;
+
+    #[tokio::test]
+    async fn test_journal_records_submission_and_cancellation() {
+        let state = AppState::new();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let response = server.post("/api/v1/intent").json(&intent).await;
+        response.assert_status(StatusCode::CREATED);
+        let intent_id: uuid::Uuid = response.json::<serde_json::Value>()["id"].as_str().unwrap().parse().unwrap();
+        server.delete(&format!("/api/v1/intent/{}", intent_id)).await.assert_status(StatusCode::NO_CONTENT);
+
+        let page: JournalResponse = server.get("/api/v1/admin/journal").await.json();
+        assert_eq!(page.last_seq, 3);
+        let events: Vec<JournalEvent> = page.entries.into_iter().map(|e| e.event).collect();
+        assert_eq!(events, vec![
+            JournalEvent::IntentSubmitted { intent_id, kind: "deploy".to_string() },
+            JournalEvent::IntentCancelled { intent_id },
+            JournalEvent::StatusChanged {
+                intent_id,
+                from: IntentStatus::Received,
+                to: IntentStatus::Cancelled,
+                reason: None,
+            },
+        ]);
+        assert_eq!(state.journal.verify_chain(), Ok(()));
+
+        let page: JournalResponse = server
+            .get("/api/v1/admin/journal")
+            .add_query_param("since_seq", 1)
+            .add_query_param("limit", 1)
+            .await
+            .json();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].seq, 2);
+
+        server
+            .get("/api/v1/admin/journal")
+            .add_query_param("limit", 5_000)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,6 +1,18 @@
 //! API handlers.
 
+pub mod annotation;
+pub mod blob;
+pub mod bundle;
+pub mod chaos;
+pub mod dryrun;
+pub mod error;
+pub mod export;
 pub mod health;
 pub mod intent;
+pub mod journal;
+pub mod planner;
+pub mod schema;
 pub mod simulate;
+pub mod state;
+pub mod stats;
 pub mod ws;
@@ -0,0 +1,214 @@
+//! Planner action catalog endpoints.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::collections::BTreeMap;
+
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::DurationStats;
+use serde::{Deserialize, Serialize};
+
+use crate::state::AppState;
+
+/// Filters for listing actions.
+#[derive(Debug, Default, Deserialize)]
+pub struct ActionFilter {
+    /// Only actions in this namespace.
+    pub namespace: Option<String>,
+    
+    /// Only actions that may be planned for this intent kind.
+    pub kind: Option<String>,
+}
+
+impl ActionFilter {
+    fn matches(&self, action: &PlanningAction) -> bool {
+        let namespace_ok = self
+            .namespace
+            .as_ref()
+            .is_none_or(|ns| action.namespace.as_ref() == Some(ns));
+        let kind_ok = self.kind.as_ref().is_none_or(|kind| action.applies_to(kind));
+        namespace_ok && kind_ok
+    }
+}
+
+/// The node's planner action catalog.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActionCatalogResponse {
+    /// Catalog file the extra actions were loaded from, if any.
+    pub source: Option<String>,
+    
+    /// Hash of the whole catalog (unfiltered), for client-side caching.
+    pub hash: String,
+    
+    pub actions: Vec<PlanningAction>,
+}
+
+/// Step durations the planner learned from past executions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DurationsResponse {
+    /// Samples an action needs before plans quote its learned p95.
+    pub min_samples: u64,
+    
+    /// Statistics by action name.
+    pub actions: BTreeMap<String, DurationStats>,
+}
+
+/// List the actions the planner can use.
+pub async fn list_actions(
+    State(state): State<AppState>,
+    Query(filter): Query<ActionFilter>,
+) -> Json<ActionCatalogResponse> {
+    let planner = &state.planner;
+    
+    Json(ActionCatalogResponse {
+        source: planner.catalog_source().map(str::to_string),
+        hash: planner.catalog_hash(),
+        actions: planner.actions().iter().filter(|a| filter.matches(a)).cloned().collect(),
+    })
+}
+
+/// Get a single planner action by name.
+pub async fn get_action(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<PlanningAction>, (StatusCode, String)> {
+    state.planner.action(&name).cloned().map(Json).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, format!("Action {} not found", name))
+    })
+}
+
+/// List the step durations learned so far.
+pub async fn list_durations(State(state): State<AppState>) -> Json<DurationsResponse> {
+    let durations = state.planner.durations();
+    Json(DurationsResponse {
+        min_samples: durations.min_samples(),
+        actions: durations.all(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use std::path::Path as FsPath;
+    
+    use orpheon_core::artifact::{ExecutionEvent, Outcome};
+    use orpheon_core::{ExecutionArtifact, Intent};
+    use orpheon_planner::planner::{Planner, PlanningState};
+    use orpheon_state::{Keys, StateStore};
+    
+    use crate::seed::SeedData;
+    use crate::testing::TestNode;
+    
+    #[tokio::test]
+    async fn test_default_actions_listed_and_filtered() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        
+        let catalog: ActionCatalogResponse = server.get("/api/v1/planner/actions").await.json();
+        assert!(catalog.source.is_none());
+        let names: Vec<_> = catalog.actions.iter().map(|a| a.name.as_str()).collect();
+        assert!(names.contains(&"allocate_resource"));
+        assert!(names.contains(&"finalize"));
+        
+        let filtered: ActionCatalogResponse = server
+            .get("/api/v1/planner/actions")
+            .add_query_param("namespace", "billing")
+            .await
+            .json();
+        assert!(filtered.actions.is_empty());
+        assert_eq!(filtered.hash, catalog.hash);
+        
+        let action: PlanningAction = server.get("/api/v1/planner/actions/finalize").await.json();
+        assert_eq!(action.effects, vec!["complete".to_string()]);
+        server
+            .get("/api/v1/planner/actions/launch_rocket")
+            .await
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_seeded_catalog_reports_source_and_hash() {
+        let fixture = FsPath::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/seed");
+        let node = TestNode::seeded(&fixture).await.unwrap();
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap();
+        
+        let catalog = client.list_actions().await.unwrap();
+        assert_eq!(catalog.source.as_deref(), Some("seed/actions.json"));
+        assert!(catalog.actions.iter().any(|a| a.name == "provision_compute_eu"));
+        
+        // A different catalog hashes differently
+        let defaults = AppState::new().planner.catalog_hash();
+        assert_ne!(catalog.hash, defaults);
+        let reloaded = SeedData::load(&fixture).unwrap().into_state().await.unwrap();
+        assert_eq!(reloaded.planner.catalog_hash(), catalog.hash);
+    }
+    
+    #[tokio::test]
+    async fn test_plans_quote_learned_durations() {
+        let state = AppState::new();
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+        let intent = Intent::builder().kind("provision_compute").build().unwrap();
+        let plan = state.planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert!(plan.steps.iter().all(|s| s.metadata["duration_source"] == "static"));
+        
+        // Every step of past executions took three times its estimate
+        let min_samples = state.planner.durations().min_samples();
+        for _ in 0..min_samples {
+            let mut artifact = ExecutionArtifact::new(intent.clone(), plan.clone(), Outcome::Success);
+            for step in &plan.steps {
+                artifact.add_event(ExecutionEvent::step_completed(step.id, step.estimated_duration_ms * 3));
+            }
+            state.store_artifact(artifact).await;
+        }
+        
+        let replanned = state.planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        for (before, after) in plan.steps.iter().zip(&replanned.steps) {
+            assert_eq!(after.estimated_duration_ms, before.estimated_duration_ms * 3);
+            assert_eq!(after.metadata["duration_source"], "learned");
+        }
+        assert_eq!(replanned.estimated_latency_ms, plan.estimated_latency_ms * 3);
+        
+        let learned: DurationsResponse = server.get("/api/v1/planner/durations").await.json();
+        assert_eq!(learned.min_samples, min_samples);
+        let stats = &learned.actions["provision_compute"];
+        assert_eq!((stats.samples, stats.p50_ms, stats.p95_ms), (min_samples, 1_500, 1_500));
+        
+        // Kept in the state store, and restored from it
+        let restored = AppState::new();
+        for entry in state.state_store.get_prefix(Keys::action_stats_prefix()).await.unwrap() {
+            restored.state_store.set(&entry.key, entry.value).await.unwrap();
+        }
+        restored.restore_durations().await.unwrap();
+        assert_eq!(restored.planner.durations().all(), state.planner.durations().all());
+    }
+    
+    #[tokio::test]
+    async fn test_kind_filter() {
+        let mut planner = orpheon_planner::AStarPlanner::new();
+        planner.register_action(PlanningAction {
+            name: "snapshot_db".to_string(),
+            namespace: Some("storage".to_string()),
+            kinds: vec!["backup".to_string()],
+            ..Default::default()
+        }).unwrap();
+        let server = TestServer::new(crate::create_router(AppState::with_planner(planner))).unwrap();
+        
+        let deploy: ActionCatalogResponse = server
+            .get("/api/v1/planner/actions")
+            .add_query_param("kind", "deploy")
+            .await
+            .json();
+        assert!(deploy.actions.iter().all(|a| a.name != "snapshot_db"));
+        
+        let storage: ActionCatalogResponse = server
+            .get("/api/v1/planner/actions")
+            .add_query_param("namespace", "storage")
+            .add_query_param("kind", "backup")
+            .await
+            .json();
+        assert_eq!(storage.actions.len(), 1);
+    }
+}
@@ -0,0 +1,63 @@
+//! JSON Schemas of the wire types, for clients not written in Rust.
+
+use axum::{extract::Path, http::StatusCode, Json};
+use orpheon_core::{Budget, Constraint, ExecutionArtifact, Intent, Plan, Preference, Step, TimeWindow};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+
+use crate::api::error::ApiError;
+use crate::api::intent::SubmitIntentBody;
+
+/// Names served by `/api/v1/schema/:type`.
+pub const SCHEMA_TYPES: [&str; 9] = [
+    "intent",
+    "constraint",
+    "preference",
+    "budget",
+    "time_window",
+    "plan",
+    "step",
+    "execution_artifact",
+    "submit_intent_request",
+];
+
+/// The schema of the type called `name`, if it is one of [`SCHEMA_TYPES`].
+pub fn schema(name: &str) -> Option<RootSchema> {
+    let schema = match name {
+        "intent" => schema_for!(Intent),
+        "constraint" => schema_for!(Constraint),
+        "preference" => schema_for!(Preference),
+        "budget" => schema_for!(Budget),
+        "time_window" => schema_for!(TimeWindow),
+        "plan" => schema_for!(Plan),
+        "step" => schema_for!(Step),
+        "execution_artifact" => schema_for!(ExecutionArtifact),
+        "submit_intent_request" => schema_for!(SubmitIntentBody),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// Get the JSON Schema of a type.
+pub async fn get_schema(Path(name): Path<String>) -> Result<Json<RootSchema>, ApiError> {
+    schema(&name).map(Json).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::NOT_FOUND,
+            "unknown_schema",
+            format!("No schema named {}; expected one of {}", name, SCHEMA_TYPES.join(", ")),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_listed_type_has_a_schema() {
+        for name in SCHEMA_TYPES {
+            assert!(schema(name).is_some(), "{}", name);
+        }
+        assert!(schema("proposal").is_none());
+    }
+}
@@ -1,13 +1,15 @@
 //! Simulation endpoint.
 
-use axum::{extract::State, http::StatusCode, Json};
-use orpheon_core::{Budget, Intent, Plan};
+use axum::{extract::State, http::StatusCode, Extension, Json};
+use orpheon_core::{Budget, Intent, ResourceType};
 use orpheon_planner::planner::PlanningState;
-use orpheon_planner::Planner;
+use orpheon_planner::CancellationToken;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::api::error::ApiError;
 use crate::state::AppState;
+use crate::timeouts::RequestIntent;
 
 /// Request for simulation.
 #[derive(Debug, Deserialize)]
@@ -15,25 +17,18 @@ pub struct SimulateRequest {
     /// The kind of intent to simulate.
     pub kind: String,
     
-    /// Constraints for the simulation.
-    #[serde(default)]
-    pub constraints: Vec<serde_json::Value>,
-    
-    /// Preferences for the simulation.
-    #[serde(default)]
-    pub preferences: Vec<serde_json::Value>,
-    
     /// Budget configuration.
     pub budget: Option<BudgetInput>,
-    
-    /// Target simulation time (ISO 8601).
-    pub simulate_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct BudgetInput {
     pub max_cost: Option<f64>,
+    pub currency: Option<String>,
     pub max_duration_ms: Option<u64>,
+    pub max_retries: Option<u32>,
+    #[serde(default)]
+    pub resource_limits: Vec<(ResourceType, f64)>,
 }
 
 /// Response from simulation.
@@ -43,7 +38,10 @@ pub struct SimulateResponse {
     pub success: bool,
     pub plan: Option<PlanSummary>,
     pub estimated_cost: f64,
+    /// Length of the plan's critical path.
     pub estimated_duration_ms: u64,
+    /// Sum of all step durations, however many run at once.
+    pub estimated_work_ms: u64,
     pub confidence_score: f32,
     pub warnings: Vec<String>,
     pub error: Option<String>,
@@ -54,50 +52,69 @@ pub struct PlanSummary {
     pub id: Uuid,
     pub steps: usize,
     pub strategy: String,
+    /// Number of waves of steps that can run in parallel.
+    pub waves: usize,
 }
 
 /// Simulate an intent without executing.
+///
+/// Planning runs on a blocking thread and is cancelled if the request is
+/// dropped, e.g. when it runs out of time.
 pub async fn simulate_intent(
     State(state): State<AppState>,
+    Extension(request_intent): Extension<RequestIntent>,
     Json(req): Json<SimulateRequest>,
-) -> Result<Json<SimulateResponse>, (StatusCode, String)> {
+) -> Result<Json<SimulateResponse>, ApiError> {
     // Build a temporary intent for simulation
+    let budget = req.budget.as_ref();
     let intent = Intent::builder()
         .kind(&req.kind)
         .budget(Budget {
-            max_cost: req.budget.as_ref().and_then(|b| b.max_cost),
-            currency: "USD".to_string(),
-            max_duration_ms: req.budget.as_ref().and_then(|b| b.max_duration_ms),
-            max_retries: 3,
+            max_cost: budget.and_then(|b| b.max_cost),
+            max_cost_minor: None,
+            currency: budget
+                .and_then(|b| b.currency.as_deref())
+                .map_or_else(|| "USD".to_string(), |c| c.to_ascii_uppercase()),
+            max_duration_ms: budget.and_then(|b| b.max_duration_ms),
+            max_retries: budget.and_then(|b| b.max_retries).unwrap_or(3),
+            resource_limits: budget.map(|b| b.resource_limits.clone()).unwrap_or_default(),
         })
-        .build()
-        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        .build()?;
+    intent.validate_against(&state.intent_limits, Some(state.constraint_registry()))?;
+    // Action costs are in the base currency, so the budget is planned in it
+    let budget = state.base_budget(&intent)?;
+    let intent = Intent { budget: budget.clone(), ..intent };
+    request_intent.record(intent.id);
 
     // Run the planner
-    let initial_state = PlanningState::default();
-    let plan_result = state.planner.plan(&intent, &initial_state).await;
+    let cancel = CancellationToken::new();
+    let _guard = cancel.clone().drop_guard();
+    let planner = state.planner.clone();
+    let plan_result = tokio::task::spawn_blocking(move || {
+        planner.plan_with_cancellation(&intent, &PlanningState::default(), &cancel).into_result()
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     match plan_result {
         Ok(plan) => {
             let mut warnings = Vec::new();
             
             // Check budget
-            if let Some(ref budget) = req.budget {
-                if let Some(max) = budget.max_cost {
-                    if plan.estimated_cost > max {
-                        warnings.push(format!(
-                            "Estimated cost ${:.2} exceeds budget ${:.2}",
-                            plan.estimated_cost, max
-                        ));
-                    }
+            if let Some(max) = budget.cost_limit() {
+                if plan.estimated_cost > max {
+                    warnings.push(format!(
+                        "Estimated cost {:.2} {} exceeds budget {:.2} {}",
+                        plan.estimated_cost, budget.currency, max, budget.currency
+                    ));
                 }
-                if let Some(max) = budget.max_duration_ms {
-                    if plan.estimated_latency_ms > max {
-                        warnings.push(format!(
-                            "Estimated duration {}ms exceeds limit {}ms",
-                            plan.estimated_latency_ms, max
-                        ));
-                    }
+            }
+            if let Some(max) = budget.max_duration_ms {
+                if plan.estimated_latency_ms > max {
+                    warnings.push(format!(
+                        "Estimated duration {}ms exceeds limit {}ms",
+                        plan.estimated_latency_ms, max
+                    ));
                 }
             }
 
@@ -108,9 +125,11 @@ pub async fn simulate_intent(
                     id: plan.id,
                     steps: plan.steps.len(),
                     strategy: format!("{:?}", plan.strategy).to_lowercase(),
+                    waves: plan.parallel_groups().len(),
                 }),
                 estimated_cost: plan.estimated_cost,
                 estimated_duration_ms: plan.estimated_latency_ms,
+                estimated_work_ms: plan.estimated_work_ms,
                 confidence_score: plan.confidence_score,
                 warnings,
                 error: None,
@@ -123,6 +142,7 @@ pub async fn simulate_intent(
                 plan: None,
                 estimated_cost: 0.0,
                 estimated_duration_ms: 0,
+                estimated_work_ms: 0,
                 confidence_score: 0.0,
                 warnings: Vec::new(),
                 error: Some(e.to_string()),
@@ -130,3 +150,106 @@ pub async fn simulate_intent(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum_test::TestServer;
+    use orpheon_planner::planner::PlanningAction;
+    use orpheon_planner::{AStarPlanner, PlannerConfig};
+    use serde_json::json;
+
+    use crate::state::AppState;
+    use crate::timeouts::RequestTimeoutConfig;
+
+    #[tokio::test]
+    async fn test_simulate_rejects_invalid_budget_fields() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        for (field, budget) in [
+            ("budget.max_cost", json!({ "max_cost": -50.0 })),
+            ("budget.currency", json!({ "currency": "dollars" })),
+            ("budget.max_duration_ms", json!({ "max_duration_ms": 1u64 << 40 })),
+            ("budget.max_retries", json!({ "max_retries": 500 })),
+        ] {
+            let response = server.post("/api/v1/simulate").json(&json!({ "kind": "deploy", "budget": budget })).await;
+            response.assert_status_bad_request();
+            let error: serde_json::Value = response.json();
+            assert_eq!(error["error"]["fields"][0]["field"], field);
+        }
+
+        let response = server
+            .post("/api/v1/simulate")
+            .json(&json!({ "kind": "deploy", "budget": { "max_cost": 10.0, "currency": "usd" } }))
+            .await;
+        response.assert_status_ok();
+
+        // Valid, but this node has no rate for it
+        let response = server
+            .post("/api/v1/simulate")
+            .json(&json!({ "kind": "deploy", "budget": { "max_cost": 10.0, "currency": "gbp" } }))
+            .await;
+        response.assert_status_bad_request();
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["message"], "Intent validation failed: No exchange rate from GBP to USD");
+    }
+    
+    #[tokio::test]
+    async fn test_simulate_reports_critical_path_and_total_work() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+        let response = server.post("/api/v1/simulate").json(&json!({ "kind": "provision_compute" })).await;
+        response.assert_status_ok();
+        let simulation: serde_json::Value = response.json();
+        
+        // The planner chains its steps, so nothing overlaps
+        let steps = simulation["plan"]["steps"].as_u64().unwrap();
+        assert!(steps > 1);
+        assert_eq!(simulation["plan"]["waves"], steps);
+        assert!(simulation["estimated_duration_ms"].as_u64().unwrap() > 0);
+        assert_eq!(simulation["estimated_work_ms"], simulation["estimated_duration_ms"]);
+    }
+    
+    #[tokio::test]
+    async fn test_simulate_times_out_and_cancels_planning() {
+        // A long chain of actions, with a heuristic slow enough that
+        // planning it takes seconds
+        let catalog = (1..=100)
+            .map(|i| PlanningAction {
+                name: format!("step_{}", i),
+                preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+                effects: vec![if i == 100 { "complete".to_string() } else { format!("s{}", i) }],
+                cost: 1.0,
+                duration_ms: 1,
+                ..Default::default()
+            })
+            .collect();
+        let expanded = Arc::new(AtomicUsize::new(0));
+        let counter = expanded.clone();
+        let planner = AStarPlanner::with_actions(PlannerConfig { max_steps: 200, ..Default::default() }, catalog)
+            .with_heuristic(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(20));
+                0.0
+            });
+        let mut state = AppState::with_planner(planner);
+        state.request_timeouts = RequestTimeoutConfig { planning_ms: Some(100), ..Default::default() };
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+
+        let response = server.post("/api/v1/simulate").json(&json!({ "kind": "deploy" })).await;
+        response.assert_status(axum::http::StatusCode::GATEWAY_TIMEOUT);
+        let error: serde_json::Value = response.json();
+        assert_eq!(error["error"]["code"], "timeout");
+
+        // The search stops at its next node rather than running on
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stopped_at = expanded.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(expanded.load(Ordering::SeqCst), stopped_at);
+        assert!(stopped_at < 100, "{}", stopped_at);
+
+        // Other routes keep their own budget
+        server.get("/api/v1/intents").await.assert_status_ok();
+    }
+}
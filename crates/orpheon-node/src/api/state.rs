@@ -0,0 +1,77 @@
+//! State store export.
+
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+
+use crate::state::AppState;
+
+/// Content type of a state export, one JSON entry per line.
+const NDJSON: &str = "application/x-ndjson";
+
+/// Header carrying the store version an export was taken at.
+pub const STATE_VERSION_HEADER: &str = "x-orpheon-state-version";
+
+/// Entries serialized per chunk of the response body.
+const EXPORT_CHUNK_ENTRIES: usize = 256;
+
+/// Stream every current state entry as newline-delimited JSON.
+///
+/// The export is a point-in-time snapshot; entries are serialized as the
+/// body is sent, so large stores are never held in memory twice.
+pub async fn export_state(State(state): State<AppState>) -> Response {
+    let snapshot = state.state_store.snapshot_streaming().await;
+    let version = snapshot.version();
+
+    let chunks = futures::stream::iter(snapshot).chunks(EXPORT_CHUNK_ENTRIES).map(|entries| {
+        let mut chunk = String::new();
+        for entry in entries {
+            chunk.push_str(&serde_json::to_string(&entry).unwrap_or_default());
+            chunk.push('\n');
+        }
+        Ok::<_, Infallible>(chunk)
+    });
+
+    (
+        [
+            (header::CONTENT_TYPE, NDJSON.to_string()),
+            (header::HeaderName::from_static(STATE_VERSION_HEADER), version.to_string()),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use orpheon_state::store::StateEntry;
+    use orpheon_state::StateStore;
+
+    #[tokio::test]
+    async fn test_export_streams_every_entry() {
+        let state = AppState::new();
+        for i in 0..1_000 {
+            state.state_store.set(&format!("service/{:04}", i), serde_json::json!({"replicas": i})).await.unwrap();
+        }
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+
+        let response = server.get("/api/v1/admin/state/export").await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::CONTENT_TYPE), NDJSON);
+        assert_eq!(response.header(STATE_VERSION_HEADER), "1000");
+
+        let entries: Vec<StateEntry> =
+            response.text().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries.len(), 1_000);
+        let entry = entries.iter().find(|e| e.key == "service/0999").unwrap();
+        assert_eq!(entry.value["replicas"], 999);
+    }
+}
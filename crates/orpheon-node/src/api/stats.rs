@@ -0,0 +1,165 @@
+//! Historical funnel statistics.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::backlog::BacklogWatermarks;
+use crate::state::AppState;
+use crate::stats::{DayStats, Funnel};
+
+/// Most days one stats query may span.
+const MAX_STATS_DAYS: i64 = 366;
+
+/// Range of UTC days to report, both included; each defaults to today.
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// Daily aggregates over a range, their totals and the funnel they make.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Days with any statistic, oldest first.
+    pub days: Vec<DayStats>,
+    /// Each metric summed over the range.
+    pub totals: BTreeMap<String, f64>,
+    pub funnel: Funnel,
+    /// The oldest intent waiting in each status, as of now.
+    #[serde(default)]
+    pub backlog: BacklogWatermarks,
+}
+
+/// Report intent and negotiation statistics for a range of days.
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsResponse>, (StatusCode, String)> {
+    let today = Utc::now().date_naive();
+    let from = query.from.unwrap_or(today);
+    let to = query.to.unwrap_or(today);
+    if from > to {
+        return Err((StatusCode::BAD_REQUEST, format!("from ({}) is after to ({})", from, to)));
+    }
+    if (to - from).num_days() >= MAX_STATS_DAYS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("range must span at most {} days", MAX_STATS_DAYS),
+        ));
+    }
+
+    let days = state
+        .stats
+        .days(from, to)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut totals = BTreeMap::new();
+    for day in &days {
+        for (metric, value) in &day.metrics {
+            *totals.entry(metric.clone()).or_insert(0.0) += value;
+        }
+    }
+    let funnel = Funnel::from_totals(&totals);
+
+    let backlog = state.backlog_index.watermarks(Utc::now());
+
+    Ok(Json(StatsResponse { from, to, days, totals, funnel, backlog }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum_test::TestServer;
+    use orpheon_core::{Intent, Plan, PlanningStrategy};
+    use orpheon_negotiate::{CounterOffer, NegotiationMessage, NegotiationSession};
+    use tokio::sync::mpsc::Receiver;
+
+    /// A session negotiating a fresh intent, reporting to the node's stats,
+    /// with the receiver of its messages for the client.
+    async fn session(state: &AppState, timeout: Duration) -> (Arc<NegotiationSession>, Intent, Receiver<NegotiationMessage>) {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        state.store_intent(intent.clone()).await;
+        let (session, _incoming_tx, outgoing_rx) = NegotiationSession::with_timeout(intent.clone(), timeout, 5);
+        (Arc::new(session.with_observer(state.stats.clone())), intent, outgoing_rx)
+    }
+
+    fn plan(intent: &Intent) -> Plan {
+        Plan::new(intent.id, PlanningStrategy::Heuristic)
+    }
+
+    #[tokio::test]
+    async fn test_scripted_negotiations_make_the_funnel() {
+        let state = AppState::new();
+        let minute = Duration::from_secs(60);
+
+        // Accepted in the first round
+        let (first, intent, _first_rx) = session(&state, minute).await;
+        let proposal = first.send_proposal(plan(&intent)).await.unwrap();
+        first.accept(proposal.id).await.unwrap();
+
+        // Countered, then accepted in the second round
+        let (second, intent, _second_rx) = session(&state, minute).await;
+        let proposal = second.send_proposal(plan(&intent)).await.unwrap();
+        second.counter(CounterOffer::new(proposal.id).with_max_cost(1.0)).await.unwrap();
+        let proposal = second.send_proposal(plan(&intent)).await.unwrap();
+        second.accept(proposal.id).await.unwrap();
+
+        // Rejected
+        let (third, intent, _third_rx) = session(&state, minute).await;
+        third.send_proposal(plan(&intent)).await.unwrap();
+        third.reject("no thanks".to_string()).await.unwrap();
+
+        // Timed out
+        let (fourth, intent, _fourth_rx) = session(&state, Duration::from_millis(20)).await;
+        fourth.send_proposal(plan(&intent)).await.unwrap();
+        assert!(fourth.spawn_timeout_watch().await.unwrap());
+
+        let server = TestServer::new(crate::create_router(state)).unwrap();
+        let response = server.get("/api/v1/stats").await;
+        response.assert_status_ok();
+        let stats: StatsResponse = response.json();
+
+        let today = Utc::now().date_naive();
+        assert_eq!((stats.from, stats.to), (today, today));
+        assert_eq!(stats.days.len(), 1);
+        assert_eq!(stats.totals["proposals_sent"], 5.0);
+        assert_eq!(stats.totals["negotiation_rounds"], 5.0);
+
+        let funnel = stats.funnel;
+        assert_eq!(funnel.submitted, 4);
+        assert_eq!(funnel.proposals, 5);
+        assert_eq!(funnel.countered, 1);
+        assert_eq!((funnel.accepted, funnel.rejected, funnel.timed_out), (2, 1, 1));
+        assert_eq!(funnel.average_rounds, Some(1.25));
+        assert!(funnel.average_acceptance_latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stats_range_is_checked() {
+        let server = TestServer::new(crate::create_router(AppState::new())).unwrap();
+
+        let response = server.get("/api/v1/stats").add_query_param("from", "2026-03-02").add_query_param("to", "2026-03-01").await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+        let response = server.get("/api/v1/stats").add_query_param("from", "2024-01-01").add_query_param("to", "2026-01-01").await;
+        response.assert_status(StatusCode::BAD_REQUEST);
+
+        // Days without statistics are empty, not errors
+        let response = server.get("/api/v1/stats").add_query_param("from", "2020-01-01").add_query_param("to", "2020-01-31").await;
+        response.assert_status_ok();
+        let stats: StatsResponse = response.json();
+        assert!(stats.days.is_empty());
+        assert_eq!(stats.funnel, Funnel::default());
+    }
+}
@@ -1,36 +1,154 @@
 //! WebSocket endpoints.
+//!
+//! Streams the node ends itself are closed with a [`WsCloseReason`] in the
+//! close frame.
+
+use std::collections::{HashMap, HashSet};
 
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
         Path, State,
     },
     response::Response,
 };
-use orpheon_state::StateStore;
+use orpheon_core::WsCloseReason;
+use orpheon_negotiate::handshake::{self, COUNTER_OFFERS};
+use orpheon_negotiate::{Agreement, NegotiationMessage, NegotiationSession};
 use serde::{Deserialize, Serialize};
-use tokio::time::{interval, Duration};
+use tokio::sync::watch;
+use tokio::time::{interval, sleep_until, Duration, Instant};
 use uuid::Uuid;
 
-use crate::state::AppState;
+use crate::state::{AppState, IntentRecord, StatusChange};
+
+/// How long a negotiation client has to answer the node's hello before it
+/// is taken to speak protocol version 1.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Maximum number of intents a single multiplexed connection may watch.
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 256;
 
 /// WebSocket message for intent updates.
+///
+/// Status updates for an intent are sent in order, each carrying the
+/// intent's status change `seq`; a jump in `seq` means updates were missed.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum IntentStreamMessage {
     /// Status update.
     StatusUpdate {
         intent_id: Uuid,
+        seq: u64,
         status: String,
         plan_id: Option<Uuid>,
         artifact_id: Option<Uuid>,
+        /// Percentage of steps that succeeded, for `partially_complete`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        success_rate: Option<u8>,
+    },
+    /// Warning about an intent that is not a status change, such as
+    /// `deadline_at_risk`.
+    Warning {
+        intent_id: Uuid,
+        code: String,
+        message: String,
+    },
+    /// Notice about the node itself rather than any intent, such as
+    /// `backlog_age` (multiplexed streams only).
+    SystemNotice {
+        code: String,
+        message: String,
     },
     /// Error message.
-    Error { message: String },
+    Error {
+        /// The intent the error relates to (multiplexed streams only).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intent_id: Option<Uuid>,
+        message: String,
+    },
     /// Ping for keepalive.
     Ping,
 }
 
+impl IntentStreamMessage {
+    fn status_update(intent_id: Uuid, change: &StatusChange) -> Self {
+        IntentStreamMessage::StatusUpdate {
+            intent_id,
+            seq: change.seq,
+            status: change.status.as_str().to_string(),
+            plan_id: change.plan_id,
+            artifact_id: change.artifact_id,
+            success_rate: change.success_rate,
+        }
+    }
+    
+    /// Updates for the changes after `last_seq`; a subscriber that has seen
+    /// nothing yet (`last_seq` 0) only gets the latest.
+    fn updates_since(record: &IntentRecord, last_seq: u64) -> Vec<Self> {
+        let changes = match (last_seq, record.changes.last()) {
+            (0, Some(latest)) => std::slice::from_ref(latest),
+            _ => record.changes.as_slice(),
+        };
+        changes
+            .iter()
+            .filter(|c| c.seq > last_seq)
+            .map(|c| Self::status_update(record.intent.id, c))
+            .collect()
+    }
+    
+    /// Warnings after the first `sent` of the record's warnings.
+    fn warnings_since(record: &IntentRecord, sent: usize) -> Vec<Self> {
+        record.warnings[sent.min(record.warnings.len())..]
+            .iter()
+            .map(|w| IntentStreamMessage::Warning {
+                intent_id: record.intent.id,
+                code: w.code.clone(),
+                message: w.message.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Client frame on the multiplexed intents stream.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntentsStreamRequest {
+    /// Watch specific intents.
+    Subscribe(Vec<Uuid>),
+    /// Stop watching specific intents.
+    Unsubscribe(Vec<Uuid>),
+    /// Watch every intent matching a filter, including ones submitted later.
+    SubscribeFilter(IntentFilter),
+}
+
+/// Filter for intent subscriptions.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IntentFilter {
+    /// Only intents of this kind.
+    pub kind: Option<String>,
+    /// Status name, or "active"/"terminal" for status groups.
+    pub status: Option<String>,
+}
+
+impl IntentFilter {
+    /// Check whether an intent record matches this filter.
+    pub fn matches(&self, record: &IntentRecord) -> bool {
+        if let Some(ref kind) = self.kind {
+            if &record.intent.kind != kind {
+                return false;
+            }
+        }
+        
+        match self.status.as_deref() {
+            None => true,
+            Some("active") => record.status.is_active(),
+            Some("terminal") => record.status.is_terminal(),
+            Some(status) => record.status.as_str() == status,
+        }
+    }
+}
+
 /// Intent status stream.
 pub async fn intent_stream(
     ws: WebSocketUpgrade,
@@ -42,48 +160,48 @@ pub async fn intent_stream(
 
 async fn handle_intent_stream(mut socket: WebSocket, intent_id: Uuid, state: AppState) {
     let mut poll_interval = interval(Duration::from_millis(500));
-    let mut last_status = String::new();
+    let mut shutdown = state.shutdown_signal();
+    let mut last_seq = 0;
+    let mut warnings_sent = 0;
 
-    loop {
+    let reason = loop {
         tokio::select! {
             _ = poll_interval.tick() => {
                 // Check intent status
                 if let Some(record) = state.get_intent(intent_id).await {
-                    let status = format!("{:?}", record.status).to_lowercase();
-                    
-                    // Only send if status changed
-                    if status != last_status {
-                        last_status = status.clone();
-                        
-                        let msg = IntentStreamMessage::StatusUpdate {
-                            intent_id,
-                            status,
-                            plan_id: record.plan_id,
-                            artifact_id: record.artifact_id,
-                        };
-                        
+                    // Only send the changes and warnings not sent yet
+                    let mut messages = Vec::new();
+                    if record.seq() != last_seq {
+                        messages = IntentStreamMessage::updates_since(&record, last_seq);
+                    }
+                    messages.extend(IntentStreamMessage::warnings_since(&record, warnings_sent));
+                    for msg in messages {
                         let json = serde_json::to_string(&msg).unwrap();
-                        if socket.send(Message::Text(json.into())).await.is_err() {
-                            break;
-                        }
-                        
-                        // Close if terminal
-                        if record.status.is_terminal() {
-                            break;
+                        if socket.send(Message::Text(json)).await.is_err() {
+                            return;
                         }
                     }
+                    last_seq = record.seq();
+                    warnings_sent = record.warnings.len();
+                    
+                    // Close if terminal
+                    if record.status.is_terminal() {
+                        break WsCloseReason::Terminal;
+                    }
                 } else {
                     let msg = IntentStreamMessage::Error {
+                        intent_id: None,
                         message: format!("Intent {} not found", intent_id),
                     };
                     let json = serde_json::to_string(&msg).unwrap();
-                    let _ = socket.send(Message::Text(json.into())).await;
-                    break;
+                    let _ = socket.send(Message::Text(json)).await;
+                    break WsCloseReason::NotFound;
                 }
             }
+            _ = shutting_down(&mut shutdown) => break WsCloseReason::ServerShutdown,
             msg = socket.recv() => {
                 match msg {
-                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(Message::Close(_))) | None => return,
                     Some(Ok(Message::Ping(data))) => {
                         let _ = socket.send(Message::Pong(data)).await;
                     }
@@ -91,6 +209,211 @@ async fn handle_intent_stream(mut socket: WebSocket, intent_id: Uuid, state: App
                 }
             }
         }
+    };
+    close(&mut socket, reason).await;
+}
+
+/// Multiplexed status stream for many intents over one socket.
+pub async fn intents_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_intents_stream(socket, state))
+}
+
+/// What a connection has been sent about one intent.
+#[derive(Debug, Default, Clone, Copy)]
+struct Sent {
+    /// Seq of the last status update sent (0 before the first).
+    seq: u64,
+    /// Number of the intent's warnings sent.
+    warnings: usize,
+}
+
+/// Per-connection subscription bookkeeping for the multiplexed stream.
+#[derive(Default)]
+struct IntentWatchSet {
+    /// Watched intents and what has been sent for each.
+    watched: HashMap<Uuid, Sent>,
+    /// Intents subscribed by id; only these count against the limit.
+    explicit: HashSet<Uuid>,
+    /// Filters whose future matches are added automatically.
+    filters: Vec<IntentFilter>,
+    /// Filter matches that were dropped after finishing or no longer
+    /// matching, with what had been sent, so they aren't replayed.
+    retired: HashMap<Uuid, Sent>,
+    /// Seq of the last system notice sent.
+    notices: u64,
+}
+
+impl IntentWatchSet {
+    /// Start watching an intent by id. Returns false if the limit is reached.
+    fn watch(&mut self, id: Uuid) -> bool {
+        if self.explicit.contains(&id) {
+            return true;
+        }
+        if self.explicit.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            return false;
+        }
+        self.explicit.insert(id);
+        let sent = self.retired.remove(&id).unwrap_or_default();
+        self.watched.entry(id).or_insert(sent);
+        true
+    }
+    
+    /// Collect the messages to send, recording the statuses as sent.
+    async fn poll(&mut self, state: &AppState) -> Vec<IntentStreamMessage> {
+        let mut messages: Vec<IntentStreamMessage> = state
+            .notices
+            .since(self.notices)
+            .into_iter()
+            .map(|notice| {
+                self.notices = notice.seq;
+                IntentStreamMessage::SystemNotice { code: notice.code, message: notice.message }
+            })
+            .collect();
+        
+        let intents = state.intents.read().await;
+        if !self.filters.is_empty() {
+            self.retired.retain(|id, _| intents.contains_key(id));
+            for (id, record) in intents.iter() {
+                if self.watched.contains_key(id) || !self.filters.iter().any(|f| f.matches(record)) {
+                    continue;
+                }
+                // A retired match comes back only once it has changed again
+                let sent = match self.retired.get(id) {
+                    Some(sent) if record.seq() <= sent.seq && record.warnings.len() <= sent.warnings => continue,
+                    Some(_) => self.retired.remove(id).unwrap_or_default(),
+                    None => Sent::default(),
+                };
+                self.watched.insert(*id, sent);
+            }
+        }
+        
+        let mut missing = Vec::new();
+        let mut finished = Vec::new();
+        for (id, sent) in self.watched.iter_mut() {
+            match intents.get(id) {
+                Some(record) => {
+                    messages.extend(IntentStreamMessage::updates_since(record, sent.seq));
+                    messages.extend(IntentStreamMessage::warnings_since(record, sent.warnings));
+                    *sent = Sent { seq: record.seq(), warnings: record.warnings.len() };
+                    
+                    let done = record.status.is_terminal() || !self.filters.iter().any(|f| f.matches(record));
+                    if done && !self.explicit.contains(id) {
+                        finished.push(*id);
+                    }
+                }
+                None => missing.push(*id),
+            }
+        }
+        drop(intents);
+        
+        for id in finished {
+            if let Some(sent) = self.watched.remove(&id) {
+                self.retired.insert(id, sent);
+            }
+        }
+        for id in missing {
+            self.watched.remove(&id);
+            self.explicit.remove(&id);
+            messages.push(IntentStreamMessage::Error {
+                intent_id: Some(id),
+                message: format!("Intent {} not found", id),
+            });
+        }
+        
+        messages
+    }
+    
+    /// Apply a client frame, returning any immediate error messages.
+    fn apply(&mut self, request: IntentsStreamRequest) -> Vec<IntentStreamMessage> {
+        let mut errors = Vec::new();
+        match request {
+            IntentsStreamRequest::Subscribe(ids) => {
+                for id in ids {
+                    if !self.watch(id) {
+                        errors.push(limit_error(id));
+                        break;
+                    }
+                }
+            }
+            IntentsStreamRequest::Unsubscribe(ids) => {
+                for id in ids {
+                    self.explicit.remove(&id);
+                    self.watched.remove(&id);
+                }
+            }
+            IntentsStreamRequest::SubscribeFilter(filter) => {
+                if self.filters.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+                    errors.push(IntentStreamMessage::Error {
+                        intent_id: None,
+                        message: "Too many subscription filters".to_string(),
+                    });
+                } else {
+                    self.filters.push(filter);
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn limit_error(intent_id: Uuid) -> IntentStreamMessage {
+    IntentStreamMessage::Error {
+        intent_id: Some(intent_id),
+        message: format!(
+            "Subscription limit of {} intents reached",
+            MAX_SUBSCRIPTIONS_PER_CONNECTION
+        ),
+    }
+}
+
+async fn handle_intents_stream(mut socket: WebSocket, state: AppState) {
+    let mut poll_interval = interval(Duration::from_millis(500));
+    let mut shutdown = state.shutdown_signal();
+    // Only notices raised while connected are sent
+    let mut watch_set = IntentWatchSet { notices: state.notices.latest_seq(), ..Default::default() };
+
+    loop {
+        let messages = tokio::select! {
+            _ = poll_interval.tick() => watch_set.poll(&state).await,
+            _ = shutting_down(&mut shutdown) => {
+                close(&mut socket, WsCloseReason::ServerShutdown).await;
+                return;
+            }
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<IntentsStreamRequest>(&text) {
+                            Ok(request) => {
+                                // Errors first, then a snapshot of the current statuses
+                                let mut messages = watch_set.apply(request);
+                                messages.extend(watch_set.poll(&state).await);
+                                messages
+                            }
+                            Err(e) => vec![IntentStreamMessage::Error {
+                                intent_id: None,
+                                message: format!("Invalid subscription frame: {}", e),
+                            }],
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                    Some(Ok(Message::Ping(data))) => {
+                        let _ = socket.send(Message::Pong(data)).await;
+                        Vec::new()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        };
+        
+        for msg in messages {
+            let json = serde_json::to_string(&msg).unwrap();
+            if socket.send(Message::Text(json)).await.is_err() {
+                return;
+            }
+        }
     }
 }
 
@@ -103,40 +426,180 @@ pub async fn negotiate_stream(
     ws.on_upgrade(move |socket| handle_negotiate_stream(socket, id, state))
 }
 
-async fn handle_negotiate_stream(mut socket: WebSocket, intent_id: Uuid, _state: AppState) {
-    // Send initial message
-    let msg = serde_json::json!({
-        "type": "connected",
-        "intent_id": intent_id,
-        "message": "Connected to negotiation stream"
-    });
+async fn handle_negotiate_stream(mut socket: WebSocket, intent_id: Uuid, state: AppState) {
+    let mut shutdown = state.shutdown_signal();
     
-    if socket.send(Message::Text(msg.to_string().into())).await.is_err() {
+    // Announce the protocol and learn what the client speaks
+    let hello = serde_json::to_string(&handshake::hello()).unwrap();
+    if socket.send(Message::Text(hello)).await.is_err() {
         return;
     }
-
-    // Handle incoming messages
-    while let Some(msg) = socket.recv().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                // Echo for now (real implementation would handle negotiation protocol)
-                let response = serde_json::json!({
-                    "type": "ack",
-                    "received": text.to_string()
-                });
-                if socket.send(Message::Text(response.to_string().into())).await.is_err() {
+    let agreement = match client_hello(&mut socket).await {
+        Ok(Some(agreement)) => agreement,
+        Ok(None) => return,
+        Err(e) => {
+            let _ = send_negotiation_error(&mut socket, &e.to_string()).await;
+            close(&mut socket, WsCloseReason::ProtocolError).await;
+            return;
+        }
+    };
+    
+    // Wait for the engine to open the session (planning may still be running)
+    let mut attach_interval = interval(Duration::from_millis(100));
+    let handle = loop {
+        tokio::select! {
+            _ = attach_interval.tick() => {
+                if let Some(handle) = state.get_negotiation(intent_id).await {
+                    break handle;
+                }
+                match state.get_intent(intent_id).await {
+                    Some(record) if !record.status.is_terminal() => {}
+                    _ => {
+                        let _ = send_negotiation_error(&mut socket, "No negotiation for this intent").await;
+                        close(&mut socket, WsCloseReason::NotFound).await;
+                        return;
+                    }
+                }
+            }
+            _ = shutting_down(&mut shutdown) => {
+                close(&mut socket, WsCloseReason::ServerShutdown).await;
+                return;
+            }
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(Message::Ping(data))) => {
+                        let _ = socket.send(Message::Pong(data)).await;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    };
+    
+    // Only one client may drive a negotiation at a time
+    let Ok(mut outgoing) = handle.outgoing.clone().try_lock_owned() else {
+        let _ = send_negotiation_error(&mut socket, "Another client is attached to this negotiation").await;
+        close(&mut socket, WsCloseReason::ProtocolError).await;
+        return;
+    };
+    
+    loop {
+        tokio::select! {
+            outgoing_msg = outgoing.recv() => {
+                let Some(outgoing_msg) = outgoing_msg else { break };
+                let outgoing_msg = agreement.restrict(outgoing_msg);
+                let terminal = matches!(outgoing_msg, NegotiationMessage::Failed { .. });
+                
+                let json = serde_json::to_string(&outgoing_msg).unwrap();
+                if socket.send(Message::Text(json)).await.is_err() {
                     break;
                 }
+                
+                if terminal {
+                    close(&mut socket, WsCloseReason::Terminal).await;
+                    break;
+                }
+            }
+            _ = shutting_down(&mut shutdown) => {
+                close(&mut socket, WsCloseReason::ServerShutdown).await;
+                break;
+            }
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Err(e) = handle_negotiation_frame(&handle.session, &agreement, &text).await {
+                            if send_negotiation_error(&mut socket, &e).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(Message::Ping(data))) => {
+                        let _ = socket.send(Message::Pong(data)).await;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Wait for the client's hello and agree on a protocol with it.
+///
+/// A client that sends anything else first, or nothing within
+/// [`HANDSHAKE_TIMEOUT`], speaks version 1; what it sent is dropped, as
+/// frames sent before the session opens always were. Returns `None` if
+/// the client went away.
+async fn client_hello(socket: &mut WebSocket) -> orpheon_core::Result<Option<Agreement>> {
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    loop {
+        let msg = tokio::select! {
+            _ = sleep_until(deadline) => return Ok(Some(Agreement::legacy())),
+            msg = socket.recv() => msg,
+        };
+        match msg {
+            Some(Ok(Message::Text(text))) => {
+                return match serde_json::from_str(&text) {
+                    Ok(NegotiationMessage::Hello { protocol_version, supported_features }) => {
+                        Agreement::with_peer(protocol_version, &supported_features).map(Some)
+                    }
+                    _ => Ok(Some(Agreement::legacy())),
+                };
             }
-            Ok(Message::Close(_)) | Err(_) => break,
-            Ok(Message::Ping(data)) => {
+            Some(Ok(Message::Ping(data))) => {
                 let _ = socket.send(Message::Pong(data)).await;
             }
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(None),
             _ => {}
         }
     }
 }
 
+/// Apply a client frame to a negotiation session.
+///
+/// Replies (confirmations, failures) flow back through the session's
+/// outgoing channel.
+async fn handle_negotiation_frame(session: &NegotiationSession, agreement: &Agreement, text: &str) -> Result<(), String> {
+    let msg: NegotiationMessage = serde_json::from_str(text)
+        .map_err(|e| format!("Invalid negotiation message: {}", e))?;
+    
+    let result = match msg {
+        NegotiationMessage::Accept { proposal_id } => session.accept(proposal_id).await.map(|_| ()),
+        NegotiationMessage::Counter(_) if !agreement.supports(COUNTER_OFFERS) => {
+            return Err(format!("Counter-offers need the {} feature, which was not agreed", COUNTER_OFFERS));
+        }
+        NegotiationMessage::Counter(counter) => session.counter(counter).await,
+        NegotiationMessage::Reject { reason, .. } => session.reject(reason).await,
+        NegotiationMessage::Ping { .. } | NegotiationMessage::Pong { .. } => Ok(()),
+        other => return Err(format!("Unexpected client message: {:?}", other)),
+    };
+    
+    result.map_err(|e| e.to_string())
+}
+
+async fn send_negotiation_error(socket: &mut WebSocket, message: &str) -> Result<(), axum::Error> {
+    let msg = serde_json::json!({
+        "type": "error",
+        "message": message,
+    });
+    socket.send(Message::Text(msg.to_string())).await
+}
+
+/// Close a stream, naming the reason in the close frame.
+async fn close(socket: &mut WebSocket, reason: WsCloseReason) {
+    let frame = CloseFrame {
+        code: reason.close_code(),
+        reason: reason.as_str().into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// Resolves once the node starts shutting down.
+async fn shutting_down(shutdown: &mut watch::Receiver<bool>) {
+    let _ = shutdown.wait_for(|down| *down).await;
+}
+
 /// State subscription stream.
 pub async fn state_stream(
     ws: WebSocketUpgrade,
@@ -146,37 +609,56 @@ pub async fn state_stream(
 }
 
 async fn handle_state_stream(mut socket: WebSocket, state: AppState) {
+    let mut shutdown = state.shutdown_signal();
+    let mut versions = state.state_store.watch_version();
+    let min_interval = Duration::from_millis(state.state_stream.min_interval_ms);
+    
     // Send initial message
-    let version = state.state_store.version().await;
+    let version = *versions.borrow_and_update();
     let msg = serde_json::json!({
         "type": "connected",
         "version": version,
         "message": "Connected to state stream"
     });
     
-    if socket.send(Message::Text(msg.to_string().into())).await.is_err() {
+    if socket.send(Message::Text(msg.to_string())).await.is_err() {
         return;
     }
 
-    let mut poll_interval = interval(Duration::from_secs(1));
     let mut last_version = version;
+    let mut last_sent = Instant::now();
 
     loop {
         tokio::select! {
-            _ = poll_interval.tick() => {
-                let current_version = state.state_store.version().await;
-                if current_version != last_version {
-                    last_version = current_version;
-                    
-                    let msg = serde_json::json!({
-                        "type": "version_update",
-                        "version": current_version
-                    });
-                    
-                    if socket.send(Message::Text(msg.to_string().into())).await.is_err() {
-                        break;
-                    }
+            changed = versions.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                // Let a burst of writes settle into one update
+                sleep_until(last_sent + min_interval).await;
+                let current_version = *versions.borrow_and_update();
+                if current_version == last_version {
+                    continue;
                 }
+                
+                let changed = state.state_store.keys_changed_since(last_version, state.state_stream.max_keys).await;
+                let msg = serde_json::json!({
+                    "type": "version_update",
+                    "version": current_version,
+                    "advanced": current_version - last_version,
+                    "keys": changed.keys,
+                    "keys_complete": changed.complete,
+                });
+                last_version = current_version;
+                last_sent = Instant::now();
+                
+                if socket.send(Message::Text(msg.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            _ = shutting_down(&mut shutdown) => {
+                close(&mut socket, WsCloseReason::ServerShutdown).await;
+                break;
             }
             msg = socket.recv() => {
                 match msg {
@@ -190,3 +672,378 @@ async fn handle_state_stream(mut socket: WebSocket, state: AppState) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::Intent;
+    use orpheon_sdk::stream::Event;
+    use orpheon_sdk::{OrpheonClient, WatchFilter};
+
+    #[test]
+    fn test_subscription_frames_parse() {
+        let id = Uuid::new_v4();
+        let frame = format!(r#"{{ "subscribe": ["{}"] }}"#, id);
+        assert!(matches!(
+            serde_json::from_str::<IntentsStreamRequest>(&frame).unwrap(),
+            IntentsStreamRequest::Subscribe(ids) if ids == vec![id]
+        ));
+        
+        let frame = r#"{ "subscribe_filter": { "kind": "deploy", "status": "active" } }"#;
+        let IntentsStreamRequest::SubscribeFilter(filter) = serde_json::from_str(frame).unwrap() else {
+            panic!("expected filter frame");
+        };
+        assert_eq!(filter.kind.as_deref(), Some("deploy"));
+        assert_eq!(filter.status.as_deref(), Some("active"));
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        let mut watch_set = IntentWatchSet::default();
+        let ids: Vec<Uuid> = (0..=MAX_SUBSCRIPTIONS_PER_CONNECTION).map(|_| Uuid::new_v4()).collect();
+        
+        let errors = watch_set.apply(IntentsStreamRequest::Subscribe(ids));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(watch_set.watched.len(), MAX_SUBSCRIPTIONS_PER_CONNECTION);
+    }
+
+    #[tokio::test]
+    async fn test_filter_matches_do_not_count_against_limit() {
+        let state = AppState::new();
+        let mut watch_set = IntentWatchSet::default();
+        let mut ids = Vec::new();
+        for _ in 0..MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            let intent = Intent::builder().kind("build").build().unwrap();
+            ids.push(intent.id);
+            state.store_intent(intent).await;
+        }
+        assert!(watch_set.apply(IntentsStreamRequest::Subscribe(ids)).is_empty());
+        watch_set.apply(IntentsStreamRequest::SubscribeFilter(IntentFilter {
+            kind: Some("deploy".to_string()),
+            status: None,
+        }));
+        
+        // More intents than the limit match over the connection's lifetime
+        for _ in 0..=MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            let intent = Intent::builder().kind("deploy").build().unwrap();
+            let id = intent.id;
+            state.store_intent(intent).await;
+            state.update_intent_status(id, orpheon_core::IntentStatus::Cancelled).await;
+        }
+        let late = Intent::builder().kind("deploy").build().unwrap();
+        let late_id = late.id;
+        state.store_intent(late).await;
+        
+        let messages = watch_set.poll(&state).await;
+        assert!(!messages.iter().any(|m| matches!(m, IntentStreamMessage::Error { .. })));
+        assert!(messages.iter().any(|m| matches!(m, IntentStreamMessage::StatusUpdate { intent_id, .. } if *intent_id == late_id)));
+        
+        // Finished matches are dropped and not sent again
+        assert_eq!(watch_set.explicit.len(), MAX_SUBSCRIPTIONS_PER_CONNECTION);
+        assert!(watch_set.watched.contains_key(&late_id));
+        assert_eq!(watch_set.watched.len(), MAX_SUBSCRIPTIONS_PER_CONNECTION + 1);
+        let repeated = watch_set.poll(&state).await;
+        assert!(!repeated.iter().any(|m| matches!(m, IntentStreamMessage::StatusUpdate { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_multiplexed_stream_two_intents() {
+        let state = AppState::new();
+        let first = Intent::builder().kind("first").build().unwrap();
+        let second = Intent::builder().kind("second").build().unwrap();
+        let (first_id, second_id) = (first.id, second.id);
+        state.store_intent(first).await;
+        state.store_intent(second).await;
+        
+        let node = crate::testing::TestNode::with_state(state).await;
+        let addr = node.addr;
+        let client = OrpheonClient::connect(&format!("http://{}", addr)).await.unwrap();
+        let mut stream = client
+            .watch_intents(WatchFilter::ids(vec![first_id, second_id]))
+            .await
+            .unwrap();
+        
+        let mut seen = HashSet::new();
+        let mut complete = HashSet::new();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+        while complete.len() < 2 {
+            let (intent_id, event) = tokio::time::timeout_at(deadline, stream.next())
+                .await
+                .expect("intents did not complete in time")
+                .expect("stream closed early");
+            
+            assert!(intent_id == first_id || intent_id == second_id);
+            seen.insert(intent_id);
+            if let Event::Complete { .. } = event {
+                complete.insert(intent_id);
+            }
+        }
+        
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_status_updates_are_sequenced_without_gaps() {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+        
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        
+        let node = crate::testing::TestNode::with_state(state).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("{}/ws/intent/{}", node.ws_url(), intent_id))
+            .await
+            .unwrap();
+        
+        // Every change is sent, even ones that happened between polls
+        let mut seqs = Vec::new();
+        loop {
+            let msg = tokio::time::timeout(Duration::from_secs(20), socket.next()).await.unwrap().unwrap().unwrap();
+            if let WsMessage::Text(text) = msg {
+                let update: serde_json::Value = serde_json::from_str(&text).unwrap();
+                seqs.push(update["seq"].as_u64().unwrap());
+                if update["status"] == "complete" {
+                    break;
+                }
+            }
+        }
+        assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1), "{:?}", seqs);
+        
+        let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+        let intent = client.get_intent(intent_id).await.unwrap();
+        assert_eq!(intent.status, "complete");
+        assert_eq!(Some(&intent.seq), seqs.last());
+    }
+    
+    #[tokio::test]
+    async fn test_state_stream_compacts_bursts() {
+        use futures::StreamExt;
+        use orpheon_state::StateStore;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+        
+        let state = AppState::new();
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("{}/ws/state", node.ws_url())).await.unwrap();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        async fn next_frame<S>(socket: &mut S, deadline: tokio::time::Instant) -> serde_json::Value
+        where
+            S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+        {
+            loop {
+                if let WsMessage::Text(text) = tokio::time::timeout_at(deadline, socket.next()).await.unwrap().unwrap().unwrap() {
+                    return serde_json::from_str(&text).unwrap();
+                }
+            }
+        }
+        assert_eq!(next_frame(&mut socket, deadline).await["type"], "connected");
+        
+        for i in 0..100 {
+            state.state_store.set(&format!("resource:{}", i % 5), serde_json::json!(i)).await.unwrap();
+        }
+        
+        let mut updates = Vec::new();
+        let mut advanced = 0;
+        while advanced < 100 {
+            let update = next_frame(&mut socket, deadline).await;
+            assert_eq!(update["type"], "version_update");
+            advanced += update["advanced"].as_u64().unwrap();
+            updates.push(update);
+        }
+        assert_eq!(advanced, 100);
+        assert!(updates.len() <= 5, "{} updates for one burst", updates.len());
+        
+        let last = updates.last().unwrap();
+        assert_eq!(last["version"], 100);
+        if updates.len() == 1 {
+            assert_eq!(last["keys"].as_array().unwrap().len(), 5);
+            assert_eq!(last["keys_complete"], true);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_negotiation_timeout_fails_and_closes() {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+        
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        let options = crate::negotiation::NegotiationOptions { timeout_ms: 700, max_rounds: 3, ..Default::default() };
+        state.store_intent_with_negotiation(intent, Some(options)).await;
+        
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        let addr = node.addr;
+        let (mut negotiate, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/negotiate/{}", addr, intent_id))
+            .await
+            .unwrap();
+        let mut status = orpheon_sdk::EventStream::connect(&format!("ws://{}/ws/intent/{}", addr, intent_id), intent_id)
+            .await
+            .unwrap();
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        let mut frames = Vec::new();
+        let close = loop {
+            let msg = tokio::time::timeout_at(deadline, negotiate.next())
+                .await
+                .expect("negotiation did not end in time")
+                .expect("socket ended without a close frame")
+                .unwrap();
+            match msg {
+                WsMessage::Text(text) => frames.push(serde_json::from_str::<serde_json::Value>(&text).unwrap()),
+                WsMessage::Close(frame) => break frame,
+                _ => {}
+            }
+        };
+        
+        let types: Vec<_> = frames.iter().map(|f| f["type"].as_str().unwrap().to_string()).collect();
+        assert_eq!(types, vec!["hello", "offer", "failed"]);
+        assert_eq!(frames[2]["reason"], orpheon_negotiate::TIMEOUT_REASON);
+        assert_eq!(close.unwrap().code, CloseCode::Normal);
+        
+        // The intent stream reports the failure too
+        loop {
+            let event = tokio::time::timeout_at(deadline, status.next())
+                .await
+                .expect("intent stream did not report failure")
+                .expect("intent stream closed early");
+            if let Event::StatusUpdate { status, .. } = event {
+                if status == "failed" {
+                    break;
+                }
+            }
+        }
+        
+        let record = state.get_intent(intent_id).await.unwrap();
+        assert_eq!(record.status, orpheon_core::IntentStatus::Failed);
+        assert_eq!(record.error.as_deref(), Some(orpheon_negotiate::TIMEOUT_REASON));
+    }
+    
+    #[tokio::test]
+    async fn test_negotiation_accept_executes_plan() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+        
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent_with_negotiation(intent, Some(Default::default())).await;
+        
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        let addr = node.addr;
+        let (mut negotiate, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/negotiate/{}", addr, intent_id))
+            .await
+            .unwrap();
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+        
+        let mut frames = Vec::new();
+        while frames.len() < 2 {
+            if let WsMessage::Text(text) = tokio::time::timeout_at(deadline, negotiate.next()).await.unwrap().unwrap().unwrap() {
+                frames.push(serde_json::from_str::<serde_json::Value>(&text).unwrap());
+            }
+        }
+        assert_eq!(frames[1]["type"], "offer");
+        let proposal_id = frames[1]["id"].as_str().unwrap().to_string();
+        
+        let accept = serde_json::json!({ "type": "accept", "proposal_id": proposal_id });
+        negotiate.send(WsMessage::Text(accept.to_string())).await.unwrap();
+        
+        loop {
+            if let WsMessage::Text(text) = tokio::time::timeout_at(deadline, negotiate.next()).await.unwrap().unwrap().unwrap() {
+                let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(frame["type"], "confirmed");
+                break;
+            }
+        }
+        
+        while state.get_artifact_for_intent(intent_id).await.is_none() {
+            assert!(tokio::time::Instant::now() < deadline, "accepted intent did not complete");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        let record = state.get_intent(intent_id).await.unwrap();
+        assert_eq!(record.status, orpheon_core::IntentStatus::Complete);
+    }
+    
+    #[tokio::test]
+    async fn test_counter_offer_replans_line_items() {
+        use futures::{SinkExt, StreamExt};
+        use orpheon_core::Constraint;
+        use orpheon_planner::planner::PlanningAction;
+        use orpheon_planner::{AStarPlanner, PlannerConfig};
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+        
+        let deploy = |name: &str, cost: f64, provider: &str| PlanningAction {
+            name: name.to_string(),
+            preconditions: vec!["provisioned".to_string()],
+            effects: vec!["complete".to_string()],
+            cost,
+            provider: Some(provider.to_string()),
+            ..Default::default()
+        };
+        let catalog = vec![
+            PlanningAction {
+                name: "provision".to_string(),
+                effects: vec!["provisioned".to_string()],
+                cost: 1.0,
+                ..Default::default()
+            },
+            deploy("deploy_premium", 10.0, "premium"),
+            deploy("deploy_budget", 4.0, "budget"),
+        ];
+        let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+        
+        // Preferring the premium provider outweighs its price until the client caps the cost
+        let intent = Intent::builder()
+            .kind("deploy")
+            .soft_constraint(Constraint::Provider { node_id: "premium".to_string() })
+            .build()
+            .unwrap();
+        let intent_id = intent.id;
+        state.store_intent_with_negotiation(intent, Some(Default::default())).await;
+        
+        let node = crate::testing::TestNode::with_state(state.clone()).await;
+        let (mut negotiate, _) = tokio_tungstenite::connect_async(format!("{}/ws/negotiate/{}", node.ws_url(), intent_id))
+            .await
+            .unwrap();
+        
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+        async fn next_offer<S>(socket: &mut S, deadline: tokio::time::Instant) -> orpheon_negotiate::Proposal
+        where
+            S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+        {
+            loop {
+                if let WsMessage::Text(text) = tokio::time::timeout_at(deadline, socket.next()).await.unwrap().unwrap().unwrap() {
+                    let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+                    if frame["type"] == "offer" {
+                        return serde_json::from_value(frame).unwrap();
+                    }
+                }
+            }
+        }
+        
+        let first = next_offer(&mut negotiate, deadline).await;
+        assert_eq!(first.quoted_cost, 11.0);
+        
+        let counter = NegotiationMessage::Counter(orpheon_negotiate::CounterOffer::new(first.id).with_max_cost(6.0));
+        negotiate.send(WsMessage::Text(serde_json::to_string(&counter).unwrap())).await.unwrap();
+        let second = next_offer(&mut negotiate, deadline).await;
+        
+        assert_eq!(second.quoted_cost, 5.0);
+        assert!(second.check_line_items().is_ok());
+        
+        // The shared provisioning step is unchanged; only the deployment moved
+        assert_eq!(first.line_items[0].action, "provision");
+        assert_eq!(second.line_items[0].action, "provision");
+        assert_eq!(first.line_items[0].unit_cost, second.line_items[0].unit_cost);
+        assert_eq!(first.line_items[1].action, "deploy_premium");
+        assert_eq!(first.line_items[1].provider.as_deref(), Some("premium"));
+        assert_eq!(second.line_items[1].action, "deploy_budget");
+        assert_eq!(second.line_items[1].provider.as_deref(), Some("budget"));
+        assert_eq!(second.line_items[1].unit_cost, 4.0);
+        
+        assert_eq!(state.get_plans_for_intent(intent_id).await.unwrap().len(), 2);
+    }
+}
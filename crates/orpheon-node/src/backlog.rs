@@ -0,0 +1,345 @@
+//! Age of the engine's backlog.
+//!
+//! Queue depth hides the worst symptom of a stuck engine: one intent left
+//! in `Received` for an hour. The node keeps, for each status an intent
+//! waits in ([`TRACKED_STATUSES`]), an index of its intents ordered by when
+//! they entered it, updated on every transition, so the oldest one is at
+//! hand without a scan. The [`BacklogMonitor`] reports those watermarks as
+//! metrics and raises an alert once an intent has waited longer than its
+//! status's threshold in
+//! [`BacklogConfig`](crate::config::BacklogConfig): a journal entry and a
+//! [`SystemNotice`] on every multiplexed intents stream.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use orpheon_core::IntentStatus;
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::journal::JournalEvent;
+use crate::state::AppState;
+
+/// Notice code for intents waiting longer than their status's threshold.
+pub const BACKLOG_AGE: &str = "backlog_age";
+
+/// System notices kept for streams to catch up on.
+const MAX_NOTICES: usize = 100;
+
+/// Statuses whose oldest intent is tracked.
+pub const TRACKED_STATUSES: [IntentStatus; 3] =
+    [IntentStatus::Received, IntentStatus::Planning, IntentStatus::Executing];
+
+fn slot(status: IntentStatus) -> Option<usize> {
+    TRACKED_STATUSES.iter().position(|s| *s == status)
+}
+
+/// The intent that has waited longest in a status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watermark {
+    pub intent_id: Uuid,
+
+    /// When the intent entered the status.
+    pub since: DateTime<Utc>,
+
+    /// How long it has been there, in milliseconds.
+    pub age_ms: u64,
+}
+
+/// Oldest intent of each tracked status; `None` where the status is empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacklogWatermarks {
+    pub received: Option<Watermark>,
+    pub planning: Option<Watermark>,
+    pub executing: Option<Watermark>,
+}
+
+impl BacklogWatermarks {
+    /// The watermark of `status`, if it is tracked and not empty.
+    pub fn get(&self, status: IntentStatus) -> Option<&Watermark> {
+        match status {
+            IntentStatus::Received => self.received.as_ref(),
+            IntentStatus::Planning => self.planning.as_ref(),
+            IntentStatus::Executing => self.executing.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Tracked status each intent is in and since when.
+    current: HashMap<Uuid, (usize, DateTime<Utc>)>,
+
+    /// Intents of each tracked status, ordered by when they entered it.
+    ordered: [BTreeSet<(DateTime<Utc>, Uuid)>; 3],
+}
+
+/// Intents of each tracked status, ordered by when they entered it.
+#[derive(Debug, Default)]
+pub struct BacklogIndex {
+    entries: Mutex<Entries>,
+}
+
+impl BacklogIndex {
+    /// Record that an intent entered `status` at `at`; an untracked status
+    /// drops it from the index.
+    pub fn enter(&self, id: Uuid, status: IntentStatus, at: DateTime<Utc>) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((slot, since)) = entries.current.remove(&id) {
+            entries.ordered[slot].remove(&(since, id));
+        }
+        if let Some(slot) = slot(status) {
+            entries.current.insert(id, (slot, at));
+            entries.ordered[slot].insert((at, id));
+        }
+    }
+
+    /// Move an intent already in the index to `status`, now. Intents the
+    /// index does not hold, such as forwarded ones, stay out of it.
+    pub fn transition(&self, id: Uuid, status: IntentStatus) {
+        if self.entries.lock().unwrap().current.contains_key(&id) {
+            self.enter(id, status, Utc::now());
+        }
+    }
+
+    /// Drop an intent from the index.
+    pub fn remove(&self, id: Uuid) {
+        self.enter(id, IntentStatus::Cancelled, Utc::now());
+    }
+
+    /// Oldest intent of each tracked status, aged as of `now`.
+    pub fn watermarks(&self, now: DateTime<Utc>) -> BacklogWatermarks {
+        let entries = self.entries.lock().unwrap();
+        let oldest = |slot: usize| {
+            entries.ordered[slot].first().map(|(since, intent_id)| Watermark {
+                intent_id: *intent_id,
+                since: *since,
+                age_ms: (now - *since).num_milliseconds().max(0) as u64,
+            })
+        };
+        BacklogWatermarks { received: oldest(0), planning: oldest(1), executing: oldest(2) }
+    }
+}
+
+/// A notice about the node itself rather than any one intent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemNotice {
+    /// Position among the node's notices, starting at 1.
+    pub seq: u64,
+    pub code: String,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Recent system notices, for multiplexed streams to send on.
+#[derive(Debug, Default)]
+pub struct NoticeLog {
+    notices: Mutex<VecDeque<SystemNotice>>,
+}
+
+impl NoticeLog {
+    /// Add a notice, returning it.
+    pub fn push(&self, code: &str, message: String) -> SystemNotice {
+        let mut notices = self.notices.lock().unwrap();
+        let notice = SystemNotice {
+            seq: notices.back().map_or(1, |n| n.seq + 1),
+            code: code.to_string(),
+            message,
+            at: Utc::now(),
+        };
+        notices.push_back(notice.clone());
+        if notices.len() > MAX_NOTICES {
+            notices.pop_front();
+        }
+        notice
+    }
+
+    /// Seq of the latest notice, or 0 if there is none.
+    pub fn latest_seq(&self) -> u64 {
+        self.notices.lock().unwrap().back().map_or(0, |n| n.seq)
+    }
+
+    /// Notices after `seq` that are still kept, oldest first.
+    pub fn since(&self, seq: u64) -> Vec<SystemNotice> {
+        self.notices.lock().unwrap().iter().filter(|n| n.seq > seq).cloned().collect()
+    }
+}
+
+/// A status whose oldest intent has waited longer than its threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacklogAlert {
+    pub status: IntentStatus,
+    pub watermark: Watermark,
+    pub threshold_ms: u64,
+}
+
+/// Reports backlog watermarks and alerts on intents waiting too long.
+pub struct BacklogMonitor {
+    state: AppState,
+
+    /// Watermarks already alerted on, so each is alerted once.
+    alerted: Mutex<HashSet<(usize, Uuid)>>,
+}
+
+impl BacklogMonitor {
+    /// Create a monitor over a node's backlog.
+    pub fn new(state: AppState) -> Self {
+        Self { state, alerted: Mutex::new(HashSet::new()) }
+    }
+
+    /// Check the backlog every configured interval, forever.
+    pub async fn run(self: Arc<Self>) {
+        info!("📈 Backlog monitor started");
+
+        loop {
+            self.check();
+            sleep(Duration::from_millis(self.state.backlog.interval_ms)).await;
+        }
+    }
+
+    /// Check the backlog once.
+    pub fn check(&self) -> Vec<BacklogAlert> {
+        self.check_at(Utc::now())
+    }
+
+    /// Check the backlog as of `now`, returning the alerts it raised.
+    pub fn check_at(&self, now: DateTime<Utc>) -> Vec<BacklogAlert> {
+        let watermarks = self.state.backlog_index.watermarks(now);
+        let mut alerted = self.alerted.lock().unwrap();
+        let mut current = HashSet::new();
+        let mut alerts = Vec::new();
+
+        for (slot, status) in TRACKED_STATUSES.into_iter().enumerate() {
+            let watermark = watermarks.get(status);
+            let age_ms = watermark.map_or(0, |w| w.age_ms);
+            metrics::gauge!("orpheon_backlog_oldest_age_ms", "status" => status.as_str()).set(age_ms as f64);
+
+            let Some(watermark) = watermark else {
+                continue;
+            };
+            current.insert((slot, watermark.intent_id));
+            let Some(threshold_ms) = self.state.backlog.threshold_ms(status) else {
+                continue;
+            };
+            if watermark.age_ms <= threshold_ms || alerted.contains(&(slot, watermark.intent_id)) {
+                continue;
+            }
+
+            warn!(
+                "📈 Intent {} has been {} for {} ms, over the {} ms threshold",
+                watermark.intent_id,
+                status.as_str(),
+                watermark.age_ms,
+                threshold_ms
+            );
+            self.state.journal.append(JournalEvent::BacklogAlert {
+                status,
+                intent_id: watermark.intent_id,
+                age_ms: watermark.age_ms,
+            });
+            self.state.notices.push(
+                BACKLOG_AGE,
+                format!(
+                    "Intent {} has been {} for {} ms, over the {} ms threshold",
+                    watermark.intent_id,
+                    status.as_str(),
+                    watermark.age_ms,
+                    threshold_ms
+                ),
+            );
+            alerts.push(BacklogAlert { status, watermark: watermark.clone(), threshold_ms });
+        }
+
+        // Forget watermarks that moved on, keeping the rest from alerting again
+        alerted.retain(|entry| current.contains(entry));
+        alerted.extend(alerts.iter().map(|a| (slot(a.status).unwrap_or_default(), a.watermark.intent_id)));
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_test::TestServer;
+    use orpheon_core::Intent;
+
+    use crate::api::health::ReadyResponse;
+    use crate::api::stats::StatsResponse;
+
+    #[test]
+    fn test_watermarks_follow_transitions() {
+        let index = BacklogIndex::default();
+        let start = Utc::now() - chrono::Duration::minutes(10);
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        index.enter(first, IntentStatus::Received, start);
+        index.enter(second, IntentStatus::Received, start + chrono::Duration::minutes(1));
+
+        let watermarks = index.watermarks(start + chrono::Duration::minutes(5));
+        assert_eq!(watermarks.received.as_ref().unwrap().intent_id, first);
+        assert_eq!(watermarks.received.unwrap().age_ms, 5 * 60_000);
+        assert!(watermarks.planning.is_none());
+
+        index.transition(first, IntentStatus::Planning);
+        let watermarks = index.watermarks(Utc::now());
+        assert_eq!(watermarks.received.unwrap().intent_id, second);
+        assert_eq!(watermarks.planning.unwrap().intent_id, first);
+
+        // Terminal intents leave the index, and untracked ones never join
+        index.transition(first, IntentStatus::Complete);
+        index.remove(second);
+        index.transition(Uuid::new_v4(), IntentStatus::Executing);
+        assert_eq!(index.watermarks(Utc::now()), BacklogWatermarks::default());
+    }
+
+    #[tokio::test]
+    async fn test_monitor_alerts_once_on_an_aged_intent() {
+        let state = AppState::new();
+        state.pause_engine();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        state.store_intent(intent.clone()).await;
+        let server = TestServer::new(crate::create_router(state.clone())).unwrap();
+
+        let ready: ReadyResponse = server.get("/health/ready").await.json();
+        assert_eq!(ready.backlog.received.unwrap().intent_id, intent.id);
+        let stats: StatsResponse = server.get("/api/v1/stats").await.json();
+        assert_eq!(stats.backlog.received.unwrap().intent_id, intent.id);
+        assert!(stats.backlog.planning.is_none());
+
+        // Not yet over the five minute threshold
+        let monitor = BacklogMonitor::new(state.clone());
+        assert!(monitor.check().is_empty());
+
+        let later = Utc::now() + chrono::Duration::minutes(6);
+        let alerts = monitor.check_at(later);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].status, IntentStatus::Received);
+        assert_eq!(alerts[0].watermark.intent_id, intent.id);
+        assert!(alerts[0].watermark.age_ms > 6 * 60_000 - 1_000);
+        assert!(monitor.check_at(later).is_empty());
+
+        let entry = state.journal.since(0, 100).pop().unwrap();
+        assert!(
+            matches!(entry.event, JournalEvent::BacklogAlert { intent_id, status: IntentStatus::Received, .. } if intent_id == intent.id)
+        );
+        let notices = state.notices.since(0);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].code, BACKLOG_AGE);
+        assert!(notices[0].message.contains(&intent.id.to_string()));
+    }
+
+    #[test]
+    fn test_notice_log_keeps_recent_notices() {
+        let log = NoticeLog::default();
+        assert_eq!(log.latest_seq(), 0);
+        for i in 0..MAX_NOTICES + 5 {
+            log.push(BACKLOG_AGE, format!("notice {}", i));
+        }
+        assert_eq!(log.latest_seq(), MAX_NOTICES as u64 + 5);
+        assert_eq!(log.since(0).len(), MAX_NOTICES);
+        assert_eq!(log.since(MAX_NOTICES as u64 + 3).len(), 2);
+    }
+}
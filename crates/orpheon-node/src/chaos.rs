@@ -0,0 +1,247 @@
+//! Fault injection for exercising failure handling.
+//!
+//! A [`FaultInjector`] holds rules that make the [`SimulatedExecutor`]
+//! misbehave deterministically: fail a step on a given attempt, delay an
+//! action, or panic mid-step. Rules come from [`ChaosConfig::rules`] at
+//! startup or the `/api/v1/admin/chaos/rules` endpoints at runtime.
+//!
+//! Nothing here is active unless the node was started with
+//! [`ChaosConfig::unsafe_chaos`] set; without it the node has no injector
+//! and the admin endpoints refuse every request.
+//!
+//! [`SimulatedExecutor`]: crate::engine::SimulatedExecutor
+
+use std::sync::RwLock;
+
+use orpheon_core::Step;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::engine::StepContext;
+
+/// Chaos settings for a node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    /// Enable fault injection. Never set this on a production node.
+    #[serde(default)]
+    pub unsafe_chaos: bool,
+
+    /// Rules installed at startup.
+    #[serde(default)]
+    pub rules: Vec<FaultRule>,
+}
+
+impl ChaosConfig {
+    /// Build the node's injector, or `None` when chaos is disabled.
+    pub fn injector(&self) -> Option<FaultInjector> {
+        if !self.unsafe_chaos {
+            return None;
+        }
+        let injector = FaultInjector::default();
+        for rule in &self.rules {
+            injector.add(rule.clone());
+        }
+        Some(injector)
+    }
+}
+
+/// Which step attempts a rule applies to. Unset fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultMatcher {
+    /// Step name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_name: Option<String>,
+
+    /// Action the step performs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+
+    /// Intent the step belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent_id: Option<Uuid>,
+
+    /// Attempt number, starting at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<u32>,
+}
+
+impl FaultMatcher {
+    /// Whether an attempt of `step` matches.
+    pub fn matches(&self, step: &Step, ctx: &StepContext) -> bool {
+        self.step_name.as_ref().is_none_or(|name| *name == step.name)
+            && self.action.as_ref().is_none_or(|action| *action == step.action)
+            && self.intent_id.is_none_or(|id| id == ctx.intent_id)
+            && self.attempt.is_none_or(|attempt| attempt == ctx.attempt)
+    }
+}
+
+/// What happens to a matching step attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fault {
+    /// Fail the attempt with a reason.
+    Fail { reason: String },
+
+    /// Hold the attempt back before running it.
+    Delay { ms: u64 },
+
+    /// Panic inside the executor.
+    Panic { message: String },
+}
+
+/// A fault and the step attempts it applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultRule {
+    /// Attempts the fault applies to.
+    #[serde(default)]
+    pub when: FaultMatcher,
+
+    /// The fault.
+    pub fault: Fault,
+}
+
+impl FaultRule {
+    /// Fail steps with the given name.
+    pub fn fail_step(name: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            when: FaultMatcher { step_name: Some(name.into()), ..Default::default() },
+            fault: Fault::Fail { reason: reason.into() },
+        }
+    }
+
+    /// Delay steps running the given action.
+    pub fn delay_action(action: impl Into<String>, ms: u64) -> Self {
+        Self {
+            when: FaultMatcher { action: Some(action.into()), ..Default::default() },
+            fault: Fault::Delay { ms },
+        }
+    }
+
+    /// Panic the executor on every step of an intent.
+    pub fn panic_intent(intent_id: Uuid, message: impl Into<String>) -> Self {
+        Self {
+            when: FaultMatcher { intent_id: Some(intent_id), ..Default::default() },
+            fault: Fault::Panic { message: message.into() },
+        }
+    }
+
+    /// Only apply on the given attempt.
+    pub fn on_attempt(mut self, attempt: u32) -> Self {
+        self.when.attempt = Some(attempt);
+        self
+    }
+}
+
+/// An installed rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InjectedFault {
+    /// ID used to remove the rule.
+    pub id: Uuid,
+
+    /// The rule.
+    #[serde(flatten)]
+    pub rule: FaultRule,
+}
+
+/// Runtime set of fault rules.
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    rules: RwLock<Vec<InjectedFault>>,
+}
+
+impl FaultInjector {
+    /// Install a rule.
+    pub fn add(&self, rule: FaultRule) -> InjectedFault {
+        let injected = InjectedFault { id: Uuid::new_v4(), rule };
+        self.rules.write().unwrap().push(injected.clone());
+        injected
+    }
+
+    /// Installed rules, oldest first.
+    pub fn list(&self) -> Vec<InjectedFault> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Remove a rule; returns false if no rule has that ID.
+    pub fn remove(&self, id: Uuid) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let before = rules.len();
+        rules.retain(|r| r.id != id);
+        rules.len() != before
+    }
+
+    /// Remove every rule.
+    pub fn clear(&self) {
+        self.rules.write().unwrap().clear();
+    }
+
+    /// Faults that apply to an attempt of `step`, in installation order.
+    pub fn faults_for(&self, step: &Step, ctx: &StepContext) -> Vec<Fault> {
+        self.rules
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.rule.when.matches(step, ctx))
+            .map(|r| r.rule.fault.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(attempt: u32) -> StepContext {
+        StepContext { intent_id: Uuid::new_v4(), attempt }
+    }
+
+    #[test]
+    fn test_disabled_config_has_no_injector() {
+        let config = ChaosConfig {
+            unsafe_chaos: false,
+            rules: vec![FaultRule::fail_step("deploy", "boom")],
+        };
+        assert!(config.injector().is_none());
+
+        let enabled = ChaosConfig { unsafe_chaos: true, ..config };
+        assert_eq!(enabled.injector().unwrap().list().len(), 1);
+    }
+
+    #[test]
+    fn test_matcher_fields_combine() {
+        let step = Step::new("deploy", "deploy_workload");
+        let rule = FaultRule::fail_step("deploy", "boom").on_attempt(1);
+
+        assert!(rule.when.matches(&step, &ctx(1)));
+        assert!(!rule.when.matches(&step, &ctx(2)));
+        assert!(!rule.when.matches(&Step::new("verify", "deploy_workload"), &ctx(1)));
+        assert!(FaultRule::delay_action("deploy_workload", 10).when.matches(&step, &ctx(3)));
+    }
+
+    #[test]
+    fn test_rules_are_removable() {
+        let injector = FaultInjector::default();
+        let first = injector.add(FaultRule::fail_step("deploy", "boom"));
+        injector.add(FaultRule::delay_action("deploy_workload", 10));
+
+        let step = Step::new("deploy", "deploy_workload");
+        assert_eq!(injector.faults_for(&step, &ctx(1)).len(), 2);
+
+        assert!(injector.remove(first.id));
+        assert!(!injector.remove(first.id));
+        assert_eq!(injector.faults_for(&step, &ctx(1)), vec![Fault::Delay { ms: 10 }]);
+
+        injector.clear();
+        assert!(injector.list().is_empty());
+    }
+
+    #[test]
+    fn test_rule_json_shape() {
+        let json = serde_json::json!({
+            "when": { "action": "deploy_workload", "attempt": 1 },
+            "fault": { "type": "delay", "ms": 2000 }
+        });
+        let rule: FaultRule = serde_json::from_value(json).unwrap();
+        assert_eq!(rule, FaultRule::delay_action("deploy_workload", 2000).on_attempt(1));
+    }
+}
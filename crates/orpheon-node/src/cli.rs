@@ -0,0 +1,209 @@
+//! Offline operations behind the `orpheon-node` subcommands.
+//!
+//! Each operation runs against files only, never binding a port, and goes
+//! through the same code the server uses: intent files are read like submit
+//! requests, catalogs like seed `actions.json`, and artifacts are checked
+//! with the SDK verifier.
+//!
+//! A state store file is a JSON [`StateSnapshot`]. `export-state` prints its
+//! keys and values in the seed `state.json` shape, `import-state` merges such
+//! a file back in, and `serve --store` loads it on startup, upgrading records
+//! written by older nodes; `serve --store --migrate-dry-run` reports what
+//! that upgrade would change.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use orpheon_core::Plan;
+use orpheon_planner::planner::{PlanningAction, PlanningState};
+use orpheon_planner::{AStarPlanner, Planner};
+use orpheon_state::{InMemoryStateStore, StateSnapshot, StateStore};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::api::intent::SubmitIntentBody;
+use crate::migrations;
+
+/// Exit code for input that failed validation.
+pub const EXIT_INVALID: i32 = 1;
+
+/// Exit code for internal errors, such as files that cannot be read or
+/// written. Exit code 2 is left to usage errors.
+pub const EXIT_INTERNAL: i32 = 3;
+
+/// Errors from an offline operation.
+#[derive(Debug, Error)]
+pub enum CliError {
+    /// The input is malformed or failed validation.
+    #[error("{0}")]
+    Invalid(String),
+
+    /// The operation itself failed.
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl CliError {
+    /// Process exit code for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Invalid(_) => EXIT_INVALID,
+            CliError::Internal(_) => EXIT_INTERNAL,
+        }
+    }
+}
+
+/// Output format of a plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+    /// The plan as JSON.
+    Json,
+    /// The step graph in Graphviz DOT.
+    Dot,
+}
+
+/// Plan an intent file locally with the default catalog plus the actions in
+/// `catalog`, returning the plan in `format`.
+///
+/// Intent files hold a submit request body, as JSON or, unless the file
+/// name ends in `.json`, YAML.
+pub async fn plan(intent_file: &Path, catalog: Option<&Path>, format: PlanFormat) -> Result<String, CliError> {
+    let body: SubmitIntentBody = if has_extension(intent_file, "json") {
+        read_json(intent_file)?
+    } else {
+        let text = read(intent_file)?;
+        serde_yaml::from_str(&text).map_err(|e| invalid(intent_file, e))?
+    };
+    let intent = body
+        .into_intent()
+        .and_then(|intent| intent.validate().map(|_| intent))
+        .map_err(|e| invalid(intent_file, e))?;
+
+    let mut planner = AStarPlanner::new();
+    if let Some(path) = catalog {
+        let actions: Vec<PlanningAction> = read_json(path)?;
+        let mut names = HashSet::new();
+        for action in actions {
+            if action.name.trim().is_empty() {
+                return Err(invalid(path, "action name cannot be empty"));
+            }
+            if !names.insert(action.name.clone()) {
+                return Err(invalid(path, format!("duplicate action '{}'", action.name)));
+            }
+            planner.register_action(action).map_err(|e| invalid(path, e))?;
+        }
+        planner.set_catalog_source(path.display().to_string());
+    }
+
+    let plan = planner
+        .plan(&intent, &PlanningState::default())
+        .await
+        .map_err(|e| CliError::Invalid(e.to_string()))?;
+    match format {
+        PlanFormat::Json => to_json(&plan),
+        PlanFormat::Dot => Ok(plan_dot(&plan)),
+    }
+}
+
+/// Verify an artifact file, returning the report as JSON and whether the
+/// artifact passed. The signature is checked only if `public_key` is given.
+pub fn verify_artifact(file: &Path, public_key: Option<&str>) -> Result<(String, bool), CliError> {
+    let artifact: orpheon_core::ExecutionArtifact = read_json(file)?;
+    let report = orpheon_sdk::verify_artifact(&artifact, public_key);
+    Ok((to_json(&report)?, report.is_valid()))
+}
+
+/// Keys and values of the state store file at `store`, as a JSON object.
+pub fn export_state(store: &Path) -> Result<String, CliError> {
+    let state: serde_json::Map<String, serde_json::Value> = read_store(store)?.into_iter().collect();
+    to_json(&state)
+}
+
+/// Merge the keys and values in `file` into the state store file at
+/// `store`, creating it if needed. Returns the number of keys imported.
+pub async fn import_state(store: &Path, file: &Path) -> Result<usize, CliError> {
+    let state: serde_json::Map<String, serde_json::Value> = read_json(file)?;
+    if let Some(key) = state.keys().find(|key| key.trim().is_empty()) {
+        return Err(invalid(file, format!("state key cannot be empty: {:?}", key)));
+    }
+
+    let target = load_store(store).await?;
+    for (key, value) in &state {
+        target.set(key, value.clone()).await.map_err(internal)?;
+    }
+    let snapshot = target.snapshot().await.map_err(internal)?;
+    std::fs::write(store, to_json(&snapshot)?)
+        .map_err(|e| CliError::Internal(format!("failed to write {}: {}", store.display(), e)))?;
+    Ok(state.len())
+}
+
+/// What upgrading the records in the state store file at `store` to the
+/// current schema would change, as JSON; the file is not written.
+pub async fn migrate_dry_run(store: &Path) -> Result<String, CliError> {
+    if !store.exists() {
+        return Err(CliError::Internal(format!("{} does not exist", store.display())));
+    }
+    let target = load_store(store).await?;
+    let report = migrations::migrate_store(&target, None, true).await.map_err(internal)?;
+    to_json(&report)
+}
+
+/// Keys and values in a state store file, sorted by key.
+pub fn read_store(store: &Path) -> Result<Vec<(String, serde_json::Value)>, CliError> {
+    let snapshot: StateSnapshot = read_json(store)?;
+    let mut entries: Vec<_> = snapshot.entries.into_iter().map(|(key, entry)| (key, entry.value)).collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(entries)
+}
+
+/// Load a state store file into memory; a missing file is an empty store.
+async fn load_store(store: &Path) -> Result<InMemoryStateStore, CliError> {
+    let target = InMemoryStateStore::new();
+    if store.exists() {
+        for (key, value) in read_store(store)? {
+            target.set(&key, value).await.map_err(internal)?;
+        }
+    }
+    Ok(target)
+}
+
+/// A plan's step graph in Graphviz DOT, one node per step and one edge per
+/// dependency.
+pub fn plan_dot(plan: &Plan) -> String {
+    let mut dot = format!("digraph \"plan-{}\" {{\n", plan.id);
+    for step in &plan.steps {
+        let _ = writeln!(dot, "    \"{}\" [label=\"{}\\n{}\"];", step.id, step.name, step.action);
+    }
+    for step in &plan.steps {
+        for dependency in &step.dependencies {
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\";", dependency, step.id);
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+fn read(path: &Path) -> Result<String, CliError> {
+    std::fs::read_to_string(path).map_err(|e| CliError::Internal(format!("failed to read {}: {}", path.display(), e)))
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    serde_json::from_str(&read(path)?).map_err(|e| invalid(path, e))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, CliError> {
+    serde_json::to_string_pretty(value).map_err(internal)
+}
+
+fn invalid(path: &Path, message: impl std::fmt::Display) -> CliError {
+    CliError::Invalid(format!("{}: {}", path.display(), message))
+}
+
+fn internal(error: impl std::fmt::Display) -> CliError {
+    CliError::Internal(error.to_string())
+}
@@ -0,0 +1,266 @@
+//! Node configuration.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use orpheon_core::{IntentLimits, IntentStatus, StaticRates, WeightBy, DEFAULT_MAX_EVENT_DATA_BYTES};
+use orpheon_negotiate::DEFAULT_HISTORY_CAP;
+
+use crate::chaos::ChaosConfig;
+use crate::federation::FederationConfig;
+use crate::gc::GcConfig;
+use crate::journal::JournalConfig;
+use crate::requote::RequoteConfig;
+use crate::security::CorsConfig;
+use crate::timeouts::RequestTimeoutConfig;
+
+/// Configuration for an Orpheon node.
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// Address the API server binds to.
+    pub bind_addr: SocketAddr,
+    
+    /// Directory of seed files to load on startup (see [`crate::seed`]).
+    pub seed_path: Option<PathBuf>,
+    
+    /// State store file to load on startup (see [`crate::cli`]).
+    pub store_path: Option<PathBuf>,
+    
+    /// Limits on execution event payloads.
+    pub event_data: EventDataConfig,
+    
+    /// Pacing of `/ws/state` version updates.
+    pub state_stream: StateStreamConfig,
+    
+    /// Dry-run defaults.
+    pub dry_run: DryRunConfig,
+    
+    /// Bounds on submitted intent budgets.
+    pub intent_limits: IntentLimits,
+    
+    /// Checks on intents at risk of missing their deadline.
+    pub deadline_watchdog: DeadlineWatchdogConfig,
+    
+    /// Alerts on intents waiting too long in one status.
+    pub backlog: BacklogConfig,
+    
+    /// Order in which received intents are planned.
+    pub queue: QueueConfig,
+    
+    /// How paused executions are accounted for.
+    pub pause: PauseConfig,
+    
+    /// What each step counts for in the success rate of a partially
+    /// complete intent; cost by default.
+    pub success_weighting: WeightBy,
+    
+    /// Cross-origin access; same-origin only by default.
+    pub cors: CorsConfig,
+    
+    /// Time budgets of each route group, and when a request counts as slow.
+    pub request_timeouts: RequestTimeoutConfig,
+    
+    /// Fault injection; disabled unless `unsafe_chaos` is set.
+    pub chaos: ChaosConfig,
+    
+    /// Operations journal.
+    pub journal: JournalConfig,
+    
+    /// Peer nodes intents can be forwarded to.
+    pub federation: FederationConfig,
+    
+    /// Eviction of finished intents and export of their artifacts.
+    pub gc: GcConfig,
+    
+    /// Reject intents submitted without an issuer signature.
+    pub require_signatures: bool,
+    
+    /// Proposals and counter-offers each negotiation keeps; see
+    /// [`NegotiationSession::with_history_cap`](orpheon_negotiate::NegotiationSession::with_history_cap).
+    pub negotiation_history_cap: usize,
+    
+    /// How accepted proposals are re-quoted before they execute.
+    pub requote: RequoteConfig,
+    
+    /// Currency action costs are in, and exchange rates into it for budgets
+    /// and quotes in other currencies; USD with no other currencies by
+    /// default.
+    pub currency: StaticRates,
+    
+    /// WebSocket base URL clients reach this node at, e.g.
+    /// `wss://gw.example.com/orpheon`, for nodes behind a proxy. Without it,
+    /// stream URLs are built from the `Host` header of each request.
+    pub public_ws_url: Option<String>,
+    
+    /// Capabilities this node offers, matched against intent
+    /// [requirements](orpheon_core::Intent::requirements), e.g.
+    /// `capability:gpu` or `region:us-east-1`.
+    pub capabilities: Vec<String>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            seed_path: None,
+            store_path: None,
+            event_data: EventDataConfig::default(),
+            state_stream: StateStreamConfig::default(),
+            dry_run: DryRunConfig::default(),
+            intent_limits: IntentLimits::default(),
+            deadline_watchdog: DeadlineWatchdogConfig::default(),
+            backlog: BacklogConfig::default(),
+            queue: QueueConfig::default(),
+            pause: PauseConfig::default(),
+            success_weighting: WeightBy::default(),
+            cors: CorsConfig::default(),
+            request_timeouts: RequestTimeoutConfig::default(),
+            chaos: ChaosConfig::default(),
+            journal: JournalConfig::default(),
+            federation: FederationConfig::default(),
+            gc: GcConfig::default(),
+            require_signatures: false,
+            negotiation_history_cap: DEFAULT_HISTORY_CAP,
+            requote: RequoteConfig::default(),
+            currency: StaticRates::default(),
+            public_ws_url: None,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+/// How oversized execution event payloads are handled.
+#[derive(Debug, Clone)]
+pub struct EventDataConfig {
+    /// Largest serialized `data` kept in an event, in bytes; larger payloads
+    /// are truncated to a preview and hash.
+    pub max_bytes: usize,
+    
+    /// Keep the full payload of truncated events in the state store under
+    /// `blob:{event_id}`, served by `/api/v1/events/:id/blob`.
+    pub store_blobs: bool,
+}
+
+impl Default for EventDataConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_EVENT_DATA_BYTES,
+            store_blobs: false,
+        }
+    }
+}
+
+/// How `/ws/state` reports store writes.
+#[derive(Debug, Clone)]
+pub struct StateStreamConfig {
+    /// Least time between two version updates on a connection, in
+    /// milliseconds; writes in between are reported together.
+    pub min_interval_ms: u64,
+    
+    /// Most changed keys listed in one update.
+    pub max_keys: usize,
+}
+
+impl Default for StateStreamConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_ms: 250,
+            max_keys: 64,
+        }
+    }
+}
+
+/// Defaults for `/api/v1/intent/:id/dryrun`.
+#[derive(Debug, Clone)]
+pub struct DryRunConfig {
+    /// Multiplier applied to step durations and timeouts when a request
+    /// does not give one; 0.01 runs a 10 s step in 100 ms.
+    pub time_scale: f64,
+}
+
+impl Default for DryRunConfig {
+    fn default() -> Self {
+        Self { time_scale: 0.01 }
+    }
+}
+
+/// How the deadline watchdog looks for intents that will be late.
+#[derive(Debug, Clone)]
+pub struct DeadlineWatchdogConfig {
+    /// Time between checks, in milliseconds.
+    pub interval_ms: u64,
+    
+    /// How far ahead of a deadline intents are checked, in milliseconds.
+    pub horizon_ms: u64,
+}
+
+impl Default for DeadlineWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1_000,
+            horizon_ms: 5 * 60 * 1000,
+        }
+    }
+}
+
+/// When the backlog monitor alerts on an intent waiting in one status;
+/// see [`crate::backlog`].
+#[derive(Debug, Clone)]
+pub struct BacklogConfig {
+    /// Time between checks, in milliseconds.
+    pub interval_ms: u64,
+    
+    /// Longest an intent may wait to be picked up, in milliseconds.
+    pub received_alert_ms: Option<u64>,
+    
+    /// Longest an intent may take to plan, in milliseconds.
+    pub planning_alert_ms: Option<u64>,
+    
+    /// Longest an intent may execute, in milliseconds.
+    pub executing_alert_ms: Option<u64>,
+}
+
+impl Default for BacklogConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1_000,
+            received_alert_ms: Some(5 * 60 * 1000),
+            planning_alert_ms: Some(60 * 1000),
+            executing_alert_ms: Some(60 * 60 * 1000),
+        }
+    }
+}
+
+impl BacklogConfig {
+    /// Alert threshold of `status`, if it has one.
+    pub fn threshold_ms(&self, status: IntentStatus) -> Option<u64> {
+        match status {
+            IntentStatus::Received => self.received_alert_ms,
+            IntentStatus::Planning => self.planning_alert_ms,
+            IntentStatus::Executing => self.executing_alert_ms,
+            _ => None,
+        }
+    }
+}
+
+/// How the engine orders received intents; see [`crate::queue`].
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    /// Time an intent waits before it is raised one priority, in
+    /// milliseconds; `None` never raises it.
+    pub aging_ms: Option<u64>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { aging_ms: Some(60 * 1000) }
+    }
+}
+
+/// How time spent paused is accounted for.
+#[derive(Debug, Clone, Default)]
+pub struct PauseConfig {
+    /// Count paused time towards an artifact's `actual_duration_ms`; by
+    /// default only time spent running steps counts.
+    pub count_paused_time: bool,
+}
@@ -0,0 +1,86 @@
+//! Lookup of active intents by their constraints.
+//!
+//! Monitors need to find e.g. every intent due in the next few minutes
+//! without matching the constraints of every stored intent. [`AppState`]
+//! keeps a [`ConstraintIndex`] of its non-terminal intents, updated as
+//! intents are stored, re-stored and reach a terminal status.
+//!
+//! Only hard constraints are indexed.
+//!
+//! [`AppState`]: crate::state::AppState
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use orpheon_core::{Constraint, Intent};
+use uuid::Uuid;
+
+/// Intents keyed by their deadline, SLA metrics and required providers.
+#[derive(Debug, Default)]
+pub struct ConstraintIndex {
+    deadlines: BTreeMap<DateTime<Utc>, BTreeSet<Uuid>>,
+    slas: BTreeMap<String, BTreeSet<Uuid>>,
+    providers: BTreeMap<String, BTreeSet<Uuid>>,
+}
+
+impl ConstraintIndex {
+    /// Index an intent's constraints.
+    pub fn insert(&mut self, intent: &Intent) {
+        for constraint in &intent.constraints {
+            match constraint {
+                Constraint::Deadline { by } => {
+                    self.deadlines.entry(*by).or_default().insert(intent.id);
+                }
+                Constraint::Sla { metric, .. } => {
+                    self.slas.entry(metric.clone()).or_default().insert(intent.id);
+                }
+                Constraint::Provider { node_id } => {
+                    self.providers.entry(node_id.clone()).or_default().insert(intent.id);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Drop an intent indexed with [`insert`](Self::insert).
+    pub fn remove(&mut self, intent: &Intent) {
+        for constraint in &intent.constraints {
+            match constraint {
+                Constraint::Deadline { by } => remove_from(&mut self.deadlines, by, intent.id),
+                Constraint::Sla { metric, .. } => remove_from(&mut self.slas, metric, intent.id),
+                Constraint::Provider { node_id } => remove_from(&mut self.providers, node_id, intent.id),
+                _ => {}
+            }
+        }
+    }
+
+    /// Intents with a deadline before `t`, earliest deadline first, each
+    /// with its earliest deadline.
+    pub fn deadlines_before(&self, t: DateTime<Utc>) -> Vec<(Uuid, DateTime<Utc>)> {
+        let mut seen = BTreeSet::new();
+        self.deadlines
+            .range(..t)
+            .flat_map(|(by, ids)| ids.iter().map(move |id| (*id, *by)))
+            .filter(|(id, _)| seen.insert(*id))
+            .collect()
+    }
+
+    /// Intents with an SLA on `metric`.
+    pub fn with_sla(&self, metric: &str) -> Vec<Uuid> {
+        self.slas.get(metric).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+
+    /// Intents that must run on `node_id`.
+    pub fn with_provider(&self, node_id: &str) -> Vec<Uuid> {
+        self.providers.get(node_id).map(|ids| ids.iter().copied().collect()).unwrap_or_default()
+    }
+}
+
+fn remove_from<K: Ord>(map: &mut BTreeMap<K, BTreeSet<Uuid>>, key: &K, id: Uuid) {
+    if let Some(ids) = map.get_mut(key) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            map.remove(key);
+        }
+    }
+}
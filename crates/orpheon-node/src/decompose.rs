@@ -0,0 +1,95 @@
+//! Splitting intents into child intents.
+//!
+//! Before planning an intent the engine asks the node's [`Decomposer`]
+//! whether to split it. If it returns children, they are stored with
+//! `parent_id` set and planned and executed like any other intent, while
+//! the parent waits in `Executing`. Once every child has finished the
+//! parent gets an artifact listing the children's artifacts, with the
+//! outcome worked out by [`parent_outcome`].
+//!
+//! Intents [`MAX_DECOMPOSITION_DEPTH`] levels below a root are planned
+//! directly, so a decomposer that always splits cannot recurse forever.
+
+use orpheon_core::{Intent, IntentBuilder, IntentStatus, Outcome};
+
+use crate::state::IntentRecord;
+
+/// Ancestors an intent may have and still be decomposed.
+pub const MAX_DECOMPOSITION_DEPTH: usize = 5;
+
+/// Decides whether an intent is split into child intents.
+pub trait Decomposer: Send + Sync {
+    /// Child intents to run instead of planning `intent`, or `None` to plan
+    /// it as is. The engine sets each child's parent.
+    fn decompose(&self, intent: &Intent) -> Option<Vec<IntentBuilder>>;
+}
+
+/// Decomposer that never splits; the node's default.
+#[derive(Debug, Default)]
+pub struct NoDecomposition;
+
+impl Decomposer for NoDecomposition {
+    fn decompose(&self, _intent: &Intent) -> Option<Vec<IntentBuilder>> {
+        None
+    }
+}
+
+/// Outcome of a decomposed intent whose children have all finished.
+///
+/// A failed or cancelled child fails the parent; otherwise a partially
+/// complete child makes it partially successful, by the share of children
+/// that completed.
+pub fn parent_outcome(children: &[IntentRecord]) -> Outcome {
+    let unsuccessful = children
+        .iter()
+        .find(|c| matches!(c.status, IntentStatus::Failed | IntentStatus::Cancelled));
+    if let Some(child) = unsuccessful {
+        let reason = match &child.error {
+            Some(error) => format!("child intent {} {}: {}", child.intent.id, child.status.as_str(), error),
+            None => format!("child intent {} {}", child.intent.id, child.status.as_str()),
+        };
+        return Outcome::Failure { reason, compensated: false };
+    }
+
+    let complete = children.iter().filter(|c| c.status == IntentStatus::Complete).count();
+    if complete == children.len() {
+        return Outcome::Success;
+    }
+    Outcome::PartialSuccess {
+        success_rate: (complete * 100 / children.len()) as u8,
+        details: format!("{} of {} child intents completed", complete, children.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+
+    async fn children(statuses: &[IntentStatus]) -> Vec<IntentRecord> {
+        let state = AppState::new();
+        let mut records = Vec::new();
+        for status in statuses {
+            let intent = Intent::builder().kind("deploy").build().unwrap();
+            state.store_intent(intent.clone()).await;
+            state.update_intent_status(intent.id, *status).await;
+            records.push(state.get_intent(intent.id).await.unwrap());
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn test_parent_outcome() {
+        let done = children(&[IntentStatus::Complete, IntentStatus::Complete]).await;
+        assert!(parent_outcome(&done).is_success());
+
+        let partial = children(&[IntentStatus::Complete, IntentStatus::PartiallyComplete]).await;
+        assert!(matches!(parent_outcome(&partial), Outcome::PartialSuccess { success_rate: 50, .. }));
+
+        let failed = children(&[IntentStatus::PartiallyComplete, IntentStatus::Cancelled]).await;
+        let Outcome::Failure { reason, .. } = parent_outcome(&failed) else {
+            panic!("a cancelled child should fail the parent");
+        };
+        assert!(reason.contains(&failed[1].intent.id.to_string()), "{}", reason);
+    }
+}
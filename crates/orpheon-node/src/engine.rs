@@ -1,14 +1,153 @@
 //! Core execution engine.
 
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
 
-use orpheon_core::{ExecutionArtifact, ExecutionEvent, IntentStatus, Outcome, Plan};
+use async_trait::async_trait;
+use futures::future::join_all;
+use futures::FutureExt;
+use orpheon_core::constraint::budget_constraints;
+use orpheon_core::{
+    finite_or_err, Constraint, ConstraintResult, EvaluationContext, ExecutionArtifact, ExecutionContext, ExecutionEvent, Intent,
+    IntentStatus, OrpheonError, Outcome, Plan, PlanningStrategy, StateExpr, Step,
+};
+use orpheon_negotiate::{NegotiationState, Proposal};
 use orpheon_planner::planner::PlanningState;
-use orpheon_planner::Planner;
-use tokio::time::{sleep, Duration};
-use tracing::{error, info};
+use orpheon_planner::{CancellationToken, Planner};
+use orpheon_state::StateStore;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
-use crate::state::AppState;
+use crate::chaos::{Fault, FaultInjector};
+use crate::decompose;
+use crate::journal::JournalEvent;
+use crate::negotiation;
+use crate::requote::{self, Requote, RequoteDecision, COST_DRIFT};
+use crate::state::{AppState, IntentRecord, IntentWarning};
+use crate::stats;
+
+/// Delay before retrying a failed step, multiplied by the attempt number.
+const RETRY_BACKOFF_MS: u64 = 100;
+
+/// How often a paused execution checks whether it was resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Result of a successfully executed step.
+#[derive(Debug, Clone)]
+pub struct StepOutput {
+    /// How long the step took in milliseconds.
+    pub duration_ms: u64,
+    
+    /// Data recorded on the step's completion event.
+    pub data: serde_json::Value,
+}
+
+impl StepOutput {
+    /// Output with no data.
+    pub fn new(duration_ms: u64) -> Self {
+        Self {
+            duration_ms,
+            data: serde_json::Value::Null,
+        }
+    }
+    
+    /// Attach data to the output.
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = data;
+        self
+    }
+}
+
+/// Where a step attempt is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepContext {
+    /// Intent the step belongs to.
+    pub intent_id: Uuid,
+    
+    /// Attempt number, starting at 1.
+    pub attempt: u32,
+}
+
+/// Runs individual plan steps.
+#[async_trait]
+pub trait StepExecutor: Send + Sync {
+    /// Execute one attempt of a step, returning its output or the reason it failed.
+    async fn execute(&self, step: &Step, ctx: &StepContext) -> Result<StepOutput, String>;
+}
+
+/// Executor that simulates each step by sleeping for its estimated duration.
+///
+/// With a [`FaultInjector`] attached, matching rules fail, delay or panic
+/// step attempts before the simulation runs.
+#[derive(Debug, Default)]
+pub struct SimulatedExecutor {
+    faults: Option<Arc<FaultInjector>>,
+}
+
+impl SimulatedExecutor {
+    /// Simulated executor that applies the given fault rules.
+    pub fn with_faults(faults: Arc<FaultInjector>) -> Self {
+        Self { faults: Some(faults) }
+    }
+}
+
+#[async_trait]
+impl StepExecutor for SimulatedExecutor {
+    async fn execute(&self, step: &Step, ctx: &StepContext) -> Result<StepOutput, String> {
+        let faults = self.faults.as_ref().map(|f| f.faults_for(step, ctx)).unwrap_or_default();
+        for fault in faults {
+            match fault {
+                Fault::Delay { ms } => sleep(Duration::from_millis(ms)).await,
+                Fault::Fail { reason } => return Err(reason),
+                Fault::Panic { message } => panic!("{}", message),
+            }
+        }
+        
+        let duration_ms = step.estimated_duration_ms.max(50);
+        sleep(Duration::from_millis(duration_ms)).await;
+        Ok(StepOutput::new(duration_ms))
+    }
+}
+
+/// Executor for dry runs: steps do nothing but wait for their estimated
+/// duration multiplied by `time_scale`.
+///
+/// Fault rules apply as in [`SimulatedExecutor`], with delays scaled too.
+/// Outputs report the unscaled duration and are marked `"dry_run": true`.
+#[derive(Debug)]
+pub struct NoopExecutor {
+    /// Multiplier applied to step durations and fault delays.
+    pub time_scale: f64,
+    
+    /// Fault rules to apply, if any.
+    pub faults: Option<Arc<FaultInjector>>,
+}
+
+impl NoopExecutor {
+    fn scaled(&self, ms: u64) -> Duration {
+        Duration::from_secs_f64(ms as f64 * self.time_scale / 1000.0)
+    }
+}
+
+#[async_trait]
+impl StepExecutor for NoopExecutor {
+    async fn execute(&self, step: &Step, ctx: &StepContext) -> Result<StepOutput, String> {
+        let faults = self.faults.as_ref().map(|f| f.faults_for(step, ctx)).unwrap_or_default();
+        for fault in faults {
+            match fault {
+                Fault::Delay { ms } => sleep(self.scaled(ms)).await,
+                Fault::Fail { reason } => return Err(reason),
+                Fault::Panic { message } => panic!("{}", message),
+            }
+        }
+        
+        sleep(self.scaled(step.estimated_duration_ms)).await;
+        Ok(StepOutput::new(step.estimated_duration_ms).with_data(serde_json::json!({ "dry_run": true })))
+    }
+}
 
 /// The core execution engine.
 pub struct Engine {
@@ -26,30 +165,234 @@ impl Engine {
         info!("🔧 Engine started");
         
         loop {
+            if self.state.engine_paused() {
+                sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            
+            // Expire intents whose validity window closed while they waited
+            self.expire_intents().await;
+            
             // Process pending intents
-            self.process_pending_intents().await;
+            let next_opening = self.process_pending_intents().await;
+            
+            // Advance intents whose negotiation was resolved
+            self.process_negotiations().await;
             
-            // Small delay to prevent busy-waiting
-            sleep(Duration::from_millis(100)).await;
+            // Finish decomposed intents whose children are all done
+            self.finish_decomposed().await;
+            
+            // Small delay to prevent busy-waiting, cut short when a
+            // deferred intent's window opens sooner
+            let mut delay = Duration::from_millis(100);
+            if let Some(opening) = next_opening {
+                let until = (opening - chrono::Utc::now()).to_std().unwrap_or_default();
+                delay = delay.min(until);
+            }
+            sleep(delay).await;
         }
     }
     
-    /// Process intents that are in Received or Planning state.
-    async fn process_pending_intents(&self) {
-        // Collect IDs of intents to process (to avoid borrow issues)
-        let pending_ids: Vec<uuid::Uuid> = {
+    /// Expire intents whose validity window closed before they started
+    /// executing; see [`AppState::expire_intent`].
+    ///
+    /// An intent being planned is expired by its planning task instead,
+    /// which stops the search.
+    async fn expire_intents(&self) {
+        for id in self.state.intents_past_window().await {
+            if self.state.expire_intent(id).await {
+                info!("⌛ Intent {} expired before it ran", id);
+            }
+        }
+    }
+    
+    /// Plan the next received intent, highest priority first; see
+    /// [`crate::queue`].
+    ///
+    /// Intents whose validity window has not opened yet, or is between two
+    /// of its daily windows, or that depend on intents still running, stay
+    /// Received; returns when the earliest of those windows opens. Intents
+    /// whose required dependencies did not succeed are failed.
+    async fn process_pending_intents(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let (next, next_opening, broken) = {
+            let now = chrono::Utc::now();
             let intents = self.state.intents.read().await;
-            intents
-                .iter()
-                .filter(|(_, record)| record.status == IntentStatus::Received)
-                .map(|(id, _)| *id)
-                .collect()
+            let mut next_opening = None;
+            let mut next = None;
+            let mut broken = Vec::new();
+            for id in self.state.intent_queue.ordered(now, self.state.queue.aging_ms) {
+                let Some(record) = intents.get(&id) else {
+                    continue;
+                };
+                if record.status != IntentStatus::Received || record.forwarded.is_some() {
+                    continue;
+                }
+                let window = &record.intent.validity_window;
+                if window.is_pending() {
+                    if let Some(opening) = window.next_opening() {
+                        next_opening = Some(next_opening.map_or(opening, |next: chrono::DateTime<chrono::Utc>| next.min(opening)));
+                    }
+                    continue;
+                }
+                match dependency_gate(&record.intent, &intents) {
+                    DependencyGate::Ready => {}
+                    DependencyGate::Waiting => continue,
+                    DependencyGate::Broken(error) => {
+                        broken.push((id, error));
+                        continue;
+                    }
+                }
+                next.get_or_insert(id);
+            }
+            (next, next_opening, broken)
         };
+        
+        for (id, error) in broken {
+            if self.state.fail_intent_if(id, IntentStatus::Received, &error).await {
+                info!("⛓️ Intent {} failed: {}", id, error);
+            }
+        }
 
         // Process one intent at a time
-        for id in pending_ids {
+        if let Some(id) = next {
             self.start_planning(id).await;
-            break; // Process one at a time
+        }
+        next_opening
+    }
+    
+    /// Execute accepted negotiations, re-plan countered ones and fail
+    /// rejected ones.
+    ///
+    /// Timeouts are handled by each session's watch task.
+    async fn process_negotiations(&self) {
+        let handles: Vec<_> = {
+            let negotiations = self.state.negotiations.read().await;
+            negotiations.iter().map(|(id, h)| (*id, h.clone())).collect()
+        };
+        
+        for (intent_id, handle) in handles {
+            match handle.session.state().await {
+                NegotiationState::Accepted => {
+                    let Some(proposal) = handle.session.current_proposal().await else {
+                        continue;
+                    };
+                    let Some(requote) = self.requote(intent_id, &handle, &proposal).await else {
+                        continue;
+                    };
+                    if !handle.session.mark_executing().await {
+                        continue;
+                    }
+                    let plan = self.state.get_plan(proposal.plan.id).await.unwrap_or(proposal.plan);
+                    let invalid = match negotiation::validate(&self.state, &plan).await {
+                        Ok(report) if report.valid => None,
+                        Ok(report) => Some(report.describe()),
+                        Err(e) => Some(e.to_string()),
+                    };
+                    if let Some(reason) = invalid {
+                        error!("❌ Accepted plan for intent {} failed validation: {}", intent_id, reason);
+                        self.state
+                            .fail_intent_if(intent_id, IntentStatus::Negotiating, &format!("Plan failed validation: {}", reason))
+                            .await;
+                        continue;
+                    }
+                    
+                    info!("🤝 Proposal accepted for intent {}", intent_id);
+                    self.state.journal.append(JournalEvent::ProposalAccepted { intent_id, proposal_id: proposal.id });
+                    if let Some(receipt) = handle.session.receipt().await {
+                        self.state.store_receipt(intent_id, receipt).await;
+                    }
+                    if !self
+                        .state
+                        .update_intent_status_if(intent_id, IntentStatus::Negotiating, IntentStatus::Executing)
+                        .await
+                    {
+                        // Cancelled while the proposal was out
+                        continue;
+                    }
+                    self.state.mark_plan_executed(intent_id, plan.id).await;
+                    let drift = Some(requote).filter(|r| r.decision == RequoteDecision::Proceeded);
+                    self.execute_plan(intent_id, plan, drift).await;
+                }
+                NegotiationState::Countered => {
+                    self.replan_countered(intent_id, &handle).await;
+                }
+                NegotiationState::Rejected => {
+                    self.state
+                        .fail_intent_if(intent_id, IntentStatus::Negotiating, "negotiation rejected")
+                        .await;
+                }
+                _ => {}
+            }
+        }
+    }
+    
+    /// Re-price an accepted proposal before it executes; see [`requote`].
+    ///
+    /// Returns `None` if the proposal must not execute: it was replaced by
+    /// a fresh one at the new price, or the negotiation ended trying.
+    async fn requote(
+        &self,
+        intent_id: Uuid,
+        handle: &negotiation::NegotiationHandle,
+        proposal: &Proposal,
+    ) -> Option<Requote> {
+        let model = self.state.cost_model().await;
+        let (requote, plan) = requote::requote(model.as_ref(), &self.state.requote, &self.state.pricing, proposal).await;
+        if requote.decision == RequoteDecision::WithinTolerance {
+            return Some(requote);
+        }
+        
+        warn!("💱 {}", requote.describe());
+        self.state
+            .add_warning(intent_id, IntentWarning { code: COST_DRIFT.to_string(), message: requote.describe() })
+            .await;
+        if requote.decision == RequoteDecision::Proceeded {
+            return Some(requote);
+        }
+        
+        let plan = Plan { id: Uuid::new_v4(), ..plan };
+        let Some(plan) = self.state.store_plan_if(plan, IntentStatus::Negotiating).await else {
+            // Cancelled while re-quoting
+            return None;
+        };
+        if let Err(e) = handle.session.reopen(plan).await {
+            warn!("Could not renegotiate intent {} at its new price: {}", intent_id, e);
+            self.state
+                .fail_intent_if(intent_id, IntentStatus::Negotiating, &format!("Cannot renegotiate at the new price: {}", e))
+                .await;
+        }
+        None
+    }
+    
+    /// Re-plan under a counter-offer's caps and send the new proposal.
+    ///
+    /// The negotiation is rejected when no plan fits the counter-offer.
+    async fn replan_countered(&self, intent_id: uuid::Uuid, handle: &negotiation::NegotiationHandle) {
+        let Some(counter) = handle.session.last_counter().await else {
+            return;
+        };
+        let intent = counter.tighten(&handle.session.intent);
+        
+        let result = match self.plan_segments(&intent).await {
+            Ok((plan, continuations)) => match self
+                .state
+                .store_checked_segments(&intent, plan, continuations, IntentStatus::Negotiating)
+                .await
+            {
+                Ok(Some(plan)) => handle.session.send_proposal(plan).await.map(|_| ()),
+                // The intent finished while re-planning
+                Ok(None) => return,
+                Err(e) => Err(OrpheonError::PlanningFailed { intent_id, message: e.to_string() }),
+            },
+            Err(e) => Err(e),
+        };
+        
+        match result {
+            Ok(()) => info!("🔁 Re-planned intent {} for counter-offer", intent_id),
+            Err(e) => {
+                warn!("Could not meet counter-offer for intent {}: {}", intent_id, e);
+                let _ = handle.session.reject(format!("Cannot meet counter-offer: {}", e)).await;
+            }
         }
     }
     
@@ -57,8 +400,14 @@ impl Engine {
     async fn start_planning(&self, intent_id: uuid::Uuid) {
         info!("📋 Starting planning for intent {}", intent_id);
         
-        // Update status to Planning
-        self.state.update_intent_status(intent_id, IntentStatus::Planning).await;
+        // Update status to Planning, unless the intent was cancelled first
+        if !self
+            .state
+            .update_intent_status_if(intent_id, IntentStatus::Received, IntentStatus::Planning)
+            .await
+        {
+            return;
+        }
         
         // Get the intent
         let record = match self.state.get_intent(intent_id).await {
@@ -69,38 +418,209 @@ impl Engine {
             }
         };
         
+        // Fail fast rather than deep in execution if this node cannot run it
+        let missing = record.intent.missing_capabilities(&self.state.capabilities);
+        if !missing.is_empty() {
+            let e = OrpheonError::CapabilityMissing { intent_id, missing };
+            error!("❌ Cannot plan intent {}: {}", intent_id, e);
+            self.state.fail_intent_if(intent_id, IntentStatus::Planning, &e.to_string()).await;
+            return;
+        }
+        
+        // Run child intents instead if the decomposer splits this one
+        if self.decompose(&record.intent).await {
+            return;
+        }
+        
         // Generate a plan
-        let initial_state = PlanningState::default();
-        let plan_result = self.state.planner.plan(&record.intent, &initial_state).await;
+        let plan_result = self.plan_segments(&record.intent).await;
         
         match plan_result {
-            Ok(plan) => {
-                info!("✅ Plan generated for intent {} with {} steps", intent_id, plan.steps.len());
+            Ok((plan, continuations)) => {
+                self.state.stats.count_for_kind(stats::PLANNING_SUCCEEDED, &record.intent.kind).await;
+                let steps = plan.steps.len() + continuations.iter().map(|s| s.steps.len()).sum::<usize>();
+                info!(
+                    "✅ Plan generated for intent {} with {} steps in {} segment(s)",
+                    intent_id,
+                    steps,
+                    continuations.len() + 1
+                );
+                
+                // Store the plan as the newest revision, once its bindings check out
+                let plan = match self
+                    .state
+                    .store_checked_segments(&record.intent, plan, continuations, IntentStatus::Planning)
+                    .await
+                {
+                    Ok(Some(plan)) => plan,
+                    Ok(None) => {
+                        info!("Intent {} finished while planning; dropping its plan", intent_id);
+                        return;
+                    }
+                    Err(e) => {
+                        error!("❌ Plan for intent {} has invalid bindings: {}", intent_id, e);
+                        self.state.fail_intent_if(intent_id, IntentStatus::Planning, &e.to_string()).await;
+                        return;
+                    }
+                };
                 
-                // Store the plan
-                self.state.store_plan(plan.clone()).await;
+                // Let the client approve the plan first if it asked to
+                if let Some(options) = record.negotiation.clone() {
+                    if !self
+                        .state
+                        .update_intent_status_if(intent_id, IntentStatus::Planning, IntentStatus::Negotiating)
+                        .await
+                    {
+                        return;
+                    }
+                    self.state.stats.count(stats::INTENTS_NEGOTIATING).await;
+                    if let Err(e) = negotiation::open(&self.state, record.intent.clone(), plan, &options).await {
+                        error!("❌ Could not open negotiation for intent {}: {}", intent_id, e);
+                        self.state
+                            .fail_intent_if(intent_id, IntentStatus::Negotiating, &e.to_string())
+                            .await;
+                    }
+                    return;
+                }
                 
                 // For simplicity, skip negotiation and go straight to execution
-                self.state.update_intent_status(intent_id, IntentStatus::Executing).await;
+                if !self
+                    .state
+                    .update_intent_status_if(intent_id, IntentStatus::Planning, IntentStatus::Executing)
+                    .await
+                {
+                    return;
+                }
+                self.state.mark_plan_executed(intent_id, plan.id).await;
                 
                 // Execute the plan
-                self.execute_plan(intent_id, plan).await;
+                self.execute_plan(intent_id, plan, None).await;
             }
             Err(e) => {
                 error!("❌ Planning failed for intent {}: {}", intent_id, e);
+                self.state.stats.count_for_kind(stats::PLANNING_FAILED, &record.intent.kind).await;
                 
                 // Update status to Failed
-                let mut intents = self.state.intents.write().await;
-                if let Some(record) = intents.get_mut(&intent_id) {
-                    record.status = IntentStatus::Failed;
-                    record.error = Some(e.to_string());
+                self.state.fail_intent_if(intent_id, IntentStatus::Planning, &e.to_string()).await;
+            }
+        }
+    }
+    
+    /// Split a planning intent into child intents if the decomposer wants
+    /// to, moving it to `Executing` until they finish.
+    ///
+    /// Returns whether the intent was taken care of, i.e. should not be
+    /// planned.
+    async fn decompose(&self, intent: &Intent) -> bool {
+        if self.state.intent_depth(intent.id).await >= decompose::MAX_DECOMPOSITION_DEPTH {
+            return false;
+        }
+        let Some(builders) = self.state.decomposer().await.decompose(intent).filter(|b| !b.is_empty()) else {
+            return false;
+        };
+        
+        let children: orpheon_core::Result<Vec<Intent>> =
+            builders.into_iter().map(|builder| builder.parent(intent.id).build()).collect();
+        let children = match children {
+            Ok(children) => children,
+            Err(e) => {
+                error!("❌ Could not decompose intent {}: {}", intent.id, e);
+                self.state.fail_intent_if(intent.id, IntentStatus::Planning, &e.to_string()).await;
+                return true;
+            }
+        };
+        if !self
+            .state
+            .update_intent_status_if(intent.id, IntentStatus::Planning, IntentStatus::Executing)
+            .await
+        {
+            return true;
+        }
+        
+        info!("🌿 Decomposed intent {} into {} child intent(s)", intent.id, children.len());
+        for child in children {
+            self.state.store_intent(child).await;
+        }
+        self.state.mark_decomposed(intent.id).await;
+        true
+    }
+    
+    /// Store an artifact for each decomposed intent whose children have
+    /// all finished, referencing theirs.
+    async fn finish_decomposed(&self) {
+        for parent_id in self.state.decomposed_in_progress().await {
+            let children = self.state.children_of(parent_id).await;
+            if !children.iter().all(|c| c.status.is_terminal()) {
+                continue;
+            }
+            let Some(parent) = self.state.get_intent(parent_id).await else {
+                continue;
+            };
+            
+            let plan = Plan::new(parent_id, PlanningStrategy::Deterministic);
+            let mut artifact = ExecutionArtifact::new(parent.intent, plan, decompose::parent_outcome(&children));
+            artifact.child_artifacts = children.iter().filter_map(|c| c.artifact_id).collect();
+            for child_artifact in artifact.child_artifacts.clone() {
+                if let Some(child) = self.state.get_artifact(child_artifact).await {
+                    artifact.add_child_cost(&child);
+                    artifact.actual_duration_ms += child.actual_duration_ms;
                 }
             }
+            artifact.execution_metadata.node_version = env!("CARGO_PKG_VERSION").to_string();
+            artifact.finalize();
+            self.state.node_key.sign_artifact(&mut artifact);
+            
+            info!("✅ All {} child intent(s) of intent {} finished", children.len(), parent_id);
+            self.state.store_artifact(artifact).await;
         }
     }
     
-    /// Execute a plan.
-    async fn execute_plan(&self, intent_id: uuid::Uuid, plan: Plan) {
+    /// Plan an intent as its first segment and any continuing ones.
+    ///
+    /// The intent is planned with its budget in the base currency, which
+    /// action costs are in. If its validity window closes first, the
+    /// search stops and the intent expires.
+    async fn plan_segments(&self, intent: &Intent) -> orpheon_core::Result<(Plan, Vec<Plan>)> {
+        let intent = Intent { budget: self.state.base_budget(intent)?, ..intent.clone() };
+        let intent_id = intent.id;
+        let closes = intent.validity_window.not_after;
+        
+        // Search on a blocking thread, stopped if the window closes first
+        let cancel = CancellationToken::new();
+        let _guard = cancel.clone().drop_guard();
+        let planner = self.state.planner.clone();
+        let token = cancel.clone();
+        let search = tokio::task::spawn_blocking(move || {
+            let plan = planner.plan_with_cancellation(&intent, &PlanningState::default(), &token).into_result()?;
+            let config = planner.config();
+            Ok(if config.split_long_plans { plan.split(config.max_steps) } else { vec![plan] })
+        });
+        let until_closed = closes.map(|at| (at - chrono::Utc::now()).to_std().unwrap_or_default());
+        let result = tokio::select! {
+            result = search => result.map_err(|e| OrpheonError::Internal(e.to_string()))?,
+            _ = sleep(until_closed.unwrap_or_default()), if until_closed.is_some() => {
+                cancel.cancel();
+                if self.state.expire_intent(intent_id).await {
+                    info!("⌛ Intent {} expired while planning", intent_id);
+                }
+                Err(OrpheonError::PlanningFailed {
+                    intent_id,
+                    message: "validity window closed while planning".to_string(),
+                })
+            }
+        };
+        
+        let mut segments = result?.into_iter();
+        let plan = segments.next().ok_or_else(|| OrpheonError::PlanningFailed {
+            intent_id,
+            message: "planner returned no plan".to_string(),
+        })?;
+        Ok((plan, segments.collect()))
+    }
+    
+    /// Execute a plan, recording on the artifact how far its price drifted
+    /// since it was accepted, if that was beyond the tolerance.
+    async fn execute_plan(&self, intent_id: uuid::Uuid, plan: Plan, drift: Option<Requote>) {
         info!("🚀 Executing plan for intent {}", intent_id);
         
         let record = match self.state.get_intent(intent_id).await {
@@ -111,33 +631,527 @@ impl Engine {
             }
         };
         
-        // Create artifact
-        let mut artifact = ExecutionArtifact::new(
-            record.intent.clone(),
-            plan.clone(),
-            Outcome::Success,
-        );
+        let executor = self.state.step_executor().await;
+        let mut artifact = self.run_plan(&record.intent, plan, executor.as_ref(), 1.0, true).await;
+        artifact.plan_revision = record.plan_revision(artifact.final_plan.id).unwrap_or(0);
         
-        // Execute each step (simplified simulation)
-        for step in &plan.steps {
-            info!("  📌 Executing step: {}", step.name);
+        if artifact.outcome.is_success() {
+            info!("✅ Execution complete for intent {}", intent_id);
+        }
+        
+        artifact.execution_metadata.node_version = env!("CARGO_PKG_VERSION").to_string();
+        let mut extra = serde_json::Map::new();
+        if let Some(receipt) = &record.receipt {
+            extra.insert("receipt_id".to_string(), serde_json::json!(receipt.id));
+        }
+        if let Some(drift) = drift {
+            extra.insert("cost_drift".to_string(), serde_json::json!(drift));
+        }
+        if !extra.is_empty() {
+            artifact.execution_metadata.extra = serde_json::Value::Object(extra);
+        }
+        artifact.finalize();
+        self.state.node_key.sign_artifact(&mut artifact);
+        
+        // Store the artifact; a cancel that raced the last step wins
+        let artifact_id = artifact.id;
+        if !self.state.store_artifact(artifact).await {
+            warn!("Intent {} finished before its execution did; artifact {} filed as orphaned", intent_id, artifact_id);
+        }
+    }
+    
+    /// Run a plan's steps without executing anything for real.
+    ///
+    /// Steps sleep for their estimated duration and time out after their
+    /// timeout, both multiplied by `time_scale`; the node's chaos rules
+    /// still apply. The returned artifact is marked with
+    /// `execution_metadata.extra.dry_run`, is not signed, and is stored
+    /// apart from the intent, whose status is left alone.
+    pub async fn dry_run(&self, intent: &Intent, plan: Plan, time_scale: f64) -> ExecutionArtifact {
+        info!("🧪 Dry-running plan {} for intent {} at {}× time", plan.id, intent.id, time_scale);
+        let executor = NoopExecutor { time_scale, faults: self.state.chaos.clone() };
+        let mut artifact = self.run_plan(intent, plan, &executor, time_scale, false).await;
+        
+        artifact.execution_metadata.node_version = env!("CARGO_PKG_VERSION").to_string();
+        artifact.execution_metadata.extra = serde_json::json!({ "dry_run": true, "time_scale": time_scale });
+        artifact.finalize();
+        self.state.store_dry_run(artifact.clone()).await;
+        artifact
+    }
+    
+    /// Run a plan's steps with an executor and record what happened.
+    ///
+    /// Steps start as soon as all their dependencies are done, so
+    /// independent branches run concurrently. A step only sees the outputs
+    /// of steps finished before it started. Execution stops at the first
+    /// failed step that is not optional, once the steps running alongside
+    /// it have finished; completed steps are then compensated in reverse.
+    /// Step timeouts are multiplied by `time_scale`.
+    ///
+    /// A `live` run is the intent's real execution: its record lists the
+    /// steps done after each wave, and pausing the intent holds back the
+    /// next wave until it is resumed.
+    ///
+    /// A plan split into segments runs one segment after another, into a
+    /// single artifact whose `final_plan` is the segments joined.
+    async fn run_plan(
+        &self,
+        intent: &Intent,
+        plan: Plan,
+        executor: &dyn StepExecutor,
+        time_scale: f64,
+        live: bool,
+    ) -> ExecutionArtifact {
+        let intent_id = intent.id;
+        let segments = self.state.plan_segments(plan).await;
+        let joined = segments[0].clone().joined(segments[1..].iter().cloned());
+        let mut artifact = ExecutionArtifact::new(intent.clone(), joined, Outcome::Success);
+        // Step costs are in the base currency, so the budget is enforced in it
+        let budget = match self.state.base_budget(intent) {
+            Ok(budget) => budget_constraints(&budget),
+            Err(e) => {
+                artifact.outcome = Outcome::Failure { reason: e.to_string(), compensated: false };
+                return artifact;
+            }
+        };
+        let mut context = ExecutionContext::for_intent(intent);
+        let mut completed: Vec<Step> = Vec::new();
+        let mut skipped: Vec<&str> = Vec::new();
+        let mut done: HashSet<Uuid> = HashSet::new();
+        let mut variables: HashMap<String, serde_json::Value> = HashMap::new();
+        
+        // Run waves of ready steps, stopping at the first failure of a required one
+        'segments: for segment in &segments {
+            let mut remaining: Vec<&Step> = segment.steps.iter().collect();
+            while !remaining.is_empty() {
+                if live {
+                    if let Some(outcome) = self.wait_while_paused(&mut artifact, intent_id).await {
+                        artifact.outcome = outcome;
+                        break 'segments;
+                    }
+                }
+                
+                let (ready, waiting): (Vec<&Step>, Vec<&Step>) = remaining
+                    .into_iter()
+                    .partition(|s| s.dependencies.iter().all(|d| done.contains(d)));
+                remaining = waiting;
+                if ready.is_empty() {
+                    artifact.outcome = Outcome::Failure {
+                        reason: "plan has steps whose dependencies can never complete".to_string(),
+                        compensated: false,
+                    };
+                    break 'segments;
+                }
+            
+                let mut starting = Vec::new();
+                for step in ready {
+                    if let Some(condition) = &step.skip_if {
+                        if self.should_skip(&context, step, condition).await {
+                            info!("  ⏭️ Skipping step {}: {}", step.name, condition);
+                            self.record_event(&mut artifact, ExecutionEvent::step_skipped(step.id, condition)).await;
+                            // Later steps see the skipped one as done
+                            context.record_step_output(&step.name, serde_json::json!({ "skipped": true }));
+                            done.insert(step.id);
+                            continue;
+                        }
+                    }
+                
+                    match &step.description {
+                        Some(description) => info!("  📌 Executing step: {} ({})", step.name, description),
+                        None => info!("  📌 Executing step: {}", step.name),
+                    }
+                
+                    // Resolve bindings and record the start event with their values
+                    let resolved = context.substitute(&step.parameters);
+                    let mut data = serde_json::Map::new();
+                    if let Some(description) = &step.description {
+                        data.insert("description".to_string(), serde_json::json!(description));
+                    }
+                    if let Ok(resolved) = &resolved {
+                        if !resolved.bindings.is_empty() {
+                            data.insert("bindings".to_string(), serde_json::json!(resolved.bindings));
+                        }
+                    }
+                    let mut started = ExecutionEvent::step_started(step.id);
+                    if !data.is_empty() {
+                        started = started.with_data(serde_json::Value::Object(data));
+                    }
+                    self.record_event(&mut artifact, started).await;
+                
+                    let resolved = resolved
+                        .map(|r| Step { parameters: r.value, ..step.clone() })
+                        .map_err(|e| e.to_string());
+                    starting.push((step, resolved));
+                }
             
-            // Record start event
-            artifact.add_event(ExecutionEvent::step_started(step.id));
+                let runs = join_all(
+                    starting
+                        .iter()
+                        .map(|(step, resolved)| self.run_started_step(executor, intent_id, step, resolved, time_scale)),
+                )
+                .await;
             
-            // Simulate execution time
-            let duration_ms = step.estimated_duration_ms.max(50);
-            sleep(Duration::from_millis(duration_ms)).await;
+                // Record the wave's events in the order they happened
+                let mut events: Vec<ExecutionEvent> = runs.iter().flat_map(|run| run.events.iter().cloned()).collect();
+                events.sort_by_key(|e| e.timestamp);
+                for event in events {
+                    self.record_event(&mut artifact, event).await;
+                }
             
-            // Record completion event
-            artifact.add_event(ExecutionEvent::step_completed(step.id, duration_ms));
+                let mut failure = None;
+                for ((step, _), run) in starting.into_iter().zip(runs) {
+                    match run.result {
+                        Ok((step, output)) => {
+                            if let Some(fields) = output.data.as_object() {
+                                variables.extend(fields.iter().map(|(k, v)| (k.clone(), v.clone())));
+                            }
+                            context.record_step_output(&step.name, output.data);
+                            match finite_or_err(step.estimated_cost, format_args!("cost of step {}", step.name)) {
+                                Ok(cost) => artifact.add_cost(cost),
+                                Err(e) => warn!("{}; left out of the cost of intent {}", e, intent_id),
+                            }
+                            done.insert(step.id);
+                            completed.push(step);
+                        }
+                        Err(reason) if step.optional => {
+                            warn!("⚠️ Optional step {} failed for intent {}: {}", step.name, intent_id, reason);
+                            done.insert(step.id);
+                            skipped.push(&step.name);
+                        }
+                        Err(reason) => {
+                            error!("❌ Step {} failed for intent {}: {}", step.name, intent_id, reason);
+                            failure.get_or_insert((step, reason));
+                        }
+                    }
+                }
+                if live {
+                    self.state.record_steps_done(intent_id, done.iter().copied()).await;
+                }
             
-            artifact.actual_cost += step.estimated_cost;
+                if let Some((step, reason)) = failure {
+                    let compensated = self.compensate(&mut artifact, executor, intent_id, &completed, time_scale).await;
+                    artifact.outcome = Outcome::Failure {
+                        reason: format!("step {} failed: {}", step.name, reason),
+                        compensated,
+                    };
+                    break 'segments;
+                }
+                
+                if let Some(violation) = self.constraint_violation(intent, &budget, &artifact, &variables) {
+                    error!("❌ Intent {} broke a constraint: {}", intent_id, violation.detail);
+                    let compensated = self.compensate(&mut artifact, executor, intent_id, &completed, time_scale).await;
+                    artifact.outcome = Outcome::Failure {
+                        reason: violation.into_error(intent_id).to_string(),
+                        compensated,
+                    };
+                    break 'segments;
+                }
+            }
         }
         
-        info!("✅ Execution complete for intent {}", intent_id);
+        if artifact.outcome.is_success() && !skipped.is_empty() {
+            artifact.outcome = Outcome::partial(
+                artifact.success_rate_weighted(self.state.success_weighting),
+                format!("optional steps failed: {}", skipped.join(", ")),
+            );
+            warn!("✅ Execution partially complete for intent {}", intent_id);
+        }
+        artifact
+    }
+    
+    /// The first hard constraint or budget cap broken by an execution so
+    /// far, judged with the planner's evaluator against the cost and time
+    /// recorded in `artifact` and the fields of the step outputs.
+    fn constraint_violation(
+        &self,
+        intent: &Intent,
+        budget: &[Constraint],
+        artifact: &ExecutionArtifact,
+        variables: &HashMap<String, serde_json::Value>,
+    ) -> Option<ConstraintResult> {
+        let elapsed_ms = artifact.actual_duration_ms;
+        let mut ctx = EvaluationContext::new()
+            .with_variables(variables.clone())
+            .with_cost(artifact.total_cost())
+            .with_elapsed_ms(elapsed_ms)
+            // The work so far finishes now, so deadlines are judged by the clock
+            .with_started_at(chrono::Utc::now() - chrono::Duration::milliseconds(elapsed_ms as i64));
+        ctx.node_id = self.state.federation.config().node_id.clone();
         
-        // Store the artifact
-        self.state.store_artifact(artifact).await;
+        self.state
+            .planner
+            .evaluator()
+            .violations(intent.constraints.iter().chain(budget), &ctx)
+            .into_iter()
+            .next()
     }
+    
+    /// Hold execution while the intent is paused, recording the pause and
+    /// the resume in the trace.
+    ///
+    /// Returns the outcome to stop with if the intent was cancelled while
+    /// paused.
+    async fn wait_while_paused(&self, artifact: &mut ExecutionArtifact, intent_id: Uuid) -> Option<Outcome> {
+        if self.state.get_intent(intent_id).await?.status != IntentStatus::Paused {
+            return None;
+        }
+        
+        info!("⏸️ Execution paused for intent {}", intent_id);
+        self.record_event(artifact, ExecutionEvent::paused()).await;
+        let paused_at = Instant::now();
+        let status = loop {
+            sleep(PAUSE_POLL_INTERVAL).await;
+            match self.state.get_intent(intent_id).await.map(|r| r.status) {
+                Some(IntentStatus::Paused) => {}
+                status => break status,
+            }
+        };
+        
+        if status != Some(IntentStatus::Executing) {
+            return Some(Outcome::Cancelled {
+                by: "client".to_string(),
+                reason: "intent was cancelled while paused".to_string(),
+            });
+        }
+        let paused_ms = paused_at.elapsed().as_millis() as u64;
+        info!("▶️ Execution resumed for intent {} after {} ms", intent_id, paused_ms);
+        self.record_event(artifact, ExecutionEvent::resumed(paused_ms, self.state.pause.count_paused_time)).await;
+        None
+    }
+    
+    /// Run a started step to completion or failure, collecting its events.
+    async fn run_started_step(
+        &self,
+        executor: &dyn StepExecutor,
+        intent_id: Uuid,
+        step: &Step,
+        resolved: &Result<Step, String>,
+        time_scale: f64,
+    ) -> StepRun {
+        let mut events = Vec::new();
+        let result = match resolved {
+            Ok(resolved) => self
+                .run_step(&mut events, executor, intent_id, resolved, time_scale)
+                .await
+                .map(|output| (resolved.clone(), output)),
+            Err(reason) => Err(reason.clone()),
+        };
+        events.push(match &result {
+            Ok((_, output)) => ExecutionEvent::step_completed(step.id, output.duration_ms).with_data(output.data.clone()),
+            Err(reason) => ExecutionEvent::step_failed(step.id, reason.clone()),
+        });
+        StepRun { events, result }
+    }
+    
+    /// Evaluate a step's `skip_if` condition against the execution context and
+    /// the state-store keys it reads. A condition that cannot be parsed or
+    /// reads a missing variable counts as false, so the step runs.
+    async fn should_skip(&self, context: &ExecutionContext, step: &Step, condition: &str) -> bool {
+        let expr = match StateExpr::parse(condition) {
+            Ok(expr) => expr,
+            Err(e) => {
+                warn!("Running step {} despite its skip_if: {}", step.name, e);
+                return false;
+            }
+        };
+        
+        let mut snapshot = serde_json::Map::new();
+        for key in expr.state_keys() {
+            let value = match self.state.state_store.get(&key).await {
+                Ok(entry) => entry.map_or(serde_json::Value::Null, |e| e.value),
+                Err(e) => {
+                    warn!("Could not read state key {} for step {}: {}", key, step.name, e);
+                    serde_json::Value::Null
+                }
+            };
+            snapshot.insert(key, value);
+        }
+        
+        context.evaluate(&expr, snapshot).unwrap_or_else(|e| {
+            warn!("Running step {} despite its skip_if: {}", step.name, e);
+            false
+        })
+    }
+    
+    /// Run a step, retrying failed attempts while the step allows it.
+    ///
+    /// Each failed attempt that will be retried is added to `events` as a
+    /// `StepRetrying` event; the error of the last attempt is returned.
+    async fn run_step(
+        &self,
+        events: &mut Vec<ExecutionEvent>,
+        executor: &dyn StepExecutor,
+        intent_id: Uuid,
+        step: &Step,
+        time_scale: f64,
+    ) -> Result<StepOutput, String> {
+        let mut attempt = 1;
+        loop {
+            let ctx = StepContext { intent_id, attempt };
+            let reason = match self.attempt_step(executor, step, &ctx, time_scale).await {
+                Ok(output) => return Ok(output),
+                Err(e) => {
+                    if let AttemptError::TimedOut(timeout_ms) = e {
+                        events.push(ExecutionEvent::step_timed_out(step.id, timeout_ms));
+                    }
+                    e.to_string()
+                }
+            };
+            
+            if !step.retryable || attempt > step.max_retries {
+                return Err(reason);
+            }
+            
+            warn!("🔁 Step {} attempt {} failed, retrying: {}", step.name, attempt, reason);
+            events.push(ExecutionEvent::step_retrying(step.id, attempt, reason));
+            sleep(Duration::from_millis(RETRY_BACKOFF_MS * attempt as u64)).await;
+            attempt += 1;
+        }
+    }
+    
+    /// Run one attempt of a step, enforcing its timeout (multiplied by
+    /// `time_scale`) and containing panics.
+    async fn attempt_step(
+        &self,
+        executor: &dyn StepExecutor,
+        step: &Step,
+        ctx: &StepContext,
+        time_scale: f64,
+    ) -> Result<StepOutput, AttemptError> {
+        let attempt = AssertUnwindSafe(executor.execute(step, ctx)).catch_unwind();
+        let result = match step.timeout_ms.map(|ms| (ms as f64 * time_scale).round() as u64) {
+            Some(timeout_ms) => timeout(Duration::from_millis(timeout_ms), attempt)
+                .await
+                .map_err(|_| AttemptError::TimedOut(timeout_ms))?,
+            None => attempt.await,
+        };
+        
+        match result {
+            Ok(output) => output.map_err(AttemptError::Failed),
+            Err(panic) => Err(AttemptError::Failed(format!("executor panicked: {}", panic_message(&panic)))),
+        }
+    }
+    
+    /// Undo completed steps in reverse order after a failure.
+    ///
+    /// Returns true if any compensation action was run. A failed
+    /// compensation is logged and the remaining ones still run.
+    async fn compensate(
+        &self,
+        artifact: &mut ExecutionArtifact,
+        executor: &dyn StepExecutor,
+        intent_id: Uuid,
+        completed: &[Step],
+        time_scale: f64,
+    ) -> bool {
+        let mut compensated = false;
+        
+        for step in completed.iter().rev() {
+            let Some(compensation) = &step.compensate else {
+                continue;
+            };
+            compensated = true;
+            info!("  ↩️ Compensating step {} with {}", step.name, compensation.action);
+            self.record_event(artifact, ExecutionEvent::compensation_started(step.id, &compensation.action)).await;
+            
+            let undo = Step {
+                name: format!("compensate {}", step.name),
+                action: compensation.action.clone(),
+                parameters: compensation.parameters.clone(),
+                compensate: None,
+                ..step.clone()
+            };
+            let ctx = StepContext { intent_id, attempt: 1 };
+            match self.attempt_step(executor, &undo, &ctx, time_scale).await {
+                Ok(output) => {
+                    self.record_event(artifact, ExecutionEvent::compensation_completed(step.id, output.duration_ms)).await;
+                }
+                Err(e) => error!("❌ Compensation {} failed for intent {}: {}", compensation.action, intent_id, e),
+            }
+        }
+        
+        compensated
+    }
+    
+    /// Add an event to an artifact, applying the event data limits.
+    async fn record_event(&self, artifact: &mut ExecutionArtifact, event: ExecutionEvent) {
+        let event_id = event.id;
+        let limits = &self.state.event_data;
+        let Some(original) = artifact.add_event_with_limit(event, limits.max_bytes) else {
+            return;
+        };
+        
+        warn!("✂️ Truncated oversized data on event {}", event_id);
+        if limits.store_blobs {
+            if let Err(e) = self.state.store_event_blob(event_id, original).await {
+                error!("Could not store full data for event {}: {}", event_id, e);
+            }
+        }
+    }
+}
+
+/// Events and result of running one step.
+struct StepRun {
+    events: Vec<ExecutionEvent>,
+    result: Result<(Step, StepOutput), String>,
+}
+
+/// Why a single step attempt did not produce output.
+#[derive(Debug)]
+enum AttemptError {
+    /// The executor reported a failure (or panicked).
+    Failed(String),
+    
+    /// The attempt ran past the step's timeout, in milliseconds.
+    TimedOut(u64),
+}
+
+impl std::fmt::Display for AttemptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptError::Failed(reason) => f.write_str(reason),
+            AttemptError::TimedOut(timeout_ms) => write!(f, "timed out after {}ms", timeout_ms),
+        }
+    }
+}
+
+/// Whether an intent's `DependsOn` constraints let it start.
+enum DependencyGate {
+    /// Every dependency has finished, successfully where required.
+    Ready,
+    
+    /// A dependency is still running.
+    Waiting,
+    
+    /// A dependency that had to succeed did not, for this reason.
+    Broken(String),
+}
+
+/// Check `intent`'s dependencies against the stored `intents`.
+///
+/// Complete and partially complete intents count as succeeded. A
+/// dependency no longer stored can only be waited for if it need not
+/// succeed.
+fn dependency_gate(intent: &Intent, intents: &HashMap<Uuid, IntentRecord>) -> DependencyGate {
+    let mut gate = DependencyGate::Ready;
+    for (id, require_success) in intent.dependencies() {
+        let status = intents.get(&id).map(|record| record.status);
+        match status {
+            Some(status) if !status.is_terminal() => gate = DependencyGate::Waiting,
+            Some(IntentStatus::Complete | IntentStatus::PartiallyComplete) => {}
+            _ if !require_success => {}
+            Some(status) => return DependencyGate::Broken(format!("dependency intent {} is {}", id, status.as_str())),
+            None => return DependencyGate::Broken(format!("dependency intent {} not found", id)),
+        }
+    }
+    gate
+}
+
+/// Best-effort text of a panic payload.
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    panic
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| panic.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic")
 }
@@ -0,0 +1,325 @@
+//! Forwarding intents to peer nodes.
+//!
+//! An intent with a hard `Provider { node_id }` constraint naming a known
+//! peer is submitted to that peer instead of being planned here. The local
+//! node keeps a mirror record whose status follows the remote intent, and
+//! serves its plan and artifact by fetching them from the peer.
+//!
+//! Every forwarded request carries a hop count in
+//! [`FORWARD_HOPS_HEADER`](orpheon_core::FORWARD_HOPS_HEADER); a node refuses
+//! to forward an intent that has already made [`FederationConfig::max_hops`]
+//! hops, which breaks forwarding loops between misconfigured peers.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use orpheon_core::{Constraint, Intent, IntentStatus, OrpheonError};
+use orpheon_sdk::client::IntentResponse;
+use orpheon_sdk::OrpheonClient;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Consecutive failed polls after which a mirror gives up on its peer.
+const MAX_MIRROR_FAILURES: u32 = 10;
+
+/// How to reach a peer node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerConfig {
+    /// Base URL of the peer's API, e.g. `http://10.0.0.2:3000`.
+    pub base_url: String,
+
+    /// API key sent to the peer.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Federation settings for a node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederationConfig {
+    /// This node's ID; `Provider` constraints naming it are planned locally.
+    #[serde(default)]
+    pub node_id: Option<String>,
+
+    /// Known peers, keyed by node ID.
+    #[serde(default)]
+    pub peers: HashMap<String, PeerConfig>,
+
+    /// Most hops an intent may make before forwarding is refused.
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u32,
+
+    /// How often mirrors poll the peer for status, in milliseconds.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+fn default_max_hops() -> u32 {
+    3
+}
+
+fn default_poll_interval_ms() -> u64 {
+    250
+}
+
+impl Default for FederationConfig {
+    fn default() -> Self {
+        Self {
+            node_id: None,
+            peers: HashMap::new(),
+            max_hops: default_max_hops(),
+            poll_interval_ms: default_poll_interval_ms(),
+        }
+    }
+}
+
+/// Errors from forwarding an intent.
+#[derive(Debug, Error)]
+pub enum FederationError {
+    #[error("Forwarding loop detected: intent already forwarded {hops} time(s) (limit {max_hops})")]
+    LoopDetected { hops: u32, max_hops: u32 },
+
+    #[error("Unknown peer node {0}")]
+    UnknownPeer(String),
+
+    #[error("Peer node {node_id} failed: {source}")]
+    Peer {
+        node_id: String,
+        #[source]
+        source: OrpheonError,
+    },
+}
+
+/// Where a mirrored intent actually lives.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardedIntent {
+    /// Peer the intent was forwarded to.
+    pub node_id: String,
+
+    /// The intent's ID on the peer.
+    pub remote_id: Uuid,
+}
+
+/// The node's peer registry.
+#[derive(Debug, Default)]
+pub struct Federation {
+    config: FederationConfig,
+    /// Peers from the config plus any added at runtime.
+    peers: RwLock<HashMap<String, PeerConfig>>,
+}
+
+impl Federation {
+    /// Create a registry from config.
+    pub fn new(config: FederationConfig) -> Self {
+        let peers = RwLock::new(config.peers.clone());
+        Self { config, peers }
+    }
+
+    /// Federation settings.
+    pub fn config(&self) -> &FederationConfig {
+        &self.config
+    }
+
+    /// Register a peer, replacing any existing entry for the node ID.
+    pub fn add_peer(&self, node_id: impl Into<String>, peer: PeerConfig) {
+        self.peers.write().unwrap().insert(node_id.into(), peer);
+    }
+
+    /// The peer an intent must run on, if its hard `Provider` constraint
+    /// names a known peer other than this node.
+    pub fn target_peer(&self, intent: &Intent) -> Option<String> {
+        let peers = self.peers.read().unwrap();
+        intent.constraints.iter().find_map(|c| match c {
+            Constraint::Provider { node_id }
+                if self.config.node_id.as_deref() != Some(node_id.as_str()) && peers.contains_key(node_id) =>
+            {
+                Some(node_id.clone())
+            }
+            _ => None,
+        })
+    }
+
+    /// The first peer, by node ID, advertising every one of `requirements`.
+    ///
+    /// Peers that cannot be reached are skipped.
+    pub async fn capable_peer(&self, requirements: &[String]) -> Option<String> {
+        let mut node_ids: Vec<String> = self.peers.read().unwrap().keys().cloned().collect();
+        node_ids.sort();
+        for node_id in node_ids {
+            if self.config.node_id.as_deref() == Some(node_id.as_str()) {
+                continue;
+            }
+            let Ok(client) = self.client(&node_id, 0).await else {
+                continue;
+            };
+            match client.capabilities().await {
+                Ok(advertised) if requirements.iter().all(|r| advertised.capabilities.contains(r)) => {
+                    return Some(node_id)
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to fetch capabilities of node {}: {}", node_id, e),
+            }
+        }
+        None
+    }
+
+    /// A client for a peer, marking requests with `hops` forwarding hops.
+    pub async fn client(&self, node_id: &str, hops: u32) -> Result<OrpheonClient, FederationError> {
+        let peer = self
+            .peers
+            .read()
+            .unwrap()
+            .get(node_id)
+            .cloned()
+            .ok_or_else(|| FederationError::UnknownPeer(node_id.to_string()))?;
+        let peer_error = |source| FederationError::Peer { node_id: node_id.to_string(), source };
+
+        let mut client = OrpheonClient::connect(&peer.base_url).await.map_err(peer_error)?;
+        if let Some(key) = &peer.api_key {
+            client = client.with_api_key(key).map_err(peer_error)?;
+        }
+        client.with_forward_hops(hops).map_err(peer_error)
+    }
+
+    /// Submit an intent to a peer. `hops` is how many times the intent has
+    /// already been forwarded.
+    pub async fn forward(&self, node_id: &str, intent: Intent, hops: u32) -> Result<ForwardedIntent, FederationError> {
+        if hops >= self.config.max_hops {
+            return Err(FederationError::LoopDetected { hops, max_hops: self.config.max_hops });
+        }
+        let client = self.client(node_id, hops + 1).await?;
+        let remote_id = client.submit_detached(intent).await.map_err(|source| FederationError::Peer {
+            node_id: node_id.to_string(),
+            source,
+        })?;
+        info!("📡 Forwarded intent to node {} as {}", node_id, remote_id);
+        Ok(ForwardedIntent { node_id: node_id.to_string(), remote_id })
+    }
+}
+
+/// Parse a status name as reported by the intent API.
+pub fn parse_status(status: &str) -> Option<IntentStatus> {
+    serde_json::from_value(serde_json::Value::String(status.to_string())).ok()
+}
+
+/// Keep a mirror record in step with its remote intent until either ends.
+pub fn spawn_mirror(state: AppState, local_id: Uuid, forwarded: ForwardedIntent) {
+    tokio::spawn(async move {
+        let federation = state.federation.clone();
+        let interval = Duration::from_millis(federation.config().poll_interval_ms);
+        let mut failures = 0;
+
+        loop {
+            sleep(interval).await;
+            match state.get_intent(local_id).await {
+                Some(record) if !record.status.is_terminal() => {}
+                _ => break,
+            }
+
+            let remote = match federation.client(&forwarded.node_id, 0).await {
+                Ok(client) => client.get_intent(forwarded.remote_id).await.map_err(|source| {
+                    FederationError::Peer { node_id: forwarded.node_id.clone(), source }
+                }),
+                Err(e) => Err(e),
+            };
+            match remote {
+                Ok(remote) => {
+                    failures = 0;
+                    if mirror_status(&state, local_id, &remote).await {
+                        break;
+                    }
+                }
+                Err(FederationError::Peer { source: OrpheonError::NotFound { .. }, .. }) => {
+                    state.fail_intent(local_id, "forwarded intent disappeared from the peer").await;
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    warn!("Failed to poll forwarded intent {}: {}", local_id, e);
+                    if failures >= MAX_MIRROR_FAILURES {
+                        state.fail_intent(local_id, &format!("lost contact with peer: {}", e)).await;
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Copy a remote intent's status onto its mirror; returns whether it is terminal.
+async fn mirror_status(state: &AppState, local_id: Uuid, remote: &IntentResponse) -> bool {
+    let Some(status) = parse_status(&remote.status) else {
+        warn!("Peer reported unknown status {:?} for intent {}", remote.status, local_id);
+        return false;
+    };
+    match status {
+        IntentStatus::Failed => {
+            let error = remote.error.as_deref().unwrap_or("failed on peer");
+            state.fail_intent(local_id, error).await;
+        }
+        IntentStatus::Complete | IntentStatus::PartiallyComplete => {
+            state.set_artifact_id(local_id, remote.artifact_id).await;
+            state.set_success_rate(local_id, remote.success_rate).await;
+            state.update_intent_status(local_id, status).await;
+        }
+        _ => {
+            state.update_intent_status(local_id, status).await;
+        }
+    }
+    status.is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn federation() -> Federation {
+        Federation::new(FederationConfig {
+            node_id: Some("node-a".to_string()),
+            peers: [(
+                "node-b".to_string(),
+                PeerConfig { base_url: "http://127.0.0.1:1".to_string(), api_key: None },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        })
+    }
+
+    fn provider_intent(node_id: &str) -> Intent {
+        Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::Provider { node_id: node_id.to_string() })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_only_known_peers_are_targets() {
+        let federation = federation();
+        assert_eq!(federation.target_peer(&provider_intent("node-b")).as_deref(), Some("node-b"));
+        assert_eq!(federation.target_peer(&provider_intent("node-a")), None);
+        assert_eq!(federation.target_peer(&provider_intent("node-z")), None);
+
+        federation.add_peer("node-z", PeerConfig { base_url: "http://127.0.0.1:2".to_string(), api_key: None });
+        assert_eq!(federation.target_peer(&provider_intent("node-z")).as_deref(), Some("node-z"));
+        assert_eq!(federation.target_peer(&Intent::builder().kind("deploy").build().unwrap()), None);
+    }
+
+    #[tokio::test]
+    async fn test_hop_limit_stops_forwarding() {
+        let err = federation().forward("node-b", provider_intent("node-b"), 3).await.unwrap_err();
+        assert!(matches!(err, FederationError::LoopDetected { hops: 3, max_hops: 3 }));
+    }
+
+    #[test]
+    fn test_parse_status_names() {
+        assert_eq!(parse_status("complete"), Some(IntentStatus::Complete));
+        assert_eq!(parse_status("negotiating"), Some(IntentStatus::Negotiating));
+        assert_eq!(parse_status("bogus"), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! Garbage collection of finished intents.
+//!
+//! When [`GcConfig::retention_ms`] is set, the [`GarbageCollector`] sweeps
+//! every [`GcConfig::interval_ms`] and evicts intents that finished longer
+//! ago than the retention period, together with their plans and artifacts.
+//!
+//! Before an intent is evicted its artifacts are handed to the node's
+//! [`ArtifactSink`]. Eviction only goes ahead once the sink has persisted
+//! all of them; if it fails the intent is kept, counted under
+//! [`stats::ARTIFACT_EXPORT_FAILURES`], and retried on the next sweep. A
+//! retried intent may export an artifact the sink already accepted, so
+//! sinks should tolerate duplicates.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use orpheon_core::{ExecutionArtifact, OrpheonError, Result};
+use tokio::io::AsyncWriteExt;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::state::AppState;
+use crate::stats;
+
+/// Where artifacts go before their intent is evicted.
+#[async_trait]
+pub trait ArtifactSink: Send + Sync {
+    /// Durably store `artifact`; eviction waits for this to succeed.
+    async fn persist(&self, artifact: &ExecutionArtifact) -> Result<()>;
+}
+
+/// Sink that keeps nothing; evicted artifacts are gone.
+#[derive(Debug, Clone, Default)]
+pub struct NoopSink;
+
+#[async_trait]
+impl ArtifactSink for NoopSink {
+    async fn persist(&self, _artifact: &ExecutionArtifact) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink appending artifacts as JSON lines to one file per UTC day,
+/// `artifacts-YYYY-MM-DD.jsonl`, under a directory.
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    dir: PathBuf,
+}
+
+impl FileSink {
+    /// Export under `dir`, creating it when needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// File artifacts exported today are appended to.
+    pub fn today_path(&self) -> PathBuf {
+        self.dir.join(format!("artifacts-{}.jsonl", Utc::now().format("%Y-%m-%d")))
+    }
+}
+
+#[async_trait]
+impl ArtifactSink for FileSink {
+    async fn persist(&self, artifact: &ExecutionArtifact) -> Result<()> {
+        let io_error = |e: std::io::Error| OrpheonError::Internal(format!("artifact export to {} failed: {}", self.dir.display(), e));
+
+        let mut line = serde_json::to_string(artifact).map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+        line.push('\n');
+
+        tokio::fs::create_dir_all(&self.dir).await.map_err(io_error)?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.today_path())
+            .await
+            .map_err(io_error)?;
+        file.write_all(line.as_bytes()).await.map_err(io_error)?;
+        file.sync_data().await.map_err(io_error)
+    }
+}
+
+/// Garbage collection settings for a node.
+#[derive(Debug, Clone)]
+pub struct GcConfig {
+    /// Time between sweeps, in milliseconds.
+    pub interval_ms: u64,
+
+    /// How long finished intents are kept, in milliseconds; never evicted
+    /// when unset.
+    pub retention_ms: Option<u64>,
+
+    /// Directory artifacts are exported to before eviction; see
+    /// [`FileSink`]. Evicted artifacts are dropped when unset.
+    pub export_dir: Option<PathBuf>,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 60_000,
+            retention_ms: None,
+            export_dir: None,
+        }
+    }
+}
+
+impl GcConfig {
+    /// The sink these settings describe.
+    pub fn sink(&self) -> Arc<dyn ArtifactSink> {
+        match &self.export_dir {
+            Some(dir) => Arc::new(FileSink::new(dir)),
+            None => Arc::new(NoopSink),
+        }
+    }
+}
+
+/// Outcome of one sweep.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepReport {
+    /// Intents evicted.
+    pub evicted: Vec<Uuid>,
+
+    /// Intents due for eviction but kept because their artifacts could not
+    /// be exported.
+    pub retained: Vec<Uuid>,
+}
+
+/// Evicts intents that finished longer ago than the retention period.
+pub struct GarbageCollector {
+    state: AppState,
+}
+
+impl GarbageCollector {
+    /// Create a collector over a node's intents.
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Sweep every configured interval, forever; returns at once when no
+    /// retention period is set.
+    pub async fn run(self: Arc<Self>) {
+        let Some(retention_ms) = self.state.gc.retention_ms else {
+            return;
+        };
+        info!("🧹 Garbage collector started, keeping finished intents for {} ms", retention_ms);
+
+        loop {
+            sleep(Duration::from_millis(self.state.gc.interval_ms)).await;
+            self.sweep().await;
+        }
+    }
+
+    /// Export and evict the intents due for eviction once.
+    pub async fn sweep(&self) -> SweepReport {
+        let mut report = SweepReport::default();
+        let Some(retention_ms) = self.state.gc.retention_ms else {
+            return report;
+        };
+        let cutoff = Utc::now() - chrono::Duration::milliseconds(retention_ms as i64);
+
+        for id in self.state.intents_finished_before(cutoff).await {
+            if self.export(id).await {
+                self.state.evict_intent(id).await;
+                report.evicted.push(id);
+            } else {
+                report.retained.push(id);
+            }
+        }
+        if !report.evicted.is_empty() {
+            info!("🧹 Evicted {} finished intent(s)", report.evicted.len());
+        }
+        report
+    }
+
+    /// Hand an intent's artifacts to the sink, returning whether all of
+    /// them were persisted.
+    async fn export(&self, intent_id: Uuid) -> bool {
+        let mut artifacts: Vec<ExecutionArtifact> = self.state.get_artifact_for_intent(intent_id).await.into_iter().collect();
+        artifacts.extend(self.state.orphaned_artifacts(intent_id).await);
+
+        for artifact in &artifacts {
+            if let Err(e) = self.state.artifact_sink.persist(artifact).await {
+                warn!("🧹 Keeping intent {}: exporting artifact {} failed: {}", intent_id, artifact.id, e);
+                self.state.stats.count(stats::ARTIFACT_EXPORT_FAILURES).await;
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::{Intent, IntentStatus, Outcome, Plan, PlanningStrategy};
+
+    /// Sink that refuses everything.
+    struct FailingSink;
+
+    #[async_trait]
+    impl ArtifactSink for FailingSink {
+        async fn persist(&self, _artifact: &ExecutionArtifact) -> Result<()> {
+            Err(OrpheonError::Internal("sink unavailable".to_string()))
+        }
+    }
+
+    /// A node keeping finished intents for no time at all.
+    fn state(sink: Arc<dyn ArtifactSink>) -> AppState {
+        let mut state = AppState::new();
+        state.gc.retention_ms = Some(0);
+        state.artifact_sink = sink;
+        state
+    }
+
+    /// Store an intent that completed with an artifact.
+    async fn completed(state: &AppState) -> (Uuid, Uuid) {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        let plan = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        assert!(state.update_intent_status(intent_id, IntentStatus::Executing).await);
+        let artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        let artifact_id = artifact.id;
+        assert!(state.store_artifact(artifact).await);
+        assert_eq!(state.get_intent(intent_id).await.unwrap().status, IntentStatus::Complete);
+        (intent_id, artifact_id)
+    }
+
+    #[tokio::test]
+    async fn test_artifacts_are_exported_before_eviction() {
+        let dir = std::env::temp_dir().join(format!("orpheon-gc-{}", Uuid::new_v4()));
+        let sink = FileSink::new(&dir);
+        let state = state(Arc::new(sink.clone()));
+        let (intent_id, artifact_id) = completed(&state).await;
+        let running = Intent::builder().kind("deploy").build().unwrap();
+        let running_id = running.id;
+        state.store_intent(running).await;
+
+        let report = GarbageCollector::new(state.clone()).sweep().await;
+        assert_eq!(report.evicted, vec![intent_id]);
+        assert!(report.retained.is_empty());
+        assert!(state.get_intent(intent_id).await.is_none());
+        assert!(state.get_artifact(artifact_id).await.is_none());
+        assert!(state.get_plan_for_intent(intent_id).await.is_none());
+        // Unfinished intents are never collected
+        assert!(state.get_intent(running_id).await.is_some());
+
+        let exported = std::fs::read_to_string(sink.today_path()).unwrap();
+        let lines: Vec<&str> = exported.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let artifact: ExecutionArtifact = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!((artifact.id, artifact.intent.id), (artifact_id, intent_id));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_export_retains_intent_until_it_succeeds() {
+        let mut state = state(Arc::new(FailingSink));
+        let (intent_id, artifact_id) = completed(&state).await;
+
+        let report = GarbageCollector::new(state.clone()).sweep().await;
+        assert_eq!(report.retained, vec![intent_id]);
+        assert!(report.evicted.is_empty());
+        assert!(state.get_intent(intent_id).await.is_some());
+        assert!(state.get_artifact(artifact_id).await.is_some());
+
+        let today = Utc::now().date_naive();
+        let days = state.stats.days(today, today).await.unwrap();
+        assert_eq!(days[0].metrics[stats::ARTIFACT_EXPORT_FAILURES], 1.0);
+
+        // Retried on the next sweep once the sink works again
+        state.artifact_sink = Arc::new(NoopSink);
+        let report = GarbageCollector::new(state.clone()).sweep().await;
+        assert_eq!(report.evicted, vec![intent_id]);
+        assert!(state.get_artifact(artifact_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retention_period_is_respected() {
+        let mut state = state(Arc::new(NoopSink));
+        state.gc.retention_ms = Some(60_000);
+        let (intent_id, _) = completed(&state).await;
+
+        let report = GarbageCollector::new(state.clone()).sweep().await;
+        assert_eq!(report, SweepReport::default());
+        assert!(state.get_intent(intent_id).await.is_some());
+    }
+}
@@ -0,0 +1,483 @@
+//! Append-only journal of node operations.
+//!
+//! Every entry carries the hash of the entry before it, so rewriting,
+//! dropping or reordering entries breaks the chain and is reported by
+//! [`Journal::verify_chain`]. The node keeps the most recent entries in
+//! memory; with [`JournalConfig::path`] set, every entry is also appended to
+//! a JSON-lines file that survives restarts.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use orpheon_core::crypto::hex_encode;
+use orpheon_core::IntentStatus;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// `prev_hash` of the first entry in a journal.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Entries kept in memory by default.
+pub const DEFAULT_JOURNAL_CAPACITY: usize = 10_000;
+
+/// Journal settings for a node.
+#[derive(Debug, Clone)]
+pub struct JournalConfig {
+    /// Number of recent entries kept in memory.
+    pub capacity: usize,
+
+    /// File the journal is appended to; in-memory only when unset.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_JOURNAL_CAPACITY,
+            path: None,
+        }
+    }
+}
+
+impl JournalConfig {
+    /// Open the journal these settings describe.
+    pub fn open(&self) -> Result<Journal, JournalError> {
+        match &self.path {
+            Some(path) => Journal::open(path, self.capacity),
+            None => Ok(Journal::in_memory(self.capacity)),
+        }
+    }
+}
+
+/// Errors from reading or writing a journal file.
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("Journal I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed journal entry on line {line}: {message}")]
+    Malformed { line: usize, message: String },
+}
+
+/// Something the node did.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// An intent was accepted for processing.
+    IntentSubmitted { intent_id: Uuid, kind: String },
+
+    /// An intent moved between statuses.
+    StatusChanged {
+        intent_id: Uuid,
+        from: IntentStatus,
+        to: IntentStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+
+    /// A client approved a negotiated proposal.
+    ProposalAccepted { intent_id: Uuid, proposal_id: Uuid },
+
+    /// A client cancelled an intent.
+    IntentCancelled { intent_id: Uuid },
+
+    /// A client replaced an intent's spec before it was planned.
+    IntentAmended { intent_id: Uuid },
+
+    /// Node configuration changed at runtime.
+    ConfigChanged { description: String },
+
+    /// An intent has waited in one status longer than its threshold; see
+    /// [`crate::backlog`].
+    BacklogAlert { intent_id: Uuid, status: IntentStatus, age_ms: u64 },
+
+    /// A stored record was upgraded to a newer schema; see
+    /// [`crate::migrations`].
+    RecordMigrated { key: String, from: u32, to: u32 },
+
+    /// A request was turned away by a quota.
+    QuotaRejected {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        intent_id: Option<Uuid>,
+        reason: String,
+    },
+}
+
+/// One link in the journal chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Position in the journal, starting at 1.
+    pub seq: u64,
+
+    /// When the entry was appended.
+    pub timestamp: DateTime<Utc>,
+
+    /// What happened.
+    pub event: JournalEvent,
+
+    /// Hash of the previous entry, or [`GENESIS_HASH`] for the first.
+    pub prev_hash: String,
+
+    /// SHA-256 over the other fields.
+    pub hash: String,
+}
+
+/// The hashed fields of an entry.
+#[derive(Serialize)]
+struct HashedFields<'a> {
+    seq: u64,
+    timestamp: &'a DateTime<Utc>,
+    event: &'a JournalEvent,
+    prev_hash: &'a str,
+}
+
+impl JournalEntry {
+    fn new(seq: u64, event: JournalEvent, prev_hash: String) -> Self {
+        let mut entry = Self {
+            seq,
+            timestamp: Utc::now(),
+            event,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        entry
+    }
+
+    /// Hash of this entry's contents, ignoring the stored `hash`.
+    pub fn compute_hash(&self) -> String {
+        let fields = HashedFields {
+            seq: self.seq,
+            timestamp: &self.timestamp,
+            event: &self.event,
+            prev_hash: &self.prev_hash,
+        };
+        let json = serde_json::to_vec(&fields).expect("journal entries always serialize");
+        hex_encode(Sha256::digest(json))
+    }
+}
+
+/// How the chain is broken.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BreakKind {
+    /// The entry's contents do not match its hash.
+    #[error("entry hash does not match its contents")]
+    HashMismatch,
+
+    /// The entry does not point at the entry before it.
+    #[error("prev_hash does not match the previous entry")]
+    PrevHashMismatch,
+
+    /// Entries are missing before this one.
+    #[error("expected seq {expected}")]
+    Gap { expected: u64 },
+
+    /// The journal file could not be read.
+    #[error("{0}")]
+    Unreadable(String),
+}
+
+/// The first place a journal chain fails to verify.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Journal chain broken at seq {seq}: {kind}")]
+pub struct ChainBreak {
+    /// Seq of the offending entry; 0 when the journal file is unreadable.
+    pub seq: u64,
+
+    pub kind: BreakKind,
+}
+
+/// Check that `entries` form an unbroken chain.
+///
+/// A chain starting at seq 1 must start from [`GENESIS_HASH`]; a chain that
+/// starts later (the oldest entries were evicted) is trusted up to its first
+/// entry's `prev_hash`.
+pub fn verify_entries<'a>(entries: impl IntoIterator<Item = &'a JournalEntry>) -> Result<(), ChainBreak> {
+    let mut previous: Option<&JournalEntry> = None;
+    for entry in entries {
+        if entry.compute_hash() != entry.hash {
+            return Err(ChainBreak { seq: entry.seq, kind: BreakKind::HashMismatch });
+        }
+        let (expected_seq, expected_prev) = match previous {
+            Some(prev) => (prev.seq + 1, Some(prev.hash.as_str())),
+            None if entry.seq == 1 => (1, Some(GENESIS_HASH)),
+            None => (entry.seq, None),
+        };
+        if entry.seq != expected_seq {
+            return Err(ChainBreak { seq: entry.seq, kind: BreakKind::Gap { expected: expected_seq } });
+        }
+        if expected_prev.is_some_and(|hash| hash != entry.prev_hash) {
+            return Err(ChainBreak { seq: entry.seq, kind: BreakKind::PrevHashMismatch });
+        }
+        previous = Some(entry);
+    }
+    Ok(())
+}
+
+/// Read every entry from a journal file.
+pub fn read_file(path: &Path) -> Result<Vec<JournalEntry>, JournalError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| JournalError::Malformed {
+            line: idx + 1,
+            message: e.to_string(),
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+struct Inner {
+    /// Most recent entries, oldest first.
+    recent: VecDeque<JournalEntry>,
+    next_seq: u64,
+    last_hash: String,
+    file: Option<File>,
+}
+
+/// Hash-chained log of node operations.
+pub struct Journal {
+    inner: Mutex<Inner>,
+    capacity: usize,
+    path: Option<PathBuf>,
+}
+
+impl Journal {
+    /// A journal kept only in memory, holding at most `capacity` entries.
+    pub fn in_memory(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                recent: VecDeque::new(),
+                next_seq: 1,
+                last_hash: GENESIS_HASH.to_string(),
+                file: None,
+            }),
+            capacity: capacity.max(1),
+            path: None,
+        }
+    }
+
+    /// Open a file-backed journal, continuing the chain already in the file.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize) -> Result<Self, JournalError> {
+        let path = path.into();
+        let existing = if path.exists() { read_file(&path)? } else { Vec::new() };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let journal = Self::in_memory(capacity);
+        {
+            let mut inner = journal.inner.lock().unwrap();
+            if let Some(last) = existing.last() {
+                inner.next_seq = last.seq + 1;
+                inner.last_hash = last.hash.clone();
+            }
+            let skip = existing.len().saturating_sub(journal.capacity);
+            inner.recent = existing.into_iter().skip(skip).collect();
+            inner.file = Some(file);
+        }
+        Ok(Self { path: Some(path), ..journal })
+    }
+
+    /// File the journal is appended to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Append an event, chaining it to the previous entry.
+    ///
+    /// A failed file write is logged; the entry is still kept in memory.
+    pub fn append(&self, event: JournalEvent) -> JournalEntry {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = JournalEntry::new(inner.next_seq, event, inner.last_hash.clone());
+
+        if let Some(file) = inner.file.as_mut() {
+            let line = serde_json::to_string(&entry).expect("journal entries always serialize");
+            if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+                tracing::error!("Failed to append journal entry {}: {}", entry.seq, e);
+            }
+        }
+
+        inner.next_seq += 1;
+        inner.last_hash = entry.hash.clone();
+        if inner.recent.len() == self.capacity {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back(entry.clone());
+        entry
+    }
+
+    /// Up to `limit` entries with a seq greater than `since_seq`, oldest first.
+    pub fn since(&self, since_seq: u64, limit: usize) -> Vec<JournalEntry> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .recent
+            .iter()
+            .filter(|e| e.seq > since_seq)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Seq of the newest entry, or 0 if the journal is empty.
+    pub fn last_seq(&self) -> u64 {
+        self.inner.lock().unwrap().next_seq - 1
+    }
+
+    /// Check the chain for tampering or missing entries.
+    ///
+    /// A file-backed journal verifies the whole file; otherwise the entries
+    /// still held in memory are checked.
+    pub fn verify_chain(&self) -> Result<(), ChainBreak> {
+        let inner = self.inner.lock().unwrap();
+        let entries = match &self.path {
+            Some(path) => match read_file(path) {
+                Ok(entries) => entries,
+                Err(e) => return Err(ChainBreak { seq: 0, kind: BreakKind::Unreadable(e.to_string()) }),
+            },
+            None => inner.recent.iter().cloned().collect(),
+        };
+        verify_entries(&entries)?;
+
+        // Entries cut off the end leave the chain short of what was appended
+        let last_seq = entries.last().map_or(0, |e| e.seq);
+        if last_seq + 1 != inner.next_seq {
+            return Err(ChainBreak { seq: inner.next_seq - 1, kind: BreakKind::Gap { expected: inner.next_seq - 1 } });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Self::in_memory(DEFAULT_JOURNAL_CAPACITY)
+    }
+}
+
+impl std::fmt::Debug for Journal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Journal")
+            .field("capacity", &self.capacity)
+            .field("path", &self.path)
+            .field("last_seq", &self.last_seq())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripted(journal: &Journal) -> Uuid {
+        let intent_id = Uuid::new_v4();
+        journal.append(JournalEvent::IntentSubmitted { intent_id, kind: "deploy".to_string() });
+        journal.append(JournalEvent::StatusChanged {
+            intent_id,
+            from: IntentStatus::Received,
+            to: IntentStatus::Planning,
+            reason: None,
+        });
+        journal.append(JournalEvent::ProposalAccepted { intent_id, proposal_id: Uuid::new_v4() });
+        journal.append(JournalEvent::ConfigChanged { description: "fault rule added".to_string() });
+        journal.append(JournalEvent::IntentCancelled { intent_id });
+        intent_id
+    }
+
+    fn scratch_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("orpheon-journal-{}-{}.jsonl", name, Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_scripted_sequence_verifies() {
+        let journal = Journal::in_memory(100);
+        scripted(&journal);
+
+        let entries = journal.since(0, 100);
+        assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[3].prev_hash, entries[2].hash);
+        assert_eq!(journal.verify_chain(), Ok(()));
+
+        assert_eq!(journal.since(3, 1).iter().map(|e| e.seq).collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_corrupted_entry_is_located() {
+        let journal = Journal::in_memory(100);
+        scripted(&journal);
+
+        journal.inner.lock().unwrap().recent[2].event = JournalEvent::IntentCancelled { intent_id: Uuid::new_v4() };
+        assert_eq!(journal.verify_chain(), Err(ChainBreak { seq: 3, kind: BreakKind::HashMismatch }));
+    }
+
+    #[test]
+    fn test_rehashed_entry_breaks_the_next_link() {
+        let mut entries = {
+            let journal = Journal::in_memory(100);
+            scripted(&journal);
+            journal.since(0, 100)
+        };
+
+        // Rewriting an entry and its hash still leaves the next entry pointing at the old hash
+        entries[1].event = JournalEvent::ConfigChanged { description: "forged".to_string() };
+        entries[1].hash = entries[1].compute_hash();
+        assert_eq!(verify_entries(&entries), Err(ChainBreak { seq: 3, kind: BreakKind::PrevHashMismatch }));
+    }
+
+    #[test]
+    fn test_missing_entry_is_a_gap() {
+        let journal = Journal::in_memory(100);
+        scripted(&journal);
+        let mut entries = journal.since(0, 100);
+        entries.remove(3);
+        assert_eq!(verify_entries(&entries), Err(ChainBreak { seq: 5, kind: BreakKind::Gap { expected: 4 } }));
+
+        // Dropping the newest entry is caught against the journal's head
+        journal.inner.lock().unwrap().recent.pop_back();
+        assert_eq!(journal.verify_chain(), Err(ChainBreak { seq: 5, kind: BreakKind::Gap { expected: 5 } }));
+    }
+
+    #[test]
+    fn test_ring_evicts_oldest_and_still_verifies() {
+        let journal = Journal::in_memory(3);
+        scripted(&journal);
+
+        let entries = journal.since(0, 100);
+        assert_eq!(entries.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(journal.verify_chain(), Ok(()));
+    }
+
+    #[test]
+    fn test_file_journal_survives_reopen_and_detects_edits() {
+        let path = scratch_file("reopen");
+        {
+            let journal = Journal::open(&path, 100).unwrap();
+            scripted(&journal);
+        }
+
+        let journal = Journal::open(&path, 100).unwrap();
+        assert_eq!(journal.last_seq(), 5);
+        let sixth = journal.append(JournalEvent::ConfigChanged { description: "restart".to_string() });
+        assert_eq!(sixth.seq, 6);
+        assert_eq!(sixth.prev_hash, journal.since(4, 1)[0].hash);
+        assert_eq!(journal.verify_chain(), Ok(()));
+
+        // Edit the second line in place
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let tampered = contents.replacen("\"to\":\"planning\"", "\"to\":\"complete\"", 1);
+        assert_ne!(contents, tampered);
+        std::fs::write(&path, tampered).unwrap();
+        assert_eq!(journal.verify_chain(), Err(ChainBreak { seq: 2, kind: BreakKind::HashMismatch }));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+//! Registry of intent kinds known to the node.
+//!
+//! A renamed kind keeps its old names as [aliases](KindAlias). Intents
+//! submitted under an alias are stored under the current name, with the
+//! alias kept in metadata as [`ORIGINAL_KIND_KEY`], so planning and metrics
+//! only ever see the current name; the submitter is warned until the
+//! alias's sunset, and refused after it.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Definition of an intent kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KindDefinition {
+    /// Kind name, as used in `Intent::kind`.
+    pub name: String,
+    
+    /// Human-readable description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Free-form metadata.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub metadata: serde_json::Value,
+    
+    /// Former names of this kind still accepted on submission.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<KindAlias>,
+}
+
+impl KindDefinition {
+    /// Create a kind definition with just a name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            metadata: serde_json::Value::Null,
+            aliases: Vec::new(),
+        }
+    }
+    
+    /// Also accept intents submitted as `alias`, until `sunset` if set.
+    pub fn with_alias(mut self, alias: impl Into<String>, sunset: Option<DateTime<Utc>>) -> Self {
+        self.aliases.push(KindAlias { name: alias.into(), sunset });
+        self
+    }
+}
+
+/// Metadata key under which the kind an intent was submitted as is kept,
+/// when that was an alias.
+pub const ORIGINAL_KIND_KEY: &str = "original_kind";
+
+/// A former name of a kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KindAlias {
+    /// The old kind name.
+    pub name: String,
+    
+    /// When intents stop being accepted under this name; never if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<DateTime<Utc>>,
+}
+
+/// An intent kind given by an alias, and the kind it now names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasedKind {
+    pub alias: KindAlias,
+    
+    /// The current name of the kind.
+    pub kind: String,
+}
+
+impl AliasedKind {
+    /// The warning for an intent submitted under the alias at `now`, or an
+    /// error naming the replacement once the alias is past its sunset.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<String, String> {
+        match self.alias.sunset {
+            Some(sunset) if now >= sunset => Err(format!(
+                "Kind '{}' was retired on {}; use '{}' instead",
+                self.alias.name,
+                sunset.to_rfc3339_opts(SecondsFormat::Secs, true),
+                self.kind
+            )),
+            Some(sunset) => Ok(format!(
+                "Kind '{}' is deprecated and will be rejected from {}; use '{}' instead",
+                self.alias.name,
+                sunset.to_rfc3339_opts(SecondsFormat::Secs, true),
+                self.kind
+            )),
+            None => Ok(format!("Kind '{}' is deprecated; use '{}' instead", self.alias.name, self.kind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_alias_is_refused_after_its_sunset() {
+        let sunset = "2030-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let aliased = AliasedKind {
+            alias: KindAlias { name: "provision_gpu_cluster".to_string(), sunset: Some(sunset) },
+            kind: "gpu.cluster.provision".to_string(),
+        };
+
+        let warning = aliased.check(sunset - Duration::days(1)).unwrap();
+        assert_eq!(
+            warning,
+            "Kind 'provision_gpu_cluster' is deprecated and will be rejected from 2030-01-01T00:00:00Z; use 'gpu.cluster.provision' instead"
+        );
+        let error = aliased.check(sunset).unwrap_err();
+        assert!(error.contains("retired") && error.contains("'gpu.cluster.provision'"), "{}", error);
+
+        let forever = AliasedKind { alias: KindAlias { sunset: None, ..aliased.alias.clone() }, ..aliased };
+        assert!(forever.check(sunset + Duration::days(365)).is_ok());
+    }
+}
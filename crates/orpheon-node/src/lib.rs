@@ -0,0 +1,247 @@
+//! # Orpheon Node
+//!
+//! Orpheon node library: API server, execution engine and shared state.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    middleware,
+    routing::{get, post, put, delete},
+    Router,
+};
+use orpheon_negotiate::Pricing;
+use orpheon_state::StateStore;
+use tokio::net::TcpListener;
+use tower_http::compression::CompressionLayer;
+use tower_http::trace::TraceLayer;
+use tracing::{info, warn};
+
+pub mod api;
+pub mod backlog;
+pub mod chaos;
+pub mod cli;
+pub mod config;
+pub mod constraint_index;
+pub mod decompose;
+pub mod engine;
+pub mod federation;
+pub mod gc;
+pub mod journal;
+pub mod kinds;
+pub mod lineage;
+pub mod migrations;
+pub mod negotiation;
+pub mod queue;
+pub mod requote;
+pub mod response_cache;
+pub mod security;
+pub mod seed;
+pub mod state;
+pub mod stats;
+pub mod testing;
+pub mod timeouts;
+pub mod watchdog;
+
+pub use config::NodeConfig;
+
+use backlog::BacklogMonitor;
+use engine::Engine;
+use federation::Federation;
+use gc::GarbageCollector;
+use state::AppState;
+use watchdog::DeadlineWatchdog;
+
+/// Run the Orpheon node server.
+pub async fn run_server(config: NodeConfig) -> anyhow::Result<()> {
+    info!("🚀 Orpheon Node starting...");
+
+    // Create shared application state
+    let state = build_state(&config).await?;
+
+    // Create the engine
+    let engine = Arc::new(Engine::new(state.clone()));
+
+    // Start the engine background task
+    let engine_clone = engine.clone();
+    tokio::spawn(async move {
+        engine_clone.run().await;
+    });
+
+    // Start the deadline watchdog
+    let watchdog = Arc::new(DeadlineWatchdog::new(state.clone()));
+    tokio::spawn(async move {
+        watchdog.run().await;
+    });
+
+    // Start the backlog monitor
+    let backlog = Arc::new(BacklogMonitor::new(state.clone()));
+    tokio::spawn(async move {
+        backlog.run().await;
+    });
+
+    // Start the garbage collector
+    let gc = Arc::new(GarbageCollector::new(state.clone()));
+    tokio::spawn(async move {
+        gc.run().await;
+    });
+
+    // Build the router
+    let app = create_router(state.clone());
+
+    info!("🌐 Listening on http://{}", config.bind_addr);
+
+    // Start the server; on Ctrl-C, open streams are closed before it stops
+    let listener = TcpListener::bind(config.bind_addr).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("👋 Shutting down");
+            state.begin_shutdown();
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Build node state from a config, seeding it if requested.
+pub async fn build_state(config: &NodeConfig) -> anyhow::Result<AppState> {
+    let mut state = match &config.seed_path {
+        Some(dir) => {
+            info!("🌱 Loading seed data from {}", dir.display());
+            seed::SeedData::load(dir)?.into_state().await?
+        }
+        None => AppState::new(),
+    };
+    if let Some(path) = config.store_path.as_deref().filter(|path| path.exists()) {
+        info!("💾 Loading state from {}", path.display());
+        for (key, value) in cli::read_store(path)? {
+            state.state_store.set(&key, value).await?;
+        }
+    }
+    state.event_data = config.event_data.clone();
+    state.state_stream = config.state_stream.clone();
+    state.dry_run = config.dry_run.clone();
+    state.intent_limits = config.intent_limits.clone();
+    state.deadline_watchdog = config.deadline_watchdog.clone();
+    state.backlog = config.backlog.clone();
+    state.queue = config.queue.clone();
+    state.pause = config.pause.clone();
+    state.success_weighting = config.success_weighting;
+    state.request_timeouts = config.request_timeouts.clone();
+    config.cors.validate().map_err(anyhow::Error::msg)?;
+    if config.cors.allowed_origins.iter().any(|o| o == "*") {
+        warn!("🌍 CORS allows any origin; only use this for local development");
+    }
+    state.cors = config.cors.clone();
+    if let Some(path) = &config.journal.path {
+        info!("📓 Journaling to {}", path.display());
+    }
+    state.journal = Arc::new(config.journal.open()?);
+    // Upgrade records loaded from an older store before anything reads them
+    let migrated = migrations::migrate_store(&*state.state_store, Some(&state.journal), false).await?;
+    if !migrated.is_empty() {
+        info!("🗄️ Migrated {} stored record(s) to schema version {}", migrated.len(), migrations::CURRENT_SCHEMA_VERSION);
+    }
+    state.restore_durations().await?;
+    if !config.federation.peers.is_empty() {
+        info!("📡 Federation enabled with {} peer(s)", config.federation.peers.len());
+    }
+    state.federation = Arc::new(Federation::new(config.federation.clone()));
+    state.gc = config.gc.clone();
+    if let Some(dir) = &config.gc.export_dir {
+        info!("🧹 Exporting artifacts of evicted intents to {}", dir.display());
+    }
+    state.artifact_sink = config.gc.sink();
+    state.require_signatures = config.require_signatures;
+    state.negotiation_history_cap = config.negotiation_history_cap;
+    state.requote = config.requote.clone();
+    state.pricing = Pricing::new(config.currency.base.clone(), Arc::new(config.currency.clone()));
+    state.public_ws_url = config.public_ws_url.as_ref().map(|url| url.trim_end_matches('/').to_string());
+    state.capabilities = config.capabilities.clone();
+    if let Some(injector) = config.chaos.injector() {
+        warn!("💥 Chaos mode is on: {} fault rule(s) installed; do not use this node in production", config.chaos.rules.len());
+        state.enable_chaos(injector).await;
+    }
+    Ok(state)
+}
+
+/// Create the API router.
+pub fn create_router(state: AppState) -> Router {
+    let cors = state.cors.clone();
+    let request_timeouts = state.request_timeouts.clone();
+
+    Router::new()
+        // Health check
+        .route("/health", get(api::health::health_check))
+        .route("/health/ready", get(api::health::ready))
+        .route("/api/v1/capabilities", get(api::health::capabilities))
+        
+        // Intent API
+        .route("/api/v1/intent", post(api::intent::submit_intent))
+        .route("/api/v1/intent/validate", post(api::intent::validate_intent))
+        .route("/api/v1/intent/:id", get(api::intent::get_intent))
+        .route("/api/v1/intent/:id", put(api::intent::amend_intent))
+        .route("/api/v1/intent/:id", delete(api::intent::cancel_intent))
+        .route("/api/v1/intent/:id/pause", post(api::intent::pause_intent))
+        .route("/api/v1/intent/:id/resume", post(api::intent::resume_intent))
+        .route("/api/v1/intent/:id/plan", get(api::intent::get_plan))
+        .route("/api/v1/intent/:id/plans", get(api::intent::list_plans))
+        .route("/api/v1/intent/:id/proposal", get(api::intent::get_proposal))
+        .route("/api/v1/intent/:id/receipt", get(api::intent::get_receipt))
+        .route("/api/v1/intent/:id/lineage", get(api::intent::get_lineage))
+        .route("/api/v1/intent/:id/children", get(api::intent::list_children))
+        .route("/api/v1/intent/:id/artifact", get(api::intent::get_artifact))
+        .route("/api/v1/intent/:id/artifact/bundle", get(api::bundle::get_artifact_bundle))
+        .route("/api/v1/intent/:id/artifact/trace", get(api::intent::get_trace))
+        .route("/api/v1/intent/:id/artifact/verify", get(api::intent::verify_artifact))
+        .route("/api/v1/intent/:id/artifact/annotations", post(api::annotation::annotate_artifact))
+        .route("/api/v1/intent/:id/dryrun", post(api::dryrun::dry_run_intent))
+        .route("/api/v1/intent/:id/dryruns", get(api::dryrun::list_dry_runs))
+        .route("/api/v1/intents", get(api::intent::list_intents))
+        .route("/api/v1/events/:id/blob", get(api::blob::get_event_blob))
+        .route("/api/v1/plan/:id/annotations", post(api::annotation::annotate_plan))
+        
+        // Planner catalog
+        .route("/api/v1/planner/actions", get(api::planner::list_actions))
+        .route("/api/v1/planner/actions/:name", get(api::planner::get_action))
+        .route("/api/v1/planner/durations", get(api::planner::list_durations))
+        
+        // JSON Schemas of the wire types
+        .route("/api/v1/schema/:type", get(api::schema::get_schema))
+        
+        // Fault injection (only on nodes started with unsafe_chaos)
+        .route("/api/v1/admin/chaos/rules", get(api::chaos::list_rules))
+        .route("/api/v1/admin/chaos/rules", post(api::chaos::add_rule))
+        .route("/api/v1/admin/chaos/rules/:id", delete(api::chaos::remove_rule))
+        
+        // Operations journal
+        .route("/api/v1/admin/journal", get(api::journal::list_entries))
+        
+        // Bulk exports, gzipped for clients that accept it
+        .route("/api/v1/export/intents", get(api::export::export_intents).layer(CompressionLayer::new()))
+        .route("/api/v1/export/artifacts", get(api::export::export_artifacts).layer(CompressionLayer::new()))
+        
+        // State store export
+        .route("/api/v1/admin/state/export", get(api::state::export_state))
+        
+        // Funnel statistics
+        .route("/api/v1/stats", get(api::stats::get_stats))
+        
+        // WebSocket endpoints
+        .route("/ws/intent/:id", get(api::ws::intent_stream))
+        .route("/ws/intents", get(api::ws::intents_stream))
+        .route("/ws/negotiate/:id", get(api::ws::negotiate_stream))
+        .route("/ws/state", get(api::ws::state_stream))
+        
+        // Simulation endpoint
+        .route("/api/v1/simulate", post(api::simulate::simulate_intent))
+        
+        // Add middleware
+        .layer(middleware::from_fn_with_state(request_timeouts, timeouts::enforce))
+        .layer(TraceLayer::new_for_http())
+        .layer(cors.layer())
+        .layer(middleware::from_fn_with_state(cors, security::reject_disallowed_preflight))
+        .layer(middleware::from_fn(security::security_headers))
+        .with_state(state)
+}
@@ -0,0 +1,252 @@
+//! Parent/child relationships between intents.
+//!
+//! Intents form trees through `Intent::parent_id`. The node keeps a
+//! parent→children index (see [`AppState::children_of`]) so walking
+//! downwards does not scan every intent. Parent links come from clients
+//! and imports, so a cycle is reported as [`LineageError::Cycle`] instead of
+//! being followed forever.
+//!
+//! [`AppState::children_of`]: crate::state::AppState::children_of
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::state::IntentRecord;
+
+/// Errors from walking intent lineage.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LineageError {
+    /// The intent is not stored on this node.
+    #[error("Intent {0} not found")]
+    NotFound(Uuid),
+
+    /// Parent links loop back on themselves; the IDs trace the loop.
+    #[error("Intent lineage contains a cycle: {}", format_cycle(.0))]
+    Cycle(Vec<Uuid>),
+}
+
+fn format_cycle(ids: &[Uuid]) -> String {
+    ids.iter().map(Uuid::to_string).collect::<Vec<_>>().join(" -> ")
+}
+
+/// Summary of one intent in a lineage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineageEntry {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub kind: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+impl From<&IntentRecord> for LineageEntry {
+    fn from(record: &IntentRecord) -> Self {
+        Self {
+            id: record.intent.id,
+            parent_id: record.intent.parent_id,
+            kind: record.intent.kind.clone(),
+            status: record.status.as_str().to_string(),
+            created_at: record.intent.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// An intent and all of its descendants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineageNode {
+    #[serde(flatten)]
+    pub intent: LineageEntry,
+    pub children: Vec<LineageNode>,
+}
+
+/// Where an intent sits in its family tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// Ancestors from the root down to the intent's parent.
+    pub ancestors: Vec<LineageEntry>,
+
+    /// Parent of the topmost ancestor when that parent is not stored on
+    /// this node, i.e. the known chain stops short of the real root.
+    pub unknown_parent: Option<Uuid>,
+
+    /// The intent with its descendants.
+    pub tree: LineageNode,
+}
+
+/// Build the lineage of `id` from a snapshot of intents and the child index.
+pub fn build(
+    intents: &HashMap<Uuid, IntentRecord>,
+    children: &HashMap<Uuid, Vec<Uuid>>,
+    id: Uuid,
+) -> Result<Lineage, LineageError> {
+    let record = intents.get(&id).ok_or(LineageError::NotFound(id))?;
+
+    // Walk up to the root
+    let mut ancestors = Vec::new();
+    let mut seen = vec![id];
+    let mut unknown_parent = None;
+    let mut next = record.intent.parent_id;
+    while let Some(parent_id) = next {
+        if let Some(pos) = seen.iter().position(|s| *s == parent_id) {
+            let mut cycle = seen[pos..].to_vec();
+            cycle.push(parent_id);
+            return Err(LineageError::Cycle(cycle));
+        }
+        let Some(parent) = intents.get(&parent_id) else {
+            unknown_parent = Some(parent_id);
+            break;
+        };
+        ancestors.push(LineageEntry::from(parent));
+        seen.push(parent_id);
+        next = parent.intent.parent_id;
+    }
+    ancestors.reverse();
+
+    let mut path = Vec::new();
+    let tree = descend(intents, children, record, &mut path)?;
+
+    Ok(Lineage { ancestors, unknown_parent, tree })
+}
+
+/// Direct children of `id` that are stored on this node.
+pub fn children_of(
+    intents: &HashMap<Uuid, IntentRecord>,
+    children: &HashMap<Uuid, Vec<Uuid>>,
+    id: Uuid,
+) -> Result<Vec<LineageEntry>, LineageError> {
+    if !intents.contains_key(&id) {
+        return Err(LineageError::NotFound(id));
+    }
+    Ok(children
+        .get(&id)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| intents.get(child))
+        .map(LineageEntry::from)
+        .collect())
+}
+
+fn descend(
+    intents: &HashMap<Uuid, IntentRecord>,
+    children: &HashMap<Uuid, Vec<Uuid>>,
+    record: &IntentRecord,
+    path: &mut Vec<Uuid>,
+) -> Result<LineageNode, LineageError> {
+    let id = record.intent.id;
+    path.push(id);
+
+    let mut nodes = Vec::new();
+    let mut visited = HashSet::new();
+    for child_id in children.get(&id).into_iter().flatten() {
+        if let Some(pos) = path.iter().position(|p| p == child_id) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(*child_id);
+            return Err(LineageError::Cycle(cycle));
+        }
+        if !visited.insert(*child_id) {
+            continue;
+        }
+        if let Some(child) = intents.get(child_id) {
+            nodes.push(descend(intents, children, child, path)?);
+        }
+    }
+
+    path.pop();
+    Ok(LineageNode {
+        intent: LineageEntry::from(record),
+        children: nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::Intent;
+
+    use crate::state::AppState;
+
+    async fn store(state: &AppState, kind: &str, parent: Option<Uuid>) -> Uuid {
+        let mut builder = Intent::builder().kind(kind);
+        if let Some(parent) = parent {
+            builder = builder.parent(parent);
+        }
+        let intent = builder.build().unwrap();
+        let id = intent.id;
+        state.store_intent(intent).await;
+        id
+    }
+
+    #[tokio::test]
+    async fn test_three_level_tree() {
+        let state = AppState::new();
+        let root = store(&state, "batch", None).await;
+        let first = store(&state, "deploy", Some(root)).await;
+        let second = store(&state, "deploy", Some(root)).await;
+        let leaf = store(&state, "verify", Some(first)).await;
+
+        // Upwards from the leaf
+        let lineage = state.lineage(leaf).await.unwrap();
+        let ancestors: Vec<Uuid> = lineage.ancestors.iter().map(|a| a.id).collect();
+        assert_eq!(ancestors, vec![root, first]);
+        assert_eq!(lineage.unknown_parent, None);
+        assert!(lineage.tree.children.is_empty());
+
+        // Downwards from the root
+        let lineage = state.lineage(root).await.unwrap();
+        assert!(lineage.ancestors.is_empty());
+        let tree = lineage.tree;
+        assert_eq!(tree.intent.kind, "batch");
+        let level_one: Vec<Uuid> = tree.children.iter().map(|c| c.intent.id).collect();
+        assert_eq!(level_one, vec![first, second]);
+        assert_eq!(tree.children[0].children[0].intent.id, leaf);
+        assert_eq!(tree.children[0].children[0].intent.status, "received");
+        assert!(tree.children[1].children.is_empty());
+
+        let children: Vec<Uuid> = state.children_of(root).await.iter().map(|r| r.intent.id).collect();
+        assert_eq!(children, vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_parent_is_reported() {
+        let state = AppState::new();
+        let orphan_parent = Uuid::new_v4();
+        let child = store(&state, "deploy", Some(orphan_parent)).await;
+
+        let lineage = state.lineage(child).await.unwrap();
+        assert!(lineage.ancestors.is_empty());
+        assert_eq!(lineage.unknown_parent, Some(orphan_parent));
+    }
+
+    #[tokio::test]
+    async fn test_cycle_is_detected() {
+        let state = AppState::new();
+        let mut a = Intent::builder().kind("a").build().unwrap();
+        let b = Intent::builder().kind("b").parent(a.id).build().unwrap();
+        a.parent_id = Some(b.id);
+        let (a_id, b_id) = (a.id, b.id);
+        state.store_intent(a).await;
+        state.store_intent(b).await;
+
+        // Parent links: a -> b -> a
+        assert_eq!(state.lineage(a_id).await, Err(LineageError::Cycle(vec![a_id, b_id, a_id])));
+        assert!(matches!(state.lineage(b_id).await, Err(LineageError::Cycle(_))));
+    }
+
+    #[tokio::test]
+    async fn test_reparenting_updates_index() {
+        let state = AppState::new();
+        let old_parent = store(&state, "batch", None).await;
+        let new_parent = store(&state, "batch", None).await;
+        let mut child = Intent::builder().kind("deploy").parent(old_parent).build().unwrap();
+        state.store_intent(child.clone()).await;
+
+        child.parent_id = Some(new_parent);
+        state.store_intent(child.clone()).await;
+
+        assert!(state.children_of(old_parent).await.is_empty());
+        assert_eq!(state.children_of(new_parent).await[0].intent.id, child.id);
+    }
+}
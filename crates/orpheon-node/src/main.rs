@@ -1,97 +1,297 @@
 //! # Orpheon Node
 //!
 //! Main Orpheon node binary with API server.
+//!
+//! Without a subcommand the node serves its API, as with `serve`. The other
+//! subcommands are offline tools that never bind a port (see
+//! [`orpheon_node::cli`]).
 
 use std::net::SocketAddr;
-use std::sync::Arc;
-
-use axum::{
-    routing::{get, post, delete},
-    Router,
-};
-use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::TraceLayer;
-use tracing::{info, Level};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use orpheon_node::cli::{self, CliError, PlanFormat};
+use orpheon_node::federation::PeerConfig;
+use orpheon_node::requote::RequotePolicy;
+use orpheon_node::security::CorsConfig;
+use orpheon_node::NodeConfig;
+use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 
-mod api;
-mod engine;
-mod state;
+/// Orpheon node and offline tools.
+#[derive(Debug, Parser)]
+#[command(name = "orpheon-node", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-use engine::Engine;
-use state::AppState;
+    #[command(flatten)]
+    serve: Box<ServeArgs>,
+}
 
-/// Run the Orpheon node server.
-pub async fn run_server(addr: SocketAddr) -> anyhow::Result<()> {
-    // Initialize tracing
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the node's API server (the default).
+    Serve(Box<ServeArgs>),
 
-    info!("🚀 Orpheon Node starting...");
+    /// Plan an intent locally and print the plan.
+    Plan {
+        /// Intent to plan, as a submit request body in YAML or JSON.
+        #[arg(long)]
+        intent_file: PathBuf,
 
-    // Create shared application state
-    let state = AppState::new();
+        /// JSON array of actions to add to the default catalog.
+        #[arg(long)]
+        catalog: Option<PathBuf>,
 
-    // Create the engine
-    let engine = Arc::new(Engine::new(state.clone()));
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+        format: OutputFormat,
+    },
 
-    // Start the engine background task
-    let engine_clone = engine.clone();
-    tokio::spawn(async move {
-        engine_clone.run().await;
-    });
+    /// Verify an artifact file and print the verification report.
+    VerifyArtifact {
+        /// Artifact JSON file.
+        #[arg(long)]
+        file: PathBuf,
 
-    // Build the router
-    let app = create_router(state);
+        /// Hex-encoded key of the node expected to have signed the artifact.
+        #[arg(long)]
+        public_key: Option<String>,
+    },
 
-    info!("🌐 Listening on http://{}", addr);
+    /// Print the keys and values of a state store file as JSON.
+    ExportState {
+        /// State store file.
+        #[arg(long)]
+        store: PathBuf,
+    },
 
-    // Start the server
-    let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    /// Merge a JSON object of keys and values into a state store file.
+    ImportState {
+        /// State store file, created if missing.
+        #[arg(long)]
+        store: PathBuf,
 
-    Ok(())
+        /// JSON object of keys and values, as printed by `export-state`.
+        #[arg(long)]
+        file: PathBuf,
+    },
 }
 
-/// Create the API router.
-fn create_router(state: AppState) -> Router {
-    // CORS layer
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    Router::new()
-        // Health check
-        .route("/health", get(api::health::health_check))
-        
-        // Intent API
-        .route("/api/v1/intent", post(api::intent::submit_intent))
-        .route("/api/v1/intent/:id", get(api::intent::get_intent))
-        .route("/api/v1/intent/:id", delete(api::intent::cancel_intent))
-        .route("/api/v1/intent/:id/plan", get(api::intent::get_plan))
-        .route("/api/v1/intent/:id/artifact", get(api::intent::get_artifact))
-        .route("/api/v1/intents", get(api::intent::list_intents))
-        
-        // WebSocket endpoints
-        .route("/ws/intent/:id", get(api::ws::intent_stream))
-        .route("/ws/negotiate/:id", get(api::ws::negotiate_stream))
-        .route("/ws/state", get(api::ws::state_stream))
-        
-        // Simulation endpoint
-        .route("/api/v1/simulate", post(api::simulate::simulate_intent))
-        
-        // Add middleware
-        .layer(TraceLayer::new_for_http())
-        .layer(cors)
-        .with_state(state)
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Dot,
+}
+
+/// Options of the API server.
+#[derive(Debug, Default, Args)]
+struct ServeArgs {
+    /// Directory of seed files to load on startup.
+    #[arg(long)]
+    seed: Option<PathBuf>,
+
+    /// State store file to load on startup.
+    #[arg(long)]
+    store: Option<PathBuf>,
+
+    /// Print what upgrading the records in the `--store` file would change,
+    /// then exit without serving.
+    #[arg(long, requires = "store")]
+    migrate_dry_run: bool,
+
+    /// File the operations journal is appended to.
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
+    /// This node's ID among its federation peers.
+    #[arg(long)]
+    node_id: Option<String>,
+
+    /// Peer node intents can be forwarded to, as `<node_id>=<url>`.
+    #[arg(long, value_parser = parse_peer)]
+    peer: Vec<(String, String)>,
+
+    /// Allow fault injection rules.
+    #[arg(long)]
+    unsafe_chaos: bool,
+
+    /// Allow cross-origin requests from local development servers.
+    #[arg(long)]
+    dev: bool,
+
+    /// Origin allowed to make cross-origin requests.
+    #[arg(long)]
+    cors_origin: Vec<String>,
+
+    /// Largest `max_duration_ms` an intent budget may ask for.
+    #[arg(long)]
+    max_duration_ms: Option<u64>,
+
+    /// Address the API server binds to.
+    #[arg(long)]
+    bind: Option<SocketAddr>,
+
+    /// Evict finished intents this many milliseconds after they finish.
+    #[arg(long)]
+    retention_ms: Option<u64>,
+
+    /// Directory artifacts are exported to before their intent is evicted.
+    #[arg(long)]
+    artifact_export_dir: Option<PathBuf>,
+
+    /// Reject intents submitted without an issuer signature.
+    #[arg(long)]
+    require_signatures: bool,
+
+    /// Proposals and counter-offers each negotiation keeps.
+    #[arg(long)]
+    negotiation_history_cap: Option<usize>,
+
+    /// Largest drift from an accepted proposal's quoted cost, as a fraction
+    /// of it, that does not need a decision before execution.
+    #[arg(long)]
+    requote_tolerance: Option<f64>,
+
+    /// Send a fresh proposal instead of executing when an accepted
+    /// proposal's price drifted beyond the tolerance.
+    #[arg(long)]
+    renegotiate_on_drift: bool,
+
+    /// Currency action costs are in; USD by default.
+    #[arg(long)]
+    base_currency: Option<String>,
+
+    /// Value of one unit of another currency in the base currency, as
+    /// `<currency>=<rate>`, e.g. `EUR=1.08`; may be repeated.
+    #[arg(long = "exchange-rate", value_parser = parse_rate)]
+    exchange_rates: Vec<(String, f64)>,
+
+    /// WebSocket base URL clients reach this node at, when behind a proxy.
+    #[arg(long)]
+    public_ws_url: Option<String>,
+
+    /// Capability this node offers, e.g. `capability:gpu`; may be repeated.
+    #[arg(long = "capability")]
+    capabilities: Vec<String>,
+}
+
+impl ServeArgs {
+    /// Build the node configuration these options describe.
+    fn into_config(self) -> NodeConfig {
+        let mut config = NodeConfig {
+            seed_path: self.seed,
+            store_path: self.store,
+            ..Default::default()
+        };
+        config.journal.path = self.journal;
+        config.federation.node_id = self.node_id;
+        for (id, url) in self.peer {
+            config.federation.peers.insert(id, PeerConfig { base_url: url, api_key: None });
+        }
+        config.chaos.unsafe_chaos = self.unsafe_chaos;
+        if self.dev {
+            config.cors = CorsConfig::dev();
+        }
+        config.cors.allowed_origins.extend(self.cors_origin);
+        if let Some(max) = self.max_duration_ms {
+            config.intent_limits.max_duration_ms = max;
+        }
+        if let Some(addr) = self.bind {
+            config.bind_addr = addr;
+        }
+        config.gc.retention_ms = self.retention_ms;
+        config.gc.export_dir = self.artifact_export_dir;
+        config.require_signatures = self.require_signatures;
+        if let Some(cap) = self.negotiation_history_cap {
+            config.negotiation_history_cap = cap;
+        }
+        if let Some(tolerance) = self.requote_tolerance {
+            config.requote.tolerance = tolerance;
+        }
+        if self.renegotiate_on_drift {
+            config.requote.policy = RequotePolicy::Renegotiate;
+        }
+        if let Some(base) = self.base_currency {
+            config.currency.base = base;
+        }
+        config.currency.rates.extend(self.exchange_rates);
+        config.public_ws_url = self.public_ws_url;
+        config.capabilities = self.capabilities;
+        config
+    }
+}
+
+fn parse_peer(spec: &str) -> Result<(String, String), String> {
+    spec.split_once('=')
+        .map(|(id, url)| (id.to_string(), url.to_string()))
+        .ok_or_else(|| "expected <node_id>=<url>".to_string())
+}
+
+fn parse_rate(spec: &str) -> Result<(String, f64), String> {
+    spec.split_once('=')
+        .and_then(|(currency, rate)| Some((currency.to_string(), rate.parse().ok()?)))
+        .filter(|(_, rate): &(String, f64)| rate.is_finite() && *rate > 0.0)
+        .ok_or_else(|| "expected <currency>=<positive rate>".to_string())
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    run_server(addr).await
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command.unwrap_or(Command::Serve(cli.serve)) {
+        Command::Serve(args) => match (args.migrate_dry_run, args.store.as_deref()) {
+            (true, Some(store)) => cli::migrate_dry_run(store).await.map(|report| println!("{}", report)),
+            _ => return serve(args.into_config()).await,
+        },
+        Command::Plan { intent_file, catalog, format } => {
+            let format = match format {
+                OutputFormat::Json => PlanFormat::Json,
+                OutputFormat::Dot => PlanFormat::Dot,
+            };
+            cli::plan(&intent_file, catalog.as_deref(), format).await.map(|plan| print!("{}", plan))
+        }
+        Command::VerifyArtifact { file, public_key } => {
+            cli::verify_artifact(&file, public_key.as_deref()).and_then(|(report, valid)| {
+                println!("{}", report);
+                if valid {
+                    Ok(())
+                } else {
+                    Err(CliError::Invalid(format!("{} failed verification", file.display())))
+                }
+            })
+        }
+        Command::ExportState { store } => cli::export_state(&store).map(|state| println!("{}", state)),
+        Command::ImportState { store, file } => cli::import_state(&store, &file)
+            .await
+            .map(|count| eprintln!("Imported {} key(s) into {}", count, store.display())),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code() as u8)
+        }
+    }
+}
+
+async fn serve(config: NodeConfig) -> ExitCode {
+    // Initialize tracing
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(Level::INFO)
+        .finish();
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("error: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match orpheon_node::run_server(config).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
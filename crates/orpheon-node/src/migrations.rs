@@ -0,0 +1,275 @@
+//! Schema migrations of records persisted in the state store.
+//!
+//! Every record the node writes to the store carries a `schema_version`
+//! (see [`stamp`]); records written before versioning count as version 1.
+//! On startup the node upgrades outdated records in place with
+//! [`migrate_store`], journaling each one, so the rest of the node only ever
+//! reads records at [`CURRENT_SCHEMA_VERSION`].
+//!
+//! A [`Migration`] takes one kind of record from one version to the next and
+//! must be a pure function of the record. Versions without a migration for
+//! a kind only bump its version, so adding a migration for one kind leaves
+//! the others unchanged. Upgrading is idempotent: records already at the
+//! current version are left alone.
+//!
+//! | Record          | Key                                 | Stored as         |
+//! |-----------------|-------------------------------------|-------------------|
+//! | Action durations| `stats:action:{action}`             | one record        |
+//! | Annotations     | `annotations:{plan,artifact}:{id}`  | array of records  |
+//!
+//! Counters and event payloads are not records and are never touched.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use orpheon_state::{Keys, ParsedKey, StateStore};
+
+use crate::journal::{Journal, JournalEvent};
+
+/// Field holding a record's schema version.
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Schema version of the records this node writes.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+/// Kinds of persisted records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    /// Learned duration statistics of a planner action.
+    ActionStats,
+    /// An operator annotation of a plan or artifact.
+    Annotation,
+}
+
+impl RecordKind {
+    /// Kind of the records stored under `key`; `None` for keys that hold no
+    /// records.
+    pub fn of(key: &str) -> Option<Self> {
+        match Keys::parse(key)? {
+            ParsedKey::ActionStats(_) => Some(RecordKind::ActionStats),
+            ParsedKey::PlanAnnotations(_) | ParsedKey::ArtifactAnnotations(_) => Some(RecordKind::Annotation),
+            _ => None,
+        }
+    }
+}
+
+/// One step in the schema of a kind of record, from version `from` to
+/// `from + 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub kind: RecordKind,
+    pub from: u32,
+    /// What the step changes, for reports.
+    pub description: &'static str,
+    /// Upgrade one record; the version is bumped by the caller.
+    pub apply: fn(Map<String, Value>) -> Map<String, Value>,
+}
+
+/// Every migration, by version.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    kind: RecordKind::ActionStats,
+    from: 1,
+    description: "backfill recent samples from p50_ms and p95_ms",
+    apply: backfill_recent_durations,
+}];
+
+/// Schema version of a record; 1 if it has none.
+pub fn version(record: &Map<String, Value>) -> u32 {
+    record
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32)
+}
+
+/// Upgrade one record of `kind` to the current version.
+pub fn migrate_record(kind: RecordKind, mut record: Map<String, Value>) -> Map<String, Value> {
+    for from in version(&record)..CURRENT_SCHEMA_VERSION {
+        if let Some(migration) = MIGRATIONS.iter().find(|m| m.kind == kind && m.from == from) {
+            record = (migration.apply)(record);
+        }
+        record.insert(SCHEMA_VERSION_FIELD.to_string(), (from + 1).into());
+    }
+    record
+}
+
+/// Oldest schema version among the records in a stored document: the
+/// record itself, or each record of an array. `None` if it holds none.
+pub fn document_version(document: &Value) -> Option<u32> {
+    match document {
+        Value::Object(record) => Some(version(record)),
+        Value::Array(records) => records.iter().filter_map(Value::as_object).map(version).min(),
+        _ => None,
+    }
+}
+
+/// Upgrade every record in a stored document of `kind`.
+pub fn migrate_document(kind: RecordKind, document: Value) -> Value {
+    match document {
+        Value::Object(record) => Value::Object(migrate_record(kind, record)),
+        Value::Array(records) => Value::Array(
+            records
+                .into_iter()
+                .map(|record| match record {
+                    Value::Object(record) => Value::Object(migrate_record(kind, record)),
+                    other => other,
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Serialize a document of records for the store, stamped with the current
+/// schema version.
+pub fn stamp<T: Serialize>(document: &T) -> serde_json::Result<Value> {
+    let mut document = serde_json::to_value(document)?;
+    let records: Vec<&mut Value> = match &mut document {
+        Value::Array(records) => records.iter_mut().collect(),
+        record => vec![record],
+    };
+    for record in records {
+        if let Value::Object(record) = record {
+            record.insert(SCHEMA_VERSION_FIELD.to_string(), CURRENT_SCHEMA_VERSION.into());
+        }
+    }
+    Ok(document)
+}
+
+/// A stored document that was, or in a dry run would be, upgraded.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MigratedDocument {
+    pub key: String,
+    pub kind: RecordKind,
+    pub from: u32,
+    pub to: u32,
+    /// What the applied migrations change.
+    pub changes: Vec<&'static str>,
+}
+
+/// Upgrade every outdated document in `store`, journaling each to
+/// `journal` if given. With `dry_run` nothing is written; the report says
+/// what would change.
+pub async fn migrate_store<S: StateStore + ?Sized>(
+    store: &S,
+    journal: Option<&Journal>,
+    dry_run: bool,
+) -> orpheon_core::Result<Vec<MigratedDocument>> {
+    let mut keys = store.keys().await?;
+    keys.sort();
+
+    let mut migrated = Vec::new();
+    for key in keys {
+        let Some(kind) = RecordKind::of(&key) else {
+            continue;
+        };
+        let Some(entry) = store.get(&key).await? else {
+            continue;
+        };
+        let Some(from) = document_version(&entry.value).filter(|from| *from < CURRENT_SCHEMA_VERSION) else {
+            continue;
+        };
+
+        if !dry_run {
+            store.set(&key, migrate_document(kind, entry.value)).await?;
+            if let Some(journal) = journal {
+                journal.append(JournalEvent::RecordMigrated { key: key.clone(), from, to: CURRENT_SCHEMA_VERSION });
+            }
+        }
+        let changes = MIGRATIONS
+            .iter()
+            .filter(|m| m.kind == kind && m.from >= from)
+            .map(|m| m.description)
+            .collect();
+        migrated.push(MigratedDocument { key, kind, from, to: CURRENT_SCHEMA_VERSION, changes });
+    }
+    Ok(migrated)
+}
+
+/// 1 -> 2: action statistics gained `recent` weighted samples. Weighting
+/// the old median and 95th percentile equally keeps both quantiles until
+/// new samples arrive.
+fn backfill_recent_durations(mut record: Map<String, Value>) -> Map<String, Value> {
+    let has_samples = record.get("samples").and_then(Value::as_u64).unwrap_or(0) > 0;
+    if !record.contains_key("recent") {
+        let recent = match (record.get("p50_ms"), record.get("p95_ms")) {
+            (Some(p50), Some(p95)) if has_samples => serde_json::json!([[p50, 0.5], [p95, 0.5]]),
+            _ => serde_json::json!([]),
+        };
+        record.insert("recent".to_string(), recent);
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_planner::DurationStats;
+    use orpheon_state::InMemoryStateStore;
+    use serde_json::json;
+
+    fn record(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_action_stats_1_to_2_keeps_quantiles() {
+        let migrated = migrate_record(RecordKind::ActionStats, record(json!({ "samples": 12, "p50_ms": 400, "p95_ms": 900 })));
+        assert_eq!(version(&migrated), 2);
+
+        let mut stats: DurationStats = serde_json::from_value(Value::Object(migrated)).unwrap();
+        assert_eq!(stats.recent, vec![(400, 0.5), (900, 0.5)]);
+        stats.record(400);
+        assert_eq!((stats.p50_ms, stats.p95_ms), (400, 900));
+
+        let empty = migrate_record(RecordKind::ActionStats, record(json!({ "samples": 0, "p50_ms": 0, "p95_ms": 0 })));
+        assert_eq!(empty["recent"], json!([]));
+    }
+
+    #[test]
+    fn test_annotation_1_to_2_only_bumps_version() {
+        let annotation = json!({ "id": uuid::Uuid::new_v4(), "author": "oncall", "text": "hung", "created_at": "2026-01-01T00:00:00Z" });
+        let migrated = migrate_record(RecordKind::Annotation, record(annotation.clone()));
+        let mut expected = record(annotation);
+        expected.insert(SCHEMA_VERSION_FIELD.to_string(), json!(2));
+        assert_eq!(migrated, expected);
+    }
+
+    #[test]
+    fn test_migrating_is_idempotent() {
+        let v1 = record(json!({ "samples": 3, "p50_ms": 10, "p95_ms": 20 }));
+        let once = migrate_record(RecordKind::ActionStats, v1);
+        assert_eq!(migrate_record(RecordKind::ActionStats, once.clone()), once);
+
+        // A current record is left as it is, even if it lacks later fields
+        let current = record(json!({ "samples": 3, "p50_ms": 10, "p95_ms": 20, "schema_version": 2 }));
+        assert_eq!(migrate_record(RecordKind::ActionStats, current.clone()), current);
+    }
+
+    #[test]
+    fn test_stamp_marks_every_record() {
+        assert_eq!(stamp(&json!({ "a": 1 })).unwrap()[SCHEMA_VERSION_FIELD], json!(CURRENT_SCHEMA_VERSION));
+        let list = stamp(&json!([{ "a": 1 }, { "b": 2 }])).unwrap();
+        assert_eq!(document_version(&list), Some(CURRENT_SCHEMA_VERSION));
+        assert_eq!(document_version(&json!(3.5)), None);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_without_writing() {
+        let store = InMemoryStateStore::new();
+        let key = Keys::action_stats("deploy");
+        store.set(&key, json!({ "samples": 1, "p50_ms": 5, "p95_ms": 5 })).await.unwrap();
+        store.set("stats:2026-01-01:submitted", json!(4.0)).await.unwrap();
+
+        let report = migrate_store(&store, None, true).await.unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!((report[0].key.as_str(), report[0].from, report[0].to), (key.as_str(), 1, 2));
+        assert_eq!(report[0].changes, vec!["backfill recent samples from p50_ms and p95_ms"]);
+        assert_eq!(document_version(&store.get(&key).await.unwrap().unwrap().value), Some(1));
+
+        let journal = Journal::in_memory(16);
+        assert_eq!(migrate_store(&store, Some(&journal), false).await.unwrap(), report);
+        assert_eq!(journal.last_seq(), 1);
+        assert!(migrate_store(&store, Some(&journal), false).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Negotiation sessions hosted by the node.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use orpheon_core::{Intent, IntentStatus, Plan};
+pub use orpheon_negotiate::NegotiationOptions;
+use orpheon_negotiate::{NegotiationMessage, NegotiationSession, TIMEOUT_REASON};
+use orpheon_planner::planner::PlanningState;
+use orpheon_planner::{Planner, ValidationReport};
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+use crate::state::{AppState, IntentWarning};
+
+/// Warning code for proposals whose plan did not validate cleanly.
+pub const PLAN_VALIDATION: &str = "plan_validation";
+
+/// A live negotiation session and the channel its messages leave through.
+#[derive(Clone)]
+pub struct NegotiationHandle {
+    /// The session.
+    pub session: Arc<NegotiationSession>,
+
+    /// Messages for the client; held by the connected WebSocket.
+    pub outgoing: Arc<Mutex<mpsc::Receiver<NegotiationMessage>>>,
+}
+
+/// Check a plan against the node's action catalog, from an empty state.
+pub async fn validate(state: &AppState, plan: &Plan) -> orpheon_core::Result<ValidationReport> {
+    state.planner.validate_plan_report(plan, &PlanningState::default()).await
+}
+
+/// Open a negotiation for an intent, send the first proposal and start the
+/// timeout watch.
+///
+/// Anything [`validate`] finds wrong with the plan is put on the intent's
+/// stream as a [`PLAN_VALIDATION`] warning before the proposal goes out, so
+/// the client knows before approving it; an invalid plan is still offered,
+/// but will not execute.
+///
+/// A first proposal within the client's auto-accept bounds is accepted on
+/// its behalf at once; later rounds are always left to the client.
+///
+/// When the watch fires before any other resolution, the intent is failed
+/// with [`TIMEOUT_REASON`].
+pub async fn open(
+    state: &AppState,
+    intent: Intent,
+    plan: Plan,
+    options: &NegotiationOptions,
+) -> orpheon_core::Result<NegotiationHandle> {
+    let intent_id = intent.id;
+    let (session, _incoming_tx, outgoing_rx) = NegotiationSession::with_timeout(
+        intent,
+        Duration::from_millis(options.timeout_ms),
+        options.max_rounds,
+    );
+    let session = Arc::new(
+        session
+            .with_observer(state.stats.clone())
+            .with_signer(state.node_key.clone())
+            .with_history_cap(state.negotiation_history_cap)
+            .with_pricing(state.pricing.clone()),
+    );
+    let handle = NegotiationHandle {
+        session: Arc::clone(&session),
+        outgoing: Arc::new(Mutex::new(outgoing_rx)),
+    };
+
+    let report = validate(state, &plan).await?;
+    if !report.is_clean() {
+        warn!("Plan {} for intent {} did not validate cleanly: {}", plan.id, intent_id, report.describe());
+        state
+            .add_warning(intent_id, IntentWarning { code: PLAN_VALIDATION.to_string(), message: report.describe() })
+            .await;
+    }
+    let proposal = session.send_proposal(plan).await?;
+    state.negotiations.write().await.insert(intent_id, handle.clone());
+    
+    // The engine picks the acceptance up like a client's
+    if options.auto_accepts(&proposal) {
+        info!("🤝 Proposal for intent {} is within its auto-accept bounds", intent_id);
+        session.accept_by_policy(proposal.id).await?;
+    }
+
+    let watch = session.spawn_timeout_watch();
+    let state = state.clone();
+    tokio::spawn(async move {
+        if watch.await.unwrap_or(false) {
+            info!("⏱️ Negotiation for intent {} timed out", intent_id);
+            state
+                .fail_intent_if(intent_id, IntentStatus::Negotiating, TIMEOUT_REASON)
+                .await;
+        }
+    });
+
+    Ok(handle)
+}
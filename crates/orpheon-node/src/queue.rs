@@ -0,0 +1,135 @@
+//! Order in which the engine plans received intents.
+//!
+//! Intents waiting to be planned are queued by [`Priority`], oldest first
+//! within a priority, so a `Critical` intent never waits behind a `Low`
+//! one. So that a steady stream of urgent work cannot starve the rest, an
+//! intent is raised one priority for every
+//! [`aging_ms`](crate::config::QueueConfig::aging_ms) it has waited since
+//! it was created.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use orpheon_core::Priority;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Intents queued at each priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepths {
+    pub low: usize,
+    pub normal: usize,
+    pub high: usize,
+    pub critical: usize,
+}
+
+impl QueueDepths {
+    /// Intents queued at `priority`.
+    pub fn get(&self, priority: Priority) -> usize {
+        match priority {
+            Priority::Low => self.low,
+            Priority::Normal => self.normal,
+            Priority::High => self.high,
+            Priority::Critical => self.critical,
+        }
+    }
+
+    /// Intents queued at any priority.
+    pub fn total(&self) -> usize {
+        self.low + self.normal + self.high + self.critical
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Queued {
+    priority: Priority,
+    created_at: DateTime<Utc>,
+}
+
+/// Received intents waiting to be planned, keyed by priority.
+#[derive(Debug, Default)]
+pub struct IntentQueue {
+    entries: Mutex<HashMap<Uuid, Queued>>,
+}
+
+impl IntentQueue {
+    /// Queue an intent, or move it if it is queued already.
+    pub fn push(&self, id: Uuid, priority: Priority, created_at: DateTime<Utc>) {
+        self.entries.lock().unwrap().insert(id, Queued { priority, created_at });
+    }
+
+    /// Drop an intent from the queue.
+    pub fn remove(&self, id: Uuid) {
+        self.entries.lock().unwrap().remove(&id);
+    }
+
+    /// Queued intents, next to plan first, as of `now`.
+    ///
+    /// Higher priorities go first and older intents first within one; with
+    /// `aging_ms`, each full `aging_ms` an intent has waited raises it a
+    /// priority.
+    pub fn ordered(&self, now: DateTime<Utc>, aging_ms: Option<u64>) -> Vec<Uuid> {
+        let mut heap: BinaryHeap<_> = self
+            .entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, queued)| {
+                let waited_ms = (now - queued.created_at).num_milliseconds().max(0) as u64;
+                let levels = aging_ms.filter(|ms| *ms > 0).map_or(0, |ms| (waited_ms / ms) as usize);
+                (queued.priority.raised(levels), Reverse(queued.created_at), Reverse(*id))
+            })
+            .collect();
+        let mut ordered = Vec::with_capacity(heap.len());
+        while let Some((_, _, Reverse(id))) = heap.pop() {
+            ordered.push(id);
+        }
+        ordered
+    }
+
+    /// Intents queued at each priority they were submitted with.
+    pub fn depths(&self) -> QueueDepths {
+        let mut depths = QueueDepths::default();
+        for queued in self.entries.lock().unwrap().values() {
+            match queued.priority {
+                Priority::Low => depths.low += 1,
+                Priority::Normal => depths.normal += 1,
+                Priority::High => depths.high += 1,
+                Priority::Critical => depths.critical += 1,
+            }
+        }
+        depths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_higher_priorities_first_and_waiting_intents_age() {
+        let queue = IntentQueue::default();
+        let start = Utc::now();
+        let (low, normal, critical, later_critical) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        queue.push(low, Priority::Low, start);
+        queue.push(normal, Priority::Normal, start + Duration::seconds(1));
+        queue.push(critical, Priority::Critical, start + Duration::seconds(2));
+        queue.push(later_critical, Priority::Critical, start + Duration::seconds(3));
+
+        assert_eq!(queue.ordered(start + Duration::seconds(4), Some(60_000)), vec![critical, later_critical, normal, low]);
+        assert_eq!(queue.depths(), QueueDepths { low: 1, normal: 1, high: 0, critical: 2 });
+
+        // Three minutes on, the low intent has aged to critical and is oldest
+        let later = start + Duration::minutes(3);
+        assert_eq!(queue.ordered(later, Some(60_000)), vec![low, normal, critical, later_critical]);
+        assert_eq!(queue.ordered(later, None)[3], low);
+        assert_eq!(queue.depths().get(Priority::Low), 1);
+
+        queue.remove(critical);
+        queue.remove(Uuid::new_v4());
+        assert_eq!(queue.depths().total(), 3);
+    }
+}
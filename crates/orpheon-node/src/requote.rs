@@ -0,0 +1,227 @@
+//! Re-quoting accepted proposals.
+//!
+//! Prices can move between planning and acceptance, so before an accepted
+//! proposal is executed the engine re-prices its plan with the node's
+//! [`CostModel`]. When the new price drifts from the quoted cost by more
+//! than [`RequoteConfig::tolerance`], the intent's stream gets a
+//! [`COST_DRIFT`] warning and the [`RequotePolicy`] decides whether the
+//! plan runs anyway, with the drift recorded on its artifact, or the
+//! client is sent a fresh proposal at the new price.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use orpheon_core::{Plan, Step};
+use orpheon_negotiate::{Pricing, Proposal};
+use orpheon_planner::AStarPlanner;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Warning code for accepted proposals whose price moved.
+pub const COST_DRIFT: &str = "cost_drift";
+
+/// Prices plan steps at the time they are about to run.
+#[async_trait]
+pub trait CostModel: Send + Sync {
+    /// Current cost of running `step` once, or `None` to keep its quoted
+    /// cost.
+    async fn price(&self, step: &Step) -> Option<f64>;
+}
+
+/// Cost model pricing steps by their action in the planner's catalog.
+pub struct CatalogCostModel {
+    planner: Arc<AStarPlanner>,
+}
+
+impl CatalogCostModel {
+    /// Price steps from `planner`'s actions.
+    pub fn new(planner: Arc<AStarPlanner>) -> Self {
+        Self { planner }
+    }
+}
+
+#[async_trait]
+impl CostModel for CatalogCostModel {
+    async fn price(&self, step: &Step) -> Option<f64> {
+        self.planner.action(&step.action).map(|action| action.cost)
+    }
+}
+
+/// What to do with an accepted proposal whose price moved too far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequotePolicy {
+    /// Execute at the quoted terms and record the drift on the artifact.
+    #[default]
+    Proceed,
+    /// Send the client a fresh proposal at the new price.
+    Renegotiate,
+}
+
+/// Re-quote settings for a node.
+#[derive(Debug, Clone)]
+pub struct RequoteConfig {
+    /// Largest drift from the quoted cost tolerated without a decision, as
+    /// a fraction of the quoted cost.
+    pub tolerance: f64,
+
+    /// What to do beyond the tolerance.
+    pub policy: RequotePolicy,
+}
+
+impl Default for RequoteConfig {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.05,
+            policy: RequotePolicy::default(),
+        }
+    }
+}
+
+/// What became of a re-quoted proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequoteDecision {
+    /// The price moved no more than the tolerance.
+    WithinTolerance,
+    /// The price moved too far, but the plan ran at the quoted terms.
+    Proceeded,
+    /// The price moved too far and a fresh proposal was sent.
+    Renegotiated,
+}
+
+/// Outcome of re-pricing an accepted proposal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Requote {
+    /// The proposal that was re-priced.
+    pub proposal_id: Uuid,
+
+    /// Cost the client accepted, in the base currency.
+    pub quoted_cost: f64,
+
+    /// Cost of the same plan now, in the base currency.
+    pub current_cost: f64,
+
+    /// Change from the quoted cost as a fraction of it; the current cost
+    /// itself when the quote was free.
+    pub drift: f64,
+
+    pub decision: RequoteDecision,
+}
+
+impl Requote {
+    /// Human-readable account for the intent's stream.
+    pub fn describe(&self) -> String {
+        let outcome = match self.decision {
+            RequoteDecision::WithinTolerance => "within tolerance",
+            RequoteDecision::Proceeded => "executing at the quoted terms",
+            RequoteDecision::Renegotiated => "sending a fresh proposal",
+        };
+        format!(
+            "Proposal {} was quoted at {:.2} but now costs {:.2} ({:+.1}%); {}",
+            self.proposal_id,
+            self.quoted_cost,
+            self.current_cost,
+            self.drift * 100.0,
+            outcome
+        )
+    }
+}
+
+/// Re-price an accepted proposal's plan and decide what to do about it.
+///
+/// Costs are compared in `pricing`'s base currency, converting the quote
+/// out of the client's currency; a quote that no longer converts is taken
+/// at the plan's accepted cost.
+///
+/// Returns the decision and the plan at current prices.
+pub async fn requote(
+    model: &dyn CostModel,
+    config: &RequoteConfig,
+    pricing: &Pricing,
+    proposal: &Proposal,
+) -> (Requote, Plan) {
+    let mut plan = proposal.plan.clone();
+    for step in &mut plan.steps {
+        if let Some(cost) = model.price(step).await.filter(|cost| cost.is_finite()) {
+            plan.estimated_cost += cost - step.estimated_cost;
+            step.estimated_cost = cost;
+        }
+    }
+
+    let quoted_cost = pricing
+        .converter
+        .convert(proposal.quoted_cost, &proposal.currency, &pricing.base)
+        .unwrap_or(proposal.plan.estimated_cost);
+    let current_cost = plan.estimated_cost;
+    let drift = if quoted_cost == 0.0 { current_cost } else { (current_cost - quoted_cost) / quoted_cost };
+    let decision = match config.policy {
+        _ if drift.abs() <= config.tolerance => RequoteDecision::WithinTolerance,
+        RequotePolicy::Proceed => RequoteDecision::Proceeded,
+        RequotePolicy::Renegotiate => RequoteDecision::Renegotiated,
+    };
+
+    let requote = Requote { proposal_id: proposal.id, quoted_cost, current_cost, drift, decision };
+    (requote, plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::PlanningStrategy;
+
+    /// Cost model charging a fixed price for every step.
+    struct Flat(Option<f64>);
+
+    #[async_trait]
+    impl CostModel for Flat {
+        async fn price(&self, _step: &Step) -> Option<f64> {
+            self.0
+        }
+    }
+
+    fn proposal() -> Proposal {
+        let intent_id = Uuid::new_v4();
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("provision", "provision").with_cost(5.0));
+        plan.add_step(Step::new("deploy", "deploy_workload").with_cost(5.0));
+        Proposal::new(intent_id, plan).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requote_measures_drift() {
+        let proposal = proposal();
+        let config = RequoteConfig::default();
+
+        let (quote, plan) = requote(&Flat(Some(6.0)), &config, &Pricing::default(), &proposal).await;
+        assert_eq!(quote.current_cost, 12.0);
+        assert!((quote.drift - 0.2).abs() < 1e-9);
+        assert_eq!(quote.decision, RequoteDecision::Proceeded);
+        assert_eq!(plan.steps[1].estimated_cost, 6.0);
+        assert_eq!(plan.id, proposal.plan.id);
+
+        // Unknown prices keep the quote
+        let (quote, _) = requote(&Flat(None), &config, &Pricing::default(), &proposal).await;
+        assert_eq!((quote.current_cost, quote.decision), (10.0, RequoteDecision::WithinTolerance));
+
+        let config = RequoteConfig { tolerance: 0.25, policy: RequotePolicy::Renegotiate };
+        let (quote, _) = requote(&Flat(Some(6.0)), &config, &Pricing::default(), &proposal).await;
+        assert_eq!(quote.decision, RequoteDecision::WithinTolerance);
+        let (quote, _) = requote(&Flat(Some(3.0)), &config, &Pricing::default(), &proposal).await;
+        assert_eq!(quote.decision, RequoteDecision::Renegotiated);
+        assert!(quote.describe().contains("-40.0%"), "{}", quote.describe());
+    }
+
+    #[tokio::test]
+    async fn test_requote_compares_in_the_base_currency() {
+        let rates = orpheon_core::StaticRates::new("USD").with_rate("EUR", 2.0);
+        let pricing = Pricing::new("USD", Arc::new(rates));
+        let base = proposal();
+        let proposal = Proposal::priced(base.intent_id, base.plan, "EUR", &pricing).unwrap();
+        assert_eq!(proposal.quoted_cost, 5.0);
+
+        let (quote, _) = requote(&Flat(None), &RequoteConfig::default(), &pricing, &proposal).await;
+        assert_eq!((quote.quoted_cost, quote.current_cost), (10.0, 10.0));
+        assert_eq!(quote.decision, RequoteDecision::WithinTolerance);
+    }
+}
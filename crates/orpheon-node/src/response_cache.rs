@@ -0,0 +1,135 @@
+//! Pre-serialized JSON for the node's most polled responses.
+//!
+//! Dashboards list every intent every second, so re-serializing each record
+//! per request soon dominates the node's CPU time. Instead, each record's
+//! JSON is kept as a fragment tagged with the [revision](crate::state::IntentRecord::revision)
+//! it was rendered from, and listings are assembled from those fragments;
+//! only records that changed since are serialized again. Stored artifacts
+//! are final, so their JSON is kept whole.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use axum::body::Bytes;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Cached JSON fragments of intent records and stored artifacts.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    /// Record JSON by intent ID, with the revision it was rendered from.
+    fragments: Mutex<HashMap<Uuid, (u64, Bytes)>>,
+
+    /// Artifact JSON by artifact ID.
+    artifacts: Mutex<HashMap<Uuid, Bytes>>,
+
+    /// Values serialized so far, cache misses only.
+    serializations: AtomicU64,
+}
+
+impl ResponseCache {
+    /// JSON of an intent record at `revision`, rendering it with `render`
+    /// only if the cached fragment is missing or older.
+    pub fn fragment<T: Serialize>(&self, id: Uuid, revision: u64, render: impl FnOnce() -> T) -> Bytes {
+        let mut fragments = self.fragments.lock().unwrap();
+        match fragments.get(&id) {
+            Some((cached, json)) if *cached == revision => json.clone(),
+            _ => {
+                let json = self.serialize(&render());
+                fragments.insert(id, (revision, json.clone()));
+                json
+            }
+        }
+    }
+
+    /// A JSON array of the given records' fragments, in order.
+    pub fn list<'a, R: 'a, T: Serialize>(
+        &self,
+        records: impl IntoIterator<Item = (Uuid, u64, &'a R)>,
+        render: impl Fn(&R) -> T,
+    ) -> Bytes {
+        let mut body = Vec::from(&b"["[..]);
+        for (i, (id, revision, record)) in records.into_iter().enumerate() {
+            if i > 0 {
+                body.push(b',');
+            }
+            body.extend_from_slice(&self.fragment(id, revision, || render(record)));
+        }
+        body.push(b']');
+        Bytes::from(body)
+    }
+
+    /// JSON of a stored artifact, rendering it with `render` on first use.
+    pub fn artifact<T: Serialize>(&self, id: Uuid, render: impl FnOnce() -> T) -> Bytes {
+        let mut artifacts = self.artifacts.lock().unwrap();
+        if let Some(json) = artifacts.get(&id) {
+            return json.clone();
+        }
+        let json = self.serialize(&render());
+        artifacts.insert(id, json.clone());
+        json
+    }
+
+    /// Drop an intent's fragment, e.g. once the intent is evicted.
+    pub fn forget_intent(&self, id: Uuid) {
+        self.fragments.lock().unwrap().remove(&id);
+    }
+
+    /// Drop an artifact's JSON, e.g. when it is replaced or evicted.
+    pub fn forget_artifact(&self, id: Uuid) {
+        self.artifacts.lock().unwrap().remove(&id);
+    }
+
+    /// How many values have been serialized, i.e. cache misses so far.
+    pub fn serializations(&self) -> u64 {
+        self.serializations.load(Ordering::Relaxed)
+    }
+
+    fn serialize<T: Serialize>(&self, value: &T) -> Bytes {
+        self.serializations.fetch_add(1, Ordering::Relaxed);
+        // Response types serialize infallibly: no maps with non-string keys
+        Bytes::from(serde_json::to_vec(value).expect("response serializes to JSON"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_list_only_renders_changed_records() {
+        let cache = ResponseCache::default();
+        let mut records: Vec<(Uuid, u64, u32)> = (0..5).map(|n| (Uuid::new_v4(), 1, n)).collect();
+        let list = |records: &[(Uuid, u64, u32)]| {
+            cache.list(records.iter().map(|(id, rev, n)| (*id, *rev, n)), |n| json!({ "n": n }))
+        };
+
+        let body: Value = serde_json::from_slice(&list(&records)).unwrap();
+        assert_eq!(body, json!([{ "n": 0 }, { "n": 1 }, { "n": 2 }, { "n": 3 }, { "n": 4 }]));
+        assert_eq!(cache.serializations(), 5);
+
+        list(&records);
+        assert_eq!(cache.serializations(), 5);
+
+        records[2] = (records[2].0, 2, 20);
+        let body: Value = serde_json::from_slice(&list(&records)).unwrap();
+        assert_eq!(body[2], json!({ "n": 20 }));
+        assert_eq!(cache.serializations(), 6);
+
+        assert_eq!(&list(&[])[..], b"[]");
+    }
+
+    #[test]
+    fn test_artifact_is_rendered_once() {
+        let cache = ResponseCache::default();
+        let id = Uuid::new_v4();
+        assert_eq!(&cache.artifact(id, || json!({ "v": 1 }))[..], br#"{"v":1}"#);
+        assert_eq!(&cache.artifact(id, || json!({ "v": 2 }))[..], br#"{"v":1}"#);
+        assert_eq!(cache.serializations(), 1);
+
+        cache.forget_artifact(id);
+        assert_eq!(&cache.artifact(id, || json!({ "v": 2 }))[..], br#"{"v":2}"#);
+    }
+}
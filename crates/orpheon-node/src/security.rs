@@ -0,0 +1,273 @@
+//! CORS policy and security response headers.
+//!
+//! Cross-origin access is off unless origins are listed in [`CorsConfig`];
+//! `--dev` nodes allow any origin. A preflight from an origin, method or
+//! header the policy does not allow is answered with `403 Forbidden` rather
+//! than a `200` without CORS headers, so misconfigured clients fail loudly.
+//!
+//! Every response carries `X-Content-Type-Options` and `Referrer-Policy`;
+//! HTML responses also get a strict `Content-Security-Policy`.
+
+use axum::extract::{Request, State};
+use axum::http::header::{self, HeaderName, HeaderValue};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use orpheon_core::API_KEY_HEADER;
+
+/// Policy applied to HTML responses; the API serves no scripts or styles.
+pub const HTML_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; base-uri 'none'; form-action 'none'; frame-ancestors 'none'";
+
+/// Which cross-origin browsers may call the API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorsConfig {
+    /// Exact origins allowed, e.g. `https://console.example.com`; `*` allows
+    /// any origin. Empty means same-origin only.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+
+    /// Methods cross-origin requests may use.
+    #[serde(default = "default_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+
+    /// Request headers cross-origin requests may send.
+    #[serde(default = "default_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+
+    /// Whether cross-origin requests may carry credentials.
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+fn default_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "DELETE"].map(String::from).to_vec()
+}
+
+fn default_allowed_headers() -> Vec<String> {
+    ["content-type", "authorization", API_KEY_HEADER].map(String::from).to_vec()
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: default_allowed_methods(),
+            allowed_headers: default_allowed_headers(),
+            allow_credentials: false,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Any origin, for local development.
+    pub fn dev() -> Self {
+        Self { allowed_origins: vec!["*".to_string()], ..Default::default() }
+    }
+
+    /// Check the policy is one browsers will honour.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.allow_credentials && self.allows_any_origin() {
+            return Err("CORS credentials cannot be allowed for a wildcard origin".to_string());
+        }
+        if let Some(method) = self.allowed_methods.iter().find(|m| m.parse::<Method>().is_err()) {
+            return Err(format!("invalid CORS method {:?}", method));
+        }
+        if let Some(name) = self.allowed_headers.iter().find(|h| h.parse::<HeaderName>().is_err()) {
+            return Err(format!("invalid CORS header {:?}", name));
+        }
+        Ok(())
+    }
+
+    fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+
+    /// Whether requests from `origin` are allowed.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allows_any_origin() || self.allowed_origins.iter().any(|o| o.eq_ignore_ascii_case(origin))
+    }
+
+    /// Whether cross-origin requests may use `method`.
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods.iter().any(|m| m.eq_ignore_ascii_case(method.trim()))
+    }
+
+    /// Whether cross-origin requests may send the header named `name`.
+    pub fn allows_header(&self, name: &str) -> bool {
+        self.allowed_headers.iter().any(|h| h.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The layer answering preflights and adding CORS headers.
+    pub fn layer(&self) -> CorsLayer {
+        let policy = self.clone();
+        let methods: Vec<Method> = self.allowed_methods.iter().filter_map(|m| m.parse().ok()).collect();
+        let headers: Vec<HeaderName> = self.allowed_headers.iter().filter_map(|h| h.parse().ok()).collect();
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(move |origin, _| {
+                origin.to_str().is_ok_and(|o| policy.allows_origin(o))
+            }))
+            .allow_methods(AllowMethods::list(methods))
+            .allow_headers(AllowHeaders::list(headers))
+            .allow_credentials(self.allow_credentials && !self.allows_any_origin())
+    }
+}
+
+/// Refuse preflights the CORS policy does not allow.
+pub async fn reject_disallowed_preflight(
+    State(cors): State<CorsConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let (Some(origin), Some(method)) = (
+        headers.get(header::ORIGIN),
+        headers.get(header::ACCESS_CONTROL_REQUEST_METHOD),
+    ) else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let origin = origin.to_str().unwrap_or_default();
+    let refusal = if !cors.allows_origin(origin) {
+        Some(format!("CORS origin {} is not allowed", origin))
+    } else if !method.to_str().is_ok_and(|m| cors.allows_method(m)) {
+        Some(format!("CORS method {:?} is not allowed", method))
+    } else {
+        headers
+            .get_all(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .iter()
+            .flat_map(|v| v.to_str().unwrap_or_default().split(','))
+            .find(|h| !h.trim().is_empty() && !cors.allows_header(h))
+            .map(|h| format!("CORS header {} is not allowed", h.trim()))
+    };
+    match refusal {
+        Some(message) => (StatusCode::FORBIDDEN, message).into_response(),
+        None => next.run(request).await,
+    }
+}
+
+/// Add standard security headers to a response.
+pub async fn security_headers(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+
+    let headers = response.headers_mut();
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    if is_html {
+        headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static(HTML_CONTENT_SECURITY_POLICY));
+        headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+    use axum::http::HeaderValue;
+    use axum_test::TestServer;
+
+    const CONSOLE: &str = "https://console.example.com";
+
+    fn server(cors: CorsConfig) -> TestServer {
+        let mut state = AppState::new();
+        state.cors = cors;
+        TestServer::new(crate::create_router(state)).unwrap()
+    }
+
+    fn console_only() -> CorsConfig {
+        CorsConfig { allowed_origins: vec![CONSOLE.to_string()], allow_credentials: true, ..Default::default() }
+    }
+
+    async fn preflight(server: &TestServer, origin: &str, method: &str, headers: &str) -> axum_test::TestResponse {
+        server
+            .method(Method::OPTIONS, "/api/v1/intent")
+            .add_header(header::ORIGIN, HeaderValue::from_str(origin).unwrap())
+            .add_header(header::ACCESS_CONTROL_REQUEST_METHOD, HeaderValue::from_str(method).unwrap())
+            .add_header(header::ACCESS_CONTROL_REQUEST_HEADERS, HeaderValue::from_str(headers).unwrap())
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_preflight_from_allowed_origin() {
+        let server = server(console_only());
+        let response = preflight(&server, CONSOLE, "POST", "content-type, x-orpheon-api-key").await;
+
+        response.assert_status_ok();
+        assert_eq!(response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN), CONSOLE);
+        assert_eq!(response.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS), "true");
+        assert!(response.header(header::ACCESS_CONTROL_ALLOW_METHODS).to_str().unwrap().contains("POST"));
+        let allowed_headers = response.header(header::ACCESS_CONTROL_ALLOW_HEADERS);
+        assert!(allowed_headers.to_str().unwrap().contains(API_KEY_HEADER));
+    }
+
+    #[tokio::test]
+    async fn test_preflight_is_rejected_outside_policy() {
+        let server = server(console_only());
+        for (origin, method, headers) in [
+            ("https://evil.example.com", "POST", "content-type"),
+            (CONSOLE, "PUT", "content-type"),
+            (CONSOLE, "POST", "x-debug"),
+        ] {
+            let response = preflight(&server, origin, method, headers).await;
+            response.assert_status(StatusCode::FORBIDDEN);
+            assert!(response.maybe_header(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        }
+
+        // The default policy is same-origin only
+        let response = preflight(&self::server(CorsConfig::default()), CONSOLE, "GET", "").await;
+        response.assert_status(StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_dev_policy_allows_any_origin() {
+        let server = server(CorsConfig::dev());
+        let response = preflight(&server, "http://localhost:5173", "GET", "").await;
+        response.assert_status_ok();
+        assert_eq!(response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN), "http://localhost:5173");
+        assert!(response.maybe_header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_responses_carry_security_headers() {
+        let server = server(console_only());
+        let response = server.get("/health").add_header(header::ORIGIN, HeaderValue::from_static(CONSOLE)).await;
+
+        response.assert_status_ok();
+        assert_eq!(response.header(header::X_CONTENT_TYPE_OPTIONS), "nosniff");
+        assert_eq!(response.header(header::REFERRER_POLICY), "no-referrer");
+        assert_eq!(response.header(header::ACCESS_CONTROL_ALLOW_ORIGIN), CONSOLE);
+        // JSON responses need no content security policy
+        assert!(response.maybe_header(header::CONTENT_SECURITY_POLICY).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_html_responses_get_a_content_security_policy() {
+        let app = axum::Router::new()
+            .route("/docs", axum::routing::get(|| async { axum::response::Html("<h1>docs</h1>") }))
+            .layer(axum::middleware::from_fn(security_headers));
+        let response = TestServer::new(app).unwrap().get("/docs").await;
+        assert_eq!(response.header(header::CONTENT_SECURITY_POLICY), HTML_CONTENT_SECURITY_POLICY);
+        assert_eq!(response.header(header::X_FRAME_OPTIONS), "DENY");
+    }
+
+    #[test]
+    fn test_validate_refuses_credentials_with_wildcard() {
+        assert!(CorsConfig::default().validate().is_ok());
+        let config = CorsConfig { allow_credentials: true, ..CorsConfig::dev() };
+        assert!(config.validate().unwrap_err().contains("wildcard"));
+        let config = CorsConfig { allowed_methods: vec!["GET POST".to_string()], ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! Cold-start seed data.
+//!
+//! A seed directory may contain any of the following files:
+//!
+//! - `actions.json`: array of planner actions, added to the default catalog
+//! - `kinds.json`: array of intent kind definitions
+//! - `state.json`: object of initial state-store keys and values
+//! - `intents.json`: array of example intent specs (same shape as the
+//!   submit API), stored as `Received` for the engine to pick up
+//!
+//! Any error aborts loading and reports the file and line it came from.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use orpheon_core::Intent;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::AStarPlanner;
+use orpheon_state::StateStore;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::api::intent::SubmitIntentRequest;
+use crate::kinds::KindDefinition;
+use crate::state::AppState;
+
+/// Errors raised while loading seed data.
+#[derive(Debug, Error)]
+pub enum SeedError {
+    /// A seed file or directory could not be read.
+    #[error("failed to read {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A seed file is malformed or contains an invalid entry.
+    #[error("{}:{line}: {message}", path.display())]
+    Invalid {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+
+    /// Seed data could not be applied to the node state.
+    #[error("failed to apply seed data: {0}")]
+    Apply(#[from] orpheon_core::OrpheonError),
+}
+
+/// Seed data loaded from a directory.
+#[derive(Debug, Default)]
+pub struct SeedData {
+    /// Extra planner actions.
+    pub actions: Vec<PlanningAction>,
+    
+    /// Catalog name reported for the actions, e.g. `seed/actions.json`.
+    pub actions_source: Option<String>,
+
+    /// Intent kinds to register.
+    pub kinds: Vec<KindDefinition>,
+
+    /// Initial state-store keys, in file order.
+    pub state: Vec<(String, serde_json::Value)>,
+
+    /// Validated example intents.
+    pub intents: Vec<Intent>,
+}
+
+impl SeedData {
+    /// Load and validate all seed files in a directory.
+    pub fn load(dir: &Path) -> Result<Self, SeedError> {
+        if !dir.is_dir() {
+            return Err(SeedError::Io {
+                path: dir.to_path_buf(),
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, "seed directory not found"),
+            });
+        }
+
+        let mut seed = SeedData::default();
+
+        if let Some(file) = SeedFile::read(dir, "actions.json")? {
+            let actions: Vec<PlanningAction> = file.parse()?;
+            let mut names = HashSet::new();
+            for (idx, action) in actions.iter().enumerate() {
+                if action.name.trim().is_empty() {
+                    return Err(file.invalid(idx, "action name cannot be empty"));
+                }
+                if !names.insert(action.name.clone()) {
+                    return Err(file.invalid(idx, format!("duplicate action '{}'", action.name)));
+                }
+            }
+            seed.actions = actions;
+            seed.actions_source = Some(file.catalog_name(dir));
+        }
+
+        if let Some(file) = SeedFile::read(dir, "kinds.json")? {
+            let kinds: Vec<KindDefinition> = file.parse()?;
+            let mut names = HashSet::new();
+            for (idx, kind) in kinds.iter().enumerate() {
+                if kind.name.trim().is_empty() {
+                    return Err(file.invalid(idx, "kind name cannot be empty"));
+                }
+                if !names.insert(kind.name.clone()) {
+                    return Err(file.invalid(idx, format!("duplicate kind '{}'", kind.name)));
+                }
+            }
+            // An alias must not shadow a kind or another alias
+            for (idx, kind) in kinds.iter().enumerate() {
+                for alias in &kind.aliases {
+                    if alias.name.trim().is_empty() {
+                        return Err(file.invalid(idx, "alias name cannot be empty"));
+                    }
+                    if !names.insert(alias.name.clone()) {
+                        return Err(file.invalid(idx, format!("alias '{}' is already a kind or alias", alias.name)));
+                    }
+                }
+            }
+            seed.kinds = kinds;
+        }
+
+        if let Some(file) = SeedFile::read(dir, "state.json")? {
+            let state: serde_json::Map<String, serde_json::Value> = file.parse()?;
+            for (idx, key) in state.keys().enumerate() {
+                if key.trim().is_empty() {
+                    return Err(file.invalid(idx, "state key cannot be empty"));
+                }
+            }
+            seed.state = state.into_iter().collect();
+        }
+
+        if let Some(file) = SeedFile::read(dir, "intents.json")? {
+            let specs: Vec<SubmitIntentRequest> = file.parse()?;
+            for (idx, spec) in specs.into_iter().enumerate() {
+                let intent = spec
+                    .into_intent()
+                    .and_then(|intent| intent.validate().map(|_| intent))
+                    .map_err(|e| file.invalid(idx, e.to_string()))?;
+                seed.intents.push(intent);
+            }
+        }
+
+        Ok(seed)
+    }
+
+    /// Build node state from this seed data.
+    pub async fn into_state(self) -> Result<AppState, SeedError> {
+        let mut planner = AStarPlanner::new();
+        for action in self.actions {
+            planner.register_action(action)?;
+        }
+        if let Some(source) = self.actions_source {
+            planner.set_catalog_source(source);
+        }
+
+        let state = AppState::with_planner(planner);
+
+        for kind in self.kinds {
+            state.register_kind(kind).await;
+        }
+
+        for (key, value) in self.state {
+            state.state_store.set(&key, value).await?;
+        }
+
+        for intent in self.intents {
+            state.store_intent(intent).await;
+        }
+
+        Ok(state)
+    }
+}
+
+/// A seed file read into memory.
+struct SeedFile {
+    path: PathBuf,
+    text: String,
+}
+
+impl SeedFile {
+    /// Read a seed file, if it exists.
+    fn read(dir: &Path, name: &str) -> Result<Option<Self>, SeedError> {
+        let path = dir.join(name);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let text = std::fs::read_to_string(&path).map_err(|source| SeedError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(Some(Self { path, text }))
+    }
+
+    /// Deserialize the whole file.
+    fn parse<T: DeserializeOwned>(&self) -> Result<T, SeedError> {
+        serde_json::from_str(&self.text).map_err(|e| SeedError::Invalid {
+            path: self.path.clone(),
+            line: e.line(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Name of the file relative to the seed directory's parent, so
+    /// clients see `seed/actions.json` rather than a full server path.
+    fn catalog_name(&self, dir: &Path) -> String {
+        let base = dir.parent().unwrap_or(dir);
+        self.path.strip_prefix(base).unwrap_or(&self.path).display().to_string()
+    }
+    
+    /// Error for the top-level entry at `idx`, reported at its line.
+    fn invalid(&self, idx: usize, message: impl Into<String>) -> SeedError {
+        SeedError::Invalid {
+            path: self.path.clone(),
+            line: entry_lines(&self.text).get(idx).copied().unwrap_or(1),
+            message: message.into(),
+        }
+    }
+}
+
+/// Line numbers (1-based) where each top-level array element or object key starts.
+///
+/// Assumes the text is valid JSON.
+fn entry_lines(text: &str) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut line = 1;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut expect_entry = false;
+
+    for c in text.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        if expect_entry && depth == 1 && c != ']' && c != '}' {
+            lines.push(line);
+        }
+        expect_entry = false;
+
+        match c {
+            '[' | '{' => {
+                depth += 1;
+                expect_entry = depth == 1;
+            }
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' => expect_entry = depth == 1,
+            '"' => in_string = true,
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::IntentStatus;
+    use tokio::time::{sleep, Duration, Instant};
+
+    fn fixture_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/seed")
+    }
+
+    /// Create an empty scratch directory for a test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("orpheon-seed-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn assert_invalid_at(err: SeedError, file: &str, expected_line: usize) {
+        match err {
+            SeedError::Invalid { path, line, .. } => {
+                assert!(path.ends_with(file), "unexpected file {}", path.display());
+                assert_eq!(line, expected_line);
+            }
+            other => panic!("expected invalid seed error, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_entry_lines() {
+        let text = "[\n  {\"a\": [1, 2]},\n  {\"b\": \"x,]\\\"\"}\n]";
+        assert_eq!(entry_lines(text), vec![2, 3]);
+        assert_eq!(entry_lines("{\n\"k1\": 1,\n\n\"k2\": {\"n\": 2}\n}"), vec![2, 4]);
+        assert!(entry_lines("[]").is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error_reports_line() {
+        let dir = scratch_dir("syntax");
+        std::fs::write(dir.join("kinds.json"), "[\n  {\"name\": \"a\"},\n  {\"name\": }\n]").unwrap();
+
+        assert_invalid_at(SeedData::load(&dir).unwrap_err(), "kinds.json", 3);
+    }
+
+    #[test]
+    fn test_invalid_intent_reports_entry_line() {
+        let dir = scratch_dir("intent");
+        std::fs::write(
+            dir.join("intents.json"),
+            "[\n  {\"kind\": \"deploy\"},\n  {\n    \"kind\": \"   \"\n  }\n]",
+        )
+        .unwrap();
+
+        assert_invalid_at(SeedData::load(&dir).unwrap_err(), "intents.json", 3);
+    }
+
+    #[test]
+    fn test_duplicate_action_rejected() {
+        let dir = scratch_dir("actions");
+        std::fs::write(dir.join("actions.json"), r#"[{"name": "a"}, {"name": "a"}]"#).unwrap();
+
+        assert_invalid_at(SeedData::load(&dir).unwrap_err(), "actions.json", 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_finite_action_cost_is_not_applied() {
+        let seed = SeedData {
+            actions: vec![PlanningAction { name: "a".to_string(), cost: f64::NAN, ..Default::default() }],
+            ..Default::default()
+        };
+        let err = seed.into_state().await.err().unwrap();
+        assert!(matches!(err, SeedError::Apply(orpheon_core::OrpheonError::NonFinite { .. })));
+    }
+
+    #[test]
+    fn test_missing_dir_is_error() {
+        let dir = std::env::temp_dir().join(format!("orpheon-seed-missing-{}", uuid::Uuid::new_v4()));
+        assert!(matches!(SeedData::load(&dir), Err(SeedError::Io { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_spawn_seeded_fixture() {
+        let node = crate::testing::spawn_seeded(fixture_dir()).await.unwrap();
+        let state = &node.state;
+
+        // Kinds
+        let deploy = state.get_kind("deploy").await.unwrap();
+        assert!(deploy.description.is_some());
+        assert!(state.get_kind("backup").await.is_some());
+
+        // State keys
+        let region = state.state_store.get("config/default_region").await.unwrap().unwrap();
+        assert_eq!(region.value, serde_json::json!("eu-west-1"));
+        assert!(state.state_store.get("quota/compute").await.unwrap().is_some());
+
+        // Example intents are picked up by the engine and complete
+        let ids: Vec<_> = state.list_intents().await.iter().map(|r| r.intent.id).collect();
+        assert_eq!(ids.len(), 2);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let records = state.list_intents().await;
+            if records.iter().all(|r| r.status == IntentStatus::Complete) {
+                break;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "seeded intents did not complete: {:?}",
+                records.iter().map(|r| (r.status, r.error.clone())).collect::<Vec<_>>()
+            );
+            sleep(Duration::from_millis(50)).await;
+        }
+
+        // The seeded node serves them over its API
+        let client = orpheon_sdk::OrpheonClient::connect(&node.base_url()).await.unwrap();
+        for id in ids {
+            assert_eq!(client.get_intent(id).await.unwrap().status, "complete");
+        }
+    }
+}
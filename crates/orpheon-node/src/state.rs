@@ -1,31 +1,230 @@
 //! Application state.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use orpheon_core::{ExecutionArtifact, Intent, Plan};
-use orpheon_planner::AStarPlanner;
-use orpheon_state::InMemoryStateStore;
-use tokio::sync::RwLock;
+use orpheon_core::{
+    BindingError, Budget, ConstraintRegistry, ExecutionArtifact, ExecutionContext, Intent, IntentLimits, NodeKey, OrpheonError,
+    Outcome, Plan, Provenance, WeightBy,
+};
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_negotiate::{AcceptanceReceipt, Pricing, DEFAULT_HISTORY_CAP};
+use orpheon_planner::{AStarPlanner, DurationStats};
+use orpheon_sdk::{Annotation, ExportFilter, IntentRecordExport};
+use orpheon_state::{InMemoryStateStore, Keys, ParsedKey, StateStore, StateStoreExt};
+use tokio::sync::{watch, RwLock};
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::api::export::ExportStats;
+use crate::chaos::FaultInjector;
+use crate::backlog::{BacklogIndex, NoticeLog};
+use crate::config::{BacklogConfig, DeadlineWatchdogConfig, DryRunConfig, EventDataConfig, PauseConfig, QueueConfig, StateStreamConfig};
+use crate::constraint_index::ConstraintIndex;
+use crate::decompose::{Decomposer, NoDecomposition};
+use crate::engine::{SimulatedExecutor, StepExecutor};
+use crate::federation::{Federation, ForwardedIntent};
+use crate::gc::{ArtifactSink, GcConfig, NoopSink};
+use crate::journal::{Journal, JournalEvent};
+use crate::kinds::{AliasedKind, KindDefinition};
+use crate::lineage::{self, Lineage, LineageEntry, LineageError};
+use crate::migrations;
+use crate::negotiation::{NegotiationHandle, NegotiationOptions};
+use crate::queue::IntentQueue;
+use crate::requote::{CatalogCostModel, CostModel, RequoteConfig};
+use crate::response_cache::ResponseCache;
+use crate::security::CorsConfig;
+use crate::timeouts::RequestTimeoutConfig;
+use crate::stats::{self, Stats};
+
 /// Shared application state.
 #[derive(Clone)]
 pub struct AppState {
     /// Active intents.
     pub intents: Arc<RwLock<HashMap<Uuid, IntentRecord>>>,
     
+    /// Child intent IDs keyed by parent ID, in the order they were stored.
+    children: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>,
+    
+    /// Constraints of non-terminal intents.
+    constraints: Arc<RwLock<ConstraintIndex>>,
+    
     /// Generated plans.
     pub plans: Arc<RwLock<HashMap<Uuid, Plan>>>,
     
     /// Execution artifacts.
     pub artifacts: Arc<RwLock<HashMap<Uuid, ExecutionArtifact>>>,
     
+    /// Dry-run artifacts keyed by intent ID, oldest first; kept apart from
+    /// real artifacts so they never affect an intent.
+    dry_runs: Arc<RwLock<HashMap<Uuid, Vec<ExecutionArtifact>>>>,
+    
+    /// IDs of artifacts that finished after their intent had already
+    /// finished otherwise, e.g. been cancelled, by intent ID.
+    orphaned_artifacts: Arc<RwLock<HashMap<Uuid, Vec<Uuid>>>>,
+    
+    /// Operator annotations of plans and artifacts, oldest first.
+    annotations: Arc<RwLock<HashMap<AnnotationTarget, Vec<Annotation>>>>,
+    
     /// The planner engine.
     pub planner: Arc<AStarPlanner>,
     
     /// The state store.
     pub state_store: Arc<InMemoryStateStore>,
+    
+    /// Registered intent kinds, keyed by name.
+    pub kinds: Arc<RwLock<HashMap<String, KindDefinition>>>,
+    
+    /// Key this node signs artifacts and bundles with.
+    pub node_key: Arc<NodeKey>,
+    
+    /// Negotiation sessions, keyed by intent ID.
+    pub negotiations: Arc<RwLock<HashMap<Uuid, NegotiationHandle>>>,
+    
+    /// Limits on execution event payloads.
+    pub event_data: EventDataConfig,
+    
+    /// Pacing of `/ws/state` version updates.
+    pub state_stream: StateStreamConfig,
+    
+    /// Dry-run defaults.
+    pub dry_run: DryRunConfig,
+    
+    /// Bounds on submitted intent budgets.
+    pub intent_limits: IntentLimits,
+    
+    /// How often and how far ahead deadlines are checked.
+    pub deadline_watchdog: DeadlineWatchdogConfig,
+    
+    /// When intents waiting in one status are alerted on.
+    pub backlog: BacklogConfig,
+    
+    /// Intents of each waiting status, oldest first; see [`crate::backlog`].
+    pub backlog_index: Arc<BacklogIndex>,
+    
+    /// How received intents are ordered for planning.
+    pub queue: QueueConfig,
+    
+    /// Received intents waiting to be planned; see [`crate::queue`].
+    pub intent_queue: Arc<IntentQueue>,
+    
+    /// Notices about the node itself, sent on multiplexed intent streams.
+    pub notices: Arc<NoticeLog>,
+    
+    /// Accounting of paused executions.
+    pub pause: PauseConfig,
+    
+    /// What each step counts for in partial success rates.
+    pub success_weighting: WeightBy,
+    
+    /// Cross-origin access, applied by the router.
+    pub cors: CorsConfig,
+    
+    /// Time budgets of each route group; see [`crate::timeouts`].
+    pub request_timeouts: RequestTimeoutConfig,
+    
+    /// Fault rules; only present on nodes started with chaos enabled.
+    pub chaos: Option<Arc<FaultInjector>>,
+    
+    /// Hash-chained log of submissions, status changes and admin actions.
+    pub journal: Arc<Journal>,
+    
+    /// Peer nodes intents can be forwarded to.
+    pub federation: Arc<Federation>,
+    
+    /// When finished intents are evicted.
+    pub gc: GcConfig,
+    
+    /// Where artifacts are exported before their intent is evicted.
+    pub artifact_sink: Arc<dyn ArtifactSink>,
+    
+    /// Reject intents submitted without an issuer signature.
+    pub require_signatures: bool,
+    
+    /// Proposals and counter-offers each negotiation keeps.
+    pub negotiation_history_cap: usize,
+    
+    /// How accepted proposals are re-quoted before they execute.
+    pub requote: RequoteConfig,
+    
+    /// Base currency of action costs, and how budgets and quotes in other
+    /// currencies are converted to and from it.
+    pub pricing: Pricing,
+    
+    /// WebSocket base URL advertised to clients; see
+    /// [`NodeConfig::public_ws_url`](crate::config::NodeConfig::public_ws_url).
+    pub public_ws_url: Option<String>,
+    
+    /// Capabilities intents may require of this node.
+    pub capabilities: Vec<String>,
+    
+    /// Funnel statistics, aggregated daily in the state store.
+    pub stats: Arc<Stats>,
+    
+    /// Serialized intent records and artifacts, reused across requests.
+    pub responses: Arc<ResponseCache>,
+    
+    /// Counts of records sent by bulk exports.
+    pub exports: Arc<ExportStats>,
+    
+    /// Executor the engine runs plan steps with.
+    step_executor: Arc<RwLock<Arc<dyn StepExecutor>>>,
+    
+    /// Splits intents into child intents before planning.
+    decomposer: Arc<RwLock<Arc<dyn Decomposer>>>,
+    
+    /// Prices accepted plans just before they execute.
+    cost_model: Arc<RwLock<Arc<dyn CostModel>>>,
+    
+    /// Whether the engine should stop picking up work.
+    engine_paused: Arc<AtomicBool>,
+    
+    /// Set once the node starts shutting down.
+    shutdown: Arc<watch::Sender<bool>>,
+}
+
+/// Status changes kept per intent for stream subscribers to catch up on.
+pub const MAX_STATUS_CHANGES: usize = 32;
+
+/// Dry runs kept per intent.
+pub const MAX_DRY_RUNS_PER_INTENT: usize = 8;
+
+/// A status change, numbered in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusChange {
+    /// Per-intent sequence number, starting at 1 for `Received`.
+    pub seq: u64,
+    pub status: orpheon_core::IntentStatus,
+    pub plan_id: Option<Uuid>,
+    pub artifact_id: Option<Uuid>,
+    /// Percentage of steps that succeeded, for partially complete intents.
+    pub success_rate: Option<u8>,
+}
+
+/// What an operator annotation is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnnotationTarget {
+    Plan(Uuid),
+    Artifact(Uuid),
+}
+
+impl AnnotationTarget {
+    /// State-store key the target's annotations are mirrored under.
+    pub fn key(self) -> String {
+        match self {
+            AnnotationTarget::Plan(id) => Keys::plan_annotations(id),
+            AnnotationTarget::Artifact(id) => Keys::artifact_annotations(id),
+        }
+    }
+}
+
+/// Something watchers of an intent should know that is not a status change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntentWarning {
+    /// Machine-readable kind, e.g. `deadline_at_risk`.
+    pub code: String,
+    pub message: String,
 }
 
 /// Record of an intent with its status.
@@ -37,40 +236,508 @@ pub struct IntentRecord {
     /// Current status.
     pub status: orpheon_core::IntentStatus,
     
-    /// Associated plan ID (if generated).
-    pub plan_id: Option<Uuid>,
+    /// Plan revisions generated for this intent, oldest first.
+    /// The last element is the active plan.
+    pub plan_ids: Vec<Uuid>,
+    
+    /// The plan revision that was handed to execution (if any).
+    pub executed_plan_id: Option<Uuid>,
     
     /// Associated artifact ID (if complete).
     pub artifact_id: Option<Uuid>,
     
     /// Error message (if failed).
     pub error: Option<String>,
+    
+    /// Percentage of steps that succeeded (if partially complete).
+    pub success_rate: Option<u8>,
+    
+    /// Negotiation options, if the client asked to approve the plan first.
+    pub negotiation: Option<NegotiationOptions>,
+    
+    /// Set when the intent was forwarded to a peer; the record then mirrors
+    /// the remote intent instead of being planned here.
+    pub forwarded: Option<ForwardedIntent>,
+    
+    /// The most recent status changes, oldest first.
+    pub changes: Vec<StatusChange>,
+    
+    /// Steps of the executing plan that have finished or been skipped.
+    pub steps_done: HashSet<Uuid>,
+    
+    /// Warnings raised about the intent, oldest first.
+    pub warnings: Vec<IntentWarning>,
+    
+    /// When the node received the intent.
+    pub received_at: chrono::DateTime<chrono::Utc>,
+    
+    /// Who submitted the intent and from where; set once, when it is
+    /// first stored.
+    pub provenance: Provenance,
+    
+    /// When the intent reached a terminal status.
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    
+    /// Receipt for the proposal the client accepted, if it negotiated.
+    pub receipt: Option<AcceptanceReceipt>,
+    
+    /// Bumped on every change to the record, so cached JSON of an older
+    /// revision is known to be stale.
+    pub revision: u64,
+    
+    /// Whether the intent was split into child intents instead of planned;
+    /// it finishes once they all have.
+    pub decomposed: bool,
+    
+    /// Whether the node accepted the intent's proposal on the client's
+    /// behalf, under its auto-accept bounds.
+    pub auto_accepted_by_policy: bool,
+}
+
+impl IntentRecord {
+    /// Sequence number of the latest status change.
+    pub fn seq(&self) -> u64 {
+        self.changes.last().map_or(0, |c| c.seq)
+    }
+    
+    /// Move to a new status, recording the change if it is one.
+    fn set_status(&mut self, status: orpheon_core::IntentStatus) {
+        if self.status == status {
+            return;
+        }
+        self.status = status;
+        self.changes.push(StatusChange {
+            seq: self.seq() + 1,
+            status,
+            plan_id: self.plan_id(),
+            artifact_id: self.artifact_id,
+            success_rate: self.success_rate,
+        });
+        if self.changes.len() > MAX_STATUS_CHANGES {
+            self.changes.remove(0);
+        }
+    }
+    
+    /// Active plan ID (the most recent revision, if any).
+    pub fn plan_id(&self) -> Option<Uuid> {
+        self.plan_ids.last().copied()
+    }
+    
+    /// When the validity window of an intent waiting for it next opens,
+    /// including the next of its daily windows.
+    pub fn scheduled_for(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let window = &self.intent.validity_window;
+        if self.status != orpheon_core::IntentStatus::Received || !window.is_pending() {
+            return None;
+        }
+        window.next_opening()
+    }
+    
+    /// The record as exported in bulk.
+    pub fn to_export(&self) -> IntentRecordExport {
+        IntentRecordExport {
+            intent: self.intent.clone(),
+            status: self.status,
+            plan_ids: self.plan_ids.clone(),
+            artifact_id: self.artifact_id,
+            error: self.error.clone(),
+            success_rate: self.success_rate,
+            received_at: self.received_at,
+            finished_at: self.finished_at,
+        }
+    }
+    
+    /// 1-based revision number of a plan belonging to this intent.
+    pub fn plan_revision(&self, plan_id: Uuid) -> Option<u32> {
+        self.plan_ids
+            .iter()
+            .position(|id| *id == plan_id)
+            .map(|idx| idx as u32 + 1)
+    }
 }
 
 impl AppState {
     /// Create a new application state.
     pub fn new() -> Self {
+        Self::with_planner(AStarPlanner::new())
+    }
+    
+    /// Create a new application state around a preconfigured planner.
+    pub fn with_planner(planner: AStarPlanner) -> Self {
+        let state_store = Arc::new(InMemoryStateStore::new());
+        let planner = Arc::new(planner);
         Self {
             intents: Arc::new(RwLock::new(HashMap::new())),
+            children: Arc::new(RwLock::new(HashMap::new())),
+            constraints: Arc::new(RwLock::new(ConstraintIndex::default())),
             plans: Arc::new(RwLock::new(HashMap::new())),
             artifacts: Arc::new(RwLock::new(HashMap::new())),
-            planner: Arc::new(AStarPlanner::new()),
-            state_store: Arc::new(InMemoryStateStore::new()),
+            dry_runs: Arc::new(RwLock::new(HashMap::new())),
+            annotations: Arc::new(RwLock::new(HashMap::new())),
+            orphaned_artifacts: Arc::new(RwLock::new(HashMap::new())),
+            cost_model: Arc::new(RwLock::new(Arc::new(CatalogCostModel::new(planner.clone())))),
+            planner,
+            stats: Arc::new(Stats::new(state_store.clone())),
+            state_store,
+            kinds: Arc::new(RwLock::new(HashMap::new())),
+            node_key: Arc::new(NodeKey::generate()),
+            negotiations: Arc::new(RwLock::new(HashMap::new())),
+            event_data: EventDataConfig::default(),
+            state_stream: StateStreamConfig::default(),
+            dry_run: DryRunConfig::default(),
+            intent_limits: IntentLimits::default(),
+            deadline_watchdog: DeadlineWatchdogConfig::default(),
+            backlog: BacklogConfig::default(),
+            backlog_index: Arc::new(BacklogIndex::default()),
+            queue: QueueConfig::default(),
+            intent_queue: Arc::new(IntentQueue::default()),
+            notices: Arc::new(NoticeLog::default()),
+            pause: PauseConfig::default(),
+            success_weighting: WeightBy::default(),
+            cors: CorsConfig::default(),
+            request_timeouts: RequestTimeoutConfig::default(),
+            chaos: None,
+            journal: Arc::new(Journal::default()),
+            federation: Arc::new(Federation::default()),
+            gc: GcConfig::default(),
+            artifact_sink: Arc::new(NoopSink),
+            require_signatures: false,
+            negotiation_history_cap: DEFAULT_HISTORY_CAP,
+            requote: RequoteConfig::default(),
+            pricing: Pricing::default(),
+            public_ws_url: None,
+            capabilities: Vec::new(),
+            responses: Arc::new(ResponseCache::default()),
+            exports: Arc::new(ExportStats::default()),
+            step_executor: Arc::new(RwLock::new(Arc::new(SimulatedExecutor::default()))),
+            decomposer: Arc::new(RwLock::new(Arc::new(NoDecomposition))),
+            engine_paused: Arc::new(AtomicBool::new(false)),
+            shutdown: Arc::new(watch::channel(false).0),
+        }
+    }
+    
+    /// Validators for `Custom` constraints: submitted intents may only use
+    /// the ones registered here, and the planner and engine judge them with
+    /// their checks. Register them on the planner's evaluator (see
+    /// [`AStarPlanner::with_evaluator`]) before building the state.
+    pub fn constraint_registry(&self) -> &ConstraintRegistry {
+        self.planner.evaluator().registry()
+    }
+    
+    /// `intent`'s budget in the base currency action costs are in, for
+    /// comparing against them.
+    ///
+    /// Fails with [`OrpheonError::IntentInvalid`] if its currency has no
+    /// exchange rate to the base currency.
+    pub fn base_budget(&self, intent: &Intent) -> orpheon_core::Result<Budget> {
+        intent
+            .budget
+            .converted(&self.pricing.base, self.pricing.converter.as_ref())
+            .map_err(|e| match e {
+                OrpheonError::IntentInvalid { message, .. } => {
+                    OrpheonError::IntentInvalid { intent_id: Some(intent.id), message }
+                }
+                other => other,
+            })
+    }
+    
+    /// Decides whether the engine splits an intent into child intents.
+    pub async fn decomposer(&self) -> Arc<dyn Decomposer> {
+        self.decomposer.read().await.clone()
+    }
+    
+    /// Replace the decomposer; intents already split are unaffected.
+    pub async fn set_decomposer(&self, decomposer: Arc<dyn Decomposer>) {
+        *self.decomposer.write().await = decomposer;
+    }
+    
+    /// Executor the engine runs plan steps with.
+    pub async fn step_executor(&self) -> Arc<dyn StepExecutor> {
+        self.step_executor.read().await.clone()
+    }
+    
+    /// Replace the step executor; plans already executing keep the old one.
+    pub async fn set_step_executor(&self, executor: Arc<dyn StepExecutor>) {
+        *self.step_executor.write().await = executor;
+    }
+    
+    /// The cost model accepted plans are re-quoted with.
+    pub async fn cost_model(&self) -> Arc<dyn CostModel> {
+        self.cost_model.read().await.clone()
+    }
+    
+    /// Replace the cost model; by default steps are priced from the
+    /// planner's catalog.
+    pub async fn set_cost_model(&self, model: Arc<dyn CostModel>) {
+        *self.cost_model.write().await = model;
+    }
+    
+    /// Turn on fault injection: steps run on a [`SimulatedExecutor`] that
+    /// applies the injector's rules.
+    pub async fn enable_chaos(&mut self, injector: FaultInjector) -> Arc<FaultInjector> {
+        let injector = Arc::new(injector);
+        self.set_step_executor(Arc::new(SimulatedExecutor::with_faults(Arc::clone(&injector)))).await;
+        self.chaos = Some(Arc::clone(&injector));
+        self.journal.append(JournalEvent::ConfigChanged { description: "chaos mode enabled".to_string() });
+        injector
+    }
+    
+    /// Stop the engine from picking up new work until [`resume_engine`](Self::resume_engine).
+    pub fn pause_engine(&self) {
+        self.engine_paused.store(true, Ordering::SeqCst);
+    }
+    
+    /// Let a paused engine continue.
+    pub fn resume_engine(&self) {
+        self.engine_paused.store(false, Ordering::SeqCst);
+    }
+    
+    /// Whether the engine is paused.
+    pub fn engine_paused(&self) -> bool {
+        self.engine_paused.load(Ordering::SeqCst)
+    }
+    
+    /// Tell open streams the node is shutting down.
+    pub fn begin_shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+    
+    /// Changes to whether the node is shutting down.
+    pub fn shutdown_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+    
+    /// Register an intent kind, replacing any existing definition.
+    pub async fn register_kind(&self, kind: KindDefinition) {
+        let mut kinds = self.kinds.write().await;
+        kinds.insert(kind.name.clone(), kind);
+    }
+    
+    /// Get a registered kind by name.
+    pub async fn get_kind(&self, name: &str) -> Option<KindDefinition> {
+        let kinds = self.kinds.read().await;
+        kinds.get(name).cloned()
+    }
+    
+    /// The kind `name` is an alias of, if it is one.
+    pub async fn resolve_kind_alias(&self, name: &str) -> Option<AliasedKind> {
+        let kinds = self.kinds.read().await;
+        if kinds.contains_key(name) {
+            return None;
         }
+        kinds.values().find_map(|kind| {
+            let alias = kind.aliases.iter().find(|alias| alias.name == name)?;
+            Some(AliasedKind { alias: alias.clone(), kind: kind.name.clone() })
+        })
     }
     
     /// Store an intent.
     pub async fn store_intent(&self, intent: Intent) {
+        self.store_intent_with_negotiation(intent, None).await;
+    }
+    
+    /// Store an intent whose plan must be negotiated before execution.
+    pub async fn store_intent_with_negotiation(&self, intent: Intent, negotiation: Option<NegotiationOptions>) {
+        self.insert_intent(intent, negotiation, None, Provenance::default()).await;
+    }
+    
+    /// Store an intent submitted through the API, along with where it came
+    /// from.
+    pub async fn store_submitted_intent(
+        &self,
+        intent: Intent,
+        negotiation: Option<NegotiationOptions>,
+        provenance: Provenance,
+    ) {
+        self.insert_intent(intent, negotiation, None, provenance).await;
+    }
+    
+    /// Store a mirror of an intent that was forwarded to a peer.
+    pub async fn store_forwarded_intent(&self, intent: Intent, forwarded: ForwardedIntent, provenance: Provenance) {
+        self.insert_intent(intent, None, Some(forwarded), provenance).await;
+    }
+    
+    async fn insert_intent(
+        &self,
+        intent: Intent,
+        negotiation: Option<NegotiationOptions>,
+        forwarded: Option<ForwardedIntent>,
+        provenance: Provenance,
+    ) {
         let record = IntentRecord {
             intent: intent.clone(),
             status: orpheon_core::IntentStatus::Received,
-            plan_id: None,
+            plan_ids: Vec::new(),
+            executed_plan_id: None,
             artifact_id: None,
             error: None,
+            success_rate: None,
+            negotiation,
+            forwarded,
+            changes: vec![StatusChange {
+                seq: 1,
+                status: orpheon_core::IntentStatus::Received,
+                plan_id: None,
+                artifact_id: None,
+                success_rate: None,
+            }],
+            steps_done: HashSet::new(),
+            warnings: Vec::new(),
+            received_at: chrono::Utc::now(),
+            provenance,
+            finished_at: None,
+            receipt: None,
+            revision: 0,
+            decomposed: false,
+            auto_accepted_by_policy: false,
+        };
+        
+        let mut intents = self.intents.write().await;
+        let mut children = self.children.write().await;
+        let mut constraints = self.constraints.write().await;
+        if let Some(previous) = intents.get(&intent.id) {
+            constraints.remove(&previous.intent);
+        }
+        constraints.insert(&intent);
+        if record.forwarded.is_none() {
+            self.backlog_index.enter(intent.id, orpheon_core::IntentStatus::Received, record.received_at);
+            self.intent_queue.push(intent.id, intent.priority, intent.created_at);
+        }
+        if !intents.contains_key(&intent.id) {
+            self.journal.append(JournalEvent::IntentSubmitted { intent_id: intent.id, kind: intent.kind.clone() });
+        }
+        let previous_parent = intents.get(&intent.id).and_then(|r| r.intent.parent_id);
+        if previous_parent != intent.parent_id {
+            if let Some(siblings) = previous_parent.and_then(|p| children.get_mut(&p)) {
+                siblings.retain(|id| *id != intent.id);
+            }
+            if let Some(parent_id) = intent.parent_id {
+                children.entry(parent_id).or_default().push(intent.id);
+            }
+        }
+        let is_new = !intents.contains_key(&intent.id);
+        let revision = intents.get(&intent.id).map_or(0, |previous| previous.revision + 1);
+        let provenance = intents.get(&intent.id).map_or(record.provenance.clone(), |previous| previous.provenance.clone());
+        intents.insert(intent.id, IntentRecord { revision, provenance, ..record });
+        if is_new {
+            self.stats.count(stats::INTENTS_SUBMITTED).await;
+        }
+    }
+    
+    /// Replace the spec of an intent that has not started planning yet,
+    /// keeping its ID, creation time and parent, and so its place in the
+    /// queue and lineage.
+    ///
+    /// Fails with `NotFound` for an unknown intent and `InvalidTransition`
+    /// once the intent has left `Received`. Returns the stored intent.
+    pub async fn replace_intent(&self, id: Uuid, intent: Intent) -> orpheon_core::Result<Intent> {
+        let mut intents = self.intents.write().await;
+        let Some(record) = intents.get(&id) else {
+            return Err(OrpheonError::NotFound { resource_type: "Intent".to_string(), id: id.to_string() });
         };
+        if record.status != orpheon_core::IntentStatus::Received {
+            return Err(OrpheonError::InvalidTransition {
+                intent_id: id,
+                action: "amend".to_string(),
+                status: record.status,
+            });
+        }
         
+        let intent = Intent {
+            id,
+            created_at: record.intent.created_at,
+            parent_id: record.intent.parent_id,
+            signature: None,
+            ..intent
+        };
+        let mut constraints = self.constraints.write().await;
+        constraints.remove(&record.intent);
+        constraints.insert(&intent);
+        if let Some(record) = record_mut(&mut intents, id) {
+            if record.forwarded.is_none() {
+                self.intent_queue.push(id, intent.priority, intent.created_at);
+            }
+            record.intent = intent.clone();
+        }
+        self.journal.append(JournalEvent::IntentAmended { intent_id: id });
+        Ok(intent)
+    }
+    
+    /// An intent's ancestors and descendants.
+    pub async fn lineage(&self, id: Uuid) -> Result<Lineage, LineageError> {
+        let intents = self.intents.read().await;
+        let children = self.children.read().await;
+        lineage::build(&intents, &children, id)
+    }
+    
+    /// Summaries of an intent's direct children.
+    pub async fn child_entries(&self, id: Uuid) -> Result<Vec<LineageEntry>, LineageError> {
+        let intents = self.intents.read().await;
+        let children = self.children.read().await;
+        lineage::children_of(&intents, &children, id)
+    }
+    
+    /// Records of an intent's direct children, in the order they were stored.
+    pub async fn children_of(&self, id: Uuid) -> Vec<IntentRecord> {
+        let intents = self.intents.read().await;
+        let children = self.children.read().await;
+        children
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| intents.get(child).cloned())
+            .collect()
+    }
+    
+    /// Ancestors of an intent stored on this node.
+    pub async fn intent_depth(&self, id: Uuid) -> usize {
+        let intents = self.intents.read().await;
+        let mut depth = 0;
+        let mut current = intents.get(&id).and_then(|r| r.intent.parent_id);
+        // Bounded by the number of intents, in case the chain loops
+        while let Some(parent) = current.and_then(|id| intents.get(&id)).filter(|_| depth < intents.len()) {
+            depth += 1;
+            current = parent.intent.parent_id;
+        }
+        depth
+    }
+    
+    /// Ancestors of an intent through the intents stored on this node,
+    /// checked against [`IntentLimits::max_parent_depth`]; see
+    /// [`Intent::ancestor_chain`].
+    ///
+    /// [`IntentLimits::max_parent_depth`]: orpheon_core::IntentLimits::max_parent_depth
+    pub async fn ancestor_chain(&self, intent: &Intent) -> orpheon_core::Result<Vec<Uuid>> {
+        let intents = self.intents.read().await;
+        let resolver = |id: Uuid| intents.get(&id).map(|r| r.intent.clone());
+        intent.ancestor_chain(&resolver, self.intent_limits.max_parent_depth)
+    }
+    
+    /// Check the intents stored on this node for a chain of `DependsOn`
+    /// constraints leading back to `intent`; see
+    /// [`Intent::check_dependency_cycles`].
+    pub async fn check_dependency_cycles(&self, intent: &Intent) -> orpheon_core::Result<()> {
+        let intents = self.intents.read().await;
+        let resolver = |id: Uuid| intents.get(&id).map(|r| r.intent.clone());
+        intent.check_dependency_cycles(&resolver)
+    }
+    
+    /// Mark an intent as split into child intents.
+    pub async fn mark_decomposed(&self, id: Uuid) {
         let mut intents = self.intents.write().await;
-        intents.insert(intent.id, record);
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.decomposed = true;
+        }
+    }
+    
+    /// Decomposed intents still waiting for their children.
+    pub async fn decomposed_in_progress(&self) -> Vec<Uuid> {
+        let intents = self.intents.read().await;
+        intents
+            .values()
+            .filter(|r| r.decomposed && !r.status.is_terminal())
+            .map(|r| r.intent.id)
+            .collect()
     }
     
     /// Get an intent by ID.
@@ -79,29 +746,382 @@ impl AppState {
         intents.get(&id).cloned()
     }
     
-    /// Update intent status.
-    pub async fn update_intent_status(&self, id: Uuid, status: orpheon_core::IntentStatus) {
+    /// Move an intent to `status`, unless it already finished in another
+    /// status.
+    ///
+    /// Returns whether the intent is now in `status`.
+    pub async fn update_intent_status(&self, id: Uuid, status: orpheon_core::IntentStatus) -> bool {
         let mut intents = self.intents.write().await;
-        if let Some(record) = intents.get_mut(&id) {
-            record.status = status;
+        match record_mut(&mut intents, id) {
+            Some(record) if record.status.can_transition_to(status) => {
+                let from = record.status;
+                self.journal_transition(id, from, status, None);
+                record.set_status(status);
+                self.finish_if_terminal(from, record).await;
+                true
+            }
+            _ => false,
         }
     }
     
-    /// Store a plan.
-    pub async fn store_plan(&self, plan: Plan) {
-        let intent_id = plan.intent_id;
-        let plan_id = plan.id;
+    /// Cancel an intent, unless it already finished.
+    ///
+    /// Returns whether the intent was cancelled.
+    pub async fn cancel_intent(&self, id: Uuid) -> bool {
+        let mut intents = self.intents.write().await;
+        match record_mut(&mut intents, id) {
+            Some(record) if !record.status.is_terminal() => {
+                self.journal.append(JournalEvent::IntentCancelled { intent_id: id });
+                let from = record.status;
+                self.journal_transition(id, from, orpheon_core::IntentStatus::Cancelled, None);
+                record.set_status(orpheon_core::IntentStatus::Cancelled);
+                self.finish_if_terminal(from, record).await;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Expire an intent whose validity window has closed, unless it
+    /// already finished or started executing.
+    ///
+    /// Returns whether the intent expired.
+    pub async fn expire_intent(&self, id: Uuid) -> bool {
+        let mut intents = self.intents.write().await;
+        match record_mut(&mut intents, id) {
+            Some(record) if record.status.awaits_execution() && record.intent.validity_window.has_closed() => {
+                let from = record.status;
+                let error = match record.intent.validity_window.not_after {
+                    Some(at) => format!("validity window closed at {}", at.to_rfc3339()),
+                    None => "validity window closed".to_string(),
+                };
+                self.journal_transition(id, from, orpheon_core::IntentStatus::Expired, Some(&error));
+                record.set_status(orpheon_core::IntentStatus::Expired);
+                record.error = Some(error);
+                self.finish_if_terminal(from, record).await;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Intents whose validity window closed before they started executing;
+    /// see [`expire_intent`](Self::expire_intent).
+    pub async fn intents_past_window(&self) -> Vec<Uuid> {
+        let intents = self.intents.read().await;
+        intents
+            .values()
+            .filter(|r| r.forwarded.is_none() && r.status.awaits_execution() && r.intent.validity_window.has_closed())
+            .map(|r| r.intent.id)
+            .collect()
+    }
+    
+    /// Move an intent to `status`, but only if it is still in the
+    /// `expected` status.
+    ///
+    /// Returns whether the status changed.
+    pub async fn update_intent_status_if(
+        &self,
+        id: Uuid,
+        expected: orpheon_core::IntentStatus,
+        status: orpheon_core::IntentStatus,
+    ) -> bool {
+        let mut intents = self.intents.write().await;
+        match record_mut(&mut intents, id) {
+            Some(record) if record.status == expected => {
+                self.journal_transition(id, expected, status, None);
+                record.set_status(status);
+                self.finish_if_terminal(expected, record).await;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Point an intent at its artifact without storing the artifact here.
+    pub async fn set_artifact_id(&self, id: Uuid, artifact_id: Option<Uuid>) {
+        let mut intents = self.intents.write().await;
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.artifact_id = artifact_id;
+        }
+    }
+    
+    /// Set an intent's success rate, e.g. when mirroring a peer.
+    pub async fn set_success_rate(&self, id: Uuid, success_rate: Option<u8>) {
+        let mut intents = self.intents.write().await;
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.success_rate = success_rate;
+        }
+    }
+    
+    /// Fail an intent, unless it already finished.
+    ///
+    /// Returns whether the intent was failed.
+    pub async fn fail_intent(&self, id: Uuid, error: &str) -> bool {
+        let mut intents = self.intents.write().await;
+        match record_mut(&mut intents, id) {
+            Some(record) if record.status.can_transition_to(orpheon_core::IntentStatus::Failed) => {
+                let from = record.status;
+                self.journal_transition(id, from, orpheon_core::IntentStatus::Failed, Some(error));
+                record.set_status(orpheon_core::IntentStatus::Failed);
+                record.error = Some(error.to_string());
+                self.finish_if_terminal(from, record).await;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Once an intent has just finished, drop it from the constraint index
+    /// and record when and how long it took.
+    async fn finish_if_terminal(&self, from: orpheon_core::IntentStatus, record: &mut IntentRecord) {
+        if record.status.is_terminal() && !from.is_terminal() {
+            self.constraints.write().await.remove(&record.intent);
+            let now = chrono::Utc::now();
+            record.finished_at = Some(now);
+            let elapsed_ms = (now - record.received_at).num_milliseconds().max(0) as u64;
+            self.stats.count(stats::INTENTS_FINISHED).await;
+            self.stats.duration(stats::TIME_TO_TERMINAL, elapsed_ms).await;
+        }
+    }
+    
+    /// Intents that finished before `t`, earliest first.
+    pub async fn intents_finished_before(&self, t: chrono::DateTime<chrono::Utc>) -> Vec<Uuid> {
+        let intents = self.intents.read().await;
+        let mut finished: Vec<_> = intents
+            .values()
+            .filter_map(|record| record.finished_at.filter(|at| *at < t).map(|at| (at, record.intent.id)))
+            .collect();
+        finished.sort();
+        finished.into_iter().map(|(_, id)| id).collect()
+    }
+    
+    /// Forget an intent along with its plans, artifacts, dry runs and
+    /// negotiation; see [`crate::gc`].
+    ///
+    /// Returns the evicted record.
+    pub async fn evict_intent(&self, id: Uuid) -> Option<IntentRecord> {
+        let record = self.intents.write().await.remove(&id)?;
+        self.backlog_index.remove(id);
+        self.intent_queue.remove(id);
+        self.responses.forget_intent(id);
+        let mut annotations = self.annotations.write().await;
+        {
+            let mut plans = self.plans.write().await;
+            for plan_id in &record.plan_ids {
+                plans.remove(plan_id);
+                annotations.remove(&AnnotationTarget::Plan(*plan_id));
+            }
+        }
+        let orphaned = self.orphaned_artifacts.write().await.remove(&id).unwrap_or_default();
+        {
+            let mut artifacts = self.artifacts.write().await;
+            for artifact_id in record.artifact_id.iter().chain(&orphaned) {
+                artifacts.remove(artifact_id);
+                annotations.remove(&AnnotationTarget::Artifact(*artifact_id));
+                self.responses.forget_artifact(*artifact_id);
+            }
+        }
+        drop(annotations);
+        self.dry_runs.write().await.remove(&id);
+        self.negotiations.write().await.remove(&id);
+        {
+            let mut children = self.children.write().await;
+            children.remove(&id);
+            if let Some(siblings) = record.intent.parent_id.and_then(|p| children.get_mut(&p)) {
+                siblings.retain(|child| *child != id);
+            }
+        }
+        Some(record)
+    }
+    
+    /// Non-terminal intents with a deadline before `t`, earliest deadline
+    /// first, each with its earliest deadline.
+    pub async fn intents_with_deadline_before(&self, t: chrono::DateTime<chrono::Utc>) -> Vec<(Uuid, chrono::DateTime<chrono::Utc>)> {
+        self.constraints.read().await.deadlines_before(t)
+    }
+    
+    /// Non-terminal intents with an SLA on `metric`.
+    pub async fn intents_with_sla(&self, metric: &str) -> Vec<Uuid> {
+        self.constraints.read().await.with_sla(metric)
+    }
+    
+    /// Non-terminal intents that must run on `node_id`.
+    pub async fn intents_with_provider(&self, node_id: &str) -> Vec<Uuid> {
+        self.constraints.read().await.with_provider(node_id)
+    }
+    
+    /// Record steps of an intent's executing plan as done.
+    pub async fn record_steps_done(&self, id: Uuid, step_ids: impl IntoIterator<Item = Uuid>) {
+        let mut intents = self.intents.write().await;
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.steps_done.extend(step_ids);
+        }
+    }
+    
+    /// Keep the receipt for the proposal a client accepted.
+    pub async fn store_receipt(&self, id: Uuid, receipt: AcceptanceReceipt) {
+        let mut intents = self.intents.write().await;
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.auto_accepted_by_policy = receipt.auto_accepted_by_policy;
+            record.receipt = Some(receipt);
+        }
+    }
+    
+    /// Raise a warning about an intent for its stream watchers.
+    pub async fn add_warning(&self, id: Uuid, warning: IntentWarning) {
+        let mut intents = self.intents.write().await;
+        if let Some(record) = record_mut(&mut intents, id) {
+            record.warnings.push(warning);
+        }
+    }
+    
+    fn journal_transition(
+        &self,
+        intent_id: Uuid,
+        from: orpheon_core::IntentStatus,
+        to: orpheon_core::IntentStatus,
+        reason: Option<&str>,
+    ) {
+        if from != to {
+            self.backlog_index.transition(intent_id, to);
+            self.intent_queue.remove(intent_id);
+            self.journal.append(JournalEvent::StatusChanged {
+                intent_id,
+                from,
+                to,
+                reason: reason.map(str::to_string),
+            });
+        }
+    }
+    
+    /// Fail an intent, but only if it is still in the `expected` status.
+    ///
+    /// Returns whether the intent was failed.
+    pub async fn fail_intent_if(&self, id: Uuid, expected: orpheon_core::IntentStatus, error: &str) -> bool {
+        let mut intents = self.intents.write().await;
+        match record_mut(&mut intents, id) {
+            Some(record) if record.status == expected => {
+                self.journal_transition(id, expected, orpheon_core::IntentStatus::Failed, Some(error));
+                record.set_status(orpheon_core::IntentStatus::Failed);
+                record.error = Some(error.to_string());
+                self.finish_if_terminal(expected, record).await;
+                true
+            }
+            _ => false,
+        }
+    }
+    
+    /// Get the negotiation session for an intent.
+    pub async fn get_negotiation(&self, intent_id: Uuid) -> Option<NegotiationHandle> {
+        let negotiations = self.negotiations.read().await;
+        negotiations.get(&intent_id).cloned()
+    }
+    
+    /// Check a plan's `${...}` bindings against its intent, then store it
+    /// with [`store_plan_if`](Self::store_plan_if).
+    ///
+    /// A plan referencing a binding that cannot resolve is not stored.
+    pub async fn store_checked_plan(
+        &self,
+        intent: &Intent,
+        plan: Plan,
+        expected: orpheon_core::IntentStatus,
+    ) -> Result<Option<Plan>, BindingError> {
+        ExecutionContext::for_intent(intent).check_plan(&plan)?;
+        Ok(self.store_plan_if(plan, expected).await)
+    }
+    
+    /// Store a plan split into segments: the first as the newest revision,
+    /// as [`store_checked_plan`](Self::store_checked_plan) does, and the
+    /// segments continuing it alongside.
+    ///
+    /// Bindings are checked across all segments. Returns the segments
+    /// joined into one plan under the first segment's ID, which is what
+    /// gets proposed and executed, or `None` if the intent is no longer in
+    /// the `expected` status.
+    pub async fn store_checked_segments(
+        &self,
+        intent: &Intent,
+        head: Plan,
+        continuations: Vec<Plan>,
+        expected: orpheon_core::IntentStatus,
+    ) -> Result<Option<Plan>, BindingError> {
+        let joined = head.clone().joined(continuations.clone());
+        ExecutionContext::for_intent(intent).check_plan(&joined)?;
         
         let mut plans = self.plans.write().await;
-        plans.insert(plan_id, plan);
+        let mut intents = self.intents.write().await;
+        let Some(record) = record_mut(&mut intents, head.intent_id).filter(|r| r.status == expected) else {
+            return Ok(None);
+        };
+        let head = attach_plan(&mut plans, Some(record), head);
+        for segment in continuations {
+            plans.insert(segment.id, Plan { version: head.version, ..segment });
+        }
+        Ok(Some(Plan { version: head.version, ..joined }))
+    }
+    
+    /// A plan followed by the stored segments continuing it, in order.
+    ///
+    /// `plan` may be the first segment or all segments already joined.
+    pub async fn plan_segments(&self, plan: Plan) -> Vec<Plan> {
+        let plans = self.plans.read().await;
+        let mut continuations: Vec<Plan> = Vec::new();
+        let mut last = plan.id;
+        while let Some(next) = plans.values().find(|p| p.continuation_of == Some(last)) {
+            last = next.id;
+            continuations.push(next.clone());
+        }
+        if continuations.is_empty() {
+            return vec![plan];
+        }
         
-        // Update intent record
+        let head = plans.get(&plan.id).cloned().unwrap_or(plan);
+        std::iter::once(head).chain(continuations).collect()
+    }
+    
+    /// Store a plan as the newest revision for its intent, whatever the
+    /// intent's status.
+    ///
+    /// Earlier revisions are kept but marked superseded (expired as of now),
+    /// and the stored plan's `version` is set to its revision number.
+    pub async fn store_plan(&self, plan: Plan) -> Plan {
+        let mut plans = self.plans.write().await;
+        let mut intents = self.intents.write().await;
+        let record = record_mut(&mut intents, plan.intent_id);
+        attach_plan(&mut plans, record, plan)
+    }
+    
+    /// Store a plan as [`store_plan`](Self::store_plan) does, but only if
+    /// its intent is still in the `expected` status.
+    ///
+    /// Returns `None`, storing nothing, otherwise.
+    pub async fn store_plan_if(&self, plan: Plan, expected: orpheon_core::IntentStatus) -> Option<Plan> {
+        let mut plans = self.plans.write().await;
+        let mut intents = self.intents.write().await;
+        let record = record_mut(&mut intents, plan.intent_id).filter(|r| r.status == expected)?;
+        Some(attach_plan(&mut plans, Some(record), plan))
+    }
+    
+    /// Record which plan revision is being executed for an intent.
+    pub async fn mark_plan_executed(&self, intent_id: Uuid, plan_id: Uuid) {
         let mut intents = self.intents.write().await;
-        if let Some(record) = intents.get_mut(&intent_id) {
-            record.plan_id = Some(plan_id);
+        if let Some(record) = record_mut(&mut intents, intent_id) {
+            record.executed_plan_id = Some(plan_id);
         }
     }
     
+    /// Get all plan revisions for an intent, oldest first.
+    pub async fn get_plans_for_intent(&self, intent_id: Uuid) -> Option<Vec<Plan>> {
+        let intents = self.intents.read().await;
+        let plan_ids = intents.get(&intent_id)?.plan_ids.clone();
+        drop(intents);
+        
+        let plans = self.plans.read().await;
+        Some(plan_ids.iter().filter_map(|id| plans.get(id).cloned()).collect())
+    }
+    
     /// Get a plan by ID.
     pub async fn get_plan(&self, id: Uuid) -> Option<Plan> {
         let plans = self.plans.read().await;
@@ -111,28 +1131,148 @@ impl AppState {
     /// Get plan by intent ID.
     pub async fn get_plan_for_intent(&self, intent_id: Uuid) -> Option<Plan> {
         let intents = self.intents.read().await;
-        let plan_id = intents.get(&intent_id)?.plan_id?;
+        let plan_id = intents.get(&intent_id)?.plan_id()?;
         drop(intents);
         
         self.get_plan(plan_id).await
     }
     
     /// Store an artifact.
-    pub async fn store_artifact(&self, artifact: ExecutionArtifact) {
+    ///
+    /// The intent is marked `Complete`, `Failed` if execution failed, or
+    /// `Cancelled` if it was cancelled. An intent that already finished
+    /// otherwise, e.g. was cancelled while its plan ran to completion,
+    /// keeps its status; the artifact is then filed as orphaned instead.
+    ///
+    /// Returns whether the intent was updated.
+    pub async fn store_artifact(&self, artifact: ExecutionArtifact) -> bool {
         let intent_id = artifact.intent.id;
         let artifact_id = artifact.id;
+        self.learn_durations(&artifact).await;
+        let (status, failure, success_rate) = match &artifact.outcome {
+            Outcome::Failure { reason, .. } => (orpheon_core::IntentStatus::Failed, Some(reason.clone()), None),
+            Outcome::PartialSuccess { success_rate, .. } => {
+                (orpheon_core::IntentStatus::PartiallyComplete, None, Some(*success_rate))
+            }
+            Outcome::Cancelled { .. } => (orpheon_core::IntentStatus::Cancelled, None, None),
+            _ => (orpheon_core::IntentStatus::Complete, None, None),
+        };
         
         let mut artifacts = self.artifacts.write().await;
         artifacts.insert(artifact_id, artifact);
+        self.responses.forget_artifact(artifact_id);
         
         // Update intent record
         let mut intents = self.intents.write().await;
-        if let Some(record) = intents.get_mut(&intent_id) {
-            record.artifact_id = Some(artifact_id);
-            record.status = orpheon_core::IntentStatus::Complete;
+        match record_mut(&mut intents, intent_id) {
+            Some(record) if record.status.can_transition_to(status) => {
+                record.artifact_id = Some(artifact_id);
+                record.success_rate = success_rate;
+                let from = record.status;
+                self.journal_transition(intent_id, from, status, failure.as_deref());
+                record.set_status(status);
+                if failure.is_some() {
+                    record.error = failure;
+                }
+                self.finish_if_terminal(from, record).await;
+                true
+            }
+            Some(_) => {
+                let mut orphaned = self.orphaned_artifacts.write().await;
+                orphaned.entry(intent_id).or_default().push(artifact_id);
+                false
+            }
+            None => false,
+        }
+    }
+    
+    /// Teach the planner how long each completed step of `artifact` took,
+    /// keeping the updated statistics under [`Keys::action_stats`].
+    async fn learn_durations(&self, artifact: &ExecutionArtifact) {
+        let durations = self.planner.durations();
+        for event in &artifact.trace {
+            let (ExecutionEventType::StepCompleted, Some(duration_ms)) = (&event.event_type, event.duration_ms) else {
+                continue;
+            };
+            let Some(step) = artifact.final_plan.steps.iter().find(|s| s.id == event.step_id) else {
+                continue;
+            };
+            let stats = durations.record(&step.action, duration_ms);
+            let kept = match migrations::stamp(&stats) {
+                Ok(record) => self.state_store.set(&Keys::action_stats(&step.action), record).await.map(|_| ()),
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = kept {
+                warn!("Could not keep duration statistics of {}: {}", step.action, e);
+            }
+        }
+    }
+    
+    /// Load learned step durations kept in the state store, e.g. after
+    /// restoring it from disk.
+    pub async fn restore_durations(&self) -> orpheon_core::Result<()> {
+        for entry in self.state_store.get_prefix(Keys::action_stats_prefix()).await? {
+            let Some(ParsedKey::ActionStats(action)) = Keys::parse(&entry.key) else {
+                continue;
+            };
+            let stats: DurationStats = serde_json::from_value(entry.value)?;
+            self.planner.durations().insert(action, stats);
+        }
+        Ok(())
+    }
+    
+    /// Artifacts filed as orphaned for an intent, oldest first.
+    pub async fn orphaned_artifacts(&self, intent_id: Uuid) -> Vec<ExecutionArtifact> {
+        let ids = self.orphaned_artifacts.read().await.get(&intent_id).cloned().unwrap_or_default();
+        let artifacts = self.artifacts.read().await;
+        ids.iter().filter_map(|id| artifacts.get(id).cloned()).collect()
+    }
+    
+    /// Append an annotation to a plan or artifact, mirroring the target's
+    /// annotations into the state store.
+    pub async fn annotate(&self, target: AnnotationTarget, annotation: Annotation) -> orpheon_core::Result<()> {
+        // Held while mirroring, so the store never goes back to a shorter list
+        let mut all = self.annotations.write().await;
+        let mut annotations = all.get(&target).cloned().unwrap_or_default();
+        annotations.push(annotation);
+        self.state_store.set(&target.key(), migrations::stamp(&annotations)?).await?;
+        all.insert(target, annotations);
+        Ok(())
+    }
+    
+    /// Annotations of a plan or artifact, oldest first.
+    pub async fn annotations(&self, target: AnnotationTarget) -> Vec<Annotation> {
+        self.annotations.read().await.get(&target).cloned().unwrap_or_default()
+    }
+    
+    /// Keep the full payload of a truncated execution event.
+    pub async fn store_event_blob(&self, event_id: Uuid, data: serde_json::Value) -> orpheon_core::Result<()> {
+        self.state_store.set_typed(&Keys::event_blob(event_id), &data).await?;
+        Ok(())
+    }
+    
+    /// Get the full payload of a truncated execution event.
+    pub async fn get_event_blob(&self, event_id: Uuid) -> orpheon_core::Result<Option<serde_json::Value>> {
+        self.state_store.get_typed(&Keys::event_blob(event_id)).await
+    }
+    
+    /// Keep a dry-run artifact, dropping the intent's oldest beyond
+    /// [`MAX_DRY_RUNS_PER_INTENT`].
+    pub async fn store_dry_run(&self, artifact: ExecutionArtifact) {
+        let mut dry_runs = self.dry_runs.write().await;
+        let runs = dry_runs.entry(artifact.intent.id).or_default();
+        runs.push(artifact);
+        if runs.len() > MAX_DRY_RUNS_PER_INTENT {
+            runs.remove(0);
         }
     }
     
+    /// Dry-run artifacts for an intent, oldest first.
+    pub async fn get_dry_runs(&self, intent_id: Uuid) -> Vec<ExecutionArtifact> {
+        let dry_runs = self.dry_runs.read().await;
+        dry_runs.get(&intent_id).cloned().unwrap_or_default()
+    }
+    
     /// Get an artifact by ID.
     pub async fn get_artifact(&self, id: Uuid) -> Option<ExecutionArtifact> {
         let artifacts = self.artifacts.read().await;
@@ -148,11 +1288,112 @@ impl AppState {
         self.get_artifact(artifact_id).await
     }
     
+    /// JSON of an intent's artifact, serialized once and then served from
+    /// [`responses`](Self::responses).
+    pub async fn artifact_json_for_intent(&self, intent_id: Uuid) -> Option<axum::body::Bytes> {
+        let intents = self.intents.read().await;
+        let artifact_id = intents.get(&intent_id)?.artifact_id?;
+        drop(intents);
+        
+        let artifacts = self.artifacts.read().await;
+        let artifact = artifacts.get(&artifact_id)?;
+        Some(self.responses.artifact(artifact_id, || artifact))
+    }
+    
     /// List all intents.
     pub async fn list_intents(&self) -> Vec<IntentRecord> {
         let intents = self.intents.read().await;
         intents.values().cloned().collect()
     }
+    
+    /// A JSON array of the intent records `include` accepts, rendered by
+    /// `render`, reusing the cached JSON of records that have not changed
+    /// since.
+    pub async fn list_intents_json<T: serde::Serialize>(
+        &self,
+        include: impl Fn(&IntentRecord) -> bool,
+        render: impl Fn(&IntentRecord) -> T,
+    ) -> axum::body::Bytes {
+        let intents = self.intents.read().await;
+        let records = intents.values().filter(|record| include(record));
+        self.responses.list(records.map(|record| (record.intent.id, record.revision, record)), render)
+    }
+    
+    /// IDs of the intents an export with `filter` includes, oldest first.
+    pub async fn intent_ids_for_export(&self, filter: &ExportFilter) -> Vec<Uuid> {
+        let intents = self.intents.read().await;
+        let mut matching: Vec<(chrono::DateTime<chrono::Utc>, Uuid)> = intents
+            .values()
+            .filter(|r| filter.include_active || r.status.is_terminal())
+            .filter(|r| filter.created_after.is_none_or(|after| r.intent.created_at > after))
+            .map(|r| (r.intent.created_at, r.intent.id))
+            .collect();
+        matching.sort_unstable();
+        matching.into_iter().map(|(_, id)| id).collect()
+    }
+    
+    /// Export lines of the intents with `ids` that still exist, and how
+    /// many there are.
+    pub async fn intents_ndjson(&self, ids: &[Uuid]) -> (usize, String) {
+        let intents = self.intents.read().await;
+        ndjson_lines(ids.iter().filter_map(|id| intents.get(id)).map(IntentRecord::to_export))
+    }
+    
+    /// IDs of the artifacts an export with `filter` includes, oldest first.
+    pub async fn artifact_ids_for_export(&self, filter: &ExportFilter) -> Vec<Uuid> {
+        let artifacts = self.artifacts.read().await;
+        let mut matching: Vec<(chrono::DateTime<chrono::Utc>, Uuid)> = artifacts
+            .values()
+            .filter(|a| filter.created_after.is_none_or(|after| a.timestamp > after))
+            .map(|a| (a.timestamp, a.id))
+            .collect();
+        matching.sort_unstable();
+        matching.into_iter().map(|(_, id)| id).collect()
+    }
+    
+    /// Export lines of the artifacts with `ids` that are still stored, and
+    /// how many there are.
+    pub async fn artifacts_ndjson(&self, ids: &[Uuid]) -> (usize, String) {
+        let artifacts = self.artifacts.read().await;
+        ndjson_lines(ids.iter().filter_map(|id| artifacts.get(id)))
+    }
+}
+
+/// One JSON line per record, and the number of records.
+fn ndjson_lines<T: serde::Serialize>(records: impl Iterator<Item = T>) -> (usize, String) {
+    let mut count = 0;
+    let mut lines = String::new();
+    for record in records {
+        // Records serialize infallibly: no maps with non-string keys
+        lines.push_str(&serde_json::to_string(&record).expect("record serializes to JSON"));
+        lines.push('\n');
+        count += 1;
+    }
+    (count, lines)
+}
+
+/// Get a record to change, marking cached JSON of it stale.
+fn record_mut(intents: &mut HashMap<Uuid, IntentRecord>, id: Uuid) -> Option<&mut IntentRecord> {
+    let record = intents.get_mut(&id)?;
+    record.revision += 1;
+    Some(record)
+}
+
+/// Add a plan to `plans` as the newest revision of `record`'s intent.
+fn attach_plan(plans: &mut HashMap<Uuid, Plan>, record: Option<&mut IntentRecord>, mut plan: Plan) -> Plan {
+    if let Some(record) = record {
+        let now = chrono::Utc::now();
+        for old_id in &record.plan_ids {
+            if let Some(old) = plans.get_mut(old_id) {
+                old.expires_at = Some(old.expires_at.map_or(now, |t| t.min(now)));
+            }
+        }
+        record.plan_ids.push(plan.id);
+        plan.version = record.plan_ids.len() as u32;
+    }
+    
+    plans.insert(plan.id, plan.clone());
+    plan
 }
 
 impl Default for AppState {
@@ -160,3 +1401,199 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::PlanningStrategy;
+
+    #[tokio::test]
+    async fn test_store_plan_keeps_revisions() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("test").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+        
+        let first = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        let second = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        
+        let record = state.get_intent(intent_id).await.unwrap();
+        assert_eq!(record.plan_ids, vec![first.id, second.id]);
+        assert_eq!(record.plan_id(), Some(second.id));
+        assert_eq!(record.plan_revision(second.id), Some(2));
+        assert_eq!(second.version, 2);
+        
+        // The older revision is marked superseded, the active one is not
+        let old = state.get_plan(first.id).await.unwrap();
+        assert!(old.expires_at.is_some_and(|t| t <= chrono::Utc::now()));
+        assert_eq!(state.get_plan_for_intent(intent_id).await.unwrap().id, second.id);
+    }
+
+    #[tokio::test]
+    async fn test_replace_intent_only_while_received() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let id = intent.id;
+        state.store_intent(intent.clone()).await;
+        
+        let amended = Intent::builder().kind("deploy").resource_limit("cpu", 2.0).build().unwrap();
+        let stored = state.replace_intent(id, amended.clone()).await.unwrap();
+        assert_eq!(stored.id, id);
+        assert_eq!(stored.created_at, intent.created_at);
+        assert_eq!(stored.constraints, amended.constraints);
+        assert_eq!(state.get_intent(id).await.unwrap().intent.constraints, stored.constraints);
+        
+        assert!(state.update_intent_status_if(id, orpheon_core::IntentStatus::Received, orpheon_core::IntentStatus::Planning).await);
+        match state.replace_intent(id, intent).await {
+            Err(OrpheonError::InvalidTransition { status, .. }) => assert_eq!(status, orpheon_core::IntentStatus::Planning),
+            other => panic!("expected an invalid transition, got {:?}", other),
+        }
+        assert_eq!(state.get_intent(id).await.unwrap().intent.constraints, stored.constraints);
+        assert!(matches!(
+            state.replace_intent(Uuid::new_v4(), stored).await,
+            Err(OrpheonError::NotFound { .. })
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_constraint_index_follows_amendments_and_terminal_status() {
+        use chrono::{Duration, Utc};
+        use orpheon_core::Constraint;
+        
+        let state = AppState::new();
+        let soon = Utc::now() + Duration::minutes(2);
+        let later = Utc::now() + Duration::minutes(10);
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::Deadline { by: soon })
+            .constraint(Constraint::Sla { metric: "latency".into(), threshold: 200, unit: "ms".into() })
+            .constraint(Constraint::Provider { node_id: "node-a".into() })
+            .build()
+            .unwrap();
+        let id = intent.id;
+        state.store_intent(intent.clone()).await;
+        let other = Intent::builder().kind("deploy").constraint(Constraint::Deadline { by: soon }).build().unwrap();
+        state.store_intent(other.clone()).await;
+        
+        let five_minutes = Utc::now() + Duration::minutes(5);
+        let due: Vec<Uuid> = state.intents_with_deadline_before(five_minutes).await.into_iter().map(|(id, _)| id).collect();
+        assert_eq!(due.len(), 2);
+        assert!(due.contains(&id) && due.contains(&other.id));
+        assert_eq!(state.intents_with_sla("latency").await, vec![id]);
+        assert!(state.intents_with_sla("throughput").await.is_empty());
+        assert_eq!(state.intents_with_provider("node-a").await, vec![id]);
+        
+        // Amending replaces the old constraints
+        let amended = Intent { constraints: vec![Constraint::Deadline { by: later }], ..intent };
+        state.store_intent(amended).await;
+        assert_eq!(state.intents_with_deadline_before(five_minutes).await, vec![(other.id, soon)]);
+        assert_eq!(state.intents_with_deadline_before(later + Duration::seconds(1)).await.last(), Some(&(id, later)));
+        assert!(state.intents_with_sla("latency").await.is_empty());
+        assert!(state.intents_with_provider("node-a").await.is_empty());
+        
+        // Terminal intents leave the index
+        state.fail_intent(id, "gave up").await;
+        let mut plan = Plan::new(other.id, PlanningStrategy::Heuristic);
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy"));
+        state.store_artifact(ExecutionArtifact::new(other, plan, Outcome::Success)).await;
+        assert!(state.intents_with_deadline_before(later + Duration::seconds(1)).await.is_empty());
+    }
+    
+    /// An executing intent with a stored plan, and an artifact that
+    /// completes it.
+    async fn executing_intent(state: &AppState) -> (Uuid, ExecutionArtifact) {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        let plan = state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+        assert!(state.update_intent_status(intent_id, orpheon_core::IntentStatus::Executing).await);
+        (intent_id, ExecutionArtifact::new(intent, plan, Outcome::Success))
+    }
+    
+    #[tokio::test]
+    async fn test_cancel_before_completion_wins() {
+        let state = AppState::new();
+        let (intent_id, artifact) = executing_intent(&state).await;
+        let artifact_id = artifact.id;
+        
+        assert!(state.cancel_intent(intent_id).await);
+        assert!(!state.store_artifact(artifact).await);
+        
+        let record = state.get_intent(intent_id).await.unwrap();
+        assert_eq!(record.status, orpheon_core::IntentStatus::Cancelled);
+        assert_eq!(record.artifact_id, None);
+        let orphaned = state.orphaned_artifacts(intent_id).await;
+        assert_eq!(orphaned.iter().map(|a| a.id).collect::<Vec<_>>(), vec![artifact_id]);
+        
+        // Nothing moves a finished intent on
+        assert!(!state.cancel_intent(intent_id).await);
+        assert!(!state.fail_intent(intent_id, "late").await);
+        assert!(!state.update_intent_status(intent_id, orpheon_core::IntentStatus::Executing).await);
+        assert_eq!(state.get_intent(intent_id).await.unwrap().status, orpheon_core::IntentStatus::Cancelled);
+    }
+    
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cancel_and_completion_race_has_one_winner() {
+        for _ in 0..100 {
+            let state = AppState::new();
+            let (intent_id, artifact) = executing_intent(&state).await;
+            let barrier = Arc::new(tokio::sync::Barrier::new(2));
+            
+            let cancel = tokio::spawn({
+                let (state, barrier) = (state.clone(), barrier.clone());
+                async move {
+                    barrier.wait().await;
+                    state.cancel_intent(intent_id).await
+                }
+            });
+            let complete = tokio::spawn({
+                let state = state.clone();
+                async move {
+                    barrier.wait().await;
+                    state.store_artifact(artifact).await
+                }
+            });
+            let cancelled = cancel.await.unwrap();
+            let completed = complete.await.unwrap();
+            
+            // Exactly one write applies, and a cancel that got in wins
+            assert_ne!(cancelled, completed);
+            let record = state.get_intent(intent_id).await.unwrap();
+            if cancelled {
+                assert_eq!(record.status, orpheon_core::IntentStatus::Cancelled);
+                assert_eq!(record.artifact_id, None);
+                assert_eq!(state.orphaned_artifacts(intent_id).await.len(), 1);
+            } else {
+                assert_eq!(record.status, orpheon_core::IntentStatus::Complete);
+                assert!(state.orphaned_artifacts(intent_id).await.is_empty());
+            }
+            let transitions = state
+                .journal
+                .since(0, usize::MAX)
+                .into_iter()
+                .filter(|e| matches!(e.event, crate::journal::JournalEvent::StatusChanged { to, .. } if to.is_terminal()))
+                .count();
+            assert_eq!(transitions, 1);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_plan_is_not_attached_to_cancelled_intent() {
+        let state = AppState::new();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent.clone()).await;
+        assert!(state.update_intent_status_if(intent_id, orpheon_core::IntentStatus::Received, orpheon_core::IntentStatus::Planning).await);
+        assert!(state.cancel_intent(intent_id).await);
+        
+        let plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        let plan_id = plan.id;
+        let stored = state
+            .store_checked_segments(&intent, plan, Vec::new(), orpheon_core::IntentStatus::Planning)
+            .await
+            .unwrap();
+        assert!(stored.is_none());
+        assert!(state.get_intent(intent_id).await.unwrap().plan_ids.is_empty());
+        assert!(state.get_plan(plan_id).await.is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! Intent and negotiation funnel statistics.
+//!
+//! Each statistic is emitted through the [`metrics`] facade, so whatever
+//! recorder the process installs sees it, and added to a daily aggregate
+//! in the state store under [`Keys::stats`]. The aggregates are what
+//! `GET /api/v1/stats` serves, so historical funnels need no metrics
+//! backend.
+//!
+//! Counts are stored as totals per day and durations as sums in
+//! milliseconds (`{metric}_ms`); averages are worked out when read.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use orpheon_core::Intent;
+use orpheon_negotiate::{NegotiationState, SessionEvent, SessionObserver};
+use orpheon_state::{InMemoryStateStore, Keys, ParsedKey, StateStore, StateStoreExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Intents accepted for processing.
+pub const INTENTS_SUBMITTED: &str = "intents_submitted";
+
+/// Intents whose plan went to the client for approval.
+pub const INTENTS_NEGOTIATING: &str = "intents_negotiating";
+
+/// Intents that reached a terminal status.
+pub const INTENTS_FINISHED: &str = "intents_finished";
+
+/// Time from receiving an intent to its terminal status.
+pub const TIME_TO_TERMINAL: &str = "time_to_terminal";
+
+/// Proposals sent, counting every round.
+pub const PROPOSALS_SENT: &str = "proposals_sent";
+
+/// Proposals the client countered.
+pub const PROPOSALS_COUNTERED: &str = "proposals_countered";
+
+/// Negotiations the client accepted.
+pub const NEGOTIATIONS_ACCEPTED: &str = "negotiations_accepted";
+
+/// Negotiations rejected by either side.
+pub const NEGOTIATIONS_REJECTED: &str = "negotiations_rejected";
+
+/// Negotiations that timed out.
+pub const NEGOTIATIONS_TIMED_OUT: &str = "negotiations_timed_out";
+
+/// Rounds of resolved negotiations.
+pub const NEGOTIATION_ROUNDS: &str = "negotiation_rounds";
+
+/// Time from opening a negotiation to its acceptance.
+pub const ACCEPTANCE_LATENCY: &str = "acceptance_latency";
+
+/// Intents planned successfully, per kind (`planning_succeeded:{kind}`).
+pub const PLANNING_SUCCEEDED: &str = "planning_succeeded";
+
+/// Intents that could not be planned, per kind (`planning_failed:{kind}`).
+pub const PLANNING_FAILED: &str = "planning_failed";
+
+/// Artifacts that could not be exported before eviction; see [`crate::gc`].
+pub const ARTIFACT_EXPORT_FAILURES: &str = "artifact_export_failures";
+
+/// Records statistics; shared by the engine, the state and every
+/// negotiation session.
+pub struct Stats {
+    store: Arc<InMemoryStateStore>,
+
+    /// Serializes the read-modify-write of aggregates.
+    update: Mutex<()>,
+}
+
+impl Stats {
+    /// Keep aggregates in `store`.
+    pub fn new(store: Arc<InMemoryStateStore>) -> Self {
+        Self { store, update: Mutex::new(()) }
+    }
+
+    /// Count one occurrence of `metric`.
+    pub async fn count(&self, metric: &'static str) {
+        self.count_n(metric, 1).await;
+    }
+
+    /// Count `n` occurrences of `metric`.
+    pub async fn count_n(&self, metric: &'static str, n: u64) {
+        metrics::counter!(format!("orpheon_{}", metric)).increment(n);
+        self.add(metric, n as f64).await;
+    }
+
+    /// Count one occurrence of `metric` for intents of `kind`.
+    pub async fn count_for_kind(&self, metric: &'static str, kind: &str) {
+        metrics::counter!(format!("orpheon_{}", metric), "kind" => kind.to_string()).increment(1);
+        self.add(&format!("{}:{}", metric, kind), 1.0).await;
+    }
+
+    /// Record a duration of `metric`.
+    pub async fn duration(&self, metric: &'static str, ms: u64) {
+        metrics::histogram!(format!("orpheon_{}_ms", metric)).record(ms as f64);
+        self.add(&format!("{}_ms", metric), ms as f64).await;
+    }
+
+    /// Add `amount` to today's aggregate of `metric`.
+    async fn add(&self, metric: &str, amount: f64) {
+        let key = Keys::stats(Utc::now().date_naive(), metric);
+        let _guard = self.update.lock().await;
+        let result = match self.store.get_typed::<f64>(&key).await {
+            Ok(current) => self.store.set_typed(&key, &(current.unwrap_or(0.0) + amount)).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Could not update statistic {}: {}", key, e);
+        }
+    }
+
+    /// Daily aggregates from `from` to `to`, both included, oldest first.
+    /// Days without any statistic are left out.
+    pub async fn days(&self, from: NaiveDate, to: NaiveDate) -> orpheon_core::Result<Vec<DayStats>> {
+        let mut days: BTreeMap<NaiveDate, BTreeMap<String, f64>> = BTreeMap::new();
+        for entry in self.store.get_prefix(Keys::stats_prefix()).await? {
+            let Some(ParsedKey::Stats { date, metric }) = Keys::parse(&entry.key) else {
+                continue;
+            };
+            if date < from || date > to {
+                continue;
+            }
+            if let Some(value) = entry.value.as_f64() {
+                days.entry(date).or_default().insert(metric, value);
+            }
+        }
+        Ok(days.into_iter().map(|(date, metrics)| DayStats { date, metrics }).collect())
+    }
+}
+
+#[async_trait]
+impl SessionObserver for Stats {
+    async fn observe(&self, _intent: &Intent, event: SessionEvent) {
+        match event {
+            SessionEvent::ProposalSent { .. } => self.count(PROPOSALS_SENT).await,
+            SessionEvent::Countered { .. } => self.count(PROPOSALS_COUNTERED).await,
+            SessionEvent::Resolved { state, rounds, elapsed_ms } => {
+                let outcome = match state {
+                    NegotiationState::Accepted => NEGOTIATIONS_ACCEPTED,
+                    NegotiationState::Rejected => NEGOTIATIONS_REJECTED,
+                    NegotiationState::TimedOut => NEGOTIATIONS_TIMED_OUT,
+                    _ => return,
+                };
+                self.count(outcome).await;
+                self.count_n(NEGOTIATION_ROUNDS, rounds as u64).await;
+                if state == NegotiationState::Accepted {
+                    self.duration(ACCEPTANCE_LATENCY, elapsed_ms).await;
+                }
+            }
+        }
+    }
+}
+
+/// The aggregates of one UTC day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Funnel figures over a range of days.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Funnel {
+    pub submitted: u64,
+    pub negotiating: u64,
+    pub proposals: u64,
+    pub countered: u64,
+    pub accepted: u64,
+    pub rejected: u64,
+    pub timed_out: u64,
+    /// Average rounds of the negotiations resolved.
+    pub average_rounds: Option<f64>,
+    pub average_acceptance_latency_ms: Option<f64>,
+    pub average_time_to_terminal_ms: Option<f64>,
+    /// Share of intents planned successfully, by kind.
+    pub planning_success_rate: BTreeMap<String, f64>,
+}
+
+impl Funnel {
+    /// Work out the funnel from aggregates summed over a range.
+    pub fn from_totals(totals: &BTreeMap<String, f64>) -> Self {
+        let count = |metric: &str| totals.get(metric).copied().unwrap_or(0.0) as u64;
+        let average = |sum: &str, n: u64| totals.get(sum).filter(|_| n > 0).map(|sum| sum / n as f64);
+
+        let accepted = count(NEGOTIATIONS_ACCEPTED);
+        let rejected = count(NEGOTIATIONS_REJECTED);
+        let timed_out = count(NEGOTIATIONS_TIMED_OUT);
+
+        let mut planning: BTreeMap<String, (f64, f64)> = BTreeMap::new();
+        for (metric, value) in totals {
+            if let Some(kind) = metric.strip_prefix(PLANNING_SUCCEEDED).and_then(|m| m.strip_prefix(':')) {
+                planning.entry(kind.to_string()).or_default().0 += value;
+            } else if let Some(kind) = metric.strip_prefix(PLANNING_FAILED).and_then(|m| m.strip_prefix(':')) {
+                planning.entry(kind.to_string()).or_default().1 += value;
+            }
+        }
+
+        Self {
+            submitted: count(INTENTS_SUBMITTED),
+            negotiating: count(INTENTS_NEGOTIATING),
+            proposals: count(PROPOSALS_SENT),
+            countered: count(PROPOSALS_COUNTERED),
+            accepted,
+            rejected,
+            timed_out,
+            average_rounds: average(NEGOTIATION_ROUNDS, accepted + rejected + timed_out),
+            average_acceptance_latency_ms: average(&format!("{}_ms", ACCEPTANCE_LATENCY), accepted),
+            average_time_to_terminal_ms: average(&format!("{}_ms", TIME_TO_TERMINAL), count(INTENTS_FINISHED)),
+            planning_success_rate: planning
+                .into_iter()
+                .map(|(kind, (succeeded, failed))| (kind, succeeded / (succeeded + failed)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counts_add_up_per_day() {
+        let stats = Stats::new(Arc::new(InMemoryStateStore::new()));
+        stats.count(INTENTS_SUBMITTED).await;
+        stats.count_n(INTENTS_SUBMITTED, 2).await;
+        stats.count_for_kind(PLANNING_FAILED, "deploy").await;
+        stats.duration(ACCEPTANCE_LATENCY, 120).await;
+
+        let today = Utc::now().date_naive();
+        let days = stats.days(today, today).await.unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].metrics[INTENTS_SUBMITTED], 3.0);
+        assert_eq!(days[0].metrics["planning_failed:deploy"], 1.0);
+        assert_eq!(days[0].metrics["acceptance_latency_ms"], 120.0);
+
+        let yesterday = today.pred_opt().unwrap();
+        assert!(stats.days(yesterday, yesterday).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_funnel_averages_and_rates() {
+        let totals: BTreeMap<String, f64> = [
+            (NEGOTIATIONS_ACCEPTED, 3.0),
+            (NEGOTIATIONS_REJECTED, 1.0),
+            (NEGOTIATION_ROUNDS, 6.0),
+            ("acceptance_latency_ms", 900.0),
+            ("planning_succeeded:deploy", 3.0),
+            ("planning_failed:deploy", 1.0),
+            ("planning_failed:backup", 2.0),
+        ]
+        .into_iter()
+        .map(|(metric, value)| (metric.to_string(), value))
+        .collect();
+
+        let funnel = Funnel::from_totals(&totals);
+        assert_eq!(funnel.average_rounds, Some(1.5));
+        assert_eq!(funnel.average_acceptance_latency_ms, Some(300.0));
+        assert_eq!(funnel.average_time_to_terminal_ms, None);
+        assert_eq!(funnel.planning_success_rate["deploy"], 0.75);
+        assert_eq!(funnel.planning_success_rate["backup"], 0.0);
+    }
+}
@@ -0,0 +1,175 @@
+//! In-process test harness.
+//!
+//! [`TestNode`] runs a real engine and API server on an ephemeral loopback
+//! port, so tests can drive it with the real SDK through
+//! [`TestNode::base_url`] and still reach into its [`AppState`] for
+//! assertions and fault injection: swap in a [`FailingExecutor`] with
+//! `AppState::set_step_executor`, or hold work back with
+//! `AppState::pause_engine`.
+//!
+//! The node shuts down when the `TestNode` is dropped. New end-to-end tests
+//! should use this rather than starting the server by hand; see
+//! `tests/quickstart.rs` for the pattern.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use orpheon_core::Step;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::backlog::BacklogMonitor;
+use crate::config::NodeConfig;
+use crate::engine::{Engine, SimulatedExecutor, StepContext, StepExecutor, StepOutput};
+use crate::gc::GarbageCollector;
+use crate::seed::{SeedData, SeedError};
+use crate::state::AppState;
+use crate::watchdog::DeadlineWatchdog;
+
+/// A node running in the current process.
+pub struct TestNode {
+    /// Address the API is served on.
+    pub addr: SocketAddr,
+
+    /// Shared state of the running node.
+    pub state: AppState,
+
+    engine: JoinHandle<()>,
+    watchdog: JoinHandle<()>,
+    backlog: JoinHandle<()>,
+    gc: JoinHandle<()>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl TestNode {
+    /// Start a node from a config, loading its seed data if it has any.
+    ///
+    /// The configured bind address is ignored; the node always listens on
+    /// an ephemeral loopback port. Panics if the seed data cannot be loaded.
+    pub async fn spawn(config: NodeConfig) -> Self {
+        let state = crate::build_state(&config).await.expect("failed to build node state");
+        Self::with_state(state).await
+    }
+
+    /// Start a node around existing state.
+    pub async fn with_state(state: AppState) -> Self {
+        let engine = Arc::new(Engine::new(state.clone()));
+        let engine = tokio::spawn(async move {
+            engine.run().await;
+        });
+        let watchdog = Arc::new(DeadlineWatchdog::new(state.clone()));
+        let watchdog = tokio::spawn(async move {
+            watchdog.run().await;
+        });
+        let backlog = Arc::new(BacklogMonitor::new(state.clone()));
+        let backlog = tokio::spawn(async move {
+            backlog.run().await;
+        });
+        let gc = Arc::new(GarbageCollector::new(state.clone()));
+        let gc = tokio::spawn(async move {
+            gc.run().await;
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = crate::create_router(state.clone());
+        let (shutdown, signal) = oneshot::channel();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(async move {
+                    let _ = signal.await;
+                })
+                .await
+                .unwrap();
+        });
+
+        Self {
+            addr,
+            state,
+            engine,
+            watchdog,
+            backlog,
+            gc,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Load a seed directory and start a node from it.
+    pub async fn seeded(dir: impl AsRef<Path>) -> Result<Self, SeedError> {
+        let state = SeedData::load(dir.as_ref())?.into_state().await?;
+        Ok(Self::with_state(state).await)
+    }
+
+    /// HTTP base URL of the node.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// WebSocket base URL of the node.
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        self.engine.abort();
+        self.watchdog.abort();
+        self.backlog.abort();
+        self.gc.abort();
+    }
+}
+
+/// Load a seed directory and start a node from it.
+///
+/// Shorthand for [`TestNode::seeded`].
+pub async fn spawn_seeded(dir: impl AsRef<Path>) -> Result<TestNode, SeedError> {
+    TestNode::seeded(dir).await
+}
+
+/// Step executor that fails selected steps and simulates the rest.
+#[derive(Debug, Clone)]
+pub struct FailingExecutor {
+    action: Option<String>,
+    reason: String,
+}
+
+impl FailingExecutor {
+    /// Fail every step.
+    pub fn always() -> Self {
+        Self {
+            action: None,
+            reason: "injected failure".to_string(),
+        }
+    }
+
+    /// Fail only steps running the given action.
+    pub fn on_action(action: impl Into<String>) -> Self {
+        Self {
+            action: Some(action.into()),
+            ..Self::always()
+        }
+    }
+
+    /// Set the failure reason reported for failed steps.
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = reason.into();
+        self
+    }
+}
+
+#[async_trait]
+impl StepExecutor for FailingExecutor {
+    async fn execute(&self, step: &Step, ctx: &StepContext) -> Result<StepOutput, String> {
+        match &self.action {
+            Some(action) if *action != step.action => SimulatedExecutor::default().execute(step, ctx).await,
+            _ => Err(self.reason.clone()),
+        }
+    }
+}
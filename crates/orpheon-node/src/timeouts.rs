@@ -0,0 +1,163 @@
+//! Per-route request time budgets and slow-request logging.
+//!
+//! Routes fall into a [`RouteGroup`]: quick reads and writes of intents get
+//! a short budget, planning a long one, and streams none at all. A request
+//! that outlives its budget is answered with `504 Gateway Timeout` (code
+//! `timeout`) and its handler is dropped; handlers doing blocking work hold
+//! a [`DropGuard`](orpheon_planner::cancel::DropGuard) so the work stops
+//! with them. Requests slower than [`RequestTimeoutConfig::slow_request_ms`]
+//! are logged and counted along with the intent they were about.
+
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api::error::ApiError;
+
+/// Time budgets of each route group, in milliseconds; `None` disables the
+/// timeout.
+#[derive(Debug, Clone)]
+pub struct RequestTimeoutConfig {
+    /// Budget of intent, catalog and admin requests.
+    pub crud_ms: Option<u64>,
+
+    /// Budget of requests that run the planner, e.g. `/api/v1/simulate`.
+    pub planning_ms: Option<u64>,
+
+    /// Requests taking at least this long are logged and counted.
+    pub slow_request_ms: u64,
+}
+
+impl Default for RequestTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            crud_ms: Some(10_000),
+            planning_ms: Some(60_000),
+            slow_request_ms: 1_000,
+        }
+    }
+}
+
+impl RequestTimeoutConfig {
+    /// Budget of requests in `group`, if it has one.
+    pub fn budget(&self, group: RouteGroup) -> Option<Duration> {
+        match group {
+            RouteGroup::Crud => self.crud_ms,
+            RouteGroup::Planning => self.planning_ms,
+            RouteGroup::Streaming => None,
+        }
+        .map(Duration::from_millis)
+    }
+}
+
+/// Kinds of route sharing a time budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    Crud,
+    Planning,
+    /// WebSockets and bulk exports, which last as long as the client reads.
+    Streaming,
+}
+
+impl RouteGroup {
+    /// The group of a route, given its path pattern, e.g. `/api/v1/intent/:id`.
+    pub fn of(route: &str) -> Self {
+        if route.starts_with("/ws/") || route.starts_with("/api/v1/export/") || route.ends_with("/artifact/bundle") {
+            RouteGroup::Streaming
+        } else if route == "/api/v1/simulate" {
+            RouteGroup::Planning
+        } else {
+            RouteGroup::Crud
+        }
+    }
+}
+
+/// The intent a request is about, for the slow-request log. Taken from the
+/// path where it has an intent id; handlers that make one up, like
+/// simulation, [`record`](Self::record) it themselves.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIntent(Arc<OnceLock<Uuid>>);
+
+impl RequestIntent {
+    /// Note the intent the request is about; later calls are ignored.
+    pub fn record(&self, intent_id: Uuid) {
+        let _ = self.0.set(intent_id);
+    }
+
+    /// The intent noted, if any.
+    pub fn get(&self) -> Option<Uuid> {
+        self.0.get().copied()
+    }
+}
+
+/// Apply the route's time budget and log the request if it was slow.
+pub async fn enforce(State(config): State<RequestTimeoutConfig>, mut request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path().to_string(), |path| path.as_str().to_string());
+    let intent = RequestIntent::default();
+    if let Some(id) = request.uri().path().split('/').find_map(|segment| segment.parse::<Uuid>().ok()) {
+        intent.record(id);
+    }
+    request.extensions_mut().insert(intent.clone());
+
+    let group = RouteGroup::of(&route);
+    let start = Instant::now();
+    let response = match config.budget(group) {
+        Some(budget) => match tokio::time::timeout(budget, next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => {
+                metrics::counter!("orpheon_request_timeouts_total", "route" => route.clone()).increment(1);
+                ApiError::new(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    "timeout",
+                    format!("Request took longer than its {}ms budget", budget.as_millis()),
+                )
+                .into_response()
+            }
+        },
+        None => next.run(request).await,
+    };
+
+    let elapsed = start.elapsed();
+    if group != RouteGroup::Streaming && elapsed >= Duration::from_millis(config.slow_request_ms) {
+        let duration_ms = elapsed.as_millis() as u64;
+        let intent_id = intent.get().map(|id| id.to_string()).unwrap_or_default();
+        warn!(
+            route = %route,
+            duration_ms,
+            intent_id = %intent_id,
+            status = response.status().as_u16(),
+            "🐢 Slow request"
+        );
+        metrics::counter!("orpheon_slow_requests_total", "route" => route.clone()).increment(1);
+        metrics::histogram!("orpheon_slow_request_duration_ms", "route" => route).record(duration_ms as f64);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_groups() {
+        assert_eq!(RouteGroup::of("/api/v1/intent/:id"), RouteGroup::Crud);
+        assert_eq!(RouteGroup::of("/api/v1/simulate"), RouteGroup::Planning);
+        assert_eq!(RouteGroup::of("/ws/intent/:id"), RouteGroup::Streaming);
+        assert_eq!(RouteGroup::of("/api/v1/export/artifacts"), RouteGroup::Streaming);
+        assert_eq!(RouteGroup::of("/api/v1/intent/:id/artifact/bundle"), RouteGroup::Streaming);
+
+        let config = RequestTimeoutConfig { planning_ms: None, ..Default::default() };
+        assert_eq!(config.budget(RouteGroup::Crud), Some(Duration::from_secs(10)));
+        assert_eq!(config.budget(RouteGroup::Planning), None);
+        assert_eq!(config.budget(RouteGroup::Streaming), None);
+    }
+}
@@ -0,0 +1,147 @@
+//! Deadline watchdog.
+//!
+//! Runs beside the engine and periodically looks up intents due within
+//! [`DeadlineWatchdogConfig::horizon_ms`] in the constraint index. An
+//! intent whose remaining critical path, the estimated durations of its
+//! plan's steps not done yet, is longer than the time left before its
+//! deadline gets a [`DEADLINE_AT_RISK`] warning on its stream. Each intent
+//! is warned at most once.
+//!
+//! [`DeadlineWatchdogConfig::horizon_ms`]: crate::config::DeadlineWatchdogConfig::horizon_ms
+
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::state::{AppState, IntentRecord, IntentWarning};
+
+/// Warning code for intents likely to miss their deadline.
+pub const DEADLINE_AT_RISK: &str = "deadline_at_risk";
+
+/// Flags intents that are likely to miss their deadline.
+pub struct DeadlineWatchdog {
+    state: AppState,
+}
+
+impl DeadlineWatchdog {
+    /// Create a watchdog over a node's intents.
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Check deadlines every configured interval, forever.
+    pub async fn run(self: Arc<Self>) {
+        info!("⏰ Deadline watchdog started");
+
+        loop {
+            self.check().await;
+            sleep(Duration::from_millis(self.state.deadline_watchdog.interval_ms)).await;
+        }
+    }
+
+    /// Check the intents due within the horizon once, returning those warned
+    /// by this check.
+    pub async fn check(&self) -> Vec<Uuid> {
+        let now = Utc::now();
+        let horizon = now + chrono::Duration::milliseconds(self.state.deadline_watchdog.horizon_ms as i64);
+        let mut warned = Vec::new();
+
+        for (id, deadline) in self.state.intents_with_deadline_before(horizon).await {
+            let Some(record) = self.state.get_intent(id).await else {
+                continue;
+            };
+            // Forwarded intents are watched by the node running them
+            if record.forwarded.is_some() || record.warnings.iter().any(|w| w.code == DEADLINE_AT_RISK) {
+                continue;
+            }
+
+            let remaining_ms = self.remaining_ms(&record).await;
+            let left_ms = (deadline - now).num_milliseconds();
+            if remaining_ms as i64 <= left_ms {
+                continue;
+            }
+
+            warn!("⏰ Intent {} needs about {} ms more but is due in {} ms", id, remaining_ms, left_ms);
+            let message = format!(
+                "Intent {} is estimated to need {} ms more, but its deadline {} is {} ms away",
+                id,
+                remaining_ms,
+                deadline.to_rfc3339(),
+                left_ms
+            );
+            self.state.add_warning(id, IntentWarning { code: DEADLINE_AT_RISK.to_string(), message }).await;
+            warned.push(id);
+        }
+        warned
+    }
+
+    /// Estimated time left for the intent's active plan; segments run one
+    /// after another. An intent without a plan yet counts as no work left.
+    async fn remaining_ms(&self, record: &IntentRecord) -> u64 {
+        let Some(plan) = self.state.get_plan_for_intent(record.intent.id).await else {
+            return 0;
+        };
+        self.state
+            .plan_segments(plan)
+            .await
+            .iter()
+            .map(|segment| segment.critical_path_ms(&record.steps_done))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use orpheon_core::{Constraint, Intent, Plan, PlanningStrategy, Step};
+
+    /// Store an intent due at `deadline` with a chain of steps of the given
+    /// durations, returning the intent and step IDs.
+    async fn store(state: &AppState, deadline: DateTime<Utc>, durations: &[u64]) -> (Uuid, Vec<Uuid>) {
+        let intent = Intent::builder().kind("deploy").constraint(Constraint::Deadline { by: deadline }).build().unwrap();
+        let intent_id = intent.id;
+        state.store_intent(intent).await;
+
+        let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+        let mut previous: Option<Uuid> = None;
+        for (i, duration) in durations.iter().enumerate() {
+            let mut step = Step::new(format!("step_{}", i), "work").with_duration(*duration);
+            if let Some(previous) = previous {
+                step = step.depends_on(previous);
+            }
+            previous = Some(step.id);
+            plan.add_step(step);
+        }
+        let step_ids = plan.steps.iter().map(|s| s.id).collect();
+        state.store_plan(plan).await;
+        (intent_id, step_ids)
+    }
+
+    #[tokio::test]
+    async fn test_warns_intents_that_cannot_make_their_deadline() {
+        let state = AppState::new();
+        let now = Utc::now();
+        let (late, _) = store(&state, now + chrono::Duration::seconds(2), &[10_000, 10_000]).await;
+        let (on_time, _) = store(&state, now + chrono::Duration::minutes(2), &[100, 100]).await;
+        let (far_off, _) = store(&state, now + chrono::Duration::minutes(10), &[20 * 60_000]).await;
+        let (progressed, steps) = store(&state, now + chrono::Duration::seconds(90), &[60_000, 60_000]).await;
+        state.record_steps_done(progressed, [steps[0]]).await;
+
+        let watchdog = DeadlineWatchdog::new(state.clone());
+        assert_eq!(watchdog.check().await, vec![late]);
+        let warnings = state.get_intent(late).await.unwrap().warnings;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, DEADLINE_AT_RISK);
+        for id in [on_time, far_off, progressed] {
+            assert!(state.get_intent(id).await.unwrap().warnings.is_empty());
+        }
+
+        // Each intent is warned once
+        assert!(watchdog.check().await.is_empty());
+        assert_eq!(state.get_intent(late).await.unwrap().warnings.len(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+//! End-to-end tests of operator annotations on plans and artifacts.
+
+use std::time::Duration;
+
+use orpheon_node::config::NodeConfig;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use orpheon_state::{Keys, StateStoreExt};
+use tokio::time::timeout;
+
+async fn completed(node: &TestNode) -> (OrpheonClient, ExecutionArtifact) {
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let completion = timeout(Duration::from_secs(15), client.submit_and_wait(intent))
+        .await
+        .expect("intent did not finish in time")
+        .unwrap();
+    (client, completion.into_artifact())
+}
+
+#[tokio::test]
+async fn test_step_annotations_appear_without_changing_verification() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let (client, artifact) = completed(&node).await;
+    let intent_id = artifact.final_plan.intent_id;
+    let step_id = artifact.final_plan.steps[0].id;
+    let (_, before) = client.get_verified_artifact(intent_id).await.unwrap();
+    assert!(before.is_valid());
+    assert_eq!(before.not_covered, vec!["annotations".to_string()]);
+    
+    let hung = client
+        .annotate_artifact(
+            intent_id,
+            &NewAnnotation::new("oncall", "this step hung due to quota exhaustion").on_step(step_id).label("quota"),
+        )
+        .await
+        .unwrap();
+    client
+        .annotate_artifact(intent_id, &NewAnnotation::new("oncall", "reviewed").label("postmortem"))
+        .await
+        .unwrap();
+    
+    let annotated = client.get_annotated_artifact(intent_id, None).await.unwrap();
+    assert_eq!(annotated.record.id, artifact.id);
+    assert_eq!(annotated.annotations.len(), 2);
+    assert_eq!(annotated.annotations[0], hung);
+    assert_eq!(hung.target_step_id, Some(step_id));
+    
+    let quota = client.get_annotated_artifact(intent_id, Some("quota")).await.unwrap();
+    assert_eq!(quota.annotations, vec![hung.clone()]);
+    
+    // The artifact itself, and what verifying it finds, are unchanged
+    let (served, after) = client.get_verified_artifact(intent_id).await.unwrap();
+    assert_eq!(served.merkle_root, artifact.merkle_root);
+    assert_eq!(served.content_hash(), artifact.content_hash());
+    let statuses = |report: &VerificationReport| report.checks.iter().map(|c| (c.kind, c.status)).collect::<Vec<_>>();
+    assert_eq!(statuses(&after), statuses(&before));
+    
+    // Mirrored into the state store
+    let stored: Vec<Annotation> = node
+        .state
+        .state_store
+        .get_typed(&Keys::artifact_annotations(artifact.id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.len(), 2);
+}
+
+#[tokio::test]
+async fn test_plan_annotations_are_served_with_the_plan() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let (client, artifact) = completed(&node).await;
+    let plan = client.get_plan(artifact.final_plan.intent_id).await.unwrap();
+    
+    let note = client
+        .annotate_plan(plan.id, &NewAnnotation::new("oncall", "expected two regions").label("capacity"))
+        .await
+        .unwrap();
+    
+    let annotated = client.get_annotated_plan(artifact.final_plan.intent_id, None).await.unwrap();
+    assert_eq!(annotated.record.id, plan.id);
+    assert_eq!(annotated.annotations, vec![note]);
+    assert!(client
+        .get_annotated_plan(artifact.final_plan.intent_id, Some("quota"))
+        .await
+        .unwrap()
+        .annotations
+        .is_empty());
+    
+    // The SDK's plain getter still reads the annotated response
+    assert_eq!(client.get_plan(artifact.final_plan.intent_id).await.unwrap().id, plan.id);
+}
+
+#[tokio::test]
+async fn test_rejects_bad_annotations() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let (client, artifact) = completed(&node).await;
+    
+    let unknown_plan = client.annotate_plan(uuid::Uuid::new_v4(), &NewAnnotation::new("oncall", "hi")).await;
+    assert!(matches!(unknown_plan, Err(OrpheonError::NotFound { .. })));
+    
+    let unknown_step = client
+        .annotate_artifact(artifact.final_plan.intent_id, &NewAnnotation::new("oncall", "hi").on_step(uuid::Uuid::new_v4()))
+        .await;
+    assert!(unknown_step.is_err());
+    
+    let empty = client.annotate_artifact(artifact.final_plan.intent_id, &NewAnnotation::new("oncall", " ")).await;
+    assert!(empty.is_err());
+    assert!(client.get_annotated_artifact(artifact.final_plan.intent_id, None).await.unwrap().annotations.is_empty());
+}
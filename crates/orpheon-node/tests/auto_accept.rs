@@ -0,0 +1,80 @@
+//! End-to-end tests of accepting proposals under a client's auto-accept
+//! bounds.
+
+use std::time::Duration;
+
+use orpheon_sdk::prelude::*;
+use orpheon_sdk::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use tokio::time::{sleep, timeout};
+use uuid::Uuid;
+
+async fn client(node: &TestNode) -> OrpheonClient {
+    OrpheonClient::connect(&node.base_url())
+        .await
+        .unwrap()
+        .with_node_public_key(node.state.node_key.public_key_hex())
+}
+
+fn deploy() -> Intent {
+    Intent::builder().kind("deploy").budget(Budget::usd(100.0)).build().unwrap()
+}
+
+async fn wait_for(client: &OrpheonClient, intent_id: Uuid, status: &str) {
+    timeout(Duration::from_secs(10), async {
+        while client.get_intent(intent_id).await.unwrap().status != status {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("intent never became {}", status));
+}
+
+#[tokio::test]
+async fn test_proposal_within_bounds_is_auto_accepted() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = client(&node).await;
+
+    let options = NegotiationOptions::default().auto_accept_below_cost(50.0).auto_accept_within_latency_ms(60_000);
+    let intent_id = client.submit_negotiated(deploy(), options).await.unwrap();
+    wait_for(&client, intent_id, "complete").await;
+
+    assert!(client.get_intent(intent_id).await.unwrap().auto_accepted_by_policy);
+    let (receipt, report) = client.get_verified_receipt(intent_id).await.unwrap();
+    assert!(receipt.auto_accepted_by_policy);
+    assert!(report.is_valid(), "{:?}", report);
+}
+
+#[tokio::test]
+async fn test_proposal_over_bounds_is_negotiated() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = client(&node).await;
+
+    let options = NegotiationOptions::default().auto_accept_below_cost(0.0);
+    let intent_id = client.submit_negotiated(deploy(), options).await.unwrap();
+    wait_for(&client, intent_id, "negotiating").await;
+
+    // Still waiting on the client, who accepts by hand
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(client.get_intent(intent_id).await.unwrap().status, "negotiating");
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+    let proposal = negotiation.next_proposal().await.unwrap().unwrap();
+    assert!(proposal.quoted_cost > 0.0);
+    negotiation.accept(proposal.id).await.unwrap();
+    wait_for(&client, intent_id, "complete").await;
+
+    assert!(!client.get_intent(intent_id).await.unwrap().auto_accepted_by_policy);
+    assert!(!client.get_receipt(intent_id).await.unwrap().auto_accepted_by_policy);
+}
+
+#[tokio::test]
+async fn test_bounds_above_budget_are_rejected() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = client(&node).await;
+
+    let options = NegotiationOptions::default().auto_accept_below_cost(500.0);
+    let err = client.submit_negotiated(deploy(), options).await.unwrap_err();
+    assert!(err.to_string().contains("invalid_negotiation_options"), "{}", err);
+    assert!(err.to_string().contains("max_cost"), "{}", err);
+}
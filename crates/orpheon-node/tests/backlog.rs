@@ -0,0 +1,38 @@
+//! End-to-end tests of backlog age alerts.
+
+use std::time::Duration;
+
+use orpheon_core::IntentStatus;
+use orpheon_node::backlog::BACKLOG_AGE;
+use orpheon_node::config::BacklogConfig;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_stuck_intent_raises_system_notice() {
+    let mut state = AppState::new();
+    state.backlog = BacklogConfig { interval_ms: 20, received_alert_ms: Some(200), ..Default::default() };
+    // A frozen engine leaves the intent in Received
+    state.pause_engine();
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut stream = client.watch_intents(WatchFilter::default()).await.unwrap();
+
+    let intent_id = client.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    let (id, code, message) = timeout(Duration::from_secs(10), async {
+        loop {
+            if let (id, Event::SystemNotice { code, message }) = stream.next().await.expect("stream ended early") {
+                break (id, code, message);
+            }
+        }
+    })
+    .await
+    .expect("no notice was sent");
+    assert_eq!(id, Uuid::nil());
+    assert_eq!(code, BACKLOG_AGE);
+    assert!(message.contains(&intent_id.to_string()), "{}", message);
+    assert_eq!(node.state.get_intent(intent_id).await.unwrap().status, IntentStatus::Received);
+}
@@ -0,0 +1,104 @@
+//! End-to-end tests of `${...}` bindings in step parameters.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_core::{IntentStatus, Step};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use serde_json::json;
+use tokio::time::{sleep, timeout};
+
+/// Executor that records the parameters each step ran with.
+#[derive(Default)]
+struct RecordingExecutor {
+    parameters: Mutex<Vec<serde_json::Value>>,
+}
+
+#[async_trait]
+impl StepExecutor for RecordingExecutor {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        self.parameters.lock().unwrap().push(step.parameters.clone());
+        Ok(StepOutput::new(1))
+    }
+}
+
+async fn node_with(parameters: serde_json::Value) -> (TestNode, Arc<RecordingExecutor>) {
+    let catalog = vec![PlanningAction {
+        name: "provision_compute".to_string(),
+        effects: vec!["complete".to_string()],
+        cost: 1.0,
+        parameters,
+        ..Default::default()
+    }];
+    let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    let executor = Arc::new(RecordingExecutor::default());
+    state.set_step_executor(executor.clone()).await;
+    (TestNode::with_state(state).await, executor)
+}
+
+fn intent_in(region: &str) -> Intent {
+    Intent::builder()
+        .kind("deploy")
+        .metadata(json!({ "region": region }))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_step_runs_with_bound_metadata() {
+    let (node, executor) = node_with(json!({ "region": "${intent.metadata.region}", "kind": "${intent.kind}" })).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(intent_in("eu-west")).await.unwrap();
+    let intent_id = stream.intent_id();
+    timeout(Duration::from_secs(15), async {
+        while !matches!(stream.next().await.expect("stream ended early"), Event::Complete { .. }) {}
+    })
+    .await
+    .expect("intent did not complete in time");
+
+    assert_eq!(*executor.parameters.lock().unwrap(), vec![json!({ "region": "eu-west", "kind": "deploy" })]);
+
+    // The stored plan keeps the template; the start event records what it resolved to
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    assert_eq!(artifact.final_plan.steps[0].parameters["region"], "${intent.metadata.region}");
+    let started = artifact
+        .trace
+        .iter()
+        .find(|e| e.event_type == ExecutionEventType::StepStarted)
+        .unwrap();
+    assert_eq!(started.data["bindings"]["intent.metadata.region"], "eu-west");
+    assert_eq!(started.data["bindings"]["intent.kind"], "deploy");
+}
+
+#[tokio::test]
+async fn test_missing_binding_fails_before_execution() {
+    let (node, executor) = node_with(json!({ "zone": "${intent.metadata.zone}" })).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent_id = client.submit_detached(intent_in("eu-west")).await.unwrap();
+    let record = timeout(Duration::from_secs(15), async {
+        loop {
+            let record = node.state.get_intent(intent_id).await.unwrap();
+            if record.status.is_terminal() {
+                break record;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time");
+
+    assert_eq!(record.status, IntentStatus::Failed);
+    assert!(record.error.unwrap().contains("${intent.metadata.zone}"));
+    assert!(record.plan_ids.is_empty());
+    assert!(executor.parameters.lock().unwrap().is_empty());
+    assert!(node.state.artifacts.read().await.is_empty());
+}
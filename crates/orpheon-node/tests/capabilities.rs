@@ -0,0 +1,81 @@
+//! Tests of matching intent requirements against node capabilities.
+
+use std::time::Duration;
+
+use orpheon_core::IntentStatus;
+use orpheon_node::federation::{FederationConfig, PeerConfig};
+use orpheon_node::state::IntentRecord;
+use orpheon_node::testing::TestNode;
+use orpheon_node::NodeConfig;
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+use uuid::Uuid;
+
+fn node_config(node_id: &str, capabilities: &[&str]) -> NodeConfig {
+    NodeConfig {
+        federation: FederationConfig {
+            node_id: Some(node_id.to_string()),
+            poll_interval_ms: 50,
+            ..Default::default()
+        },
+        capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+fn needs_gpu() -> Intent {
+    Intent::builder()
+        .kind("deploy")
+        .metadata(serde_json::json!({ "requirements": ["capability:gpu"] }))
+        .build()
+        .unwrap()
+}
+
+async fn finished(node: &TestNode, intent_id: Uuid) -> IntentRecord {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let record = node.state.get_intent(intent_id).await.unwrap();
+            if record.status.is_terminal() {
+                break record;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time")
+}
+
+#[tokio::test]
+async fn test_missing_capability_fails_before_planning() {
+    let node = TestNode::spawn(node_config("node-a", &["region:us-east-1"])).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    // The SDK can tell up front
+    let capabilities = client.capabilities().await.unwrap();
+    assert_eq!(capabilities.node_id.as_deref(), Some("node-a"));
+    assert_eq!(capabilities.missing_for(&needs_gpu()), vec!["capability:gpu"]);
+
+    let intent_id = client.submit(needs_gpu()).await.unwrap().intent_id();
+    let record = finished(&node, intent_id).await;
+    assert_eq!(record.status, IntentStatus::Failed);
+    assert!(record.plan_ids.is_empty());
+    let error = record.error.unwrap();
+    assert!(error.contains("requires capabilities this node lacks: capability:gpu"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_intent_is_forwarded_to_capable_peer() {
+    let remote = TestNode::spawn(node_config("node-b", &["capability:gpu"])).await;
+    let local = TestNode::spawn(node_config("node-a", &[])).await;
+    local.state.federation.add_peer("node-b", PeerConfig { base_url: remote.base_url(), api_key: None });
+
+    let client = OrpheonClient::connect(&local.base_url()).await.unwrap();
+    let intent_id = client.submit(needs_gpu()).await.unwrap().intent_id();
+    let record = finished(&local, intent_id).await;
+    assert_eq!(record.status, IntentStatus::Complete);
+    assert_eq!(record.forwarded.unwrap().node_id, "node-b");
+
+    // Intents the node can run itself stay local
+    let local_id = client.submit(Intent::builder().kind("deploy").build().unwrap()).await.unwrap().intent_id();
+    assert!(finished(&local, local_id).await.forwarded.is_none());
+}
@@ -0,0 +1,160 @@
+//! End-to-end tests of failure handling, driven by fault injection.
+
+use std::time::Duration;
+
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_node::chaos::{ChaosConfig, FaultInjector, FaultRule};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_node::NodeConfig;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+
+fn chaos_config(rules: Vec<FaultRule>) -> NodeConfig {
+    NodeConfig {
+        chaos: ChaosConfig { unsafe_chaos: true, rules },
+        ..Default::default()
+    }
+}
+
+fn deploy_intent() -> Intent {
+    Intent::builder().kind("deploy").build().unwrap()
+}
+
+/// Submit an intent and wait for its artifact.
+async fn run_to_artifact(client: &OrpheonClient, intent: Intent) -> ExecutionArtifact {
+    let mut stream = client.submit(intent).await.unwrap();
+    let intent_id = stream.intent_id();
+    timeout(Duration::from_secs(15), async {
+        loop {
+            match stream.next().await.expect("stream ended early") {
+                Event::Complete { .. } => break,
+                Event::StatusUpdate { status, .. } if status == "failed" => break,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("intent did not finish in time");
+    client.get_artifact(intent_id).await.unwrap()
+}
+
+fn events_of(artifact: &ExecutionArtifact, event_type: ExecutionEventType) -> Vec<&ExecutionEvent> {
+    artifact.trace.iter().filter(|e| e.event_type == event_type).collect()
+}
+
+fn step_action(artifact: &ExecutionArtifact, event: &ExecutionEvent) -> String {
+    let step = artifact.final_plan.steps.iter().find(|s| s.id == event.step_id).unwrap();
+    step.action.clone()
+}
+
+#[tokio::test]
+async fn test_retry_then_succeed() {
+    let rule = FaultRule::fail_step("deploy_workload", "flaky network").on_attempt(1);
+    let node = TestNode::spawn(chaos_config(vec![rule])).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let artifact = run_to_artifact(&client, deploy_intent()).await;
+    assert!(artifact.outcome.is_success());
+    assert!(artifact.failed_steps().is_empty());
+
+    let retries = events_of(&artifact, ExecutionEventType::StepRetrying);
+    assert_eq!(retries.len(), 1);
+    assert_eq!(step_action(&artifact, retries[0]), "deploy_workload");
+    assert_eq!(retries[0].data["attempt"], 1);
+    assert_eq!(retries[0].data["error"], "flaky network");
+
+    let (_, report) = client.get_verified_artifact(artifact.intent.id).await.unwrap();
+    assert!(report.is_valid());
+}
+
+#[tokio::test]
+async fn test_persistent_failure_compensates_in_reverse() {
+    let rule = FaultRule::fail_step("verify_health", "unhealthy");
+    let node = TestNode::spawn(chaos_config(vec![rule])).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let artifact = run_to_artifact(&client, deploy_intent()).await;
+    match &artifact.outcome {
+        Outcome::Failure { reason, compensated } => {
+            assert!(reason.contains("unhealthy"), "{}", reason);
+            assert!(compensated);
+        }
+        other => panic!("expected failure, got {:?}", other),
+    }
+
+    // Every allowed retry was used before giving up
+    let retries = events_of(&artifact, ExecutionEventType::StepRetrying);
+    assert_eq!(retries.len(), 3);
+    assert_eq!(artifact.failed_steps().len(), 1);
+
+    let undone: Vec<String> = events_of(&artifact, ExecutionEventType::CompensationStarted)
+        .into_iter()
+        .map(|e| e.data["action"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(undone, vec!["undeploy_workload", "deprovision_compute", "release_resource"]);
+    assert_eq!(events_of(&artifact, ExecutionEventType::CompensationCompleted).len(), 3);
+}
+
+#[tokio::test]
+async fn test_timed_out_attempt_is_retried() {
+    let catalog = vec![PlanningAction {
+        name: "call_api".to_string(),
+        effects: vec!["complete".to_string()],
+        cost: 1.0,
+        duration_ms: 50,
+        timeout_ms: Some(300),
+        ..Default::default()
+    }];
+    let mut state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    let injector = state.enable_chaos(FaultInjector::default()).await;
+    injector.add(FaultRule::delay_action("call_api", 2_000).on_attempt(1));
+
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let artifact = run_to_artifact(&client, deploy_intent()).await;
+    assert!(artifact.outcome.is_success());
+
+    let timeouts = events_of(&artifact, ExecutionEventType::Timeout);
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!(timeouts[0].duration_ms, Some(300));
+    let retries = events_of(&artifact, ExecutionEventType::StepRetrying);
+    assert_eq!(retries[0].data["error"], "timed out after 300ms");
+}
+
+#[tokio::test]
+async fn test_executor_panic_fails_only_that_intent() {
+    let node = TestNode::spawn(chaos_config(vec![])).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let injector = node.state.chaos.clone().unwrap();
+
+    // Hold the engine so the rule is in place before the intent runs
+    node.state.pause_engine();
+    let mut stream = client.submit(deploy_intent()).await.unwrap();
+    let intent_id = stream.intent_id();
+    let rule = injector.add(FaultRule::panic_intent(intent_id, "simulated crash"));
+    node.state.resume_engine();
+
+    timeout(Duration::from_secs(15), async {
+        loop {
+            if let Event::StatusUpdate { status, .. } = stream.next().await.expect("stream ended early") {
+                if status == "failed" {
+                    break;
+                }
+            }
+        }
+    })
+    .await
+    .expect("intent did not fail in time");
+
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert!(record.error.unwrap().contains("executor panicked: simulated crash"));
+
+    // The engine survived and runs the next intent normally
+    assert!(injector.remove(rule.id));
+    let artifact = run_to_artifact(&client, deploy_intent()).await;
+    assert!(artifact.outcome.is_success());
+}
@@ -0,0 +1,152 @@
+//! Process tests of the offline `orpheon-node` subcommands.
+
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use orpheon_node::cli::{EXIT_INTERNAL, EXIT_INVALID};
+use orpheon_sdk::VerificationReport;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cli").join(name)
+}
+
+fn node() -> Command {
+    Command::cargo_bin("orpheon-node").unwrap()
+}
+
+/// A fresh path in the temp directory.
+fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("orpheon-cli-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &std::process::Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn test_plan_yaml_intent_with_catalog() {
+    let output = node()
+        .args(["plan", "--intent-file"])
+        .arg(fixture("backup.yaml"))
+        .arg("--catalog")
+        .arg(fixture("actions.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let plan: orpheon_core::Plan = serde_json::from_str(&stdout(&output)).unwrap();
+    let actions: Vec<&str> = plan.steps.iter().map(|s| s.action.as_str()).collect();
+    assert_eq!(actions, vec!["snapshot_volume", "upload_snapshot"]);
+    assert_eq!(plan.estimated_cost, 1.0);
+}
+
+#[test]
+fn test_plan_as_dot() {
+    let output = node()
+        .args(["plan", "--format", "dot", "--intent-file"])
+        .arg(fixture("backup.yaml"))
+        .arg("--catalog")
+        .arg(fixture("actions.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let dot = stdout(&output);
+    assert!(dot.starts_with("digraph "), "{}", dot);
+    assert!(dot.contains("snapshot_volume") && dot.contains("upload_snapshot"));
+    assert_eq!(dot.matches(" -> ").count(), 1);
+}
+
+#[test]
+fn test_plan_exit_codes() {
+    // An invalid intent is a validation failure
+    let output = node().args(["plan", "--intent-file"]).arg(fixture("invalid.yaml")).output().unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_INVALID));
+    assert!(stderr(&output).contains("invalid.yaml"), "{}", stderr(&output));
+
+    // A missing file is not
+    let output = node().args(["plan", "--intent-file"]).arg(fixture("missing.yaml")).output().unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_INTERNAL));
+
+    // Neither is a usage error
+    let output = node().args(["plan"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_verify_artifact_reports_merkle_version() {
+    let v1 = Path::new(env!("CARGO_MANIFEST_DIR")).join("../orpheon-core/fixtures/artifact_merkle_v1.json");
+    let output = node().args(["verify-artifact", "--file"]).arg(&v1).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let report: VerificationReport = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(report.merkle_version, 1);
+    assert!(report.is_valid());
+
+    // A tampered trace still prints the report, and fails validation
+    let mut artifact: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&v1).unwrap()).unwrap();
+    artifact["trace"][1]["duration_ms"] = serde_json::json!(1);
+    let tampered = temp_path("artifact.json");
+    std::fs::write(&tampered, artifact.to_string()).unwrap();
+
+    let output = node().args(["verify-artifact", "--file"]).arg(&tampered).output().unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_INVALID));
+    let report: VerificationReport = serde_json::from_str(&stdout(&output)).unwrap();
+    assert!(!report.is_valid());
+}
+
+#[test]
+fn test_import_then_export_state() {
+    let store = temp_path("store.json");
+    let output = node()
+        .args(["import-state", "--store"])
+        .arg(&store)
+        .arg("--file")
+        .arg(fixture("state.json"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = node().args(["export-state", "--store"]).arg(&store).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let exported: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let expected: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(fixture("state.json")).unwrap()).unwrap();
+    assert_eq!(exported, expected);
+
+    // Importing an array is a validation failure and leaves the store alone
+    let output = node()
+        .args(["import-state", "--store"])
+        .arg(&store)
+        .arg("--file")
+        .arg(fixture("actions.json"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(EXIT_INVALID));
+    let output = node().args(["export-state", "--store"]).arg(&store).output().unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&stdout(&output)).unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_store_file_is_loaded_into_node_state() {
+    use orpheon_state::StateStore;
+
+    let store = temp_path("store.json");
+    node()
+        .args(["import-state", "--store"])
+        .arg(&store)
+        .arg("--file")
+        .arg(fixture("state.json"))
+        .assert()
+        .success();
+
+    let config = orpheon_node::NodeConfig { store_path: Some(store), ..Default::default() };
+    let state = orpheon_node::build_state(&config).await.unwrap();
+    let region = state.state_store.get("region").await.unwrap().unwrap();
+    assert_eq!(region.value, "eu-west-1");
+}
@@ -0,0 +1,93 @@
+//! End-to-end tests of constraints evaluated while intents execute.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::{ExecutionArtifact, IntentStatus, Step};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+/// Executor whose `provision` step reports the zone it provisioned in.
+struct Zoned(&'static str);
+
+#[async_trait]
+impl StepExecutor for Zoned {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        match step.action.as_str() {
+            "provision" => Ok(StepOutput::new(1).with_data(serde_json::json!({ "zone": self.0 }))),
+            _ => Ok(StepOutput::new(1)),
+        }
+    }
+}
+
+async fn node(zone: &'static str) -> TestNode {
+    let catalog = vec![
+        PlanningAction {
+            name: "provision".to_string(),
+            effects: vec!["provisioned".to_string()],
+            cost: 1.0,
+            ..Default::default()
+        },
+        PlanningAction {
+            name: "deploy".to_string(),
+            preconditions: vec!["provisioned".to_string()],
+            effects: vec!["complete".to_string()],
+            cost: 1.0,
+            ..Default::default()
+        },
+    ];
+    let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    state.set_step_executor(Arc::new(Zoned(zone))).await;
+    TestNode::with_state(state).await
+}
+
+/// Submit an intent that must land in zone `a` and wait for its artifact.
+async fn run_in_zone_a(node: &TestNode) -> (Uuid, ExecutionArtifact) {
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent = Intent::builder().kind("deploy").state_match("zone == 'a'").build().unwrap();
+    let intent_id = client.submit(intent).await.unwrap().intent_id();
+
+    let artifact = timeout(Duration::from_secs(15), async {
+        loop {
+            if let Some(artifact) = node.state.get_artifact_for_intent(intent_id).await {
+                return artifact;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time");
+    (intent_id, artifact)
+}
+
+#[tokio::test]
+async fn test_matching_state_completes() {
+    let node = node("a").await;
+    let (_, artifact) = run_in_zone_a(&node).await;
+    assert!(artifact.outcome.is_success(), "{:?}", artifact.outcome);
+    assert_eq!(artifact.successful_steps().len(), 2);
+}
+
+#[tokio::test]
+async fn test_broken_state_match_stops_execution() {
+    let node = node("b").await;
+    let (intent_id, artifact) = run_in_zone_a(&node).await;
+
+    match &artifact.outcome {
+        Outcome::Failure { reason, .. } => {
+            assert!(reason.contains("Constraint violated: zone == 'a'"), "{}", reason);
+            assert!(reason.contains("does not hold"), "{}", reason);
+        }
+        other => panic!("unexpected outcome {:?}", other),
+    }
+    // The deploy step never started
+    assert_eq!(artifact.successful_steps().len(), 1);
+    assert_eq!(node.state.get_intent(intent_id).await.unwrap().status, IntentStatus::Failed);
+}
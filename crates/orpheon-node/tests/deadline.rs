@@ -0,0 +1,66 @@
+//! End-to-end tests of the deadline watchdog.
+
+use std::time::Duration;
+
+use orpheon_core::{Constraint, PlanningStrategy, Step};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_node::watchdog::DEADLINE_AT_RISK;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+
+/// A paused node holding an intent due in two seconds whose plan needs a
+/// minute.
+async fn node_with_late_intent() -> (TestNode, uuid::Uuid) {
+    let state = AppState::new();
+    state.pause_engine();
+    let intent = Intent::builder()
+        .kind("deploy")
+        .constraint(Constraint::Deadline { by: chrono::Utc::now() + chrono::Duration::seconds(2) })
+        .build()
+        .unwrap();
+    let intent_id = intent.id;
+    state.store_intent(intent).await;
+    let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+    plan.add_step(Step::new("migrate", "migrate_db").with_duration(60_000));
+    state.store_plan(plan).await;
+    (TestNode::with_state(state).await, intent_id)
+}
+
+#[tokio::test]
+async fn test_late_intent_is_warned_on_its_stream() {
+    let (node, intent_id) = node_with_late_intent().await;
+    let url = format!("{}/ws/intent/{}", node.ws_url(), intent_id);
+    let mut stream = EventStream::connect(&url, intent_id).await.unwrap();
+
+    let (code, message) = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Event::Warning { code, message } = stream.next().await.expect("stream ended early") {
+                break (code, message);
+            }
+        }
+    })
+    .await
+    .expect("no warning was sent");
+    assert_eq!(code, DEADLINE_AT_RISK);
+    assert!(message.contains(&intent_id.to_string()));
+}
+
+#[tokio::test]
+async fn test_late_intent_is_warned_on_multiplexed_stream() {
+    let (node, intent_id) = node_with_late_intent().await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut stream = client.watch_intents(WatchFilter::ids(vec![intent_id])).await.unwrap();
+
+    let (id, code) = timeout(Duration::from_secs(10), async {
+        loop {
+            if let (id, Event::Warning { code, .. }) = stream.next().await.expect("stream ended early") {
+                break (id, code);
+            }
+        }
+    })
+    .await
+    .expect("no warning was sent");
+    assert_eq!(id, intent_id);
+    assert_eq!(code, DEADLINE_AT_RISK);
+}
@@ -0,0 +1,127 @@
+//! Tests of splitting intents into child intents.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use orpheon_core::{Intent, IntentBuilder, IntentStatus, Outcome};
+use orpheon_node::decompose::{Decomposer, MAX_DECOMPOSITION_DEPTH};
+use orpheon_node::state::{AppState, IntentRecord};
+use orpheon_node::testing::TestNode;
+use serde_json::json;
+use tokio::time::{sleep, timeout};
+use uuid::Uuid;
+
+/// Splits `batch` intents into one `deploy` child per entry of their
+/// `parts` metadata, each entry being the child's metadata.
+struct SplitBatches;
+
+impl Decomposer for SplitBatches {
+    fn decompose(&self, intent: &Intent) -> Option<Vec<IntentBuilder>> {
+        if intent.kind != "batch" {
+            return None;
+        }
+        let parts = intent.metadata["parts"].as_array()?;
+        Some(parts.iter().map(|part| Intent::builder().kind("deploy").metadata(part.clone())).collect())
+    }
+}
+
+/// Splits every intent into a single child, forever if allowed to.
+struct SplitForever;
+
+impl Decomposer for SplitForever {
+    fn decompose(&self, _intent: &Intent) -> Option<Vec<IntentBuilder>> {
+        Some(vec![Intent::builder().kind("deploy")])
+    }
+}
+
+async fn node(decomposer: Arc<dyn Decomposer>) -> TestNode {
+    let state = AppState::new();
+    state.set_decomposer(decomposer).await;
+    TestNode::with_state(state).await
+}
+
+fn batch(parts: serde_json::Value) -> Intent {
+    Intent::builder().kind("batch").metadata(json!({ "parts": parts })).build().unwrap()
+}
+
+async fn finished(node: &TestNode, intent_id: Uuid) -> IntentRecord {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let record = node.state.get_intent(intent_id).await.unwrap();
+            if record.status.is_terminal() {
+                break record;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time")
+}
+
+#[tokio::test]
+async fn test_parent_completes_after_its_children() {
+    let node = node(Arc::new(SplitBatches)).await;
+    let parent = batch(json!([{ "part": 1 }, { "part": 2 }]));
+    node.state.store_intent(parent.clone()).await;
+
+    let record = finished(&node, parent.id).await;
+    assert_eq!(record.status, IntentStatus::Complete);
+    assert!(record.plan_ids.is_empty());
+
+    let children = node.state.children_of(parent.id).await;
+    assert_eq!(children.len(), 2);
+    assert!(children.iter().all(|c| c.status == IntentStatus::Complete && c.intent.parent_id == Some(parent.id)));
+    assert_eq!(children[1].intent.metadata["part"], 2);
+
+    let artifact = node.state.get_artifact(record.artifact_id.unwrap()).await.unwrap();
+    assert!(artifact.outcome.is_success());
+    let child_artifacts: Vec<Uuid> = children.iter().map(|c| c.artifact_id.unwrap()).collect();
+    assert_eq!(artifact.child_artifacts, child_artifacts);
+    assert!(artifact.actual_duration_ms > 0);
+}
+
+#[tokio::test]
+async fn test_failed_child_fails_parent() {
+    let node = node(Arc::new(SplitBatches)).await;
+    // The node lacks the GPU the second child requires
+    let parent = batch(json!([{ "part": 1 }, { "requirements": ["capability:gpu"] }]));
+    node.state.store_intent(parent.clone()).await;
+
+    let record = finished(&node, parent.id).await;
+    assert_eq!(record.status, IntentStatus::Failed);
+    let children = node.state.children_of(parent.id).await;
+    assert_eq!(children[0].status, IntentStatus::Complete);
+    assert_eq!(children[1].status, IntentStatus::Failed);
+
+    let artifact = node.state.get_artifact(record.artifact_id.unwrap()).await.unwrap();
+    let Outcome::Failure { reason, .. } = &artifact.outcome else {
+        panic!("unexpected outcome {:?}", artifact.outcome);
+    };
+    assert!(reason.contains(&children[1].intent.id.to_string()), "{}", reason);
+    assert!(record.error.unwrap().contains("capability:gpu"));
+}
+
+#[tokio::test]
+async fn test_decomposition_depth_is_capped() {
+    let node = node(Arc::new(SplitForever)).await;
+    let root = Intent::builder().kind("deploy").build().unwrap();
+    node.state.store_intent(root.clone()).await;
+    assert_eq!(finished(&node, root.id).await.status, IntentStatus::Complete);
+
+    // One chain of children, the last of which was planned itself
+    let mut id = root.id;
+    let mut depth = 0;
+    loop {
+        let children = node.state.children_of(id).await;
+        let Some(child) = children.first() else {
+            break;
+        };
+        assert_eq!(children.len(), 1);
+        id = child.intent.id;
+        depth += 1;
+    }
+    assert_eq!(depth, MAX_DECOMPOSITION_DEPTH);
+    let leaf = node.state.get_intent(id).await.unwrap();
+    assert_eq!(leaf.plan_ids.len(), 1);
+    assert!(!leaf.decomposed);
+}
@@ -0,0 +1,77 @@
+//! Tests of intents that wait for other intents through `DependsOn`
+//! constraints.
+
+use std::time::Duration;
+
+use orpheon_core::IntentStatus;
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn test_dependent_intent_runs_after_its_dependency() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    // The dependency is deferred, so the dependent has to wait for it
+    let first = Intent::builder().kind("deploy").starts_in(chrono::Duration::milliseconds(300)).build().unwrap();
+    let first = client.submit_detached(first).await.unwrap();
+    let second = Intent::builder().kind("deploy").depends_on(first, true).build().unwrap();
+    let second = client.submit_detached(second).await.unwrap();
+
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let dependency = node.state.get_intent(first).await.unwrap();
+            let dependent = node.state.get_intent(second).await.unwrap();
+            if dependent.status != IntentStatus::Received {
+                assert!(dependency.status.is_terminal(), "started while its dependency was {}", dependency.status.as_str());
+            }
+            if dependent.status == IntentStatus::Complete {
+                assert_eq!(dependency.status, IntentStatus::Complete);
+                assert!(dependency.finished_at <= dependent.finished_at);
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("dependent intent never completed");
+}
+
+#[tokio::test]
+async fn test_failed_dependency_fails_dependents_that_need_it() {
+    // Negotiated, so it waits for a client until it is failed below
+    let state = AppState::new();
+    let dependency = Intent::builder().kind("deploy").build().unwrap();
+    let dependency_id = dependency.id;
+    state.store_intent_with_negotiation(dependency, Some(NegotiationOptions::default())).await;
+    let node = TestNode::with_state(state.clone()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let strict = client.submit_detached(Intent::builder().kind("deploy").depends_on(dependency_id, true).build().unwrap()).await.unwrap();
+    let lenient = client.submit_detached(Intent::builder().kind("deploy").depends_on(dependency_id, false).build().unwrap()).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(client.get_intent(strict).await.unwrap().status, "received");
+    assert_eq!(client.get_intent(lenient).await.unwrap().status, "received");
+
+    assert!(state.fail_intent(dependency_id, "quota exceeded").await);
+    let finished = |id| {
+        let client = &client;
+        async move {
+            loop {
+                let response = client.get_intent(id).await.unwrap();
+                if matches!(response.status.as_str(), "complete" | "failed") {
+                    break response;
+                }
+                sleep(Duration::from_millis(20)).await;
+            }
+        }
+    };
+    let strict = timeout(Duration::from_secs(10), finished(strict)).await.expect("dependent never failed");
+    assert_eq!(strict.status, "failed");
+    assert_eq!(strict.error, Some(format!("dependency intent {} is failed", dependency_id)));
+    let lenient = timeout(Duration::from_secs(10), finished(lenient)).await.expect("dependent never ran");
+    assert_eq!(lenient.status, "complete");
+}
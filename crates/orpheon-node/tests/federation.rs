@@ -0,0 +1,97 @@
+//! End-to-end tests of forwarding intents between nodes.
+
+use std::time::Duration;
+
+use orpheon_core::IntentStatus;
+use orpheon_node::federation::{FederationConfig, PeerConfig};
+use orpheon_node::testing::TestNode;
+use orpheon_node::NodeConfig;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+
+fn node_config(node_id: &str) -> NodeConfig {
+    NodeConfig {
+        federation: FederationConfig {
+            node_id: Some(node_id.to_string()),
+            poll_interval_ms: 50,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn peer(node: &TestNode) -> PeerConfig {
+    PeerConfig { base_url: node.base_url(), api_key: Some("secret".to_string()) }
+}
+
+fn pinned_to(node_id: &str) -> Intent {
+    Intent::builder()
+        .kind("deploy")
+        .constraint(Constraint::Provider { node_id: node_id.to_string() })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_intent_is_forwarded_and_mirrored() {
+    let remote = TestNode::spawn(node_config("node-b")).await;
+    let local = TestNode::spawn(node_config("node-a")).await;
+    local.state.federation.add_peer("node-b", peer(&remote));
+
+    let client = OrpheonClient::connect(&local.base_url()).await.unwrap();
+    let mut stream = client.submit(pinned_to("node-b")).await.unwrap();
+    let intent_id = stream.intent_id();
+
+    let artifact_id = timeout(Duration::from_secs(15), async {
+        loop {
+            if let Event::Complete { artifact_id } = stream.next().await.expect("stream ended early") {
+                break artifact_id;
+            }
+        }
+    })
+    .await
+    .expect("forwarded intent did not complete in time");
+
+    // The local node only holds a mirror; the work happened on the peer
+    let record = local.state.get_intent(intent_id).await.unwrap();
+    assert_eq!(record.status, IntentStatus::Complete);
+    let forwarded = record.forwarded.expect("intent was not forwarded");
+    assert_eq!(forwarded.node_id, "node-b");
+    assert!(local.state.artifacts.read().await.is_empty());
+    assert_eq!(
+        remote.state.get_intent(forwarded.remote_id).await.unwrap().status,
+        IntentStatus::Complete
+    );
+
+    // Plan and artifact are fetched from the peer
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    assert_eq!(artifact.id, artifact_id);
+    assert_eq!(artifact.intent.id, forwarded.remote_id);
+    assert!(artifact.outcome.is_success());
+    let plan = client.get_plan(intent_id).await.unwrap();
+    assert_eq!(plan.intent_id, forwarded.remote_id);
+}
+
+#[tokio::test]
+async fn test_intent_for_this_node_is_not_forwarded() {
+    let node = TestNode::spawn(node_config("node-a")).await;
+    node.state.federation.add_peer("node-b", PeerConfig { base_url: "http://127.0.0.1:1".to_string(), api_key: None });
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent_id = client.submit_detached(pinned_to("node-a")).await.unwrap();
+    assert!(node.state.get_intent(intent_id).await.unwrap().forwarded.is_none());
+}
+
+#[tokio::test]
+async fn test_forwarding_loop_is_refused() {
+    // Neither node knows its own ID, and each thinks the other is node-c
+    let first = TestNode::spawn(NodeConfig::default()).await;
+    let second = TestNode::spawn(NodeConfig::default()).await;
+    first.state.federation.add_peer("node-c", peer(&second));
+    second.state.federation.add_peer("node-c", peer(&first));
+
+    let client = OrpheonClient::connect(&first.base_url()).await.unwrap();
+    let err = client.submit_detached(pinned_to("node-c")).await.unwrap_err();
+    assert!(err.to_string().contains("Forwarding loop detected"), "{}", err);
+    assert!(first.state.list_intents().await.is_empty());
+}
@@ -0,0 +1,79 @@
+//! End-to-end tests of renamed intent kinds.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use orpheon_core::IntentStatus;
+use orpheon_node::kinds::{KindDefinition, ORIGINAL_KIND_KEY};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+const NEW_KIND: &str = "gpu.cluster.provision";
+const OLD_KIND: &str = "provision_gpu_cluster";
+
+/// A node whose only action plans intents of the new kind.
+async fn node_with(kind: KindDefinition) -> TestNode {
+    let catalog = vec![PlanningAction {
+        name: "provision_gpus".to_string(),
+        effects: vec!["complete".to_string()],
+        cost: 1.0,
+        kinds: vec![NEW_KIND.to_string()],
+        ..Default::default()
+    }];
+    let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    state.register_kind(kind).await;
+    TestNode::with_state(state).await
+}
+
+#[tokio::test]
+async fn test_alias_is_accepted_with_warning_and_planned_as_new_kind() {
+    let sunset = Utc::now() + chrono::Duration::days(30);
+    let node = node_with(KindDefinition::new(NEW_KIND).with_alias(OLD_KIND, Some(sunset))).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let result = client
+        .submit_with_result(Intent::builder().kind(OLD_KIND).build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains(&format!("use '{}'", NEW_KIND)), "{}", result.warnings[0]);
+
+    let record = timeout(Duration::from_secs(10), async {
+        loop {
+            let record = node.state.get_intent(result.id).await.unwrap();
+            if record.status.is_terminal() {
+                break record;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time");
+    assert_eq!(record.status, IntentStatus::Complete);
+    assert_eq!(record.intent.kind, NEW_KIND);
+    assert_eq!(record.intent.metadata_get::<String>(&format!("/{}", ORIGINAL_KIND_KEY)).unwrap().as_deref(), Some(OLD_KIND));
+    assert_eq!(client.get_plan(result.id).await.unwrap().steps[0].action, "provision_gpus");
+
+    // The current name gets no warning
+    let result = client.submit_with_result(Intent::builder().kind(NEW_KIND).build().unwrap()).await.unwrap();
+    assert!(result.warnings.is_empty());
+}
+
+#[tokio::test]
+async fn test_alias_is_refused_after_sunset() {
+    let sunset = Utc::now() - chrono::Duration::days(1);
+    let node = node_with(KindDefinition::new(NEW_KIND).with_alias(OLD_KIND, Some(sunset))).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let err = client
+        .submit_with_result(Intent::builder().kind(OLD_KIND).build().unwrap())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("kind_retired") && err.contains(NEW_KIND), "{}", err);
+    assert!(node.state.list_intents().await.is_empty());
+}
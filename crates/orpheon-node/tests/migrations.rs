@@ -0,0 +1,72 @@
+//! Upgrading a state store written by an older node.
+
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use orpheon_node::config::NodeConfig;
+use orpheon_node::journal::JournalEvent;
+use orpheon_node::migrations::{document_version, CURRENT_SCHEMA_VERSION};
+use orpheon_state::{Keys, StateStore};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/migrations").join(name)
+}
+
+#[tokio::test]
+async fn test_version_1_store_is_upgraded_on_startup() {
+    let config = NodeConfig { store_path: Some(fixture("store-v1.json")), ..Default::default() };
+    let state = orpheon_node::build_state(&config).await.unwrap();
+
+    for entry in state.state_store.get_prefix("").await.unwrap() {
+        let expected = match entry.key.as_str() {
+            "region" | "stats:2026-03-01:submitted" => None,
+            _ => Some(CURRENT_SCHEMA_VERSION),
+        };
+        assert_eq!(document_version(&entry.value), expected, "{}", entry.key);
+    }
+
+    // Learned durations survive with their quantiles
+    let stats = state.planner.durations().get("provision_compute").unwrap();
+    assert_eq!((stats.samples, stats.p50_ms, stats.p95_ms), (40, 1200, 4800));
+    assert_eq!(stats.recent, vec![(1200, 0.5), (4800, 0.5)]);
+
+    let migrated: Vec<String> = state
+        .journal
+        .since(0, 100)
+        .into_iter()
+        .filter_map(|entry| match entry.event {
+            JournalEvent::RecordMigrated { key, from: 1, to } if to == CURRENT_SCHEMA_VERSION => Some(key),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        migrated,
+        vec![
+            "annotations:artifact:8f1d2c3b-4a5e-4f60-8b7a-9c0d1e2f3a4b".to_string(),
+            Keys::action_stats("provision_compute"),
+        ]
+    );
+}
+
+#[test]
+fn test_migrate_dry_run_reports_without_writing() {
+    let store = fixture("store-v1.json");
+    let before = std::fs::read_to_string(&store).unwrap();
+
+    let output = Command::cargo_bin("orpheon-node")
+        .unwrap()
+        .arg("--store")
+        .arg(&store)
+        .arg("--migrate-dry-run")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let report = report.as_array().unwrap();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[1]["kind"], "action_stats");
+    assert_eq!(report[1]["from"], 1);
+    assert_eq!(report[1]["changes"][0], "backfill recent samples from p50_ms and p95_ms");
+    assert_eq!(std::fs::read_to_string(&store).unwrap(), before);
+}
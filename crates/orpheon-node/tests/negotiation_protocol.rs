@@ -0,0 +1,131 @@
+//! Tests of the negotiation protocol handshake between clients and a node.
+
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use orpheon_negotiate::handshake::{COUNTER_OFFERS, LINE_ITEMS, SIGNED_RECEIPTS};
+use orpheon_negotiate::{CounterOffer, NegotiationMessage, PROTOCOL_VERSION};
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// A node negotiating one intent.
+async fn negotiating() -> (TestNode, Uuid) {
+    let state = AppState::new();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    (TestNode::with_state(state).await, intent_id)
+}
+
+/// Connect to the negotiation stream without the SDK.
+async fn raw(node: &TestNode, intent_id: Uuid) -> Socket {
+    let url = format!("{}/ws/negotiate/{}", node.ws_url(), intent_id);
+    tokio_tungstenite::connect_async(url).await.unwrap().0
+}
+
+async fn send(socket: &mut Socket, frame: serde_json::Value) {
+    socket.send(Message::Text(frame.to_string())).await.unwrap();
+}
+
+/// Next frame of any kind.
+async fn next(socket: &mut Socket) -> Message {
+    timeout(Duration::from_secs(10), socket.next())
+        .await
+        .expect("no frame in time")
+        .expect("socket ended")
+        .unwrap()
+}
+
+/// Next text frame, parsed as JSON.
+async fn next_json(socket: &mut Socket) -> serde_json::Value {
+    loop {
+        if let Message::Text(text) = next(socket).await {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_current_client_gets_every_feature() {
+    let (node, intent_id) = negotiating().await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+    assert_eq!(negotiation.protocol_version(), PROTOCOL_VERSION);
+    let features: Vec<&str> = negotiation.features().iter().map(String::as_str).collect();
+    assert_eq!(features, vec![COUNTER_OFFERS, LINE_ITEMS, SIGNED_RECEIPTS]);
+
+    let proposal = negotiation.next_proposal().await.unwrap().unwrap();
+    assert!(!proposal.line_items.is_empty());
+    negotiation.accept(proposal.id).await.unwrap();
+    match negotiation.next().await.unwrap() {
+        Some(NegotiationMessage::Confirmed { receipt, .. }) => assert!(receipt.is_some()),
+        other => panic!("expected a confirmation, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_v1_client_is_downgraded() {
+    let (node, intent_id) = negotiating().await;
+    let mut socket = raw(&node, intent_id).await;
+
+    let hello = next_json(&mut socket).await;
+    assert_eq!(hello["type"], "hello");
+    assert_eq!(hello["protocol_version"], PROTOCOL_VERSION);
+
+    // A v1 client never answers the hello and still negotiates, without
+    // the parts v1 does not know
+    let offer = next_json(&mut socket).await;
+    assert_eq!(offer["type"], "offer");
+    assert!(!offer["line_items"].as_array().unwrap().is_empty());
+    send(&mut socket, serde_json::json!({ "type": "accept", "proposal_id": offer["id"] })).await;
+
+    let confirmed = next_json(&mut socket).await;
+    assert_eq!(confirmed["type"], "confirmed");
+    assert!(confirmed.get("receipt").is_none());
+}
+
+#[tokio::test]
+async fn test_features_are_restricted_to_the_intersection() {
+    let (node, intent_id) = negotiating().await;
+    let mut socket = raw(&node, intent_id).await;
+    next_json(&mut socket).await;
+    let hello = NegotiationMessage::Hello { protocol_version: PROTOCOL_VERSION, supported_features: vec![SIGNED_RECEIPTS.to_string()] };
+    send(&mut socket, serde_json::to_value(hello).unwrap()).await;
+
+    let offer = next_json(&mut socket).await;
+    assert_eq!(offer["type"], "offer");
+    assert!(offer["line_items"].as_array().unwrap().is_empty());
+
+    let counter = NegotiationMessage::Counter(CounterOffer::new(Uuid::parse_str(offer["id"].as_str().unwrap()).unwrap()));
+    send(&mut socket, serde_json::to_value(counter).unwrap()).await;
+    let error = next_json(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert!(error["message"].as_str().unwrap().contains(COUNTER_OFFERS), "{}", error);
+}
+
+#[tokio::test]
+async fn test_incompatible_client_is_closed() {
+    let (node, intent_id) = negotiating().await;
+    let mut socket = raw(&node, intent_id).await;
+    next_json(&mut socket).await;
+    send(&mut socket, serde_json::json!({ "type": "hello", "protocol_version": 0, "supported_features": [] })).await;
+
+    let error = next_json(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert!(error["message"].as_str().unwrap().contains("Unsupported negotiation protocol version 0"), "{}", error);
+    match next(&mut socket).await {
+        Message::Close(Some(frame)) => assert_eq!(frame.code, CloseCode::Protocol),
+        other => panic!("expected a close frame, got {:?}", other),
+    }
+}
@@ -0,0 +1,63 @@
+//! Client view of plans and artifacts that do not exist yet.
+
+use std::time::Duration;
+
+use orpheon_core::{ExecutionArtifact, IntentStatus, Outcome, PlanningStrategy};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_client_tells_unknown_from_not_ready() {
+    let state = AppState::new();
+    state.pause_engine();
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    node.state.store_intent(intent.clone()).await;
+
+    let unknown = Uuid::new_v4();
+    assert!(matches!(client.get_plan(unknown).await, Err(OrpheonError::NotFound { .. })));
+    assert!(matches!(client.get_artifact(unknown).await, Err(OrpheonError::NotFound { .. })));
+
+    match client.get_plan(intent_id).await {
+        Err(OrpheonError::NotReady { intent_id: id, status, .. }) => {
+            assert_eq!(id, intent_id);
+            assert_eq!(status, IntentStatus::Received);
+        }
+        other => panic!("expected NotReady, got {:?}", other),
+    }
+    assert!(matches!(
+        client.get_artifact(intent_id).await,
+        Err(OrpheonError::NotReady { status: IntentStatus::Received, .. })
+    ));
+
+    let plan = node.state.store_plan(Plan::new(intent_id, PlanningStrategy::Heuristic)).await;
+    assert_eq!(client.get_plan(intent_id).await.unwrap().id, plan.id);
+    node.state.store_artifact(ExecutionArtifact::new(intent, plan, Outcome::Success)).await;
+    assert_eq!(client.get_artifact(intent_id).await.unwrap().intent.id, intent_id);
+}
+
+#[tokio::test]
+async fn test_submit_and_wait_outlasts_a_slow_start() {
+    let state = AppState::new();
+    state.pause_engine();
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+
+    let engine = node.state.clone();
+    tokio::spawn(async move {
+        sleep(Duration::from_millis(300)).await;
+        engine.resume_engine();
+    });
+
+    let completion = timeout(Duration::from_secs(15), client.submit_and_wait(intent))
+        .await
+        .expect("intent did not finish in time")
+        .unwrap();
+    assert!(completion.into_artifact().outcome.is_success());
+}
@@ -0,0 +1,127 @@
+//! End-to-end tests of intents whose optional steps fail.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use orpheon_core::{IntentStatus, Step};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Executor that fails the `notify_team` step.
+struct FailingNotifier;
+
+#[async_trait]
+impl StepExecutor for FailingNotifier {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        match step.action.as_str() {
+            "notify_team" => Err("chat service unavailable".to_string()),
+            _ => Ok(StepOutput::new(1)),
+        }
+    }
+}
+
+async fn node() -> TestNode {
+    let catalog = vec![
+        PlanningAction {
+            name: "deploy_workload".to_string(),
+            effects: vec!["deployed".to_string()],
+            cost: 1.0,
+            ..Default::default()
+        },
+        PlanningAction {
+            name: "notify_team".to_string(),
+            preconditions: vec!["deployed".to_string()],
+            effects: vec!["complete".to_string()],
+            cost: 1.0,
+            optional: true,
+            ..Default::default()
+        },
+    ];
+    let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    state.set_step_executor(Arc::new(FailingNotifier)).await;
+    TestNode::with_state(state).await
+}
+
+fn deploy_intent() -> Intent {
+    Intent::builder().kind("deploy").build().unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_optional_step_is_partial_success() {
+    let node = node().await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(deploy_intent()).await.unwrap();
+    let intent_id = stream.intent_id();
+    let (artifact_id, success_rate) = timeout(Duration::from_secs(15), async {
+        loop {
+            match stream.next().await.expect("stream ended early") {
+                Event::PartialComplete { artifact_id, success_rate } => break (artifact_id, success_rate),
+                Event::Complete { .. } => panic!("reported as a full success"),
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("intent did not finish in time");
+    assert_eq!(success_rate, 50);
+
+    // Status and success rate over REST
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert_eq!(record.status, IntentStatus::PartiallyComplete);
+    let response = client.get_intent(intent_id).await.unwrap();
+    assert_eq!(response.status, "partially_complete");
+    assert_eq!(response.success_rate, Some(50));
+    assert_eq!(response.artifact_id, Some(artifact_id));
+
+    // The WS status frame carries the rate too
+    let url = format!("{}/ws/intent/{}", node.ws_url(), intent_id);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let frame = loop {
+        let msg = timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+        if let Message::Text(text) = msg {
+            break serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        }
+    };
+    assert_eq!(frame["status"], "partially_complete");
+    assert_eq!(frame["success_rate"], 50);
+
+    // Nothing was rolled back, and the artifact says what went wrong
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    match &artifact.outcome {
+        Outcome::PartialSuccess { success_rate, details } => {
+            assert_eq!(*success_rate, 50);
+            assert!(details.contains("notify_team"), "{}", details);
+        }
+        other => panic!("unexpected outcome {:?}", other),
+    }
+    assert_eq!(artifact.failed_steps().len(), 1);
+    assert!(verify_artifact(&artifact, None).is_valid());
+}
+
+#[tokio::test]
+async fn test_submit_and_wait_distinguishes_partial_success() {
+    let node = node().await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let completion = timeout(Duration::from_secs(15), client.submit_and_wait(deploy_intent()))
+        .await
+        .expect("intent did not finish in time")
+        .unwrap();
+    match &completion {
+        Completion::Partial { artifact, success_rate } => {
+            assert_eq!(*success_rate, 50);
+            assert_eq!(artifact.successful_steps().len(), 1);
+        }
+        Completion::Full(_) => panic!("reported as a full success"),
+    }
+    assert!(completion.is_partial());
+}
@@ -0,0 +1,123 @@
+//! End-to-end tests of pausing and resuming an executing intent.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_core::{IntentStatus, Step};
+use orpheon_node::config::PauseConfig;
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, timeout};
+
+/// Executor that records the actions it ran, holding the first one until
+/// the gate is opened.
+struct GatedExecutor {
+    actions: Mutex<Vec<String>>,
+    gate: Semaphore,
+}
+
+#[async_trait]
+impl StepExecutor for GatedExecutor {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        let first = {
+            let mut actions = self.actions.lock().unwrap();
+            actions.push(step.action.clone());
+            actions.len() == 1
+        };
+        if first {
+            self.gate.acquire().await.unwrap().forget();
+        }
+        Ok(StepOutput::new(1))
+    }
+}
+
+/// A node whose catalog needs three chained steps.
+async fn node(pause: PauseConfig) -> (TestNode, Arc<GatedExecutor>) {
+    let catalog = (1..=3)
+        .map(|i| PlanningAction {
+            name: format!("step_{}", i),
+            preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+            effects: vec![if i == 3 { "complete".to_string() } else { format!("s{}", i) }],
+            cost: 1.0,
+            duration_ms: 10,
+            ..Default::default()
+        })
+        .collect();
+    let mut state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    state.pause = pause;
+    let executor = Arc::new(GatedExecutor { actions: Mutex::new(Vec::new()), gate: Semaphore::new(0) });
+    state.set_step_executor(executor.clone()).await;
+    (TestNode::with_state(state).await, executor)
+}
+
+/// Pause during the first step, check nothing else runs, then resume and
+/// return the finished artifact.
+async fn pause_and_resume(node: &TestNode, executor: &GatedExecutor) -> ExecutionArtifact {
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut stream = client.submit(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    let intent_id = stream.intent_id();
+
+    timeout(Duration::from_secs(10), async {
+        while executor.actions.lock().unwrap().is_empty() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("execution did not start");
+    client.pause(intent_id).await.unwrap();
+    executor.gate.add_permits(1);
+
+    timeout(Duration::from_secs(10), async {
+        while !matches!(stream.next().await.expect("stream ended early"), Event::Paused { .. }) {}
+    })
+    .await
+    .expect("no paused event");
+    sleep(Duration::from_millis(400)).await;
+    assert_eq!(*executor.actions.lock().unwrap(), vec!["step_1"]);
+    assert_eq!(node.state.get_intent(intent_id).await.unwrap().status, IntentStatus::Paused);
+    assert!(matches!(client.pause(intent_id).await, Err(OrpheonError::InvalidTransition { status: IntentStatus::Paused, .. })));
+
+    client.resume(intent_id).await.unwrap();
+    timeout(Duration::from_secs(10), async {
+        while !matches!(stream.next().await.expect("stream ended early"), Event::Complete { .. }) {}
+    })
+    .await
+    .expect("intent did not finish after resuming");
+    assert_eq!(*executor.actions.lock().unwrap(), vec!["step_1", "step_2", "step_3"]);
+    assert!(matches!(client.resume(intent_id).await, Err(OrpheonError::InvalidTransition { status: IntentStatus::Complete, .. })));
+    client.get_artifact(intent_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_paused_intent_runs_no_steps_until_resumed() {
+    let (node, executor) = node(PauseConfig::default()).await;
+    let artifact = pause_and_resume(&node, &executor).await;
+
+    // The pause sits between the first step and the second in the trace
+    let types: Vec<ExecutionEventType> = artifact.trace.iter().map(|e| e.event_type.clone()).collect();
+    let paused = types.iter().position(|t| *t == ExecutionEventType::Paused).expect("no pause in trace");
+    assert_eq!(types[paused - 1], ExecutionEventType::StepCompleted);
+    assert_eq!(types[paused + 1], ExecutionEventType::Resumed);
+    assert_eq!(types[paused + 2], ExecutionEventType::StepStarted);
+    let resumed = &artifact.trace[paused + 1];
+    assert!(resumed.data["paused_ms"].as_u64().unwrap() >= 400);
+
+    // Paused time is not counted by default
+    assert!(resumed.duration_ms.is_none());
+    assert_eq!(artifact.actual_duration_ms, 3);
+    assert!(artifact.outcome.is_success());
+}
+
+#[tokio::test]
+async fn test_paused_time_can_count_towards_duration() {
+    let (node, executor) = node(PauseConfig { count_paused_time: true }).await;
+    let artifact = pause_and_resume(&node, &executor).await;
+    assert!(artifact.actual_duration_ms >= 403);
+}
@@ -0,0 +1,54 @@
+//! Tests of the plan revisions an intent collects when a counter-offer
+//! makes the node re-plan it.
+
+use std::time::Duration;
+
+use axum_test::TestServer;
+use orpheon_negotiate::CounterOffer;
+use orpheon_node::api::intent::PlanRevisionsResponse;
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn test_counter_offer_adds_a_plan_revision() {
+    let state = AppState::new();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    let node = TestNode::with_state(state.clone()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let api = TestServer::new(orpheon_node::create_router(state)).unwrap();
+
+    // Counter the first proposal with caps it already meets, so the node
+    // re-plans and proposes again
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+    let first = negotiation.next_proposal().await.unwrap().unwrap();
+    let counter = CounterOffer::new(first.id).with_max_latency(first.estimated_latency_ms);
+    negotiation.counter(counter).await.unwrap();
+    let second = negotiation.next_proposal().await.unwrap().unwrap();
+    assert_ne!(second.plan.id, first.plan.id);
+
+    let revisions: PlanRevisionsResponse = api.get(&format!("/api/v1/intent/{}/plans", intent_id)).await.json();
+    assert_eq!(revisions.active_plan_id, Some(second.plan.id));
+    assert_eq!(revisions.executed_plan_id, None);
+    let flags: Vec<_> = revisions.revisions.iter().map(|r| (r.plan_id, r.active, r.superseded, r.executed)).collect();
+    assert_eq!(flags, vec![(first.plan.id, false, true, false), (second.plan.id, true, false, false)]);
+
+    negotiation.accept(second.id).await.unwrap();
+    timeout(Duration::from_secs(10), async {
+        while client.get_intent(intent_id).await.unwrap().status != "complete" {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent never completed");
+
+    let revisions: PlanRevisionsResponse = api.get(&format!("/api/v1/intent/{}/plans", intent_id)).await.json();
+    assert_eq!(revisions.executed_plan_id, Some(second.plan.id));
+    let flags: Vec<_> =
+        revisions.revisions.iter().map(|r| (r.revision, r.plan_id, r.active, r.superseded, r.executed)).collect();
+    assert_eq!(flags, vec![(1, first.plan.id, false, true, false), (2, second.plan.id, true, false, true)]);
+}
@@ -0,0 +1,105 @@
+//! Tests of validating negotiated plans before they are offered and run.
+
+use std::time::Duration;
+
+use orpheon_core::{IntentStatus, PlanningStrategy, Step};
+use orpheon_node::negotiation::{self, NegotiationOptions, PLAN_VALIDATION};
+use orpheon_node::state::{AppState, IntentRecord};
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig, UnknownActionPolicy};
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+/// A node negotiating a plan with a step whose action it does not know.
+async fn negotiating(policy: UnknownActionPolicy) -> (TestNode, Uuid) {
+    let catalog = vec![
+        PlanningAction {
+            name: "provision".to_string(),
+            effects: vec!["provisioned".to_string()],
+            ..Default::default()
+        },
+        PlanningAction {
+            name: "deploy".to_string(),
+            preconditions: vec!["provisioned".to_string()],
+            effects: vec!["complete".to_string()],
+            ..Default::default()
+        },
+    ];
+    let config = PlannerConfig { unknown_action_policy: policy, ..Default::default() };
+    let state = AppState::with_planner(AStarPlanner::with_actions(config, catalog));
+
+    // Negotiate a hand-made plan rather than one the engine plans itself
+    state.pause_engine();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    let options = NegotiationOptions::default();
+    state.store_intent_with_negotiation(intent.clone(), Some(options.clone())).await;
+    assert!(state.update_intent_status_if(intent_id, IntentStatus::Received, IntentStatus::Planning).await);
+    assert!(state.update_intent_status_if(intent_id, IntentStatus::Planning, IntentStatus::Negotiating).await);
+    let mut plan = Plan::new(intent_id, PlanningStrategy::Heuristic);
+    plan.add_step(Step::new("provision", "provision"));
+    plan.add_step(Step::new("warm cache", "warm_cache"));
+    plan.add_step(Step::new("deploy", "deploy"));
+    let plan = state.store_plan(plan).await;
+    negotiation::open(&state, intent, plan, &options).await.unwrap();
+    state.resume_engine();
+
+    (TestNode::with_state(state).await, intent_id)
+}
+
+/// Accept the proposal on offer and wait for the intent to finish.
+async fn accept(node: &TestNode, intent_id: Uuid) -> IntentRecord {
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+    let proposal = negotiation.next_proposal().await.unwrap().unwrap();
+    negotiation.accept(proposal.id).await.unwrap();
+
+    timeout(Duration::from_secs(15), async {
+        loop {
+            let record = node.state.get_intent(intent_id).await.unwrap();
+            if record.status.is_terminal() {
+                return record;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("intent did not finish in time")
+}
+
+fn validation_warning(record: &IntentRecord) -> Option<&str> {
+    record.warnings.iter().find(|w| w.code == PLAN_VALIDATION).map(|w| w.message.as_str())
+}
+
+#[tokio::test]
+async fn test_rejected_plan_is_flagged_and_not_run() {
+    let (node, intent_id) = negotiating(UnknownActionPolicy::Reject).await;
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert_eq!(validation_warning(&record), Some("unknown actions: warm_cache"));
+
+    let record = accept(&node, intent_id).await;
+    assert_eq!(record.status, IntentStatus::Failed);
+    let error = record.error.unwrap();
+    assert!(error.contains("Plan failed validation: unknown actions: warm_cache"), "{}", error);
+    assert!(node.state.get_artifact_for_intent(intent_id).await.is_none());
+}
+
+#[tokio::test]
+async fn test_warned_plan_runs() {
+    let (node, intent_id) = negotiating(UnknownActionPolicy::Warn).await;
+
+    let record = accept(&node, intent_id).await;
+    assert_eq!(record.status, IntentStatus::Complete);
+    assert_eq!(validation_warning(&record), Some("unknown actions: warm_cache"));
+}
+
+#[tokio::test]
+async fn test_ignored_unknown_action_is_not_reported() {
+    let (node, intent_id) = negotiating(UnknownActionPolicy::Ignore).await;
+
+    let record = accept(&node, intent_id).await;
+    assert_eq!(record.status, IntentStatus::Complete);
+    assert_eq!(validation_warning(&record), None);
+}
@@ -0,0 +1,51 @@
+//! Tests of the order in which the engine plans received intents.
+
+use std::time::Duration;
+
+use orpheon_core::{IntentStatus, Priority};
+use orpheon_node::journal::JournalEvent;
+use orpheon_node::queue::QueueDepths;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn test_critical_intent_plans_before_earlier_low_one() {
+    let state = AppState::new();
+    // Hold both intents in the queue until they are submitted
+    state.pause_engine();
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let low = client
+        .submit_detached(Intent::builder().kind("deploy").priority(Priority::Low).build().unwrap())
+        .await
+        .unwrap();
+    let critical = client
+        .submit_detached(Intent::builder().kind("deploy").priority(Priority::Critical).build().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(node.state.intent_queue.depths(), QueueDepths { low: 1, critical: 1, ..Default::default() });
+
+    node.state.resume_engine();
+    timeout(Duration::from_secs(10), async {
+        while node.state.intent_queue.depths().total() > 0 {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("intents were never picked up");
+
+    let planned: Vec<_> = node
+        .state
+        .journal
+        .since(0, 1_000)
+        .into_iter()
+        .filter_map(|entry| match entry.event {
+            JournalEvent::StatusChanged { intent_id, to: IntentStatus::Planning, .. } => Some(intent_id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(planned, vec![critical, low]);
+}
@@ -0,0 +1,30 @@
+//! Tests of where submitted intents are recorded as coming from.
+
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+
+#[tokio::test]
+async fn test_sdk_submissions_record_provenance() {
+    let state = AppState::new();
+    state.pause_engine();
+    let node = TestNode::with_state(state).await;
+    let ci = OrpheonClient::connect(&node.base_url()).await.unwrap().with_source("ci").unwrap();
+    let dashboard = OrpheonClient::connect(&node.base_url()).await.unwrap().with_source("dashboard").unwrap();
+    let anonymous = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let from_ci = ci.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    dashboard.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    anonymous.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+
+    let provenance = ci.get_intent(from_ci).await.unwrap().provenance;
+    assert_eq!(provenance.source.as_deref(), Some("ci"));
+    let client = provenance.client.unwrap();
+    assert!(client.starts_with(&format!("orpheon-sdk/{} (", env!("CARGO_PKG_VERSION"))), "{}", client);
+    assert!(provenance.remote_addr.unwrap().starts_with("127.0.0.1:"));
+
+    let listed = anonymous.list_intents(Some("ci"), &[]).await.unwrap();
+    assert_eq!(listed.iter().map(|i| i.id).collect::<Vec<_>>(), vec![from_ci]);
+    assert!(anonymous.list_intents(Some("cron"), &[]).await.unwrap().is_empty());
+    assert_eq!(anonymous.list_intents(None, &[]).await.unwrap().len(), 3);
+}
@@ -0,0 +1,94 @@
+//! End-to-end tests driving an in-process node with the SDK.
+//!
+//! Start a [`TestNode`], talk to it through `OrpheonClient`, and use
+//! `node.state` for anything the API does not expose.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use orpheon_node::testing::{FailingExecutor, TestNode};
+use orpheon_node::NodeConfig;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+
+/// The intent from `examples/quickstart.rs`.
+fn quickstart_intent() -> Intent {
+    Intent::builder()
+        .kind("provision_gpu_cluster")
+        .resource_limit("count", 8.0)
+        .sla("type", 100, "H100")
+        .budget(Budget::usd(100.0))
+        .minimize("cost", 0.6)
+        .maximize("speed", 0.4)
+        .build()
+        .unwrap()
+}
+
+/// Read events until one matches, failing the test after ten seconds.
+async fn wait_for(stream: &mut EventStream, mut matches: impl FnMut(&Event) -> bool) -> Event {
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let event = stream.next().await.expect("stream ended early");
+            if matches(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for event")
+}
+
+#[tokio::test]
+async fn test_quickstart_flow() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(quickstart_intent()).await.unwrap();
+    let intent_id = stream.intent_id();
+
+    let event = wait_for(&mut stream, |e| matches!(e, Event::Complete { .. } | Event::Error { .. })).await;
+    let Event::Complete { artifact_id } = event else {
+        panic!("intent did not complete: {event:?}");
+    };
+
+    let (artifact, report) = client.get_verified_artifact(intent_id).await.unwrap();
+    assert_eq!(artifact.id, artifact_id);
+    assert!(artifact.outcome.is_success());
+    assert!(report.is_valid());
+}
+
+#[tokio::test]
+async fn test_failing_step_fails_intent() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    node.state
+        .set_step_executor(Arc::new(FailingExecutor::on_action("deploy_workload").with_reason("quota exceeded")))
+        .await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(quickstart_intent()).await.unwrap();
+    let intent_id = stream.intent_id();
+    wait_for(&mut stream, |e| matches!(e, Event::StatusUpdate { status, .. } if status == "failed")).await;
+
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert!(record.error.unwrap().contains("quota exceeded"));
+
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    assert!(artifact.outcome.is_failure());
+    assert_eq!(artifact.failed_steps().len(), 1);
+}
+
+#[tokio::test]
+async fn test_paused_engine_holds_intents() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    node.state.pause_engine();
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(quickstart_intent()).await.unwrap();
+    let intent_id = stream.intent_id();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(client.get_intent(intent_id).await.unwrap().status, "received");
+
+    node.state.resume_engine();
+    wait_for(&mut stream, |e| matches!(e, Event::Complete { .. })).await;
+}
@@ -0,0 +1,67 @@
+//! End-to-end tests of client actions racing the engine.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::{IntentStatus, Step};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::sync::{Barrier, Semaphore};
+use tokio::time::{sleep, timeout};
+
+/// Executor that meets the test at the barrier when the first step
+/// starts, then holds that step until the gate is opened.
+struct GatedExecutor {
+    first: AtomicBool,
+    started: Barrier,
+    gate: Semaphore,
+}
+
+#[async_trait]
+impl StepExecutor for GatedExecutor {
+    async fn execute(&self, _step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        if self.first.swap(false, Ordering::SeqCst) {
+            self.started.wait().await;
+            self.gate.acquire().await.unwrap().forget();
+        }
+        Ok(StepOutput::new(1))
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_during_execution_wins_over_completion() {
+    let state = AppState::new();
+    let executor = Arc::new(GatedExecutor { first: AtomicBool::new(true), started: Barrier::new(2), gate: Semaphore::new(0) });
+    state.set_step_executor(executor.clone()).await;
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent_id = client.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    timeout(Duration::from_secs(10), executor.started.wait()).await.expect("execution did not start");
+
+    // Cancel while the first step runs, then let the plan succeed
+    client.cancel(intent_id).await.unwrap();
+    executor.gate.add_permits(1);
+
+    let orphaned = timeout(Duration::from_secs(10), async {
+        loop {
+            let orphaned = node.state.orphaned_artifacts(intent_id).await;
+            if !orphaned.is_empty() {
+                return orphaned;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the execution's artifact was not filed");
+
+    assert!(orphaned[0].outcome.is_success());
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert_eq!(record.status, IntentStatus::Cancelled);
+    assert_eq!(record.artifact_id, None);
+    assert!(node.state.get_artifact_for_intent(intent_id).await.is_none());
+}
@@ -0,0 +1,108 @@
+//! End-to-end tests of acceptance receipts for negotiated intents.
+
+use std::time::Duration;
+
+use orpheon_core::{IntentStatus, NodeKey};
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use orpheon_sdk::verify::{CheckKind, CheckStatus};
+use tokio::time::{sleep, timeout};
+use uuid::Uuid;
+
+/// A node with one negotiated intent whose first proposal is out, and a
+/// client trusting the node's key.
+async fn negotiating() -> (TestNode, OrpheonClient, Uuid) {
+    let state = AppState::new();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url())
+        .await
+        .unwrap()
+        .with_node_public_key(node.state.node_key.public_key_hex());
+
+    timeout(Duration::from_secs(10), async {
+        while node.state.get_intent(intent_id).await.unwrap().status != IntentStatus::Negotiating {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("no proposal was made");
+    (node, client, intent_id)
+}
+
+/// Accept the proposal on offer and wait for the intent to finish.
+async fn accept_and_finish(node: &TestNode, intent_id: Uuid) {
+    let handle = node.state.get_negotiation(intent_id).await.unwrap();
+    let proposal = handle.session.current_proposal().await.unwrap();
+    handle.session.accept(proposal.id).await.unwrap();
+
+    timeout(Duration::from_secs(10), async {
+        while node.state.get_artifact_for_intent(intent_id).await.is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("accepted intent did not finish");
+}
+
+#[tokio::test]
+async fn test_receipt_is_issued_and_links_to_artifact() {
+    let (node, client, intent_id) = negotiating().await;
+
+    // No receipt until the client accepts
+    let err = client.get_receipt(intent_id).await.unwrap_err();
+    assert!(matches!(err, OrpheonError::NotReady { .. }), "{:?}", err);
+
+    accept_and_finish(&node, intent_id).await;
+
+    let receipt = client.get_receipt(intent_id).await.unwrap();
+    let proposal = node.state.get_negotiation(intent_id).await.unwrap().session.current_proposal().await.unwrap();
+    assert_eq!(receipt.intent_id, intent_id);
+    assert_eq!(receipt.proposal_id, proposal.id);
+    assert_eq!(receipt.quoted_cost, proposal.quoted_cost);
+
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    assert_eq!(artifact.execution_metadata.extra["receipt_id"], receipt.id.to_string());
+
+    let (verified, report) = client.get_verified_receipt(intent_id).await.unwrap();
+    assert_eq!(verified.id, receipt.id);
+    assert!(report.checks.iter().all(|c| c.status == CheckStatus::Passed), "{:?}", report.checks);
+
+    // The artifact still verifies with the receipt ID in its metadata
+    client.get_verified_artifact(intent_id).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_tampered_receipt_is_detected() {
+    let (node, client, intent_id) = negotiating().await;
+    accept_and_finish(&node, intent_id).await;
+
+    let artifact = client.get_artifact(intent_id).await.unwrap();
+    let mut receipt = client.get_receipt(intent_id).await.unwrap();
+    receipt.quoted_cost /= 2.0;
+    let key = node.state.node_key.public_key_hex();
+    let report = verify_receipt(&receipt, &artifact, Some(&key));
+    assert_eq!(report.check(CheckKind::ReceiptSignature).unwrap().status, CheckStatus::Failed);
+
+    // A receipt re-signed by someone else for a different plan fails both checks
+    receipt.plan_hash = "00".repeat(32);
+    receipt.sign(&NodeKey::generate());
+    let report = verify_receipt(&receipt, &artifact, Some(&key));
+    assert_eq!(report.failures().len(), 2);
+}
+
+#[tokio::test]
+async fn test_intent_without_negotiation_has_no_receipt() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent_id = client.submit_detached(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+
+    let err = client.get_receipt(intent_id).await.unwrap_err();
+    assert!(matches!(err, OrpheonError::NotFound { .. }), "{:?}", err);
+    let err = client.get_receipt(Uuid::new_v4()).await.unwrap_err();
+    assert!(matches!(err, OrpheonError::NotFound { .. }), "{:?}", err);
+}
@@ -0,0 +1,110 @@
+//! Tests of re-quoting accepted proposals whose price moved.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::{ExecutionArtifact, Step};
+use orpheon_negotiate::NegotiationMessage;
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::requote::{CostModel, RequoteConfig, RequotePolicy, COST_DRIFT};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+/// Cost model whose prices can be changed mid-negotiation.
+#[derive(Default)]
+struct Shifting(Mutex<Option<f64>>);
+
+impl Shifting {
+    fn set(&self, price: f64) {
+        *self.0.lock().unwrap() = Some(price);
+    }
+}
+
+#[async_trait]
+impl CostModel for Shifting {
+    async fn price(&self, _step: &Step) -> Option<f64> {
+        *self.0.lock().unwrap()
+    }
+}
+
+/// A node negotiating one intent under `policy`, priced by the returned
+/// model.
+async fn negotiating(policy: RequotePolicy) -> (TestNode, Arc<Shifting>, Uuid) {
+    let mut state = AppState::new();
+    state.requote = RequoteConfig { policy, ..RequoteConfig::default() };
+    let model = Arc::new(Shifting::default());
+    state.set_cost_model(model.clone()).await;
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    (TestNode::with_state(state).await, model, intent_id)
+}
+
+async fn artifact(state: &AppState, intent_id: Uuid) -> ExecutionArtifact {
+    timeout(Duration::from_secs(20), async {
+        loop {
+            if let Some(artifact) = state.get_artifact_for_intent(intent_id).await {
+                return artifact;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("intent did not execute in time")
+}
+
+#[tokio::test]
+async fn test_drift_is_recorded_when_proceeding() {
+    let (node, model, intent_id) = negotiating(RequotePolicy::Proceed).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+
+    let proposal = negotiation.next_proposal().await.unwrap().unwrap();
+    let steps = proposal.plan.steps.len() as f64;
+    model.set(proposal.quoted_cost * 2.0 / steps);
+    negotiation.accept(proposal.id).await.unwrap();
+
+    let artifact = artifact(&node.state, intent_id).await;
+    let drift = &artifact.execution_metadata.extra["cost_drift"];
+    assert_eq!(drift["decision"], "proceeded");
+    assert_eq!(drift["proposal_id"], proposal.id.to_string());
+    assert!((drift["drift"].as_f64().unwrap() - 1.0).abs() < 1e-9, "{}", drift);
+
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert!(record.warnings.iter().any(|w| w.code == COST_DRIFT), "{:?}", record.warnings);
+}
+
+#[tokio::test]
+async fn test_drift_sends_a_fresh_proposal_when_renegotiating() {
+    let (node, model, intent_id) = negotiating(RequotePolicy::Renegotiate).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut negotiation = client.negotiate(intent_id).await.unwrap();
+
+    let first = negotiation.next_proposal().await.unwrap().unwrap();
+    let steps = first.plan.steps.len() as f64;
+    model.set(first.quoted_cost * 3.0 / steps);
+    negotiation.accept(first.id).await.unwrap();
+
+    let second = timeout(Duration::from_secs(20), negotiation.next_proposal()).await.unwrap().unwrap().unwrap();
+    assert_ne!(second.id, first.id);
+    assert!((second.quoted_cost - first.quoted_cost * 3.0).abs() < 1e-9);
+    assert!(node.state.get_artifact_for_intent(intent_id).await.is_none());
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert!(record.warnings.iter().any(|w| w.code == COST_DRIFT && w.message.contains("fresh proposal")));
+
+    // The fresh proposal is at current prices, so it runs as accepted
+    negotiation.accept(second.id).await.unwrap();
+    loop {
+        match negotiation.next().await.unwrap() {
+            Some(NegotiationMessage::Confirmed { .. }) => break,
+            Some(_) => continue,
+            None => panic!("negotiation ended unconfirmed"),
+        }
+    }
+    let artifact = artifact(&node.state, intent_id).await;
+    assert!(artifact.execution_metadata.extra.get("cost_drift").is_none());
+}
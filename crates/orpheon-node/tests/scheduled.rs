@@ -0,0 +1,203 @@
+//! Tests of intents deferred until their validity window opens, or until
+//! one of its daily windows does, and of intents expiring when it closes.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::{IntentStatus, Step, TimeWindow};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+/// A window closing `ms` from now.
+fn closing_in(ms: i64) -> TimeWindow {
+    TimeWindow { not_before: None, not_after: Some(chrono::Utc::now() + chrono::Duration::milliseconds(ms)), recurrence: Vec::new() }
+}
+
+#[tokio::test]
+async fn test_deferred_intent_waits_for_its_window() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent = Intent::builder().kind("deploy").starts_in(chrono::Duration::milliseconds(200)).build().unwrap();
+    let opens = intent.validity_window.not_before.unwrap();
+    let id = client.submit_detached(intent).await.unwrap();
+
+    // Received, and saying when it will start, until the window opens
+    let response = client.get_intent(id).await.unwrap();
+    assert_eq!(response.status, "received");
+    assert_eq!(response.scheduled_for, Some(opens.to_rfc3339()));
+    while chrono::Utc::now() < opens - chrono::Duration::milliseconds(20) {
+        let response = client.get_intent(id).await.unwrap();
+        assert_eq!(response.status, "received", "started before its window opened");
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    let response = timeout(Duration::from_secs(10), async {
+        loop {
+            let response = client.get_intent(id).await.unwrap();
+            if response.status == "complete" {
+                break response;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("deferred intent never ran");
+    assert_eq!(response.scheduled_for, None);
+}
+
+#[tokio::test]
+async fn test_intent_with_closed_window_is_rejected() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let closed = TimeWindow {
+        not_before: None,
+        not_after: Some(chrono::Utc::now() - chrono::Duration::minutes(1)),
+        recurrence: Vec::new(),
+    };
+    let intent = Intent::builder().kind("deploy").validity_window(closed).build().unwrap();
+    assert!(client.submit_detached(intent).await.is_err());
+}
+
+#[tokio::test]
+async fn test_intent_expires_when_its_window_closes() {
+    // Negotiated, so it waits for a client that never accepts
+    let state = AppState::new();
+    let intent = Intent::builder().kind("deploy").validity_window(closing_in(100)).build().unwrap();
+    let id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    let node = TestNode::with_state(state.clone()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut stream = EventStream::connect(&format!("{}/ws/intent/{}", node.ws_url(), id), id).await.unwrap();
+
+    let mut events = Vec::new();
+    while let Some(event) = timeout(Duration::from_secs(10), stream.next()).await.expect("stream did not end in time") {
+        events.push(event);
+    }
+    assert!(
+        events.iter().any(|e| matches!(e, Event::StatusUpdate { status, .. } if status == "expired")),
+        "{:?}",
+        events
+    );
+    assert!(!events.iter().any(|e| matches!(e, Event::Executing { .. })), "{:?}", events);
+
+    let record = state.get_intent(id).await.unwrap();
+    assert_eq!(record.status, IntentStatus::Expired);
+    assert!(record.error.unwrap().starts_with("validity window closed"));
+    assert!(client.get_artifact(id).await.is_err());
+
+    // Expired is final
+    assert!(!state.cancel_intent(id).await);
+    assert!(client.cancel(id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_window_closing_stops_planning() {
+    // A long chain of actions with a slow heuristic, so planning it takes
+    // seconds, and a planner allowed to spend the whole window on it
+    let catalog = (1..=100)
+        .map(|i| PlanningAction {
+            name: format!("step_{}", i),
+            preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+            effects: vec![if i == 100 { "complete".to_string() } else { format!("s{}", i) }],
+            cost: 1.0,
+            duration_ms: 1,
+            ..Default::default()
+        })
+        .collect();
+    let expanded = Arc::new(AtomicUsize::new(0));
+    let counter = expanded.clone();
+    let config = PlannerConfig { max_steps: 200, deadline_budget_fraction: 1.0, ..Default::default() };
+    let planner = AStarPlanner::with_actions(config, catalog).with_heuristic(move |_, _| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        0.0
+    });
+    let node = TestNode::with_state(AppState::with_planner(planner)).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent = Intent::builder().kind("deploy").validity_window(closing_in(300)).build().unwrap();
+    let id = client.submit_detached(intent).await.unwrap();
+    timeout(Duration::from_secs(10), async {
+        while client.get_intent(id).await.unwrap().status != "expired" {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent never expired");
+
+    // The search stops at its next node rather than running on
+    sleep(Duration::from_millis(100)).await;
+    let stopped_at = expanded.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(expanded.load(Ordering::SeqCst), stopped_at);
+    assert!(stopped_at < 100, "{}", stopped_at);
+}
+
+/// Time of day `ms` from now.
+fn time_in(ms: i64) -> chrono::NaiveTime {
+    (chrono::Utc::now() + chrono::Duration::milliseconds(ms)).time()
+}
+
+#[tokio::test]
+async fn test_recurring_intent_waits_for_its_daily_window() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let intent = Intent::builder().kind("maintenance").recurring_window(time_in(300), time_in(3_600_000), vec![]).build().unwrap();
+    let opens = intent.validity_window.next_opening().unwrap();
+    let id = client.submit_detached(intent).await.unwrap();
+    assert_eq!(client.get_intent(id).await.unwrap().scheduled_for, Some(opens.to_rfc3339()));
+    while chrono::Utc::now() < opens - chrono::Duration::milliseconds(20) {
+        assert_eq!(client.get_intent(id).await.unwrap().status, "received", "started before its daily window opened");
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    timeout(Duration::from_secs(10), async {
+        while client.get_intent(id).await.unwrap().status != "complete" {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("recurring intent never ran");
+}
+
+/// Executor whose steps take `ms` each.
+struct Slow(u64);
+
+#[async_trait]
+impl StepExecutor for Slow {
+    async fn execute(&self, _step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        sleep(Duration::from_millis(self.0)).await;
+        Ok(StepOutput::new(self.0))
+    }
+}
+
+#[tokio::test]
+async fn test_execution_outlives_its_daily_window() {
+    let state = AppState::new();
+    state.set_step_executor(Arc::new(Slow(400))).await;
+    let node = TestNode::with_state(state.clone()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    // Open for a moment more, which may cross midnight
+    let intent = Intent::builder().kind("maintenance").recurring_window(time_in(-60_000), time_in(200), vec![]).build().unwrap();
+    let id = client.submit_detached(intent).await.unwrap();
+    timeout(Duration::from_secs(10), async {
+        while client.get_intent(id).await.unwrap().status != "complete" {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("intent was not allowed to finish");
+    assert!(!state.get_intent(id).await.unwrap().intent.validity_window.is_valid_now());
+}
@@ -0,0 +1,86 @@
+//! Tests that the served JSON Schemas match what the node reads and writes.
+
+use chrono::Duration;
+use jsonschema::JSONSchema;
+use orpheon_core::intent::{OptimizationDirection, Preference};
+use orpheon_core::{Budget, Constraint, NodeKey, OrpheonError};
+use orpheon_node::testing::TestNode;
+use orpheon_node::NodeConfig;
+use orpheon_sdk::prelude::*;
+use serde_json::json;
+
+fn compile(schema: &serde_json::Value) -> JSONSchema {
+    JSONSchema::compile(schema).expect("schema does not compile")
+}
+
+fn assert_valid(schema: &JSONSchema, document: &serde_json::Value) {
+    if let Err(errors) = schema.validate(document) {
+        let errors: Vec<String> = errors.map(|e| format!("{} at {}", e, e.instance_path)).collect();
+        panic!("document does not match its schema: {}", errors.join("; "));
+    }
+}
+
+/// An intent using every kind of constraint and optional field.
+fn example_intent() -> Intent {
+    let mut intent = Intent::builder()
+        .kind("deploy")
+        .state_match("region == 'us-east'")
+        .resource_limit("total_cost", 5.0)
+        .sla("latency", 200, "ms")
+        .deadline_in(Duration::hours(2))
+        .provider("node-a")
+        .geo_fence(vec!["us-east".to_string()], true)
+        .constraint(Constraint::Custom { name: "quota".to_string(), data: json!({ "max": 3 }) })
+        .soft_constraint(Constraint::Provider { node_id: "node-b".to_string() })
+        .preference(Preference { objective: "cost".to_string(), direction: OptimizationDirection::Minimize, weight: 0.7 })
+        .maximize("reliability", 0.3)
+        .budget(Budget::usd_cents(1_999).with_duration(60_000))
+        .metadata(json!({ "team": "ml", "labels": { "env": "prod" } }))
+        .build()
+        .unwrap();
+    NodeKey::generate().sign_intent(&mut intent);
+    intent
+}
+
+#[tokio::test]
+async fn test_example_intent_matches_served_schema() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let schema = compile(&client.fetch_schema("intent").await.unwrap());
+    let intent = serde_json::to_value(example_intent()).unwrap();
+    assert_valid(&schema, &intent);
+
+    // Drift in the other direction: a shape the node would refuse
+    let mut unknown = intent.clone();
+    unknown["constraints"][0]["type"] = json!("telepathy");
+    assert!(!schema.is_valid(&unknown));
+    let mut missing = intent;
+    missing.as_object_mut().unwrap().remove("kind");
+    assert!(!schema.is_valid(&missing));
+
+    let err = client.fetch_schema("proposal").await.unwrap_err();
+    assert!(matches!(err, OrpheonError::NotFound { .. }), "{}", err);
+}
+
+#[tokio::test]
+async fn test_submit_bodies_match_served_schema() {
+    let node = TestNode::spawn(NodeConfig::default()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let schema = compile(&client.fetch_schema("submit_intent_request").await.unwrap());
+
+    let full = json!({ "intent": example_intent(), "negotiation": { "max_rounds": 2 }, "validation": "strict" });
+    assert_valid(&schema, &full);
+    let simple = json!({
+        "kind": "deploy",
+        "constraints": [{ "type": "resource_limit", "resource": "total_cost", "limit": 5.0 }],
+        "preferences": [{ "objective": "cost", "direction": "minimize", "weight": 1.0 }],
+        "budget": { "max_cost": 10.0, "currency": "USD" },
+    });
+    assert_valid(&schema, &simple);
+
+    // So does what the node writes
+    let artifact_schema = compile(&client.fetch_schema("execution_artifact").await.unwrap());
+    let completion = client.submit_and_wait(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+    assert_valid(&artifact_schema, &serde_json::to_value(completion.artifact()).unwrap());
+}
@@ -0,0 +1,152 @@
+//! End-to-end tests of plans split into segments at `max_steps`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::{IntentStatus, Step};
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use tokio::time::{sleep, timeout};
+
+/// Executor that records the actions it ran.
+#[derive(Default)]
+struct RecordingExecutor {
+    actions: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl StepExecutor for RecordingExecutor {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        self.actions.lock().unwrap().push(step.action.clone());
+        Ok(StepOutput::new(1))
+    }
+}
+
+/// A node whose catalog needs twelve chained steps, with at most five per plan.
+async fn node() -> (TestNode, Arc<RecordingExecutor>) {
+    let catalog = (1..=12)
+        .map(|i| PlanningAction {
+            name: format!("step_{:02}", i),
+            preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+            effects: vec![if i == 12 { "complete".to_string() } else { format!("s{}", i) }],
+            cost: 1.5,
+            duration_ms: 10,
+            ..Default::default()
+        })
+        .collect();
+    let config = PlannerConfig { max_steps: 5, split_long_plans: true, ..Default::default() };
+    let state = AppState::with_planner(AStarPlanner::with_actions(config, catalog));
+    let executor = Arc::new(RecordingExecutor::default());
+    state.set_step_executor(executor.clone()).await;
+    (TestNode::with_state(state).await, executor)
+}
+
+fn expected_actions() -> Vec<String> {
+    (1..=12).map(|i| format!("step_{:02}", i)).collect()
+}
+
+#[tokio::test]
+async fn test_split_plan_runs_as_one_artifact() {
+    let (node, executor) = node().await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+
+    let completion = timeout(Duration::from_secs(15), client.submit_and_wait(intent))
+        .await
+        .expect("intent did not finish in time")
+        .unwrap();
+    let artifact = completion.into_artifact();
+    let intent_id = artifact.intent.id;
+
+    // Segments ran in order, into one artifact covering every step
+    assert_eq!(*executor.actions.lock().unwrap(), expected_actions());
+    assert!(artifact.outcome.is_success());
+    assert_eq!(artifact.final_plan.steps.len(), 12);
+    assert_eq!(artifact.successful_steps().len(), 12);
+    assert_eq!(artifact.final_plan.estimated_cost, 18.0);
+
+    // The stored plan is the first segment, continued by two more
+    let head = node.state.get_plan_for_intent(intent_id).await.unwrap();
+    assert_eq!(head.id, artifact.final_plan.id);
+    assert_eq!(head.steps.len(), 5);
+    let segments = node.state.plan_segments(head).await;
+    assert_eq!(segments.iter().map(|s| s.steps.len()).collect::<Vec<_>>(), vec![5, 5, 2]);
+    assert_eq!(segments[1].continuation_of, Some(segments[0].id));
+    assert_eq!(segments[2].continuation_of, Some(segments[1].id));
+    let record = node.state.get_intent(intent_id).await.unwrap();
+    assert_eq!(record.plan_ids, vec![segments[0].id]);
+}
+
+#[tokio::test]
+async fn test_proposal_quotes_all_segments() {
+    let (node, _executor) = node().await;
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    node.state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+
+    let proposal = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(handle) = node.state.get_negotiation(intent_id).await {
+                if let Some(proposal) = handle.session.current_proposal().await {
+                    break proposal;
+                }
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("no proposal was made");
+
+    assert_eq!(node.state.get_intent(intent_id).await.unwrap().status, IntentStatus::Negotiating);
+    assert_eq!(proposal.quoted_cost, 18.0);
+    assert_eq!(proposal.line_items.len(), 12);
+    assert_eq!(proposal.estimated_latency_ms, 120);
+}
+
+#[tokio::test]
+async fn test_receipt_covers_all_segments() {
+    let (node, _executor) = node().await;
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    node.state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+
+    let handle = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(handle) = node.state.get_negotiation(intent_id).await {
+                if handle.session.current_proposal().await.is_some() {
+                    break handle;
+                }
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("no proposal was made");
+    let proposal = handle.session.current_proposal().await.unwrap();
+    handle.session.accept(proposal.id).await.unwrap();
+
+    let client = OrpheonClient::connect(&node.base_url())
+        .await
+        .unwrap()
+        .with_node_public_key(node.state.node_key.public_key_hex());
+    let artifact = timeout(Duration::from_secs(10), async {
+        loop {
+            if let Some(artifact) = node.state.get_artifact_for_intent(intent_id).await {
+                break artifact;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("accepted intent did not finish");
+
+    // The accepted plan is the segments joined, as executed
+    assert_eq!(artifact.final_plan.steps.len(), 12);
+    client.get_verified_receipt(intent_id).await.unwrap();
+}
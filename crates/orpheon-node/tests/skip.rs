@@ -0,0 +1,116 @@
+//! End-to-end tests of `skip_if` conditions on plan steps.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_core::Step;
+use orpheon_node::engine::{StepContext, StepExecutor, StepOutput};
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_planner::planner::PlanningAction;
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use orpheon_sdk::prelude::*;
+use orpheon_state::StateStore;
+use serde_json::json;
+use tokio::time::timeout;
+
+/// Executor that records the actions it ran.
+#[derive(Default)]
+struct RecordingExecutor {
+    actions: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl StepExecutor for RecordingExecutor {
+    async fn execute(&self, step: &Step, _ctx: &StepContext) -> std::result::Result<StepOutput, String> {
+        self.actions.lock().unwrap().push(step.action.clone());
+        Ok(StepOutput::new(1))
+    }
+}
+
+async fn node() -> (TestNode, Arc<RecordingExecutor>) {
+    let catalog = vec![
+        PlanningAction {
+            name: "provision_compute".to_string(),
+            effects: vec!["compute_ready".to_string()],
+            cost: 1.0,
+            skip_if: Some("state['compute/gpu-1'].ready".to_string()),
+            ..Default::default()
+        },
+        PlanningAction {
+            name: "verify_health".to_string(),
+            preconditions: vec!["compute_ready".to_string()],
+            effects: vec!["complete".to_string()],
+            cost: 1.0,
+            skip_if: Some("intent.metadata.fast_mode == true".to_string()),
+            ..Default::default()
+        },
+    ];
+    let state = AppState::with_planner(AStarPlanner::with_actions(PlannerConfig::default(), catalog));
+    let executor = Arc::new(RecordingExecutor::default());
+    state.set_step_executor(executor.clone()).await;
+    (TestNode::with_state(state).await, executor)
+}
+
+async fn run(node: &TestNode, intent: Intent) -> ExecutionArtifact {
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let completion = timeout(Duration::from_secs(15), client.submit_and_wait(intent))
+        .await
+        .expect("intent did not finish in time")
+        .unwrap();
+    assert!(!completion.is_partial());
+    completion.into_artifact()
+}
+
+fn skipped_actions(artifact: &ExecutionArtifact) -> Vec<(String, serde_json::Value)> {
+    artifact
+        .trace
+        .iter()
+        .filter(|e| e.event_type == ExecutionEventType::StepSkipped)
+        .map(|e| {
+            let step = artifact.final_plan.steps.iter().find(|s| s.id == e.step_id).unwrap();
+            (step.action.clone(), e.data["skip_if"].clone())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_metadata_condition_skips_step() {
+    let (node, executor) = node().await;
+    let intent = Intent::builder().kind("deploy").metadata(json!({ "fast_mode": true })).build().unwrap();
+
+    let artifact = run(&node, intent).await;
+    assert_eq!(*executor.actions.lock().unwrap(), vec!["provision_compute"]);
+    assert_eq!(
+        skipped_actions(&artifact),
+        vec![("verify_health".to_string(), json!("intent.metadata.fast_mode == true"))]
+    );
+    // Skipped steps neither succeed nor fail
+    assert!(artifact.outcome.is_success());
+    assert_eq!(artifact.success_rate(), 1.0);
+}
+
+#[tokio::test]
+async fn test_state_condition_skips_step_and_dependents_run() {
+    let (node, executor) = node().await;
+    node.state.state_store.set("compute/gpu-1", json!({ "ready": true })).await.unwrap();
+    let intent = Intent::builder().kind("deploy").metadata(json!({ "fast_mode": false })).build().unwrap();
+
+    let artifact = run(&node, intent).await;
+    // verify_health depends on the skipped provisioning step and still runs
+    assert_eq!(*executor.actions.lock().unwrap(), vec!["verify_health"]);
+    assert_eq!(skipped_actions(&artifact)[0].0, "provision_compute");
+}
+
+#[tokio::test]
+async fn test_missing_variable_counts_as_false() {
+    let (node, executor) = node().await;
+    // No fast_mode in the metadata and no compute/gpu-1 state key
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+
+    let artifact = run(&node, intent).await;
+    assert_eq!(*executor.actions.lock().unwrap(), vec!["provision_compute", "verify_health"]);
+    assert!(skipped_actions(&artifact).is_empty());
+}
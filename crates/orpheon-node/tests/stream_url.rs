@@ -0,0 +1,83 @@
+//! Tests of how the SDK finds an intent's event stream.
+
+use axum::Router;
+use futures::SinkExt;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Serve one WebSocket connection on any path, sending a single status
+/// update with the given status; returns the shim's base URL.
+async fn shim(status: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+        let frame = serde_json::json!({
+            "type": "status_update",
+            "intent_id": Uuid::nil(),
+            "seq": 1,
+            "status": status,
+            "plan_id": null,
+            "artifact_id": null,
+        });
+        ws.send(Message::Text(frame.to_string())).await.unwrap();
+        let _ = ws.close(None).await;
+    });
+    format!("ws://{}", addr)
+}
+
+async fn first_status(stream: &mut EventStream) -> String {
+    match stream.next().await {
+        Some(Event::StatusUpdate { status, .. }) => status,
+        event => panic!("expected a status update, got {:?}", event),
+    }
+}
+
+fn intent() -> Intent {
+    Intent::builder().kind("deploy").build().unwrap()
+}
+
+#[tokio::test]
+async fn test_advertised_stream_url_is_preferred() {
+    let mut state = AppState::new();
+    state.public_ws_url = Some(shim("from_advertised_url").await);
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = client.submit(intent()).await.unwrap();
+    assert_eq!(first_status(&mut stream).await, "from_advertised_url");
+}
+
+#[tokio::test]
+async fn test_ws_base_url_override_wins() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url())
+        .await
+        .unwrap()
+        .with_ws_base_url(shim("from_override").await + "/");
+
+    let mut stream = client.submit(intent()).await.unwrap();
+    assert_eq!(first_status(&mut stream).await, "from_override");
+}
+
+#[tokio::test]
+async fn test_path_prefix_is_kept_when_advertised_url_fails() {
+    // A gateway serving the node under `/orpheon`: the stream URL the node
+    // builds from the Host header lacks the prefix and cannot be reached
+    let node = TestNode::with_state(AppState::new()).await;
+    let gateway = Router::new().nest("/orpheon", orpheon_node::create_router(node.state.clone()));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, gateway).await.unwrap();
+    });
+
+    let client = OrpheonClient::connect(&format!("http://{}/orpheon/", addr)).await.unwrap();
+    let completion = client.submit_and_wait(intent()).await.unwrap();
+    assert!(!completion.is_partial());
+}
@@ -0,0 +1,96 @@
+//! Tests of the reasons a node gives for closing WebSocket streams.
+
+use std::time::Duration;
+
+use futures::{SinkExt, StreamExt};
+use orpheon_core::WsCloseReason;
+use orpheon_node::negotiation::NegotiationOptions;
+use orpheon_node::state::AppState;
+use orpheon_node::testing::TestNode;
+use orpheon_sdk::prelude::*;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Events until the stream ends.
+async fn drain(stream: &mut EventStream) -> Vec<Event> {
+    let mut events = Vec::new();
+    while let Some(event) = timeout(Duration::from_secs(20), stream.next()).await.expect("stream did not end in time") {
+        events.push(event);
+    }
+    events
+}
+
+fn close_reason(events: &[Event]) -> Option<WsCloseReason> {
+    match events.last() {
+        Some(Event::Closed { reason }) => Some(*reason),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_intent_is_not_found() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let intent_id = Uuid::new_v4();
+    let mut stream = EventStream::connect(&format!("{}/ws/intent/{}", node.ws_url(), intent_id), intent_id).await.unwrap();
+
+    let events = drain(&mut stream).await;
+    assert!(matches!(events[0], Event::Error { .. }), "{:?}", events);
+    assert_eq!(close_reason(&events), Some(WsCloseReason::NotFound));
+}
+
+#[tokio::test]
+async fn test_finished_intent_is_terminal() {
+    let node = TestNode::with_state(AppState::new()).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+    let mut stream = client.submit(Intent::builder().kind("deploy").build().unwrap()).await.unwrap();
+
+    let events = drain(&mut stream).await;
+    assert_eq!(close_reason(&events), Some(WsCloseReason::Terminal));
+    assert!(matches!(events[events.len() - 2], Event::Complete { .. }), "{:?}", events);
+}
+
+#[tokio::test]
+async fn test_shutdown_closes_open_streams() {
+    let state = AppState::new();
+    state.pause_engine();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent(intent).await;
+    let node = TestNode::with_state(state).await;
+    let client = OrpheonClient::connect(&node.base_url()).await.unwrap();
+
+    let mut stream = EventStream::connect(&format!("{}/ws/intent/{}", node.ws_url(), intent_id), intent_id).await.unwrap();
+    let mut watching = client.watch_intents(WatchFilter::ids(vec![intent_id])).await.unwrap();
+    assert!(stream.next().await.is_some());
+    assert_eq!(watching.next().await.unwrap().0, intent_id);
+
+    node.state.begin_shutdown();
+    assert_eq!(close_reason(&drain(&mut stream).await), Some(WsCloseReason::ServerShutdown));
+    let (id, event) = timeout(Duration::from_secs(10), watching.next()).await.unwrap().unwrap();
+    assert_eq!(id, Uuid::nil());
+    assert!(matches!(event, Event::Closed { reason: WsCloseReason::ServerShutdown }), "{:?}", event);
+}
+
+#[tokio::test]
+async fn test_broken_handshake_is_a_protocol_error() {
+    let state = AppState::new();
+    let intent = Intent::builder().kind("deploy").build().unwrap();
+    let intent_id = intent.id;
+    state.store_intent_with_negotiation(intent, Some(NegotiationOptions::default())).await;
+    let node = TestNode::with_state(state).await;
+
+    let url = format!("{}/ws/negotiate/{}", node.ws_url(), intent_id);
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    let hello = serde_json::json!({ "type": "hello", "protocol_version": 0, "supported_features": [] });
+    socket.send(Message::Text(hello.to_string())).await.unwrap();
+
+    let frame = loop {
+        match timeout(Duration::from_secs(10), socket.next()).await.unwrap().unwrap().unwrap() {
+            Message::Close(frame) => break frame.expect("close frame without a reason"),
+            _ => continue,
+        }
+    };
+    assert_eq!(WsCloseReason::from_name(&frame.reason), Some(WsCloseReason::ProtocolError));
+    assert_eq!(u16::from(frame.code), WsCloseReason::ProtocolError.close_code());
+}
@@ -1,21 +1,80 @@
 //! A* search-based planner implementation.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::cmp::Ordering;
-use std::time::Instant;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use orpheon_core::{Intent, OrpheonError, Plan, PlanningStrategy, Result, Step};
+use chrono::Utc;
+use orpheon_core::crypto::hex_encode;
+use orpheon_core::constraint::budget_constraints;
+use orpheon_core::{
+    finite_or_err, Constraint, EvaluationContext, Evaluator, ExecutionContext, Intent, OrpheonError, Plan,
+    PlanningStrategy, ResourceType, Result, Step,
+};
+use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::planner::{Planner, PlannerConfig, PlanningAction, PlanningState};
+use crate::cancel::CancellationToken;
+use crate::durations::LearnedDurations;
+use crate::objective::Objective;
+use crate::planner::{
+    FailedPrecondition, Planner, PlannerConfig, PlanningAction, PlanningResult, PlanningState, UnknownActionPolicy,
+    ValidationReport,
+};
+
+/// Tolerance used when comparing costs during heuristic validation.
+const HEURISTIC_EPSILON: f64 = 1e-9;
+
+/// Most frontier nodes kept from a search that ran out of budget.
+const MAX_CHECKPOINT_FRONTIER: usize = 256;
+
+/// Most partial searches kept at once; the oldest is dropped first.
+const MAX_CHECKPOINTS: usize = 64;
+
+/// Heuristic function estimating the remaining cost from a state to the goal.
+pub type HeuristicFn = Box<dyn Fn(&PlanningState, &Intent) -> f64 + Send + Sync>;
 
 /// A* search-based planner.
 pub struct AStarPlanner {
     config: PlannerConfig,
     /// Available actions the planner can use.
     actions: Vec<PlanningAction>,
+    /// Custom heuristic; the built-in one is used when unset.
+    heuristic: Option<HeuristicFn>,
+    /// Name of the file the extra actions were loaded from, if any.
+    catalog_source: Option<String>,
+    /// Partial searches left by attempts that ran out of budget, keyed by
+    /// [`AStarPlanner::plan_cache_key`].
+    checkpoints: Mutex<HashMap<String, SearchCheckpoint>>,
+    /// Judges the constraints each candidate action would leave in place.
+    evaluator: Evaluator,
+    /// Durations learned from past executions, preferred over the catalog's.
+    durations: Arc<LearnedDurations>,
+}
+
+/// Counters collected during a single search.
+#[derive(Default)]
+struct SearchStats {
+    states_explored: usize,
+    heuristic_violations: usize,
+    /// Set when the search ran out of budget; lets the next attempt resume.
+    checkpoint: Option<SearchCheckpoint>,
+    /// The open set emptied without reaching the goal.
+    exhausted: bool,
+}
+
+/// Search progress saved when an attempt runs out of budget.
+struct SearchCheckpoint {
+    saved_at: Instant,
+    /// Hashes of states already expanded.
+    closed: HashSet<u64>,
+    /// Best unexpanded nodes, lowest f(n) first.
+    frontier: Vec<SearchNode>,
 }
 
 /// Node in the A* search tree.
@@ -31,10 +90,40 @@ struct SearchNode {
     h_cost: f64,
     /// f(n) = g(n) + h(n).
     f_cost: f64,
+    /// Indices of soft constraints violated along this path.
+    soft_violations: Vec<usize>,
+    /// Highest f(n) seen on the path to this node, and the depth it was seen at.
+    path_peak: (f64, usize),
+    /// Order the node was generated in; breaks ties between equal f(n),
+    /// first generated first, so the same search always finds the same plan.
+    seq: u64,
     /// Unique identifier for this node.
     id: Uuid,
 }
 
+impl SearchNode {
+    /// Hash of everything that decides how the search continues from this
+    /// node: the state variables set, time and resources used and soft
+    /// constraints violated.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        sorted_keys(&self.state).hash(&mut hasher);
+        self.state.accumulated_time_ms.hash(&mut hasher);
+        let mut resources: Vec<(String, u64)> = self
+            .state
+            .resources_used
+            .iter()
+            .map(|(resource, used)| (resource.name(), used.to_bits()))
+            .collect();
+        resources.sort_unstable();
+        resources.hash(&mut hasher);
+        let mut violations = self.soft_violations.clone();
+        violations.sort_unstable();
+        violations.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
 impl PartialEq for SearchNode {
     fn eq(&self, other: &Self) -> bool {
         self.id == other.id
@@ -45,8 +134,23 @@ impl Eq for SearchNode {}
 
 impl Ord for SearchNode {
     fn cmp(&self, other: &Self) -> Ordering {
+        debug_assert!(
+            !self.f_cost.is_nan() && !other.f_cost.is_nan(),
+            "NaN f(n) in the A* open set"
+        );
         // Reverse ordering for min-heap (lower f_cost = higher priority)
-        other.f_cost.partial_cmp(&self.f_cost).unwrap_or(Ordering::Equal)
+        compare_f_cost(other.f_cost, self.f_cost).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Order f(n) values, with NaN above every number so that a node whose
+/// cost could not be computed is expanded last rather than anywhere.
+fn compare_f_cost(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
     }
 }
 
@@ -62,6 +166,11 @@ impl AStarPlanner {
         Self {
             config: PlannerConfig::default(),
             actions: Self::default_actions(),
+            heuristic: None,
+            catalog_source: None,
+            checkpoints: Mutex::new(HashMap::new()),
+            evaluator: Evaluator::new(),
+            durations: Arc::new(LearnedDurations::default()),
         }
     }
 
@@ -70,12 +179,92 @@ impl AStarPlanner {
         Self {
             config,
             actions: Self::default_actions(),
+            heuristic: None,
+            catalog_source: None,
+            checkpoints: Mutex::new(HashMap::new()),
+            evaluator: Evaluator::new(),
+            durations: Arc::new(LearnedDurations::default()),
         }
     }
 
+    /// Create a new A* planner with a custom action catalog.
+    pub fn with_actions(config: PlannerConfig, actions: Vec<PlanningAction>) -> Self {
+        Self {
+            config,
+            actions,
+            heuristic: None,
+            catalog_source: None,
+            checkpoints: Mutex::new(HashMap::new()),
+            evaluator: Evaluator::new(),
+            durations: Arc::new(LearnedDurations::default()),
+        }
+    }
+
+    /// Replace the built-in heuristic.
+    pub fn with_heuristic(
+        mut self,
+        heuristic: impl Fn(&PlanningState, &Intent) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        self.heuristic = Some(Box::new(heuristic));
+        self
+    }
+
+    /// Evaluate constraints with `evaluator`, e.g. one with checks for
+    /// custom constraints.
+    pub fn with_evaluator(mut self, evaluator: Evaluator) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+    
+    /// The evaluator judging constraints during search.
+    pub fn evaluator(&self) -> &Evaluator {
+        &self.evaluator
+    }
+
+    /// Quote step durations from `durations` once they have enough samples.
+    pub fn with_durations(mut self, durations: Arc<LearnedDurations>) -> Self {
+        self.durations = durations;
+        self
+    }
+
+    /// Durations learned from past executions.
+    pub fn durations(&self) -> &Arc<LearnedDurations> {
+        &self.durations
+    }
+
     /// Register an action that the planner can use.
-    pub fn register_action(&mut self, action: PlanningAction) {
+    ///
+    /// Fails if the action's cost or any of its metrics is not finite.
+    pub fn register_action(&mut self, action: PlanningAction) -> Result<()> {
+        action.validate()?;
         self.actions.push(action);
+        Ok(())
+    }
+    
+    /// Actions the planner can use, in registration order.
+    pub fn actions(&self) -> &[PlanningAction] {
+        &self.actions
+    }
+    
+    /// Look up an action by name.
+    pub fn action(&self, name: &str) -> Option<&PlanningAction> {
+        self.actions.iter().find(|a| a.name == name)
+    }
+    
+    /// Record where the action catalog was loaded from.
+    pub fn set_catalog_source(&mut self, source: impl Into<String>) {
+        self.catalog_source = Some(source.into());
+    }
+    
+    /// Where the action catalog was loaded from, if it came from a file.
+    pub fn catalog_source(&self) -> Option<&str> {
+        self.catalog_source.as_deref()
+    }
+    
+    /// Hex-encoded SHA-256 of the action catalog; changes whenever any action does.
+    pub fn catalog_hash(&self) -> String {
+        let json = serde_json::to_vec(&self.actions).unwrap_or_default();
+        hex_encode(Sha256::digest(&json))
     }
 
     /// Get default actions for common operations.
@@ -87,6 +276,8 @@ impl AStarPlanner {
                 effects: vec!["resource_allocated".to_string()],
                 cost: 1.0,
                 duration_ms: 100,
+                compensate: Some("release_resource".to_string()),
+                ..Default::default()
             },
             PlanningAction {
                 name: "provision_compute".to_string(),
@@ -94,6 +285,8 @@ impl AStarPlanner {
                 effects: vec!["compute_ready".to_string()],
                 cost: 5.0,
                 duration_ms: 500,
+                compensate: Some("deprovision_compute".to_string()),
+                ..Default::default()
             },
             PlanningAction {
                 name: "configure_network".to_string(),
@@ -101,6 +294,7 @@ impl AStarPlanner {
                 effects: vec!["network_configured".to_string()],
                 cost: 2.0,
                 duration_ms: 200,
+                ..Default::default()
             },
             PlanningAction {
                 name: "deploy_workload".to_string(),
@@ -108,6 +302,8 @@ impl AStarPlanner {
                 effects: vec!["workload_deployed".to_string()],
                 cost: 3.0,
                 duration_ms: 1000,
+                compensate: Some("undeploy_workload".to_string()),
+                ..Default::default()
             },
             PlanningAction {
                 name: "verify_health".to_string(),
@@ -115,6 +311,7 @@ impl AStarPlanner {
                 effects: vec!["health_verified".to_string()],
                 cost: 0.5,
                 duration_ms: 100,
+                ..Default::default()
             },
             PlanningAction {
                 name: "finalize".to_string(),
@@ -122,30 +319,42 @@ impl AStarPlanner {
                 effects: vec!["complete".to_string()],
                 cost: 0.1,
                 duration_ms: 50,
+                ..Default::default()
             },
         ]
     }
 
     /// Heuristic function: estimate cost to reach goal.
-    fn heuristic(&self, state: &PlanningState, intent: &Intent) -> f64 {
-        // Simple heuristic: count missing goal conditions
-        // In a real implementation, this would be more sophisticated
-        let mut missing = 0.0;
-        
-        // Check if we have the "complete" state
-        if !state.variables.contains_key("complete") {
-            missing += 5.0;
-        }
-        
-        // Add penalty for budget proximity
-        if let Some(max_cost) = intent.budget.max_cost {
-            let remaining = max_cost - state.accumulated_cost;
-            if remaining < 0.0 {
-                missing += 1000.0; // Heavy penalty for exceeding budget
-            }
+    ///
+    /// Fails if the estimate is not finite.
+    fn heuristic(&self, state: &PlanningState, intent: &Intent, objective: &Objective) -> Result<f64> {
+        let estimate = match &self.heuristic {
+            Some(heuristic) => heuristic(state, intent),
+            None => self.default_heuristic(state, objective),
+        };
+        finite_or_err(estimate, format_args!("heuristic estimate for intent {}", intent.id))
+    }
+
+    /// Built-in heuristic: the cheapest action that can complete the goal,
+    /// priced by the intent's objective.
+    ///
+    /// This never overestimates and never drops by more than an action's
+    /// cost, so it is both admissible and consistent. Over-budget states are
+    /// pruned by the constraint check rather than penalized here, and
+    /// nearly exhausted resources are charged on the edge that exhausts them
+    /// (see [`PlannerConfig::resource_pressure_penalty`]) so this stays
+    /// admissible.
+    fn default_heuristic(&self, state: &PlanningState, objective: &Objective) -> f64 {
+        if state.variables.contains_key("complete") {
+            return 0.0;
         }
-        
-        missing
+
+        self.actions
+            .iter()
+            .filter(|a| a.effects.iter().any(|e| e == "complete"))
+            .map(|a| objective.step_cost(a))
+            .fold(None, |min: Option<f64>, cost| Some(min.map_or(cost, |m| m.min(cost))))
+            .unwrap_or(0.0)
     }
 
     /// Check if an action's preconditions are satisfied.
@@ -155,6 +364,14 @@ impl AStarPlanner {
         })
     }
 
+    /// Whether taking `action` would only repeat effects already in `state`.
+    ///
+    /// An action without effects is never redundant: it is kept for what it
+    /// does rather than for the variables it sets.
+    fn adds_nothing(action: &PlanningAction, state: &PlanningState) -> bool {
+        !action.effects.is_empty() && action.effects.iter().all(|e| state.variables.contains_key(e))
+    }
+
     /// Apply an action to a state, returning the new state.
     fn apply_action(&self, action: &PlanningAction, state: &PlanningState) -> PlanningState {
         let mut new_state = state.clone();
@@ -165,6 +382,9 @@ impl AStarPlanner {
         
         new_state.accumulated_cost += action.cost;
         new_state.accumulated_time_ms += action.duration_ms;
+        for (resource, amount) in &action.resources {
+            *new_state.resources_used.entry(resource.clone()).or_default() += amount;
+        }
         
         new_state
     }
@@ -174,132 +394,367 @@ impl AStarPlanner {
         state.variables.contains_key("complete")
     }
 
-    /// Check if constraints are violated.
-    fn constraints_violated(&self, state: &PlanningState, intent: &Intent) -> bool {
-        // Check budget constraint
-        if let Some(max_cost) = intent.budget.max_cost {
-            if state.accumulated_cost > max_cost {
-                return true;
-            }
-        }
+    /// What constraints are judged against after taking `action` to reach
+    /// `state`: the action's region and provider place the work.
+    fn evaluation_context(action: &PlanningAction, state: &PlanningState) -> EvaluationContext {
+        let mut ctx = EvaluationContext::new()
+            .with_variables(state.variables.clone())
+            .with_cost(state.accumulated_cost)
+            .with_elapsed_ms(state.accumulated_time_ms)
+            .with_resources(state.resources_used.clone());
+        ctx.region = action.region.clone();
+        ctx.node_id = action.provider.clone();
+        ctx
+    }
 
-        // Check time constraint
-        if let Some(max_time) = intent.budget.max_duration_ms {
-            if state.accumulated_time_ms > max_time {
-                return true;
-            }
-        }
+    /// Check if hard constraints or the budget are violated.
+    fn constraints_violated(&self, ctx: &EvaluationContext, intent: &Intent) -> bool {
+        let budget = budget_constraints(&intent.budget);
+        intent
+            .constraints
+            .iter()
+            .chain(&budget)
+            .any(|c| self.evaluator.evaluate(c, ctx).is_violated())
+    }
 
-        false
+    /// Limits on the resources actions consume: the intent's budget's and
+    /// those of its hard `ResourceLimit` constraints. Cost and time are
+    /// limited by the budget's own caps.
+    fn resource_limits(intent: &Intent) -> Vec<(ResourceType, f64)> {
+        let constraints = intent.constraints.iter().filter_map(|c| match c {
+            Constraint::ResourceLimit { resource, limit } => Some((ResourceType::from_name(resource), *limit)),
+            _ => None,
+        });
+        intent
+            .budget
+            .resource_limits
+            .iter()
+            .cloned()
+            .chain(constraints)
+            .filter(|(resource, _)| !matches!(resource, ResourceType::Money { .. } | ResourceType::Time))
+            .collect()
     }
 
-    /// Convert search steps to plan steps.
-    fn steps_to_plan(&self, steps: Vec<Step>, intent: &Intent) -> Plan {
-        let mut plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
-        
-        let total_cost: f64 = steps.iter().map(|s| s.estimated_cost).sum();
-        let total_time: u64 = steps.iter().map(|s| s.estimated_duration_ms).sum();
-        
-        plan.estimated_cost = total_cost;
-        plan.estimated_latency_ms = total_time;
-        plan.confidence_score = 0.85; // A* typically produces high-confidence plans
-        
-        for step in steps {
-            plan.steps.push(step);
+    /// How many of `limits` `state` has nearly exhausted, i.e. used past
+    /// [`PlannerConfig::resource_pressure_threshold`] of.
+    fn resources_pressured(&self, state: &PlanningState, limits: &[(ResourceType, f64)]) -> usize {
+        limits
+            .iter()
+            .filter(|(resource, limit)| {
+                let used = state.resource_used(resource);
+                used > 0.0 && used >= limit * self.config.resource_pressure_threshold
+            })
+            .count()
+    }
+
+    /// Key identifying a planning problem: everything the search depends on
+    /// except the intent's identity, so retries of the same request match.
+    pub fn plan_cache_key(&self, intent: &Intent, initial_state: &PlanningState) -> String {
+        let mut variables: Vec<(&String, &serde_json::Value)> = initial_state.variables.iter().collect();
+        variables.sort_unstable_by_key(|(k, _)| *k);
+        let content = serde_json::json!({
+            "kind": intent.kind,
+            "constraints": intent.constraints,
+            "soft_constraints": intent.soft_constraints,
+            "preferences": intent.preferences,
+            "budget": intent.budget,
+            "variables": variables,
+            "accumulated_cost": initial_state.accumulated_cost,
+            "accumulated_time_ms": initial_state.accumulated_time_ms,
+            "resources_used": initial_state.resources_used.iter().map(|(r, used)| (r.name(), used)).collect::<std::collections::BTreeMap<_, _>>(),
+            "catalog": self.catalog_hash(),
+        });
+        hex_encode(Sha256::digest(content.to_string().as_bytes()))
+    }
+
+    /// Take the saved partial search for `key`, if it has not expired.
+    fn take_checkpoint(&self, key: &str) -> Option<SearchCheckpoint> {
+        let checkpoint = self.checkpoints.lock().unwrap().remove(key)?;
+        let ttl = Duration::from_millis(self.config.resume_ttl_ms);
+        (checkpoint.saved_at.elapsed() <= ttl).then_some(checkpoint)
+    }
+
+    fn save_checkpoint(&self, key: String, checkpoint: SearchCheckpoint) {
+        let mut checkpoints = self.checkpoints.lock().unwrap();
+        let ttl = Duration::from_millis(self.config.resume_ttl_ms);
+        checkpoints.retain(|_, c| c.saved_at.elapsed() <= ttl);
+        if checkpoints.len() >= MAX_CHECKPOINTS {
+            let oldest = checkpoints.iter().min_by_key(|(_, c)| c.saved_at).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                checkpoints.remove(&oldest);
+            }
         }
-        
-        plan
+        checkpoints.insert(key, checkpoint);
     }
-}
 
-impl Default for AStarPlanner {
-    fn default() -> Self {
-        Self::new()
+    /// Plan and report search statistics alongside the result.
+    ///
+    /// With memoization enabled, an attempt that runs out of time or states
+    /// saves its closed set and best frontier nodes; the next call for the
+    /// same [`plan_cache_key`](Self::plan_cache_key) resumes from them.
+    ///
+    /// The search gets the intent's [planning budget], and fails without
+    /// searching when a near deadline leaves less than `min_planning_time_ms`.
+    ///
+    /// [planning budget]: PlannerConfig::planning_budget_ms
+    pub fn plan_with_stats(&self, intent: &Intent, initial_state: &PlanningState) -> PlanningResult {
+        self.plan_with_cancellation(intent, initial_state, &CancellationToken::new())
     }
-}
 
-#[async_trait]
-impl Planner for AStarPlanner {
-    async fn plan(&self, intent: &Intent, initial_state: &PlanningState) -> Result<Plan> {
+    /// [`plan_with_stats`](Self::plan_with_stats), stopping early once
+    /// `cancel` is cancelled. A cancelled search is saved for resuming
+    /// like one that ran out of budget.
+    pub fn plan_with_cancellation(
+        &self,
+        intent: &Intent,
+        initial_state: &PlanningState,
+        cancel: &CancellationToken,
+    ) -> PlanningResult {
         let start_time = Instant::now();
-        
+        let budget_ms = self.config.planning_budget_ms(intent, Utc::now());
+        if budget_ms < self.config.min_planning_time_ms {
+            warn!("Deadline of intent {} leaves only {}ms to plan", intent.id, budget_ms);
+            return PlanningResult {
+                plan: None,
+                states_explored: 0,
+                planning_time_ms: start_time.elapsed().as_millis() as u64,
+                planning_budget_ms: budget_ms,
+                error: Some(OrpheonError::PlanningFailed {
+                    intent_id: intent.id,
+                    message: format!(
+                        "deadline is too tight to plan: planning budget of {}ms is below the {}ms minimum",
+                        budget_ms, self.config.min_planning_time_ms
+                    ),
+                }),
+                heuristic_violations: 0,
+                resumed: false,
+            };
+        }
+
+        let mut stats = SearchStats::default();
+        let key = self.config.enable_memoization.then(|| self.plan_cache_key(intent, initial_state));
+        let checkpoint = key.as_deref().and_then(|key| self.take_checkpoint(key));
+        let resumed = checkpoint.is_some();
+        if resumed {
+            info!("Resuming saved A* search for intent {}", intent.id);
+        }
+
+        let mut result = self.search(intent, initial_state, start_time, budget_ms, cancel, checkpoint, &mut stats);
+        if resumed && stats.exhausted {
+            // The saved frontier was truncated; the plan may lie in what was dropped
+            debug!("Resumed search for intent {} was exhausted; starting fresh", intent.id);
+            stats.exhausted = false;
+            result = self.search(intent, initial_state, start_time, budget_ms, cancel, None, &mut stats);
+        }
+        if let (Some(key), Some(checkpoint)) = (key, stats.checkpoint.take()) {
+            self.save_checkpoint(key, checkpoint);
+        }
+
+        if stats.heuristic_violations > 0 {
+            warn!(
+                "A* heuristic had {} violation(s) while planning intent {}",
+                stats.heuristic_violations, intent.id
+            );
+        }
+
+        let result = result.and_then(|plan| {
+            if plan.steps.len() > self.config.max_steps && !self.config.split_long_plans {
+                return Err(OrpheonError::PlanningFailed {
+                    intent_id: intent.id,
+                    message: format!(
+                        "plan needs {} steps, more than max_steps ({}); enable split_long_plans to run it in segments",
+                        plan.steps.len(),
+                        self.config.max_steps
+                    ),
+                });
+            }
+            Ok(plan)
+        });
+        let (plan, error) = match result {
+            Ok(plan) => (Some(plan), None),
+            Err(e) => (None, Some(e)),
+        };
+        PlanningResult {
+            plan,
+            states_explored: stats.states_explored,
+            planning_time_ms: start_time.elapsed().as_millis() as u64,
+            planning_budget_ms: budget_ms,
+            error,
+            heuristic_violations: stats.heuristic_violations,
+            resumed,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        intent: &Intent,
+        initial_state: &PlanningState,
+        start_time: Instant,
+        budget_ms: u64,
+        cancel: &CancellationToken,
+        checkpoint: Option<SearchCheckpoint>,
+        stats: &mut SearchStats,
+    ) -> Result<Plan> {
         info!("Starting A* planning for intent {}", intent.id);
         
-        // Initialize open and closed sets
-        let mut open_set: BinaryHeap<SearchNode> = BinaryHeap::new();
-        let mut closed_set: HashSet<Uuid> = HashSet::new();
-        let mut states_explored = 0;
+        // Actions given to `with_actions` were never checked on registration
+        for action in &self.actions {
+            action.validate()?;
+        }
         
-        // Create initial node
-        let h_cost = self.heuristic(initial_state, intent);
-        let initial_node = SearchNode {
-            state: initial_state.clone(),
-            steps: Vec::new(),
-            g_cost: 0.0,
-            h_cost,
-            f_cost: h_cost,
-            id: Uuid::new_v4(),
-        };
+        // Initialize open and closed sets, from a saved search if there is one
+        let mut open_set: BinaryHeap<SearchNode> = BinaryHeap::new();
+        let mut closed_set: HashSet<u64> = HashSet::new();
+        let objective = Objective::for_intent(intent, &self.actions);
+        let resource_limits = Self::resource_limits(intent);
+        let mut next_seq: u64 = 0;
         
-        open_set.push(initial_node);
+        if let Some(checkpoint) = checkpoint {
+            closed_set = checkpoint.closed;
+            next_seq = checkpoint.frontier.iter().map(|n| n.seq + 1).max().unwrap_or(0);
+            open_set.extend(checkpoint.frontier);
+        } else {
+            let h_cost = self.heuristic(initial_state, intent, &objective)?;
+            open_set.push(SearchNode {
+                state: initial_state.clone(),
+                steps: Vec::new(),
+                g_cost: 0.0,
+                h_cost,
+                f_cost: h_cost,
+                soft_violations: Vec::new(),
+                path_peak: (h_cost, 0),
+                seq: next_seq,
+                id: Uuid::new_v4(),
+            });
+            next_seq += 1;
+        }
         
         while let Some(current) = open_set.pop() {
-            states_explored += 1;
+            stats.states_explored += 1;
             
             // Check resource limits
-            if states_explored > self.config.max_states_explored {
+            if stats.states_explored > self.config.max_states_explored {
                 warn!("A* exceeded max states explored limit");
+                open_set.push(current);
+                stats.checkpoint = Some(Self::checkpoint(open_set, closed_set));
                 return Err(OrpheonError::PlanningFailed {
                     intent_id: intent.id,
                     message: format!("Exceeded maximum states explored: {}", self.config.max_states_explored),
                 });
             }
             
-            let elapsed_ms = start_time.elapsed().as_millis() as u64;
-            if elapsed_ms > self.config.max_planning_time_ms {
-                warn!("A* exceeded max planning time");
+            if cancel.is_cancelled() {
+                info!("A* search for intent {} was cancelled", intent.id);
+                open_set.push(current);
+                stats.checkpoint = Some(Self::checkpoint(open_set, closed_set));
                 return Err(OrpheonError::PlanningFailed {
                     intent_id: intent.id,
-                    message: format!("Exceeded maximum planning time: {}ms", self.config.max_planning_time_ms),
+                    message: "Planning was cancelled".to_string(),
                 });
             }
             
+            let elapsed_ms = start_time.elapsed().as_millis() as u64;
+            if elapsed_ms > budget_ms {
+                warn!("A* exceeded planning time budget");
+                open_set.push(current);
+                stats.checkpoint = Some(Self::checkpoint(open_set, closed_set));
+                let message = if budget_ms < self.config.max_planning_time_ms {
+                    format!(
+                        "Exceeded planning time budget: {}ms (shortened from {}ms by the intent's deadline)",
+                        budget_ms, self.config.max_planning_time_ms
+                    )
+                } else {
+                    format!("Exceeded maximum planning time: {}ms", budget_ms)
+                };
+                return Err(OrpheonError::PlanningFailed { intent_id: intent.id, message });
+            }
+            
             // Check if goal reached
             if self.is_goal_reached(&current.state, intent) {
+                if self.config.validate_heuristic {
+                    self.check_admissible(&current, intent, stats)?;
+                }
                 info!(
                     "A* found plan with {} steps, explored {} states in {}ms",
                     current.steps.len(),
-                    states_explored,
+                    stats.states_explored,
                     elapsed_ms
                 );
-                return Ok(self.steps_to_plan(current.steps, intent));
+                return Ok(self.steps_to_plan(current.steps, &current.soft_violations, intent));
             }
             
-            // Skip if already visited
-            if closed_set.contains(&current.id) {
+            // Skip if this state was already expanded
+            if !closed_set.insert(current.state_hash()) {
                 continue;
             }
-            closed_set.insert(current.id);
             
             // Expand neighbors (try each applicable action)
             for action in &self.actions {
-                if !self.preconditions_met(action, &current.state) {
+                if !action.applies_to(&intent.kind) || !self.preconditions_met(action, &current.state) {
+                    continue;
+                }
+                
+                if Self::adds_nothing(action, &current.state) {
                     continue;
                 }
                 
                 let new_state = self.apply_action(action, &current.state);
+                let ctx = Self::evaluation_context(action, &new_state);
                 
                 // Skip if constraints violated
-                if self.constraints_violated(&new_state, intent) {
+                if self.constraints_violated(&ctx, intent) {
                     debug!("Skipping action {} due to constraint violation", action.name);
                     continue;
                 }
                 
+                // Soft constraints are penalized once, when first violated
+                let mut soft_violations = current.soft_violations.clone();
+                for (idx, constraint) in intent.soft_constraints.iter().enumerate() {
+                    if !soft_violations.contains(&idx)
+                        && self.evaluator.evaluate(constraint, &ctx).is_violated()
+                    {
+                        soft_violations.push(idx);
+                    }
+                }
+                let mut penalty = (soft_violations.len() - current.soft_violations.len()) as f64
+                    * self.config.soft_constraint_penalty;
+                
+                // So are resources, when they first run nearly out
+                let pressured = self
+                    .resources_pressured(&new_state, &resource_limits)
+                    .saturating_sub(self.resources_pressured(&current.state, &resource_limits));
+                penalty += pressured as f64 * self.config.resource_pressure_penalty;
+                
                 // Create new step
                 let mut new_steps = current.steps.clone();
-                let step = Step::new(&action.name, &action.name)
+                let mut step = Step::new(&action.name, &action.name)
                     .with_cost(action.cost)
                     .with_duration(action.duration_ms);
+                let mut parameters = action.parameters.clone();
+                if let Some(provider) = &action.provider {
+                    if parameters.is_null() {
+                        parameters = serde_json::json!({});
+                    }
+                    if let Some(map) = parameters.as_object_mut() {
+                        map.entry("provider").or_insert_with(|| serde_json::json!(provider));
+                    }
+                }
+                if !parameters.is_null() {
+                    step = step.with_parameters(parameters);
+                }
+                if let Some(compensate) = &action.compensate {
+                    step = step.with_compensation(compensate, serde_json::Value::Null);
+                }
+                if let Some(timeout_ms) = action.timeout_ms {
+                    step = step.with_timeout(timeout_ms);
+                }
+                if let Some(description) = &action.description {
+                    step = step.with_description(description);
+                }
+                step.tags = action.tags.clone();
+                step.optional = action.optional;
+                step.skip_if = action.skip_if.clone();
                 
                 // Add dependencies to previous step if any
                 let step = if let Some(last) = new_steps.last() {
@@ -311,9 +766,20 @@ impl Planner for AStarPlanner {
                 new_steps.push(step);
                 
                 // Calculate costs
-                let g_cost = current.g_cost + action.cost;
-                let h_cost = self.heuristic(&new_state, intent);
-                let f_cost = g_cost + h_cost;
+                let step_cost = objective.step_cost(action) + penalty;
+                let g_cost = current.g_cost + step_cost;
+                let h_cost = self.heuristic(&new_state, intent, &objective)?;
+                let f_cost = finite_or_err(g_cost + h_cost, format_args!("cost of the path through action {}", action.name))?;
+                
+                if self.config.validate_heuristic {
+                    self.check_consistent(&current, action, step_cost, &new_state, h_cost, intent, stats)?;
+                }
+                
+                let path_peak = if f_cost > current.path_peak.0 {
+                    (f_cost, new_steps.len())
+                } else {
+                    current.path_peak
+                };
                 
                 let new_node = SearchNode {
                     state: new_state,
@@ -321,43 +787,231 @@ impl Planner for AStarPlanner {
                     g_cost,
                     h_cost,
                     f_cost,
+                    soft_violations,
+                    path_peak,
+                    seq: next_seq,
                     id: Uuid::new_v4(),
                 };
+                next_seq += 1;
                 
                 open_set.push(new_node);
             }
         }
         
-        // No plan found
+        stats.exhausted = true;
+        
+        // No plan found; point at contradictory constraints if there are any
+        let mut message = "No valid plan found after exhaustive search".to_string();
+        let conflicts = intent.check_consistency();
+        if !conflicts.is_empty() {
+            message.push_str(&format!(
+                " (conflicting constraints detected: {})",
+                conflicts.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("; ")
+            ));
+        }
         Err(OrpheonError::PlanningFailed {
             intent_id: intent.id,
-            message: "No valid plan found after exhaustive search".to_string(),
+            message,
+        })
+    }
+
+    /// Save the best of the open set alongside the closed set.
+    fn checkpoint(mut open_set: BinaryHeap<SearchNode>, closed: HashSet<u64>) -> SearchCheckpoint {
+        let frontier = std::iter::from_fn(|| open_set.pop())
+            .take(MAX_CHECKPOINT_FRONTIER)
+            .collect();
+        SearchCheckpoint { saved_at: Instant::now(), closed, frontier }
+    }
+
+    /// Check h(parent) <= cost(parent -> child) + h(child) for one edge.
+    #[allow(clippy::too_many_arguments)]
+    fn check_consistent(
+        &self,
+        parent: &SearchNode,
+        action: &PlanningAction,
+        step_cost: f64,
+        child: &PlanningState,
+        child_h: f64,
+        intent: &Intent,
+        stats: &mut SearchStats,
+    ) -> Result<()> {
+        if parent.h_cost <= step_cost + child_h + HEURISTIC_EPSILON {
+            return Ok(());
+        }
+
+        stats.heuristic_violations += 1;
+        warn!(
+            "Inconsistent heuristic on action {}: h(parent)={:.3} > cost {:.3} + h(child)={:.3}; parent state {:?}, child state {:?}",
+            action.name,
+            parent.h_cost,
+            step_cost,
+            child_h,
+            sorted_keys(&parent.state),
+            sorted_keys(child),
+        );
+        self.heuristic_failure(intent, || {
+            format!("inconsistent heuristic on action {}", action.name)
+        })
+    }
+
+    /// Check that no state on the path to `goal` overestimated its remaining cost.
+    fn check_admissible(&self, goal: &SearchNode, intent: &Intent, stats: &mut SearchStats) -> Result<()> {
+        let (peak_f, depth) = goal.path_peak;
+        if peak_f <= goal.g_cost + HEURISTIC_EPSILON {
+            return Ok(());
+        }
+
+        stats.heuristic_violations += 1;
+        let path: Vec<&str> = goal.steps[..depth].iter().map(|s| s.action.as_str()).collect();
+        warn!(
+            "Inadmissible heuristic: estimate {:.3} exceeds plan cost {:.3} at the state reached by {:?}",
+            peak_f, goal.g_cost, path,
+        );
+        self.heuristic_failure(intent, || {
+            format!("inadmissible heuristic: estimate {:.3} exceeds plan cost {:.3}", peak_f, goal.g_cost)
         })
     }
 
-    async fn validate_plan(&self, plan: &Plan, current_state: &PlanningState) -> Result<bool> {
-        // Simulate execution of the plan
+    /// Turn a heuristic violation into an error in strict mode.
+    fn heuristic_failure(&self, intent: &Intent, message: impl FnOnce() -> String) -> Result<()> {
+        if self.config.strict_heuristic {
+            return Err(OrpheonError::PlanningFailed {
+                intent_id: intent.id,
+                message: message(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Convert search steps to plan steps.
+    fn steps_to_plan(&self, mut steps: Vec<Step>, soft_violations: &[usize], intent: &Intent) -> Plan {
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        
+        // Prefer what past executions took over the catalog's guess, and
+        // embed the action's facts so the plan can be checked without it
+        for step in &mut steps {
+            if let Some(action) = self.action(&step.action) {
+                step.preconditions = action.preconditions.clone();
+                step.produces = action.effects.clone();
+            }
+            let (duration_ms, source) = self.durations.estimate(&step.action, step.estimated_duration_ms);
+            step.estimated_duration_ms = duration_ms;
+            if !step.metadata.is_object() {
+                step.metadata = serde_json::json!({});
+            }
+            step.metadata["duration_source"] = serde_json::json!(source);
+        }
+        
+        let total_cost: f64 = steps.iter().map(|s| s.estimated_cost).sum();
+        let total_time: u64 = steps.iter().map(|s| s.estimated_duration_ms).sum();
+        
+        plan.estimated_cost = total_cost;
+        plan.estimated_work_ms = total_time;
+        plan.confidence_score = 0.85; // A* typically produces high-confidence plans
+        
+        if !soft_violations.is_empty() {
+            let violated: Vec<serde_json::Value> = soft_violations
+                .iter()
+                .map(|&idx| {
+                    serde_json::json!({
+                        "index": idx,
+                        "constraint": intent.soft_constraints[idx],
+                    })
+                })
+                .collect();
+            plan.metadata = serde_json::json!({ "soft_violations": violated });
+        }
+        
+        // Fill in description templates; one that cannot resolve is kept as written
+        let context = ExecutionContext::for_intent(intent);
+        for mut step in steps {
+            if let Some(template) = &step.description {
+                match context.render(template) {
+                    Ok(description) => step.description = Some(description),
+                    Err(e) => warn!("Could not fill in description of step {}: {}", step.name, e),
+                }
+            }
+            plan.steps.push(step);
+        }
+        
+        // Steps that do not wait on each other overlap, so latency is the
+        // longest chain rather than the sum
+        plan.estimated_latency_ms = plan.critical_path().1;
+        
+        plan
+    }
+}
+
+/// State variable names, sorted for stable log output.
+fn sorted_keys(state: &PlanningState) -> Vec<&str> {
+    let mut keys: Vec<&str> = state.variables.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    keys
+}
+
+impl Default for AStarPlanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Planner for AStarPlanner {
+    async fn plan(&self, intent: &Intent, initial_state: &PlanningState) -> Result<Plan> {
+        self.plan_with_stats(intent, initial_state).into_result()
+    }
+
+    async fn validate_plan_report(&self, plan: &Plan, current_state: &PlanningState) -> Result<ValidationReport> {
+        // Simulate execution of the plan; a step whose preconditions fail is
+        // still applied, so later steps are judged as if it had run
         let mut state = current_state.clone();
+        let mut report = ValidationReport { valid: true, unknown_actions: Vec::new(), failed_preconditions: Vec::new() };
         
         for step in &plan.steps {
-            // Find the action for this step
-            let action = self.actions.iter().find(|a| a.name == step.action);
-            
-            match action {
-                Some(action) => {
-                    if !self.preconditions_met(action, &state) {
-                        return Ok(false);
+            let Some(action) = self.action(&step.action) else {
+                // A step that carries its own facts can be simulated anyway
+                if !step.preconditions.is_empty() || !step.produces.is_empty() {
+                    let missing: Vec<String> =
+                        step.preconditions.iter().filter(|p| !state.variables.contains_key(*p)).cloned().collect();
+                    if !missing.is_empty() {
+                        report.valid = false;
+                        report.failed_preconditions.push(FailedPrecondition {
+                            step: step.name.clone(),
+                            action: step.action.clone(),
+                            missing,
+                        });
+                    }
+                    for fact in &step.produces {
+                        state.variables.insert(fact.clone(), serde_json::Value::Bool(true));
                     }
-                    state = self.apply_action(action, &state);
+                    continue;
                 }
-                None => {
-                    // Unknown action, assume it's valid
-                    debug!("Unknown action {} in plan validation", step.action);
+                match self.config.unknown_action_policy {
+                    UnknownActionPolicy::Reject => {
+                        report.valid = false;
+                        report.unknown_actions.push(step.action.clone());
+                    }
+                    UnknownActionPolicy::Warn => {
+                        warn!("Unknown action {} in plan {}", step.action, plan.id);
+                        report.unknown_actions.push(step.action.clone());
+                    }
+                    UnknownActionPolicy::Ignore => debug!("Unknown action {} in plan validation", step.action),
                 }
+                continue;
+            };
+            
+            if !self.preconditions_met(action, &state) {
+                report.valid = false;
+                report.failed_preconditions.push(FailedPrecondition {
+                    step: step.name.clone(),
+                    action: action.name.clone(),
+                    missing: action.preconditions.iter().filter(|p| !state.variables.contains_key(*p)).cloned().collect(),
+                });
             }
+            state = self.apply_action(action, &state);
         }
         
-        Ok(true)
+        Ok(report)
     }
 
     fn config(&self) -> &PlannerConfig {
@@ -372,7 +1026,8 @@ impl Planner for AStarPlanner {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use orpheon_core::Intent;
+    use std::collections::BTreeMap;
+    use orpheon_core::{Budget, Constraint, Intent};
 
     #[tokio::test]
     async fn test_astar_planning() {
@@ -389,6 +1044,27 @@ mod tests {
         let plan = result.unwrap();
         assert!(!plan.steps.is_empty());
     }
+    
+    #[tokio::test]
+    async fn test_plan_quotes_learned_durations() {
+        let durations = Arc::new(LearnedDurations::new(2));
+        let planner = AStarPlanner::new().with_durations(durations.clone());
+        let intent = Intent::builder().kind("provision_compute").build().unwrap();
+        
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        let step = plan.steps.iter().find(|s| s.action == "provision_compute").unwrap();
+        assert_eq!(step.estimated_duration_ms, 500);
+        assert_eq!(step.metadata["duration_source"], "static");
+        
+        durations.record("provision_compute", 1_500);
+        durations.record("provision_compute", 1_500);
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        let step = plan.steps.iter().find(|s| s.action == "provision_compute").unwrap();
+        assert_eq!(step.estimated_duration_ms, 1_500);
+        assert_eq!(step.metadata["duration_source"], "learned");
+        assert_eq!(plan.estimated_work_ms, plan.steps.iter().map(|s| s.estimated_duration_ms).sum::<u64>());
+        assert_eq!(plan.estimated_latency_ms, plan.estimated_work_ms);
+    }
 
     #[tokio::test]
     async fn test_plan_validation() {
@@ -404,4 +1080,781 @@ mod tests {
         let valid = planner.validate_plan(&plan, &initial_state).await.unwrap();
         assert!(valid);
     }
+
+    /// A plan deploying before provisioning, with a step nobody knows.
+    fn misordered_plan() -> (AStarPlanner, Plan) {
+        let catalog = vec![
+            PlanningAction {
+                name: "provision".to_string(),
+                effects: vec!["provisioned".to_string()],
+                ..Default::default()
+            },
+            PlanningAction {
+                name: "deploy".to_string(),
+                preconditions: vec!["provisioned".to_string(), "configured".to_string()],
+                effects: vec!["complete".to_string()],
+                ..Default::default()
+            },
+        ];
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        plan.add_step(Step::new("deploy app", "deploy"));
+        plan.add_step(Step::new("warm cache", "warm_cache"));
+        plan.add_step(Step::new("provision vm", "provision"));
+        (AStarPlanner::with_actions(PlannerConfig::default(), catalog), plan)
+    }
+    
+    #[tokio::test]
+    async fn test_validation_report_lists_each_problem() {
+        let (planner, plan) = misordered_plan();
+        let report = planner.validate_plan_report(&plan, &PlanningState::default()).await.unwrap();
+        
+        assert!(!report.valid);
+        assert_eq!(report.unknown_actions, vec!["warm_cache"]);
+        assert_eq!(
+            report.failed_preconditions,
+            vec![FailedPrecondition {
+                step: "deploy app".to_string(),
+                action: "deploy".to_string(),
+                missing: vec!["provisioned".to_string(), "configured".to_string()],
+            }]
+        );
+        assert_eq!(
+            report.describe(),
+            "unknown actions: warm_cache; step deploy app (deploy) is missing preconditions: provisioned, configured"
+        );
+        assert!(!planner.validate_plan(&plan, &PlanningState::default()).await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_unknown_action_policies() {
+        let (mut planner, mut plan) = misordered_plan();
+        plan.steps.remove(0);
+        let state = PlanningState::default();
+        
+        let report = planner.validate_plan_report(&plan, &state).await.unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.unknown_actions, vec!["warm_cache"]);
+        
+        planner.set_config(PlannerConfig { unknown_action_policy: UnknownActionPolicy::Warn, ..Default::default() });
+        let report = planner.validate_plan_report(&plan, &state).await.unwrap();
+        assert!(report.valid);
+        assert_eq!(report.unknown_actions, vec!["warm_cache"]);
+        
+        planner.set_config(PlannerConfig { unknown_action_policy: UnknownActionPolicy::Ignore, ..Default::default() });
+        let report = planner.validate_plan_report(&plan, &state).await.unwrap();
+        assert!(report.valid && report.is_clean());
+        assert!(planner.validate_plan(&plan, &state).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_plans_embed_facts_for_validation_elsewhere() {
+        let (planner, _) = misordered_plan();
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let mut configured = PlanningState::default();
+        configured.variables.insert("configured".to_string(), serde_json::json!(true));
+        let plan = planner.plan(&intent, &configured).await.unwrap();
+        let deploy = plan.steps.iter().find(|s| s.action == "deploy").unwrap();
+        assert_eq!(deploy.preconditions, vec!["provisioned", "configured"]);
+        assert_eq!(deploy.produces, vec!["complete"]);
+
+        // A planner that knows none of the actions checks the embedded facts
+        let stranger = AStarPlanner::with_actions(PlannerConfig::default(), vec![]);
+        let report = stranger.validate_plan_report(&plan, &configured).await.unwrap();
+        assert!(report.valid && report.is_clean(), "{}", report.describe());
+
+        let report = stranger.validate_plan_report(&plan, &PlanningState::default()).await.unwrap();
+        assert!(!report.valid);
+        assert!(report.unknown_actions.is_empty());
+        assert_eq!(report.failed_preconditions[0].missing, vec!["configured"]);
+
+        // Steps without facts still fall back to the unknown action policy
+        let mut bare = plan.clone();
+        bare.add_step(Step::new("warm cache", "warm_cache"));
+        let report = stranger.validate_plan_report(&bare, &configured).await.unwrap();
+        assert_eq!(report.unknown_actions, vec!["warm_cache"]);
+
+        let handwritten = {
+            let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Deterministic);
+            plan.add_step(Step::new("deploy app", "deploy").requires("provisioned"));
+            plan.add_step(Step::new("provision vm", "provision").produces("provisioned"));
+            plan
+        };
+        let report = stranger.validate_plan_report(&handwritten, &PlanningState::default()).await.unwrap();
+        assert_eq!(report.describe(), "step deploy app (deploy) is missing preconditions: provisioned");
+    }
+
+    #[tokio::test]
+    async fn test_extended_catalog_stays_within_search_limits() {
+        let mut planner = AStarPlanner::new();
+        planner.register_action(PlanningAction {
+            name: "snapshot_volume".to_string(),
+            preconditions: vec!["resource_allocated".to_string()],
+            effects: vec!["volume_snapshotted".to_string()],
+            cost: 0.5,
+            duration_ms: 300,
+            ..Default::default()
+        }).unwrap();
+        planner.register_action(PlanningAction {
+            name: "provision_compute_alt".to_string(),
+            preconditions: vec!["resource_allocated".to_string()],
+            effects: vec!["compute_ready".to_string()],
+            cost: 4.0,
+            duration_ms: 600,
+            ..Default::default()
+        }).unwrap();
+        
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        
+        // Each action is taken at most once
+        let names: HashSet<_> = plan.steps.iter().map(|s| s.action.as_str()).collect();
+        assert_eq!(names.len(), plan.steps.len());
+    }
+
+    #[test]
+    fn test_only_repeated_effects_add_nothing() {
+        let mut state = PlanningState::default();
+        state.variables.insert("compute_ready".to_string(), serde_json::Value::Bool(true));
+        let action = |effects: &[&str]| PlanningAction {
+            name: "act".to_string(),
+            effects: effects.iter().map(|e| e.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert!(AStarPlanner::adds_nothing(&action(&["compute_ready"]), &state));
+        assert!(!AStarPlanner::adds_nothing(&action(&["compute_ready", "volume_snapshotted"]), &state));
+        assert!(!AStarPlanner::adds_nothing(&action(&[]), &state));
+    }
+
+    #[tokio::test]
+    async fn test_plans_around_actions_without_effects() {
+        let mut planner = AStarPlanner::new();
+        planner.register_action(PlanningAction {
+            name: "notify_oncall".to_string(),
+            cost: 0.1,
+            duration_ms: 10,
+            ..Default::default()
+        }).unwrap();
+
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert!(plan.steps.iter().all(|s| s.action != "notify_oncall"));
+    }
+
+    fn region_catalog() -> Vec<PlanningAction> {
+        vec![
+            PlanningAction {
+                name: "deploy_us_east".to_string(),
+                effects: vec!["complete".to_string()],
+                cost: 5.0,
+                duration_ms: 100,
+                region: Some("us-east".to_string()),
+                ..Default::default()
+            },
+            PlanningAction {
+                name: "deploy_eu_west".to_string(),
+                effects: vec!["complete".to_string()],
+                cost: 1.0,
+                duration_ms: 100,
+                region: Some("eu-west".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn avoid_eu_intent() -> Intent {
+        Intent::builder()
+            .kind("deploy")
+            .soft_constraint(Constraint::GeoFence {
+                regions: vec!["eu-west".to_string()],
+                allowed: false,
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_soft_constraint_high_penalty_routes_around() {
+        let config = PlannerConfig {
+            soft_constraint_penalty: 100.0,
+            ..Default::default()
+        };
+        let planner = AStarPlanner::with_actions(config, region_catalog());
+        
+        let plan = planner.plan(&avoid_eu_intent(), &PlanningState::default()).await.unwrap();
+        
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].action, "deploy_us_east");
+        assert!(plan.metadata.get("soft_violations").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_soft_constraint_low_penalty_accepts_violation() {
+        let config = PlannerConfig {
+            soft_constraint_penalty: 1.0,
+            ..Default::default()
+        };
+        let planner = AStarPlanner::with_actions(config, region_catalog());
+        
+        let plan = planner.plan(&avoid_eu_intent(), &PlanningState::default()).await.unwrap();
+        
+        assert_eq!(plan.steps[0].action, "deploy_eu_west");
+        let violations = plan.metadata["soft_violations"].as_array().unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0]["constraint"]["type"], "geo_fence");
+    }
+
+    fn memory_catalog() -> Vec<PlanningAction> {
+        let action = |name: &str, pre: &[&str], effect: &str, cost: f64, memory: f64| PlanningAction {
+            name: name.to_string(),
+            preconditions: pre.iter().map(|p| p.to_string()).collect(),
+            effects: vec![effect.to_string()],
+            cost,
+            duration_ms: 100,
+            resources: vec![(ResourceType::Memory, memory)],
+            ..Default::default()
+        };
+        vec![
+            action("cache_in_memory", &[], "data_ready", 1.0, 12e9),
+            action("read_from_disk", &[], "data_ready", 2.0, 1e9),
+            action("serve", &["data_ready"], "complete", 1.0, 1e9),
+        ]
+    }
+
+    fn first_action(planner: &AStarPlanner, intent: &Intent) -> String {
+        let result = planner.plan_with_stats(intent, &PlanningState::default());
+        assert_eq!(result.heuristic_violations, 0);
+        result.into_result().unwrap().steps[0].action.clone()
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_routes_to_leaner_actions() {
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), memory_catalog());
+        let unlimited = Intent::builder().kind("serve").build().unwrap();
+        assert_eq!(first_action(&planner, &unlimited), "cache_in_memory");
+
+        let budget = Budget::default().with_resource(ResourceType::Memory, 8e9);
+        let limited = Intent::builder().kind("serve").budget(budget).build().unwrap();
+        assert_eq!(first_action(&planner, &limited), "read_from_disk");
+
+        // A ResourceLimit constraint on memory is held to the same accounting
+        let constrained = Intent::builder()
+            .kind("serve")
+            .constraint(Constraint::ResourceLimit { resource: "memory".to_string(), limit: 8e9 })
+            .build()
+            .unwrap();
+        assert_eq!(first_action(&planner, &constrained), "read_from_disk");
+
+        let starved = Intent::builder().kind("serve").budget(Budget::default().with_resource(ResourceType::Memory, 1e9)).build().unwrap();
+        assert!(planner.plan(&starved, &PlanningState::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_nearly_exhausted_resources_are_penalized() {
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), memory_catalog());
+        let intent = |limit: f64| {
+            Intent::builder().kind("serve").budget(Budget::default().with_resource(ResourceType::Memory, limit)).build().unwrap()
+        };
+
+        // 13 GB fits in 14 GB but leaves under 10% headroom
+        assert_eq!(first_action(&planner, &intent(14e9)), "read_from_disk");
+        assert_eq!(first_action(&planner, &intent(20e9)), "cache_in_memory");
+
+        let lenient = PlannerConfig { resource_pressure_penalty: 0.0, ..Default::default() };
+        let planner = AStarPlanner::with_actions(lenient, memory_catalog());
+        assert_eq!(first_action(&planner, &intent(14e9)), "cache_in_memory");
+    }
+
+    #[tokio::test]
+    async fn test_hard_geo_fence_prunes() {
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), region_catalog());
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::GeoFence {
+                regions: vec!["eu-west".to_string()],
+                allowed: false,
+            })
+            .build()
+            .unwrap();
+        
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_us_east");
+    }
+
+    #[tokio::test]
+    async fn test_constraints_go_through_the_evaluator() {
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::Custom { name: "min_cost".to_string(), data: serde_json::json!(2.0) })
+            .build()
+            .unwrap();
+        
+        // Unregistered custom constraints cannot be judged, so do not prune
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), region_catalog());
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_eu_west");
+        
+        let evaluator = Evaluator::new().with_custom("min_cost", |data, ctx| {
+            let min = data.as_f64().unwrap_or_default();
+            if ctx.cost < min { Err(format!("cost {} is under {}", ctx.cost, min)) } else { Ok(()) }
+        });
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), region_catalog()).with_evaluator(evaluator);
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_us_east");
+        
+        // State matches read the planning state's variables
+        let mut state = PlanningState::default();
+        state.variables.insert("tier".to_string(), serde_json::json!("gold"));
+        let matching = |expression: &str| {
+            Intent::builder().kind("deploy").state_match(expression).build().unwrap()
+        };
+        assert!(planner.plan(&matching("tier == 'gold'"), &state).await.is_ok());
+        assert!(planner.plan(&matching("tier != 'gold'"), &state).await.is_err());
+    }
+
+    fn validating_config(strict: bool) -> PlannerConfig {
+        PlannerConfig {
+            validate_heuristic: true,
+            strict_heuristic: strict,
+            ..Default::default()
+        }
+    }
+
+    /// Claims every incomplete state is far more expensive than it is.
+    fn overestimating_planner(strict: bool) -> AStarPlanner {
+        AStarPlanner::with_config(validating_config(strict)).with_heuristic(|state, _| {
+            if state.variables.contains_key("complete") { 0.0 } else { 100.0 }
+        })
+    }
+
+    #[test]
+    fn test_default_heuristic_passes_validation() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+
+        let result = AStarPlanner::with_config(validating_config(true))
+            .plan_with_stats(&intent, &PlanningState::default());
+        assert!(result.plan.is_some());
+        assert_eq!(result.heuristic_violations, 0);
+
+        // Soft constraint penalties must not break it either
+        let result = AStarPlanner::with_actions(validating_config(true), region_catalog())
+            .plan_with_stats(&avoid_eu_intent(), &PlanningState::default());
+        assert!(result.plan.is_some());
+        assert_eq!(result.heuristic_violations, 0);
+    }
+
+    #[test]
+    fn test_inadmissible_heuristic_is_counted() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+
+        let result = overestimating_planner(false).plan_with_stats(&intent, &PlanningState::default());
+
+        // Still plans, but the finalize edge is inconsistent and the plan
+        // cost is below the initial estimate
+        assert!(result.plan.is_some());
+        assert!(result.heuristic_violations >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_strict_heuristic_validation_fails_planning() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+
+        let err = overestimating_planner(true)
+            .plan(&intent, &PlanningState::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, OrpheonError::PlanningFailed { .. }));
+        assert!(err.to_string().contains("heuristic"));
+    }
+
+    #[test]
+    fn test_validation_disabled_counts_nothing() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let config = PlannerConfig {
+            validate_heuristic: false,
+            ..Default::default()
+        };
+        let planner = AStarPlanner::with_config(config).with_heuristic(|_, _| 100.0);
+
+        let result = planner.plan_with_stats(&intent, &PlanningState::default());
+        assert_eq!(result.heuristic_violations, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failure_mentions_detected_conflicts() {
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), region_catalog());
+        let intent = Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::GeoFence { regions: vec!["us-east".to_string()], allowed: true })
+            .constraint(Constraint::GeoFence { regions: vec!["eu-west".to_string()], allowed: true })
+            .build()
+            .unwrap();
+        
+        let err = planner.plan(&intent, &PlanningState::default()).await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("conflicting constraints detected"), "{}", message);
+        assert!(message.contains("constraints[0] vs constraints[1]"));
+    }
+
+    #[tokio::test]
+    async fn test_kind_scoped_actions() {
+        let mut catalog = region_catalog();
+        catalog[1].kinds = vec!["backup".to_string()];
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), catalog);
+        
+        // The cheaper action is only available to backups
+        let deploy = Intent::builder().kind("deploy").build().unwrap();
+        let plan = planner.plan(&deploy, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_us_east");
+        
+        let backup = Intent::builder().kind("backup").build().unwrap();
+        let plan = planner.plan(&backup, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_eu_west");
+    }
+
+    #[tokio::test]
+    async fn test_step_descriptions_are_filled_in() {
+        let mut catalog = region_catalog();
+        catalog[1].description = Some("Deploy ${intent.kind} for ${intent.metadata.team} in eu-west".to_string());
+        catalog[1].tags = vec!["deploy".to_string(), "eu".to_string()];
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), catalog);
+        
+        let intent = Intent::builder()
+            .kind("deploy")
+            .metadata(serde_json::json!({ "team": "search" }))
+            .build()
+            .unwrap();
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].action, "deploy_eu_west");
+        assert_eq!(plan.steps[0].description.as_deref(), Some("Deploy deploy for search in eu-west"));
+        assert_eq!(plan.steps[0].tags, vec!["deploy", "eu"]);
+        
+        // An unresolvable template is left as written
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = planner.plan(&intent, &PlanningState::default()).await.unwrap();
+        assert_eq!(plan.steps[0].description.as_deref(), Some("Deploy ${intent.kind} for ${intent.metadata.team} in eu-west"));
+    }
+
+    #[test]
+    fn test_catalog_hash_tracks_actions() {
+        let mut planner = AStarPlanner::new();
+        let before = planner.catalog_hash();
+        assert_eq!(before, AStarPlanner::new().catalog_hash());
+        
+        planner.register_action(PlanningAction {
+            name: "snapshot_volume".to_string(),
+            ..Default::default()
+        }).unwrap();
+        assert_ne!(planner.catalog_hash(), before);
+        assert!(planner.action("snapshot_volume").is_some());
+    }
+    
+    fn reliability_catalog() -> Vec<PlanningAction> {
+        let deploy = |name: &str, cost: f64, reliability: f64| PlanningAction {
+            name: name.to_string(),
+            preconditions: vec!["provisioned".to_string()],
+            effects: vec!["complete".to_string()],
+            cost,
+            metrics: [("reliability".to_string(), reliability)].into_iter().collect(),
+            ..Default::default()
+        };
+        vec![
+            PlanningAction {
+                name: "provision".to_string(),
+                effects: vec!["provisioned".to_string()],
+                cost: 1.0,
+                metrics: [("reliability".to_string(), 0.999)].into_iter().collect(),
+                ..Default::default()
+            },
+            deploy("deploy_spot", 1.0, 0.9),
+            deploy("deploy_ha", 3.0, 0.999),
+        ]
+    }
+    
+    #[tokio::test]
+    async fn test_maximize_reliability_changes_plan() {
+        let planner = AStarPlanner::with_actions(PlannerConfig::default(), reliability_catalog());
+        let state = PlanningState::default();
+        
+        let plain = Intent::builder().kind("deploy").build().unwrap();
+        let plan = planner.plan(&plain, &state).await.unwrap();
+        assert_eq!(plan.steps[1].action, "deploy_spot");
+        
+        let reliable = Intent::builder().kind("deploy").maximize("reliability", 1.0).build().unwrap();
+        let plan = planner.plan(&reliable, &state).await.unwrap();
+        assert_eq!(plan.steps[1].action, "deploy_ha");
+        assert_eq!(plan.estimated_cost, 4.0);
+        
+        // Mostly about cost, only a little about reliability
+        let balanced = Intent::builder()
+            .kind("deploy")
+            .minimize("cost", 0.9)
+            .maximize("reliability", 0.1)
+            .build()
+            .unwrap();
+        let plan = planner.plan(&balanced, &state).await.unwrap();
+        assert_eq!(plan.steps[1].action, "deploy_spot");
+    }
+    
+    /// A ten-step chain whose heuristic sleeps, so search speed is predictable.
+    fn slow_chain_planner(config: PlannerConfig) -> AStarPlanner {
+        let mut catalog = Vec::new();
+        for i in 1..=10 {
+            catalog.push(PlanningAction {
+                name: format!("step_{}", i),
+                preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+                effects: vec![if i == 10 { "complete".to_string() } else { format!("s{}", i) }],
+                cost: 1.0,
+                duration_ms: 1,
+                ..Default::default()
+            });
+        }
+        AStarPlanner::with_actions(config, catalog).with_heuristic(|_, _| {
+            std::thread::sleep(Duration::from_millis(20));
+            0.0
+        })
+    }
+    
+    /// A twelve-step chain of actions, each needing the one before.
+    fn long_chain_planner(config: PlannerConfig) -> AStarPlanner {
+        let catalog = (1..=12)
+            .map(|i| PlanningAction {
+                name: format!("step_{}", i),
+                preconditions: if i == 1 { vec![] } else { vec![format!("s{}", i - 1)] },
+                effects: vec![if i == 12 { "complete".to_string() } else { format!("s{}", i) }],
+                cost: 1.0,
+                duration_ms: 10,
+                ..Default::default()
+            })
+            .collect();
+        AStarPlanner::with_actions(config, catalog)
+    }
+    
+    #[tokio::test]
+    async fn test_long_plan_fails_at_max_steps_unless_split() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let state = PlanningState::default();
+        
+        let planner = long_chain_planner(PlannerConfig { max_steps: 5, ..Default::default() });
+        let err = planner.plan(&intent, &state).await.unwrap_err();
+        assert!(err.to_string().contains("max_steps"), "{}", err);
+        
+        let planner = long_chain_planner(PlannerConfig { max_steps: 5, split_long_plans: true, ..Default::default() });
+        let whole = planner.plan(&intent, &state).await.unwrap();
+        let segments = planner.plan_segments(&intent, &state).await.unwrap();
+        assert_eq!(segments.iter().map(|s| s.steps.len()).collect::<Vec<_>>(), vec![5, 5, 2]);
+        assert_eq!(segments[1].continuation_of, Some(segments[0].id));
+        assert_eq!(segments[2].continuation_of, Some(segments[1].id));
+        
+        let actions = |steps: &[Step]| steps.iter().map(|s| s.action.clone()).collect::<Vec<_>>();
+        let concatenated: Vec<Step> = segments.iter().flat_map(|s| s.steps.clone()).collect();
+        assert_eq!(actions(&concatenated), actions(&whole.steps));
+        assert_eq!(segments.iter().map(|s| s.estimated_cost).sum::<f64>(), whole.estimated_cost);
+    }
+    
+    fn tight_budget() -> PlannerConfig {
+        PlannerConfig { max_planning_time_ms: 120, ..Default::default() }
+    }
+    
+    #[test]
+    fn test_timed_out_search_resumes() {
+        let planner = slow_chain_planner(tight_budget());
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        
+        let first = planner.plan_with_stats(&intent, &PlanningState::default());
+        assert!(first.plan.is_none());
+        assert!(!first.resumed);
+        
+        // A retry of the same request picks up where the first attempt stopped
+        let retry = Intent::builder().kind("deploy").build().unwrap();
+        let second = planner.plan_with_stats(&retry, &PlanningState::default());
+        assert!(second.resumed);
+        let plan = second.into_result().unwrap();
+        assert_eq!(plan.intent_id, retry.id);
+        assert_eq!(plan.steps.len(), 10);
+        assert_eq!(plan.steps.last().unwrap().action, "step_10");
+    }
+    
+    #[test]
+    fn test_cancelled_search_stops_and_resumes() {
+        let planner = slow_chain_planner(PlannerConfig::default());
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = planner.plan_with_cancellation(&intent, &PlanningState::default(), &cancel);
+        assert!(result.states_explored <= 1);
+        let err = result.into_result().unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+
+        // A guard cancels along with whatever owns it
+        let cancel = CancellationToken::new();
+        drop(cancel.clone().drop_guard());
+        assert!(cancel.is_cancelled());
+
+        let retry = planner.plan_with_stats(&intent, &PlanningState::default());
+        assert!(retry.resumed);
+        assert_eq!(retry.into_result().unwrap().steps.len(), 10);
+    }
+
+    #[test]
+    fn test_catalog_change_discards_saved_search() {
+        let mut planner = slow_chain_planner(tight_budget());
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        assert!(planner.plan_with_stats(&intent, &PlanningState::default()).plan.is_none());
+        
+        planner.register_action(PlanningAction {
+            name: "snapshot_volume".to_string(),
+            effects: vec!["snapshot".to_string()],
+            ..Default::default()
+        }).unwrap();
+        let second = planner.plan_with_stats(&intent, &PlanningState::default());
+        assert!(!second.resumed);
+        assert!(second.plan.is_none());
+    }
+    
+    fn due_in(ms: i64) -> Intent {
+        Intent::builder()
+            .kind("deploy")
+            .constraint(Constraint::Deadline { by: Utc::now() + chrono::Duration::milliseconds(ms) })
+            .build()
+            .unwrap()
+    }
+    
+    #[test]
+    fn test_near_deadline_shortens_planning_budget() {
+        let config = PlannerConfig { enable_memoization: false, ..Default::default() };
+        let planner = slow_chain_planner(config.clone());
+        
+        // Without a deadline the validity window is far enough off
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        assert_eq!(config.planning_budget_ms(&intent, Utc::now()), config.max_planning_time_ms);
+        
+        // A tenth of one second left is too little for the slow chain
+        let result = planner.plan_with_stats(&due_in(1_000), &PlanningState::default());
+        assert!(result.planning_budget_ms <= 100 && result.planning_budget_ms >= 50, "{}", result.planning_budget_ms);
+        assert!(result.planning_time_ms < 1_000, "{}", result.planning_time_ms);
+        let err = result.into_result().unwrap_err().to_string();
+        assert!(err.contains("shortened from 30000ms"), "{}", err);
+        
+        // A distant deadline leaves the configured limit in place
+        let result = planner.plan_with_stats(&due_in(3_600_000), &PlanningState::default());
+        assert_eq!(result.planning_budget_ms, config.max_planning_time_ms);
+        assert!(result.plan.is_some());
+    }
+    
+    #[test]
+    fn test_too_tight_deadline_fails_without_searching() {
+        let planner = slow_chain_planner(PlannerConfig::default());
+        let result = planner.plan_with_stats(&due_in(200), &PlanningState::default());
+        
+        assert!(result.planning_budget_ms < 50);
+        assert_eq!(result.states_explored, 0);
+        assert!(result.planning_time_ms < 20, "{}", result.planning_time_ms);
+        let err = result.into_result().unwrap_err().to_string();
+        assert!(err.contains("too tight to plan"), "{}", err);
+        
+        // Deadlines already passed get no budget at all
+        assert_eq!(PlannerConfig::default().planning_budget_ms(&due_in(-1_000), Utc::now()), 0);
+    }
+    
+    #[test]
+    fn test_saved_search_expires() {
+        let config = PlannerConfig { resume_ttl_ms: 0, ..tight_budget() };
+        let planner = slow_chain_planner(config);
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        
+        assert!(planner.plan_with_stats(&intent, &PlanningState::default()).plan.is_none());
+        assert!(!planner.plan_with_stats(&intent, &PlanningState::default()).resumed);
+    }
+
+    #[test]
+    fn test_non_finite_action_cost_is_rejected() {
+        let mut planner = AStarPlanner::new();
+        let err = planner
+            .register_action(PlanningAction { name: "nan_cost".to_string(), cost: f64::NAN, ..Default::default() })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "cost of action nan_cost must be a finite number, got NaN");
+        assert!(planner.action("nan_cost").is_none());
+
+        let mut metrics = BTreeMap::new();
+        metrics.insert("reliability".to_string(), f64::INFINITY);
+        let err = planner
+            .register_action(PlanningAction { name: "inf_metric".to_string(), metrics, ..Default::default() })
+            .unwrap_err();
+        assert!(matches!(err, OrpheonError::NonFinite { .. }));
+    }
+
+    #[test]
+    fn test_non_finite_catalog_fails_planning() {
+        let mut catalog = AStarPlanner::default_actions();
+        catalog[0].cost = f64::NAN;
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+
+        let result = AStarPlanner::with_actions(PlannerConfig::default(), catalog)
+            .plan_with_stats(&intent, &PlanningState::default());
+        assert!(result.plan.is_none());
+        assert!(matches!(result.error, Some(OrpheonError::NonFinite { .. })));
+    }
+
+    #[test]
+    fn test_non_finite_heuristic_fails_planning() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        for estimate in [f64::NAN, f64::INFINITY] {
+            let result = AStarPlanner::new()
+                .with_heuristic(move |_, _| estimate)
+                .plan_with_stats(&intent, &PlanningState::default());
+            assert!(result.plan.is_none());
+            let err = result.error.unwrap();
+            assert!(err.to_string().starts_with("heuristic estimate for intent"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_nan_f_cost_sorts_last() {
+        assert_eq!(compare_f_cost(f64::NAN, f64::INFINITY), Ordering::Greater);
+        assert_eq!(compare_f_cost(1.0, f64::NAN), Ordering::Less);
+        assert_eq!(compare_f_cost(f64::NAN, f64::NAN), Ordering::Equal);
+        assert_eq!(compare_f_cost(1.0, 2.0), Ordering::Less);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "NaN f(n)")]
+    fn test_nan_f_cost_in_open_set_panics_in_debug() {
+        let node = |f_cost| SearchNode {
+            state: PlanningState::default(),
+            steps: Vec::new(),
+            g_cost: 0.0,
+            h_cost: f_cost,
+            f_cost,
+            soft_violations: Vec::new(),
+            path_peak: (f_cost, 0),
+            seq: 0,
+            id: Uuid::new_v4(),
+        };
+        let _ = node(f64::NAN).cmp(&node(1.0));
+    }
+
+    #[test]
+    fn test_equal_f_cost_pops_in_generation_order() {
+        let node = |f_cost, seq| SearchNode {
+            state: PlanningState::default(),
+            steps: Vec::new(),
+            g_cost: 0.0,
+            h_cost: f_cost,
+            f_cost,
+            soft_violations: Vec::new(),
+            path_peak: (f_cost, 0),
+            seq,
+            id: Uuid::new_v4(),
+        };
+        let mut open = BinaryHeap::new();
+        for (f_cost, seq) in [(2.0, 0), (1.0, 3), (1.0, 1), (1.0, 2)] {
+            open.push(node(f_cost, seq));
+        }
+
+        let order: Vec<u64> = std::iter::from_fn(|| open.pop()).map(|n| n.seq).collect();
+        assert_eq!(order, vec![1, 2, 3, 0]);
+    }
 }
@@ -0,0 +1,43 @@
+//! Cancelling a search from outside the planner.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stops a search started with
+/// [`AStarPlanner::plan_with_cancellation`](crate::AStarPlanner::plan_with_cancellation);
+/// clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// A token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask searches holding this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// A guard that cancels the token when dropped, e.g. along with a
+    /// request whose client gave up.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard(self)
+    }
+}
+
+/// Cancels its token when dropped; see [`CancellationToken::drop_guard`].
+#[derive(Debug)]
+pub struct DropGuard(CancellationToken);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
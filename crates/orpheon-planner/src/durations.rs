@@ -0,0 +1,180 @@
+//! Step durations learned from past executions.
+//!
+//! Catalog durations are guesses made when an action is registered. Each
+//! completed step teaches the planner how long its action really took:
+//! recent samples are kept with weights that decay geometrically, so the
+//! estimate follows an action that got slower. Once an action has at least
+//! [`LearnedDurations::min_samples`] samples, plans quote its weighted p95
+//! instead of the catalog duration.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Samples an action needs before its learned duration is trusted.
+pub const DEFAULT_MIN_SAMPLES: u64 = 5;
+
+/// Weight kept by older samples each time a new one arrives.
+pub const DECAY: f64 = 0.9;
+
+/// Samples below this weight no longer affect the estimate and are dropped.
+const MIN_WEIGHT: f64 = 0.01;
+
+/// Where a step's duration estimate came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationSource {
+    /// The action's catalog `duration_ms`.
+    Static,
+    /// The learned p95 of the action's past executions.
+    Learned,
+}
+
+/// Learned duration statistics of one action.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DurationStats {
+    /// Samples recorded in total, including ones decayed away.
+    pub samples: u64,
+
+    /// Weighted median duration.
+    pub p50_ms: u64,
+
+    /// Weighted 95th percentile duration.
+    pub p95_ms: u64,
+
+    /// Samples still carrying weight, as `(duration_ms, weight)`.
+    #[serde(default)]
+    pub recent: Vec<(u64, f64)>,
+}
+
+impl DurationStats {
+    /// Add a sample, decaying the weight of the older ones.
+    pub fn record(&mut self, duration_ms: u64) {
+        for (_, weight) in &mut self.recent {
+            *weight *= DECAY;
+        }
+        self.recent.retain(|(_, weight)| *weight >= MIN_WEIGHT);
+        self.recent.push((duration_ms, 1.0));
+        self.samples += 1;
+        self.p50_ms = self.quantile(0.5);
+        self.p95_ms = self.quantile(0.95);
+    }
+
+    /// Smallest recent duration with at least `q` of the total weight at
+    /// or below it.
+    fn quantile(&self, q: f64) -> u64 {
+        let mut sorted = self.recent.clone();
+        sorted.sort_by_key(|(duration, _)| *duration);
+        let total: f64 = sorted.iter().map(|(_, weight)| weight).sum();
+        let mut seen = 0.0;
+        for (duration, weight) in &sorted {
+            seen += weight;
+            if seen >= q * total {
+                return *duration;
+            }
+        }
+        sorted.last().map_or(0, |(duration, _)| *duration)
+    }
+}
+
+/// Per-action duration statistics, shared between the planner and
+/// whatever records completed steps.
+#[derive(Debug)]
+pub struct LearnedDurations {
+    min_samples: u64,
+    actions: RwLock<HashMap<String, DurationStats>>,
+}
+
+impl LearnedDurations {
+    /// Trust an action's learned duration after `min_samples` samples.
+    pub fn new(min_samples: u64) -> Self {
+        Self { min_samples, actions: RwLock::new(HashMap::new()) }
+    }
+
+    /// Samples an action needs before its learned duration is used.
+    pub fn min_samples(&self) -> u64 {
+        self.min_samples
+    }
+
+    /// Record that a step of `action` took `duration_ms`; returns the
+    /// action's updated statistics.
+    pub fn record(&self, action: &str, duration_ms: u64) -> DurationStats {
+        let mut actions = self.actions.write().unwrap();
+        let stats = actions.entry(action.to_string()).or_default();
+        stats.record(duration_ms);
+        stats.clone()
+    }
+
+    /// Replace an action's statistics, e.g. ones restored from storage.
+    pub fn insert(&self, action: impl Into<String>, stats: DurationStats) {
+        self.actions.write().unwrap().insert(action.into(), stats);
+    }
+
+    /// Statistics of one action, if any step of it was recorded.
+    pub fn get(&self, action: &str) -> Option<DurationStats> {
+        self.actions.read().unwrap().get(action).cloned()
+    }
+
+    /// Statistics of every action, by name.
+    pub fn all(&self) -> BTreeMap<String, DurationStats> {
+        self.actions.read().unwrap().iter().map(|(name, stats)| (name.clone(), stats.clone())).collect()
+    }
+
+    /// Duration to quote for `action`, whose catalog duration is
+    /// `static_ms`, and where the figure came from.
+    pub fn estimate(&self, action: &str, static_ms: u64) -> (u64, DurationSource) {
+        match self.actions.read().unwrap().get(action) {
+            Some(stats) if stats.samples >= self.min_samples => (stats.p95_ms, DurationSource::Learned),
+            _ => (static_ms, DurationSource::Static),
+        }
+    }
+}
+
+impl Default for LearnedDurations {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_SAMPLES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learned_estimate_needs_min_samples() {
+        let durations = LearnedDurations::new(3);
+        durations.record("deploy", 300);
+        durations.record("deploy", 300);
+        assert_eq!(durations.estimate("deploy", 100), (100, DurationSource::Static));
+
+        let stats = durations.record("deploy", 300);
+        assert_eq!(stats.samples, 3);
+        assert_eq!(durations.estimate("deploy", 100), (300, DurationSource::Learned));
+        assert_eq!(durations.estimate("finalize", 50), (50, DurationSource::Static));
+    }
+
+    #[test]
+    fn test_old_samples_decay() {
+        let mut stats = DurationStats::default();
+        for _ in 0..20 {
+            stats.record(100);
+        }
+        assert_eq!((stats.p50_ms, stats.p95_ms), (100, 100));
+
+        // A slowdown shows in the p95 at once and in the median soon after
+        stats.record(1_000);
+        assert_eq!((stats.p50_ms, stats.p95_ms), (100, 1_000));
+        for _ in 0..10 {
+            stats.record(1_000);
+        }
+        assert_eq!(stats.p50_ms, 1_000);
+
+        // Samples are dropped once their weight is negligible
+        for _ in 0..100 {
+            stats.record(1_000);
+        }
+        assert!(stats.recent.iter().all(|(duration, _)| *duration == 1_000));
+        assert_eq!(stats.samples, 131);
+    }
+}
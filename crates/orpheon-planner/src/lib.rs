@@ -3,7 +3,13 @@
 //! A* search-based planning engine for the Orpheon Protocol.
 
 pub mod astar;
+pub mod cancel;
+pub mod durations;
+pub mod objective;
 pub mod planner;
 
-pub use planner::{Planner, PlannerConfig};
+pub use planner::{FailedPrecondition, Planner, PlannerConfig, UnknownActionPolicy, ValidationReport};
 pub use astar::AStarPlanner;
+pub use cancel::CancellationToken;
+pub use durations::{DurationSource, DurationStats, LearnedDurations};
+pub use objective::Objective;
@@ -0,0 +1,176 @@
+//! Weighted objectives built from an intent's preferences.
+//!
+//! Search minimizes the sum of per-step costs, so every preference is turned
+//! into a non-negative per-step term that is smaller for better actions:
+//!
+//! - Each objective's metric is divided by its largest value in the catalog,
+//!   putting every metric on a 0..=1 scale so a metric measured in
+//!   milliseconds cannot drown out one measured as a probability.
+//! - A minimize objective contributes the normalized value `v / max`.
+//! - A maximize objective is negated and shifted by one, contributing the
+//!   shortfall `1 - v / max`; the best action in the catalog adds nothing.
+//! - An action that does not report a metric is scored as the worst possible
+//!   (a term of 1), whichever the direction.
+//!
+//! The terms are weighted by each preference's weight and summed. Without
+//! preferences a step costs its action's `cost`, as before.
+
+use orpheon_core::intent::OptimizationDirection;
+use orpheon_core::Intent;
+
+use crate::planner::PlanningAction;
+
+/// Metric name for an action's `cost`.
+pub const COST_METRIC: &str = "cost";
+
+/// Metric names for an action's `duration_ms`.
+pub const LATENCY_METRICS: [&str; 2] = ["latency", "duration"];
+
+/// Value of a metric for an action, if the action reports it.
+///
+/// `cost` and `latency`/`duration` come from the action's fields; anything
+/// else from its `metrics`.
+pub fn metric(action: &PlanningAction, name: &str) -> Option<f64> {
+    if name == COST_METRIC {
+        return Some(action.cost);
+    }
+    if LATENCY_METRICS.contains(&name) {
+        return Some(action.duration_ms as f64);
+    }
+    action.metrics.get(name).copied()
+}
+
+/// One normalized, weighted objective.
+#[derive(Debug, Clone)]
+struct Term {
+    metric: String,
+    direction: OptimizationDirection,
+    weight: f64,
+    /// Largest value of the metric in the catalog.
+    scale: f64,
+}
+
+/// Per-step cost function for one intent.
+#[derive(Debug, Clone, Default)]
+pub struct Objective {
+    terms: Vec<Term>,
+}
+
+impl Objective {
+    /// Build the objective for `intent` over a catalog.
+    ///
+    /// Preferences with no weight, or on a metric no action reports, are
+    /// ignored.
+    pub fn for_intent(intent: &Intent, actions: &[PlanningAction]) -> Self {
+        let terms = intent
+            .preferences
+            .iter()
+            .filter(|p| p.weight > 0.0)
+            .filter_map(|p| {
+                let scale = actions
+                    .iter()
+                    .filter_map(|a| metric(a, &p.objective))
+                    .fold(None, |max: Option<f64>, v| Some(max.map_or(v, |m| m.max(v))))?;
+                Some(Term {
+                    metric: p.objective.clone(),
+                    direction: p.direction,
+                    weight: f64::from(p.weight),
+                    scale,
+                })
+            })
+            .collect();
+        Self { terms }
+    }
+
+    /// Whether no preference applies, so steps cost their action's `cost`.
+    pub fn is_cost_only(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Cost of taking `action` as one step; never negative.
+    pub fn step_cost(&self, action: &PlanningAction) -> f64 {
+        if self.is_cost_only() {
+            return action.cost;
+        }
+        self.terms
+            .iter()
+            .map(|term| term.weight * term.score(metric(action, &term.metric)))
+            .sum()
+    }
+}
+
+impl Term {
+    /// 0 for the best possible value, 1 for the worst.
+    fn score(&self, value: Option<f64>) -> f64 {
+        let Some(value) = value else {
+            return 1.0;
+        };
+        let normalized = if self.scale > 0.0 {
+            (value / self.scale).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        match self.direction {
+            OptimizationDirection::Minimize => normalized,
+            OptimizationDirection::Maximize => 1.0 - normalized,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str, cost: f64, reliability: f64) -> PlanningAction {
+        PlanningAction {
+            name: name.to_string(),
+            cost,
+            metrics: [("reliability".to_string(), reliability)].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_maximize_prefers_larger_values() {
+        let actions = vec![action("cheap", 1.0, 0.9), action("solid", 4.0, 0.99)];
+        let intent = Intent::builder().kind("deploy").maximize("reliability", 1.0).build().unwrap();
+        let objective = Objective::for_intent(&intent, &actions);
+
+        assert!(objective.step_cost(&actions[1]) < objective.step_cost(&actions[0]));
+        assert_eq!(objective.step_cost(&actions[1]), 0.0);
+    }
+
+    #[test]
+    fn test_scales_are_normalized() {
+        let mut slow = action("slow", 1.0, 0.5);
+        slow.duration_ms = 60_000;
+        let mut fast = action("fast", 1.0, 1.0);
+        fast.duration_ms = 30_000;
+        let actions = vec![slow, fast];
+        let intent = Intent::builder()
+            .kind("deploy")
+            .minimize("latency", 0.5)
+            .maximize("reliability", 0.5)
+            .build()
+            .unwrap();
+        let objective = Objective::for_intent(&intent, &actions);
+
+        // Half the latency and full reliability: 0.5 * 0.5 + 0.5 * 0.0
+        assert!((objective.step_cost(&actions[1]) - 0.25).abs() < 1e-9);
+        // Worst latency and half reliability: 0.5 * 1.0 + 0.5 * 0.5
+        assert!((objective.step_cost(&actions[0]) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_missing_metric_scores_worst_and_unknown_objective_is_ignored() {
+        let actions = vec![action("known", 1.0, 0.8), PlanningAction { name: "unknown".to_string(), cost: 1.0, ..Default::default() }];
+        let intent = Intent::builder().kind("deploy").maximize("reliability", 1.0).build().unwrap();
+        let objective = Objective::for_intent(&intent, &actions);
+        assert_eq!(objective.step_cost(&actions[1]), 1.0);
+
+        let intent = Intent::builder().kind("deploy").maximize("throughput", 1.0).build().unwrap();
+        let objective = Objective::for_intent(&intent, &actions);
+        assert!(objective.is_cost_only());
+        assert_eq!(objective.step_cost(&actions[0]), 1.0);
+    }
+}
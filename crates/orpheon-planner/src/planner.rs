@@ -1,7 +1,10 @@
 //! Planner trait and configuration.
 
+use std::collections::BTreeMap;
+
 use async_trait::async_trait;
-use orpheon_core::{Intent, OrpheonError, Plan, Result};
+use chrono::{DateTime, Utc};
+use orpheon_core::{finite_or_err, Constraint, Intent, OrpheonError, Plan, ResourceType, Result};
 use serde::{Deserialize, Serialize};
 
 /// Configuration for the planner.
@@ -10,27 +13,158 @@ pub struct PlannerConfig {
     /// Maximum number of steps allowed in a plan.
     pub max_steps: usize,
 
+    /// Split plans longer than `max_steps` into a chain of segments (see
+    /// [`Planner::plan_segments`]) instead of failing.
+    #[serde(default)]
+    pub split_long_plans: bool,
+
     /// Maximum planning time in milliseconds.
     pub max_planning_time_ms: u64,
 
+    /// Share of the time left before an intent's earliest deadline that
+    /// planning may use (see [`PlannerConfig::planning_budget_ms`]).
+    #[serde(default = "default_deadline_budget_fraction")]
+    pub deadline_budget_fraction: f64,
+
+    /// Smallest planning budget worth searching with, in milliseconds.
+    ///
+    /// Intents whose deadline leaves less than this fail planning at once.
+    #[serde(default = "default_min_planning_time_ms")]
+    pub min_planning_time_ms: u64,
+
     /// Maximum number of states to explore.
     pub max_states_explored: usize,
 
     /// Enable plan caching/memoization.
+    ///
+    /// When set, a search that runs out of time or states leaves its
+    /// frontier behind so the next attempt at the same intent resumes it.
     pub enable_memoization: bool,
 
+    /// How long a saved partial search stays usable, in milliseconds.
+    #[serde(default = "default_resume_ttl_ms")]
+    pub resume_ttl_ms: u64,
+
     /// Confidence threshold (0.0 to 1.0) below which plans are rejected.
     pub min_confidence: f32,
+
+    /// Cost penalty added each time a soft constraint becomes violated.
+    #[serde(default = "default_soft_constraint_penalty")]
+    pub soft_constraint_penalty: f64,
+
+    /// Share of a resource limit past which the resource counts as nearly
+    /// exhausted.
+    #[serde(default = "default_resource_pressure_threshold")]
+    pub resource_pressure_threshold: f64,
+
+    /// Cost penalty added each time a limited resource becomes nearly
+    /// exhausted, so plans leave headroom when they can.
+    #[serde(default = "default_resource_pressure_penalty")]
+    pub resource_pressure_penalty: f64,
+
+    /// Check the heuristic for consistency and admissibility while searching.
+    ///
+    /// Defaults to on in debug builds. Violations are logged and counted in
+    /// [`PlanningResult::heuristic_violations`].
+    #[serde(default = "default_validate_heuristic")]
+    pub validate_heuristic: bool,
+
+    /// Fail planning when heuristic validation finds a violation.
+    #[serde(default)]
+    pub strict_heuristic: bool,
+
+    /// How plan validation treats steps whose action is not in the catalog.
+    #[serde(default)]
+    pub unknown_action_policy: UnknownActionPolicy,
+}
+
+/// How [`Planner::validate_plan_report`] treats steps whose action is not
+/// in the catalog and that carry no facts of their own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownActionPolicy {
+    /// Report the action and fail validation.
+    #[default]
+    Reject,
+    /// Report the action and log a warning, but pass validation.
+    Warn,
+    /// Assume the action is valid without reporting it.
+    Ignore,
+}
+
+fn default_soft_constraint_penalty() -> f64 {
+    10.0
+}
+
+fn default_resource_pressure_threshold() -> f64 {
+    0.9
+}
+
+fn default_resource_pressure_penalty() -> f64 {
+    5.0
+}
+
+fn default_deadline_budget_fraction() -> f64 {
+    0.1
+}
+
+fn default_min_planning_time_ms() -> u64 {
+    50
+}
+
+fn default_resume_ttl_ms() -> u64 {
+    300_000
+}
+
+fn default_validate_heuristic() -> bool {
+    cfg!(debug_assertions)
 }
 
 impl Default for PlannerConfig {
     fn default() -> Self {
         Self {
             max_steps: 100,
+            split_long_plans: false,
             max_planning_time_ms: 30_000,
+            deadline_budget_fraction: default_deadline_budget_fraction(),
+            min_planning_time_ms: default_min_planning_time_ms(),
             max_states_explored: 10_000,
             enable_memoization: true,
+            resume_ttl_ms: default_resume_ttl_ms(),
             min_confidence: 0.5,
+            soft_constraint_penalty: default_soft_constraint_penalty(),
+            resource_pressure_threshold: default_resource_pressure_threshold(),
+            resource_pressure_penalty: default_resource_pressure_penalty(),
+            validate_heuristic: default_validate_heuristic(),
+            strict_heuristic: false,
+            unknown_action_policy: UnknownActionPolicy::default(),
+        }
+    }
+}
+
+impl PlannerConfig {
+    /// Planning time budget for an intent in milliseconds, as of `now`.
+    ///
+    /// This is `max_planning_time_ms`, shortened to `deadline_budget_fraction`
+    /// of the time left before the intent's earliest `Deadline` or the end of
+    /// its validity window.
+    pub fn planning_budget_ms(&self, intent: &Intent, now: DateTime<Utc>) -> u64 {
+        let due = intent
+            .constraints
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Deadline { by } => Some(*by),
+                _ => None,
+            })
+            .chain(intent.validity_window.not_after)
+            .min();
+
+        match due {
+            Some(due) => {
+                let left_ms = (due - now).num_milliseconds().max(0) as f64;
+                self.max_planning_time_ms.min((left_ms * self.deadline_budget_fraction) as u64)
+            }
+            None => self.max_planning_time_ms,
         }
     }
 }
@@ -46,6 +180,9 @@ pub struct PlanningState {
     
     /// Time accumulated so far.
     pub accumulated_time_ms: u64,
+    
+    /// Resources consumed so far, e.g. memory in bytes.
+    pub resources_used: std::collections::HashMap<ResourceType, f64>,
 }
 
 impl Default for PlanningState {
@@ -54,27 +191,116 @@ impl Default for PlanningState {
             variables: std::collections::HashMap::new(),
             accumulated_cost: 0.0,
             accumulated_time_ms: 0,
+            resources_used: std::collections::HashMap::new(),
         }
     }
 }
 
+impl PlanningState {
+    /// Amount of `resource` consumed so far.
+    pub fn resource_used(&self, resource: &ResourceType) -> f64 {
+        self.resources_used.get(resource).copied().unwrap_or(0.0)
+    }
+}
+
 /// Action that can be taken during planning.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PlanningAction {
     /// Name of the action.
     pub name: String,
     
     /// Preconditions that must be true.
+    #[serde(default)]
     pub preconditions: Vec<String>,
     
     /// Effects on state variables.
+    #[serde(default)]
     pub effects: Vec<String>,
     
     /// Estimated cost.
+    #[serde(default)]
     pub cost: f64,
     
     /// Estimated duration in milliseconds.
+    #[serde(default)]
     pub duration_ms: u64,
+    
+    /// Region the action runs in (checked against GeoFence constraints).
+    #[serde(default)]
+    pub region: Option<String>,
+    
+    /// Provider/node the action runs on (checked against Provider constraints).
+    #[serde(default)]
+    pub provider: Option<String>,
+    
+    /// Namespace grouping related actions (e.g. a catalog or team name).
+    #[serde(default)]
+    pub namespace: Option<String>,
+    
+    /// Intent kinds this action may be planned for; empty means any kind.
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    
+    /// Action that undoes this one if a later step fails.
+    #[serde(default)]
+    pub compensate: Option<String>,
+    
+    /// Time limit for a single attempt, in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    
+    /// Resources the action consumes, e.g. memory in bytes, accumulated
+    /// along a plan and held to the intent's resource limits.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<(ResourceType, f64)>,
+    
+    /// Further metrics preferences can optimize, e.g. `reliability: 0.99`.
+    /// See [`crate::objective`] for how they are weighed.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    
+    /// Parameters for steps planned from this action. Strings may hold
+    /// `${...}` bindings; see [`orpheon_core::context`].
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub parameters: serde_json::Value,
+    
+    /// Description for steps planned from this action. May hold
+    /// `${intent.*}` bindings, filled in when the plan is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Tags copied onto steps planned from this action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    
+    /// Whether steps planned from this action may fail without failing
+    /// the intent.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+    
+    /// Condition under which steps planned from this action are skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_if: Option<String>,
+}
+
+impl PlanningAction {
+    /// Whether this action may be planned for an intent of `kind`.
+    pub fn applies_to(&self, kind: &str) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|k| k == kind)
+    }
+    
+    /// Check that the cost, resource consumption and every metric are
+    /// finite numbers.
+    pub fn validate(&self) -> Result<()> {
+        finite_or_err(self.cost, format_args!("cost of action {}", self.name))?;
+        for (resource, amount) in &self.resources {
+            finite_or_err(*amount, format_args!("{} consumed by action {}", resource.name(), self.name))?;
+        }
+        for (metric, value) in &self.metrics {
+            finite_or_err(*value, format_args!("metric {} of action {}", metric, self.name))?;
+        }
+        Ok(())
+    }
 }
 
 /// Trait for planning engines.
@@ -83,8 +309,25 @@ pub trait Planner: Send + Sync {
     /// Generate a plan for the given intent.
     async fn plan(&self, intent: &Intent, initial_state: &PlanningState) -> Result<Plan>;
 
+    /// Generate a plan as a chain of segments, each within `max_steps`
+    /// when [`PlannerConfig::split_long_plans`] is set, linked by
+    /// `continuation_of` and run one after another.
+    async fn plan_segments(&self, intent: &Intent, initial_state: &PlanningState) -> Result<Vec<Plan>> {
+        let plan = self.plan(intent, initial_state).await?;
+        let config = self.config();
+        Ok(if config.split_long_plans { plan.split(config.max_steps) } else { vec![plan] })
+    }
+
+    /// Check a plan against the action catalog, starting from `current_state`.
+    ///
+    /// A step whose action is not in the catalog is checked against its own
+    /// `preconditions` and `produces` when it carries any.
+    async fn validate_plan_report(&self, plan: &Plan, current_state: &PlanningState) -> Result<ValidationReport>;
+
     /// Check if a plan is still valid.
-    async fn validate_plan(&self, plan: &Plan, current_state: &PlanningState) -> Result<bool>;
+    async fn validate_plan(&self, plan: &Plan, current_state: &PlanningState) -> Result<bool> {
+        Ok(self.validate_plan_report(plan, current_state).await?.valid)
+    }
 
     /// Get the planner configuration.
     fn config(&self) -> &PlannerConfig;
@@ -93,6 +336,61 @@ pub trait Planner: Send + Sync {
     fn set_config(&mut self, config: PlannerConfig);
 }
 
+/// What checking a plan against the action catalog found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// Whether the plan may run.
+    pub valid: bool,
+
+    /// Actions of steps that are not in the catalog, in step order; empty
+    /// under [`UnknownActionPolicy::Ignore`].
+    pub unknown_actions: Vec<String>,
+
+    /// Steps taken before their preconditions hold.
+    pub failed_preconditions: Vec<FailedPrecondition>,
+}
+
+impl ValidationReport {
+    /// Whether validation found nothing to report.
+    pub fn is_clean(&self) -> bool {
+        self.unknown_actions.is_empty() && self.failed_preconditions.is_empty()
+    }
+
+    /// One-line account of the findings, e.g. for a warning.
+    pub fn describe(&self) -> String {
+        let mut findings = Vec::new();
+        if !self.unknown_actions.is_empty() {
+            findings.push(format!("unknown actions: {}", self.unknown_actions.join(", ")));
+        }
+        for failed in &self.failed_preconditions {
+            findings.push(failed.to_string());
+        }
+        if findings.is_empty() {
+            return "plan is valid".to_string();
+        }
+        findings.join("; ")
+    }
+}
+
+/// A plan step whose action's preconditions do not hold when it is taken.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailedPrecondition {
+    /// Name of the step.
+    pub step: String,
+
+    /// The step's action.
+    pub action: String,
+
+    /// Preconditions not yet established.
+    pub missing: Vec<String>,
+}
+
+impl std::fmt::Display for FailedPrecondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "step {} ({}) is missing preconditions: {}", self.step, self.action, self.missing.join(", "))
+    }
+}
+
 /// Result of a planning operation with additional metadata.
 #[derive(Debug)]
 pub struct PlanningResult {
@@ -105,6 +403,28 @@ pub struct PlanningResult {
     /// Time spent planning in milliseconds.
     pub planning_time_ms: u64,
 
+    /// Planning time budget this run was given, in milliseconds; see
+    /// [`PlannerConfig::planning_budget_ms`].
+    pub planning_budget_ms: u64,
+
     /// Error if planning failed.
     pub error: Option<OrpheonError>,
+
+    /// Heuristic consistency and admissibility violations seen while searching.
+    pub heuristic_violations: usize,
+
+    /// Whether the search resumed from an earlier attempt that ran out of
+    /// budget, rather than starting fresh.
+    pub resumed: bool,
+}
+
+impl PlanningResult {
+    /// Convert into the plan, or the planning error.
+    pub fn into_result(self) -> Result<Plan> {
+        match (self.plan, self.error) {
+            (Some(plan), _) => Ok(plan),
+            (None, Some(error)) => Err(error),
+            (None, None) => Err(OrpheonError::Internal("planning produced no plan".to_string())),
+        }
+    }
 }
@@ -0,0 +1,158 @@
+//! Golden plan tests over the cases in `tests/corpus/`.
+//!
+//! Each case `NAME.json` describes an intent and, optionally, the action
+//! catalog to plan it with; `NAME.golden.json` holds the expected summary
+//! of the plan (or of why there is none). Summaries leave out IDs, so they
+//! only change when the planner's choices do.
+//!
+//! Run with `UPDATE_GOLDENS=1` to rewrite the goldens from the current
+//! planner, then review the diff.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use orpheon_core::{parse_constraint, Budget, Intent, OrpheonError, Preference};
+use orpheon_planner::planner::{PlanningAction, PlanningState};
+use orpheon_planner::{AStarPlanner, PlannerConfig};
+use serde::{Deserialize, Serialize};
+
+/// An intent to plan and the catalog to plan it with.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Case {
+    /// What the case guards; not used by the harness.
+    #[allow(dead_code)]
+    description: String,
+    kind: String,
+    #[serde(default)]
+    max_cost: Option<f64>,
+    #[serde(default)]
+    max_duration_ms: Option<u64>,
+    /// Constraint expressions, e.g. `region in ['eu-west']`.
+    #[serde(default)]
+    constraints: Vec<String>,
+    #[serde(default)]
+    soft_constraints: Vec<String>,
+    #[serde(default)]
+    preferences: Vec<Preference>,
+    /// The planner's built-in catalog when absent.
+    #[serde(default)]
+    actions: Option<Vec<PlanningAction>>,
+}
+
+/// What a case is expected to plan.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Summary {
+    Planned {
+        /// Step actions in plan order.
+        actions: Vec<String>,
+        total_cost: f64,
+        critical_path_ms: u64,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+impl Case {
+    fn intent(&self) -> Intent {
+        let mut builder = Intent::builder().kind(&self.kind).budget(Budget {
+            max_cost: self.max_cost,
+            max_duration_ms: self.max_duration_ms,
+            ..Budget::default()
+        });
+        for text in &self.constraints {
+            builder = builder.constraint(parse_constraint(text).unwrap());
+        }
+        for text in &self.soft_constraints {
+            builder = builder.soft_constraint(parse_constraint(text).unwrap());
+        }
+        for preference in &self.preferences {
+            builder = builder.preference(preference.clone());
+        }
+        builder.build().unwrap()
+    }
+
+    fn plan(&self) -> Summary {
+        let planner = match &self.actions {
+            Some(actions) => AStarPlanner::with_actions(PlannerConfig::default(), actions.clone()),
+            None => AStarPlanner::new(),
+        };
+        match planner.plan_with_stats(&self.intent(), &PlanningState::default()).into_result() {
+            Ok(plan) => Summary::Planned {
+                actions: plan.steps.iter().map(|s| s.action.clone()).collect(),
+                total_cost: plan.estimated_cost,
+                critical_path_ms: plan.critical_path_ms(&HashSet::new()),
+            },
+            Err(OrpheonError::PlanningFailed { message, .. }) => Summary::Failed { reason: message },
+            Err(e) => Summary::Failed { reason: e.to_string() },
+        }
+    }
+}
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+/// Case files, sorted by name.
+fn cases() -> Vec<PathBuf> {
+    let mut cases: Vec<PathBuf> = fs::read_dir(corpus_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.ends_with(".json") && !name.ends_with(".golden.json")
+        })
+        .collect();
+    cases.sort();
+    cases
+}
+
+#[test]
+fn test_corpus_matches_goldens() {
+    let update = std::env::var("UPDATE_GOLDENS").is_ok_and(|v| v == "1");
+    let cases = cases();
+    assert!(cases.len() >= 10, "expected at least ten corpus cases, found {}", cases.len());
+
+    let mut mismatches = Vec::new();
+    for path in cases {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let case: Case = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let actual = case.plan();
+        let golden_path = corpus_dir().join(format!("{}.golden.json", name));
+
+        if update {
+            fs::write(&golden_path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+            continue;
+        }
+        let Ok(golden) = fs::read_to_string(&golden_path) else {
+            mismatches.push(format!("{}: no golden; run with UPDATE_GOLDENS=1 to create it", name));
+            continue;
+        };
+        let expected: Summary = serde_json::from_str(&golden).unwrap();
+        if actual != expected {
+            mismatches.push(format!("{}:\n  expected {:?}\n  got      {:?}", name, expected, actual));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "{} case(s) differ from their goldens (rerun with UPDATE_GOLDENS=1 if the change is intended):\n{}",
+        mismatches.len(),
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn test_planning_is_deterministic() {
+    for path in cases() {
+        let case: Case = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let first = case.plan();
+        for _ in 0..3 {
+            assert_eq!(case.plan(), first, "{} planned differently on a rerun", path.display());
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Operator notes on plans and execution artifacts.
+//!
+//! Annotations are kept beside the record they describe, never in it: they
+//! are not part of an artifact's Merkle root or signature, so adding one
+//! after the fact does not change what [`verify_artifact`](crate::verify_artifact)
+//! reports.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A note an operator attached to a plan or artifact.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: Uuid,
+
+    /// The step the note is about; `None` for the whole plan or artifact.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_step_id: Option<Uuid>,
+    pub author: String,
+    pub text: String,
+
+    /// Labels to find the note by, e.g. `quota` or `postmortem`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Annotation {
+    /// Whether the annotation carries `label`.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels.iter().any(|l| l == label)
+    }
+}
+
+/// Request body for adding an annotation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewAnnotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_step_id: Option<Uuid>,
+    pub author: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+impl NewAnnotation {
+    /// A note by `author` on the whole plan or artifact.
+    pub fn new(author: impl Into<String>, text: impl Into<String>) -> Self {
+        Self { author: author.into(), text: text.into(), ..Default::default() }
+    }
+
+    /// Attach the note to one step.
+    pub fn on_step(mut self, step_id: Uuid) -> Self {
+        self.target_step_id = Some(step_id);
+        self
+    }
+
+    /// Add a label.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.labels.push(label.into());
+        self
+    }
+}
+
+/// A plan or artifact as served, with its annotations alongside.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotated<T> {
+    #[serde(flatten)]
+    pub record: T,
+
+    /// Oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
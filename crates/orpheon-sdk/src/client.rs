@@ -1,10 +1,37 @@
 //! Orpheon client implementation.
 
-use orpheon_core::{Budget, Intent, IntentBuilder, OrpheonError, Plan, Result};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use orpheon_core::{
+    codec, ArtifactBundle, Budget, ExecutionArtifact, Intent, IntentStatus, IntentSummary, OrpheonError, Plan, Provenance, Result, WsCloseReason,
+    API_KEY_HEADER, CLIENT_HEADER, FORWARD_HOPS_HEADER, SOURCE_HEADER,
+};
+use orpheon_negotiate::{AcceptanceReceipt, NegotiationOptions};
+use futures::Stream;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::stream::EventStream;
+use crate::annotation::{Annotated, Annotation, NewAnnotation};
+use crate::export::{ndjson_records, ExportFilter, IntentRecordExport};
+use crate::inspect::PlanSummaryExt;
+use crate::negotiation::Negotiation;
+use crate::stream::{Event, EventStream, MultiEventStream, WatchFilter};
+use crate::verify::{verify_artifact, verify_receipt, VerificationReport};
+
+/// First wait between polls for a resource that is not ready yet.
+const POLL_INITIAL_DELAY: Duration = Duration::from_millis(50);
+
+/// Longest wait between polls.
+const POLL_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Sent as [`CLIENT_HEADER`] with every request, e.g.
+/// `orpheon-sdk/0.1.0 (linux; x86_64)`.
+fn client_name() -> String {
+    format!("orpheon-sdk/{} ({}; {})", env!("CARGO_PKG_VERSION"), std::env::consts::OS, std::env::consts::ARCH)
+}
 
 /// Client for interacting with an Orpheon node.
 #[derive(Clone)]
@@ -14,14 +41,39 @@ pub struct OrpheonClient {
     
     /// HTTP client.
     http_client: reqwest::Client,
+    
+    /// Expected node public key (hex-encoded ed25519) for artifact verification.
+    node_public_key: Option<String>,
+    
+    /// API key sent with every request.
+    api_key: Option<String>,
+    
+    /// Forwarding hop count sent with every request (node-to-node calls only).
+    forward_hops: Option<u32>,
+    
+    /// What the application says it is, sent with every request.
+    source: Option<String>,
+    
+    /// WebSocket base URL to use instead of the node's advertised stream URLs.
+    ws_base_url: Option<String>,
+    
+    /// Whether to ask for artifacts in CBOR rather than JSON.
+    prefer_cbor: bool,
 }
 
-/// Response from submitting an intent.
-#[derive(Debug, Deserialize)]
-struct SubmitResponse {
-    id: Uuid,
-    status: String,
-    message: String,
+/// What the node said about a submitted intent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmitResult {
+    /// Id the intent is stored under.
+    pub id: Uuid,
+    pub status: String,
+    pub message: String,
+    /// Where the node says the intent's event stream is.
+    #[serde(default)]
+    pub stream_url: Option<String>,
+    /// Things to fix before they become errors, e.g. a deprecated kind.
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 /// Response with intent details.
@@ -34,35 +86,135 @@ pub struct IntentResponse {
     pub artifact_id: Option<Uuid>,
     pub error: Option<String>,
     pub created_at: String,
+    /// Seq of the latest status change; compare with [`EventStream::last_seq`](crate::EventStream::last_seq).
+    #[serde(default)]
+    pub seq: u64,
+    /// Percentage of steps that succeeded, for `partially_complete` intents.
+    #[serde(default)]
+    pub success_rate: Option<u8>,
+    /// When a deferred intent still waiting to start will begin executing.
+    #[serde(default)]
+    pub scheduled_for: Option<String>,
+    /// Whether the node accepted the plan under the client's auto-accept bounds.
+    #[serde(default)]
+    pub auto_accepted_by_policy: bool,
+    /// Who submitted the intent and from where.
+    #[serde(default)]
+    pub provenance: Provenance,
+    /// Kind, constraints, budget and window at a glance; absent from older nodes.
+    #[serde(default)]
+    pub summary: Option<IntentSummary>,
+    /// The intent's labels.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
 }
 
-/// Request body for submitting an intent.
-#[derive(Debug, Serialize)]
-struct SubmitRequest {
-    kind: String,
-    constraints: Vec<serde_json::Value>,
-    preferences: Vec<serde_json::Value>,
-    budget: Option<BudgetRequest>,
-    metadata: serde_json::Value,
+/// How an intent waited on with [`OrpheonClient::submit_and_wait`] finished.
+#[derive(Debug, Clone)]
+pub enum Completion {
+    /// Every step succeeded.
+    Full(ExecutionArtifact),
+    /// The intent finished but some optional steps failed.
+    Partial {
+        artifact: ExecutionArtifact,
+        /// Percentage of steps that succeeded.
+        success_rate: u8,
+    },
+}
+
+impl Completion {
+    /// The execution artifact.
+    pub fn artifact(&self) -> &ExecutionArtifact {
+        match self {
+            Completion::Full(artifact) | Completion::Partial { artifact, .. } => artifact,
+        }
+    }
+
+    /// Take the execution artifact.
+    pub fn into_artifact(self) -> ExecutionArtifact {
+        match self {
+            Completion::Full(artifact) | Completion::Partial { artifact, .. } => artifact,
+        }
+    }
+
+    /// Whether some steps failed.
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Completion::Partial { .. })
+    }
+}
+
+/// A planner action a node can use.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionInfo {
+    pub name: String,
+    pub preconditions: Vec<String>,
+    pub effects: Vec<String>,
+    pub cost: f64,
+    pub duration_ms: u64,
+    pub region: Option<String>,
+    pub provider: Option<String>,
+    pub namespace: Option<String>,
+    /// Intent kinds the action is limited to; empty means any kind.
+    pub kinds: Vec<String>,
+    /// Description template for steps planned from the action.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A node's planner action catalog.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionCatalog {
+    /// Catalog file the extra actions were loaded from, if any.
+    pub source: Option<String>,
+    /// Hash of the whole catalog; unchanged hash means unchanged actions.
+    pub hash: String,
+    pub actions: Vec<ActionInfo>,
+}
+
+/// Capabilities a node advertises for intents to require.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Capabilities {
+    /// The node's federation ID, if it has one.
+    pub node_id: Option<String>,
+    pub capabilities: Vec<String>,
+}
+
+impl Capabilities {
+    /// The requirements of `intent` the node does not meet.
+    pub fn missing_for(&self, intent: &Intent) -> Vec<String> {
+        intent.missing_capabilities(&self.capabilities)
+    }
 }
 
+/// Request body for submitting a full intent document.
 #[derive(Debug, Serialize)]
-struct BudgetRequest {
-    max_cost: Option<f64>,
-    currency: Option<String>,
-    max_duration_ms: Option<u64>,
-    max_retries: Option<u32>,
+struct SubmitRequest<'a> {
+    intent: &'a Intent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    negotiation: Option<&'a NegotiationOptions>,
 }
 
 impl OrpheonClient {
     /// Connect to an Orpheon node.
     pub async fn connect(url: &str) -> Result<Self> {
-        let base_url = url.trim_end_matches('/').to_string();
-        let http_client = reqwest::Client::new();
+        let mut client = Self {
+            base_url: url.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::new(),
+            node_public_key: None,
+            api_key: None,
+            forward_hops: None,
+            source: None,
+            ws_base_url: None,
+            prefer_cbor: false,
+        };
+        client.rebuild_http_client()?;
         
         // Verify connection with health check
-        let health_url = format!("{}/health", base_url);
-        http_client
+        let health_url = format!("{}/health", client.base_url);
+        client
+            .http_client
             .get(&health_url)
             .send()
             .await
@@ -70,29 +222,261 @@ impl OrpheonClient {
             .error_for_status()
             .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
         
-        Ok(Self {
-            base_url,
-            http_client,
-        })
+        Ok(client)
+    }
+    
+    /// Set the node public key used to check artifact signatures.
+    pub fn with_node_public_key(mut self, key: impl Into<String>) -> Self {
+        self.node_public_key = Some(key.into());
+        self
+    }
+    
+    /// Open event streams under `url`, e.g. `wss://gw.example.com/orpheon`,
+    /// instead of where the node says they are.
+    ///
+    /// Without this the client uses the stream URL the node returns on
+    /// submit, then one derived from the client's base URL.
+    pub fn with_ws_base_url(mut self, url: impl Into<String>) -> Self {
+        self.ws_base_url = Some(url.into().trim_end_matches('/').to_string());
+        self
+    }
+    
+    /// Ask for artifacts in CBOR, which is much smaller than JSON for long
+    /// traces. Nodes that only speak JSON still answer in JSON.
+    pub fn with_prefer_cbor(mut self, prefer: bool) -> Self {
+        self.prefer_cbor = prefer;
+        self
+    }
+    
+    /// Authenticate every request with an API key.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Result<Self> {
+        self.api_key = Some(key.into());
+        self.rebuild_http_client()?;
+        Ok(self)
+    }
+    
+    /// Mark requests as forwarded by another node, `hops` times so far.
+    pub fn with_forward_hops(mut self, hops: u32) -> Result<Self> {
+        self.forward_hops = Some(hops);
+        self.rebuild_http_client()?;
+        Ok(self)
+    }
+    
+    /// Tell the node what is submitting intents, e.g. `ci` or `dashboard`;
+    /// operators can list intents by it.
+    pub fn with_source(mut self, source: impl Into<String>) -> Result<Self> {
+        self.source = Some(source.into());
+        self.rebuild_http_client()?;
+        Ok(self)
+    }
+    
+    fn rebuild_http_client(&mut self) -> Result<()> {
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(&client_name()) {
+            headers.insert(CLIENT_HEADER, value);
+        }
+        if let Some(source) = &self.source {
+            let value = HeaderValue::from_str(source)
+                .map_err(|e| OrpheonError::ConnectionError(format!("invalid source: {}", e)))?;
+            headers.insert(SOURCE_HEADER, value);
+        }
+        if let Some(key) = &self.api_key {
+            let value = HeaderValue::from_str(key)
+                .map_err(|e| OrpheonError::ConnectionError(format!("invalid API key: {}", e)))?;
+            headers.insert(API_KEY_HEADER, value);
+        }
+        if let Some(hops) = self.forward_hops {
+            headers.insert(FORWARD_HOPS_HEADER, HeaderValue::from(hops));
+        }
+        self.http_client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        Ok(())
     }
     
     /// Submit an intent and get a stream of events.
+    ///
+    /// The whole intent is sent. Unsigned intents are given a new id by the
+    /// node; use [`EventStream::intent_id`] to find it.
+    ///
+    /// The stream is opened under [`with_ws_base_url`](Self::with_ws_base_url)
+    /// if set. Otherwise the stream URL the node advertises is tried first,
+    /// then one derived from the client's base URL.
     pub async fn submit(&self, intent: Intent) -> Result<EventStream> {
-        // Submit the intent via REST
-        let url = format!("{}/api/v1/intent", self.base_url);
+        let response = self.submit_request(intent, None).await?;
+        let intent_id = response.id;
+        
+        let derived = format!("{}/ws/intent/{}", self.ws_base_url(), intent_id);
+        let mut candidates = Vec::new();
+        if self.ws_base_url.is_none() {
+            candidates.extend(response.stream_url.filter(|url| *url != derived));
+        }
+        candidates.push(derived);
         
-        let request = SubmitRequest {
-            kind: intent.kind.clone(),
-            constraints: intent.constraints.iter().map(|c| serde_json::to_value(c).unwrap()).collect(),
-            preferences: intent.preferences.iter().map(|p| serde_json::to_value(p).unwrap()).collect(),
-            budget: Some(BudgetRequest {
-                max_cost: intent.budget.max_cost,
-                currency: Some(intent.budget.currency.clone()),
-                max_duration_ms: intent.budget.max_duration_ms,
-                max_retries: Some(intent.budget.max_retries),
+        let mut error = None;
+        for ws_url in candidates {
+            match EventStream::connect(&ws_url, intent_id).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    tracing::debug!(%intent_id, %ws_url, "Could not open event stream: {}", e);
+                    error = Some(e);
+                }
+            }
+        }
+        Err(error.unwrap_or_else(|| OrpheonError::ConnectionError("no event stream URL".to_string())))
+    }
+    
+    /// Submit an intent and wait for it to finish.
+    ///
+    /// Returns the artifact, marked partial if optional steps failed; an
+    /// intent that fails or is cancelled is an error.
+    ///
+    /// If the stream drops, the node is polled until the intent finishes,
+    /// unless the node closed the stream for a reason retrying cannot fix
+    /// (see [`WsCloseReason::is_retryable`]); that is an error at once.
+    ///
+    /// Once the intent has a plan, a warning is logged for each way the plan
+    /// exceeds the intent's budget.
+    pub async fn submit_and_wait(&self, intent: Intent) -> Result<Completion> {
+        let budget = intent.budget.clone();
+        let mut stream = self.submit(intent).await?;
+        let intent_id = stream.intent_id();
+        let mut plan_checked = false;
+        
+        loop {
+            let event = stream.next().await;
+            if !plan_checked
+                && matches!(
+                    event,
+                    Some(Event::Negotiating { .. } | Event::Executing { .. } | Event::StatusUpdate { plan_id: Some(_), .. })
+                )
+            {
+                plan_checked = true;
+                self.warn_if_over_budget(intent_id, &budget).await;
+            }
+            match event {
+                Some(Event::Complete { .. }) => return Ok(Completion::Full(self.wait_for_artifact(intent_id).await?)),
+                Some(Event::PartialComplete { success_rate, .. }) => {
+                    let artifact = self.wait_for_artifact(intent_id).await?;
+                    return Ok(Completion::Partial { artifact, success_rate });
+                }
+                Some(Event::Error { message }) => return Err(OrpheonError::Internal(message)),
+                Some(Event::Closed { reason }) if !reason.is_retryable() && reason != WsCloseReason::Terminal => {
+                    return Err(match reason {
+                        WsCloseReason::NotFound => OrpheonError::NotFound { resource_type: "intent".to_string(), id: intent_id.to_string() },
+                        reason => OrpheonError::ConnectionError(format!("Event stream closed: {}", reason.as_str())),
+                    });
+                }
+                Some(Event::StatusUpdate { status, .. }) if !matches!(status.as_str(), "failed" | "cancelled" | "expired") => {}
+                Some(Event::Negotiating { .. } | Event::Executing { .. } | Event::Paused { .. } | Event::Warning { .. }) => {}
+                // Terminal failure, missed updates or a dropped stream: ask the node
+                event => {
+                    let response = self.get_intent(intent_id).await?;
+                    if let Some(result) = self.completion(response).await {
+                        return result;
+                    }
+                    if event.is_none() {
+                        // No more updates are coming; wait for the artifact instead
+                        return self.poll_until_finished(intent_id).await;
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Log a warning for each way the intent's plan exceeds `budget`.
+    async fn warn_if_over_budget(&self, intent_id: Uuid, budget: &Budget) {
+        match self.get_plan(intent_id).await {
+            Ok(plan) => {
+                for violation in plan.violates_budget(budget) {
+                    tracing::warn!(%intent_id, plan_id = %plan.id, "Plan exceeds budget: {}", violation);
+                }
+            }
+            Err(e) => tracing::debug!(%intent_id, "Could not check plan against budget: {}", e),
+        }
+    }
+    
+    /// Fetch an intent's artifact, polling while it is not ready yet.
+    ///
+    /// An intent that finished without an artifact is an error.
+    async fn wait_for_artifact(&self, intent_id: Uuid) -> Result<ExecutionArtifact> {
+        let mut delay = POLL_INITIAL_DELAY;
+        loop {
+            match self.get_artifact(intent_id).await {
+                Err(OrpheonError::NotReady { status, .. }) if !status.is_terminal() => {
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(POLL_MAX_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+    
+    /// Poll an intent's artifact until the intent finishes.
+    async fn poll_until_finished(&self, intent_id: Uuid) -> Result<Completion> {
+        match self.wait_for_artifact(intent_id).await {
+            Ok(artifact) => Ok(match artifact.outcome {
+                orpheon_core::Outcome::PartialSuccess { success_rate, .. } => Completion::Partial { artifact, success_rate },
+                _ => Completion::Full(artifact),
+            }),
+            // Finished without an artifact: report how
+            Err(OrpheonError::NotReady { .. }) => {
+                let response = self.get_intent(intent_id).await?;
+                let status = response.status.clone();
+                self.completion(response).await.unwrap_or_else(|| {
+                    Err(OrpheonError::Internal(format!("Intent {} ended as {} without an artifact", intent_id, status)))
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// How an intent finished, or `None` if it is still running.
+    async fn completion(&self, response: IntentResponse) -> Option<Result<Completion>> {
+        let result = match response.status.as_str() {
+            "complete" => self.get_artifact(response.id).await.map(Completion::Full),
+            "partially_complete" => self.get_artifact(response.id).await.map(|artifact| Completion::Partial {
+                artifact,
+                success_rate: response.success_rate.unwrap_or_default(),
             }),
-            metadata: intent.metadata.clone(),
+            "failed" | "cancelled" | "expired" => Err(OrpheonError::Internal(format!(
+                "Intent {} {}: {}",
+                response.id,
+                response.status,
+                response.error.as_deref().unwrap_or("no reason given")
+            ))),
+            _ => return None,
         };
+        Some(result)
+    }
+    
+    /// Submit an intent without opening an event stream.
+    ///
+    /// Returns the ID the node stored the intent under.
+    pub async fn submit_detached(&self, intent: Intent) -> Result<Uuid> {
+        Ok(self.submit_request(intent, None).await?.id)
+    }
+    
+    /// Submit an intent whose plan is negotiated before it executes.
+    ///
+    /// Join the negotiation with [`negotiate`](Self::negotiate), unless the
+    /// options' auto-accept bounds let the node accept the first proposal.
+    pub async fn submit_negotiated(&self, intent: Intent, options: NegotiationOptions) -> Result<Uuid> {
+        Ok(self.submit_request(intent, Some(&options)).await?.id)
+    }
+    
+    /// Submit an intent without opening its event stream, returning what
+    /// the node said about it, including any [warnings](SubmitResult::warnings).
+    pub async fn submit_with_result(&self, intent: Intent) -> Result<SubmitResult> {
+        self.submit_request(intent, None).await
+    }
+    
+    /// Submit an intent via REST; warnings are logged.
+    async fn submit_request(&self, intent: Intent, negotiation: Option<&NegotiationOptions>) -> Result<SubmitResult> {
+        let url = format!("{}/api/v1/intent", self.base_url);
+        
+        let request = SubmitRequest { intent: &intent, negotiation };
         
         let response = self.http_client
             .post(&url)
@@ -106,19 +490,35 @@ impl OrpheonClient {
             return Err(OrpheonError::Internal(format!("Failed to submit intent: {}", error_text)));
         }
         
-        let submit_response: SubmitResponse = response
+        let result: SubmitResult = response
             .json()
             .await
             .map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
-        
-        // Create WebSocket stream for updates
-        let ws_url = format!(
-            "{}/ws/intent/{}",
-            self.base_url.replace("http://", "ws://").replace("https://", "wss://"),
-            submit_response.id
-        );
-        
-        EventStream::connect(&ws_url, submit_response.id).await
+        for warning in &result.warnings {
+            tracing::warn!(intent_id = %result.id, "{}", warning);
+        }
+        Ok(result)
+    }
+    
+    /// Join the negotiation of an intent submitted with negotiation options.
+    pub async fn negotiate(&self, intent_id: Uuid) -> Result<Negotiation> {
+        let ws_url = format!("{}/ws/negotiate/{}", self.ws_base_url(), intent_id);
+        Negotiation::connect(&ws_url, intent_id).await
+    }
+    
+    /// Watch many intents over a single WebSocket connection.
+    pub async fn watch_intents(&self, filter: WatchFilter) -> Result<MultiEventStream> {
+        let ws_url = format!("{}/ws/intents", self.ws_base_url());
+        MultiEventStream::connect(&ws_url, filter).await
+    }
+    
+    /// Base URL for WebSocket connections: the override if set, otherwise
+    /// the base URL with its scheme swapped, keeping any path prefix.
+    fn ws_base_url(&self) -> String {
+        if let Some(url) = &self.ws_base_url {
+            return url.clone();
+        }
+        derive_ws_base_url(&self.base_url)
     }
     
     /// Get the status of an intent.
@@ -144,7 +544,122 @@ impl OrpheonClient {
             .map_err(|e| OrpheonError::SerializationError(e.to_string()))
     }
     
+    /// List the node's intents, only those submitted with `source` if given
+    /// and carrying every one of `labels`.
+    pub async fn list_intents(&self, source: Option<&str>, labels: &[(String, String)]) -> Result<Vec<IntentResponse>> {
+        let url = format!("{}/api/v1/intents", self.base_url);
+        let mut request = self.http_client.get(&url);
+        if let Some(source) = source {
+            request = request.query(&[("source", source)]);
+        }
+        for (key, value) in labels {
+            request = request.query(&[("label", format!("{}={}", key, value))]);
+        }
+        
+        request
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// List the planner actions the node can use.
+    pub async fn list_actions(&self) -> Result<ActionCatalog> {
+        let url = format!("{}/api/v1/planner/actions", self.base_url);
+        
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the capabilities the node offers, to check an intent's
+    /// requirements before submitting it.
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        let url = format!("{}/api/v1/capabilities", self.base_url);
+        
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the JSON Schema of a wire type by name, e.g. `intent` or
+    /// `submit_intent_request`, for generating clients or validating
+    /// documents.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for a name the node has no
+    /// schema for.
+    pub async fn fetch_schema(&self, name: &str) -> Result<serde_json::Value> {
+        let url = format!("{}/api/v1/schema/{}", self.base_url, name);
+
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Schema".to_string(),
+                id: name.to_string(),
+            });
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+
+    /// Stream every intent matching `filter`, one record at a time.
+    pub async fn export_intents(
+        &self,
+        filter: &ExportFilter,
+    ) -> Result<impl Stream<Item = Result<IntentRecordExport>>> {
+        Ok(ndjson_records(self.export("intents", filter).await?))
+    }
+    
+    /// Stream every stored artifact matching `filter`, one at a time.
+    pub async fn export_artifacts(
+        &self,
+        filter: &ExportFilter,
+    ) -> Result<impl Stream<Item = Result<ExecutionArtifact>>> {
+        Ok(ndjson_records(self.export("artifacts", filter).await?))
+    }
+    
+    async fn export(&self, records: &str, filter: &ExportFilter) -> Result<reqwest::Response> {
+        let url = format!("{}/api/v1/export/{}", self.base_url, records);
+        
+        self.http_client
+            .get(&url)
+            .query(filter)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))
+    }
+    
     /// Get the plan for an intent.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for an unknown intent and
+    /// [`OrpheonError::NotReady`] while the intent has no plan yet.
     pub async fn get_plan(&self, intent_id: Uuid) -> Result<Plan> {
         let url = format!("{}/api/v1/intent/{}/plan", self.base_url, intent_id);
         
@@ -156,10 +671,281 @@ impl OrpheonClient {
         
         if response.status().as_u16() == 404 {
             return Err(OrpheonError::NotFound {
-                resource_type: "Plan".to_string(),
+                resource_type: "Intent".to_string(),
                 id: intent_id.to_string(),
             });
         }
+        if response.status().as_u16() == 409 {
+            return Err(not_ready(response, "Plan", intent_id).await);
+        }
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the execution artifact for an intent.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for an unknown intent and
+    /// [`OrpheonError::NotReady`] while the intent has no artifact yet.
+    pub async fn get_artifact(&self, intent_id: Uuid) -> Result<ExecutionArtifact> {
+        let url = format!("{}/api/v1/intent/{}/artifact", self.base_url, intent_id);
+        
+        let response = self.artifact_request(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Intent".to_string(),
+                id: intent_id.to_string(),
+            });
+        }
+        if response.status().as_u16() == 409 {
+            return Err(not_ready(response, "Artifact", intent_id).await);
+        }
+        
+        decode_body(response).await
+    }
+    
+    /// Get the execution artifact for an intent with the annotations
+    /// operators attached to it, only those labelled `label` if given.
+    ///
+    /// Fails like [`get_artifact`](Self::get_artifact).
+    pub async fn get_annotated_artifact(&self, intent_id: Uuid, label: Option<&str>) -> Result<Annotated<ExecutionArtifact>> {
+        let url = format!("{}/api/v1/intent/{}/artifact", self.base_url, intent_id);
+        let mut request = self.artifact_request(&url);
+        if let Some(label) = label {
+            request = request.query(&[("label", label)]);
+        }
+        
+        let response = request
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Intent".to_string(),
+                id: intent_id.to_string(),
+            });
+        }
+        if response.status().as_u16() == 409 {
+            return Err(not_ready(response, "Artifact", intent_id).await);
+        }
+        
+        decode_body(response).await
+    }
+    
+    /// A GET of an artifact, asking for CBOR if the client prefers it.
+    fn artifact_request(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.http_client.get(url);
+        if self.prefer_cbor {
+            return request.header(ACCEPT, codec::CBOR_CONTENT_TYPE);
+        }
+        request
+    }
+    
+    /// Get the plan for an intent with the annotations operators attached
+    /// to it, only those labelled `label` if given.
+    ///
+    /// Fails like [`get_plan`](Self::get_plan).
+    pub async fn get_annotated_plan(&self, intent_id: Uuid, label: Option<&str>) -> Result<Annotated<Plan>> {
+        let url = format!("{}/api/v1/intent/{}/plan", self.base_url, intent_id);
+        let mut request = self.http_client.get(&url);
+        if let Some(label) = label {
+            request = request.query(&[("label", label)]);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Intent".to_string(),
+                id: intent_id.to_string(),
+            });
+        }
+        if response.status().as_u16() == 409 {
+            return Err(not_ready(response, "Plan", intent_id).await);
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+
+    /// Attach a note to a plan, or to one of its steps.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for an unknown plan.
+    pub async fn annotate_plan(&self, plan_id: Uuid, annotation: &NewAnnotation) -> Result<Annotation> {
+        let url = format!("{}/api/v1/plan/{}/annotations", self.base_url, plan_id);
+        self.annotate(&url, annotation, "Plan", plan_id).await
+    }
+    
+    /// Attach a note to an intent's execution artifact, or to one of its
+    /// steps.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for an unknown intent or one
+    /// without an artifact.
+    pub async fn annotate_artifact(&self, intent_id: Uuid, annotation: &NewAnnotation) -> Result<Annotation> {
+        let url = format!("{}/api/v1/intent/{}/artifact/annotations", self.base_url, intent_id);
+        self.annotate(&url, annotation, "Artifact", intent_id).await
+    }
+    
+    async fn annotate(&self, url: &str, annotation: &NewAnnotation, resource_type: &str, id: Uuid) -> Result<Annotation> {
+        let response = self.http_client
+            .post(url)
+            .json(annotation)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound { resource_type: resource_type.to_string(), id: id.to_string() });
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(OrpheonError::Internal(format!("Failed to add annotation: {}", error_text)));
+        }
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the receipt for the proposal accepted for a negotiated intent.
+    ///
+    /// Fails with [`OrpheonError::NotFound`] for an unknown intent or one
+    /// without an accepted proposal, and [`OrpheonError::NotReady`] while
+    /// its negotiation is still open.
+    pub async fn get_receipt(&self, intent_id: Uuid) -> Result<AcceptanceReceipt> {
+        let url = format!("{}/api/v1/intent/{}/receipt", self.base_url, intent_id);
+        
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Receipt".to_string(),
+                id: intent_id.to_string(),
+            });
+        }
+        if response.status().as_u16() == 409 {
+            return Err(not_ready(response, "Receipt", intent_id).await);
+        }
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the receipt and artifact of a negotiated intent and check them
+    /// against each other with [`verify_receipt`].
+    ///
+    /// The signature is checked against the key set with
+    /// [`OrpheonClient::with_node_public_key`]. Fails if any check fails.
+    pub async fn get_verified_receipt(&self, intent_id: Uuid) -> Result<(AcceptanceReceipt, VerificationReport)> {
+        let receipt = self.get_receipt(intent_id).await?;
+        let artifact = self.get_artifact(intent_id).await?;
+        let report = verify_receipt(&receipt, &artifact, self.node_public_key.as_deref());
+        
+        if !report.is_valid() {
+            let failures: Vec<String> = report
+                .failures()
+                .iter()
+                .map(|c| format!("{}: {}", c.kind, c.detail))
+                .collect();
+            return Err(OrpheonError::CryptoError(format!(
+                "receipt {} failed verification: {}",
+                receipt.id,
+                failures.join("; ")
+            )));
+        }
+        
+        Ok((receipt, report))
+    }
+    
+    /// Get the full data of an execution event whose payload was truncated.
+    pub async fn get_event_blob(&self, event_id: Uuid) -> Result<serde_json::Value> {
+        let url = format!("{}/api/v1/events/{}/blob", self.base_url, event_id);
+        
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Event blob".to_string(),
+                id: event_id.to_string(),
+            });
+        }
+        
+        response
+            .json()
+            .await
+            .map_err(|e| OrpheonError::SerializationError(e.to_string()))
+    }
+    
+    /// Get the execution artifact for an intent and verify it.
+    ///
+    /// The signature is checked against the key set with
+    /// [`OrpheonClient::with_node_public_key`]. Fails if any check fails;
+    /// warnings are returned in the report.
+    pub async fn get_verified_artifact(&self, intent_id: Uuid) -> Result<(ExecutionArtifact, VerificationReport)> {
+        let artifact = self.get_artifact(intent_id).await?;
+        let report = verify_artifact(&artifact, self.node_public_key.as_deref());
+        
+        if !report.is_valid() {
+            let failures: Vec<String> = report
+                .failures()
+                .iter()
+                .map(|c| format!("{}: {}", c.kind, c.detail))
+                .collect();
+            return Err(OrpheonError::CryptoError(format!(
+                "artifact {} failed verification: {}",
+                artifact.id,
+                failures.join("; ")
+            )));
+        }
+        
+        Ok((artifact, report))
+    }
+    
+    /// Download the signed artifact bundle for a finished intent.
+    ///
+    /// Call [`ArtifactBundle::verify`] to check it offline.
+    pub async fn get_artifact_bundle(&self, intent_id: Uuid) -> Result<ArtifactBundle> {
+        let url = format!("{}/api/v1/intent/{}/artifact/bundle", self.base_url, intent_id);
+        
+        let response = self.http_client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        if response.status().as_u16() == 404 {
+            return Err(OrpheonError::NotFound {
+                resource_type: "Artifact".to_string(),
+                id: intent_id.to_string(),
+            });
+        }
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(OrpheonError::Internal(format!("Failed to fetch artifact bundle: {}", error_text)));
+        }
         
         response
             .json()
@@ -185,6 +971,43 @@ impl OrpheonClient {
         Ok(())
     }
     
+    /// Pause an executing intent: the steps already running finish, but no
+    /// more start until it is resumed.
+    ///
+    /// Fails with [`OrpheonError::InvalidTransition`] unless the intent is
+    /// executing.
+    pub async fn pause(&self, id: Uuid) -> Result<()> {
+        self.change_execution(id, "pause").await
+    }
+    
+    /// Resume a paused intent.
+    ///
+    /// Fails with [`OrpheonError::InvalidTransition`] unless the intent is
+    /// paused.
+    pub async fn resume(&self, id: Uuid) -> Result<()> {
+        self.change_execution(id, "resume").await
+    }
+    
+    async fn change_execution(&self, id: Uuid, action: &str) -> Result<()> {
+        let url = format!("{}/api/v1/intent/{}/{}", self.base_url, id, action);
+        
+        let response = self.http_client
+            .post(&url)
+            .send()
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        match response.status().as_u16() {
+            404 => Err(OrpheonError::NotFound { resource_type: "Intent".to_string(), id: id.to_string() }),
+            409 => Err(invalid_transition(response, action, id).await),
+            _ if !response.status().is_success() => {
+                let error_text = response.text().await.unwrap_or_default();
+                Err(OrpheonError::Internal(format!("Failed to {} intent: {}", action, error_text)))
+            }
+            _ => Ok(()),
+        }
+    }
+    
     /// Simulate an intent without executing.
     pub async fn simulate(&self, intent: Intent) -> Result<SimulationResult> {
         let url = format!("{}/api/v1/simulate", self.base_url);
@@ -194,7 +1017,7 @@ impl OrpheonClient {
             "constraints": [],
             "preferences": [],
             "budget": {
-                "max_cost": intent.budget.max_cost,
+                "max_cost": intent.budget.cost_limit(),
                 "max_duration_ms": intent.budget.max_duration_ms,
             }
         });
@@ -213,6 +1036,47 @@ impl OrpheonClient {
     }
 }
 
+/// Decode a response body from CBOR if the node sent that, else from JSON.
+async fn decode_body<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let cbor = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|t| t.starts_with(codec::CBOR_CONTENT_TYPE));
+    if !cbor {
+        return response.json().await.map_err(|e| OrpheonError::SerializationError(e.to_string()));
+    }
+    let bytes = response.bytes().await.map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+    codec::from_cbor(&bytes)
+}
+
+/// The error for a `409` on a resource of an intent that exists but has
+/// not produced it yet.
+async fn not_ready(response: reqwest::Response, resource_type: &str, intent_id: Uuid) -> OrpheonError {
+    match conflict_status(response).await {
+        Ok(status) => OrpheonError::NotReady { intent_id, resource_type: resource_type.to_string(), status },
+        Err(e) => e,
+    }
+}
+
+/// The error for a `409` on an action the intent's status does not allow.
+async fn invalid_transition(response: reqwest::Response, action: &str, intent_id: Uuid) -> OrpheonError {
+    match conflict_status(response).await {
+        Ok(status) => OrpheonError::InvalidTransition { intent_id, action: action.to_string(), status },
+        Err(e) => e,
+    }
+}
+
+/// The intent status reported in a `409` error body.
+async fn conflict_status(response: reqwest::Response) -> Result<IntentStatus> {
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+    serde_json::from_value(body["error"]["intent_status"].clone())
+        .map_err(|_| OrpheonError::Internal(format!("Unexpected conflict: {}", body["error"]["message"])))
+}
+
 /// Result of a simulation.
 #[derive(Debug, Deserialize)]
 pub struct SimulationResult {
@@ -220,7 +1084,37 @@ pub struct SimulationResult {
     pub success: bool,
     pub estimated_cost: f64,
     pub estimated_duration_ms: u64,
+    #[serde(default)]
+    pub estimated_work_ms: u64,
     pub confidence_score: f32,
     pub warnings: Vec<String>,
     pub error: Option<String>,
 }
+
+/// WebSocket URL for an HTTP(S) base URL: `https://gw.example.com/orpheon`
+/// becomes `wss://gw.example.com/orpheon`.
+fn derive_ws_base_url(base_url: &str) -> String {
+    if let Some(rest) = base_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = base_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        base_url.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_base_url_keeps_path_prefix() {
+        assert_eq!(derive_ws_base_url("https://gw.example.com/orpheon"), "wss://gw.example.com/orpheon");
+        assert_eq!(derive_ws_base_url("http://127.0.0.1:3000"), "ws://127.0.0.1:3000");
+        // Only the scheme is swapped
+        assert_eq!(
+            derive_ws_base_url("http://gw.example.com/proxy/http://node"),
+            "ws://gw.example.com/proxy/http://node"
+        );
+    }
+}
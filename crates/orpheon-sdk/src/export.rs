@@ -0,0 +1,91 @@
+//! Bulk exports of intents and artifacts as newline-delimited JSON.
+//!
+//! The node streams one record per line, so an export of any size is read
+//! record by record without holding the whole response in memory.
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use orpheon_core::{Intent, IntentStatus, OrpheonError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Which records an export includes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportFilter {
+    /// Also export intents that have not finished yet; by default only
+    /// terminal intents are exported.
+    #[serde(default)]
+    pub include_active: bool,
+
+    /// Only records created after this time: intents by their creation
+    /// time, artifacts by when they were produced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+}
+
+impl ExportFilter {
+    /// Include intents that are still being processed.
+    pub fn with_active(mut self) -> Self {
+        self.include_active = true;
+        self
+    }
+
+    /// Only records created after `time`.
+    pub fn created_after(mut self, time: DateTime<Utc>) -> Self {
+        self.created_after = Some(time);
+        self
+    }
+}
+
+/// One exported intent with what the node knows about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntentRecordExport {
+    pub intent: Intent,
+    pub status: IntentStatus,
+
+    /// Plan revisions, oldest first.
+    pub plan_ids: Vec<Uuid>,
+    pub artifact_id: Option<Uuid>,
+    pub error: Option<String>,
+
+    /// Percentage of steps that succeeded, for partially complete intents.
+    pub success_rate: Option<u8>,
+    pub received_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Records of an NDJSON response body, parsed as they arrive.
+pub(crate) fn ndjson_records<T: DeserializeOwned>(response: reqwest::Response) -> impl Stream<Item = Result<T>> {
+    let chunks = response.bytes_stream().boxed();
+    futures::stream::unfold((chunks, Vec::new(), false), |(mut chunks, mut buffer, mut done)| async move {
+        loop {
+            if let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let record = serde_json::from_slice(&line).map_err(|e| OrpheonError::SerializationError(e.to_string()));
+                return Some((record, (chunks, buffer, done)));
+            }
+            if done {
+                // A last record without a trailing newline
+                if buffer.iter().all(u8::is_ascii_whitespace) {
+                    return None;
+                }
+                let record = serde_json::from_slice(&std::mem::take(&mut buffer))
+                    .map_err(|e| OrpheonError::SerializationError(e.to_string()));
+                return Some((record, (chunks, buffer, done)));
+            }
+            match chunks.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    done = true;
+                    buffer.clear();
+                    return Some((Err(OrpheonError::ConnectionError(e.to_string())), (chunks, buffer, done)));
+                }
+                None => done = true,
+            }
+        }
+    })
+}
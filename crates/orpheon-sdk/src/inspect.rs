@@ -0,0 +1,210 @@
+//! Client-side inspection of plans.
+//!
+//! [`PlanSummaryExt`] answers the usual questions about a plan received
+//! from [`get_plan`](crate::OrpheonClient::get_plan) or a proposal before
+//! accepting it: what it costs, what it runs, and whether it fits the
+//! intent's budget.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use orpheon_core::{Budget, Plan, Step};
+use uuid::Uuid;
+
+/// Summaries of a [`Plan`].
+pub trait PlanSummaryExt {
+    /// Sum of the steps' estimated costs.
+    fn total_cost(&self) -> f64;
+
+    /// Number of steps per action.
+    fn steps_by_action(&self) -> BTreeMap<String, usize>;
+
+    /// Number of steps on the longest dependency chain.
+    fn max_depth(&self) -> usize;
+
+    /// The step with the longest estimated duration, the first one on a tie.
+    fn longest_step(&self) -> Option<&Step>;
+
+    /// Ways the plan exceeds `budget`, one message each; empty if it fits.
+    ///
+    /// Duration is checked against the plan's critical path.
+    fn violates_budget(&self, budget: &Budget) -> Vec<String>;
+
+    /// The steps as an aligned text table, followed by a totals line.
+    fn render_table(&self) -> String;
+}
+
+impl PlanSummaryExt for Plan {
+    fn total_cost(&self) -> f64 {
+        self.steps.iter().map(|s| s.estimated_cost).sum()
+    }
+
+    fn steps_by_action(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for step in &self.steps {
+            *counts.entry(step.action.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    fn max_depth(&self) -> usize {
+        let mut depths: HashMap<Uuid, usize> = HashMap::new();
+        for step in self.topological_sort() {
+            let depth = 1 + step.dependencies.iter().filter_map(|d| depths.get(d)).max().unwrap_or(&0);
+            depths.insert(step.id, depth);
+        }
+        depths.into_values().max().unwrap_or(0)
+    }
+
+    fn longest_step(&self) -> Option<&Step> {
+        self.steps
+            .iter()
+            .reduce(|longest, step| if step.estimated_duration_ms > longest.estimated_duration_ms { step } else { longest })
+    }
+
+    fn violates_budget(&self, budget: &Budget) -> Vec<String> {
+        let mut violations = Vec::new();
+        let cost = self.total_cost();
+        if let Some(max_cost) = budget.cost_limit().filter(|max| cost > *max) {
+            violations.push(format!(
+                "estimated cost {:.2} exceeds the budget of {:.2} {}",
+                cost, max_cost, budget.currency
+            ));
+        }
+        let duration_ms = self.critical_path_ms(&HashSet::new());
+        if let Some(max_duration_ms) = budget.max_duration_ms.filter(|max| duration_ms > *max) {
+            violations.push(format!(
+                "estimated duration {} ms exceeds the budget of {} ms",
+                duration_ms, max_duration_ms
+            ));
+        }
+        violations
+    }
+
+    fn render_table(&self) -> String {
+        const HEADER: [&str; 6] = ["#", "STEP", "ACTION", "DEPENDS", "DURATION_MS", "COST"];
+        // Numbers are right-aligned
+        const RIGHT: [bool; 6] = [true, false, false, false, true, true];
+
+        let index: HashMap<Uuid, usize> = self.steps.iter().enumerate().map(|(i, s)| (s.id, i + 1)).collect();
+        let rows: Vec<[String; 6]> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                let depends: Vec<String> =
+                    step.dependencies.iter().filter_map(|d| index.get(d)).map(|i| i.to_string()).collect();
+                [
+                    (i + 1).to_string(),
+                    step.name.clone(),
+                    step.action.clone(),
+                    if depends.is_empty() { "-".to_string() } else { depends.join(",") },
+                    step.estimated_duration_ms.to_string(),
+                    format!("{:.2}", step.estimated_cost),
+                ]
+            })
+            .collect();
+
+        let mut widths = HEADER.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        let header = HEADER.map(str::to_string);
+        for row in std::iter::once(&header).chain(&rows) {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .zip(RIGHT)
+                .map(|((cell, width), right)| if right { format!("{:>width$}", cell) } else { format!("{:<width$}", cell) })
+                .collect();
+            table.push_str(cells.join("  ").trim_end());
+            table.push('\n');
+        }
+        table.push_str(&format!(
+            "{} step(s), total cost {:.2}, critical path {} ms\n",
+            self.steps.len(),
+            self.total_cost(),
+            self.critical_path_ms(&HashSet::new())
+        ));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::PlanningStrategy;
+
+    /// Provision, then configure and seed in parallel, then verify.
+    fn fixture() -> Plan {
+        let mut plan = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        let provision = Step::new("provision", "provision_vm").with_duration(1_200).with_cost(4.0);
+        let configure = Step::new("configure", "configure_vm")
+            .with_duration(300)
+            .with_cost(1.5)
+            .depends_on(provision.id);
+        let seed = Step::new("seed-data", "run_script").with_duration(900).with_cost(0.5).depends_on(provision.id);
+        let verify = Step::new("verify", "run_script")
+            .with_duration(100)
+            .with_cost(0.25)
+            .depends_on(configure.id)
+            .depends_on(seed.id);
+        for step in [provision, configure, seed, verify] {
+            plan.add_step(step);
+        }
+        plan
+    }
+
+    #[test]
+    fn test_totals_and_counts() {
+        let plan = fixture();
+        assert_eq!(plan.total_cost(), 6.25);
+        let counts = plan.steps_by_action();
+        assert_eq!(counts["run_script"], 2);
+        assert_eq!(counts["provision_vm"], 1);
+        assert_eq!(counts.len(), 3);
+    }
+
+    #[test]
+    fn test_max_depth_and_longest_step() {
+        let plan = fixture();
+        assert_eq!(plan.max_depth(), 3);
+        assert_eq!(plan.longest_step().unwrap().name, "provision");
+
+        let empty = Plan::new(Uuid::new_v4(), PlanningStrategy::Heuristic);
+        assert_eq!(empty.max_depth(), 0);
+        assert!(empty.longest_step().is_none());
+    }
+
+    #[test]
+    fn test_violates_budget() {
+        let plan = fixture();
+        assert!(plan.violates_budget(&Budget::usd(10.0).with_duration(5_000)).is_empty());
+
+        // The critical path is 1200 + 900 + 100 ms
+        let violations = plan.violates_budget(&Budget::usd(5.0).with_duration(2_000));
+        assert_eq!(
+            violations,
+            vec![
+                "estimated cost 6.25 exceeds the budget of 5.00 USD".to_string(),
+                "estimated duration 2200 ms exceeds the budget of 2000 ms".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_table() {
+        let expected = "\
+#  STEP       ACTION        DEPENDS  DURATION_MS  COST
+1  provision  provision_vm  -               1200  4.00
+2  configure  configure_vm  1                300  1.50
+3  seed-data  run_script    1                900  0.50
+4  verify     run_script    2,3              100  0.25
+4 step(s), total cost 6.25, critical path 2200 ms
+";
+        assert_eq!(fixture().render_table(), expected);
+    }
+}
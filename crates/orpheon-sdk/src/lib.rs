@@ -2,15 +2,32 @@
 //!
 //! Client SDK for interacting with Orpheon nodes.
 
+pub mod annotation;
 pub mod client;
+pub mod export;
+pub mod inspect;
+pub mod negotiation;
 pub mod stream;
+pub mod verify;
 
-pub use client::OrpheonClient;
-pub use stream::EventStream;
+pub use annotation::{Annotated, Annotation, NewAnnotation};
+pub use client::{Completion, OrpheonClient, SubmitResult};
+pub use export::{ExportFilter, IntentRecordExport};
+pub use inspect::PlanSummaryExt;
+pub use negotiation::Negotiation;
+pub use orpheon_core::ArtifactBundle;
+pub use orpheon_negotiate::{CounterOffer, NegotiationOptions, PreferenceAdjustment};
+pub use stream::{Event, EventStream, MultiEventStream, WatchFilter};
+pub use orpheon_negotiate::AcceptanceReceipt;
+pub use verify::{verify_artifact, verify_receipt, VerificationReport};
 
 /// Prelude module for common imports.
 pub mod prelude {
-    pub use crate::client::OrpheonClient;
-    pub use crate::stream::EventStream;
+    pub use crate::annotation::{Annotated, Annotation, NewAnnotation};
+    pub use crate::client::{Completion, OrpheonClient, SubmitResult};
+    pub use crate::inspect::PlanSummaryExt;
+    pub use crate::negotiation::Negotiation;
+    pub use crate::stream::{Event, EventStream, MultiEventStream, WatchFilter};
+    pub use crate::verify::{verify_artifact, verify_receipt, VerificationReport};
     pub use orpheon_core::prelude::*;
 }
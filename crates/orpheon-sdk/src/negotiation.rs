@@ -0,0 +1,232 @@
+//! Client side of plan negotiation.
+//!
+//! [`Negotiation`] drives the node's `/ws/negotiate/:id` stream for an
+//! intent submitted with negotiation options. Connecting performs the
+//! protocol handshake (see [`orpheon_negotiate::handshake`]), so
+//! [`Negotiation::features`] tells what the node agreed to before any
+//! proposal is read.
+
+use std::collections::BTreeSet;
+
+use futures::{SinkExt, StreamExt};
+use orpheon_core::{OrpheonError, Result, WsCloseReason};
+use orpheon_negotiate::handshake::{self, COUNTER_OFFERS};
+use orpheon_negotiate::{Agreement, CounterOffer, NegotiationMessage, Proposal};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+/// An open negotiation with a node.
+pub struct Negotiation {
+    intent_id: Uuid,
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    agreement: Agreement,
+    /// Message read during the handshake from a node that did not say hello.
+    pending: Option<NegotiationMessage>,
+}
+
+impl Negotiation {
+    /// Connect to the negotiation stream for an intent and agree on a
+    /// protocol with the node.
+    ///
+    /// A node whose protocol version is too old is an
+    /// [`OrpheonError::UnsupportedProtocol`] error.
+    pub async fn connect(ws_url: &str, intent_id: Uuid) -> Result<Self> {
+        let (mut socket, _) = connect_async(ws_url)
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+
+        let text = loop {
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => break text,
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err(OrpheonError::ConnectionError("negotiation closed before the handshake".to_string()));
+                }
+                Some(Err(e)) => return Err(OrpheonError::ConnectionError(e.to_string())),
+                Some(Ok(_)) => {}
+            }
+        };
+
+        let (agreement, pending) = match serde_json::from_str(&text) {
+            Ok(NegotiationMessage::Hello { protocol_version, supported_features }) => {
+                match Agreement::with_peer(protocol_version, &supported_features) {
+                    Ok(agreement) => {
+                        send(&mut socket, &handshake::hello()).await?;
+                        (agreement, None)
+                    }
+                    Err(e) => {
+                        let reason = WsCloseReason::ProtocolError;
+                        let close = CloseFrame { code: CloseCode::from(reason.close_code()), reason: reason.as_str().into() };
+                        let _ = socket.close(Some(close)).await;
+                        return Err(e);
+                    }
+                }
+            }
+            // A node from before the handshake; its first frame may already matter
+            Ok(message) => (Agreement::legacy(), Some(message)),
+            Err(_) => (Agreement::legacy(), None),
+        };
+
+        Ok(Self { intent_id, socket, agreement, pending })
+    }
+
+    /// The intent being negotiated.
+    pub fn intent_id(&self) -> Uuid {
+        self.intent_id
+    }
+
+    /// Protocol version both sides speak.
+    pub fn protocol_version(&self) -> u32 {
+        self.agreement.protocol_version
+    }
+
+    /// Features both sides agreed to, e.g. `counter_offers`.
+    pub fn features(&self) -> &BTreeSet<String> {
+        &self.agreement.features
+    }
+
+    /// Next message from the node, or `None` once the node closes the
+    /// negotiation.
+    ///
+    /// A failed negotiation is an [`OrpheonError::NegotiationRejected`]
+    /// error, and an error the node reports about a message this client
+    /// sent is an [`OrpheonError::Internal`] error.
+    pub async fn next(&mut self) -> Result<Option<NegotiationMessage>> {
+        if let Some(message) = self.pending.take() {
+            return self.received(message).map(Some);
+        }
+        loop {
+            let text = match self.socket.next().await {
+                Some(Ok(Message::Text(text))) => text,
+                Some(Ok(Message::Close(_))) | None => return Ok(None),
+                Some(Err(e)) => return Err(OrpheonError::ConnectionError(e.to_string())),
+                Some(Ok(_)) => continue,
+            };
+            let value: serde_json::Value =
+                serde_json::from_str(&text).map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+            if value["type"] == "error" {
+                let message = value["message"].as_str().unwrap_or("unknown error");
+                return Err(OrpheonError::Internal(format!("Negotiation error: {}", message)));
+            }
+            // Frames this client does not know are left for newer clients
+            let Ok(message) = serde_json::from_value::<NegotiationMessage>(value) else {
+                continue;
+            };
+            match message {
+                NegotiationMessage::Hello { .. } | NegotiationMessage::Ping { .. } | NegotiationMessage::Pong { .. } => {}
+                message => return self.received(message).map(Some),
+            }
+        }
+    }
+
+    /// Next proposal from the node, skipping other messages, or `None` once
+    /// the node closes the negotiation.
+    pub async fn next_proposal(&mut self) -> Result<Option<Proposal>> {
+        while let Some(message) = self.next().await? {
+            if let NegotiationMessage::Offer(proposal) = message {
+                return Ok(Some(*proposal));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Accept a proposal; the node answers with
+    /// [`NegotiationMessage::Confirmed`].
+    pub async fn accept(&mut self, proposal_id: Uuid) -> Result<()> {
+        send(&mut self.socket, &NegotiationMessage::Accept { proposal_id }).await
+    }
+
+    /// Counter a proposal; the node answers with a new offer.
+    ///
+    /// Fails without sending anything if the node did not agree to
+    /// counter-offers.
+    pub async fn counter(&mut self, counter: CounterOffer) -> Result<()> {
+        if !self.agreement.supports(COUNTER_OFFERS) {
+            return Err(OrpheonError::Internal(format!(
+                "The node did not agree to the {} feature",
+                COUNTER_OFFERS
+            )));
+        }
+        send(&mut self.socket, &NegotiationMessage::Counter(counter)).await
+    }
+
+    /// Reject a proposal, ending the negotiation.
+    pub async fn reject(&mut self, proposal_id: Uuid, reason: impl Into<String>) -> Result<()> {
+        send(&mut self.socket, &NegotiationMessage::Reject { proposal_id, reason: reason.into() }).await
+    }
+
+    fn received(&self, message: NegotiationMessage) -> Result<NegotiationMessage> {
+        match message {
+            NegotiationMessage::Failed { reason } => Err(OrpheonError::NegotiationRejected { intent_id: self.intent_id, reason }),
+            message => Ok(message),
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocketStream<MaybeTlsStream<TcpStream>>, message: &NegotiationMessage) -> Result<()> {
+    let json = serde_json::to_string(message).map_err(|e| OrpheonError::SerializationError(e.to_string()))?;
+    socket
+        .send(Message::Text(json))
+        .await
+        .map_err(|e| OrpheonError::ConnectionError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orpheon_core::{Plan, PlanningStrategy};
+    use tokio::net::TcpListener;
+
+    /// Serve one connection that sends `frames`, then reports the first
+    /// frame the client sent back.
+    async fn shim(frames: Vec<serde_json::Value>) -> (String, tokio::task::JoinHandle<Option<Message>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            for frame in frames {
+                ws.send(Message::Text(frame.to_string())).await.unwrap();
+            }
+            ws.next().await.and_then(|m| m.ok())
+        });
+        (format!("ws://{}", addr), server)
+    }
+
+    #[tokio::test]
+    async fn test_node_without_hello_is_legacy() {
+        let intent_id = Uuid::new_v4();
+        let proposal = Proposal::new(intent_id, Plan::new(intent_id, PlanningStrategy::Heuristic)).unwrap();
+        let (url, server) = shim(vec![
+            serde_json::json!({ "type": "connected", "intent_id": intent_id }),
+            serde_json::to_value(NegotiationMessage::Offer(Box::new(proposal.clone()))).unwrap(),
+        ])
+        .await;
+
+        let mut negotiation = Negotiation::connect(&url, intent_id).await.unwrap();
+        assert_eq!(negotiation.protocol_version(), 1);
+        assert_eq!(negotiation.features(), &Agreement::legacy().features);
+        assert_eq!(negotiation.next_proposal().await.unwrap().unwrap().id, proposal.id);
+
+        // No hello is sent to a legacy node
+        negotiation.accept(proposal.id).await.unwrap();
+        let Some(Message::Text(text)) = server.await.unwrap() else { panic!("client sent nothing") };
+        assert!(matches!(serde_json::from_str(&text).unwrap(), NegotiationMessage::Accept { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_node_is_refused() {
+        let hello = NegotiationMessage::Hello { protocol_version: 0, supported_features: vec![] };
+        let (url, server) = shim(vec![serde_json::to_value(hello).unwrap()]).await;
+
+        let err = Negotiation::connect(&url, Uuid::new_v4()).await.err().unwrap();
+        assert!(matches!(err, OrpheonError::UnsupportedProtocol { version: 0, .. }), "{:?}", err);
+        match server.await.unwrap() {
+            Some(Message::Close(Some(frame))) => assert_eq!(frame.code, CloseCode::Protocol),
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    }
+}
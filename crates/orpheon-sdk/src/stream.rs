@@ -1,8 +1,11 @@
 //! Event stream for real-time updates.
 
-use futures::StreamExt;
-use orpheon_core::{ExecutionArtifact, OrpheonError, Result};
-use serde::Deserialize;
+use std::collections::HashMap;
+
+use futures::{SinkExt, StreamExt};
+use orpheon_core::{OrpheonError, Result, WsCloseReason};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use uuid::Uuid;
 
@@ -21,20 +24,52 @@ pub enum Event {
         step_name: String,
         progress: f32,
     },
+    /// Execution was paused; it continues with a status update once resumed.
+    Paused {
+        plan_id: Option<Uuid>,
+    },
     /// Execution completed.
     Complete {
         artifact_id: Uuid,
     },
+    /// Execution finished but some optional steps failed.
+    PartialComplete {
+        artifact_id: Uuid,
+        /// Percentage of steps that succeeded.
+        success_rate: u8,
+    },
     /// Status update.
     StatusUpdate {
         status: String,
         plan_id: Option<Uuid>,
         artifact_id: Option<Uuid>,
     },
+    /// The node warned about the intent, e.g. `deadline_at_risk` when it
+    /// is likely to miss its deadline.
+    Warning {
+        code: String,
+        message: String,
+    },
+    /// A notice about the node itself, such as `backlog_age` when an
+    /// intent has waited too long; multiplexed streams only.
+    SystemNotice {
+        code: String,
+        message: String,
+    },
     /// An error occurred.
     Error {
         message: String,
     },
+    /// Status updates were missed: the next update received had seq
+    /// `received` instead of `expected`. Re-read the intent to catch up.
+    GapDetected {
+        expected: u64,
+        received: u64,
+    },
+    /// The node closed the stream; no events follow.
+    Closed {
+        reason: WsCloseReason,
+    },
 }
 
 /// WebSocket message from server.
@@ -43,20 +78,101 @@ pub enum Event {
 enum WsMessage {
     StatusUpdate {
         intent_id: Uuid,
+        #[serde(default)]
+        seq: Option<u64>,
         status: String,
         plan_id: Option<Uuid>,
         artifact_id: Option<Uuid>,
+        #[serde(default)]
+        success_rate: Option<u8>,
+    },
+    Warning {
+        intent_id: Uuid,
+        code: String,
+        message: String,
+    },
+    SystemNotice {
+        code: String,
+        message: String,
     },
     Error {
+        #[serde(default)]
+        intent_id: Option<Uuid>,
         message: String,
     },
     Ping,
 }
 
+/// A client event with the intent and seq it came with.
+struct Received {
+    intent_id: Option<Uuid>,
+    seq: Option<u64>,
+    event: Event,
+}
+
+impl WsMessage {
+    /// Map a server message to the intent it concerns and a client event.
+    fn into_event(self) -> Option<Received> {
+        match self {
+            WsMessage::StatusUpdate { intent_id, seq, status, plan_id, artifact_id, success_rate } => {
+                let event = match artifact_id {
+                    Some(aid) if status == "complete" => Event::Complete { artifact_id: aid },
+                    Some(aid) if status == "partially_complete" => Event::PartialComplete {
+                        artifact_id: aid,
+                        success_rate: success_rate.unwrap_or_default(),
+                    },
+                    _ if status == "paused" => Event::Paused { plan_id },
+                    _ => Event::StatusUpdate { status, plan_id, artifact_id },
+                };
+                Some(Received { intent_id: Some(intent_id), seq, event })
+            }
+            WsMessage::Warning { intent_id, code, message } => {
+                Some(Received { intent_id: Some(intent_id), seq: None, event: Event::Warning { code, message } })
+            }
+            WsMessage::SystemNotice { code, message } => {
+                Some(Received { intent_id: None, seq: None, event: Event::SystemNotice { code, message } })
+            }
+            WsMessage::Error { intent_id, message } => Some(Received { intent_id, seq: None, event: Event::Error { message } }),
+            WsMessage::Ping => None,
+        }
+    }
+}
+
+/// The event for a close frame naming a [`WsCloseReason`].
+fn closed(frame: Option<CloseFrame<'_>>) -> Option<Received> {
+    let reason = WsCloseReason::from_name(&frame?.reason)?;
+    Some(Received { intent_id: None, seq: None, event: Event::Closed { reason } })
+}
+
+/// Last seq seen for one intent, for spotting missed updates.
+#[derive(Debug, Default, Clone, Copy)]
+struct SeqTracker {
+    last: Option<u64>,
+}
+
+impl SeqTracker {
+    /// Record a seq, returning a gap event if updates were skipped.
+    fn observe(&mut self, seq: u64) -> Option<Event> {
+        let gap = match self.last {
+            Some(last) if seq > last + 1 => Some(Event::GapDetected { expected: last + 1, received: seq }),
+            _ => None,
+        };
+        self.last = Some(self.last.map_or(seq, |last| last.max(seq)));
+        gap
+    }
+}
+
 /// Stream of events for an intent.
+///
+/// Status updates carry a per-intent seq; when one is skipped, the stream
+/// yields [`Event::GapDetected`] before the update that revealed the gap.
+/// When the node closes the stream it yields [`Event::Closed`] last.
 pub struct EventStream {
     intent_id: Uuid,
-    receiver: tokio::sync::mpsc::Receiver<Event>,
+    receiver: tokio::sync::mpsc::Receiver<Received>,
+    seq: SeqTracker,
+    /// Event held back while its gap event is delivered.
+    pending: Option<Event>,
     _handle: tokio::task::JoinHandle<()>,
 }
 
@@ -75,29 +191,23 @@ impl EventStream {
             while let Some(msg) = read.next().await {
                 match msg {
                     Ok(Message::Text(text)) => {
-                        if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                            let event = match ws_msg {
-                                WsMessage::StatusUpdate { status, plan_id, artifact_id, .. } => {
-                                    if status == "complete" {
-                                        if let Some(aid) = artifact_id {
-                                            Event::Complete { artifact_id: aid }
-                                        } else {
-                                            Event::StatusUpdate { status, plan_id, artifact_id }
-                                        }
-                                    } else {
-                                        Event::StatusUpdate { status, plan_id, artifact_id }
-                                    }
-                                }
-                                WsMessage::Error { message } => Event::Error { message },
-                                WsMessage::Ping => continue,
-                            };
-                            
-                            if tx.send(event).await.is_err() {
+                        let received = serde_json::from_str::<WsMessage>(&text)
+                            .ok()
+                            .and_then(WsMessage::into_event);
+                        
+                        if let Some(received) = received {
+                            if tx.send(received).await.is_err() {
                                 break;
                             }
                         }
                     }
-                    Ok(Message::Close(_)) | Err(_) => break,
+                    Ok(Message::Close(frame)) => {
+                        if let Some(received) = closed(frame) {
+                            let _ = tx.send(received).await;
+                        }
+                        break;
+                    }
+                    Err(_) => break,
                     _ => {}
                 }
             }
@@ -106,6 +216,8 @@ impl EventStream {
         Ok(Self {
             intent_id,
             receiver: rx,
+            seq: SeqTracker::default(),
+            pending: None,
             _handle: handle,
         })
     }
@@ -115,8 +227,258 @@ impl EventStream {
         self.intent_id
     }
     
+    /// Seq of the latest status update received, if any.
+    pub fn last_seq(&self) -> Option<u64> {
+        self.seq.last
+    }
+    
     /// Get the next event.
     pub async fn next(&mut self) -> Option<Event> {
-        self.receiver.recv().await
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        let received = self.receiver.recv().await?;
+        if let Some(gap) = received.seq.and_then(|seq| self.seq.observe(seq)) {
+            self.pending = Some(received.event);
+            return Some(gap);
+        }
+        Some(received.event)
+    }
+}
+
+/// Selection of intents to watch on a multiplexed stream.
+#[derive(Debug, Clone, Default)]
+pub struct WatchFilter {
+    /// Specific intents to watch.
+    pub intent_ids: Vec<Uuid>,
+    
+    /// Watch all intents of this kind.
+    pub kind: Option<String>,
+    
+    /// Watch all intents with this status ("active", "terminal" or a status name).
+    pub status: Option<String>,
+}
+
+impl WatchFilter {
+    /// Watch a fixed set of intents.
+    pub fn ids(intent_ids: Vec<Uuid>) -> Self {
+        Self {
+            intent_ids,
+            ..Default::default()
+        }
+    }
+    
+    /// Watch all intents of a kind.
+    pub fn kind(kind: impl Into<String>) -> Self {
+        Self {
+            kind: Some(kind.into()),
+            ..Default::default()
+        }
+    }
+    
+    /// Restrict to a status ("active", "terminal" or a status name).
+    pub fn with_status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+    
+    /// Subscription frames to send for this filter.
+    fn frames(&self) -> Vec<SubscriptionFrame> {
+        let mut frames = Vec::new();
+        if !self.intent_ids.is_empty() {
+            frames.push(SubscriptionFrame::Subscribe(self.intent_ids.clone()));
+        }
+        if self.kind.is_some() || self.status.is_some() {
+            frames.push(SubscriptionFrame::SubscribeFilter {
+                kind: self.kind.clone(),
+                status: self.status.clone(),
+            });
+        }
+        frames
+    }
+}
+
+/// Client frame on the multiplexed stream.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SubscriptionFrame {
+    Subscribe(Vec<Uuid>),
+    Unsubscribe(Vec<Uuid>),
+    SubscribeFilter {
+        kind: Option<String>,
+        status: Option<String>,
+    },
+}
+
+/// Stream of events for many intents over a single connection.
+///
+/// Each event is paired with the intent it concerns. Connection-level
+/// errors, [`Event::SystemNotice`] and [`Event::Closed`], which are not
+/// tied to an intent, are reported with a nil UUID.
+/// Missed updates are reported per intent, as on [`EventStream`].
+pub struct MultiEventStream {
+    receiver: tokio::sync::mpsc::Receiver<Received>,
+    seqs: HashMap<Uuid, SeqTracker>,
+    pending: Option<(Uuid, Event)>,
+    frames: tokio::sync::mpsc::Sender<SubscriptionFrame>,
+    _handle: tokio::task::JoinHandle<()>,
+}
+
+impl MultiEventStream {
+    /// Connect to the multiplexed intents stream and apply the filter.
+    pub async fn connect(ws_url: &str, filter: WatchFilter) -> Result<Self> {
+        let (ws_stream, _) = connect_async(ws_url)
+            .await
+            .map_err(|e| OrpheonError::ConnectionError(e.to_string()))?;
+        
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let (frame_tx, mut frame_rx) = tokio::sync::mpsc::channel::<SubscriptionFrame>(16);
+        
+        for frame in filter.frames() {
+            let _ = frame_tx.send(frame).await;
+        }
+        
+        let handle = tokio::spawn(async move {
+            let (mut write, mut read) = ws_stream.split();
+            
+            loop {
+                tokio::select! {
+                    frame = frame_rx.recv() => {
+                        let Some(frame) = frame else { break };
+                        let json = serde_json::to_string(&frame).unwrap_or_default();
+                        if write.send(Message::Text(json)).await.is_err() {
+                            break;
+                        }
+                    }
+                    msg = read.next() => {
+                        match msg {
+                            Some(Ok(Message::Text(text))) => {
+                                let received = serde_json::from_str::<WsMessage>(&text)
+                                    .ok()
+                                    .and_then(WsMessage::into_event);
+                                
+                                if let Some(received) = received {
+                                    if tx.send(received).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            }
+                            Some(Ok(Message::Close(frame))) => {
+                                if let Some(received) = closed(frame) {
+                                    let _ = tx.send(received).await;
+                                }
+                                break;
+                            }
+                            Some(Err(_)) | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        });
+        
+        Ok(Self {
+            receiver: rx,
+            seqs: HashMap::new(),
+            pending: None,
+            frames: frame_tx,
+            _handle: handle,
+        })
+    }
+    
+    /// Start watching additional intents.
+    pub async fn subscribe(&self, intent_ids: Vec<Uuid>) -> Result<()> {
+        self.send_frame(SubscriptionFrame::Subscribe(intent_ids)).await
+    }
+    
+    /// Stop watching intents.
+    pub async fn unsubscribe(&self, intent_ids: Vec<Uuid>) -> Result<()> {
+        self.send_frame(SubscriptionFrame::Unsubscribe(intent_ids)).await
+    }
+    
+    async fn send_frame(&self, frame: SubscriptionFrame) -> Result<()> {
+        self.frames
+            .send(frame)
+            .await
+            .map_err(|_| OrpheonError::ConnectionError("Stream closed".to_string()))
+    }
+    
+    /// Seq of the latest status update received for an intent, if any.
+    pub fn last_seq(&self, intent_id: Uuid) -> Option<u64> {
+        self.seqs.get(&intent_id).and_then(|s| s.last)
+    }
+    
+    /// Get the next event and the intent it belongs to.
+    pub async fn next(&mut self) -> Option<(Uuid, Event)> {
+        if let Some(pending) = self.pending.take() {
+            return Some(pending);
+        }
+        let received = self.receiver.recv().await?;
+        let intent_id = received.intent_id.unwrap_or(Uuid::nil());
+        if let Some(seq) = received.seq {
+            if let Some(gap) = self.seqs.entry(intent_id).or_default().observe(seq) {
+                self.pending = Some((intent_id, received.event));
+                return Some((intent_id, gap));
+            }
+        }
+        Some((intent_id, received.event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serve one connection that sends status updates with the given seqs.
+    async fn shim(intent_id: Uuid, seqs: Vec<u64>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            for seq in seqs {
+                let frame = serde_json::json!({
+                    "type": "status_update",
+                    "intent_id": intent_id,
+                    "seq": seq,
+                    "status": "executing",
+                    "plan_id": null,
+                    "artifact_id": null,
+                });
+                ws.send(Message::Text(frame.to_string())).await.unwrap();
+            }
+            let _ = ws.close(None).await;
+        });
+        format!("ws://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_dropped_frame_is_detected() {
+        let intent_id = Uuid::new_v4();
+        // The shim drops the update with seq 3
+        let url = shim(intent_id, vec![1, 2, 4]).await;
+        let mut stream = EventStream::connect(&url, intent_id).await.unwrap();
+
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 4);
+        assert!(matches!(events[2], Event::GapDetected { expected: 3, received: 4 }));
+        assert!(matches!(events[3], Event::StatusUpdate { .. }));
+        assert_eq!(stream.last_seq(), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_consecutive_seqs_have_no_gap() {
+        let intent_id = Uuid::new_v4();
+        let url = shim(intent_id, vec![5, 6, 7]).await;
+        let mut stream = EventStream::connect(&url, intent_id).await.unwrap();
+
+        while let Some(event) = stream.next().await {
+            assert!(!matches!(event, Event::GapDetected { .. }));
+        }
+        assert_eq!(stream.last_seq(), Some(7));
     }
 }
@@ -0,0 +1,587 @@
+//! Client-side verification of execution artifacts.
+//!
+//! [`verify_artifact`] runs every check independently and returns a
+//! [`VerificationReport`], so callers can see exactly what passed, what is
+//! suspicious, and what is broken.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use orpheon_core::artifact::ExecutionEventType;
+use orpheon_core::crypto::{self, ED25519};
+use orpheon_core::{ExecutionArtifact, Outcome, WeightBy};
+use orpheon_negotiate::AcceptanceReceipt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The individual checks performed on an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    /// The Merkle root matches the recomputed trace root.
+    MerkleRoot,
+    /// Trace events are in chronological order and well-formed.
+    TraceOrdering,
+    /// The node signature is valid for the given key.
+    Signature,
+    /// The embedded intent matches its recorded content hash.
+    IntentHash,
+    /// The outcome agrees with what the trace shows.
+    OutcomeTrace,
+    /// The acceptance receipt's node signature is valid for the given key.
+    ReceiptSignature,
+    /// The acceptance receipt names the executed intent and plan, and the
+    /// artifact names the receipt.
+    ReceiptLinkage,
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CheckKind::MerkleRoot => "merkle_root",
+            CheckKind::TraceOrdering => "trace_ordering",
+            CheckKind::Signature => "signature",
+            CheckKind::IntentHash => "intent_hash",
+            CheckKind::OutcomeTrace => "outcome_trace",
+            CheckKind::ReceiptSignature => "receipt_signature",
+            CheckKind::ReceiptLinkage => "receipt_linkage",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Result of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// The check passed.
+    Passed,
+    /// The artifact is consistent but something looks off.
+    Warning,
+    /// The artifact failed the check.
+    Failed,
+    /// The check could not be run (e.g. no key provided).
+    Skipped,
+}
+
+/// Outcome of one verification check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationCheck {
+    /// Which check this is.
+    pub kind: CheckKind,
+    /// Result of the check.
+    pub status: CheckStatus,
+    /// Human-readable explanation.
+    pub detail: String,
+}
+
+impl VerificationCheck {
+    fn new(kind: CheckKind, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Report produced by [`verify_artifact`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// The artifact that was verified.
+    pub artifact_id: Uuid,
+    /// Merkle algorithm version the trace root was checked with.
+    pub merkle_version: u8,
+    /// Every check that was run, in a fixed order.
+    pub checks: Vec<VerificationCheck>,
+    /// What the node serves alongside an artifact that no check covers,
+    /// because it is not signed; see [`NOT_COVERED`].
+    #[serde(default)]
+    pub not_covered: Vec<String>,
+}
+
+/// Fields served with artifacts that are outside their Merkle root and
+/// signature: operator [`annotations`](crate::annotation), which may be
+/// added at any time after the artifact was signed.
+pub const NOT_COVERED: [&str; 1] = ["annotations"];
+
+impl VerificationReport {
+    /// Whether no check failed (warnings and skipped checks are allowed).
+    pub fn is_valid(&self) -> bool {
+        self.failures().is_empty()
+    }
+
+    /// Get the result of a specific check.
+    pub fn check(&self, kind: CheckKind) -> Option<&VerificationCheck> {
+        self.checks.iter().find(|c| c.kind == kind)
+    }
+
+    /// Checks that failed.
+    pub fn failures(&self) -> Vec<&VerificationCheck> {
+        self.with_status(CheckStatus::Failed)
+    }
+
+    /// Checks that passed with a warning.
+    pub fn warnings(&self) -> Vec<&VerificationCheck> {
+        self.with_status(CheckStatus::Warning)
+    }
+
+    fn with_status(&self, status: CheckStatus) -> Vec<&VerificationCheck> {
+        self.checks.iter().filter(|c| c.status == status).collect()
+    }
+}
+
+/// Verify an execution artifact.
+///
+/// `node_public_key` is the hex-encoded ed25519 key of the node expected to
+/// have signed the artifact; without it the signature check is skipped.
+pub fn verify_artifact(artifact: &ExecutionArtifact, node_public_key: Option<&str>) -> VerificationReport {
+    VerificationReport {
+        artifact_id: artifact.id,
+        merkle_version: artifact.merkle_version,
+        checks: vec![
+            check_merkle_root(artifact),
+            check_trace_ordering(artifact),
+            check_signature(artifact, node_public_key),
+            check_intent_hash(artifact),
+            check_outcome_trace(artifact),
+        ],
+        not_covered: NOT_COVERED.map(String::from).to_vec(),
+    }
+}
+
+/// Verify the receipt of an accepted proposal against the artifact of the
+/// execution that followed.
+///
+/// `node_public_key` is checked as in [`verify_artifact`]. The receipt must
+/// be for the artifact's intent and plan, hash to the plan that was
+/// executed, and be the receipt the artifact records.
+pub fn verify_receipt(
+    receipt: &AcceptanceReceipt,
+    artifact: &ExecutionArtifact,
+    node_public_key: Option<&str>,
+) -> VerificationReport {
+    VerificationReport {
+        artifact_id: artifact.id,
+        merkle_version: artifact.merkle_version,
+        checks: vec![
+            check_receipt_signature(receipt, node_public_key),
+            check_receipt_linkage(receipt, artifact),
+        ],
+        not_covered: Vec::new(),
+    }
+}
+
+fn check_merkle_root(artifact: &ExecutionArtifact) -> VerificationCheck {
+    let kind = CheckKind::MerkleRoot;
+    let version = artifact.merkle_version;
+
+    let Some(computed) = artifact.merkle_root_with(version) else {
+        return VerificationCheck::new(kind, CheckStatus::Failed, format!("unsupported merkle version {}", version));
+    };
+    if computed == artifact.merkle_root {
+        VerificationCheck::new(kind, CheckStatus::Passed, format!("merkle root matches trace (version {})", version))
+    } else {
+        VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!(
+                "recorded root {} does not match computed version {} root {}",
+                artifact.merkle_root, version, computed
+            ),
+        )
+    }
+}
+
+fn check_trace_ordering(artifact: &ExecutionArtifact) -> VerificationCheck {
+    let kind = CheckKind::TraceOrdering;
+    let mut started = HashSet::new();
+
+    for (idx, pair) in artifact.trace.windows(2).enumerate() {
+        if pair[1].timestamp < pair[0].timestamp {
+            return VerificationCheck::new(
+                kind,
+                CheckStatus::Failed,
+                format!("event {} is earlier than event {}", idx + 1, idx),
+            );
+        }
+    }
+
+    for (idx, event) in artifact.trace.iter().enumerate() {
+        match event.event_type {
+            ExecutionEventType::StepStarted => {
+                started.insert(event.step_id);
+            }
+            ExecutionEventType::StepCompleted | ExecutionEventType::StepFailed
+                if !started.contains(&event.step_id) =>
+            {
+                return VerificationCheck::new(
+                    kind,
+                    CheckStatus::Failed,
+                    format!("event {} ends step {} before it started", idx, event.step_id),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(last) = artifact.trace.last() {
+        if last.timestamp > artifact.timestamp {
+            return VerificationCheck::new(
+                kind,
+                CheckStatus::Failed,
+                "trace contains events after the artifact was finalized",
+            );
+        }
+    }
+
+    VerificationCheck::new(kind, CheckStatus::Passed, format!("{} events in order", artifact.trace.len()))
+}
+
+fn check_signature(artifact: &ExecutionArtifact, node_public_key: Option<&str>) -> VerificationCheck {
+    let kind = CheckKind::Signature;
+
+    let Some(expected_key) = node_public_key else {
+        return VerificationCheck::new(kind, CheckStatus::Skipped, "no node public key provided");
+    };
+    let Some(signature) = &artifact.signature else {
+        return VerificationCheck::new(kind, CheckStatus::Failed, "artifact is not signed");
+    };
+
+    if !signature.algorithm.eq_ignore_ascii_case(ED25519) {
+        return VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("unsupported signature algorithm '{}'", signature.algorithm),
+        );
+    }
+    if !signature.public_key.eq_ignore_ascii_case(expected_key) {
+        return VerificationCheck::new(kind, CheckStatus::Failed, "artifact was signed by a different key");
+    }
+
+    match crypto::verify_ed25519(expected_key, artifact.content_hash().as_bytes(), &signature.signature) {
+        Ok(()) => VerificationCheck::new(kind, CheckStatus::Passed, "signature is valid"),
+        Err(e) => VerificationCheck::new(kind, CheckStatus::Failed, e.to_string()),
+    }
+}
+
+fn check_intent_hash(artifact: &ExecutionArtifact) -> VerificationCheck {
+    let kind = CheckKind::IntentHash;
+
+    if artifact.final_plan.intent_id != artifact.intent.id {
+        return VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("plan belongs to intent {}, not {}", artifact.final_plan.intent_id, artifact.intent.id),
+        );
+    }
+    if artifact.intent_hash.is_empty() {
+        return VerificationCheck::new(kind, CheckStatus::Skipped, "artifact does not record an intent hash");
+    }
+
+    let computed = artifact.intent.content_hash();
+    if computed == artifact.intent_hash {
+        VerificationCheck::new(kind, CheckStatus::Passed, "intent matches recorded hash")
+    } else {
+        VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("intent hash {} does not match recorded {}", computed, artifact.intent_hash),
+        )
+    }
+}
+
+fn check_outcome_trace(artifact: &ExecutionArtifact) -> VerificationCheck {
+    let kind = CheckKind::OutcomeTrace;
+    let failed = artifact.failed_steps().len();
+    let completed = artifact.successful_steps().len();
+
+    match &artifact.outcome {
+        Outcome::Success if failed > 0 => VerificationCheck::new(
+            kind,
+            CheckStatus::Warning,
+            format!("outcome is success but {} step(s) failed", failed),
+        ),
+        Outcome::Failure { .. } if failed == 0 && completed > 0 => VerificationCheck::new(
+            kind,
+            CheckStatus::Warning,
+            "outcome is failure but no step failed",
+        ),
+        Outcome::PartialSuccess { success_rate, .. } => {
+            // Nodes may weight steps by count, cost or duration
+            let rates = [WeightBy::Count, WeightBy::Cost, WeightBy::Duration]
+                .map(|weights| (artifact.success_rate_weighted(weights) * 100.0).round() as u8);
+            if rates.iter().all(|actual| actual.abs_diff(*success_rate) > 1) {
+                VerificationCheck::new(
+                    kind,
+                    CheckStatus::Warning,
+                    format!("reported success rate {}% but trace shows {}%", success_rate, rates[0]),
+                )
+            } else {
+                VerificationCheck::new(kind, CheckStatus::Passed, "success rate matches trace")
+            }
+        }
+        _ => VerificationCheck::new(kind, CheckStatus::Passed, "outcome agrees with trace"),
+    }
+}
+
+fn check_receipt_signature(receipt: &AcceptanceReceipt, node_public_key: Option<&str>) -> VerificationCheck {
+    let kind = CheckKind::ReceiptSignature;
+
+    let Some(expected_key) = node_public_key else {
+        return VerificationCheck::new(kind, CheckStatus::Skipped, "no node public key provided");
+    };
+    match receipt.verify_signature(expected_key) {
+        Ok(()) => VerificationCheck::new(kind, CheckStatus::Passed, "signature is valid"),
+        Err(e) => VerificationCheck::new(kind, CheckStatus::Failed, e.to_string()),
+    }
+}
+
+fn check_receipt_linkage(receipt: &AcceptanceReceipt, artifact: &ExecutionArtifact) -> VerificationCheck {
+    let kind = CheckKind::ReceiptLinkage;
+    let plan = &artifact.final_plan;
+
+    if receipt.intent_id != artifact.intent.id {
+        return VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("receipt is for intent {}, not {}", receipt.intent_id, artifact.intent.id),
+        );
+    }
+    let recorded = artifact.execution_metadata.extra.get("receipt_id").and_then(|id| id.as_str());
+    if recorded != Some(receipt.id.to_string().as_str()) {
+        let detail = match recorded {
+            Some(id) => format!("artifact records receipt {}, not {}", id, receipt.id),
+            None => "artifact records no receipt".to_string(),
+        };
+        return VerificationCheck::new(kind, CheckStatus::Failed, detail);
+    }
+    if receipt.plan_id != plan.id {
+        return VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("receipt is for plan {}, but plan {} was executed", receipt.plan_id, plan.id),
+        );
+    }
+
+    let computed = plan.content_hash();
+    if computed == receipt.plan_hash {
+        VerificationCheck::new(kind, CheckStatus::Passed, "executed plan matches the accepted plan")
+    } else {
+        VerificationCheck::new(
+            kind,
+            CheckStatus::Failed,
+            format!("executed plan hash {} does not match accepted {}", computed, receipt.plan_hash),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use orpheon_core::{ExecutionEvent, Intent, NodeKey, Plan, PlanningStrategy};
+
+    fn node_key() -> NodeKey {
+        NodeKey::from_bytes(&[7u8; 32])
+    }
+
+    fn public_key_hex() -> String {
+        node_key().public_key_hex()
+    }
+
+    /// A finalized, signed artifact with two completed steps.
+    fn fixture() -> ExecutionArtifact {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+
+        for _ in 0..2 {
+            let step = Uuid::new_v4();
+            artifact.add_event(ExecutionEvent::step_started(step));
+            artifact.add_event(ExecutionEvent::step_completed(step, 50));
+        }
+        artifact.finalize();
+        node_key().sign_artifact(&mut artifact);
+        artifact
+    }
+
+    fn status(report: &VerificationReport, kind: CheckKind) -> CheckStatus {
+        report.check(kind).unwrap().status
+    }
+
+    #[test]
+    fn test_valid_artifact_passes_all_checks() {
+        let report = verify_artifact(&fixture(), Some(&public_key_hex()));
+
+        assert!(report.is_valid());
+        assert!(report.checks.iter().all(|c| c.status == CheckStatus::Passed), "{:?}", report.checks);
+    }
+
+    #[test]
+    fn test_reports_merkle_version_checked() {
+        let report = verify_artifact(&fixture(), None);
+        assert_eq!(report.merkle_version, orpheon_core::MERKLE_VERSION);
+
+        // Artifacts from before domain separation still verify as version 1
+        let v1: ExecutionArtifact =
+            serde_json::from_str(include_str!("../../orpheon-core/fixtures/artifact_merkle_v1.json")).unwrap();
+        let key = v1.signature.as_ref().unwrap().public_key.clone();
+        let report = verify_artifact(&v1, Some(&key));
+        assert_eq!(report.merkle_version, 1);
+        assert!(report.check(CheckKind::MerkleRoot).unwrap().detail.contains("version 1"));
+        assert!(report.checks.iter().all(|c| c.status == CheckStatus::Passed), "{:?}", report.checks);
+
+        let v2: ExecutionArtifact =
+            serde_json::from_str(include_str!("../../orpheon-core/fixtures/artifact_merkle_v2.json")).unwrap();
+        let report = verify_artifact(&v2, None);
+        assert_eq!(report.merkle_version, 2);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_unknown_merkle_version_fails() {
+        let mut artifact = fixture();
+        artifact.merkle_version = 9;
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(report.merkle_version, 9);
+        assert_eq!(status(&report, CheckKind::MerkleRoot), CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_tampered_trace_fails_merkle_root() {
+        let mut artifact = fixture();
+        artifact.trace[1].duration_ms = Some(1);
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::MerkleRoot), CheckStatus::Failed);
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_out_of_order_trace_fails_ordering() {
+        let mut artifact = fixture();
+        artifact.trace[2].timestamp = artifact.trace[0].timestamp - Duration::seconds(1);
+        artifact.merkle_root = artifact.compute_merkle_root();
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::MerkleRoot), CheckStatus::Passed);
+        assert_eq!(status(&report, CheckKind::TraceOrdering), CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_step_completed_without_start_fails_ordering() {
+        let mut artifact = fixture();
+        artifact.trace.remove(0);
+        artifact.merkle_root = artifact.compute_merkle_root();
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::TraceOrdering), CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_signature_checks() {
+        let artifact = fixture();
+
+        // Skipped without a key
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::Signature), CheckStatus::Skipped);
+
+        // Tampered content invalidates the signature
+        let mut tampered = artifact.clone();
+        tampered.actual_cost += 100.0;
+        let report = verify_artifact(&tampered, Some(&public_key_hex()));
+        assert_eq!(status(&report, CheckKind::Signature), CheckStatus::Failed);
+
+        // Different expected key
+        let other = NodeKey::from_bytes(&[9u8; 32]).public_key_hex();
+        let report = verify_artifact(&artifact, Some(&other));
+        assert_eq!(status(&report, CheckKind::Signature), CheckStatus::Failed);
+
+        // Unsigned artifact with a key expected
+        let mut unsigned = artifact.clone();
+        unsigned.signature = None;
+        let report = verify_artifact(&unsigned, Some(&public_key_hex()));
+        assert_eq!(status(&report, CheckKind::Signature), CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_modified_intent_fails_intent_hash() {
+        let mut artifact = fixture();
+        artifact.intent.kind = "delete_everything".to_string();
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::IntentHash), CheckStatus::Failed);
+        assert_eq!(status(&report, CheckKind::MerkleRoot), CheckStatus::Passed);
+    }
+
+    #[test]
+    fn test_success_with_failed_steps_warns() {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        let step = Uuid::new_v4();
+        artifact.add_event(ExecutionEvent::step_started(step));
+        artifact.add_event(ExecutionEvent::step_failed(step, "boom"));
+        artifact.finalize();
+
+        let report = verify_artifact(&artifact, None);
+        assert_eq!(status(&report, CheckKind::OutcomeTrace), CheckStatus::Warning);
+        assert!(report.is_valid());
+        assert_eq!(report.warnings().len(), 1);
+    }
+
+    /// A signed receipt for a one-step plan and the signed artifact of
+    /// executing it.
+    fn receipt_fixture() -> (AcceptanceReceipt, ExecutionArtifact) {
+        let intent = Intent::builder().kind("deploy").build().unwrap();
+        let mut plan = Plan::new(intent.id, PlanningStrategy::Heuristic);
+        plan.add_step(orpheon_core::Step::new("deploy", "deploy_workload").with_cost(2.0));
+        let proposal = orpheon_negotiate::Proposal::new(intent.id, plan.clone()).unwrap();
+        let mut receipt = AcceptanceReceipt::for_proposal(&proposal);
+        receipt.sign(&node_key());
+
+        let mut artifact = ExecutionArtifact::new(intent, plan, Outcome::Success);
+        artifact.execution_metadata.extra = serde_json::json!({ "receipt_id": receipt.id });
+        artifact.finalize();
+        node_key().sign_artifact(&mut artifact);
+        (receipt, artifact)
+    }
+
+    #[test]
+    fn test_valid_receipt_passes() {
+        let (receipt, artifact) = receipt_fixture();
+        let report = verify_receipt(&receipt, &artifact, Some(&public_key_hex()));
+        assert!(report.checks.iter().all(|c| c.status == CheckStatus::Passed), "{:?}", report.checks);
+
+        let report = verify_receipt(&receipt, &artifact, None);
+        assert_eq!(status(&report, CheckKind::ReceiptSignature), CheckStatus::Skipped);
+    }
+
+    #[test]
+    fn test_tampered_receipt_fails_signature() {
+        let (mut receipt, artifact) = receipt_fixture();
+        receipt.quoted_cost = 0.5;
+
+        let report = verify_receipt(&receipt, &artifact, Some(&public_key_hex()));
+        assert_eq!(status(&report, CheckKind::ReceiptSignature), CheckStatus::Failed);
+        assert_eq!(status(&report, CheckKind::ReceiptLinkage), CheckStatus::Passed);
+    }
+
+    #[test]
+    fn test_receipt_must_match_executed_plan() {
+        let (receipt, mut artifact) = receipt_fixture();
+        artifact.final_plan.steps[0].estimated_cost = 20.0;
+
+        let report = verify_receipt(&receipt, &artifact, Some(&public_key_hex()));
+        let check = report.check(CheckKind::ReceiptLinkage).unwrap();
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.detail.contains("does not match accepted"), "{}", check.detail);
+
+        let (receipt, mut artifact) = receipt_fixture();
+        artifact.execution_metadata.extra = serde_json::Value::Null;
+        let report = verify_receipt(&receipt, &artifact, None);
+        assert_eq!(report.check(CheckKind::ReceiptLinkage).unwrap().detail, "artifact records no receipt");
+    }
+}
@@ -0,0 +1,233 @@
+//! State key naming conventions.
+//!
+//! Protocol data lives under `:`-separated keys whose first segment names
+//! the record type. Build keys with [`Keys`] rather than by hand so prefix
+//! subscriptions keep matching, and use [`Keys::parse`] to go back.
+//!
+//! | Record               | Key                                      |
+//! |----------------------|------------------------------------------|
+//! | Intent status        | `intent:{intent_id}:status`              |
+//! | Artifact             | `artifact:{intent_id}`                   |
+//! | Trace event          | `trace:{intent_id}:{seq}`                |
+//! | Negotiation message  | `negotiation:{intent_id}:{round}:{kind}` |
+//! | Event payload        | `blob:{event_id}`                        |
+//! | Daily statistic      | `stats:{date}:{metric}`                  |
+//! | Action durations     | `stats:action:{action}`                  |
+//! | Plan annotations     | `annotations:plan:{plan_id}`             |
+//! | Artifact annotations | `annotations:artifact:{artifact_id}`     |
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+/// Builders for well-known state keys.
+pub struct Keys;
+
+impl Keys {
+    /// Status of an intent.
+    pub fn intent_status(intent_id: Uuid) -> String {
+        ParsedKey::IntentStatus(intent_id).to_string()
+    }
+
+    /// Execution artifact of an intent.
+    pub fn artifact(intent_id: Uuid) -> String {
+        ParsedKey::Artifact(intent_id).to_string()
+    }
+
+    /// Event `seq` of an intent's execution trace.
+    ///
+    /// Sequence numbers are zero-padded so keys sort in trace order.
+    pub fn trace(intent_id: Uuid, seq: u64) -> String {
+        ParsedKey::Trace { intent_id, seq }.to_string()
+    }
+
+    /// Prefix matching every trace event of an intent.
+    pub fn trace_prefix(intent_id: Uuid) -> String {
+        format!("trace:{}:", intent_id)
+    }
+
+    /// Negotiation message of `kind` (e.g. `proposal`, `counter`) in `round`.
+    pub fn negotiation(intent_id: Uuid, round: u32, kind: &str) -> String {
+        ParsedKey::Negotiation {
+            intent_id,
+            round,
+            kind: kind.to_string(),
+        }
+        .to_string()
+    }
+
+    /// Prefix matching every negotiation message of an intent.
+    pub fn negotiation_prefix(intent_id: Uuid) -> String {
+        format!("negotiation:{}:", intent_id)
+    }
+
+    /// Full payload of a truncated execution event.
+    pub fn event_blob(event_id: Uuid) -> String {
+        ParsedKey::EventBlob(event_id).to_string()
+    }
+
+    /// Aggregate of `metric` over the UTC day `date`.
+    ///
+    /// Dates are formatted `YYYY-MM-DD`, so keys sort by day.
+    pub fn stats(date: NaiveDate, metric: &str) -> String {
+        ParsedKey::Stats { date, metric: metric.to_string() }.to_string()
+    }
+
+    /// Learned duration statistics of a planner action.
+    pub fn action_stats(action: &str) -> String {
+        ParsedKey::ActionStats(action.to_string()).to_string()
+    }
+
+    /// Prefix matching every action's duration statistics.
+    pub fn action_stats_prefix() -> &'static str {
+        "stats:action:"
+    }
+
+    /// Prefix matching every daily statistic.
+    pub fn stats_prefix() -> &'static str {
+        "stats:"
+    }
+
+    /// Operator annotations of a plan.
+    pub fn plan_annotations(plan_id: Uuid) -> String {
+        ParsedKey::PlanAnnotations(plan_id).to_string()
+    }
+
+    /// Operator annotations of an execution artifact.
+    pub fn artifact_annotations(artifact_id: Uuid) -> String {
+        ParsedKey::ArtifactAnnotations(artifact_id).to_string()
+    }
+
+    /// Parse a key built by [`Keys`]; returns `None` for any other key.
+    pub fn parse(key: &str) -> Option<ParsedKey> {
+        let parts: Vec<&str> = key.split(':').collect();
+        let parsed = match parts.as_slice() {
+            ["intent", id, "status"] => ParsedKey::IntentStatus(id.parse().ok()?),
+            ["artifact", id] => ParsedKey::Artifact(id.parse().ok()?),
+            ["trace", id, seq] => ParsedKey::Trace {
+                intent_id: id.parse().ok()?,
+                seq: seq.parse().ok()?,
+            },
+            ["negotiation", id, round, kind] if !kind.is_empty() => ParsedKey::Negotiation {
+                intent_id: id.parse().ok()?,
+                round: round.parse().ok()?,
+                kind: kind.to_string(),
+            },
+            ["blob", id] => ParsedKey::EventBlob(id.parse().ok()?),
+            ["annotations", "plan", id] => ParsedKey::PlanAnnotations(id.parse().ok()?),
+            ["annotations", "artifact", id] => ParsedKey::ArtifactAnnotations(id.parse().ok()?),
+            ["stats", "action", action @ ..] if !action.is_empty() && action.iter().all(|a| !a.is_empty()) => {
+                ParsedKey::ActionStats(action.join(":"))
+            }
+            // Metrics may contain `:` themselves, e.g. `planning_failed:deploy`
+            ["stats", date, metric @ ..] if !metric.is_empty() && metric.iter().all(|m| !m.is_empty()) => {
+                ParsedKey::Stats {
+                    date: date.parse().ok()?,
+                    metric: metric.join(":"),
+                }
+            }
+            _ => return None,
+        };
+        Some(parsed)
+    }
+}
+
+/// A well-known state key, parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedKey {
+    /// `intent:{intent_id}:status`
+    IntentStatus(Uuid),
+    /// `artifact:{intent_id}`
+    Artifact(Uuid),
+    /// `trace:{intent_id}:{seq}`
+    Trace { intent_id: Uuid, seq: u64 },
+    /// `negotiation:{intent_id}:{round}:{kind}`
+    Negotiation { intent_id: Uuid, round: u32, kind: String },
+    /// `blob:{event_id}`
+    EventBlob(Uuid),
+    /// `stats:{date}:{metric}`
+    Stats { date: NaiveDate, metric: String },
+    /// `stats:action:{action}`
+    ActionStats(String),
+    /// `annotations:plan:{plan_id}`
+    PlanAnnotations(Uuid),
+    /// `annotations:artifact:{artifact_id}`
+    ArtifactAnnotations(Uuid),
+}
+
+impl fmt::Display for ParsedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParsedKey::IntentStatus(id) => write!(f, "intent:{}:status", id),
+            ParsedKey::Artifact(id) => write!(f, "artifact:{}", id),
+            ParsedKey::Trace { intent_id, seq } => write!(f, "trace:{}:{:010}", intent_id, seq),
+            ParsedKey::Negotiation { intent_id, round, kind } => {
+                write!(f, "negotiation:{}:{}:{}", intent_id, round, kind)
+            }
+            ParsedKey::EventBlob(id) => write!(f, "blob:{}", id),
+            ParsedKey::Stats { date, metric } => write!(f, "stats:{}:{}", date.format("%Y-%m-%d"), metric),
+            ParsedKey::ActionStats(action) => write!(f, "stats:action:{}", action),
+            ParsedKey::PlanAnnotations(id) => write!(f, "annotations:plan:{}", id),
+            ParsedKey::ArtifactAnnotations(id) => write!(f, "annotations:artifact:{}", id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        let id = Uuid::new_v4();
+        let keys = [
+            ParsedKey::IntentStatus(id),
+            ParsedKey::Artifact(id),
+            ParsedKey::Trace { intent_id: id, seq: 42 },
+            ParsedKey::Negotiation { intent_id: id, round: 3, kind: "counter".to_string() },
+            ParsedKey::EventBlob(id),
+            ParsedKey::Stats { date: NaiveDate::from_ymd_opt(2026, 3, 9).unwrap(), metric: "proposals_sent".to_string() },
+            ParsedKey::Stats {
+                date: NaiveDate::from_ymd_opt(2026, 3, 9).unwrap(),
+                metric: "planning_failed:deploy".to_string(),
+            },
+            ParsedKey::ActionStats("allocate_resource".to_string()),
+            ParsedKey::PlanAnnotations(id),
+            ParsedKey::ArtifactAnnotations(id),
+        ];
+
+        for key in keys {
+            assert_eq!(Keys::parse(&key.to_string()), Some(key));
+        }
+    }
+
+    #[test]
+    fn test_builders_match_prefixes() {
+        let id = Uuid::new_v4();
+        assert!(Keys::trace(id, 7).starts_with(&Keys::trace_prefix(id)));
+        assert!(Keys::negotiation(id, 1, "proposal").starts_with(&Keys::negotiation_prefix(id)));
+        assert_eq!(Keys::intent_status(id), format!("intent:{}:status", id));
+        let day = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(Keys::stats(day, "proposals_sent"), "stats:2026-03-09:proposals_sent");
+        assert!(Keys::stats(day, "proposals_sent").starts_with(Keys::stats_prefix()));
+        assert_eq!(Keys::action_stats("finalize"), "stats:action:finalize");
+        assert!(Keys::action_stats("finalize").starts_with(Keys::action_stats_prefix()));
+
+        // Zero-padding keeps lexical order equal to trace order
+        assert!(Keys::trace(id, 9) < Keys::trace(id, 10));
+    }
+
+    #[test]
+    fn test_parse_rejects_foreign_keys() {
+        let id = Uuid::new_v4();
+        assert_eq!(Keys::parse("config/default_region"), None);
+        assert_eq!(Keys::parse("artifact:not-a-uuid"), None);
+        assert_eq!(Keys::parse(&format!("trace:{}:x", id)), None);
+        assert_eq!(Keys::parse(&format!("intent:{}:plan", id)), None);
+        assert_eq!(Keys::parse(&format!("negotiation:{}:1:", id)), None);
+        assert_eq!(Keys::parse("stats:yesterday:proposals_sent"), None);
+        assert_eq!(Keys::parse("stats:2026-03-09:"), None);
+        assert_eq!(Keys::parse("stats:action:"), None);
+    }
+}
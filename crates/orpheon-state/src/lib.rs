@@ -2,10 +2,12 @@
 //!
 //! Temporal state store with time-travel capabilities.
 
+pub mod keys;
 pub mod store;
 pub mod subscription;
 pub mod temporal;
 
-pub use store::{InMemoryStateStore, StateStore};
-pub use subscription::{StateSubscription, SubscriptionFilter};
+pub use keys::{Keys, ParsedKey};
+pub use store::{ChangedKeys, InMemoryStateStore, SnapshotStream, StateStore, StateStoreExt};
+pub use subscription::{PublishReceipt, StateSubscription, SubscriptionFilter, SubscriptionManager};
 pub use temporal::{StateSnapshot, TimeTravelQuery};
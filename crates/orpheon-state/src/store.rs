@@ -1,17 +1,32 @@
 //! State store implementations.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use orpheon_core::{OrpheonError, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 use uuid::Uuid;
 
 use crate::temporal::StateSnapshot;
 
+/// Number of recent writes remembered for [`InMemoryStateStore::keys_changed_since`].
+pub const MAX_RECENT_CHANGES: usize = 1024;
+
+/// Keys written after some version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedKeys {
+    /// Distinct keys, in the order they were first written.
+    pub keys: Vec<String>,
+    
+    /// False if keys were left out, because of the limit or because the
+    /// writes are older than the store remembers.
+    pub complete: bool,
+}
+
 /// A versioned state entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateEntry {
@@ -68,34 +83,153 @@ pub trait StateStore: Send + Sync {
     async fn version(&self) -> u64;
 }
 
+/// Typed access on top of any [`StateStore`].
+///
+/// Values round-trip through `serde_json`; a value that does not match the
+/// requested type is reported as [`OrpheonError::SerializationError`].
+#[async_trait]
+pub trait StateStoreExt: StateStore {
+    /// Get the current value for a key, deserialized as `T`.
+    async fn get_typed<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let Some(entry) = self.get(key).await? else {
+            return Ok(None);
+        };
+        serde_json::from_value(entry.value)
+            .map(Some)
+            .map_err(|e| OrpheonError::SerializationError(format!("{}: {}", key, e)))
+    }
+
+    /// Serialize `value` and set it for a key.
+    async fn set_typed<T: Serialize + Sync>(&self, key: &str, value: &T) -> Result<StateEntry> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| OrpheonError::SerializationError(format!("{}: {}", key, e)))?;
+        self.set(key, value).await
+    }
+}
+
+impl<S: StateStore + ?Sized> StateStoreExt for S {}
+
+/// Versioned key space: key -> list of versions (append-only).
+///
+/// A persistent map of shared version lists, so readers clone it in
+/// constant time under the lock and scan the copy after releasing it; a
+/// write then copies only the map nodes and version list it touches.
+type VersionedState = im::HashMap<String, Arc<Vec<StateEntry>>>;
+
+/// Latest entry of a key that is not a tombstone.
+fn latest_live(versions: &[StateEntry]) -> Option<&StateEntry> {
+    versions.iter().rev().find(|e| !e.deleted)
+}
+
+/// Point-in-time copy of a store's current entries, yielded one at a time.
+///
+/// Returned by [`InMemoryStateStore::snapshot_streaming`]. Taking it costs
+/// a constant-time copy; writes made afterwards are not seen.
+pub struct SnapshotStream {
+    version: u64,
+    entries: im::hashmap::ConsumingIter<(String, Arc<Vec<StateEntry>>)>,
+}
+
+impl SnapshotStream {
+    /// Store version the snapshot was taken at.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+impl Iterator for SnapshotStream {
+    type Item = StateEntry;
+
+    fn next(&mut self) -> Option<StateEntry> {
+        self.entries.by_ref().find_map(|(_, versions)| latest_live(&versions).cloned())
+    }
+}
+
 /// In-memory implementation of StateStore.
 pub struct InMemoryStateStore {
     /// Main state storage: key -> list of versions (append-only).
-    state: Arc<RwLock<HashMap<String, Vec<StateEntry>>>>,
+    state: Arc<RwLock<VersionedState>>,
     
     /// Forks: fork_id -> forked state.
-    forks: Arc<RwLock<HashMap<Uuid, HashMap<String, Vec<StateEntry>>>>>,
+    forks: Arc<RwLock<HashMap<Uuid, VersionedState>>>,
     
     /// Global version counter.
     version: Arc<RwLock<u64>>,
+    
+    /// Latest version, for tasks waiting on writes.
+    version_tx: Arc<watch::Sender<u64>>,
+    
+    /// The most recent writes as (version, key), oldest first.
+    recent: Arc<RwLock<VecDeque<(u64, String)>>>,
 }
 
 impl InMemoryStateStore {
     /// Create a new in-memory state store.
     pub fn new() -> Self {
         Self {
-            state: Arc::new(RwLock::new(HashMap::new())),
+            state: Arc::new(RwLock::new(im::HashMap::new())),
             forks: Arc::new(RwLock::new(HashMap::new())),
             version: Arc::new(RwLock::new(0)),
+            version_tx: Arc::new(watch::channel(0).0),
+            recent: Arc::new(RwLock::new(VecDeque::new())),
         }
     }
     
-    /// Get the next version number.
-    async fn next_version(&self) -> u64 {
+    /// Get the next version number for a write to `key`, notifying watchers.
+    async fn next_version(&self, key: &str) -> u64 {
         let mut version = self.version.write().await;
         *version += 1;
+        
+        let mut recent = self.recent.write().await;
+        if recent.len() >= MAX_RECENT_CHANGES {
+            recent.pop_front();
+        }
+        recent.push_back((*version, key.to_string()));
+        drop(recent);
+        
+        self.version_tx.send_replace(*version);
         *version
     }
+    
+    /// Current state and version, copied without holding the lock for
+    /// longer than the copy.
+    async fn current(&self) -> (VersionedState, u64) {
+        let state = self.state.read().await;
+        let version = *self.version.read().await;
+        (state.clone(), version)
+    }
+    
+    /// Take a snapshot whose entries are produced as they are iterated,
+    /// e.g. to stream an export without building the whole snapshot first.
+    pub async fn snapshot_streaming(&self) -> SnapshotStream {
+        let (state, version) = self.current().await;
+        SnapshotStream { version, entries: state.into_iter() }
+    }
+    
+    /// Watch the store's version; the receiver is notified after each write.
+    pub fn watch_version(&self) -> watch::Receiver<u64> {
+        self.version_tx.subscribe()
+    }
+    
+    /// Keys written after `version`, at most `limit` of them.
+    pub async fn keys_changed_since(&self, version: u64, limit: usize) -> ChangedKeys {
+        let recent = self.recent.read().await;
+        // Writes between `version` and the oldest remembered one are lost
+        let mut complete = recent.front().is_none_or(|(oldest, _)| *oldest <= version + 1);
+        
+        let mut keys: Vec<String> = Vec::new();
+        for (_, key) in recent.iter().filter(|(v, _)| *v > version) {
+            if keys.contains(key) {
+                continue;
+            }
+            if keys.len() == limit {
+                complete = false;
+                break;
+            }
+            keys.push(key.clone());
+        }
+        ChangedKeys { keys, complete }
+    }
 }
 
 impl Default for InMemoryStateStore {
@@ -124,14 +258,12 @@ impl StateStore for InMemoryStateStore {
     }
     
     async fn get_prefix(&self, prefix: &str) -> Result<Vec<StateEntry>> {
-        let state = self.state.read().await;
+        let (state, _) = self.current().await;
         
         let entries: Vec<StateEntry> = state
             .iter()
             .filter(|(k, _)| k.starts_with(prefix))
-            .filter_map(|(_, versions)| {
-                versions.iter().rev().find(|e| !e.deleted).cloned()
-            })
+            .filter_map(|(_, versions)| latest_live(versions).cloned())
             .collect();
         
         Ok(entries)
@@ -139,7 +271,7 @@ impl StateStore for InMemoryStateStore {
     
     async fn set(&self, key: &str, value: serde_json::Value) -> Result<StateEntry> {
         let mut state = self.state.write().await;
-        let version = self.next_version().await;
+        let version = self.next_version(key).await;
         
         let entry = StateEntry {
             key: key.to_string(),
@@ -150,17 +282,14 @@ impl StateStore for InMemoryStateStore {
             metadata: HashMap::new(),
         };
         
-        state
-            .entry(key.to_string())
-            .or_insert_with(Vec::new)
-            .push(entry.clone());
+        Arc::make_mut(state.entry(key.to_string()).or_default()).push(entry.clone());
         
         Ok(entry)
     }
     
     async fn delete(&self, key: &str) -> Result<()> {
         let mut state = self.state.write().await;
-        let version = self.next_version().await;
+        let version = self.next_version(key).await;
         
         let tombstone = StateEntry {
             key: key.to_string(),
@@ -171,10 +300,7 @@ impl StateStore for InMemoryStateStore {
             metadata: HashMap::new(),
         };
         
-        state
-            .entry(key.to_string())
-            .or_insert_with(Vec::new)
-            .push(tombstone);
+        Arc::make_mut(state.entry(key.to_string()).or_default()).push(tombstone);
         
         Ok(())
     }
@@ -195,19 +321,12 @@ impl StateStore for InMemoryStateStore {
     }
     
     async fn snapshot(&self) -> Result<StateSnapshot> {
-        let state = self.state.read().await;
-        let version = *self.version.read().await;
+        let (state, version) = self.current().await;
         
         // Get current values for all keys
         let entries: HashMap<String, StateEntry> = state
             .iter()
-            .filter_map(|(k, versions)| {
-                versions
-                    .iter()
-                    .rev()
-                    .find(|e| !e.deleted)
-                    .map(|e| (k.clone(), e.clone()))
-            })
+            .filter_map(|(k, versions)| latest_live(versions).map(|e| (k.clone(), e.clone())))
             .collect();
         
         Ok(StateSnapshot {
@@ -246,14 +365,14 @@ impl StateStore for InMemoryStateStore {
         
         // Merge forked state into main state
         for (key, versions) in forked_state {
-            let main_versions = state.entry(key).or_insert_with(Vec::new);
+            let main_versions = Arc::make_mut(state.entry(key).or_default());
             
             // Only add versions that are newer
             let latest_main_version = main_versions.last().map(|e| e.version).unwrap_or(0);
             
-            for entry in versions {
+            for entry in versions.iter() {
                 if entry.version > latest_main_version {
-                    main_versions.push(entry);
+                    main_versions.push(entry.clone());
                 }
             }
         }
@@ -262,7 +381,7 @@ impl StateStore for InMemoryStateStore {
     }
     
     async fn keys(&self) -> Result<Vec<String>> {
-        let state = self.state.read().await;
+        let (state, _) = self.current().await;
         Ok(state.keys().cloned().collect())
     }
     
@@ -286,6 +405,42 @@ mod tests {
         assert_eq!(entry.unwrap().value["value"], 42);
     }
 
+    #[tokio::test]
+    async fn test_typed_get_and_set() {
+        let store = InMemoryStateStore::new();
+        let quota: HashMap<String, u32> = [("gpu".to_string(), 8)].into();
+
+        store.set_typed("quota/compute", &quota).await.unwrap();
+        let read: Option<HashMap<String, u32>> = store.get_typed("quota/compute").await.unwrap();
+        assert_eq!(read, Some(quota));
+
+        let missing: Option<u32> = store.get_typed("quota/storage").await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_typed_get_type_mismatch() {
+        let store = InMemoryStateStore::new();
+        store.set("config/default_region", serde_json::json!("us-east")).await.unwrap();
+
+        let err = store.get_typed::<u64>("config/default_region").await.unwrap_err();
+        match err {
+            OrpheonError::SerializationError(msg) => assert!(msg.starts_with("config/default_region:")),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_set_unserializable() {
+        let store = InMemoryStateStore::new();
+        // JSON object keys must be strings
+        let bad: HashMap<(u8, u8), u8> = [((1, 2), 3)].into();
+
+        let err = store.set_typed("bad", &bad).await.unwrap_err();
+        assert!(matches!(err, OrpheonError::SerializationError(_)));
+        assert!(store.get("bad").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_delete() {
         let store = InMemoryStateStore::new();
@@ -344,4 +499,108 @@ mod tests {
         // Cleanup
         store.merge_fork(fork_id).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_version_watch_and_changed_keys() {
+        let store = InMemoryStateStore::new();
+        let mut versions = store.watch_version();
+        
+        store.set("a", serde_json::json!(1)).await.unwrap();
+        store.set("b", serde_json::json!(2)).await.unwrap();
+        store.set("a", serde_json::json!(3)).await.unwrap();
+        store.delete("c").await.unwrap();
+        assert!(versions.has_changed().unwrap());
+        assert_eq!(*versions.borrow_and_update(), 4);
+        
+        let changed = store.keys_changed_since(0, 10).await;
+        assert_eq!(changed, ChangedKeys { keys: vec!["a".into(), "b".into(), "c".into()], complete: true });
+        assert_eq!(store.keys_changed_since(2, 10).await.keys, vec!["a", "c"]);
+        assert!(!store.keys_changed_since(0, 2).await.complete);
+        
+        // Writes older than the remembered window make the list incomplete
+        for i in 0..MAX_RECENT_CHANGES {
+            store.set(&format!("k{}", i % 4), serde_json::json!(i)).await.unwrap();
+        }
+        let changed = store.keys_changed_since(0, 10).await;
+        assert_eq!(changed.keys.len(), 4);
+        assert!(!changed.complete);
+        assert!(store.keys_changed_since(4, 10).await.complete);
+    }
+
+    /// Run `read` while a writer task keeps setting keys, returning the
+    /// number of writes and the longest one.
+    async fn writes_during(
+        store: &Arc<InMemoryStateStore>,
+        read: impl std::future::Future<Output = ()>,
+    ) -> (u64, std::time::Duration) {
+        let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let writer = {
+            let store = store.clone();
+            let done = done.clone();
+            tokio::spawn(async move {
+                let mut max = std::time::Duration::ZERO;
+                let mut writes = 0u64;
+                while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                    let start = std::time::Instant::now();
+                    store.set(&format!("writer/{}", writes % 100), serde_json::json!(writes)).await.unwrap();
+                    max = max.max(start.elapsed());
+                    writes += 1;
+                    tokio::task::yield_now().await;
+                }
+                (writes, max)
+            })
+        };
+        read.await;
+        done.store(true, std::sync::atomic::Ordering::Relaxed);
+        writer.await.unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_snapshot_does_not_stall_writers() {
+        let store = Arc::new(InMemoryStateStore::new());
+        for i in 0..50_000 {
+            store.set(&format!("key/{:05}", i), serde_json::json!({"n": i})).await.unwrap();
+        }
+
+        let reader = store.clone();
+        let (writes, max) = writes_during(&store, async move {
+            let snapshot = reader.snapshot().await.unwrap();
+            assert!(snapshot.len() >= 50_000);
+            assert!(snapshot.entries.values().all(|e| e.version <= snapshot.version));
+
+            let stream = reader.snapshot_streaming().await;
+            let version = stream.version();
+            let mut count = 0;
+            for entry in stream {
+                assert!(entry.version <= version);
+                count += 1;
+            }
+            assert!(count >= 50_000);
+
+            assert_eq!(reader.get_prefix("key/").await.unwrap().len(), 50_000);
+        })
+        .await;
+
+        // Copying 50k entries under the lock used to stall writes for far longer
+        assert!(writes > 0);
+        assert!(max < std::time::Duration::from_millis(50), "a write took {:?}", max);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_streaming_is_point_in_time() {
+        let store = InMemoryStateStore::new();
+        store.set("a", serde_json::json!(1)).await.unwrap();
+        store.set("b", serde_json::json!(2)).await.unwrap();
+
+        // Writes after the snapshot is taken are not part of it
+        let stream = store.snapshot_streaming().await;
+        store.set("a", serde_json::json!(10)).await.unwrap();
+        store.set("c", serde_json::json!(3)).await.unwrap();
+        assert_eq!(store.get("a").await.unwrap().unwrap().value, 10);
+
+        assert_eq!(stream.version(), 2);
+        let mut entries: Vec<(String, serde_json::Value)> = stream.map(|e| (e.key, e.value)).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(entries, vec![("a".to_string(), serde_json::json!(1)), ("b".to_string(), serde_json::json!(2))]);
+    }
 }
@@ -1,11 +1,40 @@
 //! State subscription system.
+//!
+//! Each subscriber has its own bounded queue. [`SubscriptionManager::publish`]
+//! filters an event against every subscription and queues it for those that
+//! match, without waiting; when a subscriber's queue is full the event is
+//! dropped for that subscriber only, and the [`PublishReceipt`] says so.
+//!
+//! Delivery is at most once. Events from one publisher reach each subscriber
+//! in the order they were published; events from different publishers may
+//! interleave.
+//!
+//! ```
+//! use orpheon_state::subscription::{ChangeType, StateChangeEvent, SubscriptionFilter, SubscriptionManager};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let manager = SubscriptionManager::with_capacity(2);
+//! let mut sub = manager.subscribe(SubscriptionFilter::prefix("gpu/")).await;
+//!
+//! for key in ["gpu/0", "gpu/1", "gpu/2", "cpu/0"] {
+//!     manager.publish(StateChangeEvent::new(key, ChangeType::Created)).await;
+//! }
+//! // gpu/2 found the queue full; cpu/0 matched nobody
+//! assert_eq!(sub.recv().await.unwrap().key, "gpu/0");
+//! assert_eq!(sub.recv().await.unwrap().key, "gpu/1");
+//! assert!(sub.try_recv().is_none());
+//! # }
+//! ```
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
+use orpheon_core::sel::{self, ConditionError};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{mpsc, RwLock};
 use uuid::Uuid;
 
 use crate::store::StateEntry;
@@ -29,6 +58,19 @@ pub struct StateChangeEvent {
     pub timestamp: DateTime<Utc>,
 }
 
+impl StateChangeEvent {
+    /// An event for `key` with no values, timestamped now.
+    pub fn new(key: impl Into<String>, change_type: ChangeType) -> Self {
+        Self {
+            key: key.into(),
+            new_value: None,
+            old_value: None,
+            change_type,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
 /// Type of state change.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -42,7 +84,7 @@ pub enum ChangeType {
 }
 
 /// Filter for subscriptions.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SubscriptionFilter {
     /// Key prefix to match.
     pub key_prefix: Option<String>,
@@ -53,18 +95,26 @@ pub struct SubscriptionFilter {
     /// Change types to watch.
     pub change_types: Option<Vec<ChangeType>>,
     
-    /// SEL (State Expression Language) expression (simplified).
-    pub expression: Option<String>,
+    /// SEL expression the new value must satisfy, e.g. `available >= 2`;
+    /// see [`orpheon_core::sel`]. Deletions never match one. Parsed when
+    /// the filter is made or deserialized, so matching never fails on it.
+    #[serde(default, with = "expression_text")]
+    expression: Option<sel::Expr>,
 }
 
-impl Default for SubscriptionFilter {
-    fn default() -> Self {
-        Self {
-            key_prefix: None,
-            keys: None,
-            change_types: None,
-            expression: None,
-        }
+/// (De)serializes a filter's expression as its text, parsing it on the way in.
+mod expression_text {
+    use orpheon_core::sel;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(expression: &Option<sel::Expr>, serializer: S) -> Result<S::Ok, S::Error> {
+        expression.as_ref().map(sel::Expr::as_str).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<sel::Expr>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|text| sel::parse(&text).map_err(de::Error::custom))
+            .transpose()
     }
 }
 
@@ -85,6 +135,20 @@ impl SubscriptionFilter {
         }
     }
     
+    /// Create a filter for values satisfying an SEL expression; fails if
+    /// the expression does not parse.
+    pub fn expression(expression: impl Into<String>) -> Result<Self, ConditionError> {
+        Ok(Self {
+            expression: Some(sel::parse(&expression.into())?),
+            ..Default::default()
+        })
+    }
+    
+    /// The parsed SEL expression, if the filter has one.
+    pub fn condition(&self) -> Option<&sel::Expr> {
+        self.expression.as_ref()
+    }
+    
     /// Check if an event matches this filter.
     pub fn matches(&self, event: &StateChangeEvent) -> bool {
         // Check key prefix
@@ -108,13 +172,25 @@ impl SubscriptionFilter {
             }
         }
         
-        // TODO: Implement SEL expression matching
+        // Check the new value against the expression; one that reads a
+        // missing field matches nothing
+        if let Some(ref expression) = self.expression {
+            let Some(entry) = &event.new_value else {
+                return false;
+            };
+            return expression.eval(&entry.value).unwrap_or(false);
+        }
         
         true
     }
 }
 
+/// Events queued per subscriber before further ones are dropped.
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 1000;
+
 /// A subscription to state changes.
+///
+/// Dropping the subscription unsubscribes it.
 pub struct StateSubscription {
     /// Unique ID for this subscription.
     pub id: Uuid,
@@ -122,25 +198,69 @@ pub struct StateSubscription {
     /// Filter for this subscription.
     pub filter: SubscriptionFilter,
     
-    /// Receiver for events.
-    pub receiver: broadcast::Receiver<StateChangeEvent>,
+    /// Events matching the filter, in publish order.
+    receiver: mpsc::Receiver<StateChangeEvent>,
+}
+
+impl StateSubscription {
+    /// Wait for the next event; `None` once the manager is gone or the
+    /// subscription was removed.
+    pub async fn recv(&mut self) -> Option<StateChangeEvent> {
+        self.receiver.recv().await
+    }
+    
+    /// Take the next event if one is queued.
+    pub fn try_recv(&mut self) -> Option<StateChangeEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// What happened to one published event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublishReceipt {
+    /// Subscriptions whose filter matched the event.
+    pub matched_subscribers: usize,
+    
+    /// Matching subscribers the event was queued for.
+    pub delivered: usize,
+    
+    /// Matching subscribers that missed the event because their queue was
+    /// full or they had gone away.
+    pub dropped: usize,
+}
+
+/// A subscription's filter and the sending half of its queue.
+struct Subscriber {
+    filter: SubscriptionFilter,
+    sender: mpsc::Sender<StateChangeEvent>,
+}
+
+impl Subscriber {
+    /// Whether events are queued that the subscriber has not taken yet.
+    fn has_queued(&self) -> bool {
+        !self.sender.is_closed() && self.sender.capacity() < self.sender.max_capacity()
+    }
 }
 
 /// Manager for state subscriptions.
 pub struct SubscriptionManager {
-    /// Sender for broadcasting events.
-    sender: broadcast::Sender<StateChangeEvent>,
+    /// Queue size for new subscriptions.
+    capacity: usize,
     
     /// Active subscriptions.
-    subscriptions: Arc<RwLock<HashMap<Uuid, SubscriptionFilter>>>,
+    subscriptions: Arc<RwLock<HashMap<Uuid, Subscriber>>>,
 }
 
 impl SubscriptionManager {
     /// Create a new subscription manager.
     pub fn new() -> Self {
-        let (sender, _) = broadcast::channel(1000);
+        Self::with_capacity(DEFAULT_SUBSCRIBER_CAPACITY)
+    }
+    
+    /// Create a manager whose subscribers each queue up to `capacity` events.
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            sender,
+            capacity: capacity.max(1),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
@@ -148,10 +268,10 @@ impl SubscriptionManager {
     /// Subscribe to state changes with a filter.
     pub async fn subscribe(&self, filter: SubscriptionFilter) -> StateSubscription {
         let id = Uuid::new_v4();
-        let receiver = self.sender.subscribe();
+        let (sender, receiver) = mpsc::channel(self.capacity);
         
         let mut subs = self.subscriptions.write().await;
-        subs.insert(id, filter.clone());
+        subs.insert(id, Subscriber { filter: filter.clone(), sender });
         
         StateSubscription { id, filter, receiver }
     }
@@ -162,15 +282,56 @@ impl SubscriptionManager {
         subs.remove(&id);
     }
     
-    /// Publish a state change event.
-    pub async fn publish(&self, event: StateChangeEvent) {
-        // Broadcast to all subscribers (they filter locally)
-        let _ = self.sender.send(event);
+    /// Publish a state change event to every matching subscription.
+    ///
+    /// Never waits for subscribers: the event is queued for those with room
+    /// and dropped for the rest. Subscriptions that were dropped are removed.
+    pub async fn publish(&self, event: StateChangeEvent) -> PublishReceipt {
+        let mut receipt = PublishReceipt::default();
+        let mut closed = Vec::new();
+        {
+            let subs = self.subscriptions.read().await;
+            for (id, sub) in subs.iter().filter(|(_, sub)| sub.filter.matches(&event)) {
+                receipt.matched_subscribers += 1;
+                match sub.sender.try_send(event.clone()) {
+                    Ok(()) => receipt.delivered += 1,
+                    Err(mpsc::error::TrySendError::Full(_)) => receipt.dropped += 1,
+                    Err(mpsc::error::TrySendError::Closed(_)) => {
+                        receipt.dropped += 1;
+                        closed.push(*id);
+                    }
+                }
+            }
+        }
+        if !closed.is_empty() {
+            let mut subs = self.subscriptions.write().await;
+            for id in closed {
+                subs.remove(&id);
+            }
+        }
+        receipt
+    }
+    
+    /// Wait until every event queued so far has been received by its
+    /// subscriber, or lost because the subscriber went away.
+    ///
+    /// Subscribers must be receiving concurrently, or this never resolves.
+    pub async fn flush(&self) {
+        loop {
+            {
+                let mut subs = self.subscriptions.write().await;
+                subs.retain(|_, sub| !sub.sender.is_closed());
+                if !subs.values().any(Subscriber::has_queued) {
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
     }
     
     /// Get the number of active subscriptions.
     pub async fn subscription_count(&self) -> usize {
-        self.subscriptions.read().await.len()
+        self.subscriptions.read().await.values().filter(|sub| !sub.sender.is_closed()).count()
     }
 }
 
@@ -224,6 +385,43 @@ mod tests {
         assert!(filter.matches(&event));
     }
 
+    #[test]
+    fn test_filter_expression() {
+        let filter = SubscriptionFilter::expression("available >= 2 && zone == 'us-east-1a'").unwrap();
+        let event = |value: serde_json::Value| StateChangeEvent {
+            new_value: Some(StateEntry {
+                key: "gpu/0".to_string(),
+                value,
+                version: 1,
+                timestamp: Utc::now(),
+                deleted: false,
+                metadata: HashMap::new(),
+            }),
+            ..StateChangeEvent::new("gpu/0", ChangeType::Updated)
+        };
+
+        assert!(filter.matches(&event(serde_json::json!({ "available": 4, "zone": "us-east-1a" }))));
+        assert!(!filter.matches(&event(serde_json::json!({ "available": 1, "zone": "us-east-1a" }))));
+        assert!(!filter.matches(&event(serde_json::json!({ "zone": "us-east-1a" }))));
+        assert!(!filter.matches(&StateChangeEvent::new("gpu/0", ChangeType::Deleted)));
+        assert!(SubscriptionFilter::expression("available >=").is_err());
+        assert_eq!(filter.condition().unwrap().as_str(), "available >= 2 && zone == 'us-east-1a'");
+    }
+
+    #[test]
+    fn test_filter_expression_is_parsed_when_deserialized() {
+        let filter = SubscriptionFilter::expression("available >= 2").unwrap();
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(json["expression"], "available >= 2");
+        let round_trip: SubscriptionFilter = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip.condition(), filter.condition());
+
+        let invalid = serde_json::json!({ "key_prefix": null, "keys": null, "change_types": null, "expression": "available >=" });
+        assert!(serde_json::from_value::<SubscriptionFilter>(invalid).is_err());
+        let bare: SubscriptionFilter = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(bare.condition().is_none());
+    }
+
     #[tokio::test]
     async fn test_subscription_manager() {
         let manager = SubscriptionManager::new();
@@ -236,4 +434,65 @@ mod tests {
         
         assert_eq!(manager.subscription_count().await, 0);
     }
+
+    #[tokio::test]
+    async fn test_receipt_counts_drops_when_buffer_is_full() {
+        let manager = SubscriptionManager::with_capacity(2);
+        let mut slow = manager.subscribe(SubscriptionFilter::default()).await;
+        let _other = manager.subscribe(SubscriptionFilter::prefix("plan:")).await;
+
+        let receipts = [
+            manager.publish(StateChangeEvent::new("intent:1", ChangeType::Created)).await,
+            manager.publish(StateChangeEvent::new("intent:2", ChangeType::Created)).await,
+            manager.publish(StateChangeEvent::new("intent:3", ChangeType::Created)).await,
+        ];
+        let full = PublishReceipt { matched_subscribers: 1, delivered: 1, dropped: 0 };
+        assert_eq!(receipts, [full, full, PublishReceipt { delivered: 0, dropped: 1, ..full }]);
+
+        // Taking an event frees room for the next one
+        assert_eq!(slow.recv().await.unwrap().key, "intent:1");
+        assert_eq!(manager.publish(StateChangeEvent::new("intent:4", ChangeType::Updated)).await, full);
+        assert_eq!(slow.recv().await.unwrap().key, "intent:2");
+        assert_eq!(slow.recv().await.unwrap().key, "intent:4");
+    }
+
+    #[tokio::test]
+    async fn test_dropped_subscription_counts_as_dropped() {
+        let manager = SubscriptionManager::new();
+        let sub = manager.subscribe(SubscriptionFilter::default()).await;
+        drop(sub);
+        assert_eq!(manager.subscription_count().await, 0);
+
+        let receipt = manager.publish(StateChangeEvent::new("k", ChangeType::Deleted)).await;
+        assert_eq!(receipt, PublishReceipt { matched_subscribers: 1, delivered: 0, dropped: 1 });
+        // It was removed, so the next event matches nobody
+        let receipt = manager.publish(StateChangeEvent::new("k", ChangeType::Deleted)).await;
+        assert_eq!(receipt, PublishReceipt::default());
+    }
+
+    #[tokio::test]
+    async fn test_flush_waits_for_subscribers_in_publish_order() {
+        let manager = SubscriptionManager::with_capacity(16);
+        let mut sub = manager.subscribe(SubscriptionFilter::default()).await;
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let consumer = tokio::spawn({
+            let seen = seen.clone();
+            async move {
+                while let Some(event) = sub.recv().await {
+                    seen.lock().unwrap().push(event.key);
+                }
+            }
+        });
+
+        let keys: Vec<String> = (0..10).map(|i| format!("key:{}", i)).collect();
+        for key in &keys {
+            assert_eq!(manager.publish(StateChangeEvent::new(key, ChangeType::Updated)).await.delivered, 1);
+        }
+        manager.flush().await;
+        assert_eq!(*seen.lock().unwrap(), keys);
+
+        consumer.abort();
+        // Nothing is queued for an aborted consumer, so this returns at once
+        manager.flush().await;
+    }
 }